@@ -1,6 +1,8 @@
 use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 #[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -8,3 +10,14 @@ pub struct Money {
     pub amount: BigDecimal,
     pub currency: String,
 }
+
+/// Published to the `billing.charged` topic once a usage charge is committed
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BillingChargedEvent {
+    pub billing_id: Uuid,
+    pub user_id: Uuid,
+    pub deployment_id: Uuid,
+    pub billing_period: DateTime<Utc>,
+    pub amount: Money,
+}
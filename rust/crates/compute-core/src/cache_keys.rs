@@ -20,8 +20,99 @@ impl CacheKeys {
         format!("deployment:{id}:pod:{uid}:metrics")
     }
 
+    /// `pod:{uid}:log_archived`
+    pub fn pod_log_archived(uid: &str) -> String {
+        format!("pod:{uid}:log_archived")
+    }
+
+    /// `pod:{namespace}/{name}:deployment`, holds the id of the deployment the pod belongs to
+    pub fn pod_deployment(namespace: &str, name: &str) -> String {
+        format!("pod:{namespace}/{name}:deployment")
+    }
+
+    /// `deployment:{id}:idle_suspended`, only deployments suspended for being idle are woken up
+    pub fn deployment_idle_suspended(id: &str) -> String {
+        format!("deployment:{id}:idle_suspended")
+    }
+
     /// `deployment:{id}:image_error_notified`
     pub fn deployment_image_error_notified(id: &str) -> String {
         format!("deployment:{id}:image_error_notified")
     }
+
+    /// `deployment:{id}:alert:{metric}_notified`
+    pub fn deployment_alert_notified(id: &str, metric: &str) -> String {
+        format!("deployment:{id}:alert:{metric}_notified")
+    }
+
+    /// `deployment:{id}:git_push_build`
+    pub fn deployment_git_push_build(id: &str) -> String {
+        format!("deployment:{id}:git_push_build")
+    }
+
+    /// `build:{id}:started_at`, unix seconds the build Job started at while it's running
+    pub fn build_started_at(id: &str) -> String {
+        format!("build:{id}:started_at")
+    }
+
+    /// `deployment:{id}:notified:{event}`, a webhook delivery of `event` went out recently
+    pub fn deployment_notified(id: &str, event: &str) -> String {
+        format!("deployment:{id}:notified:{event}")
+    }
+
+    /// `user:{user_id}:notification_channels`, JSON of the user's notification channels
+    pub fn user_notification_channels(user_id: &str) -> String {
+        format!("user:{user_id}:notification_channels")
+    }
+
+    /// `user:{user_id}:balance_suspension_checked`
+    pub fn user_balance_suspension_checked(user_id: &str) -> String {
+        format!("user:{user_id}:balance_suspension_checked")
+    }
+
+    /// `user:{user_id}:deletion_pending`, deliveries an account deletion is still waiting on
+    pub fn user_deletion_pending(user_id: &str) -> String {
+        format!("user:{user_id}:deletion_pending")
+    }
+
+    /// `sse_sequence`, last id handed to a published compute event
+    pub fn sse_sequence() -> String {
+        "sse_sequence".to_string()
+    }
+
+    /// `sse_buffer:{channel}`, recent events of a channel scored by their id
+    pub fn sse_buffer(channel: &str) -> String {
+        format!("sse_buffer:{channel}")
+    }
+
+    /// `project:{id}:deployment_counts`
+    pub fn project_deployment_counts(id: &str) -> String {
+        format!("project:{id}:deployment_counts")
+    }
+
+    /// `presets:{user_id}`
+    pub fn presets(user_id: &str) -> String {
+        format!("presets:{user_id}")
+    }
+
+    /// `github:branches:{installation_id}:{owner}/{repo}`
+    pub fn github_branches(installation_id: i64, full_name: &str) -> String {
+        format!("github:branches:{installation_id}:{full_name}")
+    }
+
+    /// `github:commits:{installation_id}:{owner}/{repo}:{branch}`
+    pub fn github_commits(installation_id: i64, full_name: &str, branch: &str) -> String {
+        format!("github:commits:{installation_id}:{full_name}:{branch}")
+    }
+
+    /// `cluster:nodes`, the node summaries of every region
+    pub fn cluster_nodes() -> String {
+        "cluster:nodes".to_string()
+    }
+
+    /// `restart_scheduler:{minute}:lock`, held by the reconciler replica publishing that minute's
+    /// scheduled restarts
+    pub fn restart_scheduler_lock(minute: i64) -> String {
+        format!("restart_scheduler:{minute}:lock")
+    }
 }
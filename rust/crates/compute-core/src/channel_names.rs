@@ -16,4 +16,14 @@ impl ChannelNames {
     pub fn deployment_events(deployment_id: &str) -> String {
         format!("deployment:{deployment_id}:events")
     }
+
+    /// One-shot, carries the `DryRunResult` of a single dry run
+    pub fn dry_run(request_id: &str) -> String {
+        format!("dry_run:{request_id}:result")
+    }
+
+    /// One-shot, carries the JSON Patch of a single diff request
+    pub fn deployment_diff(request_id: &str) -> String {
+        format!("deployment_diff:{request_id}:result")
+    }
 }
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use kube::CustomResource;
 use serde::{Deserialize, Serialize};
 
@@ -104,6 +106,42 @@ pub struct BuildStatus {
     pub conditions: Option<Vec<Condition>>,
 }
 
+// -----------------------------------------------------------------------------
+// PrometheusRule Resource (Picked up by the Prometheus Operator)
+// -----------------------------------------------------------------------------
+#[derive(CustomResource, Deserialize, Serialize, Clone, Default, Debug)]
+#[kube(
+    group = "monitoring.coreos.com",
+    version = "v1",
+    kind = "PrometheusRule",
+    plural = "prometheusrules",
+    namespaced,
+    schema = "disabled"
+)]
+#[serde(rename_all = "camelCase")]
+pub struct PrometheusRuleSpec {
+    pub groups: Vec<PrometheusRuleGroup>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PrometheusRuleGroup {
+    pub name: String,
+    pub rules: Vec<PrometheusRuleRule>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PrometheusRuleRule {
+    pub alert: String,
+    pub expr: String,
+    /// How long `expr` has to hold before the alert fires, e.g. `5m`
+    #[serde(rename = "for")]
+    pub for_: Option<String>,
+    pub labels: Option<BTreeMap<String, String>>,
+    pub annotations: Option<BTreeMap<String, String>>,
+}
+
 // -----------------------------------------------------------------------------
 // Shared sub-types
 // -----------------------------------------------------------------------------
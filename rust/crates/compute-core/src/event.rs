@@ -1,6 +1,7 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    models::{DeploymentEventLevel, ScalingAction},
     schemas::{DeploymentMetricUpdate, Pod, PodMetricUpdate, PodPhase},
     services::event_emission_service::DeploymentEventUpdate,
 };
@@ -14,6 +15,18 @@ pub enum ComputeEvent<'a> {
     DeploymentEvent {
         event: DeploymentEventUpdate,
     },
+    #[serde(rename_all = "camelCase")]
+    DeploymentSystemMessage {
+        deployment_id: &'a str,
+        level: DeploymentEventLevel,
+        message: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    ScalingRecommendation {
+        deployment_id: &'a str,
+        action: ScalingAction,
+        reason: String,
+    },
 
     PodMetricsUpdate {
         updates: Vec<PodMetricUpdate>,
@@ -28,4 +41,61 @@ pub enum ComputeEvent<'a> {
     PodDelete {
         uid: String,
     },
+
+    /// Binding progress of a persistent volume claim, `Pending` until a pod first uses it
+    VolumeStatus {
+        volume: &'a str,
+        phase: &'a str,
+    },
+}
+
+/// Current wire version of `ComputeEvent` published to Redis
+///
+/// Bump it whenever a variant changes shape in a way subscribers can't read. Subscribers drop
+/// events newer than `COMPUTE_EVENT_VERSION` with a warning, so roll out consumers (compute-api)
+/// before producers (reconciler, metrics-worker, event emission) when bumping it.
+/// - 1: bare `ComputeEvent`
+/// - 2: `ComputeEvent` wrapped in `VersionedEvent`
+pub const COMPUTE_EVENT_VERSION: u8 = 2;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct VersionedEvent<'a> {
+    pub version: u8,
+    pub payload: ComputeEvent<'a>,
+}
+
+impl<'a> VersionedEvent<'a> {
+    pub fn new(payload: ComputeEvent<'a>) -> Self {
+        Self {
+            version: COMPUTE_EVENT_VERSION,
+            payload,
+        }
+    }
+}
+
+/// Events kept per channel for SSE clients that reconnect with `Last-Event-ID`
+pub const SSE_BUFFER_SIZE: isize = 100;
+/// A channel nobody published on for this long drops its replay buffer
+pub const SSE_BUFFER_TTL_SECS: i64 = 3600;
+
+/// Only the version and id of a published event, lets relays check it without knowing every variant
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct EventVersion {
+    /// Events published before versioning carry no `version` field
+    #[serde(default = "EventVersion::unversioned")]
+    pub version: u8,
+    /// Assigned by `VersionedEvent::publish`, shared by all channels so one `Last-Event-ID`
+    /// covers a stream subscribed to several of them
+    #[serde(default)]
+    pub id: Option<u64>,
+}
+
+impl EventVersion {
+    fn unversioned() -> u8 {
+        1
+    }
+
+    pub fn is_supported(&self) -> bool {
+        self.version <= COMPUTE_EVENT_VERSION
+    }
 }
@@ -11,6 +11,10 @@ pub enum GithubAppError {
     #[error("ReqwestError")]
     ReqwestError(#[from] reqwest::Error),
 
+    /// The repository doesn't exist or the installation has no access to it
+    #[error("NotFound")]
+    NotFound,
+
     #[error("BadRequest")]
     BadRequest(String),
 }
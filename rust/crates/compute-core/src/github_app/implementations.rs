@@ -1,6 +1,7 @@
 use jsonwebtoken::{Algorithm, EncodingKey, Header, encode};
 use reqwest::Client;
 use std::{
+    collections::HashSet,
     fs::{self},
     io::Error,
     path::PathBuf,
@@ -11,9 +12,17 @@ use chrono::Utc;
 use crate::github_app::{
     GithubApp, GithubAppClaims,
     error::GithubAppError,
-    schemas::{GithubRepository, InstallationReposResponse, InstallationTokenResponse},
+    schemas::{
+        CommitSummary, GithubBranch, GithubCommit, GithubRepository, InstallationReposResponse,
+        InstallationTokenResponse,
+    },
 };
 
+/// GitHub caps `per_page` at 100
+const BRANCHES_PER_PAGE: u32 = 100;
+/// Enough to pick a recent commit, older ones are deployed by pushing
+const COMMITS_PER_PAGE: u32 = 20;
+
 impl GithubApp {
     pub fn generate_jwt(&self) -> Result<String, GithubAppError> {
         let iat = Utc::now().timestamp();
@@ -94,4 +103,119 @@ impl GithubApp {
         let res = res.json::<InstallationReposResponse>().await?;
         Ok((res.repositories, res.total_count))
     }
+
+    /// Names of every branch, following the `Link` header through all pages
+    pub async fn list_repository_branches(
+        &self,
+        access_token: &str,
+        owner: &str,
+        repo: &str,
+        http: &Client,
+    ) -> Result<Vec<String>, GithubAppError> {
+        // GET /repos/{owner}/{repo}/branches
+        let mut next = Some(format!(
+            "https://api.github.com/repos/{}/{}/branches?per_page={}",
+            owner, repo, BRANCHES_PER_PAGE
+        ));
+        let mut seen = HashSet::new();
+        let mut branches = Vec::new();
+
+        while let Some(url) = next.take() {
+            let res = http
+                .get(&url)
+                .header("Authorization", format!("Bearer {}", access_token))
+                .header("Accept", "application/vnd.github+json")
+                .header("User-Agent", "poddle-compute")
+                .send()
+                .await?;
+
+            if res.status() == reqwest::StatusCode::NOT_FOUND {
+                return Err(GithubAppError::NotFound);
+            }
+            if !res.status().is_success() {
+                return Err(GithubAppError::BadRequest(format!(
+                    "GitHub list branches failed: {}",
+                    res.status()
+                )));
+            }
+
+            next = res
+                .headers()
+                .get(reqwest::header::LINK)
+                .and_then(|v| v.to_str().ok())
+                .and_then(next_page_url);
+
+            for branch in res.json::<Vec<GithubBranch>>().await? {
+                if seen.insert(branch.name.clone()) {
+                    branches.push(branch.name);
+                }
+            }
+        }
+
+        Ok(branches)
+    }
+
+    /// Latest commits on `branch`, newest first
+    pub async fn list_repository_commits(
+        &self,
+        access_token: &str,
+        owner: &str,
+        repo: &str,
+        branch: &str,
+        http: &Client,
+    ) -> Result<Vec<CommitSummary>, GithubAppError> {
+        // GET /repos/{owner}/{repo}/commits
+        let res = http
+            .get(format!(
+                "https://api.github.com/repos/{}/{}/commits",
+                owner, repo
+            ))
+            .query(&[("sha", branch), ("per_page", &COMMITS_PER_PAGE.to_string())])
+            .header("Authorization", format!("Bearer {}", access_token))
+            .header("Accept", "application/vnd.github+json")
+            .header("User-Agent", "poddle-compute")
+            .send()
+            .await?;
+
+        if res.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(GithubAppError::NotFound);
+        }
+        if !res.status().is_success() {
+            return Err(GithubAppError::BadRequest(format!(
+                "GitHub list commits failed: {}",
+                res.status()
+            )));
+        }
+
+        let commits = res
+            .json::<Vec<GithubCommit>>()
+            .await?
+            .into_iter()
+            .map(|c| CommitSummary {
+                sha: c.sha,
+                message: c.commit.message,
+                author: c.commit.author.map(|a| a.name),
+                committed_at: c.commit.committer.map(|c| c.date),
+            })
+            .collect();
+
+        Ok(commits)
+    }
+}
+
+/// Picks the `rel="next"` target out of `<url>; rel="next", <url>; rel="last"`
+fn next_page_url(link: &str) -> Option<String> {
+    link.split(',').find_map(|part| {
+        let (target, params) = part.split_once(';')?;
+        params
+            .split(';')
+            .any(|p| p.trim() == r#"rel="next""#)
+            .then(|| {
+                target
+                    .trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string()
+            })
+    })
 }
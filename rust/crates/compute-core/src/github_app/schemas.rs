@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -13,6 +14,41 @@ pub struct InstallationReposResponse {
     pub repositories: Vec<GithubRepository>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GithubBranch {
+    pub name: String,
+}
+
+/// An entry of `GET /repos/{owner}/{repo}/commits`, only what `CommitSummary` needs
+#[derive(Deserialize, Debug)]
+pub struct GithubCommit {
+    pub sha: String,
+    pub commit: GithubCommitDetails,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GithubCommitDetails {
+    pub message: String,
+    /// Either is missing when the commit doesn't carry it
+    pub author: Option<GithubCommitSignature>,
+    pub committer: Option<GithubCommitSignature>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GithubCommitSignature {
+    pub name: String,
+    pub date: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Clone, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitSummary {
+    pub sha: String,
+    pub message: String,
+    pub author: Option<String>,
+    pub committed_at: Option<DateTime<Utc>>,
+}
+
 #[derive(Serialize, Deserialize, Clone, JsonSchema, Debug)]
 #[serde(rename_all(serialize = "camelCase"))]
 pub struct GithubRepository {
@@ -24,6 +60,14 @@ pub struct GithubRepository {
     pub clone_url: String,
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum RepositoryProvider {
+    #[default]
+    Github,
+    Gitlab,
+}
+
 #[derive(Serialize, Deserialize, Clone, JsonSchema, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Repository {
@@ -33,4 +77,7 @@ pub struct Repository {
     pub private: bool,
     pub default_branch: Option<String>,
     pub clone_url: String,
+    /// Repositories saved before GitLab support are GitHub ones
+    #[serde(default)]
+    pub provider: RepositoryProvider,
 }
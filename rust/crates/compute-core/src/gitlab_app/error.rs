@@ -0,0 +1,14 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum GitlabAppError {
+    #[error("ReqwestError")]
+    ReqwestError(#[from] reqwest::Error),
+
+    /// The access token expired or was revoked, refreshing it may help
+    #[error("Unauthorized")]
+    Unauthorized,
+
+    #[error("BadRequest")]
+    BadRequest(String),
+}
@@ -0,0 +1,111 @@
+use reqwest::Client;
+
+use crate::{
+    github_app::schemas::Repository,
+    gitlab_app::{
+        GitlabApp,
+        error::GitlabAppError,
+        schemas::{GitlabProject, GitlabTokenResponse},
+    },
+};
+
+/// GitLab caps `per_page` at 100
+const PROJECTS_PER_PAGE: u32 = 100;
+
+impl GitlabApp {
+    /// Exchanges the code GitLab redirected back with for a user access token
+    pub async fn exchange_code(
+        &self,
+        code: &str,
+        http: &Client,
+    ) -> Result<GitlabTokenResponse, GitlabAppError> {
+        // POST /oauth/token
+        let params = [
+            ("client_id", self.cfg.client_id.as_str()),
+            ("client_secret", self.cfg.client_secret.as_str()),
+            ("code", code),
+            ("grant_type", "authorization_code"),
+            ("redirect_uri", self.cfg.redirect_uri.as_str()),
+        ];
+
+        self.request_token(&params, http).await
+    }
+
+    /// GitLab access tokens live for two hours, the refresh token is single use
+    pub async fn refresh_token(
+        &self,
+        refresh_token: &str,
+        http: &Client,
+    ) -> Result<GitlabTokenResponse, GitlabAppError> {
+        let params = [
+            ("client_id", self.cfg.client_id.as_str()),
+            ("client_secret", self.cfg.client_secret.as_str()),
+            ("refresh_token", refresh_token),
+            ("grant_type", "refresh_token"),
+            ("redirect_uri", self.cfg.redirect_uri.as_str()),
+        ];
+
+        self.request_token(&params, http).await
+    }
+
+    async fn request_token(
+        &self,
+        params: &[(&str, &str)],
+        http: &Client,
+    ) -> Result<GitlabTokenResponse, GitlabAppError> {
+        let res = http
+            .post(format!("{}/oauth/token", self.cfg.base_url))
+            .form(params)
+            .send()
+            .await?;
+
+        if !res.status().is_success() {
+            return Err(GitlabAppError::BadRequest(format!(
+                "GitLab oauth token failed: {}",
+                res.status()
+            )));
+        }
+
+        Ok(res.json::<GitlabTokenResponse>().await?)
+    }
+
+    /// Projects the user is a member of, shaped like GitHub repositories
+    pub async fn list_repositories(
+        &self,
+        access_token: &str,
+        http: &Client,
+    ) -> Result<(Vec<Repository>, i64), GitlabAppError> {
+        // GET /api/v4/projects?membership=true
+        let res = http
+            .get(format!("{}/api/v4/projects", self.cfg.base_url))
+            .query(&[
+                ("membership", "true"),
+                ("simple", "true"),
+                ("order_by", "last_activity_at"),
+                ("per_page", &PROJECTS_PER_PAGE.to_string()),
+            ])
+            .bearer_auth(access_token)
+            .send()
+            .await?;
+
+        if res.status() == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(GitlabAppError::Unauthorized);
+        }
+        if !res.status().is_success() {
+            return Err(GitlabAppError::BadRequest(format!(
+                "GitLab list projects failed: {}",
+                res.status()
+            )));
+        }
+
+        let total = res
+            .headers()
+            .get("x-total")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<i64>().ok());
+        let projects = res.json::<Vec<GitlabProject>>().await?;
+        let total = total.unwrap_or(projects.len() as i64);
+
+        Ok((projects.into_iter().map(Repository::from).collect(), total))
+    }
+}
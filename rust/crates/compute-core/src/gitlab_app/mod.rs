@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+pub mod error;
+pub mod implementations;
+pub mod schemas;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GitlabAppConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    /// Must match the redirect URI registered on the GitLab application
+    pub redirect_uri: String,
+    /// `https://gitlab.com` or the address of a self-managed instance
+    pub base_url: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct GitlabApp {
+    pub cfg: GitlabAppConfig,
+}
@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+use crate::github_app::schemas::{Repository, RepositoryProvider};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GitlabTokenResponse {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_in: Option<i64>,
+}
+
+/// Subset of GitLab's project entity
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GitlabProject {
+    pub id: i64,
+    pub name: String,
+    pub path_with_namespace: String,
+    pub visibility: String,
+    pub default_branch: Option<String>,
+    pub http_url_to_repo: String,
+}
+
+impl From<GitlabProject> for Repository {
+    fn from(project: GitlabProject) -> Self {
+        Self {
+            id: project.id,
+            name: project.name,
+            full_name: project.path_with_namespace,
+            private: project.visibility != "public",
+            default_branch: project.default_branch,
+            clone_url: project.http_url_to_repo,
+            provider: RepositoryProvider::Gitlab,
+        }
+    }
+}
@@ -1,29 +1,80 @@
-use std::fmt::Display;
+use std::{borrow::Cow, fmt::Display};
 
-use redis::{ErrorKind, FromRedisValue, RedisError, RedisResult, RedisWrite, ToRedisArgs, Value};
+use once_cell::sync::Lazy;
+use redis::{
+    ErrorKind, FromRedisValue, RedisError, RedisResult, RedisWrite, Script, ToRedisArgs, Value,
+    aio::MultiplexedConnection,
+};
 use uuid::Uuid;
+use validator::ValidationError;
 
 use crate::{
-    event::ComputeEvent,
-    models::{DeploymentRow, PresetRow, ResourceSpec},
+    cache_keys::CacheKeys,
+    event::{SSE_BUFFER_SIZE, SSE_BUFFER_TTL_SECS, VersionedEvent},
+    models::{DeploymentRow, NotificationChannelType, PresetRow, ResourceSpec},
     schemas::{
-        CreateDeploymentMessage, CreateDeploymentRequest, DeploymentResponse, DeploymentSource,
-        DeploymentSourceMessage, DeploymentsResponse, MetricSnapshot, PodMeta, PodPhase,
-        UpdateDeploymentMessage, UpdateDeploymentRequest,
+        ContainerSpec, CreateDeploymentMessage, CreateDeploymentRequest, DeploymentResponse,
+        DeploymentSource, DeploymentSourceMessage, DeploymentsResponse, IMAGE_REFERENCE,
+        ImagePullPolicy, MetricSnapshot, PodMeta, PodPhase, QuotaExceeded, UpdateDeploymentMessage,
+        UpdateDeploymentRequest, VolumeAccessMode,
     },
 };
 
-impl<'a> ToRedisArgs for ComputeEvent<'a> {
+impl<'a> ToRedisArgs for VersionedEvent<'a> {
     fn write_redis_args<W>(&self, out: &mut W)
     where
         W: ?Sized + RedisWrite,
     {
         // to_vec is slightly more efficient than to_string
-        let bytes = serde_json::to_vec(self).expect("ComputeEvent must serialize");
+        let bytes = serde_json::to_vec(self).expect("VersionedEvent must serialize");
         out.write_arg(&bytes);
     }
 }
 
+/// Numbers the event, buffers it and publishes it in one step, so a reconnecting client never
+/// finds an id in the buffer that subscribers haven't been sent. The id is spliced in front of
+/// the serialized event, which always starts with `{`
+static PUBLISH_SCRIPT: Lazy<Script> = Lazy::new(|| {
+    Script::new(
+        r#"
+local id = redis.call('INCR', KEYS[1])
+local payload = '{"id":' .. id .. ',' .. string.sub(ARGV[2], 2)
+redis.call('ZADD', KEYS[2], id, payload)
+redis.call('ZREMRANGEBYRANK', KEYS[2], 0, -tonumber(ARGV[3]) - 1)
+redis.call('EXPIRE', KEYS[2], ARGV[4])
+redis.call('PUBLISH', ARGV[1], payload)
+return id
+"#,
+    )
+});
+
+impl<'a> VersionedEvent<'a> {
+    /// Publishes under the next SSE id and keeps the event in the channel's replay buffer
+    pub async fn publish(
+        &self,
+        channel: &str,
+        con: &mut MultiplexedConnection,
+    ) -> RedisResult<u64> {
+        let payload = serde_json::to_string(self).map_err(|e| {
+            RedisError::from((
+                ErrorKind::TypeError,
+                "Failed to serialize compute event",
+                e.to_string(),
+            ))
+        })?;
+
+        PUBLISH_SCRIPT
+            .key(CacheKeys::sse_sequence())
+            .key(CacheKeys::sse_buffer(channel))
+            .arg(channel)
+            .arg(payload)
+            .arg(SSE_BUFFER_SIZE)
+            .arg(SSE_BUFFER_TTL_SECS)
+            .invoke_async(con)
+            .await
+    }
+}
+
 impl From<&str> for PodPhase {
     fn from(value: &str) -> Self {
         match value {
@@ -55,6 +106,30 @@ impl Display for PodPhase {
     }
 }
 
+/// Kubernetes spelling of the access mode
+impl Display for VolumeAccessMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            VolumeAccessMode::ReadWriteOnce => "ReadWriteOnce",
+            VolumeAccessMode::ReadWriteMany => "ReadWriteMany",
+            VolumeAccessMode::ReadOnlyMany => "ReadOnlyMany",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Kubernetes spelling of the pull policy
+impl Display for ImagePullPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            ImagePullPolicy::Always => "Always",
+            ImagePullPolicy::IfNotPresent => "IfNotPresent",
+            ImagePullPolicy::Never => "Never",
+        };
+        f.write_str(s)
+    }
+}
+
 impl PodMeta {
     pub fn as_redis_items(&self) -> Vec<(&'static str, String)> {
         vec![
@@ -149,6 +224,7 @@ impl From<(DeploymentRow, Vec<MetricSnapshot>)> for DeploymentsResponse {
             secret_keys: d.secret_keys,
             environment_variables: d.environment_variables.and_then(|j| j.0).or_else(|| None),
             labels: d.labels.and_then(|j| j.0).or_else(|| None),
+            tags: d.tags.map(|j| j.0),
             status: d.status,
             domain: d.domain,
             subdomain: d.subdomain,
@@ -179,12 +255,14 @@ impl From<DeploymentRow> for DeploymentResponse {
             secret_keys: d.secret_keys,
             environment_variables: d.environment_variables.and_then(|j| j.0).or_else(|| None),
             labels: d.labels.and_then(|j| j.0).or_else(|| None),
+            tags: d.tags.map(|j| j.0),
             status: d.status,
             domain: d.domain,
             subdomain: d.subdomain,
             service: d.service,
             created_at: d.created_at,
             updated_at: d.updated_at,
+            latest_build: None,
         }
     }
 }
@@ -221,8 +299,282 @@ impl From<(Uuid, Uuid, Uuid, PresetRow, CreateDeploymentRequest)> for CreateDepl
             environment_variables: req.environment_variables,
             secrets: req.secrets,
             labels: req.labels,
+            tags: req.tags,
             domain: req.domain,
             subdomain: req.subdomain,
+            autoscaling: req.autoscaling,
+            init_containers: req.init_containers,
+            sidecar_containers: req.sidecar_containers,
+            rolling_update: req.rolling_update,
+            create_pdb: req.create_pdb.unwrap_or(true),
+            spread_across_zones: req.spread_across_zones.unwrap_or_default(),
+            volumes: req.volumes,
+            config_maps: req.config_maps,
+            suspend_after_idle_minutes: req.suspend_after_idle_minutes,
+            restart_schedule: req.restart_schedule,
+            region: req.region,
+            image_pull_policy: req.image_pull_policy,
+            liveness_probe: req.liveness_probe,
+            readiness_probe: req.readiness_probe,
+            startup_probe: req.startup_probe,
+            dry_run: false,
+            ip_allowlist: req.ip_allowlist,
+            alert_thresholds: req.alert_thresholds,
+            deployment_type: req.deployment_type.unwrap_or_default(),
+            schedule: req.schedule,
+        }
+    }
+}
+
+/// Highest replica count the provisioner accepts, autoscaling aside
+const MAX_REPLICAS: i32 = 50;
+
+fn invalid_field(field: &'static str, code: &'static str, message: String) -> ValidationError {
+    let mut error = ValidationError::new(code).with_message(Cow::Owned(message));
+    error.add_param(Cow::Borrowed("field"), &field);
+    error
+}
+
+impl CreateDeploymentMessage {
+    /// Last check before the message leaves the API, anything invalid here would
+    /// only surface later as an obscure Kubernetes error in the provisioner
+    pub fn validate(&self, preset: &PresetRow) -> Result<(), ValidationError> {
+        if !(1..=65535).contains(&self.port) {
+            return Err(invalid_field(
+                "port",
+                "port_out_of_range",
+                format!("Port must be between 1 and 65535, got {}", self.port),
+            ));
+        }
+
+        if !(1..=MAX_REPLICAS).contains(&self.desired_replicas) {
+            return Err(invalid_field(
+                "desiredReplicas",
+                "replicas_out_of_range",
+                format!(
+                    "Replicas must be between 1 and {}, got {}",
+                    MAX_REPLICAS, self.desired_replicas
+                ),
+            ));
+        }
+
+        // A single pod has nothing to spread
+        if self.spread_across_zones && self.desired_replicas < 2 {
+            return Err(invalid_field(
+                "spreadAcrossZones",
+                "spread_needs_replicas",
+                format!(
+                    "Spreading across zones needs more than one replica, got {}",
+                    self.desired_replicas
+                ),
+            ));
+        }
+
+        if let DeploymentSourceMessage::Image { url, .. } = &self.source {
+            if url.trim().is_empty() {
+                return Err(invalid_field(
+                    "source.url",
+                    "image_empty",
+                    "Image must not be empty".to_string(),
+                ));
+            }
+            if !IMAGE_REFERENCE.is_match(url) {
+                return Err(invalid_field(
+                    "source.url",
+                    "image_invalid",
+                    format!("'{}' is not a valid image reference", url),
+                ));
+            }
+        }
+
+        let max_cpu = preset.cpu_millicores + preset.max_addon_cpu_millicores;
+        if !(preset.cpu_millicores..=max_cpu).contains(&self.resource_spec.cpu_limit_millicores) {
+            return Err(invalid_field(
+                "resourceSpec.cpuLimitMillicores",
+                "cpu_out_of_preset_bounds",
+                format!(
+                    "CPU must be between {}m and {}m for preset '{}', got {}m",
+                    preset.cpu_millicores,
+                    max_cpu,
+                    preset.name,
+                    self.resource_spec.cpu_limit_millicores
+                ),
+            ));
+        }
+
+        let max_memory = preset.memory_mb + preset.max_addon_memory_mb;
+        if !(preset.memory_mb..=max_memory).contains(&self.resource_spec.memory_limit_mb) {
+            return Err(invalid_field(
+                "resourceSpec.memoryLimitMb",
+                "memory_out_of_preset_bounds",
+                format!(
+                    "Memory must be between {}MB and {}MB for preset '{}', got {}MB",
+                    preset.memory_mb, max_memory, preset.name, self.resource_spec.memory_limit_mb
+                ),
+            ));
+        }
+
+        // Names end up in claim names, mount paths in the same container
+        let volumes = self.volumes.as_deref().unwrap_or_default();
+        for (i, volume) in volumes.iter().enumerate() {
+            if let Some(other) = volumes[..i]
+                .iter()
+                .find(|v| v.name == volume.name || v.mount_path == volume.mount_path)
+            {
+                return Err(invalid_field(
+                    "volumes",
+                    "volume_duplicate",
+                    format!(
+                        "Volumes '{}' and '{}' share a name or mount path",
+                        other.name, volume.name
+                    ),
+                ));
+            }
+        }
+
+        // Mounted config maps share the container with the volumes
+        let config_maps = self.config_maps.as_deref().unwrap_or_default();
+        for (i, config_map) in config_maps.iter().enumerate() {
+            if config_maps[..i].iter().any(|c| c.name == config_map.name) {
+                return Err(invalid_field(
+                    "configMaps",
+                    "config_map_duplicate",
+                    format!("Config map '{}' is given twice", config_map.name),
+                ));
+            }
+            let Some(mount_path) = &config_map.mount_path else {
+                continue;
+            };
+            let taken = volumes.iter().any(|v| &v.mount_path == mount_path)
+                || config_maps[..i]
+                    .iter()
+                    .any(|c| c.mount_path.as_ref() == Some(mount_path));
+            if taken {
+                return Err(invalid_field(
+                    "configMaps",
+                    "config_map_mount_path_taken",
+                    format!("Mount path '{}' is already in use", mount_path),
+                ));
+            }
+        }
+
+        // Liveness and readiness only start once the startup probe succeeded, a delay on top of it
+        // means the pod waits twice
+        if let (Some(liveness), Some(readiness), Some(_)) = (
+            &self.liveness_probe,
+            &self.readiness_probe,
+            &self.startup_probe,
+        ) {
+            for (field, probe) in [
+                ("livenessProbe.initialDelaySeconds", liveness),
+                ("readinessProbe.initialDelaySeconds", readiness),
+            ] {
+                if let Some(delay) = probe.initial_delay_seconds.filter(|d| *d > 0) {
+                    return Err(invalid_field(
+                        field,
+                        "probe_delay_conflicts_with_startup",
+                        format!(
+                            "Initial delay must be 0 when a startup probe is configured, got {}s",
+                            delay
+                        ),
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Non-fatal, a `latest` image that isn't pulled every time keeps running whatever a node
+    /// cached first. An image without a tag is `latest` too
+    pub fn image_pull_policy_warning(&self) -> Option<&'static str> {
+        match &self.source {
+            DeploymentSourceMessage::Image { url, .. }
+                if uses_latest_tag(url)
+                    && self.image_pull_policy != Some(ImagePullPolicy::Always) =>
+            {
+                Some("Image uses the mutable latest tag, imagePullPolicy Always is recommended")
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A `:` after the last `/` starts the tag, one before it is a registry port. Digests never move
+fn uses_latest_tag(image: &str) -> bool {
+    if image.contains('@') {
+        return false;
+    }
+    let name = image.rsplit('/').next().unwrap_or(image);
+    name.split_once(':').is_none_or(|(_, tag)| tag == "latest")
+}
+
+impl CreateDeploymentRequest {
+    /// Add-ons, sidecars and init containers all have to fit the preset's budget
+    pub fn check_preset_limits(&self, preset: &PresetRow) -> Result<(), String> {
+        if preset.max_addon_cpu_millicores < self.addon_cpu_millicores.unwrap_or_default()
+            || preset.max_addon_memory_mb < self.addon_memory_mb.unwrap_or_default()
+        {
+            return Err(format!(
+                "Requested add-ons exceed limits for preset '{}'. Max CPU: {}m, Max Memory: {}MB",
+                preset.name, preset.max_addon_cpu_millicores, preset.max_addon_memory_mb
+            ));
+        }
+
+        if self.init_containers.is_some() || self.sidecar_containers.is_some() {
+            if !matches!(self.source, DeploymentSource::Image { .. }) {
+                return Err(
+                    "Init and sidecar containers are only supported for image deployments".into(),
+                );
+            }
+
+            // Sidecars are billed as add-ons, so they share the preset add-on budget
+            let sidecars =
+                ContainerSpec::aggregate(self.sidecar_containers.as_deref().unwrap_or(&[]));
+            if preset.max_addon_cpu_millicores
+                < self.addon_cpu_millicores.unwrap_or_default() + sidecars.cpu_limit_millicores
+                || preset.max_addon_memory_mb
+                    < self.addon_memory_mb.unwrap_or_default() + sidecars.memory_limit_mb
+            {
+                return Err(format!(
+                    "Add-ons and sidecars exceed limits for preset '{}'. Max CPU: {}m, Max Memory: {}MB",
+                    preset.name, preset.max_addon_cpu_millicores, preset.max_addon_memory_mb
+                ));
+            }
+
+            // Init containers run one at a time before the app, each one only has to fit the pod
+            let cpu_millicores =
+                preset.cpu_millicores + self.addon_cpu_millicores.unwrap_or_default();
+            let memory_mb = preset.memory_mb + self.addon_memory_mb.unwrap_or_default();
+            if let Some(c) = self
+                .init_containers
+                .iter()
+                .flatten()
+                .find(|c| c.cpu_millicores > cpu_millicores || c.memory_mb > memory_mb)
+            {
+                return Err(format!(
+                    "Init container '{}' exceeds deployment resources. Max CPU: {}m, Max Memory: {}MB",
+                    c.name, cpu_millicores, memory_mb
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ContainerSpec {
+    /// Sums resources of containers sharing the pod with the main container.
+    /// Init containers run to completion before the pod starts, so they must not be aggregated.
+    pub fn aggregate(containers: &[ContainerSpec]) -> ResourceSpec {
+        let cpu_millicores = containers.iter().map(|c| c.cpu_millicores).sum();
+        let memory_mb = containers.iter().map(|c| c.memory_mb).sum();
+
+        ResourceSpec {
+            cpu_request_millicores: cpu_millicores,
+            cpu_limit_millicores: cpu_millicores,
+            memory_request_mb: memory_mb,
+            memory_limit_mb: memory_mb,
         }
     }
 }
@@ -261,9 +613,89 @@ impl From<(Uuid, Uuid, Uuid, Option<PresetRow>, UpdateDeploymentRequest)>
             environment_variables: req.environment_variables,
             secrets: req.secrets,
             labels: req.labels,
+            tags: req.tags,
             domain: req.domain,
             subdomain: req.subdomain,
+            autoscaling: req.autoscaling,
+            canary: req.canary,
+            restart_at: None,
+            ip_allowlist: req.ip_allowlist,
+            alert_thresholds: req.alert_thresholds,
+            config_maps: req.config_maps,
             timestamp: chrono::Utc::now().timestamp(),
         }
     }
 }
+
+impl NotificationChannelType {
+    /// Only the provider's own incoming webhook endpoints, a channel can't be aimed at anything else
+    pub fn accepts_webhook_url(&self, url: &str) -> bool {
+        let prefixes: &[&str] = match self {
+            Self::Slack => &["https://hooks.slack.com/services/"],
+            Self::Discord => &[
+                "https://discord.com/api/webhooks/",
+                "https://discordapp.com/api/webhooks/",
+            ],
+        };
+        prefixes.iter().any(|prefix| url.starts_with(prefix))
+    }
+}
+
+impl QuotaExceeded {
+    /// Recognizes the `403 Forbidden` returned by the API server when a ResourceQuota rejects a request.
+    /// The message looks like:
+    /// `pods "x" is forbidden: exceeded quota: compute-resources, requested: limits.cpu=2, used: limits.cpu=1, limited: limits.cpu=2`
+    pub fn from_kube_error(e: &kube::Error) -> Option<Self> {
+        let kube::Error::Api(status) = e else {
+            return None;
+        };
+        if status.code != 403 {
+            return None;
+        }
+
+        // The quota message is usually on the status itself, fall back to the causes in details
+        let message = std::iter::once(status.message.as_str())
+            .chain(
+                status
+                    .details
+                    .iter()
+                    .flat_map(|d| d.causes.iter().map(|c| c.message.as_str())),
+            )
+            .find(|m| m.contains("exceeded quota"))?;
+
+        let (_, rest) = message.split_once("exceeded quota: ")?;
+        let (quota_name, rest) = rest.split_once(", requested: ")?;
+        let (requested, rest) = rest.split_once(", used: ")?;
+        let limited = rest
+            .split_once(", limited: ")
+            .map(|(_, l)| l)
+            .unwrap_or_default();
+
+        let (resource, requested) = requested.split(',').next()?.split_once('=')?;
+        let limit = limited
+            .split(',')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| key.trim() == resource.trim())
+            .map(|(_, value)| value.trim().to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        Some(Self {
+            quota_name: quota_name.trim().to_string(),
+            resource: resource.trim().to_string(),
+            requested: requested.trim().to_string(),
+            limit,
+        })
+    }
+
+    /// Body of the `402 Payment Required` answer, pointing at the plans that allow more
+    pub fn response_body(&self) -> serde_json::Value {
+        serde_json::json!({
+            "error_code": "QUOTA_EXCEEDED",
+            "message": format!(
+                "Your plan's resource quota '{}' does not allow {} of {} (limit: {}). Upgrade your plan to get more resources.",
+                self.quota_name, self.requested, self.resource, self.limit
+            ),
+            "upgrade_url": "/billing/presets"
+        })
+    }
+}
@@ -2,10 +2,12 @@ pub mod cache_keys;
 pub mod channel_names;
 pub mod configs;
 pub mod crds;
+pub mod cron;
 pub mod determiners;
 pub mod event;
 pub mod formatters;
 pub mod github_app;
+pub mod gitlab_app;
 pub mod helpers;
 pub mod implementations;
 pub mod models;
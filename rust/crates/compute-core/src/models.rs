@@ -6,6 +6,7 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, Type, types::Json};
 use uuid::Uuid;
+use validator::Validate;
 
 use crate::schemas::DeploymentSource;
 
@@ -94,6 +95,86 @@ pub enum DeploymentEventLevel {
     Error,
 }
 
+/// What compute-metrics-worker suggests doing with a deployment's replicas
+#[derive(Type, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "scaling_action", rename_all = "snake_case")]
+pub enum ScalingAction {
+    ScaleUp,
+    ScaleDown,
+}
+
+/// Builder behind a build, only BuildKit Jobs (Dockerfile and railpack) are spawned today
+#[derive(Type, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "build_type", rename_all = "snake_case")]
+pub enum BuildType {
+    Kpack,
+    Buildkit,
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "build_status", rename_all = "snake_case")]
+pub enum BuildStatus {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// What the main container runs as, a long running `Service` behind a Deployment, Service and
+/// IngressRoute, or a `CronJob` started on a schedule and never routed to
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, JsonSchema, Debug)]
+#[sqlx(type_name = "deployment_type", rename_all = "snake_case")]
+pub enum DeploymentType {
+    #[default]
+    Service,
+    CronJob,
+}
+
+/// What a `job_queue` row's payload is, a `CreateDeploymentMessage` of a source that still has
+/// to be built or an `UpdateDeploymentMessage` rebuilding a pushed commit
+#[derive(Type, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "job_type", rename_all = "snake_case")]
+pub enum JobType {
+    Build,
+    Rebuild,
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "notification_channel_type", rename_all = "snake_case")]
+pub enum NotificationChannelType {
+    Slack,
+    Discord,
+}
+
+/// What a notification channel can subscribe to, stored as text in `notification_channels.events`
+#[derive(Type, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum NotificationEvent {
+    DeploymentRunning,
+    /// Unhealthy, degraded or unable to pull its image
+    DeploymentUnhealthy,
+    DeploymentFailed,
+    BuildSucceeded,
+    BuildFailed,
+}
+
+impl std::fmt::Display for NotificationEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DeploymentRunning => write!(f, "deployment_running"),
+            Self::DeploymentUnhealthy => write!(f, "deployment_unhealthy"),
+            Self::DeploymentFailed => write!(f, "deployment_failed"),
+            Self::BuildSucceeded => write!(f, "build_succeeded"),
+            Self::BuildFailed => write!(f, "build_failed"),
+        }
+    }
+}
+
 impl std::fmt::Display for DeploymentEventLevel {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -172,6 +253,8 @@ pub struct DeploymentRow {
     pub environment_variables: Option<Json<Option<HashMap<String, String>>>>,
     #[schemars(with = "Option<HashMap<String, String>>")]
     pub labels: Option<Json<Option<HashMap<String, String>>>>,
+    #[schemars(with = "Option<HashMap<String, String>>")]
+    pub tags: Option<Json<HashMap<String, String>>>,
     pub status: DeploymentStatus,
     pub domain: Option<String>,
     pub subdomain: Option<String>,
@@ -190,10 +273,27 @@ pub struct DeploymentEventRow {
     #[sqlx(rename = "type")]
     pub event_type: DeploymentEventType,
     pub level: DeploymentEventLevel,
+    pub from_status: Option<DeploymentStatus>,
+    pub to_status: Option<DeploymentStatus>,
     pub message: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
+/// A Slack or Discord incoming webhook deployment events of a user are posted to
+#[derive(FromRow, Serialize, Deserialize, Clone, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationChannelRow {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    #[serde(rename = "type")]
+    #[sqlx(rename = "type")]
+    pub channel_type: NotificationChannelType,
+    pub webhook_url: String,
+    pub events: Vec<NotificationEvent>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
 #[derive(FromRow, Serialize, Deserialize, Clone, JsonSchema, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct InstallationRow {
@@ -204,6 +304,16 @@ pub struct InstallationRow {
     pub updated_at: DateTime<Utc>,
 }
 
+/// A claimed `job_queue` row, `attempts` already counts the attempt it was claimed for
+#[derive(FromRow, Clone, Debug)]
+pub struct JobRow {
+    pub id: Uuid,
+    pub job_type: JobType,
+    pub payload: serde_json::Value,
+    pub attempts: i32,
+    pub max_attempts: i32,
+}
+
 // ---------------------------------------------
 // HELPER STRUCTS FOR JSONB FIELDS
 // ---------------------------------------------
@@ -218,6 +328,19 @@ pub struct ResourceSpec {
     pub memory_limit_mb: i32,
 }
 
+/// Usage alert thresholds stored in the `alert_thresholds` JSONB field.
+/// Percentages are relative to the deployment's CPU/memory limits, averaged over `window_minutes`
+#[derive(Serialize, Deserialize, Validate, Clone, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertThreshold {
+    #[validate(range(min = 1.0, max = 100.0))]
+    pub cpu_percent: Option<f64>,
+    #[validate(range(min = 1.0, max = 100.0))]
+    pub memory_percent: Option<f64>,
+    #[validate(range(min = 1, max = 60))]
+    pub window_minutes: i64,
+}
+
 impl Default for ResourceSpec {
     fn default() -> Self {
         Self {
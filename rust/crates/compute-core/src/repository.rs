@@ -1,30 +1,48 @@
-use sqlx::{PgPool, postgres::PgQueryResult};
+use serde::Serialize;
+use sqlx::{Executor, PgPool, Postgres};
 use tracing::instrument;
 use uuid::Uuid;
 
 use crate::models::{
-    DeploymentEventLevel, DeploymentEventRow, DeploymentEventType, DeploymentStatus,
+    DeploymentEventLevel, DeploymentEventRow, DeploymentEventType, DeploymentStatus, JobType,
 };
 
 pub struct DeploymentRepository;
 
 impl DeploymentRepository {
+    #[instrument("deployment_repository.get_status", skip_all, fields(deployment_id = %deployment_id), err)]
+    pub async fn get_status(
+        deployment_id: &Uuid,
+        pool: &PgPool,
+    ) -> Result<Option<DeploymentStatus>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"SELECT status AS "status: DeploymentStatus" FROM deployments WHERE id = $1"#,
+            deployment_id
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Returns the status the deployment had before the update, `None` if the deployment doesn't exist
     #[instrument("deployment_repository.update_status", skip_all, fields(deployment_id = %deployment_id, status = %status), err)]
     pub async fn update_status(
         deployment_id: &Uuid,
         status: DeploymentStatus,
         pool: &PgPool,
-    ) -> Result<PgQueryResult, sqlx::Error> {
-        Ok(sqlx::query!(
+    ) -> Result<Option<DeploymentStatus>, sqlx::Error> {
+        sqlx::query_scalar!(
             r#"
-            UPDATE deployments
+            UPDATE deployments d
             SET status = $1
-            WHERE id = $2"#,
+            FROM (SELECT id, status FROM deployments WHERE id = $2 FOR UPDATE) prev
+            WHERE d.id = prev.id
+            RETURNING prev.status AS "status: DeploymentStatus"
+            "#,
             status as DeploymentStatus,
             deployment_id
         )
-        .execute(pool)
-        .await?)
+        .fetch_optional(pool)
+        .await
     }
 }
 
@@ -46,20 +64,23 @@ impl DeploymentEventRepository {
         deployment_id: &Uuid,
         event_type: DeploymentEventType,
         level: DeploymentEventLevel,
+        (from_status, to_status): (Option<DeploymentStatus>, Option<DeploymentStatus>),
         message: Option<&str>,
         pool: &PgPool,
     ) -> Result<DeploymentEventRow, sqlx::Error> {
         sqlx::query_as!(
             DeploymentEventRow,
             r#"
-            INSERT INTO deployment_events (project_id, deployment_id, type, level, message)
-            VALUES ($1, $2, $3, $4, $5)
+            INSERT INTO deployment_events (project_id, deployment_id, type, level, from_status, to_status, message)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
             RETURNING
                 id,
                 project_id,
                 deployment_id,
                 type AS "event_type: DeploymentEventType",
                 level AS "level: DeploymentEventLevel",
+                from_status AS "from_status: DeploymentStatus",
+                to_status AS "to_status: DeploymentStatus",
                 message,
                 created_at
             "#,
@@ -67,9 +88,42 @@ impl DeploymentEventRepository {
             deployment_id,
             event_type as DeploymentEventType,
             level as DeploymentEventLevel,
+            from_status as Option<DeploymentStatus>,
+            to_status as Option<DeploymentStatus>,
             message
         )
         .fetch_one(pool)
         .await
     }
 }
+
+pub struct JobQueueRepository;
+
+impl JobQueueRepository {
+    /// Pass the transaction that triggers the job, it is only picked up once that commits
+    #[instrument(name = "job_queue_repository.enqueue", skip_all, fields(job_type = ?job_type), err)]
+    pub async fn enqueue<'e, E, T>(
+        job_type: JobType,
+        payload: &T,
+        executor: E,
+    ) -> Result<Uuid, sqlx::Error>
+    where
+        E: Executor<'e, Database = Postgres>,
+        T: Serialize,
+    {
+        let payload =
+            serde_json::to_value(payload).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+
+        sqlx::query_scalar!(
+            r#"
+            INSERT INTO job_queue (job_type, payload)
+            VALUES ($1, $2)
+            RETURNING id
+            "#,
+            job_type as JobType,
+            payload
+        )
+        .fetch_one(executor)
+        .await
+    }
+}
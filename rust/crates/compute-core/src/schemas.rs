@@ -1,16 +1,20 @@
 use chrono::{DateTime, Utc};
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
 use once_cell::sync::Lazy;
 use redis_derive::{FromRedisValue, ToRedisArgs};
 use regex::Regex;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{collections::HashMap, net::IpAddr};
 use uuid::Uuid;
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
 use crate::{
+    cron::CronSchedule,
     github_app::schemas::Repository,
-    models::{DeploymentStatus, ResourceSpec},
+    models::{
+        AlertThreshold, BuildStatus, BuildType, DeploymentStatus, DeploymentType, ResourceSpec,
+    },
 };
 
 // -----------------------------------------------
@@ -60,13 +64,169 @@ pub enum DeploymentSource {
 #[derive(Clone, Deserialize, Serialize, Validate, JsonSchema, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct ImagePullSecret {
+    #[validate(length(min = 1))]
     pub server: String,
+    #[validate(length(min = 1))]
     pub username: String,
+    #[validate(length(min = 1))]
     pub secret: String,
 }
 
+/// Horizontal Pod Autoscaler settings, replicas are driven by average CPU utilization
+#[derive(Clone, Serialize, Deserialize, Validate, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+#[validate(schema(function = "validate_autoscaling_spec"))]
+pub struct AutoscalingSpec {
+    #[validate(range(min = 1, max = 25))]
+    pub min_replicas: i32,
+    #[validate(range(min = 1, max = 25))]
+    pub max_replicas: i32,
+    #[validate(range(min = 1, max = 100))]
+    pub target_cpu_utilization_percentage: i32,
+}
+
+fn validate_autoscaling_spec(spec: &AutoscalingSpec) -> Result<(), ValidationError> {
+    if spec.max_replicas < spec.min_replicas {
+        return Err(ValidationError::new("max_replicas_less_than_min_replicas"));
+    }
+    Ok(())
+}
+
+/// Sends `canary_weight` percent of the traffic to `canary_image` next to the current image,
+/// `promote` rolls the canary image out to the whole deployment and removes the canary
+#[derive(Clone, Serialize, Deserialize, Validate, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CanaryConfig {
+    #[validate(length(min = 1, max = 255), regex(path = *IMAGE_REFERENCE))]
+    pub canary_image: String,
+    #[validate(range(max = 100))]
+    pub canary_weight: u8,
+    pub promote: bool,
+}
+
+/// Rollout pacing for the `RollingUpdate` strategy, values are absolute pod counts or percentages like `"25%"`
+#[derive(Clone, Serialize, Deserialize, Validate, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+#[validate(schema(function = "validate_rolling_update_spec"))]
+pub struct RollingUpdateSpec {
+    #[schemars(with = "serde_json::Value")]
+    pub max_surge: IntOrString,
+    #[schemars(with = "serde_json::Value")]
+    pub max_unavailable: IntOrString,
+}
+
+fn validate_rolling_update_spec(spec: &RollingUpdateSpec) -> Result<(), ValidationError> {
+    fn parse(value: &IntOrString) -> Option<i32> {
+        match value {
+            IntOrString::Int(n) => (*n >= 0).then_some(*n),
+            IntOrString::String(s) => s
+                .strip_suffix('%')
+                .and_then(|p| p.parse::<i32>().ok())
+                .filter(|p| (0..=100).contains(p)),
+        }
+    }
+
+    match (parse(&spec.max_surge), parse(&spec.max_unavailable)) {
+        (Some(0), Some(0)) => Err(ValidationError::new(
+            "max_surge_and_max_unavailable_both_zero",
+        )),
+        (Some(_), Some(_)) => Ok(()),
+        _ => Err(ValidationError::new("invalid_int_or_percentage")),
+    }
+}
+
+/// Health check of the main container, an HTTP GET of `path` when given and a TCP connect
+/// otherwise. Timings left out fall back to the Kubernetes defaults
+#[derive(Clone, Serialize, Deserialize, Validate, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeSpec {
+    #[validate(length(min = 1, max = 255))]
+    pub path: Option<String>,
+    /// The deployment's port when not given
+    #[validate(range(min = 1, max = 65535))]
+    pub port: Option<i32>,
+    #[validate(range(min = 0, max = 3600))]
+    pub initial_delay_seconds: Option<i32>,
+    #[validate(range(min = 1, max = 300))]
+    pub period_seconds: Option<i32>,
+    #[validate(range(min = 1, max = 60))]
+    pub timeout_seconds: Option<i32>,
+    #[validate(range(min = 1, max = 100))]
+    pub failure_threshold: Option<i32>,
+}
+
+/// When the kubelet pulls the main image, `Always` picks up new pushes of mutable tags like
+/// `latest`, `IfNotPresent` is enough for tags that never move and digests
+#[derive(Clone, Copy, Default, Serialize, Deserialize, JsonSchema, Debug, PartialEq, Eq)]
+pub enum ImagePullPolicy {
+    Always,
+    #[default]
+    IfNotPresent,
+    Never,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, JsonSchema, Debug)]
+pub enum VolumeAccessMode {
+    /// Mountable by pods on a single node, what most storage classes support
+    ReadWriteOnce,
+    ReadWriteMany,
+    ReadOnlyMany,
+}
+
+/// Persistent volume claimed for the deployment and mounted into the main container
+#[derive(Clone, Serialize, Deserialize, Validate, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VolumeSpec {
+    #[validate(length(min = 1, max = 40), regex(path = *SUBDOMAIN))]
+    pub name: String,
+    #[validate(length(min = 2, max = 255), regex(path = *MOUNT_PATH))]
+    pub mount_path: String,
+    /// Cluster default storage class when not given
+    pub storage_class: Option<String>,
+    #[validate(range(min = 1, max = 100))]
+    pub size_gi: u32,
+    pub access_mode: VolumeAccessMode,
+    /// Keep the claim, and its data, after the deployment is deleted
+    #[serde(default)]
+    pub retain_on_delete: bool,
+}
+
+/// Non-sensitive configuration stored in a `ConfigMap`. Mounted as one file per key under
+/// `mount_path` when given, otherwise every key becomes an environment variable
+#[derive(Clone, Serialize, Deserialize, Validate, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigMapSpec {
+    #[validate(length(min = 1, max = 40), regex(path = *SUBDOMAIN))]
+    pub name: String,
+    #[validate(custom(function = "validate_config_map_data"))]
+    pub data: HashMap<String, String>,
+    #[validate(length(min = 2, max = 255), regex(path = *MOUNT_PATH))]
+    pub mount_path: Option<String>,
+}
+
+/// Extra container running next to the main image, either as an init container or a sidecar.
+/// Requests and limits are both set to `cpu_millicores` / `memory_mb`.
+#[derive(Clone, Serialize, Deserialize, Validate, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ContainerSpec {
+    #[validate(length(min = 1, max = 63), regex(path = *SUBDOMAIN))]
+    pub name: String,
+    #[validate(length(min = 1, max = 512))]
+    pub image: String,
+    pub command: Option<Vec<String>>,
+    pub args: Option<Vec<String>>,
+    pub environment_variables: Option<HashMap<String, String>>,
+    #[validate(range(min = 1, max = 65535))]
+    pub port: Option<i32>,
+    #[validate(range(min = 1))]
+    pub cpu_millicores: i32,
+    #[validate(range(min = 1))]
+    pub memory_mb: i32,
+}
+
 #[derive(Clone, Deserialize, Validate, JsonSchema, Debug)]
 #[serde(rename_all = "camelCase")]
+#[validate(schema(function = "validate_create_deployment_request"))]
 pub struct CreateDeploymentRequest {
     #[validate(length(min = 1, max = 128))]
     pub name: String,
@@ -81,18 +241,203 @@ pub struct CreateDeploymentRequest {
     pub secrets: Option<HashMap<String, String>>,
     pub environment_variables: Option<HashMap<String, String>>,
     pub labels: Option<HashMap<String, String>>,
+    /// Organizational key/value pairs like `env: production`, applied as labels of the K8s Deployment
+    #[validate(custom(function = "validate_tags"))]
+    pub tags: Option<HashMap<String, String>>,
     #[validate(length(min = 3, max = 253), regex(path = *DOMAIN))]
     pub domain: Option<String>,
     #[validate(length(min = 3, max = 63), regex(path = *SUBDOMAIN))]
     pub subdomain: Option<String>,
+    #[validate(nested)]
+    pub autoscaling: Option<AutoscalingSpec>,
+    #[validate(nested)]
+    pub init_containers: Option<Vec<ContainerSpec>>,
+    #[validate(nested)]
+    pub sidecar_containers: Option<Vec<ContainerSpec>>,
+    #[validate(nested)]
+    pub rolling_update: Option<RollingUpdateSpec>,
+    /// Keeps at least one pod up through node drains while there's more than one, defaults to on
+    pub create_pdb: Option<bool>,
+    /// Spreads the pods evenly over availability zones, needs more than one replica
+    pub spread_across_zones: Option<bool>,
+    #[validate(nested)]
+    pub volumes: Option<Vec<VolumeSpec>>,
+    #[validate(length(max = 20), nested)]
+    pub config_maps: Option<Vec<ConfigMapSpec>>,
+    #[validate(nested)]
+    pub alert_thresholds: Option<AlertThreshold>,
+    /// Suspends the deployment after this many minutes without requests, the next one resumes it
+    #[validate(range(min = 5, max = 10080))]
+    pub suspend_after_idle_minutes: Option<i32>,
+    /// Rolls the pods whenever the cron expression matches, e.g. `0 3 * * *` every night at 03:00 UTC
+    #[validate(custom(function = "validate_cron_expression"))]
+    pub restart_schedule: Option<String>,
+    /// Cancels a build still running after this many seconds, 900 when not given
+    #[validate(range(min = 60, max = 7200))]
+    pub build_timeout_seconds: Option<u32>,
+    /// `Service` when not given. A `CronJob` needs an `Image` source and a `schedule`
+    pub deployment_type: Option<DeploymentType>,
+    /// When a `CronJob` runs, e.g. `*/15 * * * *` every 15 minutes
+    #[validate(custom(function = "validate_cron_expression"))]
+    pub schedule: Option<String>,
+    /// Cluster to run in, see `GET /compute/regions`. The default region when not given
+    #[validate(length(min = 1, max = 63))]
+    pub region: Option<String>,
+    /// `IfNotPresent` when not given
+    pub image_pull_policy: Option<ImagePullPolicy>,
+    /// Restarts the container once it stops answering
+    #[validate(nested)]
+    pub liveness_probe: Option<ProbeSpec>,
+    /// Takes the pod out of the Service while it fails
+    #[validate(nested)]
+    pub readiness_probe: Option<ProbeSpec>,
+    /// Holds liveness and readiness checks off until it succeeds once, slow starters get
+    /// `failureThreshold * periodSeconds` to come up. With it set, the liveness probe's
+    /// `initialDelaySeconds` should typically be 0
+    #[validate(nested)]
+    pub startup_probe: Option<ProbeSpec>,
+    /// Only these addresses or CIDR ranges reach the deployment, e.g. `203.0.113.0/24`.
+    /// Everyone else gets a 403
+    #[validate(length(min = 1, max = 50), custom(function = "validate_ip_allowlist"))]
+    pub ip_allowlist: Option<Vec<String>>,
 }
 
 static SUBDOMAIN: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"^[a-z0-9]([-a-z0-9]*[a-z0-9])?$").unwrap());
 
+static MOUNT_PATH: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(/[A-Za-z0-9._-]+)+$").unwrap());
+
+static CONFIG_MAP_KEY: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[-._A-Za-z0-9]+$").unwrap());
+
+/// Leaves room for the object's metadata under the API server's 1 MiB limit
+const CONFIG_MAP_MAX_BYTES: usize = 1_000_000;
+
 static DOMAIN: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"^([a-z0-9]+(-[a-z0-9]+)*\.)+[a-z]{2,}$").unwrap());
 
+/// Name part of a label key and the whole of a label value, values may also be empty
+static LABEL_NAME: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[A-Za-z0-9]([-A-Za-z0-9_.]{0,61}[A-Za-z0-9])?$").unwrap());
+
+/// Tags become labels of the K8s Deployment, so they follow the label syntax and stay out of
+/// the `poddle.io/` prefix the platform's own labels live under
+fn validate_tags(tags: &HashMap<String, String>) -> Result<(), ValidationError> {
+    for (key, value) in tags {
+        let name = match key.split_once('/') {
+            Some((prefix, name)) => {
+                if prefix.len() > 253 || !DOMAIN.is_match(prefix) {
+                    return Err(ValidationError::new("invalid_tag_key"));
+                }
+                if prefix == "poddle.io" || prefix.ends_with(".poddle.io") {
+                    return Err(ValidationError::new("reserved_tag_key"));
+                }
+                name
+            }
+            None => key.as_str(),
+        };
+        if !LABEL_NAME.is_match(name) {
+            return Err(ValidationError::new("invalid_tag_key"));
+        }
+        if !value.is_empty() && !LABEL_NAME.is_match(value) {
+            return Err(ValidationError::new("invalid_tag_value"));
+        }
+    }
+    Ok(())
+}
+
+/// Keys become file or variable names, the API server caps a `ConfigMap` at 1 MiB
+fn validate_config_map_data(data: &HashMap<String, String>) -> Result<(), ValidationError> {
+    if data.is_empty() {
+        return Err(ValidationError::new("empty_config_map"));
+    }
+    if data
+        .keys()
+        .any(|key| key.len() > 253 || !CONFIG_MAP_KEY.is_match(key))
+    {
+        return Err(ValidationError::new("invalid_config_map_key"));
+    }
+    let size: usize = data.iter().map(|(k, v)| k.len() + v.len()).sum();
+    if size > CONFIG_MAP_MAX_BYTES {
+        return Err(ValidationError::new("config_map_too_large"));
+    }
+    Ok(())
+}
+
+/// Plain addresses or `address/prefix`, IPv4 and IPv6 alike
+fn validate_ip_allowlist(ranges: &Vec<String>) -> Result<(), ValidationError> {
+    for range in ranges {
+        let (address, prefix) = match range.split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (range.as_str(), None),
+        };
+        let Ok(address) = address.parse::<IpAddr>() else {
+            return Err(ValidationError::new("invalid_cidr"));
+        };
+        let max_prefix = if address.is_ipv4() { 32 } else { 128 };
+        if let Some(prefix) = prefix
+            && !prefix
+                .parse::<u8>()
+                .is_ok_and(|prefix| prefix <= max_prefix)
+        {
+            return Err(ValidationError::new("invalid_cidr"));
+        }
+    }
+    Ok(())
+}
+
+fn validate_cron_expression(schedule: &str) -> Result<(), ValidationError> {
+    match CronSchedule::parse(schedule) {
+        Some(_) => Ok(()),
+        None => Err(ValidationError::new("invalid_cron_expression")),
+    }
+}
+
+/// CronJobs run a prebuilt image, build sources only ever roll out as Deployments
+fn validate_create_deployment_request(
+    req: &CreateDeploymentRequest,
+) -> Result<(), ValidationError> {
+    // A ReadWriteOnce claim is recreated on rollout, a surge would wait on it forever
+    let read_write_once = req
+        .volumes
+        .iter()
+        .flatten()
+        .any(|v| matches!(v.access_mode, VolumeAccessMode::ReadWriteOnce));
+    if read_write_once && req.rolling_update.is_some() {
+        return Err(ValidationError::new(
+            "rolling_update_with_read_write_once_volume",
+        ));
+    }
+    if req.deployment_type != Some(DeploymentType::CronJob) {
+        return Ok(());
+    }
+    if req.schedule.is_none() {
+        return Err(ValidationError::new("cron_job_without_schedule"));
+    }
+    if !matches!(req.source, DeploymentSource::Image { .. }) {
+        return Err(ValidationError::new("cron_job_without_image_source"));
+    }
+    // A sidecar never exits, the Job would never complete
+    if req
+        .sidecar_containers
+        .as_ref()
+        .is_some_and(|c| !c.is_empty())
+    {
+        return Err(ValidationError::new("cron_job_with_sidecar_containers"));
+    }
+    if req.autoscaling.is_some() {
+        return Err(ValidationError::new("cron_job_with_autoscaling"));
+    }
+    Ok(())
+}
+
+/// `[registry[:port]/]repository[:tag][@digest]`, lowercase repository path as OCI requires
+pub(crate) static IMAGE_REFERENCE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^[a-z0-9]+([._-][a-z0-9]+)*(:[0-9]+)?(/[a-z0-9]+([._-]+[a-z0-9]+)*)*(:[A-Za-z0-9_][A-Za-z0-9_.-]{0,127})?(@sha256:[a-f0-9]{64})?$",
+    )
+    .unwrap()
+});
+
 #[derive(Deserialize, Validate, Clone, JsonSchema, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateDeploymentRequest {
@@ -108,8 +453,77 @@ pub struct UpdateDeploymentRequest {
     pub secrets_to_delete: Option<Vec<String>>,
     pub environment_variables: Option<HashMap<String, String>>,
     pub labels: Option<Option<HashMap<String, String>>>,
+    /// Replaces the whole set, `{}` removes every tag
+    #[validate(custom(function = "validate_tags"))]
+    pub tags: Option<HashMap<String, String>>,
     pub domain: Option<String>,
     pub subdomain: Option<String>,
+    /// `null` removes the autoscaler, omitting the field leaves it untouched
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[schemars(with = "Option<AutoscalingSpec>")]
+    #[validate(nested)]
+    pub autoscaling: Option<Option<AutoscalingSpec>>,
+    #[validate(nested)]
+    pub alert_thresholds: Option<AlertThreshold>,
+    /// Replaces the whole set, `[]` removes every config map
+    #[validate(length(max = 20), nested)]
+    pub config_maps: Option<Vec<ConfigMapSpec>>,
+    /// Can't be combined with a `source` change
+    #[validate(nested)]
+    pub canary: Option<CanaryConfig>,
+    /// Replaces the whole list, `[]` opens the deployment to everyone again
+    #[validate(length(max = 50), custom(function = "validate_ip_allowlist"))]
+    pub ip_allowlist: Option<Vec<String>>,
+}
+
+/// Keys left out keep their current value
+#[derive(Deserialize, Validate, Clone, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RotateSecretsRequest {
+    #[validate(length(min = 1))]
+    pub secrets: HashMap<String, String>,
+}
+
+/// Merged into the stored environment variables, keys in neither list keep their current value
+#[derive(Deserialize, Validate, Clone, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+#[validate(schema(function = "validate_patch_environment_variables"))]
+pub struct PatchEnvironmentVariablesRequest {
+    #[serde(default)]
+    pub set: HashMap<String, String>,
+    #[serde(default)]
+    pub unset: Vec<String>,
+}
+
+fn validate_patch_environment_variables(
+    req: &PatchEnvironmentVariablesRequest,
+) -> Result<(), ValidationError> {
+    if req.set.is_empty() && req.unset.is_empty() {
+        return Err(ValidationError::new("nothing_to_patch"));
+    }
+    if req.unset.iter().any(|key| req.set.contains_key(key)) {
+        return Err(ValidationError::new("key_both_set_and_unset"));
+    }
+    Ok(())
+}
+
+#[derive(Serialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentVariablesResponse {
+    pub environment_variables: HashMap<String, String>,
+}
+
+/// `finished_at` stays empty while the build runs, `image` is pushed once it succeeds
+#[derive(Serialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildSummary {
+    pub id: Uuid,
+    pub deployment_id: Uuid,
+    pub build_type: BuildType,
+    pub status: BuildStatus,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub image: String,
 }
 
 #[derive(Serialize, JsonSchema, Debug)]
@@ -131,12 +545,15 @@ pub struct DeploymentResponse {
     pub vault_secret_path: Option<String>,
     pub environment_variables: Option<HashMap<String, String>>,
     pub labels: Option<HashMap<String, String>>,
+    pub tags: Option<HashMap<String, String>>,
     pub status: DeploymentStatus,
     pub domain: Option<String>,
     pub subdomain: Option<String>,
     pub service: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Only filled in when a single deployment is fetched, `None` for image deployments
+    pub latest_build: Option<BuildSummary>,
 }
 
 #[derive(Serialize, JsonSchema, Debug)]
@@ -158,6 +575,7 @@ pub struct DeploymentsResponse {
     pub vault_secret_path: Option<String>,
     pub environment_variables: Option<HashMap<String, String>>,
     pub labels: Option<HashMap<String, String>>,
+    pub tags: Option<HashMap<String, String>>,
     pub status: DeploymentStatus,
     pub domain: Option<String>,
     pub subdomain: Option<String>,
@@ -200,6 +618,25 @@ pub enum DeploymentSourceMessage {
     InternalBuildComplete {
         url: String,
     },
+    /// A push to the linked branch, rebuilt with the settings the deployment was created with
+    GitPush {
+        clone_url: String,
+        branch: String,
+        commit_sha: String,
+        context_path: Option<String>,
+        /// Set for Dockerfile deployments, the rest are built by railpack
+        dockerfile_path: Option<String>,
+        use_dockerfile: bool,
+    },
+    /// Same as `GitPush`, for repositories hosted on GitLab
+    GitlabPush {
+        clone_url: String,
+        branch: String,
+        commit_sha: String,
+        context_path: Option<String>,
+        dockerfile_path: Option<String>,
+        use_dockerfile: bool,
+    },
 }
 
 /// Message sent to `compute.create` queue
@@ -218,8 +655,50 @@ pub struct CreateDeploymentMessage {
     pub secrets: Option<HashMap<String, String>>,
     pub environment_variables: Option<HashMap<String, String>>,
     pub labels: Option<HashMap<String, String>>,
+    pub tags: Option<HashMap<String, String>>,
     pub domain: Option<String>,
     pub subdomain: Option<String>,
+    pub autoscaling: Option<AutoscalingSpec>,
+    pub init_containers: Option<Vec<ContainerSpec>>,
+    pub sidecar_containers: Option<Vec<ContainerSpec>>,
+    pub rolling_update: Option<RollingUpdateSpec>,
+    #[serde(default)]
+    pub create_pdb: bool,
+    #[serde(default)]
+    pub spread_across_zones: bool,
+    pub volumes: Option<Vec<VolumeSpec>>,
+    #[serde(default)]
+    pub config_maps: Option<Vec<ConfigMapSpec>>,
+    pub suspend_after_idle_minutes: Option<i32>,
+    pub restart_schedule: Option<String>,
+    /// Resolved by compute-api, `None` only in messages queued before regions existed
+    #[serde(default)]
+    pub region: Option<String>,
+    pub image_pull_policy: Option<ImagePullPolicy>,
+    pub liveness_probe: Option<ProbeSpec>,
+    pub readiness_probe: Option<ProbeSpec>,
+    pub startup_probe: Option<ProbeSpec>,
+    /// Only validates the resources, `deployment_id` is a throwaway id nothing is stored under
+    /// and the result is published on `ChannelNames::dry_run` keyed by it
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub ip_allowlist: Option<Vec<String>>,
+    #[serde(default)]
+    pub alert_thresholds: Option<AlertThreshold>,
+    #[serde(default)]
+    pub deployment_type: DeploymentType,
+    /// Cron expression of a `CronJob`, unused for services
+    #[serde(default)]
+    pub schedule: Option<String>,
+}
+
+/// What the API server said about the resources of a dry run
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunResult {
+    pub valid: bool,
+    pub errors: Vec<String>,
 }
 
 /// Message sent to `compute.scale` queue
@@ -238,11 +717,42 @@ pub struct UpdateDeploymentMessage {
     pub secrets: Option<HashMap<String, String>>,
     pub environment_variables: Option<HashMap<String, String>>,
     pub labels: Option<Option<HashMap<String, String>>>,
+    /// The whole set, the provisioner falls back to the stored tags when not given
+    pub tags: Option<HashMap<String, String>>,
     pub domain: Option<String>,
     pub subdomain: Option<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub autoscaling: Option<Option<AutoscalingSpec>>,
+    pub canary: Option<CanaryConfig>,
+    /// Rolls the pods by stamping `poddle.io/restart-at` on the pod template with this timestamp
+    #[serde(default)]
+    pub restart_at: Option<i64>,
+    /// `Some(vec![])` drops the allowlist, `None` keeps whatever is applied
+    #[serde(default)]
+    pub ip_allowlist: Option<Vec<String>>,
+    /// Replaces the alerting rules, `None` keeps whatever is applied
+    #[serde(default)]
+    pub alert_thresholds: Option<AlertThreshold>,
+    /// The whole set, `Some(vec![])` deletes every config map, `None` keeps whatever is applied
+    #[serde(default)]
+    pub config_maps: Option<Vec<ConfigMapSpec>>,
     pub timestamp: i64,
 }
 
+/// Message sent to `compute.diff` queue, the update is flattened so the message still carries
+/// the ids a dead-lettered delivery is reported by
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeploymentDiffMessage {
+    pub request_id: Uuid,
+    #[serde(flatten)]
+    pub update: UpdateDeploymentMessage,
+}
+
 /// Message sent to `compute.delete` queue
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -250,9 +760,89 @@ pub struct DeleteDeploymentMessage {
     pub user_id: Uuid,
     pub project_id: Uuid,
     pub deployment_id: Uuid,
+    /// Read before the row is gone, the provisioner can't look it up anymore
+    #[serde(default)]
+    pub region: Option<String>,
     pub timestamp: i64,
 }
 
+/// Message sent to `compute.delete_user` queue once the user's deployments are gone, tears
+/// down the namespace and the Vault secrets left under it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteUserMessage {
+    pub user_id: Uuid,
+    pub timestamp: i64,
+}
+
+/// Published to the `compute.deployment_deleted` topic once a deployment is torn down
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeploymentDeletedEvent {
+    pub user_id: Uuid,
+    pub project_id: Uuid,
+    pub deployment_id: Uuid,
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// Message sent to `compute.suspend` queue
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SuspendDeploymentMessage {
+    pub user_id: Uuid,
+    pub project_id: Uuid,
+    pub deployment_id: Uuid,
+    pub timestamp: i64,
+}
+
+/// Message sent to `compute.resume` queue
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ResumeDeploymentMessage {
+    pub user_id: Uuid,
+    pub project_id: Uuid,
+    pub deployment_id: Uuid,
+    pub timestamp: i64,
+}
+
+/// Message sent to `compute.registry_credentials` queue
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RotateRegistryCredentialsMessage {
+    pub user_id: Uuid,
+    pub project_id: Uuid,
+    pub deployment_id: Uuid,
+    pub image_pull_secret: ImagePullSecret,
+    pub timestamp: i64,
+}
+
+/// Message sent to `compute.secrets` queue
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RotateSecretsMessage {
+    pub user_id: Uuid,
+    pub project_id: Uuid,
+    pub deployment_id: Uuid,
+    pub secrets: HashMap<String, String>,
+    pub timestamp: i64,
+}
+
+/// One write to a deployment's Vault secret, values are never recorded
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretAuditEntry {
+    pub changed_by: Uuid,
+    pub changed_at: DateTime<Utc>,
+    /// Added, removed or changed keys
+    pub fields_changed: Vec<String>,
+}
+
+/// Stored at `audit/{namespace}/{deployment_id}` in the KV mount, oldest entry first
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct SecretAuditTrail {
+    pub entries: Vec<SecretAuditEntry>,
+}
+
 // -----------------------------------------------
 // POD & DEPLOYMENT METRICS
 // -----------------------------------------------
@@ -306,3 +896,12 @@ pub struct PodMetricUpdate {
     pub meta: PodMeta,
     pub snapshot: MetricSnapshot,
 }
+
+/// A ResourceQuota in the user's namespace refused what the plan asked for
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuotaExceeded {
+    pub quota_name: String,
+    pub resource: String,
+    pub requested: String,
+    pub limit: String,
+}
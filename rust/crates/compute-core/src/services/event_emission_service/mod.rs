@@ -8,8 +8,9 @@ use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::{
+    cache_keys::CacheKeys,
     channel_names::ChannelNames,
-    event::ComputeEvent,
+    event::{ComputeEvent, VersionedEvent},
     helpers::map_status_to_event_level,
     models::{DeploymentEventLevel, DeploymentEventType, DeploymentStatus},
     repository::{DeploymentEventRepository, DeploymentRepository},
@@ -38,6 +39,7 @@ pub struct DeploymentEventEmitterInput<'a> {
     pub event_type: Option<DeploymentEventType>,
     pub level: Option<DeploymentEventLevel>,
     pub message: Option<&'a str>,
+    /// Written to history even when the status didn't change, callers seeing resyncs decide
     pub persist_event: bool,
     pub publish_project: bool,
     pub publish_deployment: bool,
@@ -46,6 +48,7 @@ pub struct DeploymentEventEmitterInput<'a> {
 pub struct DeploymentEventEmitter;
 
 impl DeploymentEventEmitter {
+    /// Returns whether the deployment moved to a different status, `false` when none was given
     #[tracing::instrument(
         name = "deployment_event_emitter.emit",
         skip_all,
@@ -61,7 +64,7 @@ impl DeploymentEventEmitter {
         input: DeploymentEventEmitterInput<'_>,
         pool: &PgPool,
         con: &mut MultiplexedConnection,
-    ) -> Result<(), EventEmissionServiceError> {
+    ) -> Result<bool, EventEmissionServiceError> {
         let level = input
             .level
             .or_else(|| input.status.map(map_status_to_event_level))
@@ -69,33 +72,45 @@ impl DeploymentEventEmitter {
 
         let mut persisted_id = None;
         let mut created_at = Utc::now();
+        let mut from_status = None;
 
         if let Some(status) = input.status {
-            let res =
+            from_status =
                 DeploymentRepository::update_status(input.deployment_id, status, pool).await?;
-            if res.rows_affected() == 0 {
+            if from_status.is_none() {
                 tracing::warn!("deployment status update affected zero rows");
             }
         }
 
-        if input.persist_event {
-            if let Some(event_type) = input.event_type {
-                let row = DeploymentEventRepository::create(
-                    input.project_id,
-                    input.deployment_id,
-                    event_type,
-                    level,
-                    input.message,
-                    pool,
-                )
-                .await?;
-
-                persisted_id = Some(row.id);
-                created_at = row.created_at;
-            }
+        let trivial = input.status.is_some() && from_status == input.status;
+
+        // The projects list counts deployments by status, the next request recounts
+        if input.status.is_some() && !trivial {
+            con.del(CacheKeys::project_deployment_counts(
+                &input.project_id.to_string(),
+            ))
+            .await?;
+        }
+
+        if input.persist_event
+            && let Some(event_type) = input.event_type
+        {
+            let row = DeploymentEventRepository::create(
+                input.project_id,
+                input.deployment_id,
+                event_type,
+                level,
+                (from_status, input.status),
+                input.message,
+                pool,
+            )
+            .await?;
+
+            persisted_id = Some(row.id);
+            created_at = row.created_at;
         }
 
-        let message = ComputeEvent::DeploymentEvent {
+        let message = VersionedEvent::new(ComputeEvent::DeploymentEvent {
             event: DeploymentEventUpdate {
                 id: persisted_id,
                 project_id: *input.project_id,
@@ -107,18 +122,18 @@ impl DeploymentEventEmitter {
                 message: input.message.map(str::to_string),
                 created_at,
             },
-        };
+        });
 
         if input.publish_project {
             let channel = ChannelNames::project_events(&input.project_id.to_string());
-            con.publish(channel, &message).await?;
+            message.publish(&channel, con).await?;
         }
 
         if input.publish_deployment {
             let channel = ChannelNames::deployment_events(&input.deployment_id.to_string());
-            con.publish(channel, message).await?;
+            message.publish(&channel, con).await?;
         }
 
-        Ok(())
+        Ok(input.status.is_some() && !trivial)
     }
 }
@@ -0,0 +1,39 @@
+use bigdecimal::BigDecimal;
+use chrono::Utc;
+use compute_core::{models::PresetRow, schemas::CreateDeploymentRequest};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+/// 500m / 512MB, with room for 250m / 256MB of add-ons
+pub fn preset() -> PresetRow {
+    PresetRow {
+        id: Uuid::new_v4(),
+        name: "starter".to_string(),
+        description: None,
+        cpu_millicores: 500,
+        memory_mb: 512,
+        currency: "USD".to_string(),
+        monthly_price: BigDecimal::from(5),
+        hourly_price: BigDecimal::from(0),
+        max_addon_cpu_millicores: 250,
+        max_addon_memory_mb: 256,
+        is_active: true,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+/// A single replica `nginx:1.27` deployment, `extra` overrides or adds fields
+pub fn request(extra: Value) -> CreateDeploymentRequest {
+    let mut req = json!({
+        "name": "web",
+        "source": { "type": "image", "url": "nginx:1.27" },
+        "port": 80,
+        "desiredReplicas": 1,
+        "presetId": Uuid::new_v4(),
+    });
+    req.as_object_mut()
+        .unwrap()
+        .extend(extra.as_object().unwrap().clone());
+    serde_json::from_value(req).unwrap()
+}
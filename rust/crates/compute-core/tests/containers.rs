@@ -0,0 +1,138 @@
+mod common;
+
+use compute_core::schemas::ContainerSpec;
+use serde_json::json;
+
+use crate::common::{preset, request};
+
+fn container(name: &str, cpu_millicores: i32, memory_mb: i32) -> ContainerSpec {
+    serde_json::from_value(json!({
+        "name": name,
+        "image": "busybox:1.36",
+        "cpuMillicores": cpu_millicores,
+        "memoryMb": memory_mb,
+    }))
+    .unwrap()
+}
+
+#[test]
+fn aggregate_sums_every_container() {
+    let total = ContainerSpec::aggregate(&[
+        container("proxy", 100, 64),
+        container("agent", 50, 128),
+        container("shipper", 25, 32),
+    ]);
+
+    assert_eq!(total.cpu_request_millicores, 175);
+    assert_eq!(total.cpu_limit_millicores, 175);
+    assert_eq!(total.memory_request_mb, 224);
+    assert_eq!(total.memory_limit_mb, 224);
+}
+
+#[test]
+fn aggregate_of_nothing_is_zero() {
+    let total = ContainerSpec::aggregate(&[]);
+
+    assert_eq!(total.cpu_limit_millicores, 0);
+    assert_eq!(total.memory_limit_mb, 0);
+}
+
+#[test]
+fn sidecars_share_the_addon_budget() {
+    let fits = request(json!({
+        "addonCpuMillicores": 100,
+        "addonMemoryMb": 128,
+        "sidecarContainers": [
+            { "name": "proxy", "image": "envoy:1.31", "cpuMillicores": 100, "memoryMb": 64 },
+            { "name": "agent", "image": "agent:2", "cpuMillicores": 50, "memoryMb": 64 },
+        ],
+    }));
+    assert_eq!(fits.check_preset_limits(&preset()), Ok(()));
+
+    // Each sidecar fits alone, together with the add-ons they go 1m over
+    let over_cpu = request(json!({
+        "addonCpuMillicores": 100,
+        "sidecarContainers": [
+            { "name": "proxy", "image": "envoy:1.31", "cpuMillicores": 100, "memoryMb": 64 },
+            { "name": "agent", "image": "agent:2", "cpuMillicores": 51, "memoryMb": 64 },
+        ],
+    }));
+    let error = over_cpu.check_preset_limits(&preset()).unwrap_err();
+    assert!(
+        error.starts_with("Add-ons and sidecars exceed limits"),
+        "{}",
+        error
+    );
+
+    let over_memory = request(json!({
+        "sidecarContainers": [
+            { "name": "proxy", "image": "envoy:1.31", "cpuMillicores": 10, "memoryMb": 200 },
+            { "name": "agent", "image": "agent:2", "cpuMillicores": 10, "memoryMb": 57 },
+        ],
+    }));
+    let error = over_memory.check_preset_limits(&preset()).unwrap_err();
+    assert!(
+        error.starts_with("Add-ons and sidecars exceed limits"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn init_containers_each_fit_the_pod_and_are_not_summed() {
+    // 600m would be too much summed, each one fits the 500m + 100m pod on its own
+    let fits = request(json!({
+        "addonCpuMillicores": 100,
+        "initContainers": [
+            { "name": "migrate", "image": "migrate:1", "cpuMillicores": 600, "memoryMb": 512 },
+            { "name": "seed", "image": "seed:1", "cpuMillicores": 600, "memoryMb": 512 },
+        ],
+    }));
+    assert_eq!(fits.check_preset_limits(&preset()), Ok(()));
+
+    let too_big = request(json!({
+        "initContainers": [
+            { "name": "migrate", "image": "migrate:1", "cpuMillicores": 100, "memoryMb": 64 },
+            { "name": "warmup", "image": "warmup:1", "cpuMillicores": 100, "memoryMb": 513 },
+        ],
+    }));
+    let error = too_big.check_preset_limits(&preset()).unwrap_err();
+    assert!(error.starts_with("Init container 'warmup'"), "{}", error);
+}
+
+#[test]
+fn addons_over_the_preset_are_rejected_without_containers() {
+    let req = request(json!({ "addonCpuMillicores": 251 }));
+    let error = req.check_preset_limits(&preset()).unwrap_err();
+    assert!(
+        error.starts_with("Requested add-ons exceed limits"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn extra_containers_need_an_image_source() {
+    let req = request(json!({
+        "source": {
+            "type": "dockerfile",
+            "repo": {
+                "id": 1,
+                "name": "web",
+                "fullName": "acme/web",
+                "private": false,
+                "defaultBranch": "main",
+                "cloneUrl": "https://github.com/acme/web.git",
+            },
+        },
+        "sidecarContainers": [
+            { "name": "proxy", "image": "envoy:1.31", "cpuMillicores": 10, "memoryMb": 16 },
+        ],
+    }));
+    let error = req.check_preset_limits(&preset()).unwrap_err();
+    assert!(
+        error.contains("only supported for image deployments"),
+        "{}",
+        error
+    );
+}
@@ -0,0 +1,122 @@
+use chrono::{DateTime, TimeZone, Utc};
+use compute_core::cron::CronSchedule;
+
+fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+        .unwrap()
+}
+
+fn schedule(expression: &str) -> CronSchedule {
+    CronSchedule::parse(expression).unwrap_or_else(|| panic!("{} should parse", expression))
+}
+
+#[test]
+fn ranges_are_inclusive() {
+    let business_hours = schedule("0 9-17 * * *");
+
+    assert!(business_hours.matches(&at(2026, 10, 15, 9, 0)));
+    assert!(business_hours.matches(&at(2026, 10, 15, 17, 0)));
+    assert!(!business_hours.matches(&at(2026, 10, 15, 8, 0)));
+    assert!(!business_hours.matches(&at(2026, 10, 15, 18, 0)));
+    assert!(!business_hours.matches(&at(2026, 10, 15, 9, 1)));
+}
+
+#[test]
+fn steps_count_from_the_start_of_their_range() {
+    let quarter_hours = schedule("*/15 * * * *");
+    for minute in [0, 15, 30, 45] {
+        assert!(quarter_hours.matches(&at(2026, 10, 15, 3, minute)));
+    }
+    assert!(!quarter_hours.matches(&at(2026, 10, 15, 3, 10)));
+
+    // A single value with a step runs to the end of the field
+    let offset = schedule("5/20 * * * *");
+    for minute in [5, 25, 45] {
+        assert!(offset.matches(&at(2026, 10, 15, 3, minute)));
+    }
+    assert!(!offset.matches(&at(2026, 10, 15, 3, 0)));
+
+    let stepped_range = schedule("10-30/10 * * * *");
+    for minute in [10, 20, 30] {
+        assert!(stepped_range.matches(&at(2026, 10, 15, 3, minute)));
+    }
+    assert!(!stepped_range.matches(&at(2026, 10, 15, 3, 40)));
+}
+
+#[test]
+fn lists_combine_values_ranges_and_steps() {
+    let twice_a_day = schedule("0,30 1,13 * * *");
+    assert!(twice_a_day.matches(&at(2026, 10, 15, 1, 0)));
+    assert!(twice_a_day.matches(&at(2026, 10, 15, 13, 30)));
+    assert!(!twice_a_day.matches(&at(2026, 10, 15, 2, 0)));
+
+    let mixed = schedule("0 1-3,*/12,20 * * *");
+    for hour in [0, 1, 2, 3, 12, 20] {
+        assert!(mixed.matches(&at(2026, 10, 15, hour, 0)));
+    }
+    assert!(!mixed.matches(&at(2026, 10, 15, 4, 0)));
+}
+
+#[test]
+fn restricted_day_of_month_and_day_of_week_match_either() {
+    // The 13th and every Friday, not only Friday the 13th
+    let schedule = schedule("0 0 13 * 5");
+
+    // Friday the 13th
+    assert!(schedule.matches(&at(2026, 11, 13, 0, 0)));
+    // Tuesday the 13th
+    assert!(schedule.matches(&at(2026, 10, 13, 0, 0)));
+    // Friday the 16th
+    assert!(schedule.matches(&at(2026, 10, 16, 0, 0)));
+    // Wednesday the 14th
+    assert!(!schedule.matches(&at(2026, 10, 14, 0, 0)));
+}
+
+#[test]
+fn starred_day_field_leaves_the_other_one_deciding() {
+    let fridays = schedule("0 0 * * 5");
+    assert!(fridays.matches(&at(2026, 10, 16, 0, 0)));
+    assert!(!fridays.matches(&at(2026, 10, 13, 0, 0)));
+
+    let thirteenths = schedule("0 0 13 * *");
+    assert!(thirteenths.matches(&at(2026, 10, 13, 0, 0)));
+    assert!(!thirteenths.matches(&at(2026, 10, 16, 0, 0)));
+
+    // Like Vixie cron, a field starting with `*` counts as unrestricted even with a step
+    let stepped_week = schedule("0 0 13 * */2");
+    // Tuesday the 13th
+    assert!(stepped_week.matches(&at(2026, 10, 13, 0, 0)));
+    // Friday the 13th
+    assert!(!stepped_week.matches(&at(2026, 11, 13, 0, 0)));
+    // Thursday the 15th
+    assert!(!stepped_week.matches(&at(2026, 10, 15, 0, 0)));
+}
+
+#[test]
+fn sunday_is_both_zero_and_seven() {
+    let sunday = at(2026, 10, 18, 0, 0);
+
+    assert!(schedule("0 0 * * 0").matches(&sunday));
+    assert!(schedule("0 0 * * 7").matches(&sunday));
+    assert!(!schedule("0 0 * * 7").matches(&at(2026, 10, 17, 0, 0)));
+}
+
+#[test]
+fn malformed_expressions_are_rejected() {
+    for expression in [
+        "",
+        "* * * *",
+        "* * * * * *",
+        "60 * * * *",
+        "* 24 * * *",
+        "* * 0 * *",
+        "* * * 13 *",
+        "* * * * 8",
+        "30-10 * * * *",
+        "*/0 * * * *",
+        "a * * * *",
+        "1,,2 * * * *",
+    ] {
+        assert_eq!(CronSchedule::parse(expression), None, "{:?}", expression);
+    }
+}
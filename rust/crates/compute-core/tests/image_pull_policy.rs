@@ -0,0 +1,67 @@
+mod common;
+
+use compute_core::schemas::{CreateDeploymentMessage, ImagePullPolicy};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::common::{preset, request};
+
+fn message(url: &str, image_pull_policy: Option<ImagePullPolicy>) -> CreateDeploymentMessage {
+    let req = request(json!({
+        "source": { "type": "image", "url": url },
+        "imagePullPolicy": image_pull_policy,
+    }));
+    (
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+        preset(),
+        req,
+    )
+        .into()
+}
+
+#[test]
+fn latest_and_untagged_images_warn() {
+    for url in [
+        "nginx:latest",
+        "nginx",
+        "library/nginx",
+        "ghcr.io/acme/web",
+        "localhost:5000/web",
+        "localhost:5000/web:latest",
+    ] {
+        for policy in [None, Some(ImagePullPolicy::IfNotPresent)] {
+            assert!(
+                message(url, policy).image_pull_policy_warning().is_some(),
+                "{} {:?}",
+                url,
+                policy
+            );
+        }
+    }
+}
+
+#[test]
+fn pinned_images_dont_warn() {
+    for url in [
+        "nginx:1.27",
+        "localhost:5000/web:v2",
+        "nginx@sha256:0d17b565c37bcbd895e9d92315a05c1c3c9a29f762b011a10c54a66cd53c9b31",
+        "nginx:latest@sha256:0d17b565c37bcbd895e9d92315a05c1c3c9a29f762b011a10c54a66cd53c9b31",
+    ] {
+        assert!(
+            message(url, None).image_pull_policy_warning().is_none(),
+            "{}",
+            url
+        );
+    }
+}
+
+#[test]
+fn always_pulling_silences_the_warning() {
+    for url in ["nginx", "nginx:latest"] {
+        let message = message(url, Some(ImagePullPolicy::Always));
+        assert!(message.image_pull_policy_warning().is_none(), "{}", url);
+    }
+}
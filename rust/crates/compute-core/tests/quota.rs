@@ -0,0 +1,78 @@
+use compute_core::schemas::QuotaExceeded;
+use kube::core::Status;
+use serde_json::json;
+
+fn api_error(status: serde_json::Value) -> kube::Error {
+    kube::Error::Api(Box::new(serde_json::from_value::<Status>(status).unwrap()))
+}
+
+#[test]
+fn quota_rejection_is_parsed_from_the_status_message() {
+    let e = api_error(json!({
+        "code": 403,
+        "reason": "Forbidden",
+        "message": "pods \"web-1\" is forbidden: exceeded quota: compute-resources, requested: limits.cpu=2,limits.memory=1Gi, used: limits.cpu=1, limited: limits.memory=2Gi,limits.cpu=2",
+    }));
+
+    assert_eq!(
+        QuotaExceeded::from_kube_error(&e),
+        Some(QuotaExceeded {
+            quota_name: "compute-resources".to_string(),
+            resource: "limits.cpu".to_string(),
+            requested: "2".to_string(),
+            limit: "2".to_string(),
+        })
+    );
+}
+
+#[test]
+fn quota_rejection_is_found_in_the_causes() {
+    let e = api_error(json!({
+        "code": 403,
+        "message": "admission denied",
+        "details": {
+            "causes": [
+                { "message": "unrelated" },
+                { "message": "exceeded quota: pods, requested: pods=1, used: pods=10" },
+            ],
+        },
+    }));
+
+    let quota = QuotaExceeded::from_kube_error(&e).unwrap();
+    assert_eq!(quota.quota_name, "pods");
+    assert_eq!(quota.limit, "unknown");
+}
+
+#[test]
+fn other_errors_are_not_quota_rejections() {
+    for e in [
+        api_error(json!({ "code": 403, "message": "pods is forbidden: User cannot create" })),
+        api_error(
+            json!({ "code": 422, "message": "exceeded quota: x, requested: a=1, used: a=1" }),
+        ),
+    ] {
+        assert_eq!(QuotaExceeded::from_kube_error(&e), None);
+    }
+}
+
+#[test]
+fn response_body_points_at_the_plans() {
+    let quota = QuotaExceeded {
+        quota_name: "compute-resources".to_string(),
+        resource: "limits.memory".to_string(),
+        requested: "1Gi".to_string(),
+        limit: "512Mi".to_string(),
+    };
+    let body = quota.response_body();
+
+    assert_eq!(body["error_code"], "QUOTA_EXCEEDED");
+    assert_eq!(body["upgrade_url"], "/billing/presets");
+    assert!(
+        body["message"]
+            .as_str()
+            .unwrap()
+            .contains("1Gi of limits.memory"),
+        "{}",
+        body
+    );
+}
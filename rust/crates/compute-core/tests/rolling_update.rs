@@ -0,0 +1,77 @@
+use compute_core::schemas::RollingUpdateSpec;
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+use serde_json::{Value, json};
+use validator::Validate;
+
+fn spec(max_surge: Value, max_unavailable: Value) -> RollingUpdateSpec {
+    serde_json::from_value(json!({ "maxSurge": max_surge, "maxUnavailable": max_unavailable }))
+        .unwrap()
+}
+
+fn error_code(spec: &RollingUpdateSpec) -> Option<String> {
+    let errors = spec.validate().err()?;
+    let errors = errors.field_errors();
+    Some(errors["__all__"][0].code.to_string())
+}
+
+#[test]
+fn counts_and_percentages_deserialize_as_int_or_string() {
+    let spec = spec(json!("25%"), json!(1));
+
+    assert_eq!(spec.max_surge, IntOrString::String("25%".to_string()));
+    assert_eq!(spec.max_unavailable, IntOrString::Int(1));
+    assert!(spec.validate().is_ok());
+
+    // Goes back out in the shape the Deployment expects
+    assert_eq!(
+        serde_json::to_value(&spec).unwrap(),
+        json!({ "maxSurge": "25%", "maxUnavailable": 1 })
+    );
+}
+
+#[test]
+fn percentage_bounds_are_inclusive() {
+    assert!(spec(json!("0%"), json!("100%")).validate().is_ok());
+    assert!(spec(json!(0), json!(3)).validate().is_ok());
+}
+
+#[test]
+fn malformed_values_are_rejected() {
+    for (max_surge, max_unavailable) in [
+        (json!("25"), json!(1)),
+        (json!("abc%"), json!(1)),
+        (json!("101%"), json!(1)),
+        (json!("-5%"), json!(1)),
+        (json!(1), json!(-1)),
+        (json!(1), json!("25 %")),
+    ] {
+        let spec = spec(max_surge.clone(), max_unavailable.clone());
+        assert_eq!(
+            error_code(&spec).as_deref(),
+            Some("invalid_int_or_percentage"),
+            "{} {}",
+            max_surge,
+            max_unavailable
+        );
+    }
+}
+
+#[test]
+fn both_zero_would_never_progress() {
+    for (max_surge, max_unavailable) in [(json!(0), json!(0)), (json!("0%"), json!(0))] {
+        assert_eq!(
+            error_code(&spec(max_surge, max_unavailable)).as_deref(),
+            Some("max_surge_and_max_unavailable_both_zero")
+        );
+    }
+}
+
+#[test]
+fn other_json_types_fail_to_deserialize() {
+    for value in [json!(1.5), json!(true), json!(null), json!(["25%"])] {
+        let result = serde_json::from_value::<RollingUpdateSpec>(
+            json!({ "maxSurge": value, "maxUnavailable": 1 }),
+        );
+        assert!(result.is_err(), "{}", value);
+    }
+}
@@ -1,53 +1,42 @@
 use crate::factories::amqp::error::AmqpError;
-use crate::factories::amqp::{Amqp, AmqpConfig, AmqpPropagator};
+use crate::factories::amqp::{
+    Amqp, AmqpConfig, AmqpPropagator, DEAD_LETTER_EXCHANGE, DEAD_LETTER_QUEUE,
+};
+use crate::factories::tls::TlsConfig;
 use axum::{Json, http::StatusCode, response::IntoResponse, response::Response};
 use lapin::ExchangeKind;
 use lapin::options::{
     BasicQosOptions, ExchangeDeclareOptions, QueueBindOptions, QueueDeclareOptions,
+    QueueDeleteOptions,
 };
-use lapin::tcp::OwnedIdentity;
+use lapin::protocol::{AMQPErrorKind, AMQPSoftError};
+use lapin::tcp::{RustlsConnector, TcpStream};
+use lapin::uri::{AMQPScheme, AMQPUri};
 use lapin::{
     BasicProperties, Channel, Connection, ConnectionProperties, options::BasicPublishOptions,
-    tcp::OwnedTLSConfig,
 };
-use opentelemetry::Context;
+use opentelemetry::{Context, global};
 use serde::Serialize;
 use serde_json::json;
+use std::io;
 use std::sync::Arc;
-use tracing::{Span, error, info};
+use std::time::Duration;
+use tracing::{Span, error, info, warn};
 
 use lapin::types::{AMQPValue, FieldTable, ShortString};
-use opentelemetry::propagation::TextMapPropagator;
-use opentelemetry_sdk::propagation::TraceContextPropagator;
 use std::collections::HashMap;
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 
 impl Amqp {
     pub async fn new(cfg: &AmqpConfig) -> Self {
         let uri = cfg.uri.clone();
-
-        let mut config = OwnedTLSConfig::default();
-
-        if let Some(tls_config) = &cfg.tls_config {
-            if let (Some(ca), Some(client_cert), Some(client_key)) = (
-                tls_config.ca.clone(),
-                tls_config.client_cert.clone(),
-                tls_config.client_key.clone(),
-            ) {
-                info!("🔐 AMQP SSL/TLS enabled");
-                config.cert_chain = Some(ca.to_string());
-                config.identity = Some(OwnedIdentity::PKCS8 {
-                    pem: client_cert.clone().into_bytes(),
-                    key: client_key.clone().into_bytes(),
-                });
-            }
-        }
-
         let options = ConnectionProperties::default();
 
-        let connection = Connection::connect_with_config(&uri, options, config)
-            .await
-            .expect(format!("Failed to connect to RabbitMQ at {}", uri).as_str());
+        let connection = match &cfg.tls_config {
+            Some(tls_config) => Self::connect_tls(&uri, tls_config, options).await,
+            None => Connection::connect(&uri, options).await,
+        }
+        .unwrap_or_else(|e| panic!("Failed to connect to RabbitMQ at {}: {}", uri, e));
         info!("✅ RabbitMQ connection created");
 
         Self {
@@ -55,12 +44,47 @@ impl Amqp {
         }
     }
 
-    pub async fn channel(&self) -> Channel {
-        let channel = self
-            .connection
-            .create_channel()
-            .await
-            .expect("Failed to create channel");
+    /// Connects over `amqps://` with the rustls config built from `tls_config`, the server is
+    /// verified against its CA and the client cert/key, when given, are sent for mutual TLS
+    #[allow(clippy::result_large_err)] // HandshakeResult is lapin's connector signature
+    async fn connect_tls(
+        uri: &str,
+        tls_config: &TlsConfig,
+        options: ConnectionProperties,
+    ) -> lapin::Result<Connection> {
+        let amqp_uri = uri
+            .parse::<AMQPUri>()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        if amqp_uri.scheme != AMQPScheme::AMQPS {
+            panic!("AMQP TLS is configured but {} is not an amqps:// URI", uri);
+        }
+
+        info!("🔐 AMQP SSL/TLS enabled");
+        let connector = RustlsConnector::from(Arc::new(tls_config.build_rustls_config()));
+
+        Connection::connector(
+            amqp_uri,
+            Box::new(move |uri: &AMQPUri| {
+                let addr = (uri.authority.host.as_str(), uri.authority.port);
+                let stream = match uri.query.connection_timeout {
+                    Some(timeout) => {
+                        TcpStream::connect_timeout(addr, Duration::from_millis(timeout))
+                    }
+                    None => TcpStream::connect(addr),
+                }?;
+                let stream = stream.into_rustls(&connector, &uri.authority.host)?;
+                stream.set_nonblocking(true)?;
+                Ok(stream)
+            }),
+            options,
+        )
+        .await
+    }
+
+    /// A channel on which the `compute` topology is declared. Declaring it on an older broker
+    /// state can fail, which is returned rather than panicking the caller
+    pub async fn channel(&self) -> Result<Channel, AmqpError> {
+        let mut channel = self.connection.create_channel().await?;
 
         let exchange = "compute";
 
@@ -78,30 +102,51 @@ impl Amqp {
                 },
                 FieldTable::default(),
             )
-            .await
-            .expect("Failed to declare exchange");
+            .await?;
+
+        // Dead letters
+        channel
+            .exchange_declare(
+                DEAD_LETTER_EXCHANGE,
+                ExchangeKind::Topic,
+                ExchangeDeclareOptions {
+                    durable: true,
+                    auto_delete: false,
+                    internal: false,
+                    nowait: false,
+                    passive: false,
+                },
+                FieldTable::default(),
+            )
+            .await?;
+
+        channel
+            .queue_declare(
+                DEAD_LETTER_QUEUE,
+                QueueDeclareOptions {
+                    durable: true,
+                    exclusive: false,
+                    auto_delete: false,
+                    nowait: false,
+                    passive: false,
+                },
+                FieldTable::default(),
+            )
+            .await?;
+
+        channel
+            .queue_bind(
+                DEAD_LETTER_QUEUE,
+                DEAD_LETTER_EXCHANGE,
+                "#",
+                QueueBindOptions::default(),
+                FieldTable::default(),
+            )
+            .await?;
 
         // Declare queues
-        for queue in &["compute.create", "compute.update", "compute.delete"] {
-            let mut args = FieldTable::default();
-            args.insert(
-                "x-dead-letter-exchange".into(),
-                AMQPValue::LongString("compute.dead_letter".into()),
-            );
-            channel
-                .queue_declare(
-                    queue,
-                    QueueDeclareOptions {
-                        durable: true,
-                        exclusive: false,
-                        auto_delete: false,
-                        nowait: false,
-                        passive: false,
-                    },
-                    args,
-                )
-                .await
-                .expect("Failed to declare queue");
+        for queue in COMPUTE_QUEUES {
+            channel = self.declare_dead_lettered_queue(channel, queue).await?;
 
             channel
                 .queue_bind(
@@ -111,17 +156,61 @@ impl Amqp {
                     QueueBindOptions::default(),
                     FieldTable::default(),
                 )
-                .await
-                .expect("Failed to bind queue");
+                .await?;
         }
 
         // Set QoS (prefetch)
-        channel
-            .basic_qos(10, BasicQosOptions::default())
-            .await
-            .expect("Failed to set basic QoS");
+        channel.basic_qos(10, BasicQosOptions::default()).await?;
+
+        Ok(channel)
+    }
 
+    /// Queues declared before dead lettering existed lack `x-dead-letter-exchange`, and the broker
+    /// refuses a redeclare with different arguments with 406 PRECONDITION_FAILED and closes the
+    /// channel. Such a queue is deleted and declared again if it is empty. A queue that still
+    /// holds messages is left alone and the error is returned, deleting it would lose them
+    async fn declare_dead_lettered_queue(
+        &self,
+        channel: Channel,
+        queue: &str,
+    ) -> Result<Channel, AmqpError> {
+        let options = QueueDeclareOptions {
+            durable: true,
+            exclusive: false,
+            auto_delete: false,
+            nowait: false,
+            passive: false,
+        };
+        let mut args = FieldTable::default();
+        args.insert(
+            "x-dead-letter-exchange".into(),
+            AMQPValue::LongString(DEAD_LETTER_EXCHANGE.into()),
+        );
+
+        match channel.queue_declare(queue, options, args.clone()).await {
+            Ok(_) => return Ok(channel),
+            Err(e) if !is_precondition_failed(&e) => return Err(e.into()),
+            Err(_) => {}
+        }
+
+        warn!(
+            queue = queue,
+            "⚠️ Queue was declared without a dead letter exchange, redeclaring it"
+        );
+        let channel = self.connection.create_channel().await?;
         channel
+            .queue_delete(
+                queue,
+                QueueDeleteOptions {
+                    if_unused: false,
+                    if_empty: true,
+                    nowait: false,
+                },
+            )
+            .await?;
+        channel.queue_declare(queue, options, args).await?;
+
+        Ok(channel)
     }
 
     pub async fn basic_publish<T: Serialize>(
@@ -130,10 +219,14 @@ impl Amqp {
         routing_key: &str,
         message: &T,
     ) -> Result<(), AmqpError> {
-        let channel = self.channel().await;
+        let channel = self.channel().await?;
 
         let payload = serde_json::to_vec(message)?;
 
+        // The consumer continues the caller's trace from these
+        let mut headers = FieldTable::default();
+        AmqpPropagator::inject_context(&mut headers);
+
         let publisher_confirm = channel
             .basic_publish(
                 exchange,
@@ -145,7 +238,8 @@ impl Amqp {
                 &payload,
                 BasicProperties::default()
                     .with_delivery_mode(2)
-                    .with_content_type("application/json".into()),
+                    .with_content_type("application/json".into())
+                    .with_headers(headers),
             )
             .await;
 
@@ -171,7 +265,7 @@ impl Amqp {
         message: &T,
         properties: BasicProperties,
     ) -> Result<(), AmqpError> {
-        let channel = self.channel().await;
+        let channel = self.channel().await?;
 
         let payload = serde_json::to_vec(message)?;
 
@@ -204,10 +298,34 @@ impl Amqp {
     }
 }
 
+/// Consumed by compute-provisioner, every one dead letters into [`DEAD_LETTER_EXCHANGE`]
+const COMPUTE_QUEUES: [&str; 9] = [
+    "compute.create",
+    "compute.update",
+    "compute.delete",
+    "compute.delete_user",
+    "compute.suspend",
+    "compute.resume",
+    "compute.registry_credentials",
+    "compute.secrets",
+    "compute.diff",
+];
+
+fn is_precondition_failed(e: &lapin::Error) -> bool {
+    matches!(
+        e.kind(),
+        lapin::ErrorKind::ProtocolError(amqp_error)
+            if *amqp_error.kind() == AMQPErrorKind::Soft(AMQPSoftError::PRECONDITIONFAILED)
+    )
+}
+
 impl IntoResponse for AmqpError {
     fn into_response(self) -> Response {
         let (status, msg) = match self {
-            // Self::LapinError(e) => (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error"),
+            Self::LapinError(e) => {
+                error!("Failed to talk to RabbitMQ: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
+            }
             Self::SerializationError(e) => {
                 error!("Failed to serialize in Amqp: {}", e);
                 (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
@@ -221,14 +339,14 @@ impl IntoResponse for AmqpError {
 }
 
 impl AmqpPropagator {
-    // Inject current tracing context into lapin FieldTable
+    // Inject current tracing context into lapin FieldTable, with whichever propagator
+    // observability registered globally
     pub fn inject_context(headers: &mut FieldTable) {
-        let propagator = TraceContextPropagator::new();
         let mut injector = HashMap::new();
 
         // Get current span context from tracing
         let cx = Span::current().context();
-        propagator.inject_context(&cx, &mut injector);
+        global::get_text_map_propagator(|propagator| propagator.inject_context(&cx, &mut injector));
 
         for (key, value) in injector {
             headers.insert(ShortString::from(key), AMQPValue::LongString(value.into()));
@@ -237,7 +355,6 @@ impl AmqpPropagator {
 
     // Extract context from lapin FieldTable and return an OTel Context
     pub fn extract_context(headers: &FieldTable) -> Context {
-        let propagator = TraceContextPropagator::new();
         let mut extractor = HashMap::new();
 
         for (key, value) in headers.inner() {
@@ -246,6 +363,6 @@ impl AmqpPropagator {
             }
         }
 
-        propagator.extract(&extractor)
+        global::get_text_map_propagator(|propagator| propagator.extract(&extractor))
     }
 }
@@ -8,6 +8,11 @@ use serde::Deserialize;
 
 use crate::factories::tls::TlsConfig;
 
+/// Messages rejected without requeue on any `compute.*` queue end up here
+pub const DEAD_LETTER_EXCHANGE: &str = "compute.dlx";
+/// Bound to [`DEAD_LETTER_EXCHANGE`] with `#`, the original routing key is kept
+pub const DEAD_LETTER_QUEUE: &str = "compute.dead-letter";
+
 pub struct AmqpPropagator;
 
 #[derive(Deserialize, Clone, Debug)]
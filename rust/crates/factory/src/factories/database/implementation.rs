@@ -1,7 +1,15 @@
-use sqlx::postgres::{PgConnectOptions, PgPoolOptions, PgSslMode};
-use tracing::info;
+use std::time::{Duration, Instant};
 
-use crate::factories::database::{Database, DatabaseConfig};
+use opentelemetry::{global, metrics::Meter};
+use sqlx::{
+    PgPool,
+    postgres::{PgConnectOptions, PgPoolOptions, PgSslMode},
+};
+use tracing::{info, warn};
+
+use crate::factories::database::{
+    Database, DatabaseConfig, POOL_METRICS_INTERVAL_SECS, PoolMetrics,
+};
 
 impl Database {
     pub async fn new(cfg: &DatabaseConfig) -> Self {
@@ -59,4 +67,55 @@ impl Database {
 
         Self { pool }
     }
+
+    /// Records pool size, idle connections and how long taking a connection takes, every
+    /// [`POOL_METRICS_INTERVAL_SECS`] until the task is dropped
+    pub async fn report_metrics(self) {
+        let metrics = PoolMetrics::new(&global::meter("database"));
+        let mut interval = tokio::time::interval(Duration::from_secs(POOL_METRICS_INTERVAL_SECS));
+
+        loop {
+            interval.tick().await;
+            metrics.record(&self.pool).await;
+        }
+    }
+}
+
+impl PoolMetrics {
+    pub fn new(meter: &Meter) -> Self {
+        let size = meter
+            .u64_gauge("db.pool.size")
+            .with_description("Open connections, idle or in use")
+            .build();
+        let idle = meter
+            .u64_gauge("db.pool.idle")
+            .with_description("Connections waiting to be acquired")
+            .build();
+        let acquire_latency = meter
+            .f64_gauge("db.pool.acquire_latency_ms")
+            .with_description("Time a probe waited for a connection")
+            .with_unit("ms")
+            .build();
+
+        Self {
+            size,
+            idle,
+            acquire_latency,
+        }
+    }
+
+    pub async fn record(&self, pool: &PgPool) {
+        self.size.record(pool.size() as u64, &[]);
+        self.idle.record(pool.num_idle() as u64, &[]);
+
+        // The probe waits like any query would, an exhausted pool shows up as a spike here
+        let started = Instant::now();
+        match pool.acquire().await {
+            Ok(_con) => {
+                self.acquire_latency
+                    .record(started.elapsed().as_secs_f64() * 1000.0, &[]);
+            }
+            Err(e) => warn!("⚠️ Failed to acquire a connection for pool metrics: {}", e),
+        }
+    }
 }
@@ -1,11 +1,14 @@
 pub mod error;
 pub mod implementation;
 
+use opentelemetry::metrics::Gauge;
 use serde::Deserialize;
 use sqlx::PgPool;
 
 use crate::factories::tls::TlsConfig;
 
+pub const POOL_METRICS_INTERVAL_SECS: u64 = 15;
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct DatabaseConfig {
     pub url: String,
@@ -18,3 +21,11 @@ pub struct DatabaseConfig {
 pub struct Database {
     pub pool: PgPool,
 }
+
+/// Gauges [`Database::report_metrics`] updates, `db.pool.size`, `db.pool.idle` and
+/// `db.pool.acquire_latency_ms`
+pub struct PoolMetrics {
+    size: Gauge<u64>,
+    idle: Gauge<u64>,
+    acquire_latency: Gauge<f64>,
+}
@@ -0,0 +1,12 @@
+use thiserror::Error;
+
+use crate::factories::{mailtrap::error::MailtrapError, zepto::error::ZeptoError};
+
+#[derive(Error, Debug)]
+pub enum EmailError {
+    #[error(transparent)]
+    Mailtrap(#[from] MailtrapError),
+
+    #[error(transparent)]
+    Zepto(#[from] ZeptoError),
+}
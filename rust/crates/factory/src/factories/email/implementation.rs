@@ -0,0 +1,47 @@
+use crate::factories::{
+    email::{
+        Email, EmailConfig, EmailProvider, EmailProviderKind, EmailTemplate, error::EmailError,
+    },
+    mailtrap::Mailtrap,
+    zepto::ZeptoMail,
+};
+
+impl Email {
+    pub fn new(cfg: &EmailConfig) -> Self {
+        match cfg.email_provider {
+            EmailProviderKind::Mailtrap => {
+                let mailtrap = cfg
+                    .mailtrap
+                    .clone()
+                    .expect("EMAIL_PROVIDER is mailtrap but mailtrap is not configured");
+                Self::Mailtrap(Mailtrap::new(mailtrap))
+            }
+            EmailProviderKind::Zepto => {
+                let zepto = cfg
+                    .zepto
+                    .clone()
+                    .expect("EMAIL_PROVIDER is zepto but zepto is not configured");
+                Self::Zepto(ZeptoMail::new(zepto))
+            }
+        }
+    }
+}
+
+impl EmailProvider for Email {
+    async fn send_templated_email(
+        &self,
+        to: &str,
+        template: EmailTemplate,
+        vars: serde_json::Value,
+    ) -> Result<(), EmailError> {
+        match self {
+            Self::Mailtrap(mailtrap) => mailtrap.send_templated_email(to, template, vars).await,
+            Self::Zepto(zepto) => zepto.send_templated_email(to, template, vars).await,
+        }
+    }
+}
+
+/// Recipient's display name, falling back to the address itself
+pub(crate) fn recipient_name<'a>(to: &'a str, vars: &'a serde_json::Value) -> &'a str {
+    vars.get("name").and_then(|v| v.as_str()).unwrap_or(to)
+}
@@ -0,0 +1,59 @@
+pub mod error;
+pub mod implementation;
+
+use std::future::Future;
+
+use serde::Deserialize;
+
+use crate::factories::{
+    email::error::EmailError,
+    mailtrap::{Mailtrap, MailtrapConfig},
+    zepto::{ZeptoConfig, ZeptoMail},
+};
+
+/// Every provider maps these onto its own templates
+#[derive(Clone, Copy, Debug)]
+pub enum EmailTemplate {
+    Verification,
+    PasswordSetup,
+    PasswordReset,
+    EmailChange,
+    AccountDeleted,
+    FeedbackConfirmation,
+    Billing,
+    Support,
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailProviderKind {
+    #[default]
+    Mailtrap,
+    Zepto,
+}
+
+/// Flattened into service configs, so `EMAIL_PROVIDER` picks the provider
+#[derive(Deserialize, Clone, Debug)]
+pub struct EmailConfig {
+    #[serde(default)]
+    pub email_provider: EmailProviderKind,
+    pub mailtrap: Option<MailtrapConfig>,
+    pub zepto: Option<ZeptoConfig>,
+}
+
+pub trait EmailProvider {
+    /// `vars` are handed to the template as is, a `name` in them is also used as the recipient's name
+    fn send_templated_email(
+        &self,
+        to: &str,
+        template: EmailTemplate,
+        vars: serde_json::Value,
+    ) -> impl Future<Output = Result<(), EmailError>> + Send;
+}
+
+/// Dispatches to whichever provider `EmailConfig` selected
+#[derive(Clone)]
+pub enum Email {
+    Mailtrap(Mailtrap),
+    Zepto(ZeptoMail),
+}
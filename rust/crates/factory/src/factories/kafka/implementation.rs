@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use rdkafka::config::ClientConfig;
-use rdkafka::consumer::StreamConsumer;
+use rdkafka::consumer::{ConsumerContext, StreamConsumer};
 use rdkafka::producer::FutureProducer;
 use tracing::info;
 
@@ -9,6 +9,57 @@ use crate::factories::kafka::{Kafka, KafkaConfig, error::KafkaError};
 
 impl Kafka {
     pub fn new(cfg: &KafkaConfig, group_id: &str) -> Result<Self, KafkaError> {
+        let producer = Self::producer(cfg)?;
+
+        let consumer = Self::client_config(cfg)
+            .set("group.id", group_id)
+            .set("enable.partition.eof", "false")
+            .set("session.timeout.ms", "5000")
+            .set("enable.auto.commit", "true")
+            .set("auto.offset.reset", "earliest")
+            .create::<StreamConsumer>()?;
+
+        info!("✅ Kafka producer and consumer created.");
+
+        Ok(Self {
+            producer,
+            consumer: Arc::new(consumer),
+        })
+    }
+
+    pub fn producer(cfg: &KafkaConfig) -> Result<FutureProducer, KafkaError> {
+        let producer = Self::client_config(cfg)
+            .set("message.timeout.ms", "5000")
+            .set("queue.buffering.max.ms", "1")
+            .create::<FutureProducer>()?;
+
+        Ok(producer)
+    }
+
+    /// Consumer with auto commit off, offsets are committed once a message is handled.
+    /// `context` is told about partition rebalances
+    pub fn manual_commit_consumer<C>(
+        cfg: &KafkaConfig,
+        group_id: &str,
+        context: C,
+    ) -> Result<StreamConsumer<C>, KafkaError>
+    where
+        C: ConsumerContext + 'static,
+    {
+        let consumer = Self::client_config(cfg)
+            .set("group.id", group_id)
+            .set("enable.partition.eof", "false")
+            .set("session.timeout.ms", "6000")
+            .set("enable.auto.commit", "false")
+            .set("auto.offset.reset", "earliest")
+            .create_with_context::<C, StreamConsumer<C>>(context)?;
+
+        info!("✅ Kafka consumer of group {} created.", group_id);
+
+        Ok(consumer)
+    }
+
+    fn client_config(cfg: &KafkaConfig) -> ClientConfig {
         let mut common = ClientConfig::new();
         common.set("bootstrap.servers", cfg.bootstrap_servers.clone());
 
@@ -27,26 +78,6 @@ impl Kafka {
             }
         }
 
-        let producer = common
-            .clone()
-            .set("message.timeout.ms", "5000")
-            .set("queue.buffering.max.ms", "1")
-            .create::<FutureProducer>()?;
-
-        let consumer = common
-            .clone()
-            .set("group.id", group_id)
-            .set("enable.partition.eof", "false")
-            .set("session.timeout.ms", "5000")
-            .set("enable.auto.commit", "true")
-            .set("auto.offset.reset", "earliest")
-            .create::<StreamConsumer>()?;
-
-        info!("✅ Kafka producer and consumer created.");
-
-        Ok(Self {
-            producer,
-            consumer: Arc::new(consumer),
-        })
+        common
     }
 }
@@ -9,7 +9,7 @@ use serde::Deserialize;
 
 use crate::factories::tls::TlsConfig;
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, Debug)]
 pub struct KafkaConfig {
     pub bootstrap_servers: String,
     pub tls_config: Option<TlsConfig>,
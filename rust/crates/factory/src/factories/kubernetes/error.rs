@@ -10,4 +10,6 @@ pub enum KubernetesError {
     KubeconfigError(#[from] kube_client::config::KubeconfigError),
     #[error("InferConfigError, {0}")]
     InferConfigError(#[from] kube_client::config::InferConfigError),
+    #[error("Unknown region: {0}")]
+    UnknownRegion(String),
 }
@@ -1,11 +1,65 @@
+use std::collections::HashMap;
+
+use kube::{
+    Client,
+    config::{KubeConfigOptions, Kubeconfig},
+};
 use tracing::info;
 
-use crate::factories::kubernetes::{Kubernetes, error::KubernetesError};
+use crate::factories::kubernetes::{
+    DEFAULT_REGION, Kubernetes, KubernetesConfig, RegionConfig, error::KubernetesError,
+};
 
 impl Kubernetes {
-    pub async fn new() -> Result<Self, KubernetesError> {
-        let client = kube::Client::try_default().await?;
-        info!("✅ Kubernetes client created");
-        Ok(Self { client })
+    pub async fn new(cfg: &KubernetesConfig) -> Result<Self, KubernetesError> {
+        let mut regions = HashMap::new();
+        if cfg.regions.is_empty() {
+            regions.insert(DEFAULT_REGION.to_string(), Client::try_default().await?);
+        }
+        for (name, region) in &cfg.regions {
+            regions.insert(name.clone(), Self::connect(region).await?);
+            info!(region = %name, "✅ Kubernetes client created");
+        }
+
+        let default_region = match &cfg.default_region {
+            Some(region) if regions.contains_key(region) => region.clone(),
+            Some(region) => return Err(KubernetesError::UnknownRegion(region.clone())),
+            None => {
+                let mut names: Vec<&String> = regions.keys().collect();
+                names.sort();
+                names[0].clone()
+            }
+        };
+
+        info!(default_region = %default_region, "✅ Kubernetes clients ready");
+        Ok(Self {
+            client: regions[&default_region].clone(),
+            regions,
+            default_region,
+        })
+    }
+
+    async fn connect(region: &RegionConfig) -> Result<Client, KubernetesError> {
+        let options = KubeConfigOptions {
+            context: region.context.clone(),
+            ..Default::default()
+        };
+        let config = match &region.kubeconfig {
+            Some(path) => {
+                let kubeconfig = Kubeconfig::read_from(path)?;
+                kube::Config::from_custom_kubeconfig(kubeconfig, &options).await?
+            }
+            None if region.context.is_some() => kube::Config::from_kubeconfig(&options).await?,
+            None => kube::Config::infer().await?,
+        };
+        Ok(Client::try_from(config)?)
+    }
+
+    /// Client of `region`, the default region's when none is given
+    pub fn client_for(&self, region: Option<&str>) -> Result<&Client, KubernetesError> {
+        let region = region.unwrap_or(&self.default_region);
+        self.regions
+            .get(region)
+            .ok_or_else(|| KubernetesError::UnknownRegion(region.to_string()))
     }
 }
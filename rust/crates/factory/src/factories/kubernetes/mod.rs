@@ -1,9 +1,38 @@
 pub mod error;
 pub mod implementation;
 
+use std::collections::HashMap;
+
 use kube::Client;
+use serde::Deserialize;
+
+/// Region the in-cluster (or local kubeconfig) client serves when no regions are configured
+pub const DEFAULT_REGION: &str = "default";
+
+/// One cluster deployments can be scheduled onto
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct RegionConfig {
+    /// Kubeconfig file to reach the cluster through, the inferred config when unset
+    pub kubeconfig: Option<String>,
+    /// Context inside `kubeconfig`, its current context when unset
+    pub context: Option<String>,
+    pub display_name: Option<String>,
+    /// Pods the region accepts before new deployments are turned away, unlimited when unset
+    pub max_pods: Option<i64>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct KubernetesConfig {
+    /// Region deployments land in when they don't ask for one, the first by name when unset
+    pub default_region: Option<String>,
+    #[serde(default)]
+    pub regions: HashMap<String, RegionConfig>,
+}
 
 #[derive(Clone)]
 pub struct Kubernetes {
+    /// Client of the default region
     pub client: Client,
+    pub regions: HashMap<String, Client>,
+    pub default_region: String,
 }
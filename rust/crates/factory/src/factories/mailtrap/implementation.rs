@@ -1,107 +1,46 @@
 use reqwest::Client;
 use tracing::{debug, error};
 
-use crate::factories::mailtrap::{
-    ErrorResponse, Mailbox, Mailtrap, MailtrapConfig, Payload, SuccessResponse,
-    error::MailtrapError,
+use crate::factories::{
+    email::{EmailProvider, EmailTemplate, error::EmailError, implementation::recipient_name},
+    mailtrap::{
+        ErrorResponse, Mailbox, Mailtrap, MailtrapConfig, MailtrapTemplateConfig, Payload,
+        SuccessResponse, error::MailtrapError,
+    },
 };
 
 use std::fmt;
 
-impl Default for Mailtrap {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 impl Mailtrap {
-    pub fn new() -> Self {
+    pub fn new(cfg: MailtrapConfig) -> Self {
         Self {
             url: "https://send.api.mailtrap.io/api/send".to_string(),
             client: Client::new(),
+            cfg,
         }
     }
 
-    #[tracing::instrument(name = "mailtrip.send_email_verification_link", skip_all, fields(recipient = %to_email) err)]
-    pub async fn send_email_verification_link(
-        &self,
-        to_name: &str,
-        to_email: &str,
-        link: &str,
-        cfg: &MailtrapConfig,
-    ) -> Result<(), MailtrapError> {
-        let template = cfg.verification.clone();
-
-        let payload = Payload {
-            from: Mailbox {
-                name: template.from_name,
-                email: template.from_email,
-            },
-            to: vec![Mailbox {
-                email: to_email.to_string(),
-                name: to_name.to_string(),
-            }],
-            template_uuid: template.template_uuid,
-            template_variables: serde_json::json!({
-                "link": link
-            }),
-        };
-
-        let res = self
-            .client
-            .post(&self.url)
-            .header("accept", "application/json")
-            .header("content-type", "application/json")
-            .header("authorization", cfg.clone().api_key)
-            .json(&payload)
-            .send()
-            .await?;
-
-        let status_code = res.status();
-
-        if status_code == 200 {
-            let response = res.json::<SuccessResponse>().await?;
-            debug!("Mailtrap success: {:?}", response);
-            Ok(())
-        } else {
-            let response = res.json::<ErrorResponse>().await?;
-            error!("Mailtrap error: {:?}", response);
-            Err(MailtrapError::Api { error: response })
+    fn template(&self, template: EmailTemplate) -> &MailtrapTemplateConfig {
+        match template {
+            EmailTemplate::Verification => &self.cfg.verification,
+            EmailTemplate::PasswordSetup => &self.cfg.password_setup,
+            EmailTemplate::PasswordReset => &self.cfg.password_reset,
+            EmailTemplate::EmailChange => &self.cfg.email_change,
+            EmailTemplate::AccountDeleted => &self.cfg.account_deleted,
+            EmailTemplate::FeedbackConfirmation => &self.cfg.feedback_confirmation,
+            EmailTemplate::Billing => &self.cfg.billing,
+            EmailTemplate::Support => &self.cfg.support,
         }
     }
 
-    #[tracing::instrument(name = "mailtrip.send_password_setup_link", skip_all, fields(recipient = %to_email) err)]
-    pub async fn send_password_setup_link(
-        &self,
-        to_name: &str,
-        to_email: &str,
-        link: &str,
-        cfg: &MailtrapConfig,
-    ) -> Result<(), MailtrapError> {
-        let template = cfg.password_setup.clone();
-
-        let payload = Payload {
-            from: Mailbox {
-                name: template.from_name,
-                email: template.from_email,
-            },
-            to: vec![Mailbox {
-                email: to_email.to_string(),
-                name: to_name.to_string(),
-            }],
-            template_uuid: template.template_uuid,
-            template_variables: serde_json::json!({
-                "link": link
-            }),
-        };
-
+    async fn send(&self, payload: &Payload) -> Result<(), MailtrapError> {
         let res = self
             .client
             .post(&self.url)
             .header("accept", "application/json")
             .header("content-type", "application/json")
-            .header("authorization", cfg.clone().api_key)
-            .json(&payload)
+            .header("authorization", &self.cfg.api_key)
+            .json(payload)
             .send()
             .await?;
 
@@ -117,16 +56,17 @@ impl Mailtrap {
             Err(MailtrapError::Api { error: response })
         }
     }
+}
 
-    #[tracing::instrument(name = "mailtrip.send_feedback_confirmation", skip_all, fields(recipient = %to_email), err)]
-    pub async fn send_feedback_confirmation(
+impl EmailProvider for Mailtrap {
+    #[tracing::instrument(name = "mailtrap.send_templated_email", skip_all, fields(recipient = %to, template = ?template), err)]
+    async fn send_templated_email(
         &self,
-        to_name: &str,
-        to_email: &str,
-        message: &str,
-        cfg: &MailtrapConfig,
-    ) -> Result<(), MailtrapError> {
-        let template = cfg.feedback_confirmation.clone();
+        to: &str,
+        template: EmailTemplate,
+        vars: serde_json::Value,
+    ) -> Result<(), EmailError> {
+        let template = self.template(template).clone();
 
         let payload = Payload {
             from: Mailbox {
@@ -134,37 +74,14 @@ impl Mailtrap {
                 email: template.from_email,
             },
             to: vec![Mailbox {
-                email: to_email.to_string(),
-                name: to_name.to_string(),
+                email: to.to_string(),
+                name: recipient_name(to, &vars).to_string(),
             }],
             template_uuid: template.template_uuid,
-            template_variables: serde_json::json!({
-                "name": to_name,
-                "message": message
-            }),
+            template_variables: vars,
         };
 
-        let res = self
-            .client
-            .post(&self.url)
-            .header("accept", "application/json")
-            .header("content-type", "application/json")
-            .header("authorization", cfg.clone().api_key)
-            .json(&payload)
-            .send()
-            .await?;
-
-        let status_code = res.status();
-
-        if status_code == 200 {
-            let response = res.json::<SuccessResponse>().await?;
-            debug!("Mailtrap success: {:?}", response);
-            Ok(())
-        } else {
-            let response = res.json::<ErrorResponse>().await?;
-            error!("Mailtrap error: {:?}", response);
-            Err(MailtrapError::Api { error: response })
-        }
+        Ok(self.send(&payload).await?)
     }
 }
 
@@ -19,11 +19,16 @@ pub struct MailtrapConfig {
     pub billing: MailtrapTemplateConfig,
     pub support: MailtrapTemplateConfig,
     pub feedback_confirmation: MailtrapTemplateConfig,
+    pub email_change: MailtrapTemplateConfig,
+    pub account_deleted: MailtrapTemplateConfig,
+    pub password_reset: MailtrapTemplateConfig,
 }
 
+#[derive(Clone)]
 pub struct Mailtrap {
     url: String,
     client: Client,
+    cfg: MailtrapConfig,
 }
 
 #[derive(Serialize)]
@@ -1,9 +1,11 @@
 pub mod amqp;
 pub mod database;
+pub mod email;
 pub mod kafka;
 pub mod kubernetes;
 pub mod mailtrap;
 pub mod observability;
+pub mod rate_limit;
 pub mod redis;
 pub mod tls;
 pub mod tonic;
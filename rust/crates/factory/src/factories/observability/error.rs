@@ -1,2 +1 @@
-
-pub enum ObservabilityError {}
\ No newline at end of file
+pub enum ObservabilityError {}
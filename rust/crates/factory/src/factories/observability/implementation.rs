@@ -9,10 +9,14 @@ use opentelemetry_sdk::{
 use opentelemetry_semantic_conventions::{SCHEMA_URL, attribute::SERVICE_VERSION};
 use time::macros::format_description;
 use tonic::transport::ClientTlsConfig;
-use tracing::Level;
+use tracing::{Level, Subscriber};
 use tracing_opentelemetry::{MetricsLayer, OpenTelemetryLayer};
 use tracing_subscriber::{
-    EnvFilter, Layer, fmt::time::LocalTime, layer::SubscriberExt, util::SubscriberInitExt,
+    EnvFilter, Layer,
+    fmt::{MakeWriter, time::LocalTime},
+    layer::SubscriberExt,
+    registry::LookupSpan,
+    util::SubscriberInitExt,
 };
 
 use crate::factories::observability::{Observability, ObservabilityConfig};
@@ -37,10 +41,7 @@ impl Observability {
     ) -> Observability {
         let endpoint = cfg.otel_exporter_otlp_endpoint.as_str();
         let rust_log = cfg.rust_log.as_deref();
-        let log_format = cfg.log_format.as_deref();
         let tracing_level = cfg.tracing_level.as_deref();
-        let with_file = cfg.with_file;
-        let with_line_number = cfg.with_line_number;
 
         global::set_text_map_propagator(TraceContextPropagator::new());
 
@@ -71,31 +72,7 @@ impl Observability {
         };
 
         // Stdout
-        let log_format = log_format.unwrap_or("json");
-        let timer = LocalTime::new(format_description!(
-            "[year]-[month]-[day] [hour]:[minute]:[second]"
-        ));
-
-        let fmt_layer = if log_format == "pretty" {
-            tracing_subscriber::fmt::layer()
-                .with_ansi(true)
-                .with_timer(timer)
-                .with_target(false)
-                .with_file(with_file.unwrap_or(true))
-                .with_line_number(with_line_number.unwrap_or(true))
-                .compact()
-                .boxed()
-        } else {
-            tracing_subscriber::fmt::layer()
-                .with_timer(timer)
-                .with_target(false)
-                .with_file(with_file.unwrap_or(true))
-                .with_line_number(with_line_number.unwrap_or(true))
-                .json()
-                .flatten_event(true)
-                .with_span_list(false)
-                .boxed()
-        };
+        let fmt_layer = Self::fmt_layer(cfg, std::io::stdout);
 
         // Registry
         tracing_subscriber::registry()
@@ -111,6 +88,42 @@ impl Observability {
         }
     }
 
+    /// `json` (the default) for log shippers, `pretty` for multi-line output, `text` for single lines
+    pub fn fmt_layer<S, W>(
+        cfg: &ObservabilityConfig,
+        make_writer: W,
+    ) -> Box<dyn Layer<S> + Send + Sync>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+        W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+    {
+        let timer = LocalTime::new(format_description!(
+            "[year]-[month]-[day] [hour]:[minute]:[second]"
+        ));
+        let layer = tracing_subscriber::fmt::layer()
+            .with_writer(make_writer)
+            .with_timer(timer)
+            .with_target(false)
+            .with_file(cfg.with_file.unwrap_or(true))
+            .with_line_number(cfg.with_line_number.unwrap_or(true))
+            .with_thread_ids(cfg.with_thread_ids.unwrap_or(false));
+
+        match cfg.log_format.as_deref() {
+            Some("pretty") => layer.with_ansi(true).pretty().boxed(),
+            Some("text") => layer.with_ansi(true).boxed(),
+            log_format => {
+                if let Some(other) = log_format.filter(|f| *f != "json") {
+                    eprintln!("⚠️ Unknown log_format {other:?}, falling back to json");
+                }
+                layer
+                    .json()
+                    .flatten_event(true)
+                    .with_span_list(false)
+                    .boxed()
+            }
+        }
+    }
+
     // Resource
     fn get_resource(cargo_crate_name: &str, cargo_pkg_version: &str) -> Resource {
         Resource::builder()
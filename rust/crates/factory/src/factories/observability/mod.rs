@@ -12,6 +12,7 @@ pub struct ObservabilityConfig {
     pub tracing_level: Option<String>,
     pub with_file: Option<bool>,
     pub with_line_number: Option<bool>,
+    pub with_thread_ids: Option<bool>,
 }
 
 pub struct Observability {
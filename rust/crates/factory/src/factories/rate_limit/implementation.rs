@@ -0,0 +1,143 @@
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use axum::{
+    Json,
+    body::Body,
+    http::{HeaderValue, Request, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+use redis::{Script, aio::MultiplexedConnection};
+use serde_json::json;
+use tower::{Layer, Service};
+use tracing::{error, warn};
+
+use crate::factories::rate_limit::{
+    RateLimit, RateLimitLayer, RateLimitService, RateLimitSource, RateLimitSubject,
+};
+
+/// Refills the bucket for the time elapsed since the last request and takes one token.
+/// Returns `{allowed, retry_after_secs}`, the clock is Redis' own so replicas can't disagree.
+const TOKEN_BUCKET_SCRIPT: &str = r#"
+local capacity = tonumber(ARGV[1])
+local rate = tonumber(ARGV[2]) / 60000
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+
+local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'ts')
+local tokens = tonumber(bucket[1]) or capacity
+local ts = tonumber(bucket[2]) or now
+tokens = math.min(capacity, tokens + math.max(0, now - ts) * rate)
+
+local allowed = 0
+local retry_after = 0
+if tokens >= 1 then
+    tokens = tokens - 1
+    allowed = 1
+else
+    retry_after = math.ceil((1 - tokens) / rate / 1000)
+end
+
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'ts', now)
+redis.call('PEXPIRE', KEYS[1], math.ceil(capacity / rate))
+return {allowed, retry_after}
+"#;
+
+impl RateLimitLayer {
+    pub fn new(con: MultiplexedConnection, route_group: &'static str, limit: RateLimit) -> Self {
+        Self::dynamic(con, route_group, move || limit)
+    }
+
+    /// `limit` is asked on every request, for limits that are reloaded at runtime
+    pub fn dynamic(
+        con: MultiplexedConnection,
+        route_group: &'static str,
+        limit: impl Fn() -> RateLimit + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            con,
+            route_group,
+            limit: Arc::new(limit) as RateLimitSource,
+            script: Arc::new(Script::new(TOKEN_BUCKET_SCRIPT)),
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            con: self.con.clone(),
+            route_group: self.route_group,
+            limit: self.limit.clone(),
+            script: self.script.clone(),
+        }
+    }
+}
+
+impl<S> Service<Request<Body>> for RateLimitService<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let Some(RateLimitSubject(subject)) = req.extensions().get::<RateLimitSubject>().cloned()
+        else {
+            return Box::pin(inner.call(req));
+        };
+
+        let mut con = self.con.clone();
+        let script = self.script.clone();
+        let route_group = self.route_group;
+        let limit = (self.limit)();
+
+        Box::pin(async move {
+            let key = format!("rate_limit:user:{}:{}", subject, route_group);
+            let result: Result<(u8, u64), _> = script
+                .key(&key)
+                .arg(limit.burst.max(1))
+                .arg(limit.requests_per_minute.max(1))
+                .invoke_async(&mut con)
+                .await;
+
+            match result {
+                Ok((0, retry_after)) => {
+                    warn!(subject = %subject, route_group = %route_group, "🚦 Rate limit exceeded");
+                    return Ok(too_many_requests(retry_after.max(1)));
+                }
+                Ok(_) => {}
+                // Redis being down must not take the API with it
+                Err(e) => error!(route_group = %route_group, "❌ Rate limit check failed: {}", e),
+            }
+
+            inner.call(req).await
+        })
+    }
+}
+
+fn too_many_requests(retry_after: u64) -> Response {
+    let mut res = (
+        StatusCode::TOO_MANY_REQUESTS,
+        Json(json!({ "error": "Too many requests" })),
+    )
+        .into_response();
+    res.headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+    res
+}
@@ -0,0 +1,40 @@
+pub mod implementation;
+
+use redis::{Script, aio::MultiplexedConnection};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Steady refill rate of the bucket
+    pub requests_per_minute: u32,
+    /// Bucket capacity, how many requests may arrive back to back
+    pub burst: u32,
+}
+
+/// Read on every request, so a limit can change without rebuilding the router
+pub type RateLimitSource = Arc<dyn Fn() -> RateLimit + Send + Sync>;
+
+/// Who a request is counted against, inserted into the request extensions by the service
+/// once it has authenticated the caller. Requests without it are not limited.
+#[derive(Clone, Debug)]
+pub struct RateLimitSubject(pub String);
+
+/// Token bucket per subject and route group, kept in Redis under
+/// `rate_limit:user:{subject}:{route_group}` so all replicas share it
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    con: MultiplexedConnection,
+    route_group: &'static str,
+    limit: RateLimitSource,
+    script: Arc<Script>,
+}
+
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    con: MultiplexedConnection,
+    route_group: &'static str,
+    limit: RateLimitSource,
+    script: Arc<Script>,
+}
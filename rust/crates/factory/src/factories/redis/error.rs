@@ -1,5 +1,9 @@
 use thiserror::Error;
 
 #[derive(Error, Debug)]
-#[error("Redis error: {0}")]
-pub struct RedisError(#[from] redis::RedisError);
+pub enum RedisError {
+    #[error("Redis error: {0}")]
+    Redis(#[from] redis::RedisError),
+    #[error("Redis config error: {0}")]
+    Config(String),
+}
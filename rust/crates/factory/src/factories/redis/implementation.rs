@@ -1,36 +1,78 @@
+use std::sync::Arc;
+
 use redis::{
     Client, ClientTlsConfig, ConnectionAddr, ConnectionInfo, IntoConnectionInfo, ProtocolVersion,
-    RedisConnectionInfo, TlsCertificates, aio::PubSub,
+    RedisConnectionInfo, TlsCertificates,
+    aio::{MultiplexedConnection, PubSub},
+};
+use rustls::{crypto::ring, sign::CertifiedKey};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+use crate::factories::{
+    redis::{
+        RECONNECT_INITIAL_BACKOFF, RECONNECT_MAX_BACKOFF, RECONNECT_MAX_RETRIES, Redis,
+        RedisConfig, error::RedisError,
+    },
+    tls::TlsConfig,
 };
-use tracing::info;
-
-use crate::factories::redis::{Redis, RedisConfig, error::RedisError};
 
 impl Redis {
-    pub async fn new(cfg: &RedisConfig) -> Self {
+    pub async fn new(cfg: &RedisConfig) -> Result<Self, RedisError> {
         let conn_info = Self::connection_info(cfg);
-        let tls_certs = Self::tls_certificates(cfg);
-
-        if let Some(tls_certs) = tls_certs {
-            let client = Client::build_with_tls(conn_info, tls_certs)
-                .unwrap_or_else(|e| panic!("Couldn't constructs client TLS connection: {}", e));
-            let connection = client
-                .get_multiplexed_tokio_connection()
-                .await
-                .unwrap_or_else(|e| panic!("Couldn't establish connection to redis: {}", e));
-            return Self { client, con: connection };
-        }
+        let tls_certs = Self::tls_certificates(cfg)?;
 
-        let client = Client::open(conn_info)
-            .unwrap_or_else(|e| panic!("Couldn't connects to a redis server: {}", e));
-        let connection = client
-            .get_multiplexed_tokio_connection()
-            .await
-            .unwrap_or_else(|e| panic!("Couldn't establish connection to redis: {}", e));
+        let client = match tls_certs {
+            Some(tls_certs) => Client::build_with_tls(conn_info, tls_certs)?,
+            None => Client::open(conn_info)?,
+        };
+        let connection = client.get_multiplexed_tokio_connection().await?;
 
         info!("✅ Redis client & connection created");
 
-        Self { client, con: connection }
+        Ok(Self {
+            client,
+            connection: Arc::new(RwLock::new(connection.clone())),
+            con: connection,
+        })
+    }
+
+    /// `con` stays broken once Redis restarts, long running tasks should take their connection
+    /// from here instead. A connection failing `PING` is replaced, retrying with exponential backoff.
+    pub async fn get_connection(&self) -> Result<MultiplexedConnection, redis::RedisError> {
+        let mut con = self.connection.read().await.clone();
+        if ping(&mut con).await.is_ok() {
+            return Ok(con);
+        }
+
+        let mut connection = self.connection.write().await;
+        // Somebody else may have reconnected while this one waited for the lock
+        let mut con = connection.clone();
+        if ping(&mut con).await.is_ok() {
+            return Ok(con);
+        }
+
+        let mut backoff = RECONNECT_INITIAL_BACKOFF;
+        let mut attempt = 1;
+        loop {
+            match self.client.get_multiplexed_tokio_connection().await {
+                Ok(con) => {
+                    info!("🔌 Reconnected to redis after {} attempts", attempt);
+                    *connection = con.clone();
+                    return Ok(con);
+                }
+                Err(e) if attempt < RECONNECT_MAX_RETRIES => {
+                    warn!(
+                        "⚠️ Redis reconnect attempt {} failed, retrying in {:?}: {}",
+                        attempt, backoff, e
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(RECONNECT_MAX_BACKOFF);
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
     }
 
     pub async fn pubsub(&self) -> Result<PubSub, RedisError> {
@@ -46,8 +88,18 @@ impl Redis {
             && let Some(host) = params.host
             && let Some(port) = params.port
         {
+            // `Client::build_with_tls` only accepts TLS addresses
+            let addr = match cfg.tls_config {
+                Some(_) => ConnectionAddr::TcpTls {
+                    host,
+                    port,
+                    insecure: false,
+                    tls_params: None,
+                },
+                None => ConnectionAddr::Tcp(host, port),
+            };
             let conn_info = ConnectionInfo {
-                addr: ConnectionAddr::Tcp(host.to_string(), port),
+                addr,
                 redis: RedisConnectionInfo {
                     db: 0,
                     username: params.username,
@@ -83,31 +135,74 @@ impl Redis {
         conn_info
     }
 
-    fn tls_certificates(cfg: &RedisConfig) -> Option<TlsCertificates> {
-        let tls_config = cfg.tls_config.clone();
-
-        if let Some(tls) = tls_config
-            && let Some(ca) = tls.ca
-            && let Some(client_cert) = tls.client_cert
-            && let Some(client_key) = tls.client_key
-        {
-            // Structure to hold mTLS client certificate and key binaries in PEM format
-            let client_tls_config = ClientTlsConfig {
-                client_cert: client_cert.as_bytes().to_vec(),
-                client_key: client_key.as_bytes().to_vec(),
-            };
-
-            // Structure to hold TLS certificates
-            // * client_tls: binaries of clientkey and certificate within a ClientTlsConfig structure if mTLS is used
-            // * root_cert: binary CA certificate in PEM format if CA is not in local truststore
-            let tls_certs = TlsCertificates {
-                client_tls: Some(client_tls_config),
-                root_cert: Some(ca.as_bytes().to_vec()),
-            };
+    /// CA only verifies the server, adding a client cert and key turns it into mTLS
+    pub fn tls_certificates(cfg: &RedisConfig) -> Result<Option<TlsCertificates>, RedisError> {
+        let Some(tls) = cfg.tls_config.as_ref() else {
+            return Ok(None);
+        };
+
+        let root_cert = TlsConfig::read_pem(tls.ca.as_deref(), tls.ca_path.as_deref(), "TLS CA");
+        let client_cert = TlsConfig::read_pem(
+            tls.client_cert.as_deref(),
+            tls.client_cert_path.as_deref(),
+            "TLS client certificate",
+        );
+        let client_key = TlsConfig::read_pem(
+            tls.client_key.as_deref(),
+            tls.client_key_path.as_deref(),
+            "TLS client key",
+        );
+
+        let client_tls = match (client_cert, client_key) {
+            (Some(client_cert), Some(client_key)) => {
+                Some(Self::client_tls(client_cert, client_key)?)
+            }
+            (None, None) => None,
+            _ => {
+                return Err(RedisError::Config(
+                    "TLS client certificate and key must be set together".to_string(),
+                ));
+            }
+        };
 
-            return Some(tls_certs);
+        if root_cert.is_none() && client_tls.is_none() {
+            return Ok(None);
         }
 
-        None
+        Ok(Some(TlsCertificates {
+            client_tls,
+            root_cert,
+        }))
+    }
+
+    /// The redis client only parses the PEM once it connects, a key from another pair fails here instead
+    fn client_tls(
+        client_cert: Vec<u8>,
+        client_key: Vec<u8>,
+    ) -> Result<ClientTlsConfig, RedisError> {
+        let config_error = |context: &str, e: &dyn std::fmt::Display| {
+            RedisError::Config(format!("{}, {}", context, e))
+        };
+
+        let cert_chain = rustls_pemfile::certs(&mut client_cert.as_slice())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| config_error("Couldn't read TLS client certificate", &e))?;
+        let key = rustls_pemfile::private_key(&mut client_key.as_slice())
+            .map_err(|e| config_error("Couldn't read TLS client key", &e))?
+            .ok_or_else(|| {
+                RedisError::Config("No private key found in TLS client key".to_string())
+            })?;
+
+        CertifiedKey::from_der(cert_chain, key, &ring::default_provider())
+            .map_err(|e| config_error("TLS client certificate doesn't match its key", &e))?;
+
+        Ok(ClientTlsConfig {
+            client_cert,
+            client_key,
+        })
     }
 }
+
+async fn ping(con: &mut MultiplexedConnection) -> Result<(), redis::RedisError> {
+    redis::cmd("PING").query_async(con).await
+}
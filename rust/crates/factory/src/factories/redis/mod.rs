@@ -1,12 +1,19 @@
 pub mod error;
 pub mod implementation;
 
+use std::{sync::Arc, time::Duration};
+
 use redis::{Client, aio::MultiplexedConnection};
 use serde::Deserialize;
 use serde_with::{NoneAsEmptyString, serde_as};
+use tokio::sync::RwLock;
 
 use crate::factories::tls::TlsConfig;
 
+pub const RECONNECT_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+pub const RECONNECT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+pub const RECONNECT_MAX_RETRIES: u32 = 10;
+
 #[serde_as]
 #[derive(Deserialize, Clone, Debug)]
 pub struct RedisParams {
@@ -31,4 +38,6 @@ pub struct RedisConfig {
 pub struct Redis {
     pub client: Client,
     pub con: MultiplexedConnection,
+    /// What [`Redis::get_connection`] hands out, replaced once it stops answering pings
+    connection: Arc<RwLock<MultiplexedConnection>>,
 }
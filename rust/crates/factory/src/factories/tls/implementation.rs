@@ -1,11 +1,13 @@
 use std::{
     fs::File,
     io::{BufRead, BufReader, Cursor},
-    path::PathBuf,
+    path::{Path, PathBuf},
+    sync::Arc,
 };
 
 use rustls::{
     ClientConfig, RootCertStore,
+    crypto::ring,
     pki_types::{CertificateDer, PrivateKeyDer},
 };
 use rustls_pemfile::{Item, read_one};
@@ -13,7 +15,7 @@ use rustls_pemfile::{Item, read_one};
 use crate::factories::tls::TlsConfig;
 
 impl TlsConfig {
-    /// Build TLS client config from Config
+    /// Build TLS client config from Config, the client certificate and key are optional but go together
     pub fn build_rustls_config(&self) -> ClientConfig {
         let ca = self.ca.clone();
         let ca_path = self.ca_path.clone();
@@ -25,12 +27,14 @@ impl TlsConfig {
         if ca.is_none() && ca_path.is_none() {
             panic!("Missing Tls Ca");
         }
-        if client_cert.is_none() && client_cert_path.is_none() {
-            panic!("Missing Tls Cert");
-        }
-        if client_key.is_none() && client_key_path.is_none() {
+        let has_cert = client_cert.is_some() || client_cert_path.is_some();
+        let has_key = client_key.is_some() || client_key_path.is_some();
+        if has_cert && !has_key {
             panic!("Missing Tls Key");
         }
+        if has_key && !has_cert {
+            panic!("Missing Tls Cert");
+        }
 
         let mut root_store: RootCertStore = RootCertStore::empty();
 
@@ -43,6 +47,16 @@ impl TlsConfig {
             panic!("Couldn't add root CA to store");
         });
 
+        // Both ring and aws-lc-rs end up enabled, so the provider can't be picked from features
+        let builder = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap_or_else(|e| panic!("Couldn't select TLS protocol versions, {}", e))
+            .with_root_certificates(root_store);
+
+        if !has_cert {
+            return builder.with_no_client_auth();
+        }
+
         // Client cert chain
         let client_certs = Self::with_reader(
             client_cert,
@@ -58,17 +72,26 @@ impl TlsConfig {
             });
 
         // Build ClientConfig
-        let client_config = ClientConfig::builder()
-            .with_root_certificates(root_store)
+        builder
             .with_client_auth_cert(client_certs, client_key)
             .unwrap_or_else(|e| {
                 panic!(
                     "Couldn't set client authentication certificate chain, {}",
                     e
                 )
-            });
+            })
+    }
+
+    /// Raw PEM of an inline value or else of the file at `path`, for clients that parse PEM themselves
+    pub fn read_pem(data: Option<&str>, path: Option<&Path>, context: &str) -> Option<Vec<u8>> {
+        if let Some(data) = data {
+            return Some(data.as_bytes().to_vec());
+        }
 
-        client_config
+        let path = path?;
+        let pem = std::fs::read(path)
+            .unwrap_or_else(|e| panic!("Couldn't read {} {:?}: {}", context, path, e));
+        Some(pem)
     }
 
     fn with_reader<T>(
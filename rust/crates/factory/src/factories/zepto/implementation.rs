@@ -1,8 +1,12 @@
 use reqwest::Client;
 use tracing::{debug, error};
 
-use crate::factories::zepto::{
-    EmailAddress, Payload, Recipient, ZeptoApiError, ZeptoApiResponse, ZeptoMail, error::ZeptoError,
+use crate::factories::{
+    email::{EmailProvider, EmailTemplate, error::EmailError, implementation::recipient_name},
+    zepto::{
+        EmailAddress, Payload, Recipient, ZeptoApiError, ZeptoApiResponse, ZeptoConfig, ZeptoMail,
+        ZeptoTemplateConfig, error::ZeptoError,
+    },
 };
 
 use std::fmt;
@@ -13,58 +17,39 @@ impl fmt::Display for ZeptoApiError {
     }
 }
 
-impl Default for ZeptoMail {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 impl ZeptoMail {
-    pub fn new() -> Self {
+    pub fn new(cfg: ZeptoConfig) -> Self {
         Self {
             api_url: "https://api.zeptomail.com/v1.1/email/template".to_string(),
             client: Client::new(),
+            cfg,
         }
     }
 
-    pub async fn send_email_verification_link(
-        &self,
-        to_email: &str,
-        name: &str,
-        link: &str,
-        email_service_api_key: &str,
-    ) -> Result<(), ZeptoError> {
-        debug!("Sending email 1");
-        let payload = Payload {
-            template_alias: "poddle-email-verification-link-key-alias".to_string(),
-            from: EmailAddress {
-                name: "Poddle Verification".to_string(),
-                address: "verification@kronk.uz".to_string(),
-            },
-            to: vec![Recipient {
-                email_address: EmailAddress {
-                    address: to_email.to_string(),
-                    name: name.to_string(),
-                },
-            }],
-            merge_info: serde_json::json!({
-                "link": link
-            }),
-        };
-
-        debug!("Sending email to '{}' with email '{}'", name, to_email);
+    fn template(&self, template: EmailTemplate) -> &ZeptoTemplateConfig {
+        match template {
+            EmailTemplate::Verification => &self.cfg.verification,
+            EmailTemplate::PasswordSetup => &self.cfg.password_setup,
+            EmailTemplate::PasswordReset => &self.cfg.password_reset,
+            EmailTemplate::EmailChange => &self.cfg.email_change,
+            EmailTemplate::AccountDeleted => &self.cfg.account_deleted,
+            EmailTemplate::FeedbackConfirmation => &self.cfg.feedback_confirmation,
+            EmailTemplate::Billing => &self.cfg.billing,
+            EmailTemplate::Support => &self.cfg.support,
+        }
+    }
 
+    async fn send(&self, payload: &Payload) -> Result<(), ZeptoError> {
         let res = self
             .client
             .post(&self.api_url)
             .header("accept", "application/json")
             .header("content-type", "application/json")
-            .header("authorization", email_service_api_key)
-            .json(&payload)
+            .header("authorization", &self.cfg.api_key)
+            .json(payload)
             .send()
             .await?;
 
-        let _ = res.status();
         let text = res.text().await?;
 
         let api_response = serde_json::from_str::<ZeptoApiResponse>(&text)?;
@@ -81,3 +66,32 @@ impl ZeptoMail {
         }
     }
 }
+
+impl EmailProvider for ZeptoMail {
+    #[tracing::instrument(name = "zepto.send_templated_email", skip_all, fields(recipient = %to, template = ?template), err)]
+    async fn send_templated_email(
+        &self,
+        to: &str,
+        template: EmailTemplate,
+        vars: serde_json::Value,
+    ) -> Result<(), EmailError> {
+        let template = self.template(template).clone();
+
+        let payload = Payload {
+            template_alias: template.template_alias,
+            from: EmailAddress {
+                name: template.from_name,
+                address: template.from_email,
+            },
+            to: vec![Recipient {
+                email_address: EmailAddress {
+                    address: to.to_string(),
+                    name: recipient_name(to, &vars).to_string(),
+                },
+            }],
+            merge_info: vars,
+        };
+
+        Ok(self.send(&payload).await?)
+    }
+}
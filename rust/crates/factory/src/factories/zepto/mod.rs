@@ -4,6 +4,26 @@ pub mod implementation;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
+#[derive(Deserialize, Clone, Debug)]
+pub struct ZeptoTemplateConfig {
+    pub from_email: String,
+    pub from_name: String,
+    pub template_alias: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ZeptoConfig {
+    pub api_key: String,
+    pub verification: ZeptoTemplateConfig,
+    pub password_setup: ZeptoTemplateConfig,
+    pub billing: ZeptoTemplateConfig,
+    pub support: ZeptoTemplateConfig,
+    pub feedback_confirmation: ZeptoTemplateConfig,
+    pub email_change: ZeptoTemplateConfig,
+    pub account_deleted: ZeptoTemplateConfig,
+    pub password_reset: ZeptoTemplateConfig,
+}
+
 #[derive(Deserialize, Debug)]
 #[allow(dead_code)]
 pub struct ZeptoResponseData {
@@ -67,7 +87,9 @@ pub enum ZeptoApiResponse {
     Failure { error: ZeptoApiError },
 }
 
+#[derive(Clone)]
 pub struct ZeptoMail {
     api_url: String,
     client: Client,
+    cfg: ZeptoConfig,
 }
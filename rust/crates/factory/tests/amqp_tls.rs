@@ -0,0 +1,54 @@
+//! Connects to a RabbitMQ listening on `amqps://` with mutual TLS, run it with
+//! `cargo test -p factory --test amqp_tls -- --ignored`
+//!
+//! RabbitMQ has to be started with `ssl_options.verify = verify_peer` and
+//! `ssl_options.fail_if_no_peer_cert = true`, the test reads the URI and the PEM files from
+//! `AMQPS_URL`, `AMQP_TLS_CA_PATH`, `AMQP_TLS_CLIENT_CERT_PATH` and `AMQP_TLS_CLIENT_KEY_PATH`
+
+use std::path::PathBuf;
+
+use factory::factories::{
+    amqp::{Amqp, AmqpConfig},
+    tls::TlsConfig,
+};
+
+fn env(name: &str) -> String {
+    std::env::var(name).unwrap_or_else(|_| panic!("{} must be set", name))
+}
+
+fn tls_config(with_client_cert: bool) -> TlsConfig {
+    TlsConfig {
+        ca: None,
+        ca_path: Some(PathBuf::from(env("AMQP_TLS_CA_PATH"))),
+        client_cert: None,
+        client_cert_path: with_client_cert.then(|| env("AMQP_TLS_CLIENT_CERT_PATH").into()),
+        client_key: None,
+        client_key_path: with_client_cert.then(|| env("AMQP_TLS_CLIENT_KEY_PATH").into()),
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs RabbitMQ with TLS"]
+async fn connects_with_client_certificate() {
+    let cfg = AmqpConfig {
+        uri: env("AMQPS_URL"),
+        tls_config: Some(tls_config(true)),
+    };
+
+    let amqp = Amqp::new(&cfg).await;
+    let channel = amqp.channel().await.unwrap();
+
+    assert!(channel.status().connected());
+}
+
+#[tokio::test]
+#[ignore = "needs RabbitMQ with TLS"]
+#[should_panic(expected = "Failed to connect to RabbitMQ")]
+async fn rejects_connection_without_client_certificate() {
+    let cfg = AmqpConfig {
+        uri: env("AMQPS_URL"),
+        tls_config: Some(tls_config(false)),
+    };
+
+    Amqp::new(&cfg).await;
+}
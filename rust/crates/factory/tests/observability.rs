@@ -0,0 +1,113 @@
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+
+use factory::factories::observability::{Observability, ObservabilityConfig};
+use tracing_subscriber::{fmt::MakeWriter, layer::SubscriberExt};
+
+/// Collects everything the fmt layer writes so the lines can be inspected
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for Captured {
+    type Writer = Self;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+fn config(log_format: Option<&str>) -> ObservabilityConfig {
+    ObservabilityConfig {
+        otel_exporter_otlp_endpoint: "http://localhost:4317".to_string(),
+        rust_log: None,
+        log_format: log_format.map(str::to_string),
+        tracing_level: None,
+        with_file: None,
+        with_line_number: None,
+        with_thread_ids: None,
+    }
+}
+
+fn log_line(cfg: &ObservabilityConfig) -> String {
+    let captured = Captured::default();
+    let subscriber =
+        tracing_subscriber::registry().with(Observability::fmt_layer(cfg, captured.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!(deployment = "web", "rolled out");
+    });
+
+    let bytes = captured.0.lock().unwrap().clone();
+    String::from_utf8(bytes).unwrap()
+}
+
+#[test]
+fn json_is_the_default_format() {
+    for log_format in [None, Some("json"), Some("jsno")] {
+        let output = log_line(&config(log_format));
+        let line: serde_json::Value = serde_json::from_str(output.trim())
+            .unwrap_or_else(|_| panic!("{:?} should log json, got {:?}", log_format, output));
+
+        assert_eq!(line["message"], "rolled out");
+        assert_eq!(line["deployment"], "web");
+        assert_eq!(line["level"], "INFO");
+    }
+}
+
+#[test]
+fn text_and_pretty_are_opt_in() {
+    let text = log_line(&config(Some("text")));
+    assert!(serde_json::from_str::<serde_json::Value>(text.trim()).is_err());
+    assert_eq!(text.lines().count(), 1);
+    assert!(text.contains("rolled out"));
+
+    let pretty = log_line(&config(Some("pretty")));
+    assert!(serde_json::from_str::<serde_json::Value>(pretty.trim()).is_err());
+    assert!(pretty.lines().count() > 1);
+    assert!(pretty.contains("rolled out"));
+}
+
+#[test]
+fn flags_default_to_file_and_line_without_thread_ids() {
+    let combinations = [
+        ((None, None, None), (true, true, false)),
+        ((Some(false), None, None), (false, true, false)),
+        ((None, Some(false), None), (true, false, false)),
+        ((Some(false), Some(false), Some(true)), (false, false, true)),
+        ((Some(true), Some(true), Some(true)), (true, true, true)),
+    ];
+
+    for ((with_file, with_line_number, with_thread_ids), expected) in combinations {
+        let cfg = ObservabilityConfig {
+            with_file,
+            with_line_number,
+            with_thread_ids,
+            ..config(None)
+        };
+        let output = log_line(&cfg);
+        let line: serde_json::Value = serde_json::from_str(output.trim()).unwrap();
+
+        let fields = (
+            line.get("filename").is_some(),
+            line.get("line_number").is_some(),
+            line.get("threadId").is_some(),
+        );
+        assert_eq!(
+            fields, expected,
+            "{:?} {:?} {:?} logged {}",
+            with_file, with_line_number, with_thread_ids, output
+        );
+    }
+}
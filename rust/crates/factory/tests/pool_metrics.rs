@@ -0,0 +1,105 @@
+use std::time::Duration;
+
+use factory::factories::database::PoolMetrics;
+use opentelemetry::metrics::MeterProvider;
+use opentelemetry_sdk::metrics::{
+    InMemoryMetricExporter, PeriodicReader, SdkMeterProvider,
+    data::{AggregatedMetrics, MetricData},
+};
+use sqlx::{PgPool, postgres::PgPoolOptions};
+
+/// Meter provider exporting into memory, flushed by hand instead of on an interval
+fn recorder() -> (SdkMeterProvider, InMemoryMetricExporter) {
+    let exporter = InMemoryMetricExporter::default();
+    let provider = SdkMeterProvider::builder()
+        .with_reader(PeriodicReader::builder(exporter.clone()).build())
+        .build();
+    (provider, exporter)
+}
+
+/// Last value each gauge was set to, `None` when it was never recorded
+#[derive(Debug, PartialEq)]
+struct Recorded {
+    size: Option<u64>,
+    idle: Option<u64>,
+    acquire_latency_ms: Option<f64>,
+}
+
+fn recorded(provider: &SdkMeterProvider, exporter: &InMemoryMetricExporter) -> Recorded {
+    provider.force_flush().unwrap();
+
+    let mut recorded = Recorded {
+        size: None,
+        idle: None,
+        acquire_latency_ms: None,
+    };
+    for resource_metrics in exporter.get_finished_metrics().unwrap() {
+        for metric in resource_metrics
+            .scope_metrics()
+            .flat_map(|scope| scope.metrics())
+        {
+            match (metric.name(), metric.data()) {
+                ("db.pool.size", AggregatedMetrics::U64(MetricData::Gauge(gauge))) => {
+                    recorded.size = gauge.data_points().last().map(|p| p.value());
+                }
+                ("db.pool.idle", AggregatedMetrics::U64(MetricData::Gauge(gauge))) => {
+                    recorded.idle = gauge.data_points().last().map(|p| p.value());
+                }
+                (
+                    "db.pool.acquire_latency_ms",
+                    AggregatedMetrics::F64(MetricData::Gauge(gauge)),
+                ) => {
+                    recorded.acquire_latency_ms = gauge.data_points().last().map(|p| p.value());
+                }
+                (name, _) => panic!("Unexpected metric {}", name),
+            }
+        }
+    }
+    recorded
+}
+
+#[tokio::test]
+async fn unreachable_database_records_an_empty_pool_and_no_latency() {
+    let (provider, exporter) = recorder();
+    let metrics = PoolMetrics::new(&provider.meter("database"));
+
+    // Nothing listens on port 1, acquiring gives up after the timeout
+    let pool = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(200))
+        .connect_lazy("postgres://postgres@127.0.0.1:1/poddle")
+        .unwrap();
+    metrics.record(&pool).await;
+
+    assert_eq!(
+        recorded(&provider, &exporter),
+        Recorded {
+            size: Some(0),
+            idle: Some(0),
+            acquire_latency_ms: None,
+        }
+    );
+}
+
+#[sqlx::test(migrations = false)]
+#[ignore = "needs Postgres"]
+async fn connected_pool_records_size_idle_and_latency(pool: PgPool) {
+    let (provider, exporter) = recorder();
+    let metrics = PoolMetrics::new(&provider.meter("database"));
+
+    // One connection held elsewhere, one left idle for the probe
+    let held = pool.acquire().await.unwrap();
+    drop(pool.acquire().await.unwrap());
+    // Connections go back to the pool in a spawned task
+    while pool.num_idle() == 0 {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    metrics.record(&pool).await;
+
+    let recorded = recorded(&provider, &exporter);
+    assert_eq!(recorded.size, Some(2));
+    assert_eq!(recorded.idle, Some(1));
+    let latency = recorded.acquire_latency_ms.expect("No acquire latency");
+    assert!((0.0..1000.0).contains(&latency), "{}", latency);
+
+    drop(held);
+}
@@ -0,0 +1,91 @@
+//! The fixtures are self-signed P-256 pairs made with
+//! `openssl req -x509 -newkey ec -pkeyopt ec_paramgen_curve:prime256v1 -nodes`
+
+use std::path::{Path, PathBuf};
+
+use factory::factories::{
+    redis::{Redis, RedisConfig, error::RedisError},
+    tls::TlsConfig,
+};
+
+const CLIENT_CERT: &str = include_str!("fixtures/client.crt");
+const CLIENT_KEY: &str = include_str!("fixtures/client.key");
+const OTHER_KEY: &str = include_str!("fixtures/other.key");
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+fn config(client_cert: Option<&str>, client_key: Option<&str>) -> RedisConfig {
+    RedisConfig {
+        url: Some("rediss://localhost:6380".to_string()),
+        params: None,
+        tls_config: Some(TlsConfig {
+            ca: Some(CLIENT_CERT.to_string()),
+            ca_path: None,
+            client_cert: client_cert.map(str::to_string),
+            client_cert_path: None,
+            client_key: client_key.map(str::to_string),
+            client_key_path: None,
+        }),
+    }
+}
+
+#[test]
+fn matching_pair_turns_on_mutual_tls() {
+    let certs = Redis::tls_certificates(&config(Some(CLIENT_CERT), Some(CLIENT_KEY)))
+        .unwrap()
+        .unwrap();
+    let client_tls = certs.client_tls.unwrap();
+    assert_eq!(client_tls.client_cert, CLIENT_CERT.as_bytes());
+    assert_eq!(client_tls.client_key, CLIENT_KEY.as_bytes());
+
+    let from_files = RedisConfig {
+        tls_config: Some(TlsConfig {
+            ca: None,
+            ca_path: Some(fixture("client.crt")),
+            client_cert: None,
+            client_cert_path: Some(fixture("client.crt")),
+            client_key: None,
+            client_key_path: Some(fixture("client.key")),
+        }),
+        ..config(None, None)
+    };
+    let certs = Redis::tls_certificates(&from_files).unwrap().unwrap();
+    assert!(certs.root_cert.is_some());
+    assert!(certs.client_tls.is_some());
+}
+
+#[test]
+fn ca_alone_verifies_the_server_only() {
+    let certs = Redis::tls_certificates(&config(None, None))
+        .unwrap()
+        .unwrap();
+    assert!(certs.root_cert.is_some());
+    assert!(certs.client_tls.is_none());
+}
+
+#[test]
+fn mismatched_key_is_a_config_error() {
+    let result = Redis::tls_certificates(&config(Some(CLIENT_CERT), Some(OTHER_KEY)));
+    assert!(matches!(result, Err(RedisError::Config(_))));
+}
+
+#[test]
+fn cert_without_key_is_a_config_error() {
+    for (client_cert, client_key) in [(Some(CLIENT_CERT), None), (None, Some(CLIENT_KEY))] {
+        let result = Redis::tls_certificates(&config(client_cert, client_key));
+        assert!(matches!(result, Err(RedisError::Config(_))));
+    }
+}
+
+#[test]
+fn unparsable_pem_is_a_config_error() {
+    let result = Redis::tls_certificates(&config(Some("not a certificate"), Some(CLIENT_KEY)));
+    assert!(matches!(result, Err(RedisError::Config(_))));
+
+    let result = Redis::tls_certificates(&config(Some(CLIENT_CERT), Some("not a key")));
+    assert!(matches!(result, Err(RedisError::Config(_))));
+}
@@ -0,0 +1,111 @@
+//! Heap allocated while rejecting an oversized upload, axum's `DefaultBodyLimit` against
+//! `StreamingBodyLimitLayer`. Run it with `cargo bench -p http-common --bench body_limit`
+//!
+//! The body is streamed in freshly allocated chunks the way hyper hands them over, so the
+//! numbers show how much of an upload each limiter pulls in before answering 413.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    Router,
+    body::{Body, Bytes},
+    extract::DefaultBodyLimit,
+    http::{Request, StatusCode, header},
+    routing::post,
+};
+use http_common::body_limit::StreamingBodyLimitLayer;
+use tower::ServiceExt;
+
+const LIMIT: usize = 8 * 1024 * 1024;
+const CHUNK: usize = 64 * 1024;
+const ITERATIONS: u32 = 20;
+
+struct CountingAlloc;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let now = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(now, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+async fn upload(body: Bytes) -> String {
+    body.len().to_string()
+}
+
+fn oversized_request(with_content_length: bool) -> Request<Body> {
+    let size = LIMIT + CHUNK;
+    let chunks = (0..size / CHUNK).map(|_| Ok::<_, std::io::Error>(Bytes::from(vec![0u8; CHUNK])));
+    let mut req = Request::post("/upload");
+    if with_content_length {
+        req = req.header(header::CONTENT_LENGTH, size);
+    }
+    req.body(Body::from_stream(futures::stream::iter(chunks)))
+        .unwrap()
+}
+
+async fn measure(name: &str, router: Router, with_content_length: bool) {
+    let mut peak = 0;
+    let mut elapsed = Duration::ZERO;
+
+    for _ in 0..ITERATIONS {
+        let req = oversized_request(with_content_length);
+        let baseline = ALLOCATED.load(Ordering::Relaxed);
+        PEAK.store(baseline, Ordering::Relaxed);
+
+        let started = Instant::now();
+        let res = router.clone().oneshot(req).await.unwrap();
+        elapsed += started.elapsed();
+
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        peak = peak.max(PEAK.load(Ordering::Relaxed) - baseline);
+    }
+
+    println!(
+        "{name:<48} peak {:>8} KiB  {:>10.2?}/request",
+        peak / 1024,
+        elapsed / ITERATIONS
+    );
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let default_limit = Router::new()
+        .route("/upload", post(upload))
+        .layer(DefaultBodyLimit::max(LIMIT));
+    let streaming_limit = Router::new()
+        .route("/upload", post(upload))
+        .layer(DefaultBodyLimit::disable())
+        .layer(StreamingBodyLimitLayer::new(LIMIT));
+
+    measure(
+        "DefaultBodyLimit, Content-Length",
+        default_limit.clone(),
+        true,
+    )
+    .await;
+    measure(
+        "StreamingBodyLimitLayer, Content-Length",
+        streaming_limit.clone(),
+        true,
+    )
+    .await;
+    measure("DefaultBodyLimit, chunked", default_limit, false).await;
+    measure("StreamingBodyLimitLayer, chunked", streaming_limit, false).await;
+}
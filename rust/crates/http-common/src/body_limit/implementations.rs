@@ -0,0 +1,75 @@
+use std::task::{Context, Poll};
+
+use axum::{
+    Json,
+    body::Body,
+    http::{Request, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+use http_body_util::Limited;
+use serde_json::json;
+use tower::{Layer, Service};
+use tracing::warn;
+
+use crate::body_limit::{StreamingBodyLimitLayer, StreamingBodyLimitService};
+
+impl StreamingBodyLimitLayer {
+    pub fn new(limit: usize) -> Self {
+        Self { limit }
+    }
+}
+
+impl<S> Layer<S> for StreamingBodyLimitLayer {
+    type Service = StreamingBodyLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        StreamingBodyLimitService {
+            inner,
+            limit: self.limit,
+        }
+    }
+}
+
+impl<S> Service<Request<Body>> for StreamingBodyLimitService<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let content_length = req
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok());
+
+        if content_length.is_some_and(|len| len > self.limit as u64) {
+            warn!(method = %req.method(), uri = %req.uri(), content_length, limit = self.limit, "🛡️ Rejected request body over the size limit");
+            return Box::pin(async {
+                Ok((
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    Json(json!({ "error": "Request body is too large" })),
+                )
+                    .into_response())
+            });
+        }
+
+        // A lying or missing Content-Length is caught while the body is read
+        let limit = self.limit;
+        let req = req.map(|body| Body::new(Limited::new(body, limit)));
+
+        // The clone may not be ready, keep the one poll_ready was called on
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move { inner.call(req).await })
+    }
+}
@@ -0,0 +1,18 @@
+pub mod implementations;
+
+/// Caps request bodies at `limit` bytes without buffering them first.
+///
+/// A `Content-Length` above the limit is answered with 413 before the body is read. Bodies
+/// without one (chunked) are wrapped so reading fails once they pass the limit, which axum's
+/// extractors turn into a 413 as well. Pair it with `DefaultBodyLimit::disable()`, axum would
+/// otherwise still apply its own 2 MB default on top.
+#[derive(Clone, Copy)]
+pub struct StreamingBodyLimitLayer {
+    limit: usize,
+}
+
+#[derive(Clone)]
+pub struct StreamingBodyLimitService<S> {
+    inner: S,
+    limit: usize,
+}
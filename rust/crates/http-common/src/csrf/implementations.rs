@@ -0,0 +1,160 @@
+use std::task::{Context, Poll};
+
+use axum::{
+    Json,
+    body::Body,
+    http::{HeaderValue, Method, Request, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use cookie::{Cookie, SameSite};
+use futures::future::BoxFuture;
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use serde_json::json;
+use sha2::Sha256;
+use tower::{Layer, Service};
+use tracing::warn;
+
+use crate::csrf::{CSRF_HEADER, CSRF_SESSION_COOKIE, CSRF_TOKEN_COOKIE, CsrfLayer, CsrfService};
+
+impl CsrfLayer {
+    pub fn new(key: impl AsRef<[u8]>) -> Self {
+        Self {
+            key: key.as_ref().into(),
+            secure: true,
+        }
+    }
+
+    /// Marks the issued cookies `Secure`, disable only for plain http development setups
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+}
+
+impl<S> Layer<S> for CsrfLayer {
+    type Service = CsrfService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CsrfService {
+            inner,
+            key: self.key.clone(),
+            secure: self.secure,
+        }
+    }
+}
+
+impl<S> Service<Request<Body>> for CsrfService<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let session_id = get_cookie(&req, CSRF_SESSION_COOKIE);
+        let safe = matches!(
+            *req.method(),
+            Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+        );
+
+        if !safe && req.headers().contains_key(header::COOKIE) {
+            let header_token = req.headers().get(CSRF_HEADER).and_then(|v| v.to_str().ok());
+
+            let valid = match (session_id.as_deref(), header_token) {
+                (Some(session_id), Some(token)) => verify(&self.key, session_id, token),
+                _ => false,
+            };
+            if !valid {
+                warn!(method = %req.method(), uri = %req.uri(), "🛡️ Rejected request with missing or invalid CSRF token");
+                return Box::pin(async {
+                    Ok((
+                        StatusCode::FORBIDDEN,
+                        Json(json!({ "error": "Missing or invalid CSRF token" })),
+                    )
+                        .into_response())
+                });
+            }
+        }
+
+        // The clone may not be ready, keep the one poll_ready was called on
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let key = self.key.clone();
+        let secure = self.secure;
+        let token_cookie = get_cookie(&req, CSRF_TOKEN_COOKIE);
+
+        Box::pin(async move {
+            let mut res = inner.call(req).await?;
+            if !safe {
+                return Ok(res);
+            }
+
+            let (session_id, new_session) = match session_id {
+                Some(session_id) => (session_id, false),
+                None => (hex::encode(rand::rng().random::<[u8; 32]>()), true),
+            };
+            let token = sign(&key, &session_id);
+
+            if new_session {
+                let cookie = Cookie::build((CSRF_SESSION_COOKIE, session_id))
+                    .http_only(true)
+                    .path("/")
+                    .same_site(SameSite::Lax)
+                    .secure(secure);
+                append_cookie(&mut res, cookie.build());
+            }
+            if new_session || token_cookie.as_deref() != Some(token.as_str()) {
+                let cookie = Cookie::build((CSRF_TOKEN_COOKIE, token))
+                    .http_only(false)
+                    .path("/")
+                    .same_site(SameSite::Lax)
+                    .secure(secure);
+                append_cookie(&mut res, cookie.build());
+            }
+
+            Ok(res)
+        })
+    }
+}
+
+fn get_cookie(req: &Request<Body>, name: &str) -> Option<String> {
+    req.headers()
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(Cookie::split_parse)
+        .filter_map(Result::ok)
+        .find(|c| c.name() == name)
+        .map(|c| c.value().to_string())
+}
+
+fn append_cookie(res: &mut Response, cookie: Cookie<'_>) {
+    if let Ok(value) = HeaderValue::from_str(&cookie.to_string()) {
+        res.headers_mut().append(header::SET_COOKIE, value);
+    }
+}
+
+fn mac(key: &[u8], session_id: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(session_id.as_bytes());
+    mac
+}
+
+fn sign(key: &[u8], session_id: &str) -> String {
+    hex::encode(mac(key, session_id).finalize().into_bytes())
+}
+
+/// Constant time comparison through the HMAC verifier
+fn verify(key: &[u8], session_id: &str, token: &str) -> bool {
+    match hex::decode(token) {
+        Ok(token) => mac(key, session_id).verify_slice(&token).is_ok(),
+        Err(_) => false,
+    }
+}
@@ -0,0 +1,27 @@
+pub mod implementations;
+
+use std::sync::Arc;
+
+/// Random per-browser id, HttpOnly so scripts can't read it
+pub const CSRF_SESSION_COOKIE: &str = "csrf_session";
+/// HMAC of the session id, readable by the frontend which echoes it in [`CSRF_HEADER`]
+pub const CSRF_TOKEN_COOKIE: &str = "csrf_token";
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/// Double-submit CSRF protection for cookie authenticated routes.
+///
+/// Safe requests get a `csrf_session` and `csrf_token` cookie pair. Unsafe requests that carry
+/// cookies must send the token back in `X-CSRF-Token`, requests without cookies (bearer tokens,
+/// webhooks) can't be forged by a browser and pass through.
+#[derive(Clone)]
+pub struct CsrfLayer {
+    key: Arc<[u8]>,
+    secure: bool,
+}
+
+#[derive(Clone)]
+pub struct CsrfService<S> {
+    inner: S,
+    key: Arc<[u8]>,
+    secure: bool,
+}
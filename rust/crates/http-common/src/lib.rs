@@ -1,3 +1,6 @@
+pub mod body_limit;
+pub mod csrf;
 pub mod handlers;
+pub mod metrics;
 pub mod router;
 pub mod trace_layer;
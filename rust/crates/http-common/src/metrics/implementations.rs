@@ -0,0 +1,162 @@
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use axum::{
+    body::Body,
+    extract::{MatchedPath, State},
+    http::{Request, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+use prometheus_client::{
+    encoding::text::encode,
+    metrics::{
+        family::Family,
+        gauge::Gauge,
+        histogram::{Histogram, exponential_buckets},
+    },
+    registry::Registry,
+};
+use tower::{Layer, Service};
+use tracing::error;
+
+use crate::metrics::{HttpMetrics, MetricsLayer, MetricsService, RequestLabels};
+
+/// 5ms up to ~10s
+fn duration_histogram() -> Histogram {
+    Histogram::new(exponential_buckets(0.005, 2.0, 12))
+}
+
+impl HttpMetrics {
+    pub fn new() -> Self {
+        let mut registry = Registry::default();
+        let requests = Family::<RequestLabels, _>::default();
+        let duration = Family::<RequestLabels, Histogram, fn() -> Histogram>::new_with_constructor(
+            duration_histogram,
+        );
+        let in_flight = Gauge::default();
+
+        registry.register("http_requests", "Handled HTTP requests", requests.clone());
+        registry.register(
+            "http_request_duration_seconds",
+            "Time until the response headers were ready",
+            duration.clone(),
+        );
+        registry.register(
+            "http_requests_in_flight",
+            "Requests currently being handled",
+            in_flight.clone(),
+        );
+
+        Self {
+            registry: Arc::new(registry),
+            requests,
+            duration,
+            in_flight,
+        }
+    }
+
+    /// Prometheus text exposition of everything registered
+    pub fn encode(&self) -> Result<String, std::fmt::Error> {
+        let mut buffer = String::new();
+        encode(&mut buffer, &self.registry)?;
+        Ok(buffer)
+    }
+}
+
+impl Default for HttpMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MetricsLayer {
+    pub fn new(metrics: HttpMetrics) -> Self {
+        Self { metrics }
+    }
+}
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = MetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsService {
+            inner,
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+impl<S> Service<Request<Body>> for MetricsService<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let started = Instant::now();
+        let method = req.method().to_string();
+        // Unmatched requests share one label, otherwise scanners would create a series per path
+        let route = req
+            .extensions()
+            .get::<MatchedPath>()
+            .map(|p| p.as_str().to_string())
+            .unwrap_or_else(|| "unmatched".to_string());
+
+        // The clone may not be ready, keep the one poll_ready was called on
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let metrics = self.metrics.clone();
+
+        Box::pin(async move {
+            metrics.in_flight.inc();
+            let res = inner.call(req).await;
+            metrics.in_flight.dec();
+
+            let status = match &res {
+                Ok(res) => res.status(),
+                Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            let labels = RequestLabels {
+                method,
+                route,
+                status: status.as_u16(),
+            };
+            metrics.requests.get_or_create(&labels).inc();
+            metrics
+                .duration
+                .get_or_create(&labels)
+                .observe(started.elapsed().as_secs_f64());
+
+            res
+        })
+    }
+}
+
+pub async fn metrics_handler(State(metrics): State<HttpMetrics>) -> impl IntoResponse {
+    match metrics.encode() {
+        Ok(body) => (
+            StatusCode::OK,
+            [(
+                header::CONTENT_TYPE,
+                "application/openmetrics-text; version=1.0.0; charset=utf-8",
+            )],
+            body,
+        )
+            .into_response(),
+        Err(e) => {
+            error!("❌ Failed to encode metrics: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
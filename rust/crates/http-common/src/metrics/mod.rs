@@ -0,0 +1,38 @@
+pub mod implementations;
+
+use std::sync::Arc;
+
+use prometheus_client::{
+    encoding::EncodeLabelSet,
+    metrics::{counter::Counter, family::Family, gauge::Gauge, histogram::Histogram},
+    registry::Registry,
+};
+
+/// Labels of every request, `route` is the matched route template so ids don't blow up cardinality
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct RequestLabels {
+    pub method: String,
+    pub route: String,
+    pub status: u16,
+}
+
+/// Request metrics of an API service and the registry they are exposed from at `/metrics`
+#[derive(Clone)]
+pub struct HttpMetrics {
+    registry: Arc<Registry>,
+    requests: Family<RequestLabels, Counter>,
+    duration: Family<RequestLabels, Histogram, fn() -> Histogram>,
+    in_flight: Gauge,
+}
+
+/// Records count, latency and in-flight requests of every routed request
+#[derive(Clone)]
+pub struct MetricsLayer {
+    metrics: HttpMetrics,
+}
+
+#[derive(Clone)]
+pub struct MetricsService<S> {
+    inner: S,
+    metrics: HttpMetrics,
+}
@@ -1,6 +1,9 @@
 use axum::{Router, routing::get};
 
-use crate::handlers::{health_handler, not_found_handler, ready_handler, root_handler};
+use crate::{
+    handlers::{health_handler, not_found_handler, ready_handler, root_handler},
+    metrics::{HttpMetrics, implementations::metrics_handler},
+};
 
 pub fn base_routes<S>(cargo_pkg_name: &'static str, cargo_pkg_version: &'static str) -> Router<S>
 where
@@ -18,3 +21,10 @@ where
         .route("/ready", get(ready_handler))
         .fallback(not_found_handler)
 }
+
+/// Merged after the CORS, CSRF and rate limit layers, scrapers send neither origins nor cookies
+pub fn metrics_routes(metrics: HttpMetrics) -> Router {
+    Router::new()
+        .route("/metrics", get(metrics_handler))
+        .with_state(metrics)
+}
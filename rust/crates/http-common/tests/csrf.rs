@@ -0,0 +1,124 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{HeaderMap, Method, Request, StatusCode, header},
+    routing::get,
+};
+use http_common::csrf::{CSRF_HEADER, CSRF_SESSION_COOKIE, CSRF_TOKEN_COOKIE, CsrfLayer};
+use tower::ServiceExt;
+
+fn app() -> Router {
+    Router::new()
+        .route("/", get(|| async { "ok" }).post(|| async { "ok" }))
+        .layer(CsrfLayer::new("test-key").secure(false))
+}
+
+async fn send(method: Method, headers: &[(&str, &str)]) -> (StatusCode, HeaderMap) {
+    let mut req = Request::builder().method(method).uri("/");
+    for (name, value) in headers {
+        req = req.header(*name, *value);
+    }
+
+    let res = app()
+        .oneshot(req.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    (res.status(), res.headers().clone())
+}
+
+fn set_cookie(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .filter_map(|v| v.split(';').next())
+        .find_map(|pair| pair.strip_prefix(&format!("{}=", name)).map(str::to_string))
+}
+
+/// Session id and token a browser holds after its first page load
+async fn issued_pair() -> (String, String) {
+    let (status, headers) = send(Method::GET, &[]).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let session_id = set_cookie(&headers, CSRF_SESSION_COOKIE).expect("No session cookie");
+    let token = set_cookie(&headers, CSRF_TOKEN_COOKIE).expect("No token cookie");
+    (session_id, token)
+}
+
+fn cookies(session_id: &str, token: &str) -> String {
+    format!(
+        "{}={}; {}={}; access_token=jwt",
+        CSRF_SESSION_COOKIE, session_id, CSRF_TOKEN_COOKIE, token
+    )
+}
+
+#[tokio::test]
+async fn safe_methods_pass_without_a_token() {
+    let (session_id, token) = issued_pair().await;
+    let cookie = cookies(&session_id, &token);
+
+    for method in [Method::GET, Method::HEAD, Method::OPTIONS] {
+        let (status, _) = send(method.clone(), &[("cookie", &cookie)]).await;
+        assert_ne!(status, StatusCode::FORBIDDEN, "{}", method);
+    }
+}
+
+#[tokio::test]
+async fn cookie_request_without_token_is_forbidden() {
+    let (session_id, token) = issued_pair().await;
+
+    let (status, _) = send(Method::POST, &[("cookie", &cookies(&session_id, &token))]).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Any cookie counts, the session cookie missing doesn't let the request through
+    let (status, _) = send(
+        Method::POST,
+        &[("cookie", "access_token=jwt"), (CSRF_HEADER, &token)],
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn mismatched_token_is_forbidden() {
+    let (session_id, token) = issued_pair().await;
+    let (_, other_token) = issued_pair().await;
+    let cookie = cookies(&session_id, &token);
+
+    for header_token in [
+        other_token.as_str(),
+        "not-hex",
+        "",
+        &token[..token.len() - 2],
+    ] {
+        let (status, _) = send(
+            Method::POST,
+            &[("cookie", &cookie), (CSRF_HEADER, header_token)],
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{:?}", header_token);
+    }
+}
+
+#[tokio::test]
+async fn matching_token_passes() {
+    let (session_id, token) = issued_pair().await;
+
+    for method in [Method::POST, Method::PUT, Method::PATCH, Method::DELETE] {
+        let (status, _) = send(
+            method.clone(),
+            &[
+                ("cookie", &cookies(&session_id, &token)),
+                (CSRF_HEADER, &token),
+            ],
+        )
+        .await;
+        assert_ne!(status, StatusCode::FORBIDDEN, "{}", method);
+    }
+}
+
+#[tokio::test]
+async fn requests_without_cookies_are_not_checked() {
+    let (status, _) = send(Method::POST, &[("authorization", "Bearer jwt")]).await;
+    assert_eq!(status, StatusCode::OK);
+}
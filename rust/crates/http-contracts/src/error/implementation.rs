@@ -0,0 +1,84 @@
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+
+use crate::error::{AppError, schema::ErrorResponse};
+
+impl AppError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::UnprocessableEntity(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::InternalServerError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status_code();
+        let body = Json(ErrorResponse {
+            error: self.to_string(),
+        });
+
+        (status, body).into_response()
+    }
+}
+
+#[cfg(feature = "sqlx")]
+impl From<sqlx::Error> for AppError {
+    fn from(e: sqlx::Error) -> Self {
+        match e {
+            sqlx::Error::RowNotFound => Self::NotFound("Resource not found".into()),
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                Self::Conflict(db.message().to_string())
+            }
+            sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+                Self::UnprocessableEntity(db.message().to_string())
+            }
+            sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed => {
+                Self::ServiceUnavailable(e.to_string())
+            }
+            e => Self::InternalServerError(e.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "kube")]
+impl From<kube::Error> for AppError {
+    fn from(e: kube::Error) -> Self {
+        // Only the API server's answer about the object itself is the caller's business, a 403
+        // here is the service's own RBAC
+        match e {
+            kube::Error::Api(ae) if ae.code == 404 => Self::NotFound(ae.message),
+            kube::Error::Api(ae) if ae.code == 409 => Self::Conflict(ae.message),
+            kube::Error::Api(ae) if ae.code == 422 => Self::UnprocessableEntity(ae.message),
+            kube::Error::Api(ae) if ae.code == 503 => Self::ServiceUnavailable(ae.message),
+            e => Self::InternalServerError(e.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "redis")]
+impl From<redis::RedisError> for AppError {
+    fn from(e: redis::RedisError) -> Self {
+        if e.is_io_error() || e.is_timeout() || e.is_connection_dropped() {
+            Self::ServiceUnavailable(e.to_string())
+        } else {
+            Self::InternalServerError(e.to_string())
+        }
+    }
+}
+
+#[cfg(feature = "lapin")]
+impl From<lapin::Error> for AppError {
+    fn from(e: lapin::Error) -> Self {
+        Self::ServiceUnavailable(e.to_string())
+    }
+}
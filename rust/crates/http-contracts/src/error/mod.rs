@@ -1 +1,23 @@
+pub mod implementation;
 pub mod schema;
+
+use thiserror::Error;
+
+/// Errors every HTTP service can answer with, each maps to exactly one status code
+#[derive(Error, Debug)]
+pub enum AppError {
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("{0}")]
+    Conflict(String),
+    #[error("{0}")]
+    UnprocessableEntity(String),
+    #[error("{0}")]
+    InternalServerError(String),
+    #[error("{0}")]
+    ServiceUnavailable(String),
+}
@@ -2,3 +2,4 @@ pub mod error;
 pub mod list;
 pub mod message;
 pub mod pagination;
+pub mod preset;
@@ -17,6 +17,9 @@ pub enum PaginationError {
 
     #[error("Limit cannot exceed 100")]
     LimitTooLarge,
+
+    #[error("Page must be positive")]
+    ZeroOrNegativePage,
 }
 
 /// Implement IntoResponse so it can be returned from an Axum handler
@@ -28,6 +31,9 @@ impl IntoResponse for PaginationError {
                 (StatusCode::BAD_REQUEST, "Limit cannot be zero or negative")
             }
             Self::LimitTooLarge => (StatusCode::BAD_REQUEST, "Limit is too large"),
+            Self::ZeroOrNegativePage => {
+                (StatusCode::BAD_REQUEST, "Page cannot be zero or negative")
+            }
         };
 
         let body = Json(ErrorResponse {
@@ -1,4 +1,10 @@
-use crate::pagination::{error::PaginationError, schema::Pagination};
+use crate::pagination::{
+    error::PaginationError,
+    schema::{Paginated, Pagination, PaginationParams},
+};
+
+pub const DEFAULT_LIMIT: i64 = 20;
+pub const MAX_LIMIT: i64 = 100;
 
 impl Pagination {
     pub fn validate(&self) -> Result<(), PaginationError> {
@@ -10,10 +16,55 @@ impl Pagination {
             return Err(PaginationError::ZeroOrNegativeLimit);
         }
 
-        if self.limit > 100 {
+        if self.limit > MAX_LIMIT {
             return Err(PaginationError::LimitTooLarge);
         }
 
         Ok(())
     }
+
+    /// 1-based page of the window. An offset that isn't a multiple of the limit leaves a short
+    /// page in front, which counts as one, so paging on by `limit` ends on `total_pages`
+    pub fn page(&self) -> i64 {
+        Self::pages(self.offset, self.limit) + 1
+    }
+
+    /// Pages needed to reach `total` stepping by `limit` from this window's offset
+    pub fn total_pages(&self, total: i64) -> i64 {
+        let offset = self.offset.min(total);
+        Self::pages(offset, self.limit) + Self::pages(total - offset, self.limit)
+    }
+
+    fn pages(items: i64, limit: i64) -> i64 {
+        (items + limit - 1) / limit
+    }
+}
+
+impl TryFrom<PaginationParams> for Pagination {
+    type Error = PaginationError;
+
+    fn try_from(params: PaginationParams) -> Result<Self, Self::Error> {
+        let limit = params.per_page.or(params.limit).unwrap_or(DEFAULT_LIMIT);
+        let offset = match params.page {
+            Some(page) if page < 1 => return Err(PaginationError::ZeroOrNegativePage),
+            Some(page) => (page - 1).saturating_mul(limit),
+            None => params.offset.unwrap_or(0),
+        };
+
+        let pagination = Self { offset, limit };
+        pagination.validate()?;
+        Ok(pagination)
+    }
+}
+
+impl<T> Paginated<T> {
+    pub fn new(data: Vec<T>, total: i64, p: &Pagination) -> Self {
+        Self {
+            data,
+            total,
+            page: p.page(),
+            per_page: p.limit,
+            total_pages: p.total_pages(total),
+        }
+    }
 }
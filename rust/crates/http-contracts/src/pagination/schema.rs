@@ -1,31 +1,34 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-// #[serde_as]
-// #[derive(Deserialize, Serialize, JsonSchema, Debug)]
-// pub struct Pagination {
-//     #[serde(default = "default_offset")]
-//     #[serde_as(as = "DisplayFromStr")]
-//     #[schemars(with = "i64")]
-//     pub offset: i64,
-//     #[serde(default = "default_limit")]
-//     #[serde_as(as = "DisplayFromStr")]
-//     #[schemars(with = "i64")]
-//     pub limit: i64,
-// }
-
-#[derive(Deserialize, Serialize, JsonSchema, Debug)]
+/// Resolved window of a list endpoint, deserialized from [`PaginationParams`] so handlers only
+/// ever see a validated `offset`/`limit`
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone)]
+#[serde(try_from = "PaginationParams")]
 pub struct Pagination {
-    #[serde(default = "default_offset")]
     pub offset: i64,
-    #[serde(default = "default_limit")]
     pub limit: i64,
 }
 
-fn default_offset() -> i64 {
-    0
+/// Either `offset`/`limit` or `page`/`per_page`, `page` wins when both are given
+#[derive(Deserialize, JsonSchema, Debug)]
+pub struct PaginationParams {
+    /// Items to skip, ignored when `page` is given. Defaults to 0
+    pub offset: Option<i64>,
+    /// Items per page, 1 to 100. Defaults to 20
+    pub limit: Option<i64>,
+    /// 1-based page to return, takes precedence over `offset`
+    pub page: Option<i64>,
+    /// Same as `limit`, takes precedence over it when both are given
+    pub per_page: Option<i64>,
 }
 
-fn default_limit() -> i64 {
-    20
+/// Page of a list endpoint with enough to render a pager
+#[derive(Serialize, JsonSchema, Debug)]
+pub struct Paginated<T> {
+    pub data: Vec<T>,
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+    pub total_pages: i64,
 }
@@ -0,0 +1,16 @@
+use bigdecimal::BigDecimal;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Preset as seen by a specific user, priced after their billing tier discount
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub cpu_millicores: i32,
+    pub memory_mb: i32,
+    pub effective_hourly_rate: BigDecimal,
+    pub currency: String,
+}
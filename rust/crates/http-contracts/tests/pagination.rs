@@ -0,0 +1,99 @@
+use http_contracts::pagination::{
+    error::PaginationError,
+    schema::{Paginated, Pagination, PaginationParams},
+};
+
+fn params(offset: Option<i64>, limit: Option<i64>, page: Option<i64>) -> PaginationParams {
+    PaginationParams {
+        offset,
+        limit,
+        page,
+        per_page: None,
+    }
+}
+
+fn window(offset: i64, limit: i64) -> Pagination {
+    Pagination::try_from(params(Some(offset), Some(limit), None)).unwrap()
+}
+
+#[test]
+fn page_is_turned_into_an_offset() {
+    let p = Pagination::try_from(params(Some(7), Some(10), Some(3))).unwrap();
+    assert_eq!((p.offset, p.limit), (20, 10));
+    assert_eq!(p.page(), 3);
+
+    let p = Pagination::try_from(PaginationParams {
+        offset: None,
+        limit: Some(50),
+        page: Some(2),
+        per_page: Some(25),
+    })
+    .unwrap();
+    assert_eq!((p.offset, p.limit), (25, 25));
+}
+
+#[test]
+fn defaults_and_bounds() {
+    let p = Pagination::try_from(params(None, None, None)).unwrap();
+    assert_eq!((p.offset, p.limit), (0, 20));
+
+    assert!(matches!(
+        Pagination::try_from(params(None, None, Some(0))),
+        Err(PaginationError::ZeroOrNegativePage)
+    ));
+    assert!(matches!(
+        Pagination::try_from(params(Some(-1), None, None)),
+        Err(PaginationError::NegativeOffset)
+    ));
+    assert!(matches!(
+        Pagination::try_from(params(None, Some(0), None)),
+        Err(PaginationError::ZeroOrNegativeLimit)
+    ));
+    assert!(matches!(
+        Pagination::try_from(params(None, Some(101), None)),
+        Err(PaginationError::LimitTooLarge)
+    ));
+}
+
+#[test]
+fn aligned_offsets_map_to_whole_pages() {
+    for (offset, page) in [(0, 1), (10, 2), (20, 3)] {
+        assert_eq!(window(offset, 10).page(), page, "{}", offset);
+        assert_eq!(window(offset, 10).total_pages(25), 3, "{}", offset);
+    }
+    assert_eq!(window(0, 10).total_pages(0), 0);
+    assert_eq!(window(0, 10).total_pages(30), 3);
+}
+
+#[test]
+fn unaligned_offset_counts_the_short_page_in_front() {
+    // Items 0-4 are a page of their own, 5-14 and 15-24 follow
+    let p = window(5, 10);
+    assert_eq!(p.page(), 2);
+    assert_eq!(p.total_pages(25), 3);
+
+    // Stepping by the limit ends on the last page
+    let last = window(15, 10);
+    assert_eq!(last.page(), last.total_pages(25));
+
+    // The window holds the last items, there's no page after it
+    let p = window(5, 10);
+    assert_eq!(p.page(), p.total_pages(15));
+}
+
+#[test]
+fn offset_past_the_end_keeps_the_item_based_total() {
+    let p = window(40, 10);
+    assert_eq!(p.page(), 5);
+    assert_eq!(p.total_pages(25), 3);
+}
+
+#[test]
+fn paginated_carries_the_page_metadata() {
+    let page = Paginated::new(vec![1, 2], 12, &window(10, 5));
+
+    assert_eq!(page.page, 3);
+    assert_eq!(page.per_page, 5);
+    assert_eq!(page.total, 12);
+    assert_eq!(page.total_pages, 3);
+}
@@ -0,0 +1,124 @@
+use axum::{
+    extract::{FromRef, FromRequestParts, MatchedPath, Request, State},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+use axum_extra::extract::cookie::Key;
+use factory::factories::database::Database;
+use sqlx::{Executor, Postgres};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::jwt::{Claims, JwtCapability};
+
+/// Row of `audit_log`
+pub struct AuditEntry<'a> {
+    pub user_id: Uuid,
+    pub impersonated_by: Option<Uuid>,
+    pub action: &'a str,
+    pub resource_type: &'a str,
+    pub resource_id: Option<Uuid>,
+}
+
+impl AuditEntry<'_> {
+    #[tracing::instrument("audit_entry.insert", skip_all, err)]
+    pub async fn insert<'e, E>(&self, executor: E) -> Result<(), sqlx::Error>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        sqlx::query!(
+            r#"
+            INSERT INTO audit_log (user_id, impersonated_by, action, resource_type, resource_id)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            self.user_id,
+            self.impersonated_by,
+            self.action,
+            self.resource_type,
+            self.resource_id
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Writes every successful create, update or delete made with an impersonation token to
+/// `audit_log`. The action is the method and route, e.g. `DELETE /api/v1/compute/projects/{id}`
+pub async fn audit_impersonated_writes<S>(
+    State(state): State<S>,
+    req: Request,
+    next: Next,
+) -> Response
+where
+    S: JwtCapability + Clone + Send + Sync + 'static,
+    Key: FromRef<S>,
+    Database: FromRef<S>,
+{
+    if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return next.run(req).await;
+    }
+
+    let (mut parts, body) = req.into_parts();
+    let impersonation = Claims::from_request_parts(&mut parts, &state)
+        .await
+        .ok()
+        .and_then(|claims| {
+            claims
+                .impersonated_by
+                .map(|admin_id| (claims.sub, admin_id))
+        });
+    let Some((user_id, admin_id)) = impersonation else {
+        return next.run(Request::from_parts(parts, body)).await;
+    };
+
+    let route = parts
+        .extensions
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| parts.uri.path().to_string());
+    let action = format!("{} {}", parts.method, route);
+    let (resource_type, resource_id) = resource(&route, parts.uri.path());
+
+    let response = next.run(Request::from_parts(parts, body)).await;
+    if !response.status().is_success() {
+        return response;
+    }
+
+    info!(user_id = %user_id, impersonated_by = %admin_id, action = %action, "🕵️ Impersonated write");
+
+    let entry = AuditEntry {
+        user_id,
+        impersonated_by: Some(admin_id),
+        action: &action,
+        resource_type,
+        resource_id,
+    };
+    // The write already happened, failing the response now would only invite a retry
+    if let Err(e) = entry.insert(&Database::from_ref(&state).pool).await {
+        error!(user_id = %user_id, impersonated_by = %admin_id, action = %action, "❌ Failed to write audit log: {}", e);
+    }
+
+    response
+}
+
+/// The segment before the route's last parameter and that parameter's value, routes without
+/// one like `/api/v1/users/profile` name their last segment
+fn resource<'a>(route: &'a str, path: &str) -> (&'a str, Option<Uuid>) {
+    let route: Vec<&str> = route.split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+
+    let last_param = route
+        .iter()
+        .rposition(|segment| segment.starts_with('{'))
+        .filter(|&index| index > 0);
+    match last_param {
+        Some(index) => (
+            route[index - 1],
+            path.get(index).and_then(|id| id.parse().ok()),
+        ),
+        None => (route.last().copied().unwrap_or_default(), None),
+    }
+}
@@ -20,6 +20,9 @@ pub enum ClaimsError {
 
     #[error("Failed to extract private key from state")]
     KeyError,
+
+    #[error("admin role required")]
+    NotAdmin,
 }
 
 impl IntoResponse for ClaimsError {
@@ -42,6 +45,7 @@ impl IntoResponse for ClaimsError {
                 StatusCode::FORBIDDEN,
                 "Failed to extract private key from state",
             ),
+            Self::NotAdmin => (StatusCode::FORBIDDEN, "Admin role required"),
         };
         (status, Json(json!({ "error": msg }))).into_response()
     }
@@ -1,6 +1,7 @@
 use aide::{
     generate::GenContext,
-    openapi::{Operation, SecurityRequirement},
+    openapi::{ApiKeyLocation, Operation, SecurityRequirement, SecurityScheme},
+    transform::TransformOpenApi,
 };
 use axum::{
     RequestPartsExt,
@@ -10,7 +11,7 @@ use axum::{
 
 use crate::{
     error::ClaimsError,
-    jwt::{Claims, JwtCapability, TokenType, verify_token},
+    jwt::{ADMIN_ROLE, AdminClaims, Claims, JwtCapability, TokenType, verify_token},
 };
 use axum_extra::{
     TypedHeader,
@@ -20,13 +21,42 @@ use axum_extra::{
 
 impl aide::OperationInput for Claims {
     fn operation_input(_ctx: &mut GenContext, operation: &mut Operation) {
-        operation.security.push(SecurityRequirement::from_iter([(
-            "bearerAuth".to_string(),
-            Vec::new(),
-        )]));
+        // Either one is enough
+        for scheme in [BEARER_AUTH, COOKIE_AUTH] {
+            operation.security.push(SecurityRequirement::from_iter([(
+                scheme.to_string(),
+                Vec::new(),
+            )]));
+        }
     }
 }
 
+const BEARER_AUTH: &str = "bearerAuth";
+const COOKIE_AUTH: &str = "cookieAuth";
+
+/// Declares the schemes operations taking `Claims` require, the access token either as a
+/// bearer token or as the `access_token` cookie the web app gets
+pub fn auth_security_schemes(api: TransformOpenApi<'_>) -> TransformOpenApi<'_> {
+    api.security_scheme(
+        BEARER_AUTH,
+        SecurityScheme::Http {
+            scheme: "bearer".to_string(),
+            bearer_format: Some("JWT".to_string()),
+            description: None,
+            extensions: Default::default(),
+        },
+    )
+    .security_scheme(
+        COOKIE_AUTH,
+        SecurityScheme::ApiKey {
+            location: ApiKeyLocation::Cookie,
+            name: "access_token".to_string(),
+            description: None,
+            extensions: Default::default(),
+        },
+    )
+}
+
 // Option A: State itself implements JwtCapability and can provide a Key
 impl<S> FromRequestParts<S> for Claims
 where
@@ -109,6 +139,31 @@ where
     }
 }
 
+impl aide::OperationInput for AdminClaims {
+    fn operation_input(ctx: &mut GenContext, operation: &mut Operation) {
+        Claims::operation_input(ctx, operation);
+    }
+}
+
+impl<S> FromRequestParts<S> for AdminClaims
+where
+    S: Send + Sync + JwtCapability,
+    Key: FromRef<S>,
+{
+    type Rejection = ClaimsError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let claims = Claims::from_request_parts(parts, state).await?;
+
+        // A demoted admin keeps access until the current access token expires
+        if claims.role.as_deref() != Some(ADMIN_ROLE) {
+            return Err(ClaimsError::NotAdmin);
+        }
+
+        Ok(AdminClaims(claims))
+    }
+}
+
 // Option B: State can produce a JwtCapability via FromRef
 // Box<dyn JwtCapability>: FromRef<S> means “For this implementation to exist, Box<dyn JwtCapability> must be constructible from &S.”
 // impl<S> FromRequestParts<S> for Claims
@@ -12,6 +12,9 @@ pub enum TokenType {
     Refresh,
     EmailVerification,
     PasswordSetup,
+    EmailChange,
+    PasswordReset,
+    TwoFactor,
 }
 use schemars::JsonSchema;
 
@@ -21,8 +24,29 @@ pub struct Claims {
     pub typ: TokenType,
     pub exp: i64,
     pub iat: i64,
+    /// Keeps tokens minted for the same user within the same second distinct
+    #[serde(default)]
+    pub jti: Uuid,
+    /// Login family the token was issued to, only set on access tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<Uuid>,
+    /// Role of the user when the token was issued, only set on access tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    /// Admin acting as `sub`, only set on impersonation tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonated_by: Option<Uuid>,
 }
 
+/// Role that `AdminClaims` requires
+pub const ADMIN_ROLE: &str = "admin";
+
+/// Impersonation tokens can't be refreshed, support gets a new one once this runs out
+pub const IMPERSONATION_TOKEN_EXPIRE_IN_MINUTES: i64 = 60;
+
+/// Access token claims of a user who was an admin when the token was issued
+pub struct AdminClaims(pub Claims);
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct JwtConfig {
     pub secret_key: String,
@@ -39,6 +63,15 @@ pub trait JwtCapability {
     fn refresh_token_expire_in_days(&self) -> i64;
     fn email_verification_token_expire_in_hours(&self) -> i64;
     fn password_setup_token_expire_in_minutes(&self) -> i64;
+    fn email_change_token_expire_in_minutes(&self) -> i64 {
+        30
+    }
+    fn password_reset_token_expire_in_minutes(&self) -> i64 {
+        15
+    }
+    fn two_factor_token_expire_in_minutes(&self) -> i64 {
+        5
+    }
 }
 
 #[tracing::instrument(name = "create_token", skip_all, fields(user_id = %user_id), err)]
@@ -46,6 +79,33 @@ pub fn create_token<C: JwtCapability + ?Sized>(
     cfg: &C,
     user_id: Uuid,
     typ: TokenType,
+) -> Result<String, ClaimsError> {
+    encode_token(cfg, user_id, typ, None, None)
+}
+
+/// Access token that remembers which session minted it
+#[tracing::instrument(name = "create_session_token", skip_all, fields(user_id = %user_id, session_id = %session_id), err)]
+pub fn create_session_token<C: JwtCapability + ?Sized>(
+    cfg: &C,
+    user_id: Uuid,
+    session_id: Uuid,
+    role: &str,
+) -> Result<String, ClaimsError> {
+    encode_token(
+        cfg,
+        user_id,
+        TokenType::Access,
+        Some(session_id),
+        Some(role.to_string()),
+    )
+}
+
+fn encode_token<C: JwtCapability + ?Sized>(
+    cfg: &C,
+    user_id: Uuid,
+    typ: TokenType,
+    sid: Option<Uuid>,
+    role: Option<String>,
 ) -> Result<String, ClaimsError> {
     let now = Utc::now();
 
@@ -59,6 +119,11 @@ pub fn create_token<C: JwtCapability + ?Sized>(
             TokenType::PasswordSetup => {
                 Duration::hours(cfg.password_setup_token_expire_in_minutes())
             }
+            TokenType::EmailChange => Duration::minutes(cfg.email_change_token_expire_in_minutes()),
+            TokenType::PasswordReset => {
+                Duration::minutes(cfg.password_reset_token_expire_in_minutes())
+            }
+            TokenType::TwoFactor => Duration::minutes(cfg.two_factor_token_expire_in_minutes()),
         };
 
     let claims = Claims {
@@ -66,6 +131,37 @@ pub fn create_token<C: JwtCapability + ?Sized>(
         typ,
         iat: now.timestamp(),
         exp: exp.timestamp(),
+        jti: Uuid::new_v4(),
+        sid,
+        role,
+        impersonated_by: None,
+    };
+
+    let encoding_key = EncodingKey::from_secret(cfg.jwt_secret().as_bytes());
+    encode(&Header::new(Algorithm::HS256), &claims, &encoding_key)
+        .map_err(|_| ClaimsError::Creation)
+}
+
+/// Access token `admin_id` acts as `user_id` with. It belongs to no session and carries no
+/// role, so there is nothing to refresh it with and it never passes `AdminClaims`
+#[tracing::instrument(name = "create_impersonation_token", skip_all, fields(user_id = %user_id, admin_id = %admin_id), err)]
+pub fn create_impersonation_token<C: JwtCapability + ?Sized>(
+    cfg: &C,
+    user_id: Uuid,
+    admin_id: Uuid,
+) -> Result<String, ClaimsError> {
+    let now = Utc::now();
+    let exp = now + Duration::minutes(IMPERSONATION_TOKEN_EXPIRE_IN_MINUTES);
+
+    let claims = Claims {
+        sub: user_id,
+        typ: TokenType::Access,
+        iat: now.timestamp(),
+        exp: exp.timestamp(),
+        jti: Uuid::new_v4(),
+        sid: None,
+        role: None,
+        impersonated_by: Some(admin_id),
     };
 
     let encoding_key = EncodingKey::from_secret(cfg.jwt_secret().as_bytes());
@@ -83,3 +179,52 @@ pub fn verify_token<C: JwtCapability + ?Sized>(
         .map(|d| d.claims)
         .map_err(|_| ClaimsError::Invalid)
 }
+
+/// Carries the address being switched to, it is only written to the user once the link is opened
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EmailChangeClaims {
+    pub sub: Uuid,
+    pub typ: TokenType,
+    pub new_email: String,
+    pub exp: i64,
+    pub iat: i64,
+}
+
+#[tracing::instrument(name = "create_email_change_token", skip_all, fields(user_id = %user_id), err)]
+pub fn create_email_change_token<C: JwtCapability + ?Sized>(
+    cfg: &C,
+    user_id: Uuid,
+    new_email: &str,
+) -> Result<String, ClaimsError> {
+    let now = Utc::now();
+    let exp = now + Duration::minutes(cfg.email_change_token_expire_in_minutes());
+
+    let claims = EmailChangeClaims {
+        sub: user_id,
+        typ: TokenType::EmailChange,
+        new_email: new_email.to_string(),
+        iat: now.timestamp(),
+        exp: exp.timestamp(),
+    };
+
+    let encoding_key = EncodingKey::from_secret(cfg.jwt_secret().as_bytes());
+    encode(&Header::new(Algorithm::HS256), &claims, &encoding_key)
+        .map_err(|_| ClaimsError::Creation)
+}
+
+#[tracing::instrument(name = "verify_email_change_token", skip_all, err)]
+pub fn verify_email_change_token<C: JwtCapability + ?Sized>(
+    cfg: &C,
+    token: &str,
+) -> Result<EmailChangeClaims, ClaimsError> {
+    let decoding_key = DecodingKey::from_secret(cfg.jwt_secret().as_bytes());
+    let claims = decode::<EmailChangeClaims>(token, &decoding_key, &Validation::default())
+        .map(|d| d.claims)
+        .map_err(|_| ClaimsError::Invalid)?;
+
+    if claims.typ != TokenType::EmailChange {
+        return Err(ClaimsError::WrongType);
+    }
+
+    Ok(claims)
+}
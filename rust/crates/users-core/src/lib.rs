@@ -1,3 +1,4 @@
+pub mod audit;
 pub mod error;
 pub mod implementation;
 pub mod jwt;
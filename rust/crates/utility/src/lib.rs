@@ -3,4 +3,5 @@ pub mod get_config_value_fromstr;
 pub mod get_optional_config_value;
 pub mod get_optional_config_value_fromstr;
 mod parse_value;
+pub mod retry;
 pub mod shutdown_signal;
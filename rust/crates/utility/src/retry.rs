@@ -0,0 +1,52 @@
+use std::{fmt::Display, time::Duration};
+
+use tracing::warn;
+
+/// Spacing of the attempts `retry_with_backoff` makes, the delay doubles after every failure
+#[derive(Clone, Copy, Debug)]
+pub struct RetryConfig {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Runs `operation` until it succeeds or the attempts run out, the last error is returned.
+/// `what` only names the operation in the logs
+pub async fn retry_with_backoff<T, E, F, Fut>(
+    what: &str,
+    cfg: &RetryConfig,
+    mut operation: F,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: Display,
+{
+    let mut backoff = cfg.initial_backoff;
+    let mut attempt = 1;
+    loop {
+        match operation().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < cfg.max_attempts => {
+                warn!(
+                    "⚠️ {} attempt {} failed, retrying in {:?}: {}",
+                    what, attempt, backoff, e
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(cfg.max_backoff);
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::{cell::Cell, time::Duration};
+
+use tokio::time::Instant;
+use utility::retry::{RetryConfig, retry_with_backoff};
+
+fn config(max_attempts: u32) -> RetryConfig {
+    RetryConfig {
+        max_attempts,
+        initial_backoff: Duration::from_secs(1),
+        max_backoff: Duration::from_secs(5),
+    }
+}
+
+/// Fails the first `failures` calls, the error names the attempt
+async fn flaky(calls: &Cell<u32>, failures: u32) -> Result<u32, String> {
+    calls.set(calls.get() + 1);
+    if calls.get() <= failures {
+        Err(format!("attempt {}", calls.get()))
+    } else {
+        Ok(calls.get())
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn first_success_is_returned_without_waiting() {
+    let calls = Cell::new(0);
+    let start = Instant::now();
+
+    let result = retry_with_backoff("test", &config(3), || flaky(&calls, 0)).await;
+
+    assert_eq!(result, Ok(1));
+    assert_eq!(start.elapsed(), Duration::ZERO);
+}
+
+#[tokio::test(start_paused = true)]
+async fn failures_are_retried_with_a_doubling_delay() {
+    let calls = Cell::new(0);
+    let start = Instant::now();
+
+    let result = retry_with_backoff("test", &config(5), || flaky(&calls, 2)).await;
+
+    assert_eq!(result, Ok(3));
+    // 1s after the first failure, 2s after the second
+    assert_eq!(start.elapsed(), Duration::from_secs(3));
+}
+
+#[tokio::test(start_paused = true)]
+async fn last_error_is_returned_once_the_attempts_run_out() {
+    let calls = Cell::new(0);
+    let start = Instant::now();
+
+    let result = retry_with_backoff("test", &config(3), || flaky(&calls, u32::MAX)).await;
+
+    assert_eq!(result, Err("attempt 3".to_string()));
+    assert_eq!(calls.get(), 3);
+    // No sleep after the last attempt
+    assert_eq!(start.elapsed(), Duration::from_secs(3));
+}
+
+#[tokio::test(start_paused = true)]
+async fn delay_stops_growing_at_the_max_backoff() {
+    let calls = Cell::new(0);
+    let start = Instant::now();
+
+    let result = retry_with_backoff("test", &config(6), || flaky(&calls, u32::MAX)).await;
+
+    assert!(result.is_err());
+    // 1s, 2s, 4s, then capped at 5s twice
+    assert_eq!(start.elapsed(), Duration::from_secs(17));
+}
+
+#[tokio::test(start_paused = true)]
+async fn single_attempt_never_retries() {
+    let calls = Cell::new(0);
+
+    let result = retry_with_backoff("test", &config(1), || flaky(&calls, 1)).await;
+
+    assert_eq!(result, Err("attempt 1".to_string()));
+    assert_eq!(calls.get(), 1);
+}
@@ -0,0 +1,19 @@
+-- ==============================================
+-- BILLING TIERS (per-user discounts on preset pricing)
+-- ==============================================
+CREATE TABLE IF NOT EXISTS billing_tiers (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4 (),
+    name VARCHAR(50) NOT NULL UNIQUE,
+    discount_percent NUMERIC(5, 2) NOT NULL DEFAULT 0 CHECK (
+        discount_percent >= 0
+        AND discount_percent <= 100
+    ),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TRIGGER set_billing_tiers_timestamp BEFORE UPDATE ON billing_tiers FOR EACH ROW EXECUTE PROCEDURE trigger_set_timestamp();
+
+-- Users without a tier pay the list price
+ALTER TABLE users
+ADD COLUMN IF NOT EXISTS billing_tier_id UUID REFERENCES billing_tiers (id) ON DELETE SET NULL;
@@ -0,0 +1,8 @@
+-- ==============================================
+-- DEPLOYMENT EVENT STATUS TRANSITIONS
+-- ==============================================
+ALTER TABLE deployment_events
+ADD COLUMN IF NOT EXISTS from_status deployment_status,
+ADD COLUMN IF NOT EXISTS to_status deployment_status;
+
+CREATE INDEX IF NOT EXISTS idx_deployment_events_deployment_created ON deployment_events (deployment_id, created_at DESC);
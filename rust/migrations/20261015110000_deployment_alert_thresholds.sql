@@ -0,0 +1,6 @@
+-- ==============================================
+-- DEPLOYMENT USAGE ALERTS
+-- ==============================================
+-- { "cpuPercent": 90, "memoryPercent": 90, "windowMinutes": 5 }, NULL disables alerts
+ALTER TABLE deployments
+ADD COLUMN IF NOT EXISTS alert_thresholds JSONB;
@@ -0,0 +1,9 @@
+-- ==============================================
+-- BILLING PERIODS (one charge per deployment per hour)
+-- ==============================================
+ALTER TABLE billings
+ADD COLUMN IF NOT EXISTS billing_period TIMESTAMPTZ;
+
+-- Re-running the worker for an already billed hour must be a no-op
+ALTER TABLE billings
+ADD CONSTRAINT billings_deployment_period_unique UNIQUE (deployment_id, billing_period);
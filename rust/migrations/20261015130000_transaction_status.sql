@@ -0,0 +1,33 @@
+-- ==============================================
+-- TRANSACTION STATUS (card top-ups settle asynchronously)
+-- ==============================================
+DO $$ BEGIN CREATE TYPE transaction_status AS ENUM ('pending', 'completed', 'failed');
+EXCEPTION
+WHEN duplicate_object THEN NULL;
+END $$;
+
+ALTER TABLE transactions
+ADD COLUMN IF NOT EXISTS status transaction_status NOT NULL DEFAULT 'completed';
+
+-- Pending top-ups are credited by the payment webhook once the provider confirms them
+CREATE OR REPLACE FUNCTION on_transaction_insert_deduct_balance() RETURNS TRIGGER AS $$
+DECLARE 
+    current_balance NUMERIC(18, 6);
+    new_balance NUMERIC(18, 6);
+BEGIN
+    IF NEW.status != 'completed' THEN
+        RETURN NEW;
+    END IF;
+
+    SELECT amount INTO current_balance FROM balances WHERE id = NEW.balance_id FOR UPDATE;
+    
+    new_balance := (current_balance + NEW.amount);
+    
+    IF new_balance < 0 AND NEW.type != 'usage_charge' THEN 
+        RAISE EXCEPTION 'Insufficient funds. Transaction aborted.';
+    END IF;
+
+    UPDATE balances SET amount = new_balance, updated_at = NOW() WHERE id = NEW.balance_id;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
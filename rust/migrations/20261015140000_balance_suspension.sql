@@ -0,0 +1,20 @@
+-- ==============================================
+-- BALANCE SUSPENSION (resumed automatically after a top-up)
+-- ==============================================
+ALTER TABLE deployments
+ADD COLUMN IF NOT EXISTS suspended_for_balance BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Leaving the suspended state any other way (manual resume, deletion) drops the flag
+CREATE OR REPLACE FUNCTION trigger_clear_balance_suspension() RETURNS TRIGGER AS $$ BEGIN
+IF NEW.status != 'suspended' THEN
+    NEW.suspended_for_balance = FALSE;
+END IF;
+RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS clear_deployments_balance_suspension ON deployments;
+
+CREATE TRIGGER clear_deployments_balance_suspension BEFORE UPDATE OF status ON deployments FOR EACH ROW EXECUTE PROCEDURE trigger_clear_balance_suspension();
+
+CREATE INDEX IF NOT EXISTS idx_deployments_suspended_for_balance ON deployments (user_id) WHERE suspended_for_balance;
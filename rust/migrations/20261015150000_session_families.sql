@@ -0,0 +1,10 @@
+-- ==============================================
+-- SESSION FAMILIES (refresh token rotation)
+-- ==============================================
+-- Every refresh rotates the token into a new row of the same family, the previous row is revoked.
+-- Presenting a revoked token again means it leaked, so the whole family gets revoked.
+ALTER TABLE sessions
+ADD COLUMN IF NOT EXISTS family_id UUID NOT NULL DEFAULT uuid_generate_v4 (),
+ADD COLUMN IF NOT EXISTS revoked_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_sessions_family_id ON sessions (family_id);
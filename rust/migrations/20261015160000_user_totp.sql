@@ -0,0 +1,25 @@
+-- ==============================================
+-- TWO-FACTOR AUTHENTICATION (TOTP)
+-- ==============================================
+-- The secret is stored AES-GCM encrypted, it only becomes active once a first code was verified.
+-- last_used_step keeps an accepted code from being replayed within its time window.
+CREATE TABLE IF NOT EXISTS user_totp_secrets (
+    user_id UUID PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    secret_ciphertext TEXT NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT FALSE,
+    last_used_step BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TRIGGER set_user_totp_secrets_timestamp BEFORE UPDATE ON user_totp_secrets FOR EACH ROW EXECUTE PROCEDURE trigger_set_timestamp();
+
+-- One-time recovery codes, only their SHA-256 is kept
+CREATE TABLE IF NOT EXISTS user_totp_backup_codes (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4 (),
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    code_hash VARCHAR(64) NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (user_id, code_hash)
+);
@@ -0,0 +1,13 @@
+-- ==============================================
+-- GITLAB CONNECTIONS
+-- ==============================================
+-- OAuth tokens live in Vault under gitlab/users/{user_id}, only the webhook token's SHA-256 is kept here.
+-- GitLab sends the token back verbatim in X-Gitlab-Token, the hash tells whose push it is.
+CREATE TABLE IF NOT EXISTS gitlab_connections (
+    user_id UUID PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    webhook_token_hash VARCHAR(64) NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TRIGGER set_gitlab_connections_timestamp BEFORE UPDATE ON gitlab_connections FOR EACH ROW EXECUTE PROCEDURE trigger_set_timestamp();
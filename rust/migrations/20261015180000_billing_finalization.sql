@@ -0,0 +1,33 @@
+-- ==============================================
+-- BILLING FINALIZATION
+-- ==============================================
+-- set_billing_timestamp has been writing updated_at all along, the column was never there
+ALTER TABLE billings
+ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP;
+
+-- Set once the deployment is deleted, no more charges are expected after it
+ALTER TABLE billings
+ADD COLUMN IF NOT EXISTS finalized_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_billings_open ON billings (deployment_id) WHERE finalized_at IS NULL;
+
+-- ==============================================
+-- DEPLOYMENT CHARGES (fed by the billing.charged topic)
+-- ==============================================
+CREATE TABLE IF NOT EXISTS deployment_charges (
+    deployment_id UUID PRIMARY KEY REFERENCES deployments (id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    total_amount NUMERIC(18, 6) NOT NULL DEFAULT 0,
+    currency CHAR(3) NOT NULL DEFAULT 'UZS',
+    charge_count INTEGER NOT NULL DEFAULT 0,
+    last_billing_id UUID NOT NULL,
+    -- Periods are charged in order, an event for an older one is a redelivery
+    last_billing_period TIMESTAMPTZ NOT NULL,
+    finalized_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_deployment_charges_user_id ON deployment_charges (user_id);
+
+CREATE TRIGGER set_deployment_charges_timestamp BEFORE UPDATE ON deployment_charges FOR EACH ROW EXECUTE PROCEDURE trigger_set_timestamp();
@@ -0,0 +1,15 @@
+-- ==============================================
+-- DEPLOYMENT METRICS (copied from Redis by compute-metrics-worker)
+-- ==============================================
+-- Redis only keeps the last few snapshots, history is served from here
+CREATE TABLE IF NOT EXISTS deployment_metrics (
+    deployment_id UUID NOT NULL REFERENCES deployments (id) ON DELETE CASCADE,
+    ts TIMESTAMPTZ NOT NULL,
+    cpu DOUBLE PRECISION NOT NULL,
+    memory DOUBLE PRECISION NOT NULL,
+    -- The same Redis list is copied over and over, the key makes that idempotent
+    PRIMARY KEY (deployment_id, ts)
+);
+
+-- Rows arrive in time order, a BRIN index stays tiny however large the table grows
+CREATE INDEX IF NOT EXISTS idx_deployment_metrics_ts ON deployment_metrics USING BRIN (ts);
@@ -0,0 +1,12 @@
+-- ==============================================
+-- IDEMPOTENCY KEYS (compute-api create deployment)
+-- ==============================================
+-- key_hash is sha256 of `{user_id}:{Idempotency-Key}`, so keys of different users never collide
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    key_hash TEXT PRIMARY KEY,
+    response_body JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- A partial index can't compare against NOW(), the cleanup job range scans this instead
+CREATE INDEX IF NOT EXISTS idx_idempotency_keys_created_at ON idempotency_keys (created_at);
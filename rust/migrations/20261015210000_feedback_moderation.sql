@@ -0,0 +1,13 @@
+-- ==============================================
+-- FEEDBACK MODERATION
+-- ==============================================
+DO $$ BEGIN CREATE TYPE feedback_status AS ENUM ('pending', 'reviewed', 'actioned');
+EXCEPTION
+WHEN duplicate_object THEN NULL;
+END $$;
+
+ALTER TABLE feedbacks
+ADD COLUMN IF NOT EXISTS status feedback_status NOT NULL DEFAULT 'pending';
+
+-- The moderation queue is read newest first, usually filtered to pending
+CREATE INDEX IF NOT EXISTS idx_feedbacks_status_created_at ON feedbacks (status, created_at DESC);
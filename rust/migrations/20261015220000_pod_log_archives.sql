@@ -0,0 +1,14 @@
+-- ==============================================
+-- POD LOG ARCHIVES (written by compute-reconciler)
+-- ==============================================
+-- Pod logs disappear with the pod, the tail is gzipped into object storage before that
+CREATE TABLE IF NOT EXISTS pod_log_archives (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid (),
+    deployment_id UUID NOT NULL REFERENCES deployments (id) ON DELETE CASCADE,
+    pod_uid TEXT NOT NULL,
+    -- logs/{deployment_id}/{pod_uid}/{timestamp}.log.gz
+    object_path TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_pod_log_archives_pod ON pod_log_archives (deployment_id, pod_uid, created_at DESC);
@@ -0,0 +1,18 @@
+-- ==============================================
+-- SCALING RECOMMENDATIONS (written by compute-metrics-worker)
+-- ==============================================
+DO $$ BEGIN
+    CREATE TYPE scaling_action AS ENUM ('scale_up', 'scale_down');
+EXCEPTION WHEN duplicate_object THEN NULL; END $$;
+
+CREATE TABLE IF NOT EXISTS scaling_recommendations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid (),
+    deployment_id UUID NOT NULL REFERENCES deployments (id) ON DELETE CASCADE,
+    action scaling_action NOT NULL,
+    reason TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Set once the user scales the deployment as recommended
+    acted_on BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX IF NOT EXISTS idx_scaling_recommendations_deployment ON scaling_recommendations (deployment_id, created_at DESC);
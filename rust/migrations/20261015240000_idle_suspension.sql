@@ -0,0 +1,7 @@
+-- ==============================================
+-- IDLE SUSPENSION
+-- ==============================================
+-- NULL never suspends, otherwise compute-reconciler suspends the deployment after this many
+-- minutes without HTTP requests and the next request wakes it up again
+ALTER TABLE deployments
+ADD COLUMN IF NOT EXISTS suspend_after_idle_minutes INTEGER;
@@ -0,0 +1,7 @@
+-- ==============================================
+-- PROJECT ENVIRONMENT VARIABLES
+-- ==============================================
+-- Inherited by every deployment of the project, a deployment's own environment_variables win
+-- on conflicting keys
+ALTER TABLE projects
+ADD COLUMN IF NOT EXISTS project_env_vars JSONB;
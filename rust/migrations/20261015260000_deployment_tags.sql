@@ -0,0 +1,9 @@
+-- ==============================================
+-- DEPLOYMENT TAGS
+-- ==============================================
+-- User defined key/value pairs for grouping deployments, also applied as labels of the K8s
+-- Deployment. GIN backs the `tags @> $filter` containment lookups of the deployment list
+ALTER TABLE deployments
+ADD COLUMN IF NOT EXISTS tags JSONB;
+
+CREATE INDEX IF NOT EXISTS idx_deployments_tags ON deployments USING GIN (tags);
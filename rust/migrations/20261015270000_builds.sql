@@ -0,0 +1,23 @@
+-- ==============================================
+-- BUILDS (written by compute-reconciler)
+-- ==============================================
+DO $$ BEGIN
+    CREATE TYPE build_type AS ENUM ('kpack', 'buildkit');
+EXCEPTION WHEN duplicate_object THEN NULL; END $$;
+
+DO $$ BEGIN
+    CREATE TYPE build_status AS ENUM ('running', 'succeeded', 'failed');
+EXCEPTION WHEN duplicate_object THEN NULL; END $$;
+
+-- One row per build of a source deployment, `id` is the `poddle.io/build-id` label of its Job
+CREATE TABLE IF NOT EXISTS builds (
+    id UUID PRIMARY KEY,
+    deployment_id UUID NOT NULL REFERENCES deployments (id) ON DELETE CASCADE,
+    build_type build_type NOT NULL,
+    status build_status NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL,
+    finished_at TIMESTAMPTZ,
+    image TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_builds_deployment ON builds (deployment_id, started_at DESC);
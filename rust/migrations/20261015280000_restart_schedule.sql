@@ -0,0 +1,7 @@
+-- ==============================================
+-- SCHEDULED RESTARTS
+-- ==============================================
+-- Five field cron expression in UTC, compute-reconciler rolls the deployment's pods every time
+-- it matches. NULL never restarts
+ALTER TABLE deployments
+ADD COLUMN IF NOT EXISTS restart_schedule TEXT;
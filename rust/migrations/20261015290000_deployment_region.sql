@@ -0,0 +1,9 @@
+-- ==============================================
+-- DEPLOYMENT REGIONS
+-- ==============================================
+-- Name of the cluster the deployment runs in, one of the regions the compute services are
+-- configured with. NULL is the default region, where every deployment ran before regions existed
+ALTER TABLE deployments
+ADD COLUMN IF NOT EXISTS region TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_deployments_region ON deployments(region);
@@ -0,0 +1,22 @@
+-- ==============================================
+-- NOTIFICATION CHANNELS
+-- ==============================================
+DO $$ BEGIN
+    CREATE TYPE notification_channel_type AS ENUM ('slack', 'discord');
+EXCEPTION WHEN duplicate_object THEN NULL; END $$;
+
+-- Incoming webhooks compute-reconciler posts a user's deployment events to. `events` holds the
+-- subscribed event names, e.g. `deployment_unhealthy` or `build_failed`
+CREATE TABLE IF NOT EXISTS notification_channels (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    type notification_channel_type NOT NULL,
+    webhook_url TEXT NOT NULL,
+    events TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TRIGGER set_notification_channels_timestamp BEFORE UPDATE ON notification_channels FOR EACH ROW EXECUTE PROCEDURE trigger_set_timestamp();
+
+CREATE INDEX IF NOT EXISTS idx_notification_channels_user ON notification_channels (user_id);
@@ -0,0 +1,20 @@
+-- ==============================================
+-- AUDIT LOG
+-- ==============================================
+-- Writes made on behalf of `user_id`. `impersonated_by` is the admin holding an impersonation
+-- token, `resource_id` is empty for routes without an id in their path
+CREATE TABLE IF NOT EXISTS audit_log (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    impersonated_by UUID REFERENCES users (id) ON DELETE SET NULL,
+    action TEXT NOT NULL,
+    resource_type TEXT NOT NULL,
+    resource_id UUID,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_user ON audit_log (user_id, created_at DESC);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_impersonated_by ON audit_log (impersonated_by, created_at DESC)
+WHERE
+    impersonated_by IS NOT NULL;
@@ -0,0 +1,36 @@
+-- ==============================================
+-- PROMO CODES (discount on the next billing month)
+-- ==============================================
+CREATE TABLE IF NOT EXISTS promo_codes (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4 (),
+    code TEXT NOT NULL UNIQUE,
+    discount_percent INT NOT NULL CHECK (
+        discount_percent > 0
+        AND discount_percent <= 100
+    ),
+    max_uses INT NOT NULL CHECK (max_uses > 0),
+    -- The check is the last line of defence against overselling a code
+    uses_so_far INT NOT NULL DEFAULT 0 CHECK (
+        uses_so_far >= 0
+        AND uses_so_far <= max_uses
+    ),
+    expires_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- A user redeems a code once
+CREATE TABLE IF NOT EXISTS promo_code_redemptions (
+    promo_code_id UUID NOT NULL REFERENCES promo_codes (id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    redeemed_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (promo_code_id, user_id)
+);
+
+-- Charges of billing periods in [discount_starts_at, discount_ends_at) get the discount on top of the tier's
+ALTER TABLE balances
+ADD COLUMN IF NOT EXISTS discount_percent INT NOT NULL DEFAULT 0 CHECK (
+    discount_percent >= 0
+    AND discount_percent <= 100
+),
+ADD COLUMN IF NOT EXISTS discount_starts_at TIMESTAMPTZ,
+ADD COLUMN IF NOT EXISTS discount_ends_at TIMESTAMPTZ;
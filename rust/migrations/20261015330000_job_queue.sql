@@ -0,0 +1,28 @@
+-- ==============================================
+-- JOB QUEUE (written by compute-api, run by compute-provisioner)
+-- ==============================================
+DO $$ BEGIN
+    CREATE TYPE job_type AS ENUM ('build', 'rebuild');
+EXCEPTION WHEN duplicate_object THEN NULL; END $$;
+
+DO $$ BEGIN
+    CREATE TYPE job_status AS ENUM ('pending', 'running', 'done', 'failed');
+EXCEPTION WHEN duplicate_object THEN NULL; END $$;
+
+-- Builds are written in the transaction that triggers them, so they survive a broker restart.
+-- A running job's `next_attempt_at` is its lease, a runner that dies leaves it to be claimed again
+CREATE TABLE IF NOT EXISTS job_queue (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4 (),
+    job_type job_type NOT NULL,
+    payload JSONB NOT NULL,
+    status job_status NOT NULL DEFAULT 'pending',
+    attempts INT NOT NULL DEFAULT 0 CHECK (attempts >= 0),
+    max_attempts INT NOT NULL DEFAULT 5 CHECK (max_attempts > 0),
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_queue_due ON job_queue (next_attempt_at)
+WHERE
+    status IN ('pending', 'running');
@@ -0,0 +1,7 @@
+-- ==============================================
+-- BUILD TIMEOUT
+-- ==============================================
+-- compute-reconciler cancels a build Job still running this many seconds after it started and
+-- fails the deployment. NULL falls back to 900
+ALTER TABLE deployments
+ADD COLUMN IF NOT EXISTS build_timeout_seconds INT CHECK (build_timeout_seconds > 0);
@@ -0,0 +1,15 @@
+-- ==============================================
+-- DEPLOYMENT TYPES
+-- ==============================================
+DO $$ BEGIN
+    CREATE TYPE deployment_type AS ENUM ('service', 'cron_job');
+EXCEPTION WHEN duplicate_object THEN NULL; END $$;
+
+-- What the provisioner created for the deployment, a Deployment with its Service and route or a
+-- CronJob. The reconciler, updates, suspends and scheduled restarts act on whichever it is
+ALTER TABLE deployments
+ADD COLUMN IF NOT EXISTS deployment_type deployment_type NOT NULL DEFAULT 'service';
+
+-- Five field cron expression in UTC a `cron_job` runs on, NULL for services
+ALTER TABLE deployments
+ADD COLUMN IF NOT EXISTS schedule TEXT;
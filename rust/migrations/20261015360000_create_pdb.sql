@@ -0,0 +1,7 @@
+-- ==============================================
+-- POD DISRUPTION BUDGETS
+-- ==============================================
+-- Whether the deployment keeps a PodDisruptionBudget. The provisioner only applies it while
+-- there's more than one replica, updates that scale across that line create or delete it
+ALTER TABLE deployments
+ADD COLUMN IF NOT EXISTS create_pdb BOOLEAN NOT NULL DEFAULT TRUE;
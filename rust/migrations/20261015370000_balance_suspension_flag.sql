@@ -0,0 +1,13 @@
+-- ==============================================
+-- BALANCE SUSPENSION FLAG (outlives the status writes around a suspension)
+-- ==============================================
+-- The billing worker only sets the flag, the provisioner writes 'suspended' once it scales the
+-- deployment down and the reconciler may rewrite the status in between. Only leaving the
+-- suspended state (a resume) or deletion drops the flag
+CREATE OR REPLACE FUNCTION trigger_clear_balance_suspension() RETURNS TRIGGER AS $$ BEGIN
+IF (OLD.status = 'suspended' AND NEW.status != 'suspended') OR NEW.status = 'deleted' THEN
+    NEW.suspended_for_balance = FALSE;
+END IF;
+RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
@@ -13,12 +13,17 @@ use axum::{
     Extension, Json, Router,
     extract::DefaultBodyLimit,
     http::{HeaderName, HeaderValue, Method, header},
+    middleware,
 };
 use http_common::{
-    router::base_routes,
+    body_limit::StreamingBodyLimitLayer,
+    csrf::{CSRF_HEADER, CsrfLayer},
+    metrics::MetricsLayer,
+    router::{base_routes, metrics_routes},
     trace_layer::{custom_make_span::CustomMakeSpan, custom_on_response::CustomOnResponse},
 };
 use tower_http::{cors::CorsLayer, trace::TraceLayer};
+use users_core::{audit::audit_impersonated_writes, implementation::auth_security_schemes};
 
 use crate::{features, utilities::app_state::AppState};
 
@@ -28,6 +33,7 @@ pub async fn app(
     cfg: &Config,
 ) -> Result<Router, AppError> {
     let app_state = AppState::init(&cfg).await?;
+    let metrics = app_state.metrics.clone();
 
     let cors = CorsLayer::new()
         .allow_origin([
@@ -50,8 +56,11 @@ pub async fn app(
             header::CONTENT_TYPE,
             header::ACCEPT,
             HeaderName::from_static("x-requested-with"),
+            HeaderName::from_static(CSRF_HEADER),
         ]);
 
+    let csrf = CsrfLayer::new(&cfg.csrf_key).secure(cfg.cookie_secure);
+
     let tracer_layer = TraceLayer::new_for_http()
         .make_span_with(CustomMakeSpan)
         .on_response(CustomOnResponse)
@@ -83,12 +92,21 @@ pub async fn app(
             Swagger::new("/api/v1/billing/api.json").axum_route(),
         )
         .route("/api/v1/billing/api.json", get(serve_api))
-        .finish_api(&mut api)
+        .finish_api_with(&mut api, auth_security_schemes)
         .layer(Extension(Arc::new(api)))
-        .layer(DefaultBodyLimit::max(50 * 1024 * 1024))
+        .layer(DefaultBodyLimit::disable())
+        .layer(StreamingBodyLimitLayer::new(50 * 1024 * 1024))
+        .layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            audit_impersonated_writes::<AppState>,
+        ))
         .with_state(app_state)
+        .layer(MetricsLayer::new(metrics.clone()))
         .layer(tracer_layer)
-        .layer(cors);
+        // Inside CORS so rejected requests still carry the CORS headers
+        .layer(csrf)
+        .layer(cors)
+        .merge(metrics_routes(metrics));
 
     Ok(app)
 }
@@ -1,19 +1,36 @@
 use std::{net::SocketAddr, path::PathBuf};
 
 use config::{ConfigBuilder, ConfigError, Environment, File, builder::AsyncState};
-use factory::factories::{amqp::AmqpConfig, database::DatabaseConfig, observability::ObservabilityConfig, redis::RedisConfig};
+use factory::factories::{
+    amqp::AmqpConfig, database::DatabaseConfig, kafka::KafkaConfig,
+    observability::ObservabilityConfig, redis::RedisConfig,
+};
 use serde::Deserialize;
 use users_core::jwt::JwtConfig;
 
+use crate::services::s3::S3ServiceConfig;
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct Config {
     pub server_address: SocketAddr,
     pub observability: ObservabilityConfig,
     pub redis: RedisConfig,
     pub amqp: AmqpConfig,
+    pub kafka: KafkaConfig,
     pub database: DatabaseConfig,
     pub cookie_key: String,
+    pub cookie_secure: bool,
+    /// HMAC key of the CSRF tokens
+    pub csrf_key: String,
     pub jwt: JwtConfig,
+    /// Secret API key (`sk_...`), set through `STRIPE_SECRET_KEY`
+    pub stripe_secret_key: String,
+    /// Signing secret of the fund webhook endpoint (`whsec_...`), set through `STRIPE_WEBHOOK_SECRET`
+    pub stripe_webhook_secret: String,
+    /// Generated invoices of closed months are cached there
+    pub s3: S3ServiceConfig,
+    /// Base URL of users-api, e.g. `http://users-api:8000`
+    pub users_api_url: String,
 }
 
 impl Config {
@@ -1,7 +1,7 @@
 use axum::Json;
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
-use http_contracts::error::schema::ErrorResponse;
+use http_contracts::error::{AppError as HttpError, schema::ErrorResponse};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -27,7 +27,7 @@ pub enum AppError {
     #[error("Encountered an error trying to convert an infallible value: {0}")]
     FromRequestPartsError(#[from] std::convert::Infallible),
 
-    #[error("Serde json error")]
+    #[error("Serde json error: {0}")]
     SerdejsonError(#[from] serde_json::Error),
 
     #[error("External service error")]
@@ -36,9 +36,21 @@ pub enum AppError {
         code: String,
         message: String,
     },
+    #[error("Object storage error: {0}")]
+    ObjectStorageError(#[from] object_store::Error),
+    #[error("AMQP error: {0}")]
+    AmqpError(#[from] factory::factories::amqp::error::AmqpError),
+    #[error("Kafka error: {0}")]
+    KafkaError(#[from] factory::factories::kafka::error::KafkaError),
+    #[error("Kafka client error: {0}")]
+    KafkaClientError(#[from] rdkafka::error::KafkaError),
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Redis error: {0}")]
+    RedisError(#[from] redis::RedisError),
     #[error("Service unavailable error")]
     ServiceUnavailable(String),
-    #[error("Internal server error")]
+    #[error("Internal server error: {0}")]
     InternalServerError(String),
 
     #[error("Token creation error")]
@@ -102,7 +114,7 @@ pub enum AppError {
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let (status, msg) = match self {
-            Self::SqlxError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            Self::SqlxError(e) => return HttpError::from(e).into_response(),
             Self::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
             Self::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
             Self::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
@@ -125,6 +137,12 @@ impl IntoResponse for AppError {
                     service, code, message
                 ),
             ),
+            Self::ObjectStorageError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            Self::AmqpError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            Self::KafkaError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            Self::KafkaClientError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            Self::IoError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            Self::RedisError(e) => return HttpError::from(e).into_response(),
             Self::ServiceUnavailable(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
             Self::InternalServerError(msg) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
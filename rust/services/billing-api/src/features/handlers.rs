@@ -1,14 +1,41 @@
 use aide::axum::IntoApiResponse;
 use axum::{
     Json,
+    body::Bytes,
     extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::IntoResponse,
 };
-use factory::factories::database::Database;
-use http_contracts::{list::schema::ListResponse, pagination::schema::Pagination};
-use users_core::jwt::Claims;
+use bigdecimal::{BigDecimal, ToPrimitive};
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
+use compute_core::{cache_keys::CacheKeys, schemas::ResumeDeploymentMessage};
+use factory::factories::{amqp::Amqp, database::Database, redis::Redis};
+use http_contracts::pagination::schema::{Paginated, Pagination};
+use object_store::{ObjectStore, aws::AmazonS3, path::Path as ObjectStorePath};
+use redis::AsyncCommands;
+use sqlx::PgPool;
+use tracing::{debug, error, info, warn};
+use users_core::jwt::{AdminClaims, Claims};
 use uuid::Uuid;
+use validator::Validate;
 
-use crate::{error::AppError, features::repository::BillingRepository};
+use crate::{
+    config::Config,
+    error::AppError,
+    features::{
+        repository::BillingRepository,
+        schemas::{
+            CostEstimate, CostEstimateRequest, CostEstimateResponse, CreatePromoCodeRequest,
+            FundRequest, FundResponse, InvoicePeriod, NextTierEstimate, PromoRedemptionResponse,
+            RedeemPromoCodeRequest, UsageQuery, UsageResponse,
+        },
+    },
+    services::{
+        invoice_pdf::InvoiceDocument,
+        stripe_client::{PaymentIntent, StripeClient},
+        users_client::UsersClient,
+    },
+};
 
 #[tracing::instrument(name = "get_balance", skip_all, fields(user_id = %claims.sub), err)]
 pub async fn get_balance(
@@ -45,6 +72,53 @@ pub async fn get_addon_price(
     Ok(Json(addon_price))
 }
 
+#[tracing::instrument(name = "estimate_cost_handler", skip_all, fields(preset_id = %req.preset_id), err)]
+pub async fn estimate_cost_handler(
+    State(database): State<Database>,
+    Json(req): Json<CostEstimateRequest>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let replicas = req.replicas.unwrap_or(1);
+    if !(1..=25).contains(&replicas) {
+        return Err(AppError::ValidationError(
+            "Replicas must be between 1 and 25".to_string(),
+        ));
+    }
+
+    let preset = BillingRepository::get_preset(req.preset_id, &database.pool).await?;
+    if req.resources.cpu_limit_millicores - preset.cpu_millicores > preset.max_addon_cpu_millicores
+        || req.resources.memory_limit_mb - preset.memory_mb > preset.max_addon_memory_mb
+    {
+        return Err(AppError::ValidationError(format!(
+            "Resources exceed limits for preset '{}'. Max CPU: {}m, Max Memory: {}MB",
+            preset.name,
+            preset.cpu_millicores + preset.max_addon_cpu_millicores,
+            preset.memory_mb + preset.max_addon_memory_mb
+        )));
+    }
+
+    let addon_price = BillingRepository::get_addon_price(&database.pool).await?;
+    let estimate = CostEstimate::new(&preset, &addon_price, &req.resources, replicas);
+
+    let next_tier = BillingRepository::get_next_preset(&preset.monthly_price, &database.pool)
+        .await?
+        .map(|next| {
+            let next_estimate = CostEstimate::new(&next, &addon_price, &req.resources, replicas);
+            NextTierEstimate {
+                preset_id: next.id,
+                preset_name: next.name,
+                cpu_millicores: next.cpu_millicores,
+                memory_mb: next.memory_mb,
+                monthly_difference: &next_estimate.monthly - &estimate.monthly,
+                estimate: next_estimate,
+            }
+        });
+
+    Ok(Json(CostEstimateResponse {
+        estimate,
+        next_tier,
+    }))
+}
+
 #[tracing::instrument(name = "get_transactions", skip_all, fields(user_id = %claims.sub), err)]
 pub async fn get_transactions(
     claims: Claims,
@@ -53,31 +127,332 @@ pub async fn get_transactions(
 ) -> Result<impl IntoApiResponse, AppError> {
     let user_id = claims.sub;
     let (data, total) =
-        BillingRepository::get_transactions(user_id, pagination, &database.pool).await?;
+        BillingRepository::get_transactions(user_id, &pagination, &database.pool).await?;
 
-    Ok(Json(ListResponse { data, total }))
+    Ok(Json(Paginated::new(data, total, &pagination)))
 }
 
-#[tracing::instrument(name = "create_fund", skip_all, fields(user_id = %claims.sub), err)]
-pub async fn create_fund(
+#[tracing::instrument(name = "create_fund_handler", skip_all, fields(user_id = %claims.sub), err)]
+pub async fn create_fund_handler(
     claims: Claims,
-    State(_database): State<Database>,
+    State(database): State<Database>,
+    State(stripe): State<StripeClient>,
+    Json(req): Json<FundRequest>,
 ) -> Result<impl IntoApiResponse, AppError> {
-    // let user_id = claims.sub;
+    let user_id = claims.sub;
+
+    // Stripe amounts are integers in the minor unit of the currency
+    let amount_minor = (&req.amount * BigDecimal::from(100))
+        .round(0)
+        .to_i64()
+        .filter(|amount| *amount > 0)
+        .ok_or_else(|| AppError::ValidationError("Amount must be positive".to_string()))?;
+
+    let balance = BillingRepository::get_balance(user_id, &database.pool).await?;
+
+    let transaction_id =
+        BillingRepository::create_pending_top_up(balance.id, &req.amount, &database.pool).await?;
 
-    let data = vec![String::from("value")];
+    // Keyed by our own transaction so a retried call can't open a second intent for it
+    let intent = match stripe
+        .create_payment_intent(&user_id, amount_minor, &balance.currency, &transaction_id)
+        .await
+    {
+        Ok(intent) => intent,
+        Err(e) => {
+            BillingRepository::abandon_top_up(transaction_id, &database.pool).await?;
+            return Err(e);
+        }
+    };
+    let client_secret = intent.client_secret.ok_or_else(|| {
+        AppError::InternalServerError("Stripe returned no client secret".to_string())
+    })?;
 
-    Ok(Json(ListResponse { data, total: 0 }))
+    BillingRepository::attach_top_up_intent(transaction_id, &intent.id, &database.pool).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(FundResponse {
+            transaction_id,
+            payment_intent_id: intent.id,
+            client_secret,
+        }),
+    ))
 }
 
-#[tracing::instrument(name = "get_usage", skip_all, fields(user_id = %claims.sub), err)]
-pub async fn get_usage(
+/// Called by Stripe, authenticated through the signature instead of a user token
+#[tracing::instrument(name = "fund_webhook_handler", skip_all, err)]
+pub async fn fund_webhook_handler(
+    State(database): State<Database>,
+    State(amqp): State<Amqp>,
+    State(redis): State<Redis>,
+    State(stripe): State<StripeClient>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoResponse, AppError> {
+    let signature = headers
+        .get("Stripe-Signature")
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| AppError::BadRequest("Missing Stripe-Signature header".to_string()))?;
+
+    let event = stripe.construct_event(&body, signature)?;
+
+    match event.event_type.as_str() {
+        "payment_intent.succeeded" => {
+            let intent: PaymentIntent = serde_json::from_value(event.data.object)?;
+
+            let mut tx = database.pool.begin().await?;
+            match BillingRepository::complete_top_up(&intent.id, &mut tx).await? {
+                Some(settlement) => {
+                    tx.commit().await?;
+                    info!(payment_intent_id = %intent.id, "💳 Top-up of {} {} completed", settlement.amount, intent.currency);
+
+                    // The top-up is committed, failing here would only make Stripe redeliver an already settled event
+                    if settlement.balance > 0
+                        && let Err(e) = resume_balance_suspended_deployments(
+                            settlement.user_id,
+                            &database,
+                            &amqp,
+                            redis,
+                        )
+                        .await
+                    {
+                        error!(user_id = %settlement.user_id, "❌ Failed to resume deployments: {}", e);
+                    }
+                }
+                // Stripe retries deliveries, settled intents are acknowledged without changes
+                None => debug!(payment_intent_id = %intent.id, "💳 Top-up already settled"),
+            }
+        }
+        "payment_intent.payment_failed" | "payment_intent.canceled" => {
+            let intent: PaymentIntent = serde_json::from_value(event.data.object)?;
+            BillingRepository::fail_top_up(&intent.id, &database.pool).await?;
+            info!(payment_intent_id = %intent.id, status = %intent.status, "💳 Top-up failed");
+        }
+        other => debug!(event_id = %event.id, "Ignoring Stripe event {}", other),
+    }
+
+    Ok(StatusCode::OK)
+}
+
+/// Undoes the billing worker's negative balance suspension once the user is back in credit
+async fn resume_balance_suspended_deployments(
+    user_id: Uuid,
+    database: &Database,
+    amqp: &Amqp,
+    mut redis: Redis,
+) -> Result<(), AppError> {
+    let deployments =
+        BillingRepository::take_balance_suspended_deployments(user_id, &database.pool).await?;
+
+    for deployment in &deployments {
+        let message = ResumeDeploymentMessage {
+            user_id,
+            project_id: deployment.project_id,
+            deployment_id: deployment.id,
+            timestamp: Utc::now().timestamp(),
+        };
+        amqp.basic_publish("compute", "compute.resume", &message)
+            .await?;
+    }
+
+    // Going negative again should be acted on right away, not after the current window
+    redis
+        .con
+        .del::<_, ()>(CacheKeys::user_balance_suspension_checked(
+            &user_id.to_string(),
+        ))
+        .await?;
+
+    if !deployments.is_empty() {
+        info!(user_id = %user_id, "▶️ Resuming {} deployments after top-up", deployments.len());
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(name = "redeem_promo_code_handler", skip_all, fields(user_id = %claims.sub), err)]
+pub async fn redeem_promo_code_handler(
     claims: Claims,
-    State(_database): State<Database>,
+    State(database): State<Database>,
+    Json(req): Json<RedeemPromoCodeRequest>,
 ) -> Result<impl IntoApiResponse, AppError> {
-    // let user_id = claims.sub;
+    req.validate()?;
+
+    let redemption = redeem_promo_code(claims.sub, &req.code, &database.pool).await?;
+
+    Ok(Json(redemption))
+}
+
+/// Sets the code's discount on the user's balance for the next calendar month. Concurrent
+/// redemptions are settled by the conditional increment of `uses_so_far`, no retries needed
+pub async fn redeem_promo_code(
+    user_id: Uuid,
+    code: &str,
+    pool: &PgPool,
+) -> Result<PromoRedemptionResponse, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let promo_code = BillingRepository::get_promo_code(code, &mut tx)
+        .await?
+        .ok_or_else(|| AppError::NotFoundError("Promo code not found".to_string()))?;
+
+    let now = Utc::now();
+    if promo_code
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= now)
+    {
+        return Err(AppError::BadRequest("Promo code has expired".to_string()));
+    }
+    if !BillingRepository::take_promo_code_use(promo_code.id, &mut tx).await? {
+        return Err(AppError::BadRequest(
+            "Promo code has been fully redeemed".to_string(),
+        ));
+    }
+
+    if !BillingRepository::redeem_promo_code(promo_code.id, user_id, &mut tx).await? {
+        return Err(AppError::BadRequest(
+            "Promo code was already redeemed".to_string(),
+        ));
+    }
+
+    let (starts_at, ends_at) = next_billing_month(now)?;
+    let balance = BillingRepository::set_discount(
+        user_id,
+        promo_code.discount_percent,
+        starts_at,
+        ends_at,
+        &mut tx,
+    )
+    .await?;
+
+    tx.commit().await?;
 
-    let data = vec![String::from("value")];
+    info!(user_id = %user_id, code = %promo_code.code, "🎟️ Promo code redeemed");
+
+    Ok(PromoRedemptionResponse {
+        code: promo_code.code,
+        discount_percent: promo_code.discount_percent,
+        discount_starts_at: starts_at,
+        discount_ends_at: ends_at,
+        balance: balance.amount,
+        currency: balance.currency,
+    })
+}
+
+/// Start and end of the calendar month after `now`, the period the next invoice covers
+fn next_billing_month(now: DateTime<Utc>) -> Result<(DateTime<Utc>, DateTime<Utc>), AppError> {
+    let invalid = || AppError::InternalServerError("Billing month out of range".to_string());
+
+    let this_month = NaiveDate::from_ymd_opt(now.year(), now.month(), 1).ok_or_else(invalid)?;
+    let start = this_month
+        .checked_add_months(Months::new(1))
+        .ok_or_else(invalid)?;
+    let end = start
+        .checked_add_months(Months::new(1))
+        .ok_or_else(invalid)?;
+
+    Ok((
+        start.and_hms_opt(0, 0, 0).ok_or_else(invalid)?.and_utc(),
+        end.and_hms_opt(0, 0, 0).ok_or_else(invalid)?.and_utc(),
+    ))
+}
+
+#[tracing::instrument(name = "get_promo_codes_handler", skip_all, fields(admin_id = %claims.sub), err)]
+pub async fn get_promo_codes_handler(
+    AdminClaims(claims): AdminClaims,
+    State(database): State<Database>,
+    Query(pagination): Query<Pagination>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let (data, total) = BillingRepository::get_promo_codes(&pagination, &database.pool).await?;
+
+    Ok(Json(Paginated::new(data, total, &pagination)))
+}
+
+#[tracing::instrument(name = "create_promo_code_handler", skip_all, fields(admin_id = %claims.sub), err)]
+pub async fn create_promo_code_handler(
+    AdminClaims(claims): AdminClaims,
+    State(database): State<Database>,
+    Json(req): Json<CreatePromoCodeRequest>,
+) -> Result<impl IntoApiResponse, AppError> {
+    req.validate()?;
+
+    let promo_code = BillingRepository::create_promo_code(&req, &database.pool)
+        .await?
+        .ok_or_else(|| AppError::BadRequest(format!("Promo code '{}' already exists", req.code)))?;
+
+    info!(admin_id = %claims.sub, code = %promo_code.code, "🎟️ Promo code created");
+
+    Ok((StatusCode::CREATED, Json(promo_code)))
+}
+
+#[tracing::instrument(name = "get_usage_handler", skip_all, fields(user_id = %claims.sub), err)]
+pub async fn get_usage_handler(
+    claims: Claims,
+    Query(q): Query<UsageQuery>,
+    State(database): State<Database>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let user_id = claims.sub;
+    let (start, end) = q.resolve()?;
+
+    let items = BillingRepository::get_usage(user_id, start, end, &database.pool).await?;
+
+    Ok(Json(UsageResponse::from(items)))
+}
+
+#[tracing::instrument(name = "get_invoice_handler", skip_all, fields(user_id = %claims.sub, invoice_id = %invoice_id), err)]
+pub async fn get_invoice_handler(
+    claims: Claims,
+    Path(invoice_id): Path<String>,
+    State(database): State<Database>,
+    State(s3): State<AmazonS3>,
+    State(users_client): State<UsersClient>,
+    State(config): State<Config>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let user_id = claims.sub;
+    let period = InvoicePeriod::parse(&invoice_id)?;
+    let location = ObjectStorePath::from(format!("invoices/{}/invoice-{}.pdf", user_id, period.id));
+    let cacheable = period.is_closed(Utc::now());
+
+    if cacheable {
+        match s3.get(&location).await {
+            Ok(cached) => return Ok(invoice_response(&period.id, cached.bytes().await?)),
+            Err(object_store::Error::NotFound { .. }) => {}
+            // The cache is an optimisation, the invoice can always be rebuilt from billings
+            Err(e) => warn!("⚠️ Failed to read cached invoice {}: {}", location, e),
+        }
+    }
+
+    let items =
+        BillingRepository::get_usage(user_id, period.start, period.end, &database.pool).await?;
+    if items.is_empty() {
+        return Err(AppError::NotFoundError(format!(
+            "Invoice {} not found",
+            period.id
+        )));
+    }
+
+    let balance = BillingRepository::get_balance(user_id, &database.pool).await?;
+    let profile = users_client.get_profile(user_id, &config).await?;
+
+    let pdf = Bytes::from(InvoiceDocument::new(&period, profile, balance.currency, items).render());
+
+    if cacheable && let Err(e) = s3.put(&location, pdf.clone().into()).await {
+        warn!("⚠️ Failed to cache invoice {}: {}", location, e);
+    }
+
+    Ok(invoice_response(&period.id, pdf))
+}
 
-    Ok(Json(ListResponse { data, total: 0 }))
+fn invoice_response(invoice_id: &str, pdf: Bytes) -> impl IntoApiResponse + use<> {
+    (
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"invoice-{}.pdf\"", invoice_id),
+            ),
+        ],
+        pdf,
+    )
 }
@@ -1 +1,146 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Months, NaiveDate, TimeDelta, Utc};
+use compute_core::models::ResourceSpec;
 
+use crate::{
+    error::AppError,
+    features::{
+        models::{AddonPrice, Preset},
+        schemas::{CostEstimate, InvoicePeriod, UsageItem, UsageQuery, UsageResponse},
+    },
+    services::{
+        invoice_pdf::{InvoiceDocument, InvoiceLine},
+        users_client::UserProfile,
+    },
+};
+
+impl CostEstimate {
+    /// Prices `replicas` pods the way the billing worker charges them, limits above the preset are add-ons
+    pub fn new(
+        preset: &Preset,
+        addon_price: &AddonPrice,
+        resources: &ResourceSpec,
+        replicas: i32,
+    ) -> Self {
+        let addon_cpu_millicores = (resources.cpu_limit_millicores - preset.cpu_millicores).max(0);
+        let addon_memory_mb = (resources.memory_limit_mb - preset.memory_mb).max(0);
+
+        let hourly = (&preset.hourly_price
+            + BigDecimal::from(addon_cpu_millicores) * &addon_price.cpu_hourly_unit_price
+            + BigDecimal::from(addon_memory_mb) * &addon_price.memory_hourly_unit_price)
+            * BigDecimal::from(replicas);
+
+        Self {
+            daily: (&hourly * BigDecimal::from(24)).round(6),
+            monthly: (&hourly * BigDecimal::from(720)).round(6),
+            hourly: hourly.round(6),
+            currency: preset.currency.clone(),
+        }
+    }
+}
+
+impl InvoicePeriod {
+    pub fn parse(invoice_id: &str) -> Result<Self, AppError> {
+        let invalid = || {
+            AppError::BadRequest(format!(
+                "Invalid invoice id '{}', expected YYYY-MM",
+                invoice_id
+            ))
+        };
+
+        let first_day = NaiveDate::parse_from_str(&format!("{}-01", invoice_id), "%Y-%m-%d")
+            .map_err(|_| invalid())?;
+        let next_month = first_day
+            .checked_add_months(Months::new(1))
+            .ok_or_else(invalid)?;
+
+        let start = first_day
+            .and_hms_opt(0, 0, 0)
+            .ok_or_else(invalid)?
+            .and_utc();
+        let end = next_month
+            .and_hms_opt(0, 0, 0)
+            .ok_or_else(invalid)?
+            .and_utc();
+
+        if start > Utc::now() {
+            return Err(AppError::NotFoundError(format!(
+                "Invoice {} not found",
+                invoice_id
+            )));
+        }
+
+        Ok(Self {
+            id: first_day.format("%Y-%m").to_string(),
+            start,
+            end,
+        })
+    }
+
+    /// Invoices of the running month still change with every hourly charge
+    pub fn is_closed(&self, now: DateTime<Utc>) -> bool {
+        self.end <= now
+    }
+}
+
+impl InvoiceDocument {
+    pub fn new(
+        period: &InvoicePeriod,
+        profile: UserProfile,
+        currency: String,
+        items: Vec<UsageItem>,
+    ) -> Self {
+        let lines: Vec<InvoiceLine> = items
+            .into_iter()
+            .map(|item| InvoiceLine {
+                description: match (item.deployment_name, item.deployment_id) {
+                    (Some(name), _) => name,
+                    (None, Some(id)) => format!("Deleted deployment {}", id),
+                    (None, None) => "Deleted deployment".to_string(),
+                },
+                hours: item.hours_used,
+                rate: item.cost_per_hour,
+                amount: item.total_cost,
+            })
+            .collect();
+        let subtotal = lines.iter().map(|l| &l.amount).sum::<BigDecimal>();
+
+        Self {
+            number: format!("INV-{}", period.id),
+            period: format!(
+                "{} - {}",
+                period.start.format("%Y-%m-%d"),
+                (period.end - TimeDelta::days(1)).format("%Y-%m-%d")
+            ),
+            issued_at: Utc::now().format("%Y-%m-%d").to_string(),
+            customer_name: profile.username,
+            customer_email: profile.email,
+            currency,
+            lines,
+            total: subtotal.clone(),
+            subtotal,
+        }
+    }
+}
+
+impl UsageQuery {
+    pub fn resolve(&self) -> Result<(DateTime<Utc>, DateTime<Utc>), AppError> {
+        let end = self.end_date.unwrap_or_else(Utc::now);
+        let start = self.start_date.unwrap_or(end - TimeDelta::days(30));
+
+        if start >= end {
+            return Err(AppError::BadRequest(
+                "start_date must be before end_date".to_string(),
+            ));
+        }
+
+        Ok((start, end))
+    }
+}
+
+impl From<Vec<UsageItem>> for UsageResponse {
+    fn from(items: Vec<UsageItem>) -> Self {
+        let total_cost = items.iter().map(|i| &i.total_cost).sum::<BigDecimal>();
+        Self { items, total_cost }
+    }
+}
@@ -10,6 +10,7 @@ use aide::axum::{
     ApiRouter,
     routing::{get, post},
 };
+use axum::routing::post as axum_post;
 
 pub fn get_routes() -> ApiRouter<AppState> {
     ApiRouter::new()
@@ -23,10 +24,30 @@ pub fn get_routes() -> ApiRouter<AppState> {
             "/api/v1/billing/addon-price",
             get(handlers::get_addon_price),
         )
+        .api_route(
+            "/api/v1/billing/estimate",
+            post(handlers::estimate_cost_handler),
+        )
         .api_route(
             "/api/v1/billing/transactions",
             get(handlers::get_transactions),
         )
-        .api_route("/api/v1/billing/fund", post(handlers::create_fund))
-        .api_route("/api/v1/billing/usage", get(handlers::get_usage))
+        .api_route("/api/v1/billing/usage", get(handlers::get_usage_handler))
+        .api_route(
+            "/api/v1/billing/invoices/{invoice_id}",
+            get(handlers::get_invoice_handler),
+        )
+        .api_route(
+            "/api/v1/billing/promo",
+            post(handlers::redeem_promo_code_handler),
+        )
+        .api_route(
+            "/api/v1/admin/billing/promo-codes",
+            get(handlers::get_promo_codes_handler).post(handlers::create_promo_code_handler),
+        )
+        .api_route("/api/v1/billing/fund", post(handlers::create_fund_handler))
+        .route(
+            "/api/v1/billing/fund/webhook",
+            axum_post(handlers::fund_webhook_handler),
+        )
 }
@@ -17,6 +17,14 @@ pub enum TransactionType {
     TopUp,
 }
 
+#[derive(Type, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, Debug)]
+#[sqlx(type_name = "transaction_status", rename_all = "snake_case")]
+pub enum TransactionStatus {
+    Pending,
+    Completed,
+    Failed,
+}
+
 // ============================================
 // MODELS
 // ============================================
@@ -79,6 +87,33 @@ pub struct Transaction {
     #[sqlx(rename = "type")]
     #[serde(rename = "type")]
     pub transaction_type: TransactionType,
+    pub status: TransactionStatus,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Balance after a pending top-up was credited
+#[derive(Debug)]
+pub struct TopUpSettlement {
+    pub user_id: Uuid,
+    pub amount: BigDecimal,
+    pub balance: BigDecimal,
+}
+
+#[derive(FromRow, Debug)]
+pub struct SuspendedDeployment {
+    pub id: Uuid,
+    pub project_id: Uuid,
+}
+
+#[derive(FromRow, Serialize, Deserialize, Clone, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PromoCode {
+    pub id: Uuid,
+    pub code: String,
+    pub discount_percent: i32,
+    pub max_uses: i32,
+    pub uses_so_far: i32,
+    pub expires_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
 }
 
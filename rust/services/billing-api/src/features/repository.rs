@@ -1,8 +1,17 @@
+use bigdecimal::BigDecimal;
+use billing_core::schemas::BillingChargedEvent;
+use chrono::{DateTime, Utc};
 use http_contracts::pagination::schema::Pagination;
-use sqlx::{PgPool, Postgres};
+use sqlx::{PgPool, Postgres, Transaction as SqlxTransaction};
 use uuid::Uuid;
 
-use crate::features::models::{AddonPrice, Balance, Preset, Transaction, TransactionType};
+use crate::features::{
+    models::{
+        AddonPrice, Balance, Preset, PromoCode, SuspendedDeployment, TopUpSettlement, Transaction,
+        TransactionStatus, TransactionType,
+    },
+    schemas::{CreatePromoCodeRequest, UsageItem},
+};
 
 pub struct BillingRepository;
 
@@ -37,6 +46,26 @@ impl BillingRepository {
         Ok(a)
     }
 
+    /// The cheapest active preset priced above `monthly_price`
+    #[tracing::instrument(name = "billing_repository.get_next_preset", skip_all, err)]
+    pub async fn get_next_preset(
+        monthly_price: &BigDecimal,
+        pool: &PgPool,
+    ) -> Result<Option<Preset>, sqlx::Error> {
+        sqlx::query_as::<Postgres, Preset>(
+            r#"
+            SELECT *
+            FROM presets
+            WHERE is_active AND monthly_price > $1
+            ORDER BY monthly_price
+            LIMIT 1
+            "#,
+        )
+        .bind(monthly_price)
+        .fetch_optional(pool)
+        .await
+    }
+
     #[tracing::instrument(name = "billing_repository.get_addon_price", skip_all, err)]
     pub async fn get_addon_price(pool: &PgPool) -> Result<AddonPrice, sqlx::Error> {
         sqlx::query_as::<Postgres, AddonPrice>(r#"SELECT * FROM addon_prices"#)
@@ -47,7 +76,7 @@ impl BillingRepository {
     #[tracing::instrument(name = "billing_repository.get_transactions", skip_all, fields(user_id = %user_id), err)]
     pub async fn get_transactions(
         user_id: Uuid,
-        pagination: Pagination,
+        pagination: &Pagination,
         pool: &PgPool,
     ) -> Result<(Vec<Transaction>, i64), sqlx::Error> {
         // In standard SQL, if you use COUNT(*), the database "collapses" all your rows into a single number.
@@ -64,6 +93,7 @@ impl BillingRepository {
                 t.amount,
                 t.detail,
                 t.type AS "type: TransactionType",
+                t.status AS "status: TransactionStatus",
                 t.created_at,
                 COUNT(*) OVER() as "total!"
             FROM transactions t
@@ -94,10 +124,421 @@ impl BillingRepository {
                 amount: r.amount,
                 detail: r.detail,
                 transaction_type: r.r#type,
+                status: r.status,
                 created_at: r.created_at,
             })
             .collect();
 
         Ok((transactions, total))
     }
+
+    /// Billing snapshots of the range summed per deployment, most expensive first
+    #[tracing::instrument(name = "billing_repository.get_usage", skip_all, fields(user_id = %user_id), err)]
+    pub async fn get_usage(
+        user_id: Uuid,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        pool: &PgPool,
+    ) -> Result<Vec<UsageItem>, sqlx::Error> {
+        sqlx::query_as!(
+            UsageItem,
+            r#"
+            SELECT
+                b.deployment_id,
+                d.name AS "deployment_name?",
+                SUM(b.hours_used) AS "hours_used!",
+                COALESCE(ROUND(SUM(b.total_cost) / NULLIF(SUM(b.hours_used), 0), 6), 0) AS "cost_per_hour!",
+                SUM(b.total_cost) AS "total_cost!"
+            FROM billings b
+            LEFT JOIN deployments d ON d.id = b.deployment_id
+            WHERE b.user_id = $1
+            AND COALESCE(b.billing_period, b.created_at) >= $2
+            AND COALESCE(b.billing_period, b.created_at) < $3
+            GROUP BY b.deployment_id, d.name
+            ORDER BY SUM(b.total_cost) DESC
+            "#,
+            user_id,
+            start,
+            end
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Not applied to the balance until the payment provider confirms it. The id doubles as the
+    /// provider's idempotency key, the intent id is attached once the provider returns it
+    #[tracing::instrument(name = "billing_repository.create_pending_top_up", skip_all, fields(balance_id = %balance_id), err)]
+    pub async fn create_pending_top_up(
+        balance_id: Uuid,
+        amount: &BigDecimal,
+        pool: &PgPool,
+    ) -> Result<Uuid, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"
+            INSERT INTO transactions (balance_id, amount, type, status, detail)
+            VALUES ($1, $2, 'top_up', 'pending', 'Stripe top-up')
+            RETURNING id
+            "#,
+            balance_id,
+            amount
+        )
+        .fetch_one(pool)
+        .await
+    }
+
+    #[tracing::instrument(name = "billing_repository.attach_top_up_intent", skip_all, fields(transaction_id = %transaction_id), err)]
+    pub async fn attach_top_up_intent(
+        transaction_id: Uuid,
+        external_transaction_id: &str,
+        pool: &PgPool,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE transactions
+            SET external_transaction_id = $2
+            WHERE id = $1
+            "#,
+            transaction_id,
+            external_transaction_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The provider refused the intent, so no webhook will ever settle this top-up
+    #[tracing::instrument(name = "billing_repository.abandon_top_up", skip_all, fields(transaction_id = %transaction_id), err)]
+    pub async fn abandon_top_up(transaction_id: Uuid, pool: &PgPool) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE transactions
+            SET status = 'failed'
+            WHERE id = $1
+            AND status = 'pending'
+            "#,
+            transaction_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Marks a pending top-up completed and credits it, `None` if it was already settled.
+    /// The caller owns the transaction so both writes commit together.
+    #[tracing::instrument(name = "billing_repository.complete_top_up", skip_all, fields(external_transaction_id = %external_transaction_id), err)]
+    pub async fn complete_top_up(
+        external_transaction_id: &str,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+    ) -> Result<Option<TopUpSettlement>, sqlx::Error> {
+        let Some(row) = sqlx::query!(
+            r#"
+            UPDATE transactions
+            SET status = 'completed'
+            WHERE external_transaction_id = $1
+            AND status = 'pending'
+            RETURNING balance_id, amount
+            "#,
+            external_transaction_id
+        )
+        .fetch_optional(&mut **tx)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        let balance = sqlx::query!(
+            r#"
+            UPDATE balances
+            SET amount = amount + $2
+            WHERE id = $1
+            RETURNING user_id, amount
+            "#,
+            row.balance_id,
+            row.amount
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(Some(TopUpSettlement {
+            user_id: balance.user_id,
+            amount: row.amount,
+            balance: balance.amount,
+        }))
+    }
+
+    /// Clears the flag while reading it so two settlements never resume the same deployment twice.
+    /// The flag alone decides, the suspension may still be on its way to the provisioner
+    #[tracing::instrument(name = "billing_repository.take_balance_suspended_deployments", skip_all, fields(user_id = %user_id), err)]
+    pub async fn take_balance_suspended_deployments(
+        user_id: Uuid,
+        pool: &PgPool,
+    ) -> Result<Vec<SuspendedDeployment>, sqlx::Error> {
+        sqlx::query_as!(
+            SuspendedDeployment,
+            r#"
+            UPDATE deployments
+            SET suspended_for_balance = FALSE
+            WHERE user_id = $1
+            AND suspended_for_balance
+            RETURNING id, project_id
+            "#,
+            user_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    #[tracing::instrument(name = "billing_repository.fail_top_up", skip_all, fields(external_transaction_id = %external_transaction_id), err)]
+    pub async fn fail_top_up(
+        external_transaction_id: &str,
+        pool: &PgPool,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE transactions
+            SET status = 'failed'
+            WHERE external_transaction_id = $1
+            AND status = 'pending'
+            "#,
+            external_transaction_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// `false` when the period was already counted, a redelivered event changes nothing
+    #[tracing::instrument(name = "billing_repository.record_charge", skip_all, fields(billing_id = %event.billing_id), err)]
+    pub async fn record_charge(
+        event: &BillingChargedEvent,
+        pool: &PgPool,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO deployment_charges AS dc (
+                deployment_id,
+                user_id,
+                total_amount,
+                currency,
+                charge_count,
+                last_billing_id,
+                last_billing_period
+            )
+            VALUES ($1, $2, $3, $4, 1, $5, $6)
+            ON CONFLICT (deployment_id) DO UPDATE
+            SET total_amount = dc.total_amount + EXCLUDED.total_amount,
+                charge_count = dc.charge_count + 1,
+                last_billing_id = EXCLUDED.last_billing_id,
+                last_billing_period = EXCLUDED.last_billing_period
+            WHERE dc.last_billing_period < EXCLUDED.last_billing_period
+            AND dc.finalized_at IS NULL
+            "#,
+            event.deployment_id,
+            event.user_id,
+            event.amount.amount,
+            event.amount.currency,
+            event.billing_id,
+            event.billing_period
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Returns how many billing records were still open
+    #[tracing::instrument(name = "billing_repository.finalize_deployment_billings", skip_all, fields(deployment_id = %deployment_id), err)]
+    pub async fn finalize_deployment_billings(
+        deployment_id: &Uuid,
+        pool: &PgPool,
+    ) -> Result<u64, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE billings
+            SET finalized_at = NOW()
+            WHERE deployment_id = $1
+            AND finalized_at IS NULL
+            "#,
+            deployment_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE deployment_charges
+            SET finalized_at = NOW()
+            WHERE deployment_id = $1
+            AND finalized_at IS NULL
+            "#,
+            deployment_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(result.rows_affected())
+    }
+
+    #[tracing::instrument(name = "billing_repository.get_promo_codes", skip_all, err)]
+    pub async fn get_promo_codes(
+        pagination: &Pagination,
+        pool: &PgPool,
+    ) -> Result<(Vec<PromoCode>, i64), sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                id,
+                code,
+                discount_percent,
+                max_uses,
+                uses_so_far,
+                expires_at,
+                created_at,
+                COUNT(*) OVER() AS "total!"
+            FROM promo_codes
+            ORDER BY created_at DESC
+            LIMIT $1
+            OFFSET $2
+            "#,
+            pagination.limit,
+            pagination.offset
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let total = rows.first().map(|r| r.total).unwrap_or(0);
+        let promo_codes = rows
+            .into_iter()
+            .map(|r| PromoCode {
+                id: r.id,
+                code: r.code,
+                discount_percent: r.discount_percent,
+                max_uses: r.max_uses,
+                uses_so_far: r.uses_so_far,
+                expires_at: r.expires_at,
+                created_at: r.created_at,
+            })
+            .collect();
+
+        Ok((promo_codes, total))
+    }
+
+    /// `None` when the code is taken
+    #[tracing::instrument(name = "billing_repository.create_promo_code", skip_all, err)]
+    pub async fn create_promo_code(
+        req: &CreatePromoCodeRequest,
+        pool: &PgPool,
+    ) -> Result<Option<PromoCode>, sqlx::Error> {
+        sqlx::query_as!(
+            PromoCode,
+            r#"
+            INSERT INTO promo_codes (code, discount_percent, max_uses, expires_at)
+            VALUES (UPPER($1), $2, $3, $4)
+            ON CONFLICT (code) DO NOTHING
+            RETURNING id, code, discount_percent, max_uses, uses_so_far, expires_at, created_at
+            "#,
+            req.code,
+            req.discount_percent,
+            req.max_uses,
+            req.expires_at
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
+    #[tracing::instrument(name = "billing_repository.get_promo_code", skip_all, err)]
+    pub async fn get_promo_code(
+        code: &str,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+    ) -> Result<Option<PromoCode>, sqlx::Error> {
+        sqlx::query_as!(
+            PromoCode,
+            r#"
+            SELECT id, code, discount_percent, max_uses, uses_so_far, expires_at, created_at
+            FROM promo_codes
+            WHERE code = UPPER($1)
+            "#,
+            code
+        )
+        .fetch_optional(&mut **tx)
+        .await
+    }
+
+    /// `false` when every use of the code is taken. The row lock makes concurrent redemptions
+    /// wait and recheck `uses_so_far` against the committed count
+    #[tracing::instrument(name = "billing_repository.take_promo_code_use", skip_all, fields(promo_code_id = %promo_code_id), err)]
+    pub async fn take_promo_code_use(
+        promo_code_id: Uuid,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE promo_codes
+            SET uses_so_far = uses_so_far + 1
+            WHERE id = $1 AND uses_so_far < max_uses
+            "#,
+            promo_code_id
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// `false` when the user already redeemed the code
+    #[tracing::instrument(name = "billing_repository.redeem_promo_code", skip_all, fields(promo_code_id = %promo_code_id, user_id = %user_id), err)]
+    pub async fn redeem_promo_code(
+        promo_code_id: Uuid,
+        user_id: Uuid,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO promo_code_redemptions (promo_code_id, user_id)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            "#,
+            promo_code_id,
+            user_id
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Replaces any discount the balance already had
+    #[tracing::instrument(name = "billing_repository.set_discount", skip_all, fields(user_id = %user_id), err)]
+    pub async fn set_discount(
+        user_id: Uuid,
+        discount_percent: i32,
+        starts_at: DateTime<Utc>,
+        ends_at: DateTime<Utc>,
+        tx: &mut SqlxTransaction<'_, Postgres>,
+    ) -> Result<Balance, sqlx::Error> {
+        sqlx::query_as!(
+            Balance,
+            r#"
+            UPDATE balances
+            SET discount_percent = $2,
+                discount_starts_at = $3,
+                discount_ends_at = $4
+            WHERE user_id = $1
+            RETURNING id, user_id, amount, currency, created_at, updated_at
+            "#,
+            user_id,
+            discount_percent,
+            starts_at,
+            ends_at
+        )
+        .fetch_one(&mut **tx)
+        .await
+    }
 }
@@ -0,0 +1,132 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use compute_core::models::ResourceSpec;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+/// Invoices cover one calendar month, the invoice id is that month as `YYYY-MM`
+#[derive(Debug)]
+pub struct InvoicePeriod {
+    pub id: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// Both bounds are optional, the range defaults to the last 30 days
+#[derive(Deserialize, JsonSchema, Debug)]
+pub struct UsageQuery {
+    pub start_date: Option<DateTime<Utc>>,
+    pub end_date: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageItem {
+    /// `None` for deployments that were deleted since they were billed
+    pub deployment_id: Option<Uuid>,
+    pub deployment_name: Option<String>,
+    pub hours_used: BigDecimal,
+    /// Average over the range, replicas and resources may have changed in between
+    pub cost_per_hour: BigDecimal,
+    pub total_cost: BigDecimal,
+}
+
+#[derive(Serialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageResponse {
+    pub items: Vec<UsageItem>,
+    pub total_cost: BigDecimal,
+}
+
+#[derive(Deserialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FundRequest {
+    /// In the balance currency, e.g. `50000.00`
+    pub amount: BigDecimal,
+}
+
+#[derive(Serialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FundResponse {
+    pub transaction_id: Uuid,
+    pub payment_intent_id: String,
+    /// Passed to Stripe.js to confirm the payment on the client
+    pub client_secret: String,
+}
+
+#[derive(Deserialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CostEstimateRequest {
+    pub preset_id: Uuid,
+    /// Limits above the preset's own resources are priced as add-ons
+    pub resources: ResourceSpec,
+    /// Defaults to a single replica
+    pub replicas: Option<i32>,
+}
+
+/// List prices, billing tier discounts are applied when the usage is charged
+#[derive(Serialize, JsonSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CostEstimate {
+    pub hourly: BigDecimal,
+    pub daily: BigDecimal,
+    /// 720 hours, the same month the preset prices are quoted for
+    pub monthly: BigDecimal,
+    pub currency: String,
+}
+
+#[derive(Serialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NextTierEstimate {
+    pub preset_id: Uuid,
+    pub preset_name: String,
+    pub cpu_millicores: i32,
+    pub memory_mb: i32,
+    pub estimate: CostEstimate,
+    /// How much more the next preset costs per month, add-ons shrink as it covers more
+    pub monthly_difference: BigDecimal,
+}
+
+#[derive(Serialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CostEstimateResponse {
+    #[serde(flatten)]
+    pub estimate: CostEstimate,
+    /// `None` for the most expensive preset
+    pub next_tier: Option<NextTierEstimate>,
+}
+
+#[derive(Deserialize, Validate, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RedeemPromoCodeRequest {
+    #[validate(length(min = 1, max = 64))]
+    pub code: String,
+}
+
+#[derive(Serialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PromoRedemptionResponse {
+    pub code: String,
+    pub discount_percent: i32,
+    /// The discount covers the charges of the next calendar month
+    pub discount_starts_at: DateTime<Utc>,
+    pub discount_ends_at: DateTime<Utc>,
+    pub balance: BigDecimal,
+    pub currency: String,
+}
+
+#[derive(Deserialize, Validate, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePromoCodeRequest {
+    /// Stored upper-cased, redeeming is case-insensitive
+    #[validate(length(min = 3, max = 64))]
+    pub code: String,
+    #[validate(range(min = 1, max = 100))]
+    pub discount_percent: i32,
+    #[validate(range(min = 1))]
+    pub max_uses: i32,
+    /// Never expires when omitted
+    pub expires_at: Option<DateTime<Utc>>,
+}
@@ -77,6 +77,7 @@ impl From<ClaimsError> for AppError {
             ClaimsError::WrongType => AppError::WrongTokenTypeError,
             ClaimsError::Invalid => AppError::InvalidTokenError,
             ClaimsError::KeyError => AppError::KeyError,
+            ClaimsError::NotAdmin => AppError::Forbidden("Admin role required".into()),
         }
     }
 }
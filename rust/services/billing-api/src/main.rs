@@ -1,20 +1,16 @@
-pub mod app;
-pub mod config;
-pub mod error;
-pub mod features;
-pub mod implementations;
-pub mod services;
-pub mod utilities;
-
 use std::env;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::result::Result::Ok;
 
-use config::Config;
-use factory::factories::observability::Observability;
+use billing_api::{
+    app, config::Config, error::AppError,
+    services::kafka_consumer::implementations::start_kafka_consumer,
+};
+use factory::factories::{database::Database, observability::Observability};
 
-use tracing::info;
+use tokio::task::JoinSet;
+use tracing::{error, info};
 use utility::shutdown_signal::shutdown_signal;
 
 #[tokio::main]
@@ -46,6 +42,45 @@ async fn main() -> anyhow::Result<()> {
     )
     .await;
 
+    let database = Database::new(&cfg.database).await;
+    let pool_metrics = database.clone().report_metrics();
+
+    let mut set = JoinSet::new();
+    set.spawn(start_http_server(
+        cargo_pkg_name,
+        cargo_pkg_version,
+        cfg.clone(),
+    ));
+    set.spawn(start_kafka_consumer(cfg.kafka.clone(), database.pool));
+    set.spawn(async move {
+        pool_metrics.await;
+        Ok(())
+    });
+
+    // Unified shutdown logic
+    tokio::select! {
+        _ = shutdown_signal() => {
+            info!("🛑 Shutdown signal received");
+            set.shutdown().await;
+        }
+        Some(result) = set.join_next() => {
+            match result {
+                Ok(Ok(())) => error!("A background task exited unexpectedly!"),
+                Ok(Err(e)) => error!("Task failed: {}", e),
+                Err(e) => error!("Task panic: {}", e),
+            }
+            set.shutdown().await;
+        }
+    }
+
+    Ok(())
+}
+
+async fn start_http_server(
+    cargo_pkg_name: &'static str,
+    cargo_pkg_version: &'static str,
+    cfg: Config,
+) -> Result<(), AppError> {
     let app = app::app(cargo_pkg_name, cargo_pkg_version, &cfg).await?;
     let listener = tokio::net::TcpListener::bind(cfg.server_address).await?;
 
@@ -0,0 +1,434 @@
+use std::fmt::Write;
+
+use bigdecimal::{BigDecimal, RoundingMode};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::services::invoice_pdf::{Font, InvoiceDocument, PdfWriter};
+
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+const ROW_HEIGHT: f32 = 18.0;
+/// Right edges of the numeric columns
+const HOURS_COLUMN: f32 = 350.0;
+const RATE_COLUMN: f32 = 450.0;
+const AMOUNT_COLUMN: f32 = PAGE_WIDTH - MARGIN;
+const MAX_DESCRIPTION_CHARS: usize = 40;
+
+impl Font {
+    fn resource(self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+        }
+    }
+}
+
+impl PdfWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn text(&mut self, x: f32, y: f32, size: f32, font: Font, text: &str) {
+        let _ = writeln!(
+            self.current,
+            "BT /{} {:.1} Tf {:.2} {:.2} Td ({}) Tj ET",
+            font.resource(),
+            size,
+            x,
+            y,
+            escape(text)
+        );
+    }
+
+    pub fn text_right(&mut self, right: f32, y: f32, size: f32, font: Font, text: &str) {
+        self.text(right - text_width(text, size), y, size, font, text);
+    }
+
+    pub fn rule(&mut self, x1: f32, y1: f32, x2: f32, y2: f32) {
+        let _ = writeln!(
+            self.current,
+            "0.5 w {:.2} {:.2} m {:.2} {:.2} l S",
+            x1, y1, x2, y2
+        );
+    }
+
+    pub fn new_page(&mut self) {
+        self.pages.push(std::mem::take(&mut self.current));
+    }
+
+    /// Objects 1-4 are the catalog, page tree and fonts, every page adds a page and a content stream
+    pub fn finish(mut self) -> Vec<u8> {
+        if !self.current.is_empty() || self.pages.is_empty() {
+            self.new_page();
+        }
+
+        let page_ids: Vec<usize> = (0..self.pages.len()).map(|i| 5 + i * 2).collect();
+        let kids = page_ids
+            .iter()
+            .map(|id| format!("{} 0 R", id))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                kids,
+                page_ids.len()
+            ),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+                .to_string(),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+                .to_string(),
+        ];
+        for (page_id, content) in page_ids.iter().zip(&self.pages) {
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                 /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH,
+                PAGE_HEIGHT,
+                page_id + 1
+            ));
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{}endstream",
+                content.len(),
+                content
+            ));
+        }
+
+        let mut out = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+        }
+
+        let xref_offset = out.len();
+        let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(xref, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            xref,
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref_offset
+        );
+        out.extend_from_slice(xref.as_bytes());
+
+        out
+    }
+}
+
+impl InvoiceDocument {
+    pub fn render(&self) -> Vec<u8> {
+        let mut pdf = PdfWriter::new();
+        let mut y = PAGE_HEIGHT - MARGIN - 20.0;
+
+        pdf.text(MARGIN, y, 22.0, Font::Bold, "INVOICE");
+        pdf.text_right(AMOUNT_COLUMN, y, 14.0, Font::Bold, "Poddle");
+        y -= 36.0;
+
+        for (label, value) in [
+            ("Invoice number", self.number.as_str()),
+            ("Billing period", self.period.as_str()),
+            ("Issued", self.issued_at.as_str()),
+        ] {
+            pdf.text(MARGIN, y, 10.0, Font::Bold, label);
+            pdf.text(MARGIN + 100.0, y, 10.0, Font::Regular, value);
+            y -= 14.0;
+        }
+        y -= 14.0;
+
+        pdf.text(MARGIN, y, 10.0, Font::Bold, "Billed to");
+        y -= 14.0;
+        pdf.text(MARGIN, y, 10.0, Font::Regular, &self.customer_name);
+        y -= 14.0;
+        pdf.text(MARGIN, y, 10.0, Font::Regular, &self.customer_email);
+        y -= 32.0;
+
+        y = self.table_header(&mut pdf, y);
+        for line in &self.lines {
+            if y < MARGIN + ROW_HEIGHT * 3.0 {
+                pdf.new_page();
+                y = self.table_header(&mut pdf, PAGE_HEIGHT - MARGIN);
+            }
+
+            pdf.text(
+                MARGIN,
+                y,
+                10.0,
+                Font::Regular,
+                &truncate(&line.description, MAX_DESCRIPTION_CHARS),
+            );
+            pdf.text_right(
+                HOURS_COLUMN,
+                y,
+                10.0,
+                Font::Regular,
+                &format_decimal(&line.hours, 2),
+            );
+            pdf.text_right(
+                RATE_COLUMN,
+                y,
+                10.0,
+                Font::Regular,
+                &format_decimal(&line.rate, 4),
+            );
+            pdf.text_right(
+                AMOUNT_COLUMN,
+                y,
+                10.0,
+                Font::Regular,
+                &format_decimal(&line.amount, 2),
+            );
+            y -= ROW_HEIGHT;
+        }
+
+        pdf.rule(
+            MARGIN,
+            y + ROW_HEIGHT - 6.0,
+            AMOUNT_COLUMN,
+            y + ROW_HEIGHT - 6.0,
+        );
+        y -= 4.0;
+        pdf.text_right(RATE_COLUMN, y, 10.0, Font::Regular, "Subtotal");
+        pdf.text_right(
+            AMOUNT_COLUMN,
+            y,
+            10.0,
+            Font::Regular,
+            &format_decimal(&self.subtotal, 2),
+        );
+        y -= ROW_HEIGHT;
+        pdf.text_right(RATE_COLUMN, y, 11.0, Font::Bold, "Total");
+        pdf.text_right(
+            AMOUNT_COLUMN,
+            y,
+            11.0,
+            Font::Bold,
+            &format!("{} {}", format_decimal(&self.total, 2), self.currency),
+        );
+
+        pdf.finish()
+    }
+
+    fn table_header(&self, pdf: &mut PdfWriter, y: f32) -> f32 {
+        pdf.text(MARGIN, y, 10.0, Font::Bold, "Deployment");
+        pdf.text_right(HOURS_COLUMN, y, 10.0, Font::Bold, "Hours");
+        pdf.text_right(RATE_COLUMN, y, 10.0, Font::Bold, "Rate / hour");
+        pdf.text_right(
+            AMOUNT_COLUMN,
+            y,
+            10.0,
+            Font::Bold,
+            &format!("Amount ({})", self.currency),
+        );
+        pdf.rule(MARGIN, y - 6.0, AMOUNT_COLUMN, y - 6.0);
+        y - ROW_HEIGHT - 4.0
+    }
+}
+
+fn format_decimal(value: &BigDecimal, scale: i64) -> String {
+    value
+        .with_scale_round(scale, RoundingMode::HalfUp)
+        .to_string()
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max_chars - 3).collect();
+    truncated.push_str("...");
+    truncated
+}
+
+/// String literal escaping. WinAnsiEncoding covers Latin-1 and a few typographic characters,
+/// anything else is transliterated so names and descriptions stay readable
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' '..='~' => escaped.push(c),
+            _ => match win_ansi_code(c) {
+                Some(code) => {
+                    let _ = write!(escaped, "\\{:03o}", code);
+                }
+                None => escaped.push_str(&escape(&transliterate(c))),
+            },
+        }
+    }
+    escaped
+}
+
+/// Code of a non-ASCII character in WinAnsiEncoding, PDF 32000 annex D.2
+fn win_ansi_code(c: char) -> Option<u8> {
+    let code = match c {
+        '\u{a0}'..='\u{ff}' => c as u32 as u8,
+        '€' => 0x80,
+        '‚' => 0x82,
+        'ƒ' => 0x83,
+        '„' => 0x84,
+        '…' => 0x85,
+        '†' => 0x86,
+        '‡' => 0x87,
+        'ˆ' => 0x88,
+        '‰' => 0x89,
+        'Š' => 0x8a,
+        '‹' => 0x8b,
+        'Œ' => 0x8c,
+        'Ž' => 0x8e,
+        // Uzbek Latin writes oʻ and gʻ with modifier letters, the quotes look the same
+        '‘' | 'ʻ' => 0x91,
+        '’' | 'ʼ' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        '˜' => 0x98,
+        '™' => 0x99,
+        'š' => 0x9a,
+        '›' => 0x9b,
+        'œ' => 0x9c,
+        'ž' => 0x9e,
+        'Ÿ' => 0x9f,
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// Accented letters lose their marks, Cyrillic is romanized, whatever is left has no stand-in
+fn transliterate(c: char) -> String {
+    if let Some(latin) = stand_in(c) {
+        return latin.to_string();
+    }
+
+    let decomposed: String = c
+        .nfkd()
+        .filter(|d| *d == ' ' || d.is_ascii_graphic() || win_ansi_code(*d).is_some())
+        .collect();
+    if decomposed.is_empty() {
+        "?".to_string()
+    } else {
+        decomposed
+    }
+}
+
+/// Cyrillic romanized BGN/PCGN style, with the letters Uzbek and Kazakh add to the Russian
+/// alphabet. Latin letters with a stroke have no decomposition to drop the mark from
+fn stand_in(c: char) -> Option<&'static str> {
+    let latin = match c {
+        'Ł' => "L",
+        'ł' => "l",
+        'Đ' => "D",
+        'đ' => "d",
+        'ı' => "i",
+        'а' => "a",
+        'б' => "b",
+        'в' => "v",
+        'г' => "g",
+        'д' => "d",
+        'е' => "e",
+        'ё' => "yo",
+        'ж' => "zh",
+        'з' => "z",
+        'и' => "i",
+        'й' => "y",
+        'к' => "k",
+        'л' => "l",
+        'м' => "m",
+        'н' => "n",
+        'о' => "o",
+        'п' => "p",
+        'р' => "r",
+        'с' => "s",
+        'т' => "t",
+        'у' => "u",
+        'ф' => "f",
+        'х' => "kh",
+        'ц' => "ts",
+        'ч' => "ch",
+        'ш' => "sh",
+        'щ' => "shch",
+        'ъ' | 'ь' => "",
+        'ы' => "y",
+        'э' => "e",
+        'ю' => "yu",
+        'я' => "ya",
+        'ў' => "oʻ",
+        'қ' => "q",
+        'ғ' => "gʻ",
+        'ҳ' => "h",
+        'ә' => "a",
+        'ң' => "ng",
+        'ө' => "o",
+        'ұ' | 'ү' => "u",
+        'і' => "i",
+        'А' => "A",
+        'Б' => "B",
+        'В' => "V",
+        'Г' => "G",
+        'Д' => "D",
+        'Е' => "E",
+        'Ё' => "Yo",
+        'Ж' => "Zh",
+        'З' => "Z",
+        'И' => "I",
+        'Й' => "Y",
+        'К' => "K",
+        'Л' => "L",
+        'М' => "M",
+        'Н' => "N",
+        'О' => "O",
+        'П' => "P",
+        'Р' => "R",
+        'С' => "S",
+        'Т' => "T",
+        'У' => "U",
+        'Ф' => "F",
+        'Х' => "Kh",
+        'Ц' => "Ts",
+        'Ч' => "Ch",
+        'Ш' => "Sh",
+        'Щ' => "Shch",
+        'Ъ' | 'Ь' => "",
+        'Ы' => "Y",
+        'Э' => "E",
+        'Ю' => "Yu",
+        'Я' => "Ya",
+        'Ў' => "Oʻ",
+        'Қ' => "Q",
+        'Ғ' => "Gʻ",
+        'Ҳ' => "H",
+        'Ә' => "A",
+        'Ң' => "Ng",
+        'Ө' => "O",
+        'Ұ' | 'Ү' => "U",
+        'І' => "I",
+        _ => return None,
+    };
+    Some(latin)
+}
+
+/// Helvetica advance widths, only exact for digits and punctuation which is all that gets right aligned
+fn text_width(text: &str, size: f32) -> f32 {
+    let units: u32 = text
+        .chars()
+        .map(|c| match c {
+            '.' | ',' | ' ' | '/' => 278,
+            '(' | ')' => 333,
+            'i' | 'l' => 222,
+            'm' | 'w' => 833,
+            'A'..='Z' => 667,
+            _ => 556,
+        })
+        .sum();
+    units as f32 * size / 1000.0
+}
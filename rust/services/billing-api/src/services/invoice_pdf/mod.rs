@@ -0,0 +1,39 @@
+pub mod implementations;
+
+use bigdecimal::BigDecimal;
+
+/// Everything printed on an invoice, one line per deployment
+#[derive(Debug)]
+pub struct InvoiceDocument {
+    pub number: String,
+    pub period: String,
+    pub issued_at: String,
+    pub customer_name: String,
+    pub customer_email: String,
+    pub currency: String,
+    pub lines: Vec<InvoiceLine>,
+    pub subtotal: BigDecimal,
+    pub total: BigDecimal,
+}
+
+#[derive(Debug)]
+pub struct InvoiceLine {
+    pub description: String,
+    pub hours: BigDecimal,
+    pub rate: BigDecimal,
+    pub amount: BigDecimal,
+}
+
+/// Bare PDF 1.4 writer, A4 pages with base-14 Helvetica text and straight rules.
+/// Enough for invoices without pulling a layout engine into the service.
+#[derive(Default)]
+pub struct PdfWriter {
+    pages: Vec<String>,
+    current: String,
+}
+
+#[derive(Clone, Copy)]
+pub enum Font {
+    Regular,
+    Bold,
+}
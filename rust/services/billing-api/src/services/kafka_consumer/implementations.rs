@@ -0,0 +1,155 @@
+use std::time::Duration;
+
+use billing_core::schemas::BillingChargedEvent;
+use compute_core::schemas::DeploymentDeletedEvent;
+use factory::factories::kafka::{Kafka, KafkaConfig};
+use rdkafka::{
+    Message,
+    consumer::{CommitMode, Consumer},
+    message::{BorrowedMessage, Header, OwnedHeaders},
+    producer::{FutureProducer, FutureRecord},
+};
+use sqlx::PgPool;
+use tracing::{error, info, warn};
+
+use crate::{
+    error::AppError,
+    features::repository::BillingRepository,
+    services::kafka_consumer::{
+        BILLING_CHARGED_TOPIC, BILLING_DLQ_TOPIC, BillingConsumerContext, CONSUMER_GROUP_ID,
+        DEPLOYMENT_DELETED_TOPIC, MAX_ATTEMPTS,
+    },
+};
+
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Handles messages one at a time, an offset is committed only after its message was handled
+/// or dead-lettered
+pub async fn start_kafka_consumer(cfg: KafkaConfig, pool: PgPool) -> Result<(), AppError> {
+    let consumer = Kafka::manual_commit_consumer(&cfg, CONSUMER_GROUP_ID, BillingConsumerContext)?;
+    let producer = Kafka::producer(&cfg)?;
+
+    consumer.subscribe(&[BILLING_CHARGED_TOPIC, DEPLOYMENT_DELETED_TOPIC])?;
+    info!(
+        "🎧 Kafka consumer subscribed to {} and {}",
+        BILLING_CHARGED_TOPIC, DEPLOYMENT_DELETED_TOPIC
+    );
+
+    loop {
+        let message = match consumer.recv().await {
+            Ok(message) => message,
+            Err(e) => {
+                error!("❌ Kafka consumer error: {}", e);
+                continue;
+            }
+        };
+
+        process_message(&message, &pool, &producer).await;
+
+        if let Err(e) = consumer.commit_message(&message, CommitMode::Async) {
+            warn!(
+                topic = message.topic(),
+                offset = message.offset(),
+                "⚠️ Failed to commit offset: {}",
+                e
+            );
+        }
+    }
+}
+
+#[tracing::instrument(
+    name = "kafka_consumer.process_message",
+    skip_all,
+    fields(topic = message.topic(), partition = message.partition(), offset = message.offset())
+)]
+async fn process_message(message: &BorrowedMessage<'_>, pool: &PgPool, producer: &FutureProducer) {
+    let payload = message.payload().unwrap_or_default();
+
+    let mut last_error = String::new();
+    for attempt in 1..=MAX_ATTEMPTS {
+        match handle_message(message.topic(), payload, pool).await {
+            Ok(()) => return,
+            Err(e) => {
+                warn!(attempt, "⚠️ Failed to handle message: {}", e);
+                last_error = e.to_string();
+            }
+        }
+
+        if attempt < MAX_ATTEMPTS {
+            tokio::time::sleep(RETRY_BACKOFF * attempt).await;
+        }
+    }
+
+    if let Err(e) = dead_letter(message, &last_error, producer).await {
+        error!(
+            "❌ Failed to publish to {}, dropping message: {}",
+            BILLING_DLQ_TOPIC, e
+        );
+    }
+}
+
+async fn handle_message(topic: &str, payload: &[u8], pool: &PgPool) -> Result<(), AppError> {
+    match topic {
+        BILLING_CHARGED_TOPIC => {
+            let event: BillingChargedEvent = serde_json::from_slice(payload)?;
+            if !BillingRepository::record_charge(&event, pool).await? {
+                info!(billing_id = %event.billing_id, "⏭️ Charge was already recorded");
+            }
+        }
+        DEPLOYMENT_DELETED_TOPIC => {
+            let event: DeploymentDeletedEvent = serde_json::from_slice(payload)?;
+            let finalized =
+                BillingRepository::finalize_deployment_billings(&event.deployment_id, pool).await?;
+            info!(
+                deployment_id = %event.deployment_id,
+                "🧾 Finalized {} billing records", finalized
+            );
+        }
+        topic => warn!("⚠️ Ignoring message of unexpected topic {}", topic),
+    }
+
+    Ok(())
+}
+
+/// Original key and payload are kept, where it came from and why it failed go into headers
+async fn dead_letter(
+    message: &BorrowedMessage<'_>,
+    error: &str,
+    producer: &FutureProducer,
+) -> Result<(), AppError> {
+    let partition = message.partition().to_string();
+    let offset = message.offset().to_string();
+    let headers = OwnedHeaders::new()
+        .insert(Header {
+            key: "source-topic",
+            value: Some(message.topic()),
+        })
+        .insert(Header {
+            key: "source-partition",
+            value: Some(&partition),
+        })
+        .insert(Header {
+            key: "source-offset",
+            value: Some(&offset),
+        })
+        .insert(Header {
+            key: "error",
+            value: Some(error),
+        });
+
+    let mut record = FutureRecord::to(BILLING_DLQ_TOPIC)
+        .payload(message.payload().unwrap_or_default())
+        .headers(headers);
+    if let Some(key) = message.key() {
+        record = record.key(key);
+    }
+
+    producer
+        .send(record, Duration::from_secs(5))
+        .await
+        .map_err(|(e, _)| e)?;
+
+    warn!("📮 Message moved to {}", BILLING_DLQ_TOPIC);
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+pub mod implementations;
+
+use rdkafka::{
+    ClientContext, TopicPartitionList,
+    consumer::{BaseConsumer, CommitMode, Consumer, ConsumerContext, Rebalance},
+    error::KafkaResult,
+};
+use tracing::{error, info, warn};
+
+pub const BILLING_CHARGED_TOPIC: &str = "billing.charged";
+pub const DEPLOYMENT_DELETED_TOPIC: &str = "compute.deployment_deleted";
+/// Messages still failing after `MAX_ATTEMPTS` are parked here with the error in their headers
+pub const BILLING_DLQ_TOPIC: &str = "billing.dlq";
+pub const MAX_ATTEMPTS: u32 = 3;
+pub const CONSUMER_GROUP_ID: &str = "billing-api-group";
+
+/// Offsets are committed asynchronously per message, the ones of revoked partitions are
+/// flushed synchronously so the next owner does not handle them again
+pub struct BillingConsumerContext;
+
+impl ClientContext for BillingConsumerContext {}
+
+impl ConsumerContext for BillingConsumerContext {
+    fn pre_rebalance(&self, base_consumer: &BaseConsumer<Self>, rebalance: &Rebalance<'_>) {
+        match rebalance {
+            Rebalance::Revoke(partitions) => {
+                info!("🔄 Revoking {} partitions", partitions.count());
+                if partitions.count() > 0
+                    && let Err(e) = base_consumer.commit_consumer_state(CommitMode::Sync)
+                {
+                    warn!("⚠️ Failed to commit offsets of revoked partitions: {}", e);
+                }
+            }
+            Rebalance::Assign(partitions) => {
+                info!("🔄 Assigning {} partitions", partitions.count())
+            }
+            Rebalance::Error(e) => error!("❌ Kafka rebalance error: {}", e),
+        }
+    }
+
+    fn post_rebalance(&self, _base_consumer: &BaseConsumer<Self>, rebalance: &Rebalance<'_>) {
+        if let Rebalance::Assign(partitions) = rebalance {
+            info!("✅ Rebalanced, {} partitions assigned", partitions.count());
+        }
+    }
+
+    fn commit_callback(&self, result: KafkaResult<()>, _offsets: &TopicPartitionList) {
+        if let Err(e) = result {
+            warn!("⚠️ Failed to commit offsets: {}", e);
+        }
+    }
+}
@@ -0,0 +1,5 @@
+pub mod invoice_pdf;
+pub mod kafka_consumer;
+pub mod s3;
+pub mod stripe_client;
+pub mod users_client;
@@ -0,0 +1,107 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::AppError,
+    services::stripe_client::{PaymentIntent, StripeClient, StripeErrorResponse, StripeEvent},
+};
+
+const STRIPE_API_URL: &str = "https://api.stripe.com/v1";
+/// Signed webhooks older than this are rejected to limit replays
+const WEBHOOK_TOLERANCE_SECS: i64 = 300;
+
+impl StripeClient {
+    pub fn new(cfg: &Config) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            secret_key: cfg.stripe_secret_key.clone(),
+            webhook_secret: cfg.stripe_webhook_secret.clone(),
+        }
+    }
+
+    /// `amount` is in the currency's minor unit, `idempotency_key` makes retries return the same intent
+    #[tracing::instrument(name = "stripe_client.create_payment_intent", skip_all, fields(user_id = %user_id, amount = amount), err)]
+    pub async fn create_payment_intent(
+        &self,
+        user_id: &Uuid,
+        amount: i64,
+        currency: &str,
+        idempotency_key: &Uuid,
+    ) -> Result<PaymentIntent, AppError> {
+        let params = [
+            ("amount", amount.to_string()),
+            ("currency", currency.to_lowercase()),
+            ("automatic_payment_methods[enabled]", "true".to_string()),
+            ("metadata[user_id]", user_id.to_string()),
+        ];
+
+        let response = self
+            .http
+            .post(format!("{}/payment_intents", STRIPE_API_URL))
+            .basic_auth(&self.secret_key, None::<&str>)
+            .header("Idempotency-Key", idempotency_key.to_string())
+            .form(&params)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error = response.json::<StripeErrorResponse>().await.ok();
+            return Err(AppError::ExternalServiceError {
+                service: "stripe".to_string(),
+                code: error
+                    .as_ref()
+                    .and_then(|e| e.error.code.clone())
+                    .unwrap_or_else(|| status.to_string()),
+                message: error
+                    .and_then(|e| e.error.message)
+                    .unwrap_or_else(|| "Failed to create payment intent".to_string()),
+            });
+        }
+
+        Ok(response.json::<PaymentIntent>().await?)
+    }
+
+    /// Checks the `Stripe-Signature` header (`t=<ts>,v1=<hex hmac>`) against the raw body before parsing it
+    pub fn construct_event(
+        &self,
+        payload: &[u8],
+        signature: &str,
+    ) -> Result<StripeEvent, AppError> {
+        let mut timestamp = None;
+        let mut signatures = Vec::new();
+        for part in signature.split(',') {
+            match part.split_once('=') {
+                Some(("t", t)) => timestamp = t.parse::<i64>().ok(),
+                Some(("v1", v1)) => signatures.extend(hex::decode(v1).ok()),
+                _ => {}
+            }
+        }
+
+        let timestamp = timestamp
+            .ok_or_else(|| AppError::BadRequest("Malformed Stripe-Signature header".to_string()))?;
+        if (chrono::Utc::now().timestamp() - timestamp).abs() > WEBHOOK_TOLERANCE_SECS {
+            return Err(AppError::BadRequest(
+                "Stripe webhook timestamp outside tolerance".to_string(),
+            ));
+        }
+
+        let verified = signatures.iter().any(|expected| {
+            let mut mac = Hmac::<Sha256>::new_from_slice(self.webhook_secret.as_bytes())
+                .expect("HMAC accepts keys of any size");
+            mac.update(timestamp.to_string().as_bytes());
+            mac.update(b".");
+            mac.update(payload);
+            mac.verify_slice(expected).is_ok()
+        });
+        if !verified {
+            return Err(AppError::BadRequest(
+                "Invalid Stripe webhook signature".to_string(),
+            ));
+        }
+
+        Ok(serde_json::from_slice(payload)?)
+    }
+}
@@ -0,0 +1,44 @@
+pub mod implementations;
+
+use serde::Deserialize;
+
+/// Minimal Stripe REST client, covers the PaymentIntent flow used for balance top-ups
+#[derive(Clone)]
+pub struct StripeClient {
+    pub http: reqwest::Client,
+    pub secret_key: String,
+    pub webhook_secret: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PaymentIntent {
+    pub id: String,
+    pub amount: i64,
+    pub currency: String,
+    pub status: String,
+    pub client_secret: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct StripeEvent {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub data: StripeEventData,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct StripeEventData {
+    pub object: serde_json::Value,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct StripeErrorResponse {
+    pub error: StripeErrorBody,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct StripeErrorBody {
+    pub code: Option<String>,
+    pub message: Option<String>,
+}
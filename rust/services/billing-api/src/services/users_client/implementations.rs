@@ -0,0 +1,40 @@
+use users_core::jwt::{TokenType, create_token};
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::AppError,
+    services::users_client::{UserProfile, UsersClient},
+};
+
+impl UsersClient {
+    pub fn new(cfg: &Config) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: cfg.users_api_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Calls the profile endpoint on behalf of the user with a freshly signed access token
+    #[tracing::instrument(name = "users_client.get_profile", skip_all, fields(user_id = %user_id), err)]
+    pub async fn get_profile(&self, user_id: Uuid, cfg: &Config) -> Result<UserProfile, AppError> {
+        let token = create_token(cfg, user_id, TokenType::Access)?;
+
+        let response = self
+            .http
+            .get(format!("{}/api/v1/users/profile", self.base_url))
+            .bearer_auth(token)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(AppError::ExternalServiceError {
+                service: "users-api".to_string(),
+                code: response.status().to_string(),
+                message: "Failed to fetch user profile".to_string(),
+            });
+        }
+
+        Ok(response.json::<UserProfile>().await?)
+    }
+}
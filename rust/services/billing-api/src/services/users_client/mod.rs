@@ -0,0 +1,16 @@
+pub mod implementations;
+
+use serde::Deserialize;
+
+/// Reads user profiles from users-api, billing does not own user data
+#[derive(Clone)]
+pub struct UsersClient {
+    pub http: reqwest::Client,
+    pub base_url: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UserProfile {
+    pub username: String,
+    pub email: String,
+}
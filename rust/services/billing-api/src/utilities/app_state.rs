@@ -1,8 +1,13 @@
 use crate::config::Config;
 use crate::error::AppError;
+use crate::services::s3::build_s3;
+use crate::services::stripe_client::StripeClient;
+use crate::services::users_client::UsersClient;
 use axum::extract::FromRef;
 use axum_extra::extract::cookie::Key;
 use factory::factories::{amqp::Amqp, database::Database, kafka::Kafka, redis::Redis};
+use http_common::metrics::HttpMetrics;
+use object_store::aws::AmazonS3;
 use rustls::ClientConfig;
 use users_core::jwt::JwtCapability;
 
@@ -13,16 +18,25 @@ pub struct AppState {
     pub redis: Redis,
     pub amqp: Amqp,
     pub kafka: Option<Kafka>,
+    pub stripe_client: StripeClient,
+    pub users_client: UsersClient,
+    pub s3: AmazonS3,
     pub config: Config,
     pub key: Key,
+    pub metrics: HttpMetrics,
 }
 
 impl AppState {
     pub async fn init(cfg: &Config) -> Result<Self, AppError> {
         let database = Database::new(&cfg.database).await;
-        let redis = Redis::new(&cfg.redis).await;
+        let redis = Redis::new(&cfg.redis)
+            .await
+            .map_err(|e| AppError::InternalServerError(e.to_string()))?;
         let amqp = Amqp::new(&cfg.amqp).await;
         let key = Key::from(cfg.cookie_key.as_bytes());
+        let stripe_client = StripeClient::new(cfg);
+        let users_client = UsersClient::new(cfg);
+        let s3 = build_s3(&cfg.s3);
 
         Ok(Self {
             rustls_config: None,
@@ -30,8 +44,12 @@ impl AppState {
             redis,
             amqp,
             kafka: None,
+            stripe_client,
+            users_client,
+            s3,
             config: cfg.clone(),
             key,
+            metrics: HttpMetrics::new(),
         })
     }
 }
@@ -0,0 +1,57 @@
+use bigdecimal::BigDecimal;
+use billing_api::services::invoice_pdf::{InvoiceDocument, InvoiceLine};
+
+fn render(customer_name: &str, description: &str) -> String {
+    let document = InvoiceDocument {
+        number: "INV-2026-10-0001".to_string(),
+        period: "2026-10".to_string(),
+        issued_at: "2026-11-01".to_string(),
+        customer_name: customer_name.to_string(),
+        customer_email: "tester@poddle.test".to_string(),
+        currency: "UZS".to_string(),
+        lines: vec![InvoiceLine {
+            description: description.to_string(),
+            hours: BigDecimal::from(720),
+            rate: BigDecimal::from(100),
+            amount: BigDecimal::from(72000),
+        }],
+        subtotal: BigDecimal::from(72000),
+        total: BigDecimal::from(72000),
+    };
+
+    // Only the header comment is binary, every string literal is escaped to ASCII
+    String::from_utf8_lossy(&document.render()).into_owned()
+}
+
+#[test]
+fn latin_1_and_win_ansi_characters_are_encoded() {
+    let pdf = render("José Müller", "api “prod” – €");
+
+    assert!(pdf.contains(r"(Jos\351 M\374ller)"));
+    assert!(pdf.contains(r"(api \223prod\224 \226 \200)"));
+}
+
+#[test]
+fn cyrillic_is_romanized() {
+    let pdf = render("Камрон Ўзбеков", "Щётка");
+
+    assert!(pdf.contains(r"(Kamron O\221zbekov)"));
+    assert!(pdf.contains("(Shchyotka)"));
+}
+
+#[test]
+fn other_accented_letters_lose_their_marks() {
+    let pdf = render("Łukasz Dvořák", "Erdoğan ﬁle");
+
+    // á is Latin-1, ř and ğ decompose, Ł has a stand-in and ﬁ is a compatibility ligature
+    assert!(pdf.contains(r"(Lukasz Dvor\341k)"));
+    assert!(pdf.contains("(Erdogan file)"));
+}
+
+#[test]
+fn characters_without_a_stand_in_become_question_marks() {
+    let pdf = render("山田", "api (v2)");
+
+    assert!(pdf.contains("(??)"));
+    assert!(pdf.contains(r"(api \(v2\))"));
+}
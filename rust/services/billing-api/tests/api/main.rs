@@ -0,0 +1,7 @@
+//! Database level tests of billing-api, run them with `cargo test -p billing-api -- --ignored`
+//!
+//! Every test gets a fresh Postgres database from `#[sqlx::test]` (`DATABASE_URL`), `invoice_pdf`
+//! runs without one
+
+mod invoice_pdf;
+mod promo_codes;
@@ -0,0 +1,90 @@
+use billing_api::{
+    error::AppError,
+    features::{
+        handlers::redeem_promo_code, repository::BillingRepository, schemas::CreatePromoCodeRequest,
+    },
+};
+use sqlx::PgPool;
+use tokio::task::JoinSet;
+use uuid::Uuid;
+
+async fn create_user(name: &str, pool: &PgPool) -> Uuid {
+    sqlx::query_scalar("INSERT INTO users (username, email) VALUES ($1, $2) RETURNING id")
+        .bind(name)
+        .bind(format!("{}@poddle.test", name))
+        .fetch_one(pool)
+        .await
+        .expect("Failed to create test user")
+}
+
+async fn create_promo_code(code: &str, max_uses: i32, pool: &PgPool) {
+    let req = CreatePromoCodeRequest {
+        code: code.to_string(),
+        discount_percent: 20,
+        max_uses,
+        expires_at: None,
+    };
+    BillingRepository::create_promo_code(&req, pool)
+        .await
+        .expect("Failed to create promo code")
+        .expect("Promo code already exists");
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+#[ignore = "needs Postgres"]
+async fn concurrent_redemptions_never_exceed_max_uses(pool: PgPool) {
+    create_promo_code("SAVE20", 3, &pool).await;
+
+    let mut users = Vec::new();
+    for i in 0..8 {
+        users.push(create_user(&format!("redeemer{}", i), &pool).await);
+    }
+
+    // All of them start before any commits, the code only has room for three
+    let mut set = JoinSet::new();
+    for user_id in users {
+        let pool = pool.clone();
+        set.spawn(async move { redeem_promo_code(user_id, "save20", &pool).await });
+    }
+    let (redeemed, rejected): (Vec<_>, Vec<_>) =
+        set.join_all().await.into_iter().partition(Result::is_ok);
+
+    assert_eq!(redeemed.len(), 3);
+    for result in rejected {
+        assert!(
+            matches!(result, Err(AppError::BadRequest(ref msg)) if msg.contains("fully redeemed")),
+            "unexpected result {:?}",
+            result.map(|r| r.code)
+        );
+    }
+
+    let uses_so_far: i32 =
+        sqlx::query_scalar("SELECT uses_so_far FROM promo_codes WHERE code = 'SAVE20'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(uses_so_far, 3);
+
+    let discounted: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM balances WHERE discount_percent = 20")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(discounted, 3);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+#[ignore = "needs Postgres"]
+async fn promo_code_is_redeemed_once_per_user(pool: PgPool) {
+    create_promo_code("WELCOME", 10, &pool).await;
+    let user_id = create_user("returning", &pool).await;
+
+    let redemption = redeem_promo_code(user_id, "WELCOME", &pool)
+        .await
+        .expect("First redemption failed");
+    assert_eq!(redemption.discount_percent, 20);
+    assert!(redemption.discount_starts_at > chrono::Utc::now());
+
+    let again = redeem_promo_code(user_id, "WELCOME", &pool).await;
+    assert!(matches!(again, Err(AppError::BadRequest(_))));
+}
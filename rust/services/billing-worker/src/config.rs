@@ -0,0 +1,33 @@
+use std::{net::SocketAddr, path::PathBuf};
+
+use config::{ConfigBuilder, ConfigError, Environment, File, builder::AsyncState};
+use factory::factories::{
+    amqp::AmqpConfig, database::DatabaseConfig, kafka::KafkaConfig,
+    observability::ObservabilityConfig, redis::RedisConfig,
+};
+use serde::Deserialize;
+
+use crate::services::billing::BillingConfig;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Config {
+    pub server_address: SocketAddr,
+    pub observability: ObservabilityConfig,
+    pub database: DatabaseConfig,
+    pub kafka: KafkaConfig,
+    pub amqp: AmqpConfig,
+    pub redis: RedisConfig,
+    pub billing: BillingConfig,
+}
+
+impl Config {
+    pub async fn init(path: PathBuf) -> Result<Self, ConfigError> {
+        let cfg = ConfigBuilder::<AsyncState>::default()
+            .add_source(File::from(path))
+            .add_source(Environment::default())
+            .build()
+            .await?;
+
+        cfg.try_deserialize()
+    }
+}
@@ -0,0 +1,31 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum AppError {
+    #[error("Unexpected server error")]
+    Unexpected,
+
+    #[error("Serde json error: {0}")]
+    SerdejsonError(#[from] serde_json::Error),
+
+    #[error("Internal server error: {0}")]
+    InternalServerError(String),
+
+    #[error("IO error, {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Database error: {0}")]
+    SqlxError(#[from] sqlx::Error),
+
+    #[error("Kafka error: {0}")]
+    KafkaError(#[from] factory::factories::kafka::error::KafkaError),
+
+    #[error("Kafka delivery error: {0}")]
+    KafkaDeliveryError(#[from] rdkafka::error::KafkaError),
+
+    #[error("AMQP error: {0}")]
+    AmqpError(#[from] factory::factories::amqp::error::AmqpError),
+
+    #[error("Redis error: {0}")]
+    RedisError(#[from] redis::RedisError),
+}
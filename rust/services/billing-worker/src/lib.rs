@@ -0,0 +1,4 @@
+pub mod app;
+pub mod config;
+pub mod error;
+pub mod services;
@@ -1,33 +1,113 @@
-// #[tokio::main]
-// async fn main() {
-//     let mut interval = tokio::time::interval(Duration::from_secs(3600)); // 1 hour
-
-//     loop {
-//         interval.tick().await;
-
-//         // Process ALL running deployments
-//         let deployments = sqlx::query!("SELECT * FROM deployments WHERE status = 'running'")
-//             .fetch_all(&pool)
-//             .await?;
-
-//         for deployment in deployments {
-//             let cost = calculate_hourly_cost(&deployment);
-
-//             // This triggers the DB function that deducts from balance
-//             sqlx::query!(
-//                 "INSERT INTO billings (user_id, deployment_id, cost_per_hour, hours_used)
-//                  VALUES ($1, $2, $3, 1.0)",
-//                 deployment.user_id,
-//                 deployment.id,
-//                 cost
-//             )
-//             .execute(&pool)
-//             .await?;
-//         }
-
-//         // Check for negative balances and suspend deployments
-//         check_and_suspend_negative_balances(&pool).await?;
-//     }
-// }
-
-fn main() {}
+use std::path::PathBuf;
+use std::{env, net::SocketAddr};
+
+use factory::factories::{
+    amqp::Amqp, database::Database, kafka::Kafka, observability::Observability, redis::Redis,
+};
+
+use tokio::task::JoinSet;
+use tracing::{error, info};
+use utility::shutdown_signal::shutdown_signal;
+
+use billing_worker::{app, config::Config, error::AppError, services::billing::BillingWorker};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    rustls::crypto::ring::default_provider()
+        .install_default()
+        .expect("Failed to install rustls crypto provider");
+
+    // These are baked at COMPILE time
+    let cargo_manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let cargo_crate_name = env!("CARGO_CRATE_NAME");
+    let cargo_pkg_name = env!("CARGO_PKG_NAME");
+    let cargo_pkg_version = env!("CARGO_PKG_VERSION");
+
+    let env_path = cargo_manifest_dir.join(".env");
+
+    // Load service-specific .env
+    dotenvy::from_path(&env_path).ok();
+    // Load workspace root .env as fallback
+    dotenvy::dotenv().ok();
+
+    let path = env::var("CONFIG").unwrap_or("config.json".to_string());
+    let full_path = cargo_manifest_dir.join(path);
+    let cfg = Config::init(full_path).await?;
+
+    let _guard = Observability::init(
+        cargo_crate_name.to_string(),
+        cargo_pkg_version.to_string(),
+        &cfg.observability,
+    )
+    .await;
+
+    // Initialize services
+    let database = Database::new(&cfg.database).await;
+    let pool_metrics = database.clone().report_metrics();
+    let kafka = Kafka::new(&cfg.kafka, "billing-worker-group")?;
+    let amqp = Amqp::new(&cfg.amqp).await;
+    let redis = Redis::new(&cfg.redis).await?;
+
+    let mut set = JoinSet::new();
+    let worker = BillingWorker::new(
+        database.pool,
+        kafka.producer,
+        amqp,
+        redis,
+        cfg.billing.clone(),
+    );
+
+    // Spawn background tasks
+    set.spawn(worker.run());
+    set.spawn(async move {
+        pool_metrics.await;
+        Ok(())
+    });
+    set.spawn(start_health_server(
+        cargo_pkg_name,
+        cargo_pkg_version,
+        cfg.server_address,
+    ));
+
+    info!("✅ All background tasks started");
+
+    // Unified shutdown logic
+    tokio::select! {
+        _ = shutdown_signal() => {
+            info!("🛑 Shutdown signal received");
+            set.shutdown().await;
+        }
+        Some(result) = set.join_next() => {
+            match result {
+                Ok(Ok(())) => error!("A background task exited unexpectedly!"),
+                Ok(Err(e)) => error!("Task failed: {}", e),
+                Err(e) => error!("Task panic: {}", e),
+            }
+            set.shutdown().await;
+        }
+    }
+
+    Ok(())
+}
+
+// Start a simple HTTP server for health checks and metrics
+async fn start_health_server(
+    cargo_pkg_name: &'static str,
+    cargo_pkg_version: &'static str,
+    addr: SocketAddr,
+) -> Result<(), AppError> {
+    let app = app::app(cargo_pkg_name, cargo_pkg_version).await?;
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+
+    info!("🚀 {} service running at {:#?}", cargo_pkg_name, addr);
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await?;
+
+    println!("👋 Shutting down gracefully...");
+
+    Ok(())
+}
@@ -0,0 +1,249 @@
+use std::time::Duration;
+
+use billing_core::schemas::{BillingChargedEvent, Money};
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use compute_core::{cache_keys::CacheKeys, schemas::SuspendDeploymentMessage};
+use factory::factories::{amqp::Amqp, redis::Redis};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use redis::{AsyncTypedCommands, ExistenceCheck, SetExpiry, SetOptions};
+use sqlx::PgPool;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    services::{
+        billing::{BillingConfig, BillingWorker},
+        repository::{AddonPriceRow, BillableDeploymentRow, BillingRepository, BillingRow},
+    },
+};
+
+const BILLING_CHARGED_TOPIC: &str = "billing.charged";
+/// A user with a negative balance is looked at no more than once per this window
+const SUSPENSION_CHECK_WINDOW_SECS: u64 = 3600;
+
+impl BillingWorker {
+    pub fn new(
+        pool: PgPool,
+        producer: FutureProducer,
+        amqp: Amqp,
+        redis: Redis,
+        cfg: BillingConfig,
+    ) -> Self {
+        Self {
+            pool,
+            producer,
+            amqp,
+            redis,
+            cfg,
+        }
+    }
+
+    pub async fn run(self) -> Result<(), AppError> {
+        info!(
+            "💰 Starting billing worker, tick interval: {}s",
+            self.cfg.tick_interval_secs
+        );
+
+        let mut interval = tokio::time::interval(Duration::from_secs(self.cfg.tick_interval_secs));
+
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = self.bill_previous_hour(Utc::now()).await {
+                error!("❌ Failed to bill deployments: {}", e);
+            }
+
+            if let Err(e) = self.check_and_suspend_negative_balances().await {
+                error!(
+                    "❌ Failed to suspend deployments of negative balances: {}",
+                    e
+                );
+            }
+        }
+    }
+
+    /// Bills the last full hour before `now`, calling it again for the same hour charges nothing
+    pub async fn bill_previous_hour(&self, now: DateTime<Utc>) -> Result<usize, AppError> {
+        let period_end = now
+            .duration_trunc(TimeDelta::hours(1))
+            .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+        let period_start = period_end - TimeDelta::hours(1);
+
+        self.bill_period(period_start, period_end).await
+    }
+
+    /// Returns how many deployments were charged
+    #[tracing::instrument(name = "billing_worker.bill_period", skip_all, fields(period_start = %period_start), err)]
+    pub async fn bill_period(
+        &self,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+    ) -> Result<usize, AppError> {
+        let deployments =
+            BillingRepository::get_billable_deployments(period_start, period_end, &self.pool)
+                .await?;
+        if deployments.is_empty() {
+            return Ok(0);
+        }
+
+        let addon_prices = BillingRepository::get_addon_prices(&self.pool).await?;
+
+        let mut charged = 0;
+        for deployment in &deployments {
+            // One failing deployment must not block billing everybody else
+            match self.charge(deployment, &addon_prices, period_start).await {
+                Ok(true) => charged += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    error!(deployment_id = %deployment.id, "❌ Failed to charge deployment: {}", e)
+                }
+            }
+        }
+
+        info!(
+            "💰 Billed {}/{} deployments for {}",
+            charged,
+            deployments.len(),
+            period_start
+        );
+
+        Ok(charged)
+    }
+
+    /// `false` means another run got there first
+    async fn charge(
+        &self,
+        deployment: &BillableDeploymentRow,
+        addon_prices: &AddonPriceRow,
+        period_start: DateTime<Utc>,
+    ) -> Result<bool, AppError> {
+        let Some(billing) =
+            Self::record_charge(deployment, addon_prices, period_start, &self.pool).await?
+        else {
+            return Ok(false);
+        };
+
+        let event = BillingChargedEvent {
+            billing_id: billing.id,
+            user_id: deployment.user_id,
+            deployment_id: deployment.id,
+            billing_period: period_start,
+            amount: Money {
+                amount: billing.total_cost,
+                currency: deployment.currency.clone(),
+            },
+        };
+
+        // The charge is already committed, a lost event is logged rather than retried
+        if let Err(e) = self.publish_charged(&event).await {
+            warn!(billing_id = %event.billing_id, "⚠️ Failed to publish {}: {}", BILLING_CHARGED_TOPIC, e);
+        }
+
+        Ok(true)
+    }
+
+    /// Snapshot and ledger entry are committed together, `None` when the deployment was already
+    /// billed for this period
+    pub async fn record_charge(
+        deployment: &BillableDeploymentRow,
+        addon_prices: &AddonPriceRow,
+        period_start: DateTime<Utc>,
+        pool: &PgPool,
+    ) -> Result<Option<BillingRow>, AppError> {
+        let mut tx = pool.begin().await?;
+
+        let Some(billing) =
+            BillingRepository::create(deployment, addon_prices, period_start, &mut tx).await?
+        else {
+            return Ok(None);
+        };
+
+        let detail = format!(
+            "Usage charge for {}",
+            period_start.format("%Y-%m-%d %H:00 UTC")
+        );
+        BillingRepository::create_usage_charge(&deployment.user_id, &billing, &detail, &mut tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(billing))
+    }
+
+    async fn publish_charged(&self, event: &BillingChargedEvent) -> Result<(), AppError> {
+        let payload = serde_json::to_vec(event)?;
+        let key = event.user_id.to_string();
+
+        self.producer
+            .send(
+                FutureRecord::to(BILLING_CHARGED_TOPIC)
+                    .key(&key)
+                    .payload(&payload),
+                Duration::from_secs(5),
+            )
+            .await
+            .map_err(|(e, _)| e)?;
+
+        Ok(())
+    }
+
+    /// Suspends every running deployment of users who went below zero, returns how many were suspended
+    #[tracing::instrument(
+        name = "billing_worker.check_and_suspend_negative_balances",
+        skip_all,
+        err
+    )]
+    pub async fn check_and_suspend_negative_balances(&self) -> Result<usize, AppError> {
+        let user_ids = BillingRepository::get_negative_balance_users(&self.pool).await?;
+
+        let mut suspended = 0;
+        for user_id in &user_ids {
+            let checked_key = CacheKeys::user_balance_suspension_checked(&user_id.to_string());
+            let mut con = self.redis.con.clone();
+            let options = SetOptions::default()
+                .conditional_set(ExistenceCheck::NX)
+                .with_expiration(SetExpiry::EX(SUSPENSION_CHECK_WINDOW_SECS));
+            if con.set_options(&checked_key, 1, options).await?.is_none() {
+                continue;
+            }
+
+            match self.suspend_user_deployments(user_id).await {
+                Ok(count) => suspended += count,
+                Err(e) => error!(user_id = %user_id, "❌ Failed to suspend deployments: {}", e),
+            }
+        }
+
+        if suspended > 0 {
+            info!(
+                "⏸️ Suspended {} deployments of {} users with a negative balance",
+                suspended,
+                user_ids.len()
+            );
+        }
+
+        Ok(suspended)
+    }
+
+    async fn suspend_user_deployments(&self, user_id: &Uuid) -> Result<usize, AppError> {
+        let deployments =
+            BillingRepository::get_suspendable_deployments(user_id, &self.pool).await?;
+
+        for deployment in &deployments {
+            let message = SuspendDeploymentMessage {
+                user_id: *user_id,
+                project_id: deployment.project_id,
+                deployment_id: deployment.id,
+                timestamp: Utc::now().timestamp(),
+            };
+            self.amqp
+                .basic_publish("compute", "compute.suspend", &message)
+                .await?;
+
+            // Marked after publishing, a deployment flagged suspended is no longer billed
+            BillingRepository::mark_suspended_for_balance(&deployment.id, &self.pool).await?;
+        }
+
+        Ok(deployments.len())
+    }
+}
@@ -0,0 +1,21 @@
+pub mod implementations;
+
+use factory::factories::{amqp::Amqp, redis::Redis};
+use rdkafka::producer::FutureProducer;
+use serde::Deserialize;
+use sqlx::PgPool;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BillingConfig {
+    /// How often the worker looks for unbilled hours, billing is idempotent so this can be shorter than an hour
+    pub tick_interval_secs: u64,
+}
+
+#[derive(Clone)]
+pub struct BillingWorker {
+    pub pool: PgPool,
+    pub producer: FutureProducer,
+    pub amqp: Amqp,
+    pub redis: Redis,
+    pub cfg: BillingConfig,
+}
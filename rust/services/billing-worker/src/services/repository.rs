@@ -0,0 +1,245 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool, Postgres, Transaction};
+use tracing::instrument;
+use uuid::Uuid;
+
+/// Deployment that held cluster resources during a billing period, priced for its owner
+#[derive(FromRow, Debug)]
+pub struct BillableDeploymentRow {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub desired_replicas: i32,
+    pub preset_cpu_millicores: i32,
+    pub preset_memory_mb: i32,
+    pub preset_hourly_price: BigDecimal,
+    pub addon_cpu_millicores: i32,
+    pub addon_memory_mb: i32,
+    pub hours_used: BigDecimal,
+    pub currency: String,
+}
+
+#[derive(FromRow, Debug)]
+pub struct AddonPriceRow {
+    pub cpu_hourly_unit_price: BigDecimal,
+    pub memory_hourly_unit_price: BigDecimal,
+}
+
+#[derive(FromRow, Debug)]
+pub struct BillingRow {
+    pub id: Uuid,
+    pub total_cost: BigDecimal,
+}
+
+#[derive(FromRow, Debug)]
+pub struct SuspendableDeploymentRow {
+    pub id: Uuid,
+    pub project_id: Uuid,
+}
+
+pub struct BillingRepository;
+
+impl BillingRepository {
+    /// Deployments alive at some point of `[period_start, period_end)` and not billed for it yet.
+    /// Deployments created mid-period are charged for the fraction of the hour they existed.
+    /// A redeemed promo code discounts the preset price further while the period is in its month.
+    #[instrument("billing_repository.get_billable_deployments", skip_all, fields(period_start = %period_start), err)]
+    pub async fn get_billable_deployments(
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+        pool: &PgPool,
+    ) -> Result<Vec<BillableDeploymentRow>, sqlx::Error> {
+        sqlx::query_as!(
+            BillableDeploymentRow,
+            r#"
+            SELECT
+                d.id,
+                d.user_id,
+                d.desired_replicas,
+                p.cpu_millicores AS preset_cpu_millicores,
+                p.memory_mb AS preset_memory_mb,
+                ROUND(
+                    p.hourly_price
+                    * (1 - COALESCE(bt.discount_percent, 0) / 100)
+                    * (1 - CASE
+                        WHEN $1 >= bal.discount_starts_at AND $1 < bal.discount_ends_at
+                        THEN bal.discount_percent
+                        ELSE 0
+                    END / 100.0),
+                    6
+                ) AS "preset_hourly_price!",
+                COALESCE(d.addon_cpu_millicores, 0) AS "addon_cpu_millicores!",
+                COALESCE(d.addon_memory_mb, 0) AS "addon_memory_mb!",
+                ROUND(
+                    LEAST(1, EXTRACT(EPOCH FROM ($2 - GREATEST(d.created_at, $1))) / 3600),
+                    6
+                ) AS "hours_used!",
+                p.currency
+            FROM deployments d
+            JOIN presets p ON p.id = d.preset_id
+            JOIN users u ON u.id = d.user_id
+            LEFT JOIN billing_tiers bt ON bt.id = u.billing_tier_id
+            LEFT JOIN balances bal ON bal.user_id = d.user_id
+            WHERE d.status IN ('starting', 'running', 'degraded', 'updating', 'unhealthy')
+            AND NOT d.suspended_for_balance
+            AND d.created_at < $2
+            AND NOT EXISTS (
+                SELECT 1 FROM billings b
+                WHERE b.deployment_id = d.id
+                AND b.billing_period = $1
+            )
+            "#,
+            period_start,
+            period_end
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    #[instrument("billing_repository.get_addon_prices", skip_all, err)]
+    pub async fn get_addon_prices(pool: &PgPool) -> Result<AddonPriceRow, sqlx::Error> {
+        sqlx::query_as!(
+            AddonPriceRow,
+            r#"
+            SELECT cpu_hourly_unit_price, memory_hourly_unit_price
+            FROM addon_prices
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#
+        )
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Returns `None` when the deployment was already billed for this period
+    #[instrument("billing_repository.create", skip_all, fields(deployment_id = %deployment.id, period_start = %period_start), err)]
+    pub async fn create(
+        deployment: &BillableDeploymentRow,
+        addon_prices: &AddonPriceRow,
+        period_start: DateTime<Utc>,
+        tx: &mut Transaction<'_, Postgres>,
+    ) -> Result<Option<BillingRow>, sqlx::Error> {
+        sqlx::query_as!(
+            BillingRow,
+            r#"
+            INSERT INTO billings (
+                user_id,
+                deployment_id,
+                desired_replicas,
+                preset_cpu_millicores,
+                preset_memory_mb,
+                preset_hourly_price,
+                addon_cpu_millicores,
+                addon_memory_mb,
+                addon_cpu_millicores_hourly_price,
+                addon_memory_mb_hourly_price,
+                hours_used,
+                billing_period
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            ON CONFLICT (deployment_id, billing_period) DO NOTHING
+            RETURNING id, total_cost AS "total_cost!"
+            "#,
+            deployment.user_id,
+            deployment.id,
+            deployment.desired_replicas,
+            deployment.preset_cpu_millicores,
+            deployment.preset_memory_mb,
+            deployment.preset_hourly_price,
+            deployment.addon_cpu_millicores,
+            deployment.addon_memory_mb,
+            addon_prices.cpu_hourly_unit_price,
+            addon_prices.memory_hourly_unit_price,
+            deployment.hours_used,
+            period_start
+        )
+        .fetch_optional(&mut **tx)
+        .await
+    }
+
+    /// The ledger trigger deducts the amount from the user's balance
+    #[instrument("billing_repository.create_usage_charge", skip_all, fields(billing_id = %billing.id), err)]
+    pub async fn create_usage_charge(
+        user_id: &Uuid,
+        billing: &BillingRow,
+        detail: &str,
+        tx: &mut Transaction<'_, Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            INSERT INTO transactions (balance_id, billing_id, amount, type, detail)
+            SELECT b.id, $2, -$3::NUMERIC, 'usage_charge', $4
+            FROM balances b
+            WHERE b.user_id = $1
+            "#,
+            user_id,
+            billing.id,
+            billing.total_cost,
+            detail
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Owners of running deployments whose balance dropped below zero
+    #[instrument("billing_repository.get_negative_balance_users", skip_all, err)]
+    pub async fn get_negative_balance_users(pool: &PgPool) -> Result<Vec<Uuid>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT b.user_id
+            FROM balances b
+            WHERE b.amount < 0
+            AND EXISTS (
+                SELECT 1 FROM deployments d
+                WHERE d.user_id = b.user_id
+                AND d.status IN ('starting', 'running', 'degraded', 'updating', 'unhealthy')
+            )
+            "#
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    #[instrument("billing_repository.get_suspendable_deployments", skip_all, fields(user_id = %user_id), err)]
+    pub async fn get_suspendable_deployments(
+        user_id: &Uuid,
+        pool: &PgPool,
+    ) -> Result<Vec<SuspendableDeploymentRow>, sqlx::Error> {
+        sqlx::query_as!(
+            SuspendableDeploymentRow,
+            r#"
+            SELECT id, project_id
+            FROM deployments
+            WHERE user_id = $1
+            AND status IN ('starting', 'running', 'degraded', 'updating', 'unhealthy')
+            AND NOT suspended_for_balance
+            "#,
+            user_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// The flag tells a later top-up which suspended deployments it may resume, the status is
+    /// left to the provisioner once it has scaled the deployment down
+    #[instrument("billing_repository.mark_suspended_for_balance", skip_all, fields(deployment_id = %deployment_id), err)]
+    pub async fn mark_suspended_for_balance(
+        deployment_id: &Uuid,
+        pool: &PgPool,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE deployments
+            SET suspended_for_balance = TRUE
+            WHERE id = $1
+            "#,
+            deployment_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,201 @@
+//! Database level tests of the hourly billing, run them with
+//! `cargo test -p billing-worker -- --ignored`. Every test gets a fresh Postgres database from
+//! `#[sqlx::test]` (`DATABASE_URL`)
+
+use bigdecimal::BigDecimal;
+use billing_worker::services::{
+    billing::BillingWorker,
+    repository::{BillableDeploymentRow, BillingRepository},
+};
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use sqlx::PgPool;
+use tokio::task::JoinSet;
+use uuid::Uuid;
+
+/// A running deployment created two hours ago, owned by a fresh user, on a 720/month preset
+async fn create_deployment(pool: &PgPool) -> Uuid {
+    let user_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO users (username, email) VALUES ('payer', 'payer@poddle.test') RETURNING id",
+    )
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create test user");
+
+    let preset_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO presets (name, description, cpu_millicores, memory_mb, monthly_price)
+        VALUES ('Billing test', 'Test preset', 500, 512, 720)
+        RETURNING id
+        "#,
+    )
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create preset");
+
+    sqlx::query(
+        "INSERT INTO addon_prices (cpu_monthly_unit_price, memory_monthly_unit_price) VALUES (7.2, 3.6)",
+    )
+    .execute(pool)
+    .await
+    .expect("Failed to create addon prices");
+
+    let project_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO projects (owner_id, name) VALUES ($1, 'billing') RETURNING id",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create project");
+
+    sqlx::query_scalar(
+        r#"
+        INSERT INTO deployments (user_id, project_id, name, source, port, preset_id, status, service, created_at)
+        VALUES ($1, $2, 'web', '{"type": "image", "url": "nginx:1.27"}', 80, $3, 'running', 'web', NOW() - INTERVAL '2 hours')
+        RETURNING id
+        "#,
+    )
+    .bind(user_id)
+    .bind(project_id)
+    .bind(preset_id)
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create deployment")
+}
+
+fn previous_hour() -> (DateTime<Utc>, DateTime<Utc>) {
+    let period_end = Utc::now().duration_trunc(TimeDelta::hours(1)).unwrap();
+    (period_end - TimeDelta::hours(1), period_end)
+}
+
+async fn billable(
+    deployment_id: Uuid,
+    period: (DateTime<Utc>, DateTime<Utc>),
+    pool: &PgPool,
+) -> Option<BillableDeploymentRow> {
+    BillingRepository::get_billable_deployments(period.0, period.1, pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|d| d.id == deployment_id)
+}
+
+async fn count_charges(deployment_id: Uuid, pool: &PgPool) -> (i64, i64) {
+    sqlx::query_as(
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM billings WHERE deployment_id = $1),
+            (SELECT COUNT(*) FROM transactions t
+             JOIN billings b ON b.id = t.billing_id
+             WHERE b.deployment_id = $1 AND t.type = 'usage_charge')
+        "#,
+    )
+    .bind(deployment_id)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+async fn balance(deployment_id: Uuid, pool: &PgPool) -> BigDecimal {
+    sqlx::query_scalar(
+        "SELECT b.amount FROM balances b JOIN deployments d ON d.user_id = b.user_id WHERE d.id = $1",
+    )
+    .bind(deployment_id)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+#[ignore = "needs Postgres"]
+async fn same_period_is_charged_once(pool: PgPool) {
+    let deployment_id = create_deployment(&pool).await;
+    let period = previous_hour();
+    let prices = BillingRepository::get_addon_prices(&pool).await.unwrap();
+    let deployment = billable(deployment_id, period, &pool)
+        .await
+        .expect("Deployment should be billable");
+
+    let first = BillingWorker::record_charge(&deployment, &prices, period.0, &pool)
+        .await
+        .unwrap()
+        .expect("First charge should be recorded");
+    assert_eq!(first.total_cost, BigDecimal::from(1));
+
+    // A second run, or a retry after a crash, hands in the same deployment and period
+    let second = BillingWorker::record_charge(&deployment, &prices, period.0, &pool)
+        .await
+        .unwrap();
+    assert!(second.is_none());
+
+    assert_eq!(count_charges(deployment_id, &pool).await, (1, 1));
+    assert_eq!(balance(deployment_id, &pool).await, BigDecimal::from(-1));
+    assert!(billable(deployment_id, period, &pool).await.is_none());
+
+    // The next hour is a different period
+    let next = BillingWorker::record_charge(&deployment, &prices, period.1, &pool)
+        .await
+        .unwrap();
+    assert!(next.is_some());
+    assert_eq!(count_charges(deployment_id, &pool).await, (2, 2));
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+#[ignore = "needs Postgres"]
+async fn concurrent_runs_charge_once(pool: PgPool) {
+    let deployment_id = create_deployment(&pool).await;
+    let period = previous_hour();
+
+    // Every replica sees the deployment as unbilled before any of them commits
+    let mut seen = Vec::new();
+    for _ in 0..4 {
+        seen.push(billable(deployment_id, period, &pool).await.unwrap());
+    }
+
+    let mut set = JoinSet::new();
+    for deployment in seen {
+        let pool = pool.clone();
+        set.spawn(async move {
+            let prices = BillingRepository::get_addon_prices(&pool).await.unwrap();
+            BillingWorker::record_charge(&deployment, &prices, period.0, &pool).await
+        });
+    }
+    let results = set.join_all().await;
+
+    let recorded = results.iter().filter(|r| matches!(r, Ok(Some(_)))).count();
+    assert_eq!(recorded, 1);
+    assert!(results.iter().all(Result::is_ok));
+
+    assert_eq!(count_charges(deployment_id, &pool).await, (1, 1));
+    assert_eq!(balance(deployment_id, &pool).await, BigDecimal::from(-1));
+}
+
+async fn set_status(deployment_id: Uuid, status: &str, pool: &PgPool) -> bool {
+    sqlx::query_scalar(
+        "UPDATE deployments SET status = $2::deployment_status WHERE id = $1 RETURNING suspended_for_balance",
+    )
+    .bind(deployment_id)
+    .bind(status)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+#[ignore = "needs Postgres"]
+async fn balance_suspension_flag_lasts_until_resume(pool: PgPool) {
+    let deployment_id = create_deployment(&pool).await;
+    let period = previous_hour();
+
+    BillingRepository::mark_suspended_for_balance(&deployment_id, &pool)
+        .await
+        .unwrap();
+    assert!(billable(deployment_id, period, &pool).await.is_none());
+
+    // The reconciler rewriting the status before the provisioner scales down keeps the flag
+    assert!(set_status(deployment_id, "running", &pool).await);
+    assert!(set_status(deployment_id, "suspended", &pool).await);
+
+    // Resuming leaves the suspended state and drops it
+    assert!(!set_status(deployment_id, "starting", &pool).await);
+    assert!(billable(deployment_id, period, &pool).await.is_some());
+}
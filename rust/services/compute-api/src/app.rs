@@ -13,14 +13,29 @@ use axum::{
     Extension, Json, Router,
     extract::DefaultBodyLimit,
     http::{HeaderName, HeaderValue, Method, header},
+    middleware,
 };
 use http_common::{
-    router::base_routes,
+    body_limit::StreamingBodyLimitLayer,
+    metrics::MetricsLayer,
+    router::{base_routes, metrics_routes},
     trace_layer::{custom_make_span::CustomMakeSpan, custom_on_response::CustomOnResponse},
 };
-use tower_http::{cors::CorsLayer, trace::TraceLayer};
+use tower_http::{
+    cors::{AllowOrigin, CorsLayer},
+    trace::TraceLayer,
+};
+use users_core::{audit::audit_impersonated_writes, implementation::auth_security_schemes};
 
-use crate::{features, utilities::app_state::AppState};
+use crate::{
+    features,
+    utilities::{
+        app_state::AppState,
+        idempotency::{IDEMPOTENCY_KEY_HEADER, IDEMPOTENCY_REPLAY_HEADER},
+        rate_limit::insert_rate_limit_subject,
+        versioning::DEPRECATION_HEADER,
+    },
+};
 
 pub async fn app(
     cargo_pkg_name: &'static str,
@@ -28,14 +43,24 @@ pub async fn app(
     cfg: &Config,
 ) -> Result<Router, AppError> {
     let app_state = AppState::init(&cfg).await?;
+    Ok(router(cargo_pkg_name, cargo_pkg_version, app_state))
+}
 
+/// The whole HTTP surface over an already built state, tests hand in their own
+pub fn router(
+    cargo_pkg_name: &'static str,
+    cargo_pkg_version: &'static str,
+    app_state: AppState,
+) -> Router {
+    let metrics = app_state.metrics.clone();
+
+    // Checked per request, `Config::watch` may have changed the allowed origins since
+    let dynamic_config = app_state.dynamic_config.clone();
     let cors = CorsLayer::new()
-        .allow_origin([
-            HeaderValue::from_static("http://127.0.0.1:3000"),
-            HeaderValue::from_static("http://localhost:3000"),
-            HeaderValue::from_static("http://127.0.0.1:5173"),
-            HeaderValue::from_static("http://localhost:5173"),
-        ])
+        .allow_origin(AllowOrigin::predicate(move |origin: &HeaderValue, _| {
+            let dynamic = dynamic_config.read().expect("dynamic config lock poisoned");
+            dynamic.allows_origin(origin.as_bytes())
+        }))
         .allow_methods([
             Method::GET,
             Method::POST,
@@ -50,6 +75,12 @@ pub async fn app(
             header::CONTENT_TYPE,
             header::ACCEPT,
             HeaderName::from_static("x-requested-with"),
+            HeaderName::from_static(IDEMPOTENCY_KEY_HEADER),
+        ])
+        .expose_headers([
+            HeaderName::from_static(IDEMPOTENCY_REPLAY_HEADER),
+            HeaderName::from_static(DEPRECATION_HEADER),
+            header::WARNING,
         ]);
 
     let tracer_layer = TraceLayer::new_for_http()
@@ -57,7 +88,47 @@ pub async fn app(
         .on_response(CustomOnResponse)
         .on_request(());
 
-    let mut api = OpenApi {
+    let mut api = api_info(cargo_pkg_name, cargo_pkg_version);
+
+    api_routes(
+        cargo_pkg_name,
+        cargo_pkg_version,
+        features::get_routes(&app_state),
+    )
+    .finish_api_with(&mut api, auth_security_schemes)
+    .layer(Extension(Arc::new(api)))
+    .layer(DefaultBodyLimit::disable())
+    .layer(StreamingBodyLimitLayer::new(50 * 1024 * 1024))
+    .layer(middleware::from_fn_with_state(
+        app_state.clone(),
+        insert_rate_limit_subject,
+    ))
+    .layer(middleware::from_fn_with_state(
+        app_state.clone(),
+        audit_impersonated_writes::<AppState>,
+    ))
+    .with_state(app_state)
+    .layer(MetricsLayer::new(metrics.clone()))
+    .layer(tracer_layer)
+    .layer(cors)
+    .merge(metrics_routes(metrics))
+}
+
+/// The document `/api/v1/compute/api.json` serves, generated without any of the services
+pub fn openapi(cargo_pkg_name: &'static str, cargo_pkg_version: &'static str) -> OpenApi {
+    let mut api = api_info(cargo_pkg_name, cargo_pkg_version);
+    let _ = api_routes(
+        cargo_pkg_name,
+        cargo_pkg_version,
+        features::documented_routes(),
+    )
+    .finish_api_with(&mut api, auth_security_schemes);
+
+    api
+}
+
+fn api_info(cargo_pkg_name: &'static str, cargo_pkg_version: &'static str) -> OpenApi {
+    OpenApi {
         info: Info {
             title: cargo_pkg_name.to_string(),
             version: cargo_pkg_version.to_string(),
@@ -65,10 +136,16 @@ pub async fn app(
             ..Info::default()
         },
         ..OpenApi::default()
-    };
+    }
+}
 
-    let app = ApiRouter::new()
-        .merge(features::get_routes())
+fn api_routes(
+    cargo_pkg_name: &'static str,
+    cargo_pkg_version: &'static str,
+    feature_routes: ApiRouter<AppState>,
+) -> ApiRouter<AppState> {
+    ApiRouter::new()
+        .merge(feature_routes)
         .merge(base_routes(cargo_pkg_name, cargo_pkg_version))
         .route(
             "/api/v1/compute/docs/scalar",
@@ -83,14 +160,6 @@ pub async fn app(
             Swagger::new("/api/v1/compute/api.json").axum_route(),
         )
         .route("/api/v1/compute/api.json", get(serve_api))
-        .finish_api(&mut api)
-        .layer(Extension(Arc::new(api)))
-        .layer(DefaultBodyLimit::max(50 * 1024 * 1024))
-        .with_state(app_state)
-        .layer(tracer_layer)
-        .layer(cors);
-
-    Ok(app)
 }
 
 async fn serve_api(Extension(api): Extension<Arc<OpenApi>>) -> impl IntoApiResponse {
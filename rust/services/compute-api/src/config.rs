@@ -1,14 +1,34 @@
-use std::{net::SocketAddr, path::PathBuf};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
-use compute_core::{configs::PrometheusConfig, github_app::GithubAppConfig};
+use compute_core::{
+    configs::PrometheusConfig, github_app::GithubAppConfig, gitlab_app::GitlabAppConfig,
+};
 use config::{ConfigBuilder, ConfigError, Environment, File, builder::AsyncState};
 use factory::factories::{
-    amqp::AmqpConfig, database::DatabaseConfig, observability::ObservabilityConfig,
-    redis::RedisConfig,
+    amqp::AmqpConfig, database::DatabaseConfig, kubernetes::KubernetesConfig,
+    observability::ObservabilityConfig, rate_limit::RateLimit, redis::RedisConfig,
 };
+use redis::{AsyncTypedCommands, aio::MultiplexedConnection};
 use serde::Deserialize;
+use tokio::signal::unix::{SignalKind, signal};
+use tracing::{error, info};
 use users_core::jwt::JwtConfig;
 
+use crate::{
+    error::AppError,
+    services::{s3::S3ServiceConfig, vault_service::VaultServiceConfig},
+};
+
+/// JSON of a whole `DynamicConfig`, takes precedence over the config file while it is set
+const DYNAMIC_CONFIG_KEY: &str = "config:compute-api:dynamic";
+const DYNAMIC_CONFIG_POLL_INTERVAL_SECS: u64 = 60;
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct LokiConfig {
     pub url: String,
@@ -19,6 +39,45 @@ pub struct TempoConfig {
     pub url: String,
 }
 
+/// Per user limits, a write request counts against `default` and its own group
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct RateLimitsConfig {
+    pub default: RateLimit,
+    pub deployments_write: RateLimit,
+    pub projects_write: RateLimit,
+}
+
+/// Settings `Config::watch` may swap at runtime, everything else in `Config` is read once
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct DynamicConfig {
+    pub rate_limits: RateLimitsConfig,
+    #[serde(default = "default_cors_allowed_origins")]
+    pub cors_allowed_origins: Vec<String>,
+    #[serde(default)]
+    pub feature_flags: HashMap<String, bool>,
+}
+
+pub type SharedDynamicConfig = Arc<RwLock<DynamicConfig>>;
+
+impl DynamicConfig {
+    pub fn allows_origin(&self, origin: &[u8]) -> bool {
+        self.cors_allowed_origins
+            .iter()
+            .any(|allowed| allowed.as_bytes() == origin)
+    }
+}
+
+fn default_cors_allowed_origins() -> Vec<String> {
+    [
+        "http://127.0.0.1:3000",
+        "http://localhost:3000",
+        "http://127.0.0.1:5173",
+        "http://localhost:5173",
+    ]
+    .map(String::from)
+    .to_vec()
+}
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct Config {
     pub server_address: SocketAddr,
@@ -33,16 +92,76 @@ pub struct Config {
     pub loki: LokiConfig,
     pub tempo: TempoConfig,
     pub github_app: GithubAppConfig,
+    pub gitlab_app: GitlabAppConfig,
+    #[serde(flatten)]
+    pub dynamic: DynamicConfig,
+    pub vault: VaultServiceConfig,
+    pub s3: S3ServiceConfig,
+    /// Base URL of billing-api, e.g. `http://billing-api:8000`
+    pub billing_api_url: String,
+    /// The inferred cluster alone when no regions are configured
+    #[serde(default)]
+    pub clusters: KubernetesConfig,
+    /// Where `init` read this from, `reload` reads it again
+    #[serde(skip)]
+    pub path: PathBuf,
 }
 
 impl Config {
     pub async fn init(path: PathBuf) -> Result<Self, ConfigError> {
         let cfg = ConfigBuilder::<AsyncState>::default()
-            .add_source(File::from(path))
+            .add_source(File::from(path.clone()))
             .add_source(Environment::default())
             .build()
             .await?;
 
-        cfg.try_deserialize()
+        let mut cfg: Self = cfg.try_deserialize()?;
+        cfg.path = path;
+        Ok(cfg)
+    }
+
+    /// The dynamic settings as they are now, from the config file unless Redis overrides them
+    pub async fn reload(&self, con: &mut MultiplexedConnection) -> Result<DynamicConfig, AppError> {
+        if let Some(json) = con.get(DYNAMIC_CONFIG_KEY).await? {
+            return Ok(serde_json::from_str(&json)?);
+        }
+
+        let cfg = Self::init(self.path.clone()).await.map_err(|e| {
+            AppError::InternalServerError(format!("Failed to read config file: {}", e))
+        })?;
+        Ok(cfg.dynamic)
+    }
+
+    /// Reloads every minute and on SIGHUP, a failed reload keeps the settings in effect
+    pub async fn watch(self, dynamic: SharedDynamicConfig, mut con: MultiplexedConnection) {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(DYNAMIC_CONFIG_POLL_INTERVAL_SECS));
+        let mut hangup = signal(SignalKind::hangup()).expect("failed to install SIGHUP handler");
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = hangup.recv() => info!("🔄 SIGHUP received, reloading config"),
+            }
+
+            let next = match self.reload(&mut con).await {
+                Ok(next) => next,
+                Err(e) => {
+                    error!("❌ Failed to reload config: {}", e);
+                    continue;
+                }
+            };
+
+            let mut current = dynamic.write().expect("dynamic config lock poisoned");
+            if *current != next {
+                info!(
+                    rate_limits = ?next.rate_limits,
+                    cors_allowed_origins = ?next.cors_allowed_origins,
+                    feature_flags = ?next.feature_flags,
+                    "🔄 Dynamic config changed"
+                );
+                *current = next;
+            }
+        }
     }
 }
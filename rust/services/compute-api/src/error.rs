@@ -1,11 +1,19 @@
 use axum::Json;
-use axum::http::StatusCode;
+use axum::http::{StatusCode, header};
 use axum::response::{IntoResponse, Response};
+use http_contracts::error::AppError as HttpError;
 use serde_json::json;
 use thiserror::Error;
 
+use compute_core::{
+    github_app::error::GithubAppError, gitlab_app::error::GitlabAppError, schemas::QuotaExceeded,
+};
+
 use crate::features::queries::error::TimeRangeError;
 
+/// Clients are told to come back after this long when the region they picked is full
+const REGION_CAPACITY_RETRY_AFTER_SECS: u64 = 300;
+
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Database query error: {0}")]
@@ -29,12 +37,29 @@ pub enum AppError {
     #[error("Encountered an error trying to convert an infallible value: {0}")]
     FromRequestPartsError(#[from] std::convert::Infallible),
 
-    #[error("Serde json error")]
+    #[error("Serde json error: {0}")]
     SerdejsonError(#[from] serde_json::Error),
 
     #[error("Redis error: {0}")]
     RedisError(#[from] redis::RedisError),
 
+    #[error("Object store error: {0}")]
+    ObjectStoreError(#[from] object_store::Error),
+
+    #[error("Kubernetes API error: {0}")]
+    KubeError(#[from] kube::Error),
+    #[error(
+        "Kubernetes quota '{}' exceeded for {}, requested: {}, limit: {}",
+        .0.quota_name, .0.resource, .0.requested, .0.limit
+    )]
+    KubernetesQuotaExceeded(QuotaExceeded),
+
+    #[error("GitHub App error: {0}")]
+    GithubAppError(#[from] GithubAppError),
+
+    #[error("GitLab App error: {0}")]
+    GitlabAppError(#[from] GitlabAppError),
+
     #[error("External service error")]
     ExternalServiceError {
         service: String,
@@ -43,7 +68,9 @@ pub enum AppError {
     },
     #[error("Service unavailable error")]
     ServiceUnavailable(String),
-    #[error("Internal server error")]
+    #[error("Region {0} is at capacity")]
+    RegionAtCapacity(String),
+    #[error("Internal server error: {0}")]
     InternalServerError(String),
     #[error("Token creation error")]
     TokenCreationError,
@@ -97,6 +124,8 @@ pub enum AppError {
     ValidatorValidationErrors(#[from] validator::ValidationErrors),
     #[error("{0}")]
     NotFoundError(String),
+    #[error("Conflict: {0}")]
+    Conflict(String),
     #[error("Invalid image format error")]
     InvalidImageFormatError(String),
     #[error("HTTP request error: {0}")]
@@ -109,7 +138,8 @@ pub enum AppError {
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let (status, error_message) = match self {
-            Self::SqlxError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            // Lookups scoped to the caller miss for other users' rows too, so they read as absent
+            Self::SqlxError(e) => return HttpError::from(e).into_response(),
             Self::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
             Self::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
             Self::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
@@ -122,7 +152,20 @@ impl IntoResponse for AppError {
             Self::FromRequestPartsError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
             Self::SerdejsonError(e) => (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()),
 
-            Self::RedisError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            Self::RedisError(e) => return HttpError::from(e).into_response(),
+            Self::ObjectStoreError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            // A quota rejection is the plan running out rather than the service's own RBAC
+            Self::KubeError(e) => {
+                return match QuotaExceeded::from_kube_error(&e) {
+                    Some(quota) => Self::KubernetesQuotaExceeded(quota).into_response(),
+                    None => HttpError::from(e).into_response(),
+                };
+            }
+            Self::KubernetesQuotaExceeded(quota) => {
+                return (StatusCode::PAYMENT_REQUIRED, Json(quota.response_body())).into_response();
+            }
+            Self::GithubAppError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            Self::GitlabAppError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
 
             Self::ExternalServiceError {
                 service,
@@ -136,6 +179,13 @@ impl IntoResponse for AppError {
                 ),
             ),
             Self::ServiceUnavailable(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+            Self::RegionAtCapacity(region) => {
+                let body = Json(json!({
+                    "error": format!("Region '{}' is at capacity, try again later or pick another region", region),
+                }));
+                let headers = [(header::RETRY_AFTER, REGION_CAPACITY_RETRY_AFTER_SECS)];
+                return (StatusCode::SERVICE_UNAVAILABLE, headers, body).into_response();
+            }
             Self::InternalServerError(msg) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 format!("Internal server error: {}", msg),
@@ -227,10 +277,19 @@ impl IntoResponse for AppError {
                 StatusCode::UNPROCESSABLE_ENTITY,
                 "Id token not found error".to_string(),
             ),
-            Self::ValidatorValidationError(e) => (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()),
+            Self::ValidatorValidationError(e) => {
+                // Keep the code and offending field so clients can point at the input
+                let body = Json(json!({
+                    "error": e.to_string(),
+                    "code": e.code,
+                    "params": e.params,
+                }));
+                return (StatusCode::UNPROCESSABLE_ENTITY, body).into_response();
+            }
             Self::ValidatorValidationErrors(e) => (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()),
 
             Self::NotFoundError(e) => (StatusCode::NOT_FOUND, e),
+            Self::Conflict(e) => (StatusCode::CONFLICT, e),
             Self::InvalidImageFormatError(e) => (StatusCode::UNPROCESSABLE_ENTITY, e),
 
             Self::Request(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
@@ -0,0 +1,230 @@
+use std::collections::{BTreeMap, HashMap};
+
+use aide::axum::IntoApiResponse;
+use axum::{Json, extract::State};
+use factory::factories::{kubernetes::Kubernetes, redis::Redis};
+use k8s_openapi::{api::core::v1::Node, apimachinery::pkg::api::resource::Quantity};
+use kube::{Api, api::ListParams};
+use prometheus_http_query::{
+    Client as PrometheusClient,
+    response::{Data, PromqlResult},
+};
+use tracing::warn;
+use users_core::jwt::AdminClaims;
+
+use crate::{
+    config::Config,
+    error::AppError,
+    features::schemas::{ClusterCapacity, NodeSummary},
+    services::cache_service::CacheService,
+};
+
+#[tracing::instrument(name = "get_cluster_nodes_handler", skip_all, fields(admin_id = %claims.sub), err)]
+pub async fn get_cluster_nodes_handler(
+    AdminClaims(claims): AdminClaims,
+    State(cfg): State<Config>,
+    State(kubernetes): State<Kubernetes>,
+    State(http_client): State<reqwest::Client>,
+    State(mut redis): State<Redis>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let nodes = cluster_nodes(&cfg, &kubernetes, http_client, &mut redis).await?;
+
+    Ok(Json(nodes))
+}
+
+#[tracing::instrument(name = "get_cluster_capacity_handler", skip_all, fields(admin_id = %claims.sub), err)]
+pub async fn get_cluster_capacity_handler(
+    AdminClaims(claims): AdminClaims,
+    State(cfg): State<Config>,
+    State(kubernetes): State<Kubernetes>,
+    State(http_client): State<reqwest::Client>,
+    State(mut redis): State<Redis>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let nodes = cluster_nodes(&cfg, &kubernetes, http_client, &mut redis).await?;
+
+    let cpu_allocatable_millicores = nodes.iter().map(|n| n.cpu_allocatable_millicores).sum();
+    let cpu_usage_millicores = nodes.iter().filter_map(|n| n.cpu_usage_millicores).sum();
+    let memory_allocatable_mb = nodes.iter().map(|n| n.memory_allocatable_mb).sum();
+    let memory_usage_mb = nodes.iter().filter_map(|n| n.memory_usage_mb).sum();
+
+    Ok(Json(ClusterCapacity {
+        nodes: nodes.len(),
+        ready_nodes: nodes.iter().filter(|n| n.ready).count(),
+        cpu_allocatable_millicores,
+        cpu_usage_millicores,
+        cpu_remaining_millicores: (cpu_allocatable_millicores - cpu_usage_millicores).max(0),
+        memory_allocatable_mb,
+        memory_usage_mb,
+        memory_remaining_mb: (memory_allocatable_mb - memory_usage_mb).max(0),
+    }))
+}
+
+/// Nodes of every region, served from the cache for `CLUSTER_NODES_TTL_SECONDS`
+async fn cluster_nodes(
+    cfg: &Config,
+    kubernetes: &Kubernetes,
+    http_client: reqwest::Client,
+    redis: &mut Redis,
+) -> Result<Vec<NodeSummary>, AppError> {
+    if let Some(nodes) = CacheService::get_cluster_nodes(&mut redis.con).await? {
+        return Ok(nodes);
+    }
+
+    let usage = node_usage(cfg, http_client).await;
+
+    // Sorted so the list keeps its order between refreshes
+    let regions: BTreeMap<&String, &kube::Client> = kubernetes.regions.iter().collect();
+    let mut summaries = Vec::new();
+    for (region, client) in regions {
+        let nodes = Api::<Node>::all(client.clone())
+            .list(&ListParams::default())
+            .await?;
+        summaries.extend(
+            nodes
+                .into_iter()
+                .map(|node| node_summary(region, node, &usage)),
+        );
+    }
+
+    CacheService::set_cluster_nodes(&summaries, &mut redis.con).await?;
+
+    Ok(summaries)
+}
+
+/// Used millicores and megabytes by node-exporter `instance`, with the port stripped
+#[derive(Default)]
+struct NodeUsage {
+    cpu_millicores: HashMap<String, i64>,
+    memory_mb: HashMap<String, i64>,
+}
+
+/// Leaves the usage out when Prometheus fails, capacity alone is still worth showing
+async fn node_usage(cfg: &Config, http_client: reqwest::Client) -> NodeUsage {
+    let client = match PrometheusClient::from(http_client, &cfg.prometheus.url) {
+        Ok(client) => client,
+        Err(e) => {
+            warn!(error = %e, "⚠️ Invalid Prometheus URL, node usage left out");
+            return NodeUsage::default();
+        }
+    };
+
+    let cpu_query = format!(
+        r#"sum by (instance) (rate(node_cpu_seconds_total{{mode!="idle"}}[{}]))"#,
+        cfg.prometheus.rate
+    );
+    let memory_query = "node_memory_MemTotal_bytes - node_memory_MemAvailable_bytes";
+
+    let (cpu, memory) = tokio::join!(
+        client.query(cpu_query).get(),
+        client.query(memory_query).get()
+    );
+
+    NodeUsage {
+        cpu_millicores: by_instance(cpu, |cores| (cores * 1000.0).round() as i64),
+        memory_mb: by_instance(memory, |bytes| (bytes / 1024.0 / 1024.0).round() as i64),
+    }
+}
+
+fn by_instance(
+    result: Result<PromqlResult, prometheus_http_query::Error>,
+    scale: fn(f64) -> i64,
+) -> HashMap<String, i64> {
+    let response = match result {
+        Ok(response) => response,
+        Err(e) => {
+            warn!(error = %e, "⚠️ Prometheus node query failed, node usage left out");
+            return HashMap::new();
+        }
+    };
+    let Data::Vector(vector) = response.data() else {
+        return HashMap::new();
+    };
+
+    vector
+        .iter()
+        .filter_map(|sample| {
+            let instance = sample.metric().get("instance")?;
+            let host = instance.split(':').next().unwrap_or(instance);
+            Some((host.to_string(), scale(sample.sample().value())))
+        })
+        .collect()
+}
+
+fn node_summary(region: &str, node: Node, usage: &NodeUsage) -> NodeSummary {
+    let name = node.metadata.name.unwrap_or_default();
+    let spec = node.spec.unwrap_or_default();
+    let status = node.status.unwrap_or_default();
+
+    let ready = status
+        .conditions
+        .iter()
+        .flatten()
+        .any(|c| c.type_ == "Ready" && c.status == "True");
+
+    // node-exporter reports the node either by name or by its address, depending on relabeling
+    let mut hosts = vec![name.clone()];
+    hosts.extend(status.addresses.iter().flatten().map(|a| a.address.clone()));
+    let lookup = |values: &HashMap<String, i64>| hosts.iter().find_map(|h| values.get(h).copied());
+
+    let quantity = |resources: &Option<BTreeMap<String, Quantity>>, key: &str| {
+        resources.as_ref().and_then(|r| r.get(key)).cloned()
+    };
+
+    NodeSummary {
+        region: region.to_string(),
+        ready,
+        unschedulable: spec.unschedulable.unwrap_or(false),
+        cpu_capacity_millicores: quantity(&status.capacity, "cpu")
+            .and_then(|q| cpu_millicores(&q))
+            .unwrap_or_default(),
+        cpu_allocatable_millicores: quantity(&status.allocatable, "cpu")
+            .and_then(|q| cpu_millicores(&q))
+            .unwrap_or_default(),
+        cpu_usage_millicores: lookup(&usage.cpu_millicores),
+        memory_capacity_mb: quantity(&status.capacity, "memory")
+            .and_then(|q| memory_mb(&q))
+            .unwrap_or_default(),
+        memory_allocatable_mb: quantity(&status.allocatable, "memory")
+            .and_then(|q| memory_mb(&q))
+            .unwrap_or_default(),
+        memory_usage_mb: lookup(&usage.memory_mb),
+        name,
+    }
+}
+
+/// `4`, `3920m` or `0.5`
+fn cpu_millicores(Quantity(value): &Quantity) -> Option<i64> {
+    match value.strip_suffix('m') {
+        Some(millicores) => millicores.parse().ok(),
+        None => value
+            .parse::<f64>()
+            .ok()
+            .map(|cores| (cores * 1000.0).round() as i64),
+    }
+}
+
+/// Bytes with an optional binary (`Ki`, `Mi`, ...) or decimal (`k`, `M`, ...) suffix
+fn memory_mb(Quantity(value): &Quantity) -> Option<i64> {
+    const SUFFIXES: [(&str, f64); 8] = [
+        ("Ki", 1024.0),
+        ("Mi", 1024.0 * 1024.0),
+        ("Gi", 1024.0 * 1024.0 * 1024.0),
+        ("Ti", 1024.0 * 1024.0 * 1024.0 * 1024.0),
+        ("k", 1e3),
+        ("M", 1e6),
+        ("G", 1e9),
+        ("T", 1e12),
+    ];
+
+    let (number, multiplier) = SUFFIXES
+        .iter()
+        .find_map(|(suffix, multiplier)| {
+            value
+                .strip_suffix(suffix)
+                .map(|number| (number, *multiplier))
+        })
+        .unwrap_or((value.as_str(), 1.0));
+
+    let bytes = number.parse::<f64>().ok()? * multiplier;
+    Some((bytes / 1024.0 / 1024.0).round() as i64)
+}
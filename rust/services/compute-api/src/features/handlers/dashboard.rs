@@ -11,7 +11,7 @@ use axum::{
 };
 use factory::factories::{database::Database, redis::Redis};
 
-use http_contracts::{list::schema::ListResponse, pagination::schema::Pagination};
+use http_contracts::pagination::schema::{Paginated, Pagination};
 use users_core::jwt::Claims;
 use uuid::Uuid;
 
@@ -39,5 +39,5 @@ pub async fn get_dashboard_events_handler(
     let (data, total) =
         DeploymentEventRepository::get_many_by_owner(&user_id, &p, &database.pool).await?;
 
-    Ok(Json(ListResponse { data, total }))
+    Ok(Json(Paginated::new(data, total, &p)))
 }
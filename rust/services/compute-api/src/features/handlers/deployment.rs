@@ -2,44 +2,74 @@ use crate::{
     config::Config,
     error::AppError,
     features::{
-        queries::DeploymentsMetricsQuery,
+        handlers::{
+            gitlab::gitlab_access_token,
+            region::{check_region_capacity, resolve_region},
+        },
+        queries::{DeploymentsMetricsQuery, DeploymentsTagQuery, MetricsHistoryQuery},
         repositories::{
-            deployment::DeploymentRepository, deployment_preset::DeploymentPresetRepository,
+            build::BuildRepository, deployment::DeploymentRepository,
+            deployment_event::DeploymentEventRepository,
+            deployment_preset::DeploymentPresetRepository, idempotency::IdempotencyRepository,
             project::ProjectRepository,
         },
+        schemas::MetricsHistoryResponse,
+    },
+    services::{cache_service::CacheService, vault_service::VaultService},
+    utilities::{
+        app_state::AppState,
+        idempotency::{IDEMPOTENCY_REPLAY_HEADER, idempotency_key_hash},
     },
-    services::cache_service::CacheService,
 };
 use aide::axum::IntoApiResponse;
 use axum::{
     Json,
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{HeaderMap, HeaderValue, StatusCode, header},
 };
 use compute_core::{
-    github_app::GithubApp,
+    cache_keys::CacheKeys,
+    channel_names::ChannelNames,
+    formatters::format_namespace,
+    github_app::schemas::RepositoryProvider,
+    models::{DeploymentStatus, JobType, PresetRow, ResourceSpec},
+    repository::JobQueueRepository,
     schemas::{
         CreateDeploymentMessage, CreateDeploymentRequest, DeleteDeploymentMessage,
-        DeploymentResponse, DeploymentSource, DeploymentsResponse, UpdateDeploymentMessage,
-        UpdateDeploymentRequest,
+        DeploymentDiffMessage, DeploymentResponse, DeploymentSource, DeploymentSourceMessage,
+        DeploymentsResponse, DryRunResult, EnvironmentVariablesResponse, ImagePullSecret,
+        PatchEnvironmentVariablesRequest, ResumeDeploymentMessage,
+        RotateRegistryCredentialsMessage, RotateSecretsMessage, RotateSecretsRequest,
+        SuspendDeploymentMessage, UpdateDeploymentMessage, UpdateDeploymentRequest,
     },
 };
 use factory::factories::{
     amqp::{Amqp, AmqpPropagator},
     database::Database,
+    kubernetes::Kubernetes,
     redis::Redis,
 };
+use futures::StreamExt;
 use http_contracts::{
-    list::schema::ListResponse, message::MessageResponse, pagination::schema::Pagination,
+    message::MessageResponse,
+    pagination::schema::{Paginated, Pagination},
 };
 use lapin::{BasicProperties, options::BasicPublishOptions, types::FieldTable};
+use redis::AsyncTypedCommands;
+use std::time::Duration;
 
-use reqwest::Client;
-use tracing::{Instrument, info, info_span};
+use tracing::{Instrument, info, info_span, warn};
 use users_core::jwt::Claims;
 use uuid::Uuid;
 use validator::Validate;
 
+/// Clients are told to retry after this long while a deployment wakes up
+const WAKE_RETRY_AFTER_SECS: u64 = 10;
+/// The provisioner only talks to the API server, a dry run or diff slower than this is stuck
+const DRY_RUN_TIMEOUT_SECS: u64 = 30;
+/// Reads and merges again when a concurrent update bumped `updated_at` in between
+const ENVIRONMENT_PATCH_ATTEMPTS: usize = 3;
+
 #[tracing::instrument(
     name = "get_deployment_handler",
     skip_all,
@@ -60,16 +90,134 @@ pub async fn get_deployment_handler(
     let deployment =
         DeploymentRepository::get_by_id(&user_id, &deployment_id, &database.pool).await?;
 
-    let response: DeploymentResponse = deployment.into();
+    let mut response: DeploymentResponse = deployment.into();
+    response.latest_build = BuildRepository::get_latest(&deployment_id, &database.pool).await?;
+
     Ok(Json(response))
 }
 
+#[tracing::instrument(
+    name = "get_builds_handler",
+    skip_all,
+    fields(
+        user_id = %claims.sub,
+        project_id = %project_id,
+        deployment_id = %deployment_id
+    ),
+    err
+)]
+pub async fn get_builds_handler(
+    claims: Claims,
+    Path((project_id, deployment_id)): Path<(Uuid, Uuid)>,
+    Query(p): Query<Pagination>,
+    State(database): State<Database>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let user_id: Uuid = claims.sub;
+
+    let (data, total) = BuildRepository::get_all_by_deployment(
+        &user_id,
+        &project_id,
+        &deployment_id,
+        &p,
+        &database.pool,
+    )
+    .await?;
+
+    Ok(Json(Paginated::new(data, total, &p)))
+}
+
+#[tracing::instrument(
+    name = "get_deployment_events_handler",
+    skip_all,
+    fields(
+        user_id = %claims.sub,
+        project_id = %project_id,
+        deployment_id = %deployment_id
+    ),
+    err
+)]
+pub async fn get_deployment_events_handler(
+    claims: Claims,
+    Path((project_id, deployment_id)): Path<(Uuid, Uuid)>,
+    Query(p): Query<Pagination>,
+    State(database): State<Database>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let user_id: Uuid = claims.sub;
+
+    let (data, total) = DeploymentEventRepository::get_many_by_deployment(
+        &user_id,
+        &project_id,
+        &deployment_id,
+        &p,
+        &database.pool,
+    )
+    .await?;
+
+    Ok(Json(Paginated::new(data, total, &p)))
+}
+
+#[tracing::instrument(
+    name = "get_metrics_history_handler",
+    skip_all,
+    fields(
+        user_id = %claims.sub,
+        project_id = %project_id,
+        deployment_id = %deployment_id
+    ),
+    err
+)]
+pub async fn get_metrics_history_handler(
+    claims: Claims,
+    Path((project_id, deployment_id)): Path<(Uuid, Uuid)>,
+    Query(q): Query<MetricsHistoryQuery>,
+    State(database): State<Database>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let user_id: Uuid = claims.sub;
+    let (start, end, resolution) = q.resolve()?;
+
+    let deployment =
+        DeploymentRepository::get_by_id(&user_id, &deployment_id, &database.pool).await?;
+    if deployment.project_id != project_id {
+        return Err(AppError::NotFoundError("Deployment not found".into()));
+    }
+
+    let limit = q.page_size();
+
+    // One snapshot past the page tells whether there is a next one
+    let mut snapshots = DeploymentRepository::get_metrics_history(
+        &deployment_id,
+        start,
+        end,
+        resolution,
+        q.cursor,
+        limit + 1,
+        &database.pool,
+    )
+    .await?;
+    let next_cursor = if snapshots.len() as i64 > limit {
+        snapshots.truncate(limit as usize);
+        snapshots.last().map(|s| s.ts)
+    } else {
+        None
+    };
+
+    Ok(Json(MetricsHistoryResponse {
+        deployment_id,
+        start,
+        end,
+        resolution,
+        snapshots,
+        next_cursor,
+    }))
+}
+
 #[tracing::instrument(name = "get_deployments_handler", skip_all, fields(user_id = %claims.sub, project_id = %project_id), err)]
 pub async fn get_deployments_handler(
     claims: Claims,
     Path(project_id): Path<Uuid>,
     Query(p): Query<Pagination>,
     Query(q): Query<DeploymentsMetricsQuery>,
+    Query(t): Query<DeploymentsTagQuery>,
     State(cfg): State<Config>,
     State(database): State<Database>,
     State(mut redis): State<Redis>,
@@ -77,14 +225,17 @@ pub async fn get_deployments_handler(
     let user_id: Uuid = claims.sub;
     let count = q.snapshot_count(cfg.prometheus.scrape_interval_secs);
 
-    let (deployments, total) =
-        DeploymentRepository::get_all_by_project(&user_id, &project_id, &p, &database.pool).await?;
+    let (deployments, total) = DeploymentRepository::get_all_by_project(
+        &user_id,
+        &project_id,
+        &p,
+        &t.tags(),
+        &database.pool,
+    )
+    .await?;
 
     if total == 0 {
-        return Ok(Json(ListResponse {
-            data: vec![],
-            total: 0,
-        }));
+        return Ok(Json(Paginated::new(vec![], 0, &p)));
     }
 
     let ids: Vec<String> = deployments.iter().map(|d| d.id.to_string()).collect();
@@ -97,7 +248,7 @@ pub async fn get_deployments_handler(
         .map(|pair| pair.into())
         .collect();
 
-    Ok(Json(ListResponse { data, total }))
+    Ok(Json(Paginated::new(data, total, &p)))
 }
 
 #[tracing::instrument(
@@ -112,13 +263,22 @@ pub async fn get_deployments_handler(
 pub async fn create_deployment_handler(
     claims: Claims,
     Path(project_id): Path<Uuid>,
-    State(http): State<Client>,
     State(amqp): State<Amqp>,
     State(db): State<Database>,
-    State(github_app): State<GithubApp>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
     Json(mut req): Json<CreateDeploymentRequest>,
 ) -> Result<impl IntoApiResponse, AppError> {
     let user_id = claims.sub;
+    let key_hash = idempotency_key_hash(&headers, &user_id)?;
+    if let Some(key_hash) = &key_hash
+        && let Some(response_body) = IdempotencyRepository::get(key_hash, &db.pool).await?
+    {
+        let mut headers = HeaderMap::new();
+        headers.insert(IDEMPOTENCY_REPLAY_HEADER, HeaderValue::from_static("true"));
+        return Ok((StatusCode::CREATED, headers, Json(response_body)));
+    }
+
     req.validate()?;
 
     // Verify project exists
@@ -126,19 +286,51 @@ pub async fn create_deployment_handler(
 
     // Prepare message
     let preset = DeploymentPresetRepository::get_by_id(&req.preset_id, &db.pool).await?;
-    if preset.max_addon_cpu_millicores < req.addon_cpu_millicores.unwrap_or_default()
-        || preset.max_addon_memory_mb < req.addon_memory_mb.unwrap_or_default()
-    {
-        return Err(AppError::ValidationError(format!(
-            "Requested add-ons exceed limits for preset '{}'. Max CPU: {}m, Max Memory: {}MB",
-            preset.name, preset.max_addon_cpu_millicores, preset.max_addon_memory_mb
-        )));
-    }
+    req.check_preset_limits(&preset)
+        .map_err(AppError::ValidationError)?;
+
+    let region = resolve_region(&state.kubernetes, req.region.as_deref())?;
+    check_region_capacity(
+        &state.config,
+        &state.kubernetes,
+        &region,
+        req.desired_replicas,
+        &db,
+    )
+    .await?;
+    req.region = Some(region);
+
+    // Purely informational, a deployment is never refused because billing-api is down
+    let resources = ResourceSpec {
+        cpu_request_millicores: preset.cpu_millicores
+            + req.addon_cpu_millicores.unwrap_or_default(),
+        cpu_limit_millicores: preset.cpu_millicores + req.addon_cpu_millicores.unwrap_or_default(),
+        memory_request_mb: preset.memory_mb + req.addon_memory_mb.unwrap_or_default(),
+        memory_limit_mb: preset.memory_mb + req.addon_memory_mb.unwrap_or_default(),
+    };
+    let estimated_cost = state
+        .billing_client
+        .estimate_cost(&preset.id, &resources, req.desired_replicas)
+        .await
+        .inspect_err(|e| warn!("Failed to estimate cost of the new deployment: {}", e))
+        .ok();
 
     match &mut req.source {
         compute_core::schemas::DeploymentSource::Image { .. } => {}
         DeploymentSource::Dockerfile { repo, .. } | DeploymentSource::Code { repo, .. } => {
-            if repo.private {
+            if repo.private && repo.provider == RepositoryProvider::Gitlab {
+                let access_token = gitlab_access_token(
+                    &user_id,
+                    &state.gitlab_app,
+                    &state.http_client,
+                    &state.vault,
+                )
+                .await?;
+
+                repo.clone_url = repo
+                    .clone_url
+                    .replace("https://", &format!("https://oauth2:{access_token}@"));
+            } else if repo.private {
                 let installation_id = sqlx::query_scalar!(
                     "SELECT installation_id FROM installations WHERE user_id = $1",
                     user_id
@@ -153,12 +345,10 @@ pub async fn create_deployment_handler(
                     }
                 };
 
-                let access_token = github_app
-                    .create_installation_token(installation_id, &http)
-                    .await
-                    .map_err(|e| {
-                        AppError::InternalServerError(format!("github access token: {}", e))
-                    })?;
+                let access_token = state
+                    .github_app
+                    .create_installation_token(installation_id, &state.http_client)
+                    .await?;
 
                 repo.clone_url = repo.clone_url.replace(
                     "https://",
@@ -175,41 +365,143 @@ pub async fn create_deployment_handler(
     let deployment =
         DeploymentRepository::create(&user_id, &project_id, req.clone(), &mut tx).await?;
 
-    // Get RabbitMQ channel
-    let channel = amqp.channel().await;
-    let message: CreateDeploymentMessage = (user_id, project_id, deployment.id, preset, req).into();
-    let payload = serde_json::to_vec(&message)?;
-    let mut headers = FieldTable::default();
-    AmqpPropagator::inject_context(&mut headers);
+    // Stored before publishing, so a request that lost the race never reaches the provisioner
+    let mut response_body = serde_json::to_value(&deployment)?;
+    if let Some(object) = response_body.as_object_mut() {
+        object.insert(
+            "estimatedCost".to_string(),
+            serde_json::to_value(&estimated_cost)?,
+        );
+    }
+    if let Some(key_hash) = &key_hash
+        && !IdempotencyRepository::create(key_hash, &response_body, &mut tx).await?
+    {
+        return Err(AppError::Conflict(
+            "A request with this Idempotency-Key was already processed, retry to get its response"
+                .into(),
+        ));
+    }
 
-    // Publish message
-    channel
-        .basic_publish(
-            "compute",
-            "compute.create",
-            BasicPublishOptions {
-                mandatory: false,
-                immediate: false,
-            },
-            &payload,
-            BasicProperties::default()
-                .with_delivery_mode(2)
-                .with_content_type("application/json".into())
-                .with_headers(headers),
-        )
-        .instrument(info_span!("basic_publish.compute.create"))
-        .await?
-        .await?;
+    let message: CreateDeploymentMessage =
+        (user_id, project_id, deployment.id, preset.clone(), req).into();
+    // Dropping the transaction on error rolls back the deployment record
+    message.validate(&preset)?;
+    let warning = message.image_pull_policy_warning();
 
-    info!(
-        "📤 Published deployment creation message for {}",
-        deployment.id
-    );
+    // Builds are queued in the same transaction as the record, a broker restart can't lose them
+    if matches!(
+        message.source,
+        DeploymentSourceMessage::Dockerfile { .. } | DeploymentSourceMessage::Code { .. }
+    ) {
+        JobQueueRepository::enqueue(JobType::Build, &message, &mut *tx).await?;
+
+        info!("📤 Queued build job for {}", deployment.id);
+    } else {
+        // Get RabbitMQ channel
+        let channel = amqp.channel().await?;
+
+        let payload = serde_json::to_vec(&message)?;
+        let mut headers = FieldTable::default();
+        AmqpPropagator::inject_context(&mut headers);
+
+        // Publish message
+        channel
+            .basic_publish(
+                "compute",
+                "compute.create",
+                BasicPublishOptions {
+                    mandatory: false,
+                    immediate: false,
+                },
+                &payload,
+                BasicProperties::default()
+                    .with_delivery_mode(2)
+                    .with_content_type("application/json".into())
+                    .with_headers(headers),
+            )
+            .instrument(info_span!("basic_publish.compute.create"))
+            .await?
+            .await?;
+
+        info!(
+            "📤 Published deployment creation message for {}",
+            deployment.id
+        );
+    }
 
     // Commit transaction
     tx.commit().await?;
 
-    Ok((StatusCode::CREATED, Json(deployment)))
+    let mut headers = HeaderMap::new();
+    if let Some(warning) = warning
+        && let Ok(value) = HeaderValue::from_str(&format!("299 - \"{}\"", warning))
+    {
+        headers.insert(header::WARNING, value);
+    }
+
+    Ok((StatusCode::CREATED, headers, Json(response_body)))
+}
+
+/// Validates a deployment against the cluster without creating anything, the provisioner
+/// answers on a one-shot channel keyed by a throwaway request id
+#[tracing::instrument(
+    name = "dry_run_deployment_handler",
+    skip_all,
+    fields(
+        user_id = %claims.sub,
+        project_id = %project_id,
+    ),
+    err
+)]
+pub async fn dry_run_deployment_handler(
+    claims: Claims,
+    Path(project_id): Path<Uuid>,
+    State(amqp): State<Amqp>,
+    State(db): State<Database>,
+    State(redis): State<Redis>,
+    State(kubernetes): State<Kubernetes>,
+    Json(mut req): Json<CreateDeploymentRequest>,
+) -> Result<impl IntoApiResponse, AppError> {
+    req.validate()?;
+
+    let user_id = claims.sub;
+    ProjectRepository::get_one_by_id(&user_id, &project_id, &db.pool).await?;
+
+    let preset = DeploymentPresetRepository::get_by_id(&req.preset_id, &db.pool).await?;
+    req.check_preset_limits(&preset)
+        .map_err(AppError::ValidationError)?;
+    req.region = Some(resolve_region(&kubernetes, req.region.as_deref())?);
+
+    let request_id = Uuid::new_v4();
+    let mut message: CreateDeploymentMessage =
+        (user_id, project_id, request_id, preset.clone(), req).into();
+    message.validate(&preset)?;
+    message.dry_run = true;
+
+    // Subscribed before publishing, a fast result would otherwise be lost
+    let mut pubsub = redis.pubsub().await.map_err(|e| {
+        AppError::InternalServerError(format!("Failed to connect to Redis PubSub: {}", e))
+    })?;
+    pubsub
+        .subscribe(ChannelNames::dry_run(&request_id.to_string()))
+        .await?;
+
+    amqp.basic_publish("compute", "compute.create", &message)
+        .await?;
+
+    let mut results = pubsub.into_on_message();
+    let result = tokio::time::timeout(Duration::from_secs(DRY_RUN_TIMEOUT_SECS), results.next())
+        .await
+        .ok()
+        .flatten()
+        .ok_or_else(|| {
+            AppError::ServiceUnavailable("Timed out waiting for the dry run result".into())
+        })?;
+
+    let payload: String = result.get_payload()?;
+    let result: DryRunResult = serde_json::from_str(&payload)?;
+
+    Ok(Json(result))
 }
 
 #[tracing::instrument(
@@ -230,6 +522,12 @@ pub async fn update_deployment_handler(
 ) -> Result<impl IntoApiResponse, AppError> {
     req.validate()?;
 
+    if req.canary.is_some() && req.source.is_some() {
+        return Err(AppError::ValidationError(
+            "A canary can't be combined with a source change".into(),
+        ));
+    }
+
     let user_id: Uuid = claims.sub;
 
     // Start database transaction
@@ -240,19 +538,12 @@ pub async fn update_deployment_handler(
         DeploymentRepository::update(&user_id, &deployment_id, req.clone(), &mut tx).await?;
 
     // Get RabbitMQ channel
-    let channel = amqp.channel().await;
+    let channel = amqp.channel().await?;
 
     // Prepare message
     let preset = if let Some(preset_id) = req.preset_id {
         let preset = DeploymentPresetRepository::get_by_id(&preset_id, &mut *tx).await?;
-        if preset.max_addon_cpu_millicores < req.addon_cpu_millicores.unwrap_or_default()
-            || preset.max_addon_memory_mb < req.addon_memory_mb.unwrap_or_default()
-        {
-            return Err(AppError::ValidationError(format!(
-                "Requested add-ons exceed limits for preset '{}'. Max CPU: {}m, Max Memory: {}MB",
-                preset.name, preset.max_addon_cpu_millicores, preset.max_addon_memory_mb
-            )));
-        }
+        check_addon_limits(&req, &preset)?;
         Some(preset)
     } else {
         None
@@ -292,6 +583,82 @@ pub async fn update_deployment_handler(
     Ok(Json(deployment))
 }
 
+fn check_addon_limits(req: &UpdateDeploymentRequest, preset: &PresetRow) -> Result<(), AppError> {
+    if preset.max_addon_cpu_millicores < req.addon_cpu_millicores.unwrap_or_default()
+        || preset.max_addon_memory_mb < req.addon_memory_mb.unwrap_or_default()
+    {
+        return Err(AppError::ValidationError(format!(
+            "Requested add-ons exceed limits for preset '{}'. Max CPU: {}m, Max Memory: {}MB",
+            preset.name, preset.max_addon_cpu_millicores, preset.max_addon_memory_mb
+        )));
+    }
+
+    Ok(())
+}
+
+/// Previews an update as a JSON Patch against the live K8s Deployment, nothing is persisted.
+/// The provisioner computes it and answers on a one-shot channel, like a dry run
+#[tracing::instrument(
+    name = "deployment_diff_handler",
+    skip_all,
+    fields(
+        user_id = %claims.sub,
+        project_id = %project_id,
+        deployment_id = %deployment_id,
+    ),
+    err
+)]
+pub async fn deployment_diff_handler(
+    claims: Claims,
+    Path((project_id, deployment_id)): Path<(Uuid, Uuid)>,
+    State(amqp): State<Amqp>,
+    State(database): State<Database>,
+    State(redis): State<Redis>,
+    Json(req): Json<UpdateDeploymentRequest>,
+) -> Result<impl IntoApiResponse, AppError> {
+    req.validate()?;
+
+    let user_id: Uuid = claims.sub;
+    DeploymentRepository::get_by_id(&user_id, &deployment_id, &database.pool).await?;
+
+    let preset = if let Some(preset_id) = req.preset_id {
+        let preset = DeploymentPresetRepository::get_by_id(&preset_id, &database.pool).await?;
+        check_addon_limits(&req, &preset)?;
+        Some(preset)
+    } else {
+        None
+    };
+
+    let request_id = Uuid::new_v4();
+    let message = DeploymentDiffMessage {
+        request_id,
+        update: (user_id, project_id, deployment_id, preset, req).into(),
+    };
+
+    // Subscribed before publishing, a fast result would otherwise be lost
+    let mut pubsub = redis.pubsub().await.map_err(|e| {
+        AppError::InternalServerError(format!("Failed to connect to Redis PubSub: {}", e))
+    })?;
+    pubsub
+        .subscribe(ChannelNames::deployment_diff(&request_id.to_string()))
+        .await?;
+
+    amqp.basic_publish("compute", "compute.diff", &message)
+        .await?;
+
+    let mut results = pubsub.into_on_message();
+    let result = tokio::time::timeout(Duration::from_secs(DRY_RUN_TIMEOUT_SECS), results.next())
+        .await
+        .ok()
+        .flatten()
+        .ok_or_else(|| AppError::ServiceUnavailable("Timed out waiting for the diff".into()))?;
+
+    let payload: String = result.get_payload()?;
+    let patch: Result<Vec<serde_json::Value>, String> = serde_json::from_str(&payload)?;
+
+    Ok(Json(patch.map_err(AppError::BadRequest)?))
+}
+
 #[tracing::instrument(
     name = "delete_deployment_handler",
     skip_all,
@@ -313,16 +680,17 @@ pub async fn delete_deployment_handler(
     let mut tx = database.pool.begin().await?;
 
     // Deleting from database
-    DeploymentRepository::delete(&user_id, &deployment_id, &mut tx).await?;
+    let region = DeploymentRepository::delete(&user_id, &deployment_id, &mut tx).await?;
 
     // Get RabbitMQ channel
-    let channel = amqp.channel().await;
+    let channel = amqp.channel().await?;
 
     // Prepare message
     let message = DeleteDeploymentMessage {
         deployment_id,
         user_id,
         project_id,
+        region,
         timestamp: chrono::Utc::now().timestamp(),
     };
 
@@ -360,3 +728,410 @@ pub async fn delete_deployment_handler(
         Json(MessageResponse::new("Deployment deletion initiated")),
     ))
 }
+
+#[tracing::instrument(
+    name = "suspend_deployment_handler",
+    skip_all,
+    fields(
+        user_id = %claims.sub,
+        project_id = %project_id,
+        deployment_id = %deployment_id
+    ),
+    err
+)]
+pub async fn suspend_deployment_handler(
+    claims: Claims,
+    Path((project_id, deployment_id)): Path<(Uuid, Uuid)>,
+    State(database): State<Database>,
+    State(amqp): State<Amqp>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let user_id = claims.sub;
+
+    let deployment =
+        DeploymentRepository::get_by_id(&user_id, &deployment_id, &database.pool).await?;
+    if matches!(
+        deployment.status,
+        DeploymentStatus::Suspended | DeploymentStatus::Deleted
+    ) {
+        return Err(AppError::BadRequest(format!(
+            "Deployment can't be suspended while {}",
+            deployment.status
+        )));
+    }
+
+    // Prepare message
+    let message = SuspendDeploymentMessage {
+        deployment_id,
+        user_id,
+        project_id,
+        timestamp: chrono::Utc::now().timestamp(),
+    };
+
+    amqp.basic_publish("compute", "compute.suspend", &message)
+        .instrument(info_span!("basic_publish.compute.suspend"))
+        .await?;
+
+    info!(
+        "📤 Published deployment suspension message for {}",
+        deployment_id
+    );
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(MessageResponse::new("Deployment suspension initiated")),
+    ))
+}
+
+#[tracing::instrument(
+    name = "resume_deployment_handler",
+    skip_all,
+    fields(
+        user_id = %claims.sub,
+        project_id = %project_id,
+        deployment_id = %deployment_id
+    ),
+    err
+)]
+pub async fn resume_deployment_handler(
+    claims: Claims,
+    Path((project_id, deployment_id)): Path<(Uuid, Uuid)>,
+    State(database): State<Database>,
+    State(amqp): State<Amqp>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let user_id = claims.sub;
+
+    let deployment =
+        DeploymentRepository::get_by_id(&user_id, &deployment_id, &database.pool).await?;
+    if deployment.status != DeploymentStatus::Suspended {
+        return Err(AppError::BadRequest(
+            "Only suspended deployments can be resumed".to_string(),
+        ));
+    }
+
+    // Prepare message
+    let message = ResumeDeploymentMessage {
+        deployment_id,
+        user_id,
+        project_id,
+        timestamp: chrono::Utc::now().timestamp(),
+    };
+
+    amqp.basic_publish("compute", "compute.resume", &message)
+        .instrument(info_span!("basic_publish.compute.resume"))
+        .await?;
+
+    info!(
+        "📤 Published deployment resume message for {}",
+        deployment_id
+    );
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(MessageResponse::new("Deployment resume initiated")),
+    ))
+}
+
+/// Traefik's errors middleware calls this when a deployment set to suspend after being idle
+/// answers 503. Only deployments the reconciler suspended for being idle are resumed, taking
+/// the Redis key makes concurrent requests publish a single resume
+#[tracing::instrument(
+    name = "wake_deployment_handler",
+    skip_all,
+    fields(deployment_id = %deployment_id),
+    err
+)]
+pub async fn wake_deployment_handler(
+    Path(deployment_id): Path<Uuid>,
+    State(database): State<Database>,
+    State(mut redis): State<Redis>,
+    State(amqp): State<Amqp>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let Some((user_id, project_id, status)) =
+        DeploymentRepository::get_owner_and_status(&deployment_id, &database.pool).await?
+    else {
+        return Err(AppError::NotFoundError("Deployment not found".into()));
+    };
+
+    let mut waking = status != DeploymentStatus::Suspended;
+    if !waking {
+        let idle_key = CacheKeys::deployment_idle_suspended(&deployment_id.to_string());
+        if redis.con.get_del(&idle_key).await?.is_some() {
+            let message = ResumeDeploymentMessage {
+                user_id,
+                project_id,
+                deployment_id,
+                timestamp: chrono::Utc::now().timestamp(),
+            };
+            amqp.basic_publish("compute", "compute.resume", &message)
+                .await?;
+
+            info!("⏰ Waking up idle deployment {}", deployment_id);
+            waking = true;
+        }
+    }
+
+    // Deployments suspended by their owner or by billing stay down
+    let mut headers = HeaderMap::new();
+    let message = if waking {
+        headers.insert(
+            header::RETRY_AFTER,
+            HeaderValue::from(WAKE_RETRY_AFTER_SECS),
+        );
+        "Deployment is starting up, retry in a few seconds"
+    } else {
+        "Deployment is suspended"
+    };
+
+    Ok((
+        StatusCode::SERVICE_UNAVAILABLE,
+        headers,
+        Json(MessageResponse::new(message)),
+    ))
+}
+
+/// Applies `set` and `unset` to the stored environment variables and rolls out the merged set
+#[tracing::instrument(
+    name = "patch_environment_variables_handler",
+    skip_all,
+    fields(
+        user_id = %claims.sub,
+        project_id = %project_id,
+        deployment_id = %deployment_id
+    ),
+    err
+)]
+pub async fn patch_environment_variables_handler(
+    claims: Claims,
+    Path((project_id, deployment_id)): Path<(Uuid, Uuid)>,
+    State(database): State<Database>,
+    State(amqp): State<Amqp>,
+    Json(req): Json<PatchEnvironmentVariablesRequest>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let user_id = claims.sub;
+    req.validate()?;
+
+    let mut merged = None;
+    for _ in 0..ENVIRONMENT_PATCH_ATTEMPTS {
+        let deployment =
+            DeploymentRepository::get_by_id(&user_id, &deployment_id, &database.pool).await?;
+        if deployment.status == DeploymentStatus::Deleted {
+            return Err(AppError::NotFoundError("Deployment not found".to_string()));
+        }
+
+        let mut environment_variables = deployment
+            .environment_variables
+            .and_then(|e| e.0)
+            .unwrap_or_default();
+        for key in &req.unset {
+            environment_variables.remove(key);
+        }
+        environment_variables.extend(req.set.clone());
+
+        if DeploymentRepository::update_environment_variables(
+            &user_id,
+            &deployment_id,
+            &environment_variables,
+            deployment.updated_at,
+            &database.pool,
+        )
+        .await?
+        {
+            merged = Some(environment_variables);
+            break;
+        }
+    }
+    let Some(environment_variables) = merged else {
+        return Err(AppError::Conflict(
+            "Deployment is being updated concurrently, try again".to_string(),
+        ));
+    };
+
+    // Prepare message
+    let message = UpdateDeploymentMessage {
+        user_id,
+        project_id,
+        deployment_id,
+        name: None,
+        source: None,
+        port: None,
+        desired_replicas: None,
+        preset_id: None,
+        resource_spec: None,
+        secrets: None,
+        environment_variables: Some(environment_variables.clone()),
+        labels: None,
+        tags: None,
+        domain: None,
+        subdomain: None,
+        autoscaling: None,
+        canary: None,
+        restart_at: None,
+        ip_allowlist: None,
+        alert_thresholds: None,
+        config_maps: None,
+        timestamp: chrono::Utc::now().timestamp(),
+    };
+
+    amqp.basic_publish("compute", "compute.update", &message)
+        .instrument(info_span!("basic_publish.compute.update"))
+        .await?;
+
+    info!(
+        "📤 Published environment variables update message for {}",
+        deployment_id
+    );
+
+    Ok(Json(EnvironmentVariablesResponse {
+        environment_variables,
+    }))
+}
+
+#[tracing::instrument(
+    name = "rotate_registry_credentials_handler",
+    skip_all,
+    fields(
+        user_id = %claims.sub,
+        project_id = %project_id,
+        deployment_id = %deployment_id
+    ),
+    err
+)]
+pub async fn rotate_registry_credentials_handler(
+    claims: Claims,
+    Path((project_id, deployment_id)): Path<(Uuid, Uuid)>,
+    State(database): State<Database>,
+    State(amqp): State<Amqp>,
+    Json(req): Json<ImagePullSecret>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let user_id = claims.sub;
+    req.validate()?;
+
+    let deployment =
+        DeploymentRepository::get_by_id(&user_id, &deployment_id, &database.pool).await?;
+    if deployment.status == DeploymentStatus::Deleted {
+        return Err(AppError::NotFoundError("Deployment not found".to_string()));
+    }
+
+    // Stored first, so a later rebuild of the pod spec picks up the new credentials too
+    if !DeploymentRepository::update_image_pull_secret(
+        &user_id,
+        &deployment_id,
+        &req,
+        &database.pool,
+    )
+    .await?
+    {
+        return Err(AppError::BadRequest(
+            "Only image deployments pull from a registry".to_string(),
+        ));
+    }
+
+    // Prepare message
+    let message = RotateRegistryCredentialsMessage {
+        deployment_id,
+        user_id,
+        project_id,
+        image_pull_secret: req,
+        timestamp: chrono::Utc::now().timestamp(),
+    };
+
+    amqp.basic_publish("compute", "compute.registry_credentials", &message)
+        .instrument(info_span!("basic_publish.compute.registry_credentials"))
+        .await?;
+
+    info!(
+        "📤 Published registry credentials rotation message for {}",
+        deployment_id
+    );
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(MessageResponse::new(
+            "Registry credentials rotation initiated",
+        )),
+    ))
+}
+
+#[tracing::instrument(
+    name = "get_secret_audit_handler",
+    skip_all,
+    fields(
+        user_id = %claims.sub,
+        project_id = %project_id,
+        deployment_id = %deployment_id
+    ),
+    err
+)]
+pub async fn get_secret_audit_handler(
+    claims: Claims,
+    Path((project_id, deployment_id)): Path<(Uuid, Uuid)>,
+    State(database): State<Database>,
+    State(vault): State<VaultService>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let user_id = claims.sub;
+
+    // Ownership check, the audit path only carries the namespace and deployment id
+    DeploymentRepository::get_by_id(&user_id, &deployment_id, &database.pool).await?;
+
+    let entries = vault
+        .read_secret_audit(&format_namespace(&user_id), &deployment_id)
+        .await?;
+
+    Ok(Json(entries))
+}
+
+#[tracing::instrument(
+    name = "rotate_secrets_handler",
+    skip_all,
+    fields(
+        user_id = %claims.sub,
+        project_id = %project_id,
+        deployment_id = %deployment_id
+    ),
+    err
+)]
+pub async fn rotate_secrets_handler(
+    claims: Claims,
+    Path((project_id, deployment_id)): Path<(Uuid, Uuid)>,
+    State(database): State<Database>,
+    State(amqp): State<Amqp>,
+    Json(req): Json<RotateSecretsRequest>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let user_id = claims.sub;
+    req.validate()?;
+
+    let deployment =
+        DeploymentRepository::get_by_id(&user_id, &deployment_id, &database.pool).await?;
+    if deployment.status == DeploymentStatus::Deleted {
+        return Err(AppError::NotFoundError("Deployment not found".to_string()));
+    }
+    // Without a synced secret the pods have nothing to read new values from
+    if deployment.vault_secret_path.is_none() {
+        return Err(AppError::BadRequest(
+            "Deployment has no secrets, add them by updating the deployment".to_string(),
+        ));
+    }
+
+    // Prepare message
+    let message = RotateSecretsMessage {
+        deployment_id,
+        user_id,
+        project_id,
+        secrets: req.secrets,
+        timestamp: chrono::Utc::now().timestamp(),
+    };
+
+    amqp.basic_publish("compute", "compute.secrets", &message)
+        .instrument(info_span!("basic_publish.compute.secrets"))
+        .await?;
+
+    info!(
+        "📤 Published secrets rotation message for {}",
+        deployment_id
+    );
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(MessageResponse::new("Secrets rotation initiated")),
+    ))
+}
@@ -1,16 +1,20 @@
 use aide::axum::IntoApiResponse;
 use axum::{
     Json,
-    extract::{Query, State},
+    extract::{Path, Query, State},
 };
-use compute_core::github_app::GithubApp;
-use factory::factories::database::Database;
+use compute_core::github_app::{GithubApp, error::GithubAppError};
+use factory::factories::{database::Database, redis::Redis};
 use http_contracts::{list::schema::ListResponse, message::MessageResponse};
 use reqwest::Client;
 use tracing::debug;
 use users_core::jwt::Claims;
 
-use crate::{error::AppError, features::schemas::CallbackParams};
+use crate::{
+    error::AppError,
+    features::schemas::{CallbackParams, CommitsQuery},
+    services::cache_service::CacheService,
+};
 
 #[tracing::instrument(name = "github_setup_handler", skip_all, fields(user_id = %claims.sub), err)]
 pub async fn github_setup_handler(
@@ -66,13 +70,117 @@ pub async fn get_repositories_handler(
 
     let access_token = github_app
         .create_installation_token(installation_id, &http)
-        .await
-        .map_err(|e| AppError::InternalServerError(format!("github access token: {}", e)))?;
+        .await?;
 
     let (data, total) = github_app
         .list_installation_repos(&access_token, &http)
+        .await?;
+
+    Ok(Json(ListResponse { data, total }))
+}
+
+#[tracing::instrument(name = "get_repository_branches_handler", skip_all, fields(user_id = %claims.sub, repository), err)]
+pub async fn get_repository_branches_handler(
+    claims: Claims,
+    Path((owner, repo)): Path<(String, String)>,
+    State(github_app): State<GithubApp>,
+    State(http): State<Client>,
+    State(db): State<Database>,
+    State(mut redis): State<Redis>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let user_id = claims.sub;
+    let full_name = format!("{}/{}", owner, repo);
+    tracing::Span::current().record("repository", &full_name);
+
+    let installation_id = sqlx::query_scalar!(
+        "SELECT installation_id FROM installations WHERE user_id = $1",
+        user_id
+    )
+    .fetch_optional(&db.pool)
+    .await?
+    .ok_or_else(|| AppError::NotFoundError("installation_id not found".into()))?;
+
+    // Keyed by installation, so users sharing one also share the cached list
+    if let Some(data) =
+        CacheService::get_github_branches(installation_id, &full_name, &mut redis.con).await?
+    {
+        let total = data.len() as i64;
+        return Ok(Json(ListResponse { data, total }));
+    }
+
+    let access_token = github_app
+        .create_installation_token(installation_id, &http)
+        .await?;
+
+    let data = github_app
+        .list_repository_branches(&access_token, &owner, &repo, &http)
         .await
-        .map_err(|e| AppError::InternalServerError(format!("github repos: {}", e)))?;
+        .map_err(|e| match e {
+            GithubAppError::NotFound => {
+                AppError::NotFoundError(format!("Repository {} not found", full_name))
+            }
+            e => e.into(),
+        })?;
+    CacheService::set_github_branches(installation_id, &full_name, &data, &mut redis.con).await?;
+
+    let total = data.len() as i64;
+    Ok(Json(ListResponse { data, total }))
+}
+
+#[tracing::instrument(name = "get_repository_commits_handler", skip_all, fields(user_id = %claims.sub, repository, branch = %query.branch), err)]
+pub async fn get_repository_commits_handler(
+    claims: Claims,
+    Path((owner, repo)): Path<(String, String)>,
+    Query(query): Query<CommitsQuery>,
+    State(github_app): State<GithubApp>,
+    State(http): State<Client>,
+    State(db): State<Database>,
+    State(mut redis): State<Redis>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let user_id = claims.sub;
+    let full_name = format!("{}/{}", owner, repo);
+    tracing::Span::current().record("repository", &full_name);
+
+    let installation_id = sqlx::query_scalar!(
+        "SELECT installation_id FROM installations WHERE user_id = $1",
+        user_id
+    )
+    .fetch_optional(&db.pool)
+    .await?
+    .ok_or_else(|| AppError::NotFoundError("installation_id not found".into()))?;
+
+    if let Some(data) =
+        CacheService::get_github_commits(installation_id, &full_name, &query.branch, &mut redis.con)
+            .await?
+    {
+        let total = data.len() as i64;
+        return Ok(Json(ListResponse { data, total }));
+    }
+
+    let access_token = github_app
+        .create_installation_token(installation_id, &http)
+        .await?;
+
+    let data = github_app
+        .list_repository_commits(&access_token, &owner, &repo, &query.branch, &http)
+        .await
+        .map_err(|e| match e {
+            // GitHub answers 404 alike for a missing repository, branch, or one the app can't see
+            GithubAppError::NotFound => AppError::NotFoundError(format!(
+                "Branch {} of {} not found, make sure the repository is granted to the Poddle GitHub App installation",
+                query.branch, full_name
+            )),
+            e => e.into(),
+        })?;
+    CacheService::set_github_commits(
+        installation_id,
+        &full_name,
+        &query.branch,
+        &data,
+        &mut redis.con,
+    )
+    .await?;
 
+    let total = data.len() as i64;
     Ok(Json(ListResponse { data, total }))
 }
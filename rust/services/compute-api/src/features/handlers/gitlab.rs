@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use aide::axum::IntoApiResponse;
+use axum::{Json, extract::State};
+use compute_core::gitlab_app::{GitlabApp, error::GitlabAppError, schemas::GitlabTokenResponse};
+use factory::factories::database::Database;
+use http_contracts::list::schema::ListResponse;
+use rand::{Rng, distr::Alphanumeric};
+use reqwest::Client;
+use sha2::{Digest, Sha256};
+use users_core::jwt::Claims;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    features::schemas::{GitlabSetupRequest, GitlabSetupResponse},
+    services::vault_service::VaultService,
+};
+
+/// Tokens this close to expiring are refreshed before being used
+const TOKEN_REFRESH_LEEWAY_SECS: i64 = 60;
+
+#[tracing::instrument(name = "gitlab_setup_handler", skip_all, fields(user_id = %claims.sub), err)]
+pub async fn gitlab_setup_handler(
+    claims: Claims,
+    State(gitlab_app): State<GitlabApp>,
+    State(http): State<Client>,
+    State(vault): State<VaultService>,
+    State(db): State<Database>,
+    Json(req): Json<GitlabSetupRequest>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let user_id = claims.sub;
+
+    let tokens = gitlab_app
+        .exchange_code(&req.code, &http)
+        .await
+        .map_err(|e| AppError::BadRequest(format!("gitlab oauth: {}", e)))?;
+    store_tokens(&user_id, &tokens, &vault).await?;
+
+    // Setting up again rotates the webhook token
+    let webhook_token: String = rand::rng()
+        .sample_iter(&Alphanumeric)
+        .take(40)
+        .map(char::from)
+        .collect();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO gitlab_connections (user_id, webhook_token_hash)
+        VALUES ($1, $2)
+        ON CONFLICT (user_id) DO UPDATE SET webhook_token_hash = EXCLUDED.webhook_token_hash
+        "#,
+        user_id,
+        hash_webhook_token(&webhook_token),
+    )
+    .execute(&db.pool)
+    .await?;
+
+    Ok(Json(GitlabSetupResponse { webhook_token }))
+}
+
+#[tracing::instrument(name = "get_gitlab_repositories_handler", skip_all, fields(user_id = %claims.sub), err)]
+pub async fn get_gitlab_repositories_handler(
+    claims: Claims,
+    State(gitlab_app): State<GitlabApp>,
+    State(http): State<Client>,
+    State(vault): State<VaultService>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let access_token = gitlab_access_token(&claims.sub, &gitlab_app, &http, &vault).await?;
+
+    let (data, total) = gitlab_app
+        .list_repositories(&access_token, &http)
+        .await
+        .map_err(|e| match e {
+            GitlabAppError::Unauthorized => {
+                AppError::Unauthorized("GitLab access was revoked, connect GitLab again".into())
+            }
+            e => e.into(),
+        })?;
+
+    Ok(Json(ListResponse { data, total }))
+}
+
+/// The webhook token is random enough that a plain digest is sufficient
+pub fn hash_webhook_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// `gitlab/users/{user_id}` in Vault
+fn tokens_path(user_id: &Uuid) -> String {
+    format!("gitlab/users/{}", user_id)
+}
+
+async fn store_tokens(
+    user_id: &Uuid,
+    tokens: &GitlabTokenResponse,
+    vault: &VaultService,
+) -> Result<(), AppError> {
+    // GitLab has issued two hour tokens since 15.0, older instances never expire them
+    let expires_at = tokens
+        .expires_in
+        .map(|secs| chrono::Utc::now().timestamp() + secs)
+        .unwrap_or(i64::MAX);
+
+    let secrets = HashMap::from([
+        ("access_token".to_string(), tokens.access_token.clone()),
+        ("refresh_token".to_string(), tokens.refresh_token.clone()),
+        ("expires_at".to_string(), expires_at.to_string()),
+    ]);
+
+    vault.store_secrets(&tokens_path(user_id), &secrets).await
+}
+
+/// Reads the user's access token from Vault, refreshing it first when it is about to expire
+pub async fn gitlab_access_token(
+    user_id: &Uuid,
+    gitlab_app: &GitlabApp,
+    http: &Client,
+    vault: &VaultService,
+) -> Result<String, AppError> {
+    let mut secrets = vault
+        .read_secrets(&tokens_path(user_id))
+        .await?
+        .ok_or_else(|| AppError::NotFoundError("GitLab is not connected".into()))?;
+
+    let expires_at = secrets
+        .get("expires_at")
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or_default();
+    if expires_at - TOKEN_REFRESH_LEEWAY_SECS > chrono::Utc::now().timestamp()
+        && let Some(access_token) = secrets.remove("access_token")
+    {
+        return Ok(access_token);
+    }
+
+    let refresh_token = secrets
+        .remove("refresh_token")
+        .ok_or_else(|| AppError::NotFoundError("GitLab is not connected".into()))?;
+    let tokens = gitlab_app
+        .refresh_token(&refresh_token, http)
+        .await
+        .map_err(|_| {
+            AppError::Unauthorized("GitLab access was revoked, connect GitLab again".into())
+        })?;
+    store_tokens(user_id, &tokens, vault).await?;
+
+    Ok(tokens.access_token)
+}
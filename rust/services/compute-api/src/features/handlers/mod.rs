@@ -1,5 +1,9 @@
+pub mod cluster;
 pub mod dashboard;
 pub mod deployment;
 pub mod github;
+pub mod gitlab;
 pub mod pod;
+pub mod preset;
 pub mod project;
+pub mod region;
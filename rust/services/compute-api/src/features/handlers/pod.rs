@@ -3,19 +3,24 @@ use crate::{
     error::AppError,
     features::{
         queries::{DeploymentMetricsQuery, LogQuery},
-        repositories::deployment::DeploymentRepository,
-        schemas::{LogResponse, LokiResponse},
+        repositories::{
+            deployment::DeploymentRepository, pod_log_archive::PodLogArchiveRepository,
+        },
+        schemas::{LogArchiveResponse, LogResponse, LokiResponse},
     },
     services::cache_service::CacheService,
 };
+use std::time::Duration;
+
 use aide::axum::IntoApiResponse;
 use axum::{
     Json,
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{Method, StatusCode},
 };
 use factory::factories::{database::Database, redis::Redis};
-use http_contracts::{list::schema::ListResponse, pagination::schema::Pagination};
+use http_contracts::pagination::schema::{Paginated, Pagination};
+use object_store::{aws::AmazonS3, path::Path as ObjectPath, signer::Signer};
 
 use reqwest::Client;
 use tracing::{error, info};
@@ -23,6 +28,9 @@ use url::Url;
 use users_core::jwt::Claims;
 use uuid::Uuid;
 
+/// Long enough to start the download, short enough that a leaked link soon stops working
+const LOG_ARCHIVE_URL_TTL_SECS: u64 = 900;
+
 #[tracing::instrument(
     name = "get_pods_handler",
     skip_all,
@@ -49,7 +57,7 @@ pub async fn get_pods_handler(
     let (data, total) =
         CacheService::get_pods(&deployment_id.to_string(), count, &p, &mut redis.con).await?;
 
-    Ok(Json(ListResponse { data, total }))
+    Ok(Json(Paginated::new(data, total, &p)))
 }
 
 #[tracing::instrument(
@@ -72,7 +80,8 @@ pub async fn get_logs_handler(
     State(db): State<Database>,
 ) -> Result<impl IntoApiResponse, AppError> {
     let preset_id =
-        DeploymentRepository::get_prest_id(&claims.sub, &deployment_id, &db.pool).await?;
+        DeploymentRepository::get_prest_id(&claims.sub, &project_id, &deployment_id, &db.pool)
+            .await?;
 
     // Parse Base URL
     let mut url = Url::parse(&cfg.loki.url).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -121,3 +130,41 @@ pub async fn get_logs_handler(
 
     Ok(Json(response))
 }
+
+#[tracing::instrument(
+    name = "get_log_archive_handler",
+    skip_all,
+    fields(
+        user_id = %claims.sub,
+        project_id = %project_id,
+        deployment_id = %deployment_id,
+        pod_uid = %pod_uid,
+    ),
+    err
+)]
+pub async fn get_log_archive_handler(
+    claims: Claims,
+    Path((project_id, deployment_id, pod_uid)): Path<(Uuid, Uuid, String)>,
+    State(db): State<Database>,
+    State(s3): State<AmazonS3>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let object_path = PodLogArchiveRepository::get_latest_path(
+        &claims.sub,
+        &project_id,
+        &deployment_id,
+        &pod_uid,
+        &db.pool,
+    )
+    .await?
+    .ok_or_else(|| AppError::NotFoundError("Log archive not found".into()))?;
+
+    let ttl = Duration::from_secs(LOG_ARCHIVE_URL_TTL_SECS);
+    let url = s3
+        .signed_url(Method::GET, &ObjectPath::from(object_path), ttl)
+        .await?;
+
+    Ok(Json(LogArchiveResponse {
+        url: url.to_string(),
+        expires_at: chrono::Utc::now() + ttl,
+    }))
+}
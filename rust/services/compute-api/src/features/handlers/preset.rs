@@ -0,0 +1,29 @@
+use aide::axum::IntoApiResponse;
+use axum::{Json, extract::State};
+use factory::factories::{database::Database, redis::Redis};
+use users_core::jwt::Claims;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError, features::repositories::billing::BillingRepository,
+    services::cache_service::CacheService,
+};
+
+#[tracing::instrument(name = "get_presets_handler", skip_all, fields(user_id = %claims.sub), err)]
+pub async fn get_presets_handler(
+    claims: Claims,
+    State(database): State<Database>,
+    State(mut redis): State<Redis>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let user_id: Uuid = claims.sub;
+
+    // Redis only caches the prices, CacheService logs its errors and Postgres answers instead
+    if let Ok(Some(presets)) = CacheService::get_presets(&user_id, &mut redis.con).await {
+        return Ok(Json(presets));
+    }
+
+    let presets = BillingRepository::get_presets(&user_id, &database.pool).await?;
+    let _ = CacheService::set_presets(&user_id, &presets, &mut redis.con).await;
+
+    Ok(Json(presets))
+}
@@ -1,8 +1,10 @@
 use crate::{
     error::AppError,
-    features::repositories::{
-        deployment_event::DeploymentEventRepository, project::ProjectRepository,
+    features::{
+        repositories::{deployment_event::DeploymentEventRepository, project::ProjectRepository},
+        schemas::{ProjectResponse, ProjectTagsResponse},
     },
+    services::cache_service::CacheService,
 };
 use aide::axum::IntoApiResponse;
 use axum::{
@@ -10,11 +12,17 @@ use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
 };
-use compute_core::schemas::{CreateProjectRequest, UpdateProjectRequest};
-use factory::factories::{database::Database, redis::Redis};
+use compute_core::schemas::{
+    CreateProjectRequest, EnvironmentVariablesResponse, PatchEnvironmentVariablesRequest,
+    UpdateDeploymentMessage, UpdateProjectRequest,
+};
+use factory::factories::{amqp::Amqp, database::Database, redis::Redis};
 use http_contracts::{
-    list::schema::ListResponse, message::MessageResponse, pagination::schema::Pagination,
+    message::MessageResponse,
+    pagination::schema::{Paginated, Pagination},
 };
+use std::collections::{BTreeMap, HashMap};
+use tracing::{Instrument, info, info_span};
 
 use users_core::jwt::Claims;
 use uuid::Uuid;
@@ -32,7 +40,7 @@ pub async fn get_projects_overview_handler(
     let (data, total) =
         ProjectRepository::get_many_overviews(&user_id, &p, &database.pool, &mut redis.con).await?;
 
-    Ok(Json(ListResponse { data, total }))
+    Ok(Json(Paginated::new(data, total, &p)))
 }
 
 #[tracing::instrument(name = "get_project_overview_handler", skip_all, fields(user_id = %claims.sub, project_id = %project_id), err)]
@@ -56,12 +64,44 @@ pub async fn get_projects(
     claims: Claims,
     Query(p): Query<Pagination>,
     State(database): State<Database>,
+    State(mut redis): State<Redis>,
 ) -> Result<impl IntoApiResponse, AppError> {
     let user_id: Uuid = claims.sub;
 
-    let (data, total) = ProjectRepository::get_many(&user_id, &p, &database.pool).await?;
+    let (projects, total) = ProjectRepository::get_many(&user_id, &p, &database.pool).await?;
+
+    let project_ids: Vec<Uuid> = projects.iter().map(|project| project.id).collect();
+    let cached = CacheService::get_project_deployment_counts(&project_ids, &mut redis.con).await?;
+
+    // Only the projects missing from the cache are counted
+    let missing: Vec<Uuid> = project_ids
+        .iter()
+        .zip(&cached)
+        .filter(|(_, counts)| counts.is_none())
+        .map(|(id, _)| *id)
+        .collect();
+    let mut counted = HashMap::new();
+    if !missing.is_empty() {
+        counted = ProjectRepository::get_deployment_counts(&missing, &database.pool).await?;
+        CacheService::set_project_deployment_counts(&counted, &mut redis.con).await?;
+    }
 
-    Ok(Json(ListResponse { data, total }))
+    let data: Vec<ProjectResponse> = projects
+        .into_iter()
+        .zip(cached)
+        .map(|(project, cached)| {
+            let deployment_counts = cached
+                .or_else(|| counted.get(&project.id).copied())
+                .unwrap_or_default();
+            ProjectResponse {
+                project,
+                deployment_counts,
+                health: deployment_counts.health(),
+            }
+        })
+        .collect();
+
+    Ok(Json(Paginated::new(data, total, &p)))
 }
 
 #[tracing::instrument(name = "get_project_handler", skip_all, fields(user_id = %claims.sub, project_id = %project_id), err)]
@@ -130,6 +170,106 @@ pub async fn update_project_handler(
     Ok(Json(project))
 }
 
+/// Variables every deployment of the project inherits, running deployments are rolled out with
+/// the change
+#[tracing::instrument(
+    name = "patch_project_environment_variables_handler",
+    skip_all,
+    fields(
+        user_id = %claims.sub,
+        project_id = %project_id
+    ),
+    err
+)]
+pub async fn patch_project_environment_variables_handler(
+    claims: Claims,
+    Path(project_id): Path<Uuid>,
+    State(database): State<Database>,
+    State(amqp): State<Amqp>,
+    Json(req): Json<PatchEnvironmentVariablesRequest>,
+) -> Result<impl IntoApiResponse, AppError> {
+    req.validate()?;
+
+    let user_id: Uuid = claims.sub;
+
+    let environment_variables = ProjectRepository::patch_environment_variables(
+        &user_id,
+        &project_id,
+        &req.set,
+        &req.unset,
+        &database.pool,
+    )
+    .await?;
+
+    // The provisioner merges the project's variables into each deployment's own on update
+    let deployment_ids =
+        ProjectRepository::get_rollout_deployment_ids(&user_id, &project_id, &database.pool)
+            .await?;
+    for deployment_id in &deployment_ids {
+        let message = UpdateDeploymentMessage {
+            user_id,
+            project_id,
+            deployment_id: *deployment_id,
+            name: None,
+            source: None,
+            port: None,
+            desired_replicas: None,
+            preset_id: None,
+            resource_spec: None,
+            secrets: None,
+            environment_variables: None,
+            labels: None,
+            tags: None,
+            domain: None,
+            subdomain: None,
+            autoscaling: None,
+            canary: None,
+            restart_at: None,
+            ip_allowlist: None,
+            alert_thresholds: None,
+            config_maps: None,
+            timestamp: chrono::Utc::now().timestamp(),
+        };
+
+        amqp.basic_publish("compute", "compute.update", &message)
+            .instrument(info_span!("basic_publish.compute.update"))
+            .await?;
+    }
+
+    info!(
+        "📤 Published environment variables update message for {} deployments",
+        deployment_ids.len()
+    );
+
+    Ok(Json(EnvironmentVariablesResponse {
+        environment_variables,
+    }))
+}
+
+#[tracing::instrument(
+    name = "get_project_tags_handler",
+    skip_all,
+    fields(
+        user_id = %claims.sub,
+        project_id = %project_id
+    ),
+    err
+)]
+pub async fn get_project_tags_handler(
+    claims: Claims,
+    Path(project_id): Path<Uuid>,
+    State(database): State<Database>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let user_id: Uuid = claims.sub;
+
+    let mut tags: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (key, value) in ProjectRepository::get_tags(&user_id, &project_id, &database.pool).await? {
+        tags.entry(key).or_default().push(value);
+    }
+
+    Ok(Json(ProjectTagsResponse { tags }))
+}
+
 #[tracing::instrument(
     name = "delete_project_handler",
     skip_all,
@@ -167,5 +307,5 @@ pub async fn get_project_events_handler(
         DeploymentEventRepository::get_many_by_project(&user_id, &project_id, &p, &database.pool)
             .await?;
 
-    Ok(Json(ListResponse { data, total }))
+    Ok(Json(Paginated::new(data, total, &p)))
 }
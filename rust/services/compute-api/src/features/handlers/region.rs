@@ -0,0 +1,73 @@
+use aide::axum::IntoApiResponse;
+use axum::{Json, extract::State};
+use factory::factories::{database::Database, kubernetes::Kubernetes};
+use users_core::jwt::Claims;
+
+use crate::{
+    config::Config,
+    error::AppError,
+    features::{repositories::region::RegionRepository, schemas::RegionResponse},
+};
+
+#[tracing::instrument(name = "get_regions_handler", skip_all, fields(user_id = %claims.sub), err)]
+pub async fn get_regions_handler(
+    claims: Claims,
+    State(cfg): State<Config>,
+    State(db): State<Database>,
+    State(kubernetes): State<Kubernetes>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let pods = RegionRepository::get_pod_counts(&kubernetes.default_region, &db.pool).await?;
+
+    let mut names: Vec<&String> = kubernetes.regions.keys().collect();
+    names.sort();
+
+    let regions: Vec<RegionResponse> = names
+        .into_iter()
+        .map(|name| {
+            let region = cfg.clusters.regions.get(name);
+            let pods = pods.get(name).copied().unwrap_or_default();
+            let max_pods = region.and_then(|r| r.max_pods);
+            RegionResponse {
+                name: name.clone(),
+                display_name: region
+                    .and_then(|r| r.display_name.clone())
+                    .unwrap_or_else(|| name.clone()),
+                default: *name == kubernetes.default_region,
+                pods,
+                max_pods,
+                available_pods: max_pods.map(|max| (max - pods).max(0)),
+            }
+        })
+        .collect();
+
+    Ok(Json(regions))
+}
+
+/// The region a new deployment goes to, rejecting names the service isn't configured with
+pub fn resolve_region(kubernetes: &Kubernetes, region: Option<&str>) -> Result<String, AppError> {
+    match region {
+        Some(region) if kubernetes.regions.contains_key(region) => Ok(region.to_string()),
+        Some(region) => Err(AppError::BadRequest(format!("Unknown region '{}'", region))),
+        None => Ok(kubernetes.default_region.clone()),
+    }
+}
+
+/// Turns away `replicas` more pods once they would take `region` past its `max_pods`
+pub async fn check_region_capacity(
+    cfg: &Config,
+    kubernetes: &Kubernetes,
+    region: &str,
+    replicas: i32,
+    db: &Database,
+) -> Result<(), AppError> {
+    let Some(max_pods) = cfg.clusters.regions.get(region).and_then(|r| r.max_pods) else {
+        return Ok(());
+    };
+
+    let pods = RegionRepository::get_pod_counts(&kubernetes.default_region, &db.pool).await?;
+    if pods.get(region).copied().unwrap_or_default() + i64::from(replicas) > max_pods {
+        return Err(AppError::RegionAtCapacity(region.to_string()));
+    }
+
+    Ok(())
+}
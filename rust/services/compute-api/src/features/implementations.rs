@@ -1,9 +1,83 @@
-use compute_core::models::DeploymentRow;
+use axum_extra::headers::{self, Header};
+use bytes::Bytes;
+use compute_core::models::{DeploymentRow, DeploymentStatus};
+use http::{HeaderName, HeaderValue};
 
 use crate::features::schemas::{
-    DeploymentOut, LogEntry, LogResponse, LokiResponse, LokiTailResponse,
+    DeploymentCounts, DeploymentOut, ExecFrame, ExecResize, LastEventId, LogEntry, LogResponse,
+    LokiResponse, LokiTailResponse, ProjectHealth,
 };
 
+static LAST_EVENT_ID: HeaderName = HeaderName::from_static("last-event-id");
+
+impl ExecFrame {
+    pub const STDIN: u8 = 0x01;
+    pub const RESIZE: u8 = 0x02;
+
+    /// `None` for an empty frame, an unknown type or a resize that isn't valid JSON
+    pub fn parse(data: Bytes) -> Option<Self> {
+        match *data.first()? {
+            Self::STDIN => Some(Self::Stdin(data.slice(1..))),
+            Self::RESIZE => serde_json::from_slice::<ExecResize>(&data[1..])
+                .ok()
+                .map(Self::Resize),
+            _ => None,
+        }
+    }
+}
+
+impl DeploymentCounts {
+    pub fn add(&mut self, status: DeploymentStatus, count: i32) {
+        match status {
+            DeploymentStatus::Running => self.running += count,
+            DeploymentStatus::Unhealthy
+            | DeploymentStatus::Degraded
+            | DeploymentStatus::Failed
+            | DeploymentStatus::BuildFailed
+            | DeploymentStatus::ImagePullError => self.unhealthy += count,
+            DeploymentStatus::Building | DeploymentStatus::Queued => self.building += count,
+            DeploymentStatus::Suspended => self.suspended += count,
+            DeploymentStatus::Provisioning
+            | DeploymentStatus::Starting
+            | DeploymentStatus::Updating => {}
+            DeploymentStatus::Deleted => return,
+        }
+        self.total += count;
+    }
+
+    pub fn health(&self) -> ProjectHealth {
+        if self.unhealthy == 0 {
+            ProjectHealth::Healthy
+        } else if self.unhealthy * 2 < self.total {
+            ProjectHealth::Degraded
+        } else {
+            ProjectHealth::Critical
+        }
+    }
+}
+
+impl Header for LastEventId {
+    fn name() -> &'static HeaderName {
+        &LAST_EVENT_ID
+    }
+
+    fn decode<'i, I>(values: &mut I) -> Result<Self, headers::Error>
+    where
+        I: Iterator<Item = &'i HeaderValue>,
+    {
+        values
+            .next()
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok())
+            .map(Self)
+            .ok_or_else(headers::Error::invalid)
+    }
+
+    fn encode<E: Extend<HeaderValue>>(&self, values: &mut E) {
+        values.extend(std::iter::once(HeaderValue::from(self.0)));
+    }
+}
+
 impl From<LokiResponse> for LogResponse {
     fn from(loki: LokiResponse) -> Self {
         let mut entries = Vec::new();
@@ -63,6 +137,7 @@ impl From<DeploymentRow> for DeploymentOut {
             secret_keys: row.secret_keys,
             environment_variables: row.environment_variables.map(|j| j.0).flatten(),
             labels: row.labels.map(|j| j.0).flatten(),
+            tags: row.tags.map(|j| j.0),
             status: row.status,
             domain: row.domain,
             subdomain: row.subdomain,
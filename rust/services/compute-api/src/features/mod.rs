@@ -8,84 +8,289 @@ pub mod see;
 pub mod webhook;
 pub mod websocket;
 
-use crate::utilities::app_state::AppState;
+use crate::{
+    config::RateLimitsConfig,
+    utilities::{
+        app_state::AppState,
+        versioning::{ApiVersion, VersionedRouter, Versions},
+    },
+};
+use factory::factories::rate_limit::{RateLimit, RateLimitLayer};
 
 use aide::axum::{
     ApiRouter,
-    routing::{get, post},
+    routing::{ApiMethodRouter, get, patch, post},
 };
 use axum::routing::get as axum_get;
 
-pub fn get_routes() -> ApiRouter<AppState> {
-    ApiRouter::new()
-    // Dashboard
+/// Write routes share a bucket per group, everything else falls back to `default`
+struct RateLimits {
+    projects_write: RateLimitLayer,
+    deployments_write: RateLimitLayer,
+    default: RateLimitLayer,
+}
+
+impl RateLimits {
+    fn new(state: &AppState) -> Self {
+        // Limits are read per request, `Config::watch` may have swapped them since
+        let limit = |select: fn(&RateLimitsConfig) -> RateLimit| {
+            let dynamic = state.dynamic_config.clone();
+            move || {
+                select(
+                    &dynamic
+                        .read()
+                        .expect("dynamic config lock poisoned")
+                        .rate_limits,
+                )
+            }
+        };
+        let layer = |route_group, select| {
+            RateLimitLayer::dynamic(state.redis.con.clone(), route_group, limit(select))
+        };
+
+        Self {
+            projects_write: layer("projects_write", |limits| limits.projects_write),
+            deployments_write: layer("deployments_write", |limits| limits.deployments_write),
+            default: layer("default", |limits| limits.default),
+        }
+    }
+}
+
+fn limited(
+    route: ApiMethodRouter<AppState>,
+    layer: Option<&RateLimitLayer>,
+) -> ApiMethodRouter<AppState> {
+    match layer {
+        Some(layer) => route.route_layer(layer.clone()),
+        None => route,
+    }
+}
+
+pub fn get_routes(state: &AppState) -> ApiRouter<AppState> {
+    routes(Some(RateLimits::new(state)))
+}
+
+/// The same routes without their rate limits, enough to generate the OpenAPI document with no
+/// Redis around
+pub fn documented_routes() -> ApiRouter<AppState> {
+    routes(None)
+}
+
+fn routes(rate_limits: Option<RateLimits>) -> ApiRouter<AppState> {
+    let projects_write = rate_limits.as_ref().map(|limits| &limits.projects_write);
+    let deployments_write = rate_limits.as_ref().map(|limits| &limits.deployments_write);
+
+    let v1 = Versions::since(ApiVersion::V1);
+
+    let router = VersionedRouter::new()
+        // Dashboard
         .api_route(
-            "/api/v1/compute/dashboard",
+            "/compute/dashboard",
+            v1,
             get(handlers::dashboard::get_dashboard_handler),
         )
         .api_route(
-            "/api/v1/compute/dashboard/events",
+            "/compute/dashboard/events",
+            v1,
             get(handlers::dashboard::get_dashboard_events_handler),
         )
+        // Cluster, for operators
         .api_route(
-            "/api/v1/compute/projects/overview",
+            "/admin/cluster/nodes",
+            v1,
+            get(handlers::cluster::get_cluster_nodes_handler),
+        )
+        .api_route(
+            "/admin/cluster/capacity",
+            v1,
+            get(handlers::cluster::get_cluster_capacity_handler),
+        )
+        // Presets
+        .api_route(
+            "/compute/presets",
+            v1,
+            get(handlers::preset::get_presets_handler),
+        )
+        .api_route(
+            "/compute/projects/overview",
+            v1,
             get(handlers::project::get_projects_overview_handler),
         )
+        // Regions
+        .api_route(
+            "/compute/regions",
+            v1,
+            get(handlers::region::get_regions_handler),
+        )
         // Projects
         .api_route(
-            "/api/v1/compute/projects",
-            get(handlers::project::get_projects).post(handlers::project::create_project_handler),
+            "/compute/projects",
+            v1,
+            get(handlers::project::get_projects).merge(
+                limited(post(handlers::project::create_project_handler), projects_write),
+            ),
         )
         .api_route(
-            "/api/v1/compute/projects/{project_id}",
-            get(handlers::project::get_project_handler)
-                .patch(handlers::project::update_project_handler)
-                .delete(handlers::project::delete_project_handler),
+            "/compute/projects/{project_id}",
+            v1,
+            get(handlers::project::get_project_handler).merge(
+                limited(patch(handlers::project::update_project_handler)
+                    .delete(handlers::project::delete_project_handler), projects_write),
+            ),
         )
         .api_route(
-            "/api/v1/compute/projects/{project_id}/events",
+            "/compute/projects/{project_id}/environment-variables",
+            v1,
+            limited(patch(handlers::project::patch_project_environment_variables_handler), projects_write),
+        )
+        .api_route(
+            "/compute/projects/{project_id}/events",
+            v1,
             get(handlers::project::get_project_events_handler)
         )
         .api_route(
-            "/api/v1/compute/projects/{project_id}/overview",
+            "/compute/projects/{project_id}/tags",
+            v1,
+            get(handlers::project::get_project_tags_handler),
+        )
+        .api_route(
+            "/compute/projects/{project_id}/overview",
+            v1,
             get(handlers::project::get_project_overview_handler),
         )
         // Deployments
         .api_route(
-            "/api/v1/compute/projects/{project_id}/deployments",
-            get(handlers::deployment::get_deployments_handler).post(handlers::deployment::create_deployment_handler),
+            "/compute/projects/{project_id}/deployments",
+            v1,
+            get(handlers::deployment::get_deployments_handler).merge(
+                limited(post(handlers::deployment::create_deployment_handler), deployments_write),
+            ),
         )
         .api_route(
-            "/api/v1/compute/projects/{project_id}/deployments/{deployment_id}",
-            get(handlers::deployment::get_deployment_handler)
-                .patch(handlers::deployment::update_deployment_handler)
-                .delete(handlers::deployment::delete_deployment_handler),
+            "/compute/projects/{project_id}/deployments/dry-run",
+            v1,
+            limited(post(handlers::deployment::dry_run_deployment_handler), deployments_write),
         )
         .api_route(
-            "/api/v1/compute/projects/{project_id}/deployments/{deployment_id}/pods",
+            "/compute/projects/{project_id}/deployments/{deployment_id}",
+            v1,
+            get(handlers::deployment::get_deployment_handler).merge(
+                limited(patch(handlers::deployment::update_deployment_handler)
+                    .delete(handlers::deployment::delete_deployment_handler), deployments_write),
+            ),
+        )
+        .api_route(
+            "/compute/projects/{project_id}/deployments/{deployment_id}/diff",
+            v1,
+            get(handlers::deployment::deployment_diff_handler),
+        )
+        .api_route(
+            "/compute/projects/{project_id}/deployments/{deployment_id}/environment-variables",
+            v1,
+            limited(patch(handlers::deployment::patch_environment_variables_handler), deployments_write),
+        )
+        .api_route(
+            "/compute/projects/{project_id}/deployments/{deployment_id}/suspend",
+            v1,
+            limited(post(handlers::deployment::suspend_deployment_handler), deployments_write),
+        )
+        .api_route(
+            "/compute/projects/{project_id}/deployments/{deployment_id}/resume",
+            v1,
+            limited(post(handlers::deployment::resume_deployment_handler), deployments_write),
+        )
+        .api_route(
+            "/compute/projects/{project_id}/deployments/{deployment_id}/registry-credentials",
+            v1,
+            limited(post(handlers::deployment::rotate_registry_credentials_handler), deployments_write),
+        )
+        .api_route(
+            "/compute/projects/{project_id}/deployments/{deployment_id}/secrets/rotate",
+            v1,
+            limited(post(handlers::deployment::rotate_secrets_handler), deployments_write),
+        )
+        .api_route(
+            "/compute/projects/{project_id}/deployments/{deployment_id}/secrets/audit",
+            v1,
+            get(handlers::deployment::get_secret_audit_handler),
+        )
+        .api_route(
+            "/compute/projects/{project_id}/deployments/{deployment_id}/events",
+            v1,
+            get(handlers::deployment::get_deployment_events_handler),
+        )
+        .api_route(
+            "/compute/projects/{project_id}/deployments/{deployment_id}/pods",
+            v1,
             get(handlers::pod::get_pods_handler),
         )
         .api_route(
-            "/api/v1/compute/projects/{project_id}/deployments/{deployment_id}/pods/{pod_uid}/logs",
+            "/compute/projects/{project_id}/deployments/{deployment_id}/pods/{pod_uid}/logs",
+            v1,
             get(handlers::pod::get_logs_handler),
         )
+        .api_route(
+            "/compute/projects/{project_id}/deployments/{deployment_id}/pods/{pod_uid}/logs/archive",
+            v1,
+            get(handlers::pod::get_log_archive_handler),
+        )
         .route(
-            "/api/v1/compute/projects/{project_id}/deployments/{deployment_id}/pods/{pod_uid}/logs/ws",
+            "/compute/projects/{project_id}/deployments/{deployment_id}/pods/{pod_uid}/logs/ws",
+            v1,
             axum_get(websocket::stream_logs_ws_handler),
         )
         .route(
-            "/api/v1/compute/projects/{project_id}/deployments/{deployment_id}/pods/{pod_uid}/logs/sse",
+            "/compute/projects/{project_id}/deployments/{deployment_id}/pods/{pod_uid}/logs/sse",
+            v1,
             axum_get(see::stream_logs_sse_handler),
         )
         .route(
-            "/api/v1/compute/projects/{project_id}/deployments/{deployment_id}/metrics/sse",
+            "/compute/projects/{project_id}/deployments/{deployment_id}/pods/{pod_uid}/exec",
+            v1,
+            axum_get(websocket::exec_ws_handler),
+        )
+        .api_route(
+            "/compute/projects/{project_id}/deployments/{deployment_id}/builds",
+            v1,
+            get(handlers::deployment::get_builds_handler),
+        )
+        .route(
+            "/compute/projects/{project_id}/deployments/{deployment_id}/builds/{build_id}/logs/sse",
+            v1,
+            axum_get(see::stream_build_logs_sse_handler),
+        )
+        .api_route(
+            "/compute/projects/{project_id}/deployments/{deployment_id}/metrics/history",
+            v1,
+            get(handlers::deployment::get_metrics_history_handler),
+        )
+        .route(
+            "/compute/projects/{project_id}/deployments/{deployment_id}/metrics/sse",
+            v1,
             axum_get(see::stream_deployment_metrics_sse_handler),
         )
         .route(
-            "/api/v1/compute/projects/{project_id}/metrics/sse",
+            "/compute/projects/{project_id}/metrics/sse",
+            v1,
             axum_get(see::stream_project_metrics_sse_handler),
         )
-        .api_route("/api/v1/compute/github/repositories", get(handlers::github::get_repositories_handler))
-        .api_route("/api/v1/compute/github/setup", post(handlers::github::github_setup_handler))
-        .api_route("/api/v1/compute/github/webhook", get(webhook::github_webhook))
+        .api_route(
+            "/compute/deployments/{deployment_id}/wake",
+            v1,
+            get(handlers::deployment::wake_deployment_handler),
+        )
+        .api_route("/compute/github/repositories", v1, get(handlers::github::get_repositories_handler))
+        .api_route("/compute/github/repositories/{owner}/{repo}/branches", v1, get(handlers::github::get_repository_branches_handler))
+        .api_route("/compute/github/repositories/{owner}/{repo}/commits", v1, get(handlers::github::get_repository_commits_handler))
+        .api_route("/compute/github/setup", v1, post(handlers::github::github_setup_handler))
+        .api_route("/compute/github/webhook", v1, post(webhook::github_webhook))
+        .api_route("/compute/gitlab/repositories", v1, get(handlers::gitlab::get_gitlab_repositories_handler))
+        .api_route("/compute/gitlab/setup", v1, post(handlers::gitlab::gitlab_setup_handler))
+        .api_route("/compute/gitlab/webhook", v1, post(webhook::gitlab_webhook))
+        .into_router();
+
+    match rate_limits {
+        Some(limits) => router.route_layer(limits.default),
+        None => router,
+    }
 }
@@ -1,9 +1,12 @@
 use bigdecimal::BigDecimal;
 use chrono::{DateTime, Utc};
-use compute_core::models::{DeploymentEventLevel, DeploymentEventType};
+use compute_core::{
+    models::{DeploymentEventLevel, DeploymentEventType},
+    schemas::DeploymentSource,
+};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+use sqlx::{FromRow, types::Json};
 use uuid::Uuid;
 
 #[derive(FromRow, Debug)]
@@ -85,3 +88,12 @@ pub struct ProjectEventQueryRow {
     pub message: Option<String>,
     pub created_at: DateTime<Utc>,
 }
+
+/// Deployment built from a repository a push event arrived for
+#[derive(FromRow, Debug)]
+pub struct GitPushDeploymentRow {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub project_id: Uuid,
+    pub source: Json<DeploymentSource>,
+}
@@ -4,6 +4,7 @@ pub enum TimeRangeError {
     StartInFuture,
     EndInFuture,
     TimestampConversion,
+    RawRangeTooLong,
 }
 
 impl std::fmt::Display for TimeRangeError {
@@ -13,6 +14,10 @@ impl std::fmt::Display for TimeRangeError {
             Self::StartInFuture => write!(f, "Start time cannot be in the future"),
             Self::EndInFuture => write!(f, "End time cannot be in the future"),
             Self::TimestampConversion => write!(f, "Failed to convert timestamp to nanoseconds"),
+            Self::RawRangeTooLong => write!(
+                f,
+                "Raw resolution covers at most 24 hours, use minute or hour instead"
+            ),
         }
     }
 }
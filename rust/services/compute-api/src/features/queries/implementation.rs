@@ -1,7 +1,10 @@
-use chrono::{TimeZone, Utc};
+use std::collections::HashMap;
+
+use chrono::{DateTime, TimeDelta, TimeZone, Utc};
 
 use crate::features::queries::{
-    DeploymentMetricsQuery, DeploymentsMetricsQuery, LogQuery, TailQuery, error::TimeRangeError,
+    DeploymentMetricsQuery, DeploymentsMetricsQuery, DeploymentsTagQuery, LogQuery,
+    MetricsHistoryQuery, MetricsResolution, TailQuery, error::TimeRangeError,
 };
 
 impl std::error::Error for TimeRangeError {}
@@ -24,6 +27,72 @@ impl DeploymentsMetricsQuery {
     }
 }
 
+impl DeploymentsTagQuery {
+    /// Tags asked for, other parameters of the query are left to their own extractors
+    pub fn tags(&self) -> HashMap<String, String> {
+        self.params
+            .iter()
+            .filter_map(|(param, value)| {
+                let key = param.strip_prefix("tag[")?.strip_suffix(']')?;
+                Some((key.to_string(), value.clone()))
+            })
+            .collect()
+    }
+}
+
+impl MetricsResolution {
+    /// `date_trunc` unit of a bucket, snapshots are at least a second apart so `second` keeps them as is
+    pub fn date_trunc_unit(&self) -> &'static str {
+        match self {
+            MetricsResolution::Raw => "second",
+            MetricsResolution::Minute => "minute",
+            MetricsResolution::Hour => "hour",
+        }
+    }
+}
+
+const METRICS_HISTORY_DEFAULT_LIMIT: i64 = 1000;
+const METRICS_HISTORY_MAX_LIMIT: i64 = 5000;
+
+impl MetricsHistoryQuery {
+    /// Page size, clamped so a long raw range can't be pulled in one go
+    pub fn page_size(&self) -> i64 {
+        self.limit
+            .unwrap_or(METRICS_HISTORY_DEFAULT_LIMIT)
+            .clamp(1, METRICS_HISTORY_MAX_LIMIT)
+    }
+
+    /// Returns (start, end, resolution), raw points are only served for ranges of up to a day
+    pub fn resolve(
+        &self,
+    ) -> Result<(DateTime<Utc>, DateTime<Utc>, MetricsResolution), TimeRangeError> {
+        let now = Utc::now();
+        let end = self.end.unwrap_or(now);
+        let start = self.start.unwrap_or(end - TimeDelta::minutes(30));
+
+        if start > now {
+            return Err(TimeRangeError::StartInFuture);
+        }
+
+        if start >= end {
+            return Err(TimeRangeError::StartAfterEnd);
+        }
+
+        let range = end - start;
+        let resolution = match self.resolution {
+            Some(MetricsResolution::Raw) if range > TimeDelta::days(1) => {
+                return Err(TimeRangeError::RawRangeTooLong);
+            }
+            Some(resolution) => resolution,
+            None if range <= TimeDelta::hours(1) => MetricsResolution::Raw,
+            None if range <= TimeDelta::days(2) => MetricsResolution::Minute,
+            None => MetricsResolution::Hour,
+        };
+
+        Ok((start, end, resolution))
+    }
+}
+
 impl LogQuery {
     /// Returns (start_nanos, end_nanos) as strings for Loki query
     /// Compatible with Loki's Unix nanosecond timestamps
@@ -1,9 +1,11 @@
 pub mod error;
 pub mod implementation;
 
+use std::collections::HashMap;
+
 use chrono::{DateTime, Utc};
 use schemars::JsonSchema;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 /// Query for fetching metrics for a single deployment with pods (Deployment Page)
 #[derive(Deserialize, JsonSchema, Debug)]
@@ -14,6 +16,29 @@ pub struct DeploymentMetricsQuery {
     pub minutes: i64,
 }
 
+/// Bucket size of the metrics history, `raw` returns snapshots as they were scraped
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricsResolution {
+    Raw,
+    Minute,
+    Hour,
+}
+
+/// Query for persisted metrics of a single deployment, the last 30 minutes by default
+#[derive(Deserialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsHistoryQuery {
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    /// Picked from the length of the range when omitted
+    pub resolution: Option<MetricsResolution>,
+    /// `nextCursor` of the previous page, snapshots after it are returned
+    pub cursor: Option<i64>,
+    /// Snapshots per page, 1000 by default and at most 5000
+    pub limit: Option<i64>,
+}
+
 /// Query for fetching metrics for multiple deployments (Project Page)
 #[derive(Deserialize, JsonSchema, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -23,6 +48,13 @@ pub struct DeploymentsMetricsQuery {
     pub minutes: i64,
 }
 
+/// `?tag[env]=production&tag[team]=backend`, listed deployments carry every given tag
+#[derive(Deserialize, JsonSchema, Debug)]
+pub struct DeploymentsTagQuery {
+    #[serde(flatten)]
+    pub params: HashMap<String, String>,
+}
+
 /// Query for fetching historical logs with time range
 #[derive(Deserialize, JsonSchema, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -38,6 +70,18 @@ pub struct TailQuery {
     pub start: Option<i64>,
 }
 
+/// Query for an interactive shell, `?command=sh&command=-c&command=...`
+#[derive(Deserialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecQuery {
+    #[serde(default = "default_exec_command")]
+    pub command: Vec<String>,
+}
+
+fn default_exec_command() -> Vec<String> {
+    vec!["/bin/sh".to_string()]
+}
+
 fn default_minutes() -> i64 {
     30
 }
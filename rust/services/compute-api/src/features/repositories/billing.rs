@@ -0,0 +1,35 @@
+use http_contracts::preset::schema::PresetResponse;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub struct BillingRepository;
+
+impl BillingRepository {
+    /// Active presets priced for the given user, applying their billing tier discount if any
+    #[tracing::instrument(name = "billing_repository.get_presets", skip_all, fields(user_id = %user_id), err)]
+    pub async fn get_presets(
+        user_id: &Uuid,
+        pool: &PgPool,
+    ) -> Result<Vec<PresetResponse>, sqlx::Error> {
+        sqlx::query_as!(
+            PresetResponse,
+            r#"
+            SELECT
+                p.id,
+                p.name,
+                p.cpu_millicores,
+                p.memory_mb,
+                ROUND(p.hourly_price * (1 - COALESCE(bt.discount_percent, 0) / 100), 6) AS "effective_hourly_rate!",
+                p.currency
+            FROM presets p
+            LEFT JOIN users u ON u.id = $1
+            LEFT JOIN billing_tiers bt ON bt.id = u.billing_tier_id
+            WHERE p.is_active = TRUE
+            ORDER BY p.monthly_price ASC
+            "#,
+            user_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+}
@@ -0,0 +1,99 @@
+use compute_core::{
+    models::{BuildStatus, BuildType},
+    schemas::BuildSummary,
+};
+use http_contracts::pagination::schema::Pagination;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub struct BuildRepository;
+
+impl BuildRepository {
+    /// Newest first, an empty page also when the deployment isn't the user's
+    #[tracing::instrument(
+        name = "build_repository.get_all_by_deployment",
+        skip_all,
+        fields(user_id = %user_id, deployment_id = %deployment_id),
+        err
+    )]
+    pub async fn get_all_by_deployment(
+        user_id: &Uuid,
+        project_id: &Uuid,
+        deployment_id: &Uuid,
+        pagination: &Pagination,
+        pool: &PgPool,
+    ) -> Result<(Vec<BuildSummary>, i64), sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                b.id,
+                b.deployment_id,
+                b.build_type AS "build_type: BuildType",
+                b.status AS "status: BuildStatus",
+                b.started_at,
+                b.finished_at,
+                b.image,
+                COUNT(*) OVER() AS "total!"
+            FROM builds b
+            INNER JOIN deployments d ON b.deployment_id = d.id
+            INNER JOIN projects p ON d.project_id = p.id
+            WHERE b.deployment_id = $1 AND d.project_id = $2 AND p.owner_id = $3
+            ORDER BY b.started_at DESC
+            LIMIT $4
+            OFFSET $5
+            "#,
+            deployment_id,
+            project_id,
+            user_id,
+            pagination.limit,
+            pagination.offset
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let total = rows.first().map(|r| r.total).unwrap_or(0);
+
+        let builds = rows
+            .into_iter()
+            .map(|r| BuildSummary {
+                id: r.id,
+                deployment_id: r.deployment_id,
+                build_type: r.build_type,
+                status: r.status,
+                started_at: r.started_at,
+                finished_at: r.finished_at,
+                image: r.image,
+            })
+            .collect();
+
+        Ok((builds, total))
+    }
+
+    /// Ownership is checked by the caller, it already fetched the deployment
+    #[tracing::instrument(name = "build_repository.get_latest", skip_all, fields(deployment_id = %deployment_id), err)]
+    pub async fn get_latest(
+        deployment_id: &Uuid,
+        pool: &PgPool,
+    ) -> Result<Option<BuildSummary>, sqlx::Error> {
+        sqlx::query_as!(
+            BuildSummary,
+            r#"
+            SELECT
+                id,
+                deployment_id,
+                build_type AS "build_type: BuildType",
+                status AS "status: BuildStatus",
+                started_at,
+                finished_at,
+                image
+            FROM builds
+            WHERE deployment_id = $1
+            ORDER BY started_at DESC
+            LIMIT 1
+            "#,
+            deployment_id
+        )
+        .fetch_optional(pool)
+        .await
+    }
+}
@@ -1,9 +1,15 @@
+use chrono::{DateTime, Utc};
 use compute_core::{
     formatters::format_resource_name,
-    models::{DeploymentRow, DeploymentStatus},
-    schemas::{CreateDeploymentRequest, DeploymentSource, UpdateDeploymentRequest},
+    models::{DeploymentRow, DeploymentStatus, DeploymentType},
+    schemas::{
+        CreateDeploymentRequest, DeploymentSource, ImagePullSecret, MetricSnapshot,
+        UpdateDeploymentRequest,
+    },
 };
 use http_contracts::pagination::schema::Pagination;
+
+use crate::features::{models::GitPushDeploymentRow, queries::MetricsResolution};
 use sqlx::types::Json;
 use std::collections::HashMap;
 
@@ -23,8 +29,12 @@ impl DeploymentRepository {
         user_id: &Uuid,
         project_id: &Uuid,
         pagination: &Pagination,
+        tags: &HashMap<String, String>,
         pool: &PgPool,
     ) -> Result<(Vec<DeploymentRow>, i64), sqlx::Error> {
+        // Deployments have to carry every requested tag, no filter lists them all
+        let tags = (!tags.is_empty()).then(|| serde_json::to_value(tags).unwrap());
+
         // In standard SQL, if you use COUNT(*), the database "collapses" all your rows into a single number.
         // You lose your individual deployment data.
         // OVER() turns the count into a Window Function.
@@ -49,6 +59,7 @@ impl DeploymentRepository {
                 d.secret_keys,
                 d.environment_variables AS "environment_variables: Json<Option<HashMap<String, String>>>",
                 d.labels AS "labels: Json<Option<HashMap<String, String>>>",
+                d.tags AS "tags: Json<HashMap<String, String>>",
                 d.status AS "status: DeploymentStatus",
                 d.domain,
                 d.subdomain,
@@ -59,6 +70,7 @@ impl DeploymentRepository {
             FROM deployments d
             INNER JOIN projects p ON d.project_id = p.id
             WHERE p.owner_id = $1 AND d.project_id = $2
+            AND ($5::jsonb IS NULL OR d.tags @> $5)
             ORDER BY d.created_at DESC
             LIMIT $3
             OFFSET $4
@@ -66,7 +78,8 @@ impl DeploymentRepository {
             user_id,
             project_id,
             pagination.limit,
-            pagination.offset
+            pagination.offset,
+            tags
         )
         .fetch_all(pool)
         .await?;
@@ -95,6 +108,7 @@ impl DeploymentRepository {
                 secret_keys: r.secret_keys,
                 environment_variables: r.environment_variables,
                 labels: r.labels,
+                tags: r.tags,
                 status: r.status,
                 domain: r.domain,
                 subdomain: r.subdomain,
@@ -133,6 +147,7 @@ impl DeploymentRepository {
                 d.secret_keys,
                 d.environment_variables AS "environment_variables: Json<Option<HashMap<String, String>>>",
                 d.labels AS "labels: Json<Option<HashMap<String, String>>>",
+                d.tags AS "tags: Json<HashMap<String, String>>",
                 d.status AS "status: DeploymentStatus",
                 d.domain,
                 d.subdomain,
@@ -164,6 +179,12 @@ impl DeploymentRepository {
             .as_ref()
             .map(|l| serde_json::to_value(l).unwrap());
 
+        let alert_thresholds = req
+            .alert_thresholds
+            .as_ref()
+            .map(|a| serde_json::to_value(a).unwrap());
+        let tags = req.tags.as_ref().map(|t| serde_json::to_value(t).unwrap());
+
         let source = serde_json::to_value(req.source).unwrap();
 
         let id = Uuid::new_v4();
@@ -187,9 +208,18 @@ impl DeploymentRepository {
                 labels,
                 domain,
                 subdomain,
-                service
+                service,
+                alert_thresholds,
+                suspend_after_idle_minutes,
+                tags,
+                restart_schedule,
+                region,
+                build_timeout_seconds,
+                deployment_type,
+                schedule,
+                create_pdb
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24)
             RETURNING
                 id,
                 user_id,
@@ -207,6 +237,7 @@ impl DeploymentRepository {
                 secret_keys,
                 environment_variables AS "environment_variables: Json<Option<HashMap<String, String>>>",
                 labels AS "labels: Json<Option<HashMap<String, String>>>",
+                tags AS "tags: Json<HashMap<String, String>>",
                 status AS "status: DeploymentStatus",
                 domain,
                 subdomain,
@@ -228,7 +259,16 @@ impl DeploymentRepository {
             labels,
             req.domain,
             req.subdomain,
-            name
+            name,
+            alert_thresholds,
+            req.suspend_after_idle_minutes,
+            tags,
+            req.restart_schedule,
+            req.region,
+            req.build_timeout_seconds.map(|secs| secs as i32),
+            req.deployment_type.unwrap_or_default() as DeploymentType,
+            req.schedule,
+            req.create_pdb.unwrap_or(true)
         )
         .fetch_one(&mut **tx)
         .await
@@ -275,6 +315,11 @@ impl DeploymentRepository {
             .source
             .as_ref()
             .map(|s| serde_json::to_value(s).unwrap());
+        let alert_thresholds = req
+            .alert_thresholds
+            .as_ref()
+            .map(|a| serde_json::to_value(a).unwrap());
+        let tags = req.tags.as_ref().map(|t| serde_json::to_value(t).unwrap());
 
         sqlx::query_as!(
             DeploymentRow,
@@ -291,7 +336,9 @@ impl DeploymentRepository {
                 environment_variables = COALESCE($10, d.environment_variables),
                 labels = COALESCE($11, d.labels),
                 domain = COALESCE($12, d.domain),
-                subdomain = COALESCE($13, d.subdomain)
+                subdomain = COALESCE($13, d.subdomain),
+                alert_thresholds = COALESCE($14, d.alert_thresholds),
+                tags = COALESCE($15, d.tags)
             FROM projects p
             JOIN deployments d2 ON d2.project_id = p.id
             WHERE
@@ -315,6 +362,7 @@ impl DeploymentRepository {
                 d.secret_keys,
                 d.environment_variables AS "environment_variables: Json<Option<HashMap<String, String>>>",
                 d.labels AS "labels: Json<Option<HashMap<String, String>>>",
+                d.tags AS "tags: Json<HashMap<String, String>>",
                 d.status AS "status: DeploymentStatus",
                 d.domain,
                 d.subdomain,
@@ -334,7 +382,9 @@ impl DeploymentRepository {
             environment_variables,
             labels.flatten(),
             req.domain,
-            req.subdomain
+            req.subdomain,
+            alert_thresholds,
+            tags
         )
         .fetch_one(&mut **tx)
         .await
@@ -345,25 +395,27 @@ impl DeploymentRepository {
         user_id: &Uuid,
         deployment_id: &Uuid,
         tx: &mut Transaction<'_, Postgres>,
-    ) -> Result<(), sqlx::Error> {
-        sqlx::query(
+    ) -> Result<Option<String>, sqlx::Error> {
+        let region = sqlx::query_scalar::<_, Option<String>>(
             r#"
             DELETE FROM deployments d
             USING projects p
             WHERE d.id = $1 AND d.project_id = p.id AND p.owner_id = $2
+            RETURNING d.region
             "#,
         )
         .bind(deployment_id)
         .bind(user_id)
-        .execute(&mut **tx)
+        .fetch_optional(&mut **tx)
         .await?;
 
-        Ok(())
+        Ok(region.flatten())
     }
 
-    #[tracing::instrument(name = "deployment_repository.get_prest_id", skip_all, fields(user_id = %user_id, deployment_id = %deployment_id), err)]
+    #[tracing::instrument(name = "deployment_repository.get_prest_id", skip_all, fields(user_id = %user_id, project_id = %project_id, deployment_id = %deployment_id), err)]
     pub async fn get_prest_id(
         user_id: &Uuid,
+        project_id: &Uuid,
         deployment_id: &Uuid,
         pool: &PgPool,
     ) -> Result<Uuid, sqlx::Error> {
@@ -372,12 +424,240 @@ impl DeploymentRepository {
             SELECT d.preset_id
             FROM deployments d
             INNER JOIN projects p ON d.project_id = p.id
-            WHERE d.id = $1 AND p.owner_id = $2
+            WHERE d.id = $1 AND p.owner_id = $2 AND d.project_id = $3
             "#,
             deployment_id,
-            user_id
+            user_id,
+            project_id
         )
         .fetch_one(pool)
         .await
     }
+
+    /// Region the user's deployment runs in, `None` for the default region. Fails like
+    /// `get_prest_id` when the deployment isn't the user's or isn't in the project
+    #[tracing::instrument(name = "deployment_repository.get_region", skip_all, fields(user_id = %user_id, project_id = %project_id, deployment_id = %deployment_id), err)]
+    pub async fn get_region(
+        user_id: &Uuid,
+        project_id: &Uuid,
+        deployment_id: &Uuid,
+        pool: &PgPool,
+    ) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT d.region
+            FROM deployments d
+            INNER JOIN projects p ON d.project_id = p.id
+            WHERE d.id = $1 AND p.owner_id = $2 AND d.project_id = $3
+            "#,
+            deployment_id,
+            user_id,
+            project_id
+        )
+        .fetch_one(pool)
+        .await
+    }
+
+    /// `(user_id, project_id, status)`, for callers that have no claims to check ownership with
+    #[tracing::instrument(name = "deployment_repository.get_owner_and_status", skip_all, fields(deployment_id = %deployment_id), err)]
+    pub async fn get_owner_and_status(
+        deployment_id: &Uuid,
+        pool: &PgPool,
+    ) -> Result<Option<(Uuid, Uuid, DeploymentStatus)>, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+            SELECT user_id, project_id, status AS "status: DeploymentStatus"
+            FROM deployments
+            WHERE id = $1
+            "#,
+            deployment_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(row.map(|r| (r.user_id, r.project_id, r.status)))
+    }
+
+    /// Deployments of the installation's owner that build `repository_id` from `branch`,
+    /// the branch followed is the repository's default one
+    #[tracing::instrument(
+        name = "deployment_repository.get_by_pushed_repository",
+        skip_all,
+        fields(installation_id = installation_id, repository_id = repository_id, branch = %branch),
+        err
+    )]
+    pub async fn get_by_pushed_repository(
+        installation_id: i64,
+        repository_id: i64,
+        branch: &str,
+        pool: &PgPool,
+    ) -> Result<Vec<GitPushDeploymentRow>, sqlx::Error> {
+        sqlx::query_as!(
+            GitPushDeploymentRow,
+            r#"
+            SELECT
+                d.id,
+                d.user_id,
+                d.project_id,
+                d.source AS "source: Json<DeploymentSource>"
+            FROM deployments d
+            INNER JOIN installations i ON i.user_id = d.user_id
+            WHERE i.installation_id = $1
+            AND d.source->>'type' IN ('dockerfile', 'code')
+            AND COALESCE(d.source->'repo'->>'provider', 'github') = 'github'
+            AND (d.source->'repo'->>'id')::BIGINT = $2
+            AND COALESCE(d.source->'repo'->>'defaultBranch', 'main') = $3
+            AND d.status NOT IN ('suspended', 'deleted')
+            "#,
+            installation_id,
+            repository_id,
+            branch
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// GitLab project ids are only unique per instance, so the lookup is scoped to the user
+    /// the webhook token belongs to
+    #[tracing::instrument(
+        name = "deployment_repository.get_by_pushed_gitlab_repository",
+        skip_all,
+        fields(user_id = %user_id, repository_id = repository_id, branch = %branch),
+        err
+    )]
+    pub async fn get_by_pushed_gitlab_repository(
+        user_id: &Uuid,
+        repository_id: i64,
+        branch: &str,
+        pool: &PgPool,
+    ) -> Result<Vec<GitPushDeploymentRow>, sqlx::Error> {
+        sqlx::query_as!(
+            GitPushDeploymentRow,
+            r#"
+            SELECT
+                d.id,
+                d.user_id,
+                d.project_id,
+                d.source AS "source: Json<DeploymentSource>"
+            FROM deployments d
+            WHERE d.user_id = $1
+            AND d.source->>'type' IN ('dockerfile', 'code')
+            AND d.source->'repo'->>'provider' = 'gitlab'
+            AND (d.source->'repo'->>'id')::BIGINT = $2
+            AND COALESCE(d.source->'repo'->>'defaultBranch', 'main') = $3
+            AND d.status NOT IN ('suspended', 'deleted')
+            "#,
+            user_id,
+            repository_id,
+            branch
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Returns `false` when the deployment isn't built from an image, only those pull from a registry
+    #[tracing::instrument(
+        name = "deployment_repository.update_image_pull_secret",
+        skip_all,
+        fields(user_id = %user_id, deployment_id = %deployment_id),
+        err
+    )]
+    pub async fn update_image_pull_secret(
+        user_id: &Uuid,
+        deployment_id: &Uuid,
+        image_pull_secret: &ImagePullSecret,
+        pool: &PgPool,
+    ) -> Result<bool, sqlx::Error> {
+        let image_pull_secret = serde_json::to_value(image_pull_secret).unwrap();
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE deployments
+            SET source = jsonb_set(source, '{imagePullSecret}', $3)
+            WHERE id = $1
+            AND user_id = $2
+            AND source->>'type' = 'image'
+            "#,
+            deployment_id,
+            user_id,
+            image_pull_secret
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Optimistic lock on `updated_at`, returns `false` when the row changed since it was read
+    #[tracing::instrument(
+        name = "deployment_repository.update_environment_variables",
+        skip_all,
+        fields(user_id = %user_id, deployment_id = %deployment_id),
+        err
+    )]
+    pub async fn update_environment_variables(
+        user_id: &Uuid,
+        deployment_id: &Uuid,
+        environment_variables: &HashMap<String, String>,
+        read_at: DateTime<Utc>,
+        pool: &PgPool,
+    ) -> Result<bool, sqlx::Error> {
+        let environment_variables = serde_json::to_value(environment_variables).unwrap();
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE deployments
+            SET environment_variables = $3
+            WHERE id = $1
+            AND user_id = $2
+            AND updated_at = $4
+            "#,
+            deployment_id,
+            user_id,
+            environment_variables,
+            read_at
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Averages snapshots per bucket of `resolution`, oldest first
+    #[tracing::instrument(name = "deployment_repository.get_metrics_history", skip_all, fields(deployment_id = %deployment_id), err)]
+    pub async fn get_metrics_history(
+        deployment_id: &Uuid,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        resolution: MetricsResolution,
+        cursor: Option<i64>,
+        limit: i64,
+        pool: &PgPool,
+    ) -> Result<Vec<MetricSnapshot>, sqlx::Error> {
+        sqlx::query_as!(
+            MetricSnapshot,
+            r#"
+            SELECT
+                EXTRACT(EPOCH FROM date_trunc($4, ts))::BIGINT AS "ts!",
+                AVG(cpu) AS "cpu!",
+                AVG(memory) AS "memory!"
+            FROM deployment_metrics
+            WHERE deployment_id = $1
+            AND ts >= $2
+            AND ts < $3
+            AND ($5::BIGINT IS NULL OR date_trunc($4, ts) > to_timestamp($5))
+            GROUP BY 1
+            ORDER BY 1
+            LIMIT $6
+            "#,
+            deployment_id,
+            start,
+            end,
+            resolution.date_trunc_unit(),
+            cursor,
+            limit
+        )
+        .fetch_all(pool)
+        .await
+    }
 }
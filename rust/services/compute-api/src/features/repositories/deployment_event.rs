@@ -4,7 +4,9 @@ use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::features::models::{DashboardEventQueryRow, ProjectEventQueryRow};
-use compute_core::models::{DeploymentEventLevel, DeploymentEventType};
+use compute_core::models::{
+    DeploymentEventLevel, DeploymentEventRow, DeploymentEventType, DeploymentStatus,
+};
 
 pub struct DeploymentEventRepository;
 
@@ -115,4 +117,63 @@ impl DeploymentEventRepository {
 
         Ok((data, total))
     }
+
+    #[tracing::instrument(name = "deployment_event_repository.get_many_by_deployment", skip_all, fields(user_id = %user_id, project_id = %project_id, deployment_id = %deployment_id), err)]
+    pub async fn get_many_by_deployment(
+        user_id: &Uuid,
+        project_id: &Uuid,
+        deployment_id: &Uuid,
+        p: &Pagination,
+        pool: &PgPool,
+    ) -> Result<(Vec<DeploymentEventRow>, i64), sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                de.id,
+                de.project_id,
+                de.deployment_id,
+                de.type AS "event_type: DeploymentEventType",
+                de.level AS "level: DeploymentEventLevel",
+                de.from_status AS "from_status: DeploymentStatus",
+                de.to_status AS "to_status: DeploymentStatus",
+                de.message,
+                de.created_at,
+                COUNT(*) OVER() AS "total!"
+            FROM deployment_events de
+            JOIN projects p ON de.project_id = p.id
+            WHERE p.owner_id = $1
+            AND de.project_id = $2
+            AND de.deployment_id = $3
+            ORDER BY de.created_at DESC
+            LIMIT $4
+            OFFSET $5
+            "#,
+            user_id,
+            project_id,
+            deployment_id,
+            p.limit,
+            p.offset
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let total = rows.first().map(|r| r.total).unwrap_or(0);
+
+        let data = rows
+            .into_iter()
+            .map(|r| DeploymentEventRow {
+                id: r.id,
+                project_id: r.project_id,
+                deployment_id: r.deployment_id,
+                event_type: r.event_type,
+                level: r.level,
+                from_status: r.from_status,
+                to_status: r.to_status,
+                message: r.message,
+                created_at: r.created_at,
+            })
+            .collect();
+
+        Ok((data, total))
+    }
 }
@@ -0,0 +1,58 @@
+use sqlx::{PgPool, Postgres, Transaction};
+
+pub struct IdempotencyRepository;
+
+impl IdempotencyRepository {
+    /// Records older than 24 hours are treated as gone even before the cleanup job deletes them
+    #[tracing::instrument(name = "idempotency_repository.get", skip_all, err)]
+    pub async fn get(
+        key_hash: &str,
+        pool: &PgPool,
+    ) -> Result<Option<serde_json::Value>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT response_body
+            FROM idempotency_keys
+            WHERE key_hash = $1 AND created_at > NOW() - INTERVAL '24 hours'
+            "#,
+            key_hash
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// `false` means a concurrent request with the same key committed first
+    #[tracing::instrument(name = "idempotency_repository.create", skip_all, err)]
+    pub async fn create(
+        key_hash: &str,
+        response_body: &serde_json::Value,
+        tx: &mut Transaction<'_, Postgres>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO idempotency_keys (key_hash, response_body)
+            VALUES ($1, $2)
+            ON CONFLICT (key_hash) DO UPDATE
+            SET response_body = EXCLUDED.response_body, created_at = NOW()
+            WHERE idempotency_keys.created_at <= NOW() - INTERVAL '24 hours'
+            "#,
+            key_hash,
+            response_body
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    #[tracing::instrument(name = "idempotency_repository.delete_expired", skip_all, err)]
+    pub async fn delete_expired(pool: &PgPool) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!(
+            "DELETE FROM idempotency_keys WHERE created_at <= NOW() - INTERVAL '24 hours'"
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
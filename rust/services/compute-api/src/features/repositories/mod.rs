@@ -1,5 +1,10 @@
+pub mod billing;
+pub mod build;
 pub mod dashboard;
 pub mod deployment;
 pub mod deployment_event;
 pub mod deployment_preset;
+pub mod idempotency;
+pub mod pod_log_archive;
 pub mod project;
+pub mod region;
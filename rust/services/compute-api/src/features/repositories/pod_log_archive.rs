@@ -0,0 +1,39 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub struct PodLogArchiveRepository;
+
+impl PodLogArchiveRepository {
+    /// Object path of the pod's latest archive, `None` also when the deployment isn't the user's
+    #[tracing::instrument(
+        name = "pod_log_archive_repository.get_latest_path",
+        skip_all,
+        fields(user_id = %user_id, deployment_id = %deployment_id, pod_uid = %pod_uid),
+        err
+    )]
+    pub async fn get_latest_path(
+        user_id: &Uuid,
+        project_id: &Uuid,
+        deployment_id: &Uuid,
+        pod_uid: &str,
+        pool: &PgPool,
+    ) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT a.object_path
+            FROM pod_log_archives a
+            INNER JOIN deployments d ON a.deployment_id = d.id
+            INNER JOIN projects p ON d.project_id = p.id
+            WHERE a.deployment_id = $1 AND a.pod_uid = $2 AND d.project_id = $3 AND p.owner_id = $4
+            ORDER BY a.created_at DESC
+            LIMIT 1
+            "#,
+            deployment_id,
+            pod_uid,
+            project_id,
+            user_id
+        )
+        .fetch_optional(pool)
+        .await
+    }
+}
@@ -2,10 +2,13 @@ use std::collections::HashMap;
 
 use bigdecimal::BigDecimal;
 use billing_core::schemas::Money;
-use compute_core::{models::ProjectRow, schemas::CreateProjectRequest};
+use compute_core::{
+    models::{DeploymentStatus, ProjectRow},
+    schemas::CreateProjectRequest,
+};
 use http_contracts::pagination::schema::Pagination;
 use redis::aio::MultiplexedConnection;
-use sqlx::PgPool;
+use sqlx::{PgPool, types::Json};
 use uuid::Uuid;
 
 use crate::{
@@ -13,8 +16,8 @@ use crate::{
     features::{
         models::ProjectOverviewQueryRow,
         schemas::{
-            CostOverview, CpuOverview, DeploymentOverview, MemoryOverview, ProjectOverviewResponse,
-            ResourceOverview,
+            CostOverview, CpuOverview, DeploymentCounts, DeploymentOverview, MemoryOverview,
+            ProjectOverviewResponse, ResourceOverview,
         },
     },
 };
@@ -416,6 +419,42 @@ impl ProjectRepository {
         Ok((projects, total))
     }
 
+    /// Every project of `project_ids` gets an entry, zeroed when it has no deployments
+    #[tracing::instrument(name = "project_repository.get_deployment_counts", skip_all, err)]
+    pub async fn get_deployment_counts(
+        project_ids: &[Uuid],
+        pool: &PgPool,
+    ) -> Result<HashMap<Uuid, DeploymentCounts>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                project_id,
+                status AS "status: DeploymentStatus",
+                COUNT(*)::INT AS "count!"
+            FROM deployments
+            WHERE project_id = ANY($1)
+            AND status != 'deleted'
+            GROUP BY project_id, status
+            "#,
+            project_ids
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let mut counts: HashMap<Uuid, DeploymentCounts> = project_ids
+            .iter()
+            .map(|id| (*id, DeploymentCounts::default()))
+            .collect();
+        for row in rows {
+            counts
+                .entry(row.project_id)
+                .or_default()
+                .add(row.status, row.count);
+        }
+
+        Ok(counts)
+    }
+
     #[tracing::instrument(name = "project_repository.get_one_by_id", skip(pool), err)]
     pub async fn get_one_by_id(
         user_id: &Uuid,
@@ -482,6 +521,92 @@ impl ProjectRepository {
         .await
     }
 
+    /// Merged in a single statement, so concurrent patches can't drop each other's keys
+    #[tracing::instrument(
+        name = "project_repository.patch_environment_variables",
+        skip(set, unset, pool),
+        err
+    )]
+    pub async fn patch_environment_variables(
+        user_id: &Uuid,
+        project_id: &Uuid,
+        set: &HashMap<String, String>,
+        unset: &[String],
+        pool: &PgPool,
+    ) -> Result<HashMap<String, String>, sqlx::Error> {
+        let set = serde_json::to_value(set).unwrap();
+
+        let row = sqlx::query!(
+            r#"
+            UPDATE projects
+            SET project_env_vars = (COALESCE(project_env_vars, '{}'::jsonb) - $3::text[]) || $4::jsonb
+            WHERE id = $1 AND owner_id = $2
+            RETURNING project_env_vars AS "project_env_vars!: Json<HashMap<String, String>>"
+            "#,
+            project_id,
+            user_id,
+            unset,
+            set
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(row.project_env_vars.0)
+    }
+
+    /// Deployments whose pods are up and would run with stale project variables. Earlier states
+    /// read them when they're provisioned, suspended ones when they resume
+    #[tracing::instrument(
+        name = "project_repository.get_rollout_deployment_ids",
+        skip(pool),
+        err
+    )]
+    pub async fn get_rollout_deployment_ids(
+        user_id: &Uuid,
+        project_id: &Uuid,
+        pool: &PgPool,
+    ) -> Result<Vec<Uuid>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT d.id
+            FROM deployments d
+            INNER JOIN projects p ON d.project_id = p.id
+            WHERE p.owner_id = $1
+              AND d.project_id = $2
+              AND d.status IN ('starting', 'running', 'unhealthy', 'degraded', 'updating')
+            "#,
+            user_id,
+            project_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Distinct `(key, value)` pairs of the project's live deployments, ordered by key then value
+    #[tracing::instrument(name = "project_repository.get_tags", skip(pool), err)]
+    pub async fn get_tags(
+        user_id: &Uuid,
+        project_id: &Uuid,
+        pool: &PgPool,
+    ) -> Result<Vec<(String, String)>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT DISTINCT t.key AS "key!", t.value AS "value!"
+            FROM deployments d
+            INNER JOIN projects p ON d.project_id = p.id
+            CROSS JOIN LATERAL jsonb_each_text(d.tags) t
+            WHERE p.owner_id = $1 AND d.project_id = $2 AND d.status != 'deleted'
+            ORDER BY 1, 2
+            "#,
+            user_id,
+            project_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|r| (r.key, r.value)).collect())
+    }
+
     #[tracing::instrument(name = "project_repository.delete", skip(pool), err)]
     pub async fn delete(
         user_id: &Uuid,
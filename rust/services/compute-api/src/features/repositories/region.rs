@@ -0,0 +1,28 @@
+use std::collections::HashMap;
+
+use sqlx::PgPool;
+
+pub struct RegionRepository;
+
+impl RegionRepository {
+    /// Desired replicas per region, deployments without one are counted to `default_region`
+    #[tracing::instrument(name = "region_repository.get_pod_counts", skip(pool), err)]
+    pub async fn get_pod_counts(
+        default_region: &str,
+        pool: &PgPool,
+    ) -> Result<HashMap<String, i64>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT COALESCE(region, $1) AS "region!", SUM(desired_replicas)::BIGINT AS "pods!"
+            FROM deployments
+            WHERE status NOT IN ('suspended', 'failed', 'build_failed', 'deleted', 'image_pull_error')
+            GROUP BY 1
+            "#,
+            default_region
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|r| (r.region, r.pods)).collect())
+    }
+}
@@ -1,12 +1,18 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use billing_core::schemas::Money;
+use bytes::Bytes;
 use chrono::{DateTime, Utc};
-use compute_core::{models::DeploymentStatus, schemas::DeploymentSource};
+use compute_core::{
+    models::{DeploymentStatus, ProjectRow},
+    schemas::{DeploymentSource, MetricSnapshot},
+};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::features::queries::MetricsResolution;
+
 #[derive(Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CallbackParams {
@@ -14,6 +20,11 @@ pub struct CallbackParams {
     pub setup_action: Option<String>,
 }
 
+#[derive(Deserialize, JsonSchema)]
+pub struct CommitsQuery {
+    pub branch: String,
+}
+
 #[derive(Deserialize, Serialize, JsonSchema, Debug)]
 pub struct LokiResponse {
     pub status: String,
@@ -27,6 +38,21 @@ pub struct LokiData {
     pub result: Vec<LokiStreamResult>,
 }
 
+/// Binary frame a client sends over an exec WebSocket, the first byte is the frame type:
+/// `0x01` followed by stdin bytes, or `0x02` followed by `{"cols":120,"rows":40}`
+#[derive(Debug, PartialEq, Eq)]
+pub enum ExecFrame {
+    Stdin(Bytes),
+    Resize(ExecResize),
+}
+
+/// Terminal size in character cells
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecResize {
+    pub cols: u16,
+    pub rows: u16,
+}
+
 #[derive(Deserialize, Serialize, JsonSchema, Debug)]
 pub struct LokiTailResponse {
     pub streams: Vec<LokiStreamResult>,
@@ -51,6 +77,26 @@ pub struct LogResponse {
     pub entries: Vec<LogEntry>,
 }
 
+/// Persisted deployment metrics, oldest snapshot first. Snapshots reach the table in batches,
+/// the last few minutes are only on the live stream
+#[derive(Serialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsHistoryResponse {
+    pub deployment_id: Uuid,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub resolution: MetricsResolution,
+    pub snapshots: Vec<MetricSnapshot>,
+    /// Passed back as `cursor` for the next page, absent on the last one
+    pub next_cursor: Option<i64>,
+}
+
+/// Tag keys in use across a project's deployments, each with its distinct values sorted
+#[derive(Serialize, JsonSchema, Debug)]
+pub struct ProjectTagsResponse {
+    pub tags: BTreeMap<String, Vec<String>>,
+}
+
 #[derive(Serialize, Deserialize, Clone, JsonSchema, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct DeploymentOut {
@@ -70,6 +116,7 @@ pub struct DeploymentOut {
     pub secret_keys: Option<Vec<String>>,
     pub environment_variables: Option<HashMap<String, String>>,
     pub labels: Option<HashMap<String, String>>,
+    pub tags: Option<HashMap<String, String>>,
     pub status: DeploymentStatus,
     pub domain: Option<String>,
     pub subdomain: Option<String>,
@@ -133,6 +180,36 @@ pub struct DashboardResponse {
     pub cost_overview: CostOverview,
 }
 
+/// Deployments of a project by status, deleted ones left out. `unhealthy` also counts degraded,
+/// failed and image pull errors, `building` also counts queued ones
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DeploymentCounts {
+    pub running: i32,
+    pub unhealthy: i32,
+    pub building: i32,
+    pub suspended: i32,
+    pub total: i32,
+}
+
+/// `healthy` with no unhealthy deployment, `critical` once at least half of them are
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectHealth {
+    Healthy,
+    Degraded,
+    Critical,
+}
+
+#[derive(Serialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectResponse {
+    #[serde(flatten)]
+    pub project: ProjectRow,
+    pub deployment_counts: DeploymentCounts,
+    pub health: ProjectHealth,
+}
+
 #[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct ProjectOverviewResponse {
@@ -143,3 +220,130 @@ pub struct ProjectOverviewResponse {
     pub resource_overview: ResourceOverview,
     pub cost_overview: CostOverview,
 }
+
+/// Just enough of any GitHub App delivery to find the secret it was signed with
+#[derive(Deserialize, Debug)]
+pub struct GithubWebhookEnvelope {
+    pub installation: Option<GithubWebhookInstallation>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GithubWebhookInstallation {
+    pub id: i64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GithubPushEvent {
+    /// `refs/heads/<branch>` or `refs/tags/<tag>`
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    /// Head commit after the push, all zeros when the branch was deleted
+    pub after: String,
+    #[serde(default)]
+    pub deleted: bool,
+    pub repository: GithubPushRepository,
+    pub installation: GithubWebhookInstallation,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GithubPushRepository {
+    pub id: i64,
+    pub full_name: String,
+    pub clone_url: String,
+    #[serde(default)]
+    pub private: bool,
+}
+
+#[derive(Deserialize, JsonSchema, Debug)]
+pub struct GitlabSetupRequest {
+    /// Authorization code GitLab redirected back with
+    pub code: String,
+}
+
+#[derive(Serialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LogArchiveResponse {
+    /// Pre-signed, downloads the gzipped logs without credentials until `expires_at`
+    pub url: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GitlabSetupResponse {
+    /// Goes into the project webhook's "Secret token", it is shown only once
+    pub webhook_token: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GitlabPushEvent {
+    /// `push` or `tag_push`
+    pub object_kind: String,
+    /// `refs/heads/<branch>`
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    /// All zeros when the branch was deleted
+    pub after: String,
+    pub project: GitlabPushProject,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GitlabPushProject {
+    pub id: i64,
+    pub path_with_namespace: String,
+    pub git_http_url: String,
+    /// 0 private, 10 internal, 20 public
+    pub visibility_level: i32,
+}
+
+/// A cluster deployments can be created in
+#[derive(Serialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RegionResponse {
+    pub name: String,
+    pub display_name: String,
+    /// Where deployments that don't name a region land
+    pub default: bool,
+    /// Replicas of the region's deployments that aren't suspended or failed
+    pub pods: i64,
+    /// Unlimited when absent
+    pub max_pods: Option<i64>,
+    pub available_pods: Option<i64>,
+}
+
+/// Capacity of a node next to what its pods use. Usage is missing while Prometheus can't be
+/// reached or has no node-exporter series for the node
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeSummary {
+    pub name: String,
+    pub region: String,
+    pub ready: bool,
+    /// Cordoned, no new pods land on it
+    pub unschedulable: bool,
+    pub cpu_capacity_millicores: i64,
+    /// Capacity minus what the kubelet and system daemons reserve
+    pub cpu_allocatable_millicores: i64,
+    pub cpu_usage_millicores: Option<i64>,
+    pub memory_capacity_mb: i64,
+    pub memory_allocatable_mb: i64,
+    pub memory_usage_mb: Option<i64>,
+}
+
+/// Totals over every node of every region, cordoned nodes included
+#[derive(Serialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterCapacity {
+    pub nodes: usize,
+    pub ready_nodes: usize,
+    pub cpu_allocatable_millicores: i64,
+    pub cpu_usage_millicores: i64,
+    pub cpu_remaining_millicores: i64,
+    pub memory_allocatable_mb: i64,
+    pub memory_usage_mb: i64,
+    pub memory_remaining_mb: i64,
+}
+
+/// `Last-Event-ID` an `EventSource` sends when it reconnects, the id of the last event it got
+#[derive(Clone, Copy, Debug)]
+pub struct LastEventId(pub u64);
@@ -6,30 +6,120 @@ use axum::{
         sse::{Event, KeepAlive},
     },
 };
-use futures::{Stream, StreamExt};
+use futures::{AsyncBufReadExt, Stream, StreamExt};
 use http::{HeaderName, HeaderValue};
-use std::convert::Infallible;
+use k8s_openapi::api::{batch::v1::Job, core::v1::Pod};
+use kube::{
+    Api, Client,
+    api::{ListParams, LogParams},
+};
+use redis::AsyncTypedCommands;
+use std::{convert::Infallible, time::Duration};
 use url::Url;
 use users_core::jwt::Claims;
 
-use compute_core::channel_names::ChannelNames;
-use factory::factories::{database::Database, redis::Redis};
+use axum_extra::TypedHeader;
+use compute_core::{cache_keys::CacheKeys, channel_names::ChannelNames, event::EventVersion};
+use factory::factories::{database::Database, kubernetes::Kubernetes, redis::Redis};
 use tokio_tungstenite::{
     connect_async,
     tungstenite::{client::IntoClientRequest, protocol::Message},
 };
-use tracing::error;
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
+/// Namespaces the provisioner runs builds in
+const BUILDKIT_NAMESPACE: &str = "buildkit";
+const KPACK_BUILD_NAMESPACE: &str = "kpack-build";
+/// How often a build pod is checked while its next container hasn't started
+const BUILD_CONTAINER_POLL_SECS: u64 = 2;
+
 use crate::{
     config::Config,
     features::{
         queries::TailQuery,
         repositories::{deployment::DeploymentRepository, project::ProjectRepository},
-        schemas::{LogResponse, LokiTailResponse},
+        schemas::{LastEventId, LogResponse, LokiTailResponse},
     },
 };
 
+/// Relays a published `VersionedEvent` as is, events newer than this build understands are dropped
+/// and so are events up to `after`, the client already has those
+fn compute_event(payload: String, after: Option<u64>) -> Option<Event> {
+    let mut event = Event::default().event("compute");
+
+    if let Ok(version) = serde_json::from_str::<EventVersion>(&payload) {
+        if !version.is_supported() {
+            warn!(
+                version = version.version,
+                "⚠️ Skipping compute event with unsupported version"
+            );
+            return None;
+        }
+
+        if let Some(id) = version.id {
+            if after.is_some_and(|after| id <= after) {
+                return None;
+            }
+            event = event.id(id.to_string());
+        }
+    }
+
+    Some(event.data(payload))
+}
+
+/// Live events of `channels`, preceded by whatever they buffered after `last_event_id`.
+/// Subscribing before reading the buffers loses nothing published in between, live events
+/// the replay already covered are skipped by their id
+async fn compute_event_stream(
+    mut redis: Redis,
+    channels: [String; 2],
+    last_event_id: Option<u64>,
+) -> Result<impl Stream<Item = Result<Event, Infallible>>, StatusCode> {
+    let mut pubsub = redis.pubsub().await.map_err(|err| {
+        error!("❌ Failed to connect to Redis PubSub: {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    pubsub.subscribe(&channels).await.map_err(|err| {
+        error!("❌ Failed to subscribe to channel pattern: {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let mut replay = Vec::new();
+    if let Some(last_event_id) = last_event_id {
+        for channel in &channels {
+            let buffered = redis
+                .con
+                .zrangebyscore_withscores(
+                    CacheKeys::sse_buffer(channel),
+                    format!("({}", last_event_id),
+                    "+inf",
+                )
+                .await
+                .map_err(|err| {
+                    error!("❌ Failed to read buffered events: {}", err);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+            replay.extend(buffered);
+        }
+        replay.sort_by_key(|(_, id)| *id);
+    }
+
+    let after = replay.last().map(|(_, id)| *id as u64).or(last_event_id);
+    let replay: Vec<_> = replay
+        .into_iter()
+        .filter_map(|(payload, _)| compute_event(payload, None).map(Ok))
+        .collect();
+
+    let live = pubsub.into_on_message().filter_map(move |msg| {
+        let payload: String = msg.get_payload().unwrap_or_default();
+        futures::future::ready(compute_event(payload, after).map(Ok))
+    });
+
+    Ok(futures::stream::iter(replay).chain(live))
+}
+
 #[tracing::instrument(
     name = "stream_deployment_metrics_see_handler",
     skip_all,
@@ -43,28 +133,14 @@ pub async fn stream_deployment_metrics_sse_handler(
     claims: Claims,
     Path((_project_id, deployment_id)): Path<(Uuid, Uuid)>,
     State(redis): State<Redis>,
+    last_event_id: Option<TypedHeader<LastEventId>>,
 ) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
     let metrics_channel = ChannelNames::deployment_metrics(&deployment_id.to_string());
     let events_channel = ChannelNames::deployment_events(&deployment_id.to_string());
     let channel_name = [metrics_channel, events_channel];
 
-    let mut pubsub = redis.pubsub().await.map_err(|err| {
-        error!("❌ Failed to connect to Redis PubSub: {}", err);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    pubsub.subscribe(&channel_name).await.map_err(|err| {
-        error!("❌ Failed to subscribe to channel pattern: {}", err);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    let stream = pubsub.into_on_message().map(move |msg| {
-        // let channel = msg.get_channel_name();
-        let payload: String = msg.get_payload().unwrap_or_default();
-        // info!(channel = %channel, payload = %payload, "📡 pubsub payload received in stream_deployment_metrics_see_handler");
-
-        Ok(Event::default().event("compute").data(payload))
-    });
+    let last_event_id = last_event_id.map(|TypedHeader(LastEventId(id))| id);
+    let stream = compute_event_stream(redis, channel_name, last_event_id).await?;
 
     Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
 }
@@ -82,28 +158,14 @@ pub async fn stream_project_metrics_sse_handler(
     claims: Claims,
     Path(project_id): Path<Uuid>,
     State(redis): State<Redis>,
+    last_event_id: Option<TypedHeader<LastEventId>>,
 ) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
     let metrics_channel = ChannelNames::project_metrics(&project_id.to_string());
     let events_channel = ChannelNames::project_events(&project_id.to_string());
     let channel_name = [metrics_channel, events_channel];
 
-    let mut pubsub = redis.pubsub().await.map_err(|err| {
-        error!("❌ Failed to connect to Redis PubSub: {}", err);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    pubsub.subscribe(&channel_name).await.map_err(|err| {
-        error!("❌ Failed to subscribe to channel pattern: {}", err);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    let stream = pubsub.into_on_message().map(move |msg| {
-        // let channel = msg.get_channel_name();
-        let payload: String = msg.get_payload().unwrap_or_default();
-        // info!(channel = %channel, payload = %payload, "📡 pubsub payload received in stream_deployments_metrics_see_handler");
-
-        Ok(Event::default().event("compute").data(payload))
-    });
+    let last_event_id = last_event_id.map(|TypedHeader(LastEventId(id))| id);
+    let stream = compute_event_stream(redis, channel_name, last_event_id).await?;
 
     Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
 }
@@ -130,9 +192,10 @@ pub async fn stream_logs_sse_handler(
         .await
         .map_err(|_| StatusCode::FORBIDDEN)?;
 
-    let preset_id = DeploymentRepository::get_prest_id(&claims.sub, &deployment_id, &db.pool)
-        .await
-        .map_err(|_| StatusCode::NOT_FOUND)?;
+    let preset_id =
+        DeploymentRepository::get_prest_id(&claims.sub, &project_id, &deployment_id, &db.pool)
+            .await
+            .map_err(|_| StatusCode::NOT_FOUND)?;
 
     // Parse Base URL
     let mut url = Url::parse(&cfg.loki.url).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -203,3 +266,162 @@ pub async fn stream_logs_sse_handler(
 
     Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
 }
+
+#[tracing::instrument(
+    name = "stream_build_logs_sse_handler",
+    skip_all,
+    fields(
+        user_id = %claims.sub,
+        project_id = %project_id,
+        deployment_id = %deployment_id,
+        build_id = %build_id,
+    ),
+    err
+)]
+pub async fn stream_build_logs_sse_handler(
+    claims: Claims,
+    Path((project_id, deployment_id, build_id)): Path<(Uuid, Uuid, Uuid)>,
+    State(db): State<Database>,
+    State(kubernetes): State<Kubernetes>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
+    // Builds run in the deployment's region
+    let region =
+        DeploymentRepository::get_region(&claims.sub, &project_id, &deployment_id, &db.pool)
+            .await
+            .map_err(|_| StatusCode::NOT_FOUND)?;
+    let client = kubernetes.client_for(region.as_deref()).map_err(|e| {
+        error!("❌ {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let (pods, pod_name) = find_build_pod(client, &deployment_id, &build_id)
+        .await?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    info!(pod = %pod_name, "🔨 Streaming build logs");
+
+    // Containers run one after another, each one's log is followed until it exits
+    let stream = async_stream::stream! {
+        let Ok(pod) = pods.get(&pod_name).await else {
+            return;
+        };
+        let containers: Vec<String> = pod
+            .spec
+            .iter()
+            .flat_map(|s| s.init_containers.iter().flatten().chain(s.containers.iter()))
+            .map(|c| c.name.clone())
+            .collect();
+
+        for container in containers {
+            if !wait_for_container(&pods, &pod_name, &container).await {
+                break;
+            }
+
+            let lp = LogParams {
+                container: Some(container.clone()),
+                follow: true,
+                ..Default::default()
+            };
+            let logs = match pods.log_stream(&pod_name, &lp).await {
+                Ok(logs) => logs,
+                Err(e) => {
+                    error!(container = %container, "❌ Failed to stream build logs: {}", e);
+                    break;
+                }
+            };
+
+            let mut lines = logs.lines();
+            while let Some(Ok(line)) = lines.next().await {
+                yield Ok(Event::default().event("log").data(format!("[{}] {}", container, line)));
+            }
+        }
+
+        let phase = pods
+            .get(&pod_name)
+            .await
+            .ok()
+            .and_then(|p| p.status)
+            .and_then(|s| s.phase)
+            .unwrap_or_else(|| "Unknown".into());
+        yield Ok(Event::default().event("end").data(phase));
+    };
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// kpack build pods inherit the Image's labels, buildkit pods only carry their Job's name
+async fn find_build_pod(
+    client: &Client,
+    deployment_id: &Uuid,
+    build_id: &Uuid,
+) -> Result<Option<(Api<Pod>, String)>, StatusCode> {
+    let selector = format!(
+        "poddle.io/deployment-id={},poddle.io/build-id={}",
+        deployment_id, build_id
+    );
+    let lp = ListParams::default().labels(&selector);
+
+    let kpack_pods: Api<Pod> = Api::namespaced(client.clone(), KPACK_BUILD_NAMESPACE);
+    if let Some(name) = first_pod_name(&kpack_pods, &lp).await? {
+        return Ok(Some((kpack_pods, name)));
+    }
+
+    let jobs: Api<Job> = Api::namespaced(client.clone(), BUILDKIT_NAMESPACE);
+    let Some(job_name) = jobs
+        .list(&lp)
+        .await
+        .map_err(|e| {
+            error!("❌ Failed to list build jobs: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .into_iter()
+        .find_map(|j| j.metadata.name)
+    else {
+        return Ok(None);
+    };
+
+    let buildkit_pods: Api<Pod> = Api::namespaced(client.clone(), BUILDKIT_NAMESPACE);
+    let lp = ListParams::default().labels(&format!("job-name={}", job_name));
+    Ok(first_pod_name(&buildkit_pods, &lp)
+        .await?
+        .map(|name| (buildkit_pods, name)))
+}
+
+async fn first_pod_name(pods: &Api<Pod>, lp: &ListParams) -> Result<Option<String>, StatusCode> {
+    let list = pods.list(lp).await.map_err(|e| {
+        error!("❌ Failed to list build pods: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(list.into_iter().find_map(|p| p.metadata.name))
+}
+
+/// Waits until the container runs or has run, `false` once the pod finished without starting it
+async fn wait_for_container(pods: &Api<Pod>, pod_name: &str, container: &str) -> bool {
+    loop {
+        let Ok(pod) = pods.get(pod_name).await else {
+            return false;
+        };
+        let Some(status) = pod.status else {
+            return false;
+        };
+
+        let started = status
+            .init_container_statuses
+            .iter()
+            .flatten()
+            .chain(status.container_statuses.iter().flatten())
+            .find(|s| s.name == container)
+            .and_then(|s| s.state.as_ref())
+            .is_some_and(|state| state.running.is_some() || state.terminated.is_some());
+        if started {
+            return true;
+        }
+
+        if matches!(status.phase.as_deref(), Some("Succeeded") | Some("Failed")) {
+            return false;
+        }
+
+        tokio::time::sleep(Duration::from_secs(BUILD_CONTAINER_POLL_SECS)).await;
+    }
+}
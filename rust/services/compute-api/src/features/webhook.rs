@@ -1,11 +1,342 @@
 use aide::axum::IntoApiResponse;
-use axum::Json;
-use axum_extra::extract::PrivateCookieJar;
-use serde_json::json;
+use axum::{Json, body::Bytes, extract::State, http::HeaderMap, http::StatusCode};
+use compute_core::{
+    cache_keys::CacheKeys,
+    github_app::{GithubApp, schemas::RepositoryProvider},
+    models::JobType,
+    repository::JobQueueRepository,
+    schemas::{DeploymentSource, DeploymentSourceMessage, UpdateDeploymentMessage},
+};
+use factory::factories::{database::Database, redis::Redis};
+use hmac::{Hmac, Mac};
+use http_contracts::message::MessageResponse;
+use redis::AsyncTypedCommands;
+use reqwest::Client;
+use sha2::Sha256;
+use tracing::{Instrument, error, info, info_span};
+use uuid::Uuid;
 
-use crate::error::AppError;
+use crate::{
+    error::AppError,
+    features::{
+        handlers::gitlab::{gitlab_access_token, hash_webhook_token},
+        models::GitPushDeploymentRow,
+        repositories::deployment::DeploymentRepository,
+        schemas::{GithubPushEvent, GithubWebhookEnvelope, GitlabPushEvent},
+    },
+    services::vault_service::VaultService,
+    utilities::app_state::AppState,
+};
 
-#[allow(unused)]
-pub async fn github_webhook(jar: PrivateCookieJar) -> Result<impl IntoApiResponse, AppError> {
-    return Ok(Json(json!({"ok": true})));
+/// A deployment is rebuilt at most once per this window, however many pushes land in it
+const GIT_PUSH_BUILD_WINDOW_SECS: u64 = 30;
+
+/// What GitHub and GitLab push events boil down to
+struct PushedCommit {
+    provider: RepositoryProvider,
+    /// `owner/name`, for logs only since `clone_url` may embed a token
+    repository: String,
+    clone_url: String,
+    branch: String,
+    commit_sha: String,
+}
+
+/// GitHub gives up on a delivery after 10 seconds, so only the signature is checked inline
+/// and the rebuilds are queued from a spawned task
+#[tracing::instrument(name = "github_webhook", skip_all, fields(event, installation_id), err)]
+pub async fn github_webhook(
+    State(vault): State<VaultService>,
+    State(database): State<Database>,
+    State(redis): State<Redis>,
+    State(github_app): State<GithubApp>,
+    State(http_client): State<Client>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoApiResponse, AppError> {
+    let event = header(&headers, "x-github-event")?;
+    let signature = header(&headers, "x-hub-signature-256")?;
+    tracing::Span::current().record("event", event);
+
+    // Unverified until the signature checks out, it only tells which secret to use
+    let envelope: GithubWebhookEnvelope = serde_json::from_slice(&body)?;
+    let installation_id = envelope
+        .installation
+        .ok_or_else(|| AppError::BadRequest("Delivery is not from a GitHub App".into()))?
+        .id;
+    tracing::Span::current().record("installation_id", installation_id);
+
+    let secret = vault
+        .read_github_webhook_secret(installation_id)
+        .await?
+        .ok_or_else(|| AppError::Unauthorized("Unknown installation".into()))?;
+    verify_signature(&secret, signature, &body)?;
+
+    if event != "push" {
+        return Ok((
+            StatusCode::ACCEPTED,
+            Json(MessageResponse::new(format!("Ignored {} event", event))),
+        ));
+    }
+
+    let push: GithubPushEvent = serde_json::from_slice(&body)?;
+    let span = info_span!("github_webhook.push", repository = %push.repository.full_name);
+    tokio::spawn(
+        async move {
+            if let Err(e) =
+                redeploy_pushed(push, &database, &redis, &github_app, &http_client).await
+            {
+                error!("❌ Failed to redeploy after push: {}", e);
+            }
+        }
+        .instrument(span),
+    );
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(MessageResponse::new("Push accepted")),
+    ))
+}
+
+/// GitLab echoes the project webhook's secret token back in `X-Gitlab-Token` instead of signing
+/// the body, the token's hash identifies the user it was issued to
+#[tracing::instrument(name = "gitlab_webhook", skip_all, fields(event, user_id), err)]
+pub async fn gitlab_webhook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoApiResponse, AppError> {
+    let event = header(&headers, "x-gitlab-event")?;
+    let token = header(&headers, "x-gitlab-token")?;
+    tracing::Span::current().record("event", event);
+
+    let user_id = sqlx::query_scalar!(
+        "SELECT user_id FROM gitlab_connections WHERE webhook_token_hash = $1",
+        hash_webhook_token(token)
+    )
+    .fetch_optional(&state.database.pool)
+    .await?
+    .ok_or_else(|| AppError::Unauthorized("Invalid webhook token".into()))?;
+    tracing::Span::current().record("user_id", user_id.to_string());
+
+    if event != "Push Hook" {
+        return Ok((
+            StatusCode::ACCEPTED,
+            Json(MessageResponse::new(format!("Ignored {} event", event))),
+        ));
+    }
+
+    let push: GitlabPushEvent = serde_json::from_slice(&body)?;
+    let span = info_span!("gitlab_webhook.push", repository = %push.project.path_with_namespace);
+    tokio::spawn(
+        async move {
+            if let Err(e) = redeploy_gitlab_pushed(&user_id, push, &state).await {
+                error!("❌ Failed to redeploy after push: {}", e);
+            }
+        }
+        .instrument(span),
+    );
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(MessageResponse::new("Push accepted")),
+    ))
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Result<&'a str, AppError> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| AppError::BadRequest(format!("Missing {} header", name)))
+}
+
+/// `X-Hub-Signature-256` is `sha256=<hex hmac of the raw body>`
+fn verify_signature(secret: &str, signature: &str, body: &[u8]) -> Result<(), AppError> {
+    let expected = signature
+        .strip_prefix("sha256=")
+        .and_then(|hex_digest| hex::decode(hex_digest).ok())
+        .ok_or_else(|| AppError::Unauthorized("Malformed webhook signature".into()))?;
+
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    mac.verify_slice(&expected)
+        .map_err(|_| AppError::Unauthorized("Invalid webhook signature".into()))
+}
+
+/// Queues a rebuild for every deployment following the pushed branch, returns how many
+async fn redeploy_pushed(
+    push: GithubPushEvent,
+    database: &Database,
+    redis: &Redis,
+    github_app: &GithubApp,
+    http_client: &Client,
+) -> Result<usize, AppError> {
+    let Some(branch) = push.git_ref.strip_prefix("refs/heads/") else {
+        return Ok(0);
+    };
+    if push.deleted {
+        return Ok(0);
+    }
+
+    let deployments = DeploymentRepository::get_by_pushed_repository(
+        push.installation.id,
+        push.repository.id,
+        branch,
+        &database.pool,
+    )
+    .await?;
+    if deployments.is_empty() {
+        return Ok(0);
+    }
+
+    // Only public repositories can be cloned anonymously, same as on create
+    let mut clone_url = push.repository.clone_url;
+    if push.repository.private {
+        let access_token = github_app
+            .create_installation_token(push.installation.id, http_client)
+            .await?;
+        clone_url = clone_url.replace(
+            "https://",
+            &format!("https://x-access-token:{access_token}@"),
+        );
+    }
+
+    let commit = PushedCommit {
+        provider: RepositoryProvider::Github,
+        branch: branch.to_string(),
+        repository: push.repository.full_name,
+        clone_url,
+        commit_sha: push.after,
+    };
+
+    queue_rebuilds(&commit, deployments, database, redis).await
+}
+
+async fn redeploy_gitlab_pushed(
+    user_id: &Uuid,
+    push: GitlabPushEvent,
+    state: &AppState,
+) -> Result<usize, AppError> {
+    let Some(branch) = push.git_ref.strip_prefix("refs/heads/") else {
+        return Ok(0);
+    };
+    if push.object_kind != "push" || push.after.bytes().all(|b| b == b'0') {
+        return Ok(0);
+    }
+
+    let deployments = DeploymentRepository::get_by_pushed_gitlab_repository(
+        user_id,
+        push.project.id,
+        branch,
+        &state.database.pool,
+    )
+    .await?;
+    if deployments.is_empty() {
+        return Ok(0);
+    }
+
+    // Only public projects can be cloned anonymously
+    let mut clone_url = push.project.git_http_url;
+    if push.project.visibility_level < 20 {
+        let access_token =
+            gitlab_access_token(user_id, &state.gitlab_app, &state.http_client, &state.vault)
+                .await?;
+        clone_url = clone_url.replace("https://", &format!("https://oauth2:{access_token}@"));
+    }
+
+    let commit = PushedCommit {
+        provider: RepositoryProvider::Gitlab,
+        branch: branch.to_string(),
+        repository: push.project.path_with_namespace,
+        clone_url,
+        commit_sha: push.after,
+    };
+
+    queue_rebuilds(&commit, deployments, &state.database, &state.redis).await
+}
+
+/// Rebuilds go through `job_queue` rather than RabbitMQ, a broker restart can't lose a push
+async fn queue_rebuilds(
+    commit: &PushedCommit,
+    deployments: Vec<GitPushDeploymentRow>,
+    database: &Database,
+    redis: &Redis,
+) -> Result<usize, AppError> {
+    let mut con = redis.con.clone();
+    let mut queued = 0;
+    for deployment in deployments {
+        let (context_path, dockerfile_path, use_dockerfile) = match deployment.source.0 {
+            DeploymentSource::Dockerfile {
+                context_path,
+                dockerfile_path,
+                ..
+            } => (context_path, dockerfile_path, true),
+            DeploymentSource::Code { context_path, .. } => (context_path, None, false),
+            DeploymentSource::Image { .. } => continue,
+        };
+
+        let build_key = CacheKeys::deployment_git_push_build(&deployment.id.to_string());
+        if !con.set_nx(&build_key, &commit.commit_sha).await? {
+            info!(deployment_id = %deployment.id, "⏳ Skipping push, a build started less than {}s ago", GIT_PUSH_BUILD_WINDOW_SECS);
+            continue;
+        }
+        con.expire(&build_key, GIT_PUSH_BUILD_WINDOW_SECS as i64)
+            .await?;
+
+        let clone_url = commit.clone_url.clone();
+        let branch = commit.branch.clone();
+        let commit_sha = commit.commit_sha.clone();
+        let source = match commit.provider {
+            RepositoryProvider::Github => DeploymentSourceMessage::GitPush {
+                clone_url,
+                branch,
+                commit_sha,
+                context_path,
+                dockerfile_path,
+                use_dockerfile,
+            },
+            RepositoryProvider::Gitlab => DeploymentSourceMessage::GitlabPush {
+                clone_url,
+                branch,
+                commit_sha,
+                context_path,
+                dockerfile_path,
+                use_dockerfile,
+            },
+        };
+
+        let message = UpdateDeploymentMessage {
+            user_id: deployment.user_id,
+            project_id: deployment.project_id,
+            deployment_id: deployment.id,
+            name: None,
+            source: Some(source),
+            port: None,
+            desired_replicas: None,
+            preset_id: None,
+            resource_spec: None,
+            secrets: None,
+            environment_variables: None,
+            labels: None,
+            tags: None,
+            domain: None,
+            subdomain: None,
+            autoscaling: None,
+            canary: None,
+            restart_at: None,
+            ip_allowlist: None,
+            alert_thresholds: None,
+            config_maps: None,
+            timestamp: chrono::Utc::now().timestamp(),
+        };
+        JobQueueRepository::enqueue(JobType::Rebuild, &message, &database.pool).await?;
+        queued += 1;
+    }
+
+    info!(
+        "📤 Queued {} rebuilds for push to {}@{}",
+        queued, commit.repository, commit.branch
+    );
+
+    Ok(queued)
 }
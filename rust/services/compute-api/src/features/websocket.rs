@@ -1,20 +1,36 @@
 use crate::{
     config::Config,
+    error::AppError,
     features::{
+        queries::ExecQuery,
         repositories::deployment::DeploymentRepository,
-        schemas::{LogResponse, LokiTailResponse},
+        schemas::{ExecFrame, LogResponse, LokiTailResponse},
     },
+    utilities::app_state::AppState,
 };
 use axum::{
     extract::{
         Path, State,
-        ws::{Message as WSMessage, WebSocket, WebSocketUpgrade},
+        ws::{CloseFrame, Message as WSMessage, WebSocket, WebSocketUpgrade, close_code},
     },
     response::IntoResponse,
 };
+use axum_extra::extract::Query;
+use bytes::Bytes;
+use compute_core::formatters::{format_namespace, format_resource_name};
 use factory::factories::database::Database;
-use http::{HeaderName, HeaderValue, StatusCode};
-use tracing::instrument;
+use http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header::ORIGIN};
+use k8s_openapi::api::core::v1::Pod;
+use kube::{
+    Api,
+    api::{AttachParams, AttachedProcess, ListParams, TerminalSize},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    sync::mpsc::{self, error::TrySendError},
+    time::{Duration, Instant, interval_at, sleep_until},
+};
+use tracing::{info, instrument, warn};
 use url::Url;
 use users_core::jwt::Claims;
 use uuid::Uuid;
@@ -25,6 +41,11 @@ use tokio_tungstenite::{
     tungstenite::{Message, client::IntoClientRequest as _, handshake::client::Request},
 };
 
+/// Quiet log streams would otherwise be dropped by load balancers after around 30 idle seconds
+const HEARTBEAT_INTERVAL_SECS: u64 = 15;
+/// A client that answered none of the pings for this long is gone
+const PONG_TIMEOUT_SECS: u64 = 30;
+
 #[instrument(
     name = "stream_logs_ws_handler",
     skip_all,
@@ -42,9 +63,10 @@ pub async fn stream_logs_ws_handler(
     State(cfg): State<Config>,
     State(db): State<Database>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let preset_id = DeploymentRepository::get_prest_id(&claims.sub, &deployment_id, &db.pool)
-        .await
-        .map_err(|_| StatusCode::NOT_FOUND)?;
+    let preset_id =
+        DeploymentRepository::get_prest_id(&claims.sub, &project_id, &deployment_id, &db.pool)
+            .await
+            .map_err(|_| StatusCode::NOT_FOUND)?;
 
     // Parse Base URL
     let mut url = Url::parse(&cfg.loki.url).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -105,31 +127,218 @@ async fn handle_stream_logs(request: Request, mut client_socket: WebSocket) {
     // Pipe the streams
     // We split both sockets into Sender and Receiver parts
     let (mut _loki_sender, mut loki_receiver) = loki_socket.split();
-    let (mut client_sender, mut _client_receiver) = client_socket.split();
+    let (mut client_sender, mut client_receiver) = client_socket.split();
+
+    let heartbeat_period = Duration::from_secs(HEARTBEAT_INTERVAL_SECS);
+    let pong_timeout = Duration::from_secs(PONG_TIMEOUT_SECS);
+    let mut heartbeat = interval_at(Instant::now() + heartbeat_period, heartbeat_period);
+    let pong_deadline = sleep_until(Instant::now() + pong_timeout);
+    tokio::pin!(pong_deadline);
 
-    // When Loki sends a message, forward it to the Client
-    while let Some(msg) = loki_receiver.next().await {
-        match msg {
-            Ok(Message::Text(text)) => {
-                if let Ok(loki_push) = serde_json::from_str::<LokiTailResponse>(&text) {
+    loop {
+        tokio::select! {
+            // When Loki sends a message, forward it to the Client
+            msg = loki_receiver.next() => match msg {
+                Some(Ok(Message::Text(text))) => {
+                    let Ok(loki_push) = serde_json::from_str::<LokiTailResponse>(&text) else {
+                        continue;
+                    };
                     let log_batch = LogResponse::from(loki_push);
 
                     // Yield each entry individually to the frontend
                     for entry in log_batch.entries {
-                        if let Ok(json) = serde_json::to_string(&entry) {
-                            if client_sender
+                        if let Ok(json) = serde_json::to_string(&entry)
+                            && client_sender
                                 .send(WSMessage::Text(json.into()))
                                 .await
                                 .is_err()
-                            {
-                                return; // Client disconnected
-                            }
+                        {
+                            return; // Client disconnected
+                        }
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {} // Ignore Ping/Pong/Binary
+            },
+            _ = heartbeat.tick() => {
+                if client_sender.send(WSMessage::Ping(Bytes::new())).await.is_err() {
+                    return;
+                }
+            }
+            msg = client_receiver.next() => match msg {
+                Some(Ok(WSMessage::Pong(_))) => {
+                    pong_deadline.as_mut().reset(Instant::now() + pong_timeout);
+                }
+                Some(Ok(WSMessage::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+            _ = &mut pong_deadline => {
+                warn!("⚠️ No pong from the log stream client, closing");
+                let _ = client_sender
+                    .send(WSMessage::Close(Some(CloseFrame {
+                        code: close_code::AWAY,
+                        reason: "Heartbeat timeout".into(),
+                    })))
+                    .await;
+                return;
+            }
+        }
+    }
+}
+
+/// Resizes queued while the exec stream isn't taking terminal sizes yet, older ones are dropped
+/// past this since only the latest size matters
+const RESIZE_BUFFER: usize = 5;
+
+#[instrument(
+    name = "exec_ws_handler",
+    skip_all,
+    fields(
+        user_id = %claims.sub,
+        project_id = %project_id,
+        deployment_id = %deployment_id,
+        pod_uid = %pod_uid,
+    ),
+    err
+)]
+pub async fn exec_ws_handler(
+    ws: WebSocketUpgrade,
+    claims: Claims,
+    Path((project_id, deployment_id, pod_uid)): Path<(Uuid, Uuid, String)>,
+    Query(q): Query<ExecQuery>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    // CORS does not apply to WebSocket upgrades, any site could otherwise ride the session cookie
+    let origin_allowed = headers.get(ORIGIN).is_some_and(|origin| {
+        state
+            .dynamic_config
+            .read()
+            .expect("dynamic config lock poisoned")
+            .allows_origin(origin.as_bytes())
+    });
+    if !origin_allowed {
+        return Err(AppError::Forbidden("Origin not allowed".into()));
+    }
+
+    let region = DeploymentRepository::get_region(
+        &claims.sub,
+        &project_id,
+        &deployment_id,
+        &state.database.pool,
+    )
+    .await?;
+    let client = state
+        .kubernetes
+        .client_for(region.as_deref())
+        .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+
+    if q.command.is_empty() {
+        return Err(AppError::BadRequest("Command must not be empty".into()));
+    }
+
+    let ns = format_namespace(&claims.sub);
+    let pods: Api<Pod> = Api::namespaced(client.clone(), &ns);
+    let lp = ListParams::default().labels(&format!("poddle.io/deployment-id={}", deployment_id));
+    let pod_name = pods
+        .list(&lp)
+        .await?
+        .into_iter()
+        .find(|p| p.metadata.uid.as_deref() == Some(pod_uid.as_str()))
+        .and_then(|p| p.metadata.name)
+        .ok_or_else(|| AppError::NotFoundError("Pod not found".into()))?;
+
+    let ap = AttachParams::interactive_tty().container(format_resource_name(&deployment_id));
+    let attached = pods.exec(&pod_name, q.command, &ap).await?;
+
+    info!(pod = %pod_name, "🐚 Exec session opened");
+
+    Ok(ws.on_upgrade(move |socket| handle_exec(attached, socket)))
+}
+
+async fn handle_exec(mut attached: AttachedProcess, client_socket: WebSocket) {
+    let (Some(mut stdin), Some(mut stdout)) = (attached.stdin(), attached.stdout()) else {
+        attached.abort();
+        return;
+    };
+    // A tty merges stderr into stdout, there is only a separate stream without one
+    let mut stderr = attached.stderr();
+    let mut terminal_size = attached.terminal_size();
+    let (resize_tx, mut resize_rx) = mpsc::channel::<TerminalSize>(RESIZE_BUFFER);
+
+    let (mut client_sender, mut client_receiver) = client_socket.split();
+    let mut stdout_buf = [0u8; 4096];
+    let mut stderr_buf = [0u8; 4096];
+
+    loop {
+        tokio::select! {
+            // The process exited once its output is closed
+            n = stdout.read(&mut stdout_buf) => match n {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    let frame = WSMessage::Binary(Bytes::copy_from_slice(&stdout_buf[..n]));
+                    if client_sender.send(frame).await.is_err() {
+                        break;
+                    }
+                }
+            },
+            Some(n) = async {
+                match stderr.as_mut() {
+                    Some(stderr) => Some(stderr.read(&mut stderr_buf).await),
+                    None => None,
+                }
+            } => match n {
+                Ok(0) | Err(_) => stderr = None,
+                Ok(n) => {
+                    let frame = WSMessage::Binary(Bytes::copy_from_slice(&stderr_buf[..n]));
+                    if client_sender.send(frame).await.is_err() {
+                        break;
+                    }
+                }
+            },
+            // Drained only once the process takes terminal sizes, until then they queue up
+            Some(size) = resize_rx.recv(), if terminal_size.is_some() => {
+                if let Some(tx) = terminal_size.as_mut()
+                    && tx.send(size).await.is_err()
+                {
+                    terminal_size = None;
+                }
+            }
+            msg = client_receiver.next() => {
+                let frame = match msg {
+                    Some(Ok(WSMessage::Binary(data))) => ExecFrame::parse(data),
+                    // Plain text is typed input, handy for clients that can't send binary frames
+                    Some(Ok(WSMessage::Text(text))) => {
+                        Some(ExecFrame::Stdin(Bytes::copy_from_slice(text.as_bytes())))
+                    }
+                    Some(Ok(WSMessage::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue, // Ping/Pong
+                };
+
+                match frame {
+                    Some(ExecFrame::Stdin(data)) => {
+                        if stdin.write_all(&data).await.is_err() {
+                            break;
                         }
                     }
+                    Some(ExecFrame::Resize(size)) => {
+                        let size = TerminalSize {
+                            width: size.cols,
+                            height: size.rows,
+                        };
+                        if let Err(TrySendError::Full(size)) = resize_tx.try_send(size) {
+                            let _ = resize_rx.try_recv();
+                            let _ = resize_tx.try_send(size);
+                        }
+                    }
+                    None => warn!("⚠️ Ignoring malformed exec frame"),
                 }
             }
-            Ok(Message::Close(_)) => break,
-            _ => {} // Ignore Ping/Pong/Binary
         }
     }
+
+    // Either side hanging up ends the other one
+    attached.abort();
+    let _ = client_sender.send(WSMessage::Close(None)).await;
+    info!("🐚 Exec session closed");
 }
@@ -5,6 +5,7 @@ use aide::{
 };
 use axum::Json;
 use compute_core::services::event_emission_service::error::EventEmissionServiceError;
+use factory::factories::amqp::error::AmqpError;
 use http_contracts::error::schema::ErrorResponse;
 use users_core::jwt::JwtCapability;
 
@@ -86,6 +87,7 @@ impl From<ClaimsError> for AppError {
             ClaimsError::WrongType => AppError::WrongTokenTypeError,
             ClaimsError::Invalid => AppError::InvalidTokenError,
             ClaimsError::KeyError => AppError::KeyError,
+            ClaimsError::NotAdmin => AppError::Forbidden("Admin role required".into()),
         }
     }
 }
@@ -98,6 +100,12 @@ impl From<lapin::Error> for AppError {
     }
 }
 
+impl From<AmqpError> for AppError {
+    fn from(value: AmqpError) -> Self {
+        AppError::InternalServerError(value.to_string())
+    }
+}
+
 impl From<http::StatusCode> for AppError {
     fn from(value: http::StatusCode) -> Self {
         match value {
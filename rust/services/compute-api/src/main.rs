@@ -1,17 +1,9 @@
-pub mod app;
-pub mod config;
-pub mod error;
-pub mod features;
-pub mod implementations;
-pub mod services;
-pub mod utilities;
-
 use std::env;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::result::Result::Ok;
 
-use config::Config;
+use compute_api::{app, config::Config};
 use factory::factories::observability::Observability;
 
 use tracing::info;
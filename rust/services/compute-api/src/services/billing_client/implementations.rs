@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use compute_core::models::ResourceSpec;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::AppError,
+    services::billing_client::{BillingClient, CostEstimate},
+};
+
+/// The estimate is awaited while creating a deployment, a slow billing-api must not hold it up
+const ESTIMATE_TIMEOUT: Duration = Duration::from_secs(2);
+
+impl BillingClient {
+    pub fn new(cfg: &Config, http: reqwest::Client) -> Self {
+        Self {
+            http,
+            base_url: cfg.billing_api_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    #[tracing::instrument(name = "billing_client.estimate_cost", skip_all, fields(preset_id = %preset_id), err)]
+    pub async fn estimate_cost(
+        &self,
+        preset_id: &Uuid,
+        resources: &ResourceSpec,
+        replicas: i32,
+    ) -> Result<CostEstimate, AppError> {
+        let response = self
+            .http
+            .post(format!("{}/api/v1/billing/estimate", self.base_url))
+            .json(&json!({
+                "presetId": preset_id,
+                "resources": resources,
+                "replicas": replicas,
+            }))
+            .timeout(ESTIMATE_TIMEOUT)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(AppError::ExternalServiceError {
+                service: "billing-api".to_string(),
+                code: response.status().to_string(),
+                message: "Failed to estimate deployment cost".to_string(),
+            });
+        }
+
+        Ok(response.json::<CostEstimate>().await?)
+    }
+}
@@ -0,0 +1,21 @@
+pub mod implementations;
+
+use bigdecimal::BigDecimal;
+use serde::{Deserialize, Serialize};
+
+/// Prices deployments through billing-api, presets and add-on prices are billing's data
+#[derive(Clone)]
+pub struct BillingClient {
+    pub http: reqwest::Client,
+    pub base_url: String,
+}
+
+/// The part of billing-api's estimate attached to a created deployment
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CostEstimate {
+    pub hourly: BigDecimal,
+    pub daily: BigDecimal,
+    pub monthly: BigDecimal,
+    pub currency: String,
+}
@@ -1,12 +1,30 @@
 use compute_core::{
     cache_keys::CacheKeys,
+    github_app::schemas::CommitSummary,
     schemas::{MetricSnapshot, Pod, PodMeta},
 };
-use http_contracts::pagination::schema::Pagination;
+use http_contracts::{pagination::schema::Pagination, preset::schema::PresetResponse};
 use redis::{AsyncTypedCommands, aio::MultiplexedConnection};
+use std::collections::HashMap;
 use tracing::{error, info};
+use uuid::Uuid;
 
-use crate::{error::AppError, services::cache_service::CacheService};
+use crate::{
+    error::AppError,
+    features::schemas::{DeploymentCounts, NodeSummary},
+    services::cache_service::CacheService,
+};
+
+/// Presets change rarely, but tier discounts should show up reasonably fast
+const PRESETS_TTL_SECONDS: u64 = 300;
+/// Short enough that a freshly pushed branch shows up while the user is still on the form
+const GITHUB_BRANCHES_TTL_SECONDS: u64 = 60;
+/// A commit pushed while the user picks one shows up on the next refresh
+const GITHUB_COMMITS_TTL_SECONDS: u64 = 30;
+/// Listing every node and querying Prometheus is too slow to repeat on each dashboard refresh
+const CLUSTER_NODES_TTL_SECONDS: u64 = 30;
+/// Dropped on every status transition anyway, the TTL covers deployments created or deleted
+const PROJECT_DEPLOYMENT_COUNTS_TTL_SECONDS: u64 = 10;
 
 impl CacheService {
     /// Get pods with metrics for a deployment (Deployment Page)
@@ -23,13 +41,14 @@ impl CacheService {
         let stop = (p.offset + p.limit) as isize - 1;
 
         // Get pod UIDs
-        let uids = con.zrevrange(&index_key, start, stop).await.map_err(|e| {
-            error!(error = %e, "❌ Failed to get pod UIDs");
-            AppError::InternalServerError(format!("❌ Failed to get pod UIDs: {}", e))
-        })?;
-        let total = con.zcard(index_key).await.map_err(|e| {
+        let uids = con
+            .zrevrange(&index_key, start, stop)
+            .await
+            .inspect_err(|e| {
+                error!(error = %e, "❌ Failed to get pod UIDs");
+            })?;
+        let total = con.zcard(index_key).await.inspect_err(|e| {
             error!(error = %e, "❌ Failed to get number of pod UIDs");
-            AppError::InternalServerError(format!("❌ Failed to get number of pod UIDs: {}", e))
         })?;
 
         if uids.is_empty() {
@@ -52,9 +71,8 @@ impl CacheService {
         // The power of redis-rs: It deserializes the flat stream into tuples!
         // Expect: Vec<(PodHistory, Vec<MetricSnapshot>)>
         let results: Vec<(PodMeta, Vec<MetricSnapshot>)> =
-            p.query_async(con).await.map_err(|e| {
+            p.query_async(con).await.inspect_err(|e| {
                 error!(error = %e, "❌ Redis pipeline failed");
-                AppError::InternalServerError(format!("❌ Redis pipeline failed: {}", e))
             })?;
 
         info!(
@@ -91,9 +109,8 @@ impl CacheService {
 
         let start = std::time::Instant::now();
 
-        let results: Vec<Vec<MetricSnapshot>> = p.query_async(con).await.map_err(|e| {
+        let results: Vec<Vec<MetricSnapshot>> = p.query_async(con).await.inspect_err(|e| {
             error!(error = %e, "❌ Redis pipeline failed");
-            AppError::InternalServerError(format!("❌ Redis pipeline failed: {}", e))
         })?;
 
         info!(
@@ -120,11 +137,199 @@ impl CacheService {
             p.lindex(key, 0);
         }
 
-        let results: Vec<Option<MetricSnapshot>> = p.query_async(con).await.map_err(|e| {
+        let results: Vec<Option<MetricSnapshot>> = p.query_async(con).await.inspect_err(|e| {
             error!(error = %e, "❌ Redis pipeline failed");
-            AppError::InternalServerError(format!("❌ Redis pipeline failed: {}", e))
         })?;
 
         Ok(results)
     }
+
+    #[tracing::instrument(name = "cache_service.get_presets", skip_all, fields(user_id = %user_id), err)]
+    pub async fn get_presets(
+        user_id: &Uuid,
+        con: &mut MultiplexedConnection,
+    ) -> Result<Option<Vec<PresetResponse>>, AppError> {
+        let key = CacheKeys::presets(&user_id.to_string());
+
+        let cached = con.get(&key).await.inspect_err(|e| {
+            error!(error = %e, "❌ Failed to get cached presets");
+        })?;
+
+        // A payload we can't decode is treated as a miss, it gets overwritten on the next set
+        Ok(cached.and_then(|raw| serde_json::from_str(&raw).ok()))
+    }
+
+    #[tracing::instrument(name = "cache_service.set_presets", skip_all, fields(user_id = %user_id), err)]
+    pub async fn set_presets(
+        user_id: &Uuid,
+        presets: &[PresetResponse],
+        con: &mut MultiplexedConnection,
+    ) -> Result<(), AppError> {
+        let key = CacheKeys::presets(&user_id.to_string());
+        let payload = serde_json::to_string(presets)?;
+
+        con.set_ex(&key, payload, PRESETS_TTL_SECONDS)
+            .await
+            .inspect_err(|e| {
+                error!(error = %e, "❌ Failed to cache presets");
+            })?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "cache_service.get_github_branches", skip_all, fields(installation_id = installation_id, repository = %full_name), err)]
+    pub async fn get_github_branches(
+        installation_id: i64,
+        full_name: &str,
+        con: &mut MultiplexedConnection,
+    ) -> Result<Option<Vec<String>>, AppError> {
+        let key = CacheKeys::github_branches(installation_id, full_name);
+
+        let cached = con.get(&key).await.inspect_err(|e| {
+            error!(error = %e, "❌ Failed to get cached branches");
+        })?;
+
+        Ok(cached.and_then(|raw| serde_json::from_str(&raw).ok()))
+    }
+
+    #[tracing::instrument(name = "cache_service.set_github_branches", skip_all, fields(installation_id = installation_id, repository = %full_name), err)]
+    pub async fn set_github_branches(
+        installation_id: i64,
+        full_name: &str,
+        branches: &[String],
+        con: &mut MultiplexedConnection,
+    ) -> Result<(), AppError> {
+        let key = CacheKeys::github_branches(installation_id, full_name);
+        let payload = serde_json::to_string(branches)?;
+
+        con.set_ex(&key, payload, GITHUB_BRANCHES_TTL_SECONDS)
+            .await
+            .inspect_err(|e| {
+                error!(error = %e, "❌ Failed to cache branches");
+            })?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "cache_service.get_github_commits", skip_all, fields(installation_id = installation_id, repository = %full_name, branch = %branch), err)]
+    pub async fn get_github_commits(
+        installation_id: i64,
+        full_name: &str,
+        branch: &str,
+        con: &mut MultiplexedConnection,
+    ) -> Result<Option<Vec<CommitSummary>>, AppError> {
+        let key = CacheKeys::github_commits(installation_id, full_name, branch);
+
+        let cached = con.get(&key).await.inspect_err(|e| {
+            error!(error = %e, "❌ Failed to get cached commits");
+        })?;
+
+        Ok(cached.and_then(|raw| serde_json::from_str(&raw).ok()))
+    }
+
+    #[tracing::instrument(name = "cache_service.set_github_commits", skip_all, fields(installation_id = installation_id, repository = %full_name, branch = %branch), err)]
+    pub async fn set_github_commits(
+        installation_id: i64,
+        full_name: &str,
+        branch: &str,
+        commits: &[CommitSummary],
+        con: &mut MultiplexedConnection,
+    ) -> Result<(), AppError> {
+        let key = CacheKeys::github_commits(installation_id, full_name, branch);
+        let payload = serde_json::to_string(commits)?;
+
+        con.set_ex(&key, payload, GITHUB_COMMITS_TTL_SECONDS)
+            .await
+            .inspect_err(|e| {
+                error!(error = %e, "❌ Failed to cache commits");
+            })?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "cache_service.get_cluster_nodes", skip_all, err)]
+    pub async fn get_cluster_nodes(
+        con: &mut MultiplexedConnection,
+    ) -> Result<Option<Vec<NodeSummary>>, AppError> {
+        let key = CacheKeys::cluster_nodes();
+
+        let cached = con.get(&key).await.inspect_err(|e| {
+            error!(error = %e, "❌ Failed to get cached cluster nodes");
+        })?;
+
+        Ok(cached.and_then(|raw| serde_json::from_str(&raw).ok()))
+    }
+
+    #[tracing::instrument(name = "cache_service.set_cluster_nodes", skip_all, err)]
+    pub async fn set_cluster_nodes(
+        nodes: &[NodeSummary],
+        con: &mut MultiplexedConnection,
+    ) -> Result<(), AppError> {
+        let key = CacheKeys::cluster_nodes();
+        let payload = serde_json::to_string(nodes)?;
+
+        con.set_ex(&key, payload, CLUSTER_NODES_TTL_SECONDS)
+            .await
+            .inspect_err(|e| {
+                error!(error = %e, "❌ Failed to cache cluster nodes");
+            })?;
+
+        Ok(())
+    }
+
+    /// In the order of `project_ids`, `None` for a project that isn't cached
+    #[tracing::instrument(name = "cache_service.get_project_deployment_counts", skip_all, err)]
+    pub async fn get_project_deployment_counts(
+        project_ids: &[Uuid],
+        con: &mut MultiplexedConnection,
+    ) -> Result<Vec<Option<DeploymentCounts>>, AppError> {
+        if project_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let keys: Vec<String> = project_ids
+            .iter()
+            .map(|id| CacheKeys::project_deployment_counts(&id.to_string()))
+            .collect();
+
+        let cached: Vec<Option<String>> = redis::cmd("MGET")
+            .arg(&keys)
+            .query_async(con)
+            .await
+            .inspect_err(|e| {
+                error!(error = %e, "❌ Failed to get cached deployment counts");
+            })?;
+
+        Ok(cached
+            .into_iter()
+            .map(|raw| raw.and_then(|raw| serde_json::from_str(&raw).ok()))
+            .collect())
+    }
+
+    #[tracing::instrument(name = "cache_service.set_project_deployment_counts", skip_all, err)]
+    pub async fn set_project_deployment_counts(
+        counts: &HashMap<Uuid, DeploymentCounts>,
+        con: &mut MultiplexedConnection,
+    ) -> Result<(), AppError> {
+        if counts.is_empty() {
+            return Ok(());
+        }
+
+        let mut p = redis::pipe();
+        for (project_id, counts) in counts {
+            let key = CacheKeys::project_deployment_counts(&project_id.to_string());
+            p.set_ex(
+                key,
+                serde_json::to_string(counts)?,
+                PROJECT_DEPLOYMENT_COUNTS_TTL_SECONDS,
+            )
+            .ignore();
+        }
+
+        p.query_async::<()>(con).await.inspect_err(|e| {
+            error!(error = %e, "❌ Failed to cache deployment counts");
+        })?;
+
+        Ok(())
+    }
 }
@@ -1 +1,4 @@
+pub mod billing_client;
 pub mod cache_service;
+pub mod s3;
+pub mod vault_service;
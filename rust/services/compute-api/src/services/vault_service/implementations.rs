@@ -0,0 +1,114 @@
+use std::{collections::HashMap, sync::Arc};
+
+use compute_core::schemas::{SecretAuditEntry, SecretAuditTrail};
+use tracing::{error, info};
+use uuid::Uuid;
+use vaultrs::{
+    auth::kubernetes,
+    client::{Client, VaultClient, VaultClientSettingsBuilder},
+    error::ClientError,
+    kv2,
+};
+
+use crate::{
+    error::AppError,
+    services::vault_service::{VaultService, VaultServiceConfig},
+};
+
+impl VaultService {
+    pub async fn init(cfg: &VaultServiceConfig) -> Result<Self, AppError> {
+        info!("🔐 Initializing Vault client");
+
+        let mut client = VaultClient::new(
+            VaultClientSettingsBuilder::default()
+                .address(&cfg.address)
+                .build()
+                .map_err(|e| AppError::InternalServerError(e.to_string()))?,
+        )
+        .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+
+        let auth_info = kubernetes::login(&client, &cfg.auth_mount, &cfg.auth_role, &cfg.auth_jwt)
+            .await
+            .map_err(|e| AppError::InternalServerError(format!("Vault login failed: {}", e)))?;
+
+        client.set_token(&auth_info.client_token);
+
+        Ok(Self {
+            client: Arc::new(client),
+            cfg: cfg.clone(),
+        })
+    }
+
+    /// Stored at `github/installations/{installation_id}` under `webhook_secret`,
+    /// `None` when the installation has none
+    #[tracing::instrument(name = "vault_service.read_github_webhook_secret", skip_all, fields(installation_id = installation_id), err)]
+    pub async fn read_github_webhook_secret(
+        &self,
+        installation_id: i64,
+    ) -> Result<Option<String>, AppError> {
+        let path = format!("github/installations/{}", installation_id);
+
+        Ok(self
+            .read_secrets(&path)
+            .await?
+            .and_then(|mut secret| secret.remove("webhook_secret")))
+    }
+
+    /// `None` when nothing was ever written to `path`
+    #[tracing::instrument(name = "vault_service.read_secrets", skip_all, fields(path = %path), err)]
+    pub async fn read_secrets(
+        &self,
+        path: &str,
+    ) -> Result<Option<HashMap<String, String>>, AppError> {
+        match kv2::read::<HashMap<String, String>>(&*self.client, &self.cfg.kv_mount, path).await {
+            Ok(secrets) => Ok(Some(secrets)),
+            Err(ClientError::APIError { code: 404, .. }) => Ok(None),
+            Err(e) => {
+                error!(path = %path, error = %e, "🚨 Failed to read secrets from Vault");
+                Err(AppError::InternalServerError(format!(
+                    "🚨 Failed to read secrets from Vault: {}",
+                    e
+                )))
+            }
+        }
+    }
+
+    /// Written by the provisioner at `audit/{ns}/{deployment_id}`, empty when the secrets never changed
+    #[tracing::instrument(name = "vault_service.read_secret_audit", skip_all, fields(ns = %ns, deployment_id = %deployment_id), err)]
+    pub async fn read_secret_audit(
+        &self,
+        ns: &str,
+        deployment_id: &Uuid,
+    ) -> Result<Vec<SecretAuditEntry>, AppError> {
+        let path = format!("audit/{}/{}", ns, deployment_id);
+
+        match kv2::read::<SecretAuditTrail>(&*self.client, &self.cfg.kv_mount, &path).await {
+            Ok(trail) => Ok(trail.entries),
+            Err(ClientError::APIError { code: 404, .. }) => Ok(Vec::new()),
+            Err(e) => {
+                error!(path = %path, error = %e, "🚨 Failed to read secret audit trail from Vault");
+                Err(AppError::InternalServerError(format!(
+                    "🚨 Failed to read secret audit trail from Vault: {}",
+                    e
+                )))
+            }
+        }
+    }
+
+    /// Writes a new version of `path`, keys missing from `secrets` are gone from it
+    #[tracing::instrument(name = "vault_service.store_secrets", skip_all, fields(path = %path), err)]
+    pub async fn store_secrets(
+        &self,
+        path: &str,
+        secrets: &HashMap<String, String>,
+    ) -> Result<(), AppError> {
+        kv2::set(&*self.client, &self.cfg.kv_mount, path, secrets)
+            .await
+            .map_err(|e| {
+                error!(path = %path, error = %e, "🚨 Failed to store secrets in Vault");
+                AppError::InternalServerError(format!("🚨 Failed to store secrets in Vault: {}", e))
+            })?;
+
+        Ok(())
+    }
+}
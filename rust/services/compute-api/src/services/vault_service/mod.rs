@@ -0,0 +1,23 @@
+pub mod implementations;
+
+use std::sync::Arc;
+
+use serde::Deserialize;
+use vaultrs::client::VaultClient;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct VaultServiceConfig {
+    pub address: String,
+    pub auth_mount: String,
+    pub auth_role: String,
+    pub auth_jwt: String,
+    pub kv_mount: String,
+}
+
+/// Secrets compute-api needs itself, like webhook secrets and users' GitLab tokens,
+/// deployment secrets are the provisioner's business
+#[derive(Clone)]
+pub struct VaultService {
+    pub client: Arc<VaultClient>,
+    pub cfg: VaultServiceConfig,
+}
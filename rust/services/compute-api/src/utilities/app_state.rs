@@ -1,12 +1,19 @@
-use crate::config::Config;
+use crate::config::{Config, SharedDynamicConfig};
 use crate::error::AppError;
+use crate::services::{billing_client::BillingClient, s3::build_s3, vault_service::VaultService};
+use crate::utilities::idempotency::cleanup_expired_idempotency_keys;
 use axum::extract::FromRef;
 use axum_extra::extract::cookie::Key;
-use compute_core::github_app::GithubApp;
-use factory::factories::{amqp::Amqp, database::Database, kafka::Kafka, redis::Redis};
+use compute_core::{github_app::GithubApp, gitlab_app::GitlabApp};
+use factory::factories::{
+    amqp::Amqp, database::Database, kafka::Kafka, kubernetes::Kubernetes, redis::Redis,
+};
+use http_common::metrics::HttpMetrics;
+use object_store::aws::AmazonS3;
 
 use reqwest::Client;
 use rustls::ClientConfig;
+use std::sync::{Arc, RwLock};
 use users_core::jwt::JwtCapability;
 
 #[derive(FromRef, Clone)]
@@ -17,23 +24,45 @@ pub struct AppState {
     pub amqp: Amqp,
     pub kafka: Option<Kafka>,
     pub config: Config,
+    pub dynamic_config: SharedDynamicConfig,
     pub http_client: Client,
+    pub billing_client: BillingClient,
     pub key: Key,
     pub github_app: GithubApp,
+    pub gitlab_app: GitlabApp,
+    pub vault: VaultService,
+    pub kubernetes: Kubernetes,
+    pub s3: AmazonS3,
+    pub metrics: HttpMetrics,
 }
 
 impl AppState {
     pub async fn init(cfg: &Config) -> Result<Self, AppError> {
         let database = Database::new(&cfg.database).await;
-        let redis = Redis::new(&cfg.redis).await;
+        tokio::spawn(database.clone().report_metrics());
+        tokio::spawn(cleanup_expired_idempotency_keys(database.clone()));
+        let redis = Redis::new(&cfg.redis)
+            .await
+            .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+        let dynamic_config = Arc::new(RwLock::new(cfg.dynamic.clone()));
+        tokio::spawn(cfg.clone().watch(dynamic_config.clone(), redis.con.clone()));
         let amqp = Amqp::new(&cfg.amqp).await;
         let http_client = reqwest::ClientBuilder::new()
             .build()
             .unwrap_or_else(|e| panic!("Failed to construct http client: {}", e));
+        let billing_client = BillingClient::new(cfg, http_client.clone());
         let key = Key::from(cfg.cookie_key.as_bytes());
         let github_app = GithubApp {
             cfg: cfg.github_app.clone(),
         };
+        let gitlab_app = GitlabApp {
+            cfg: cfg.gitlab_app.clone(),
+        };
+        let vault = VaultService::init(&cfg.vault).await?;
+        let kubernetes = Kubernetes::new(&cfg.clusters)
+            .await
+            .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+        let s3 = build_s3(&cfg.s3);
 
         Ok(Self {
             rustls_config: None,
@@ -42,9 +71,16 @@ impl AppState {
             amqp,
             kafka: None,
             config: cfg.clone(),
+            dynamic_config,
             http_client,
+            billing_client,
             key,
             github_app,
+            gitlab_app,
+            vault,
+            kubernetes,
+            s3,
+            metrics: HttpMetrics::new(),
         })
     }
 }
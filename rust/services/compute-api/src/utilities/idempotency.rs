@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use axum::http::HeaderMap;
+use factory::factories::database::Database;
+use sha2::{Digest, Sha256};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{error::AppError, features::repositories::idempotency::IdempotencyRepository};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+pub const IDEMPOTENCY_REPLAY_HEADER: &str = "x-idempotency-replay";
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+const CLEANUP_INTERVAL_SECS: u64 = 3600;
+
+/// Hash of the `Idempotency-Key` header scoped to the user, `None` when the header is absent
+pub fn idempotency_key_hash(
+    headers: &HeaderMap,
+    user_id: &Uuid,
+) -> Result<Option<String>, AppError> {
+    let Some(value) = headers.get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(None);
+    };
+
+    let key = value
+        .to_str()
+        .map_err(|_| AppError::BadRequest("Idempotency-Key must be visible ASCII".into()))?;
+    if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LEN {
+        return Err(AppError::ValidationError(format!(
+            "Idempotency-Key must be 1 to {} characters",
+            MAX_IDEMPOTENCY_KEY_LEN
+        )));
+    }
+
+    let digest = Sha256::digest(format!("{}:{}", user_id, key).as_bytes());
+    Ok(Some(hex::encode(digest)))
+}
+
+/// Deletes expired idempotency records every hour, lookups already ignore them in between
+pub async fn cleanup_expired_idempotency_keys(database: Database) {
+    let mut interval = tokio::time::interval(Duration::from_secs(CLEANUP_INTERVAL_SECS));
+
+    loop {
+        interval.tick().await;
+
+        match IdempotencyRepository::delete_expired(&database.pool).await {
+            Ok(0) => {}
+            Ok(deleted) => info!("🧹 Deleted {} expired idempotency keys", deleted),
+            Err(e) => error!("❌ Failed to delete expired idempotency keys: {}", e),
+        }
+    }
+}
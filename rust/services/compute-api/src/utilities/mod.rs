@@ -1 +1,4 @@
 pub mod app_state;
+pub mod idempotency;
+pub mod rate_limit;
+pub mod versioning;
@@ -0,0 +1,27 @@
+use axum::{
+    extract::{FromRequestParts, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use factory::factories::rate_limit::RateLimitSubject;
+use users_core::jwt::Claims;
+
+use crate::utilities::app_state::AppState;
+
+/// Identifies the caller for the route level `RateLimitLayer`s, rejecting unauthenticated
+/// requests is left to the handlers
+pub async fn insert_rate_limit_subject(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let (mut parts, body) = req.into_parts();
+
+    if let Ok(claims) = Claims::from_request_parts(&mut parts, &state).await {
+        parts
+            .extensions
+            .insert(RateLimitSubject(claims.sub.to_string()));
+    }
+
+    next.run(Request::from_parts(parts, body)).await
+}
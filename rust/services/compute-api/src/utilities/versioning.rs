@@ -0,0 +1,185 @@
+use aide::{
+    OperationInput,
+    axum::{ApiRouter, routing::ApiMethodRouter},
+};
+use axum::{
+    extract::{FromRequestParts, State},
+    http::{HeaderName, HeaderValue, request::Parts},
+    middleware::map_response_with_state,
+    response::Response,
+    routing::MethodRouter,
+};
+use chrono::NaiveDate;
+
+use crate::error::AppError;
+
+pub const DEPRECATION_HEADER: &str = "deprecation";
+/// RFC 8594, the HTTP-date after which the route stops being served
+pub const SUNSET_HEADER: &str = "sunset";
+
+/// Leading path segment after `/api`, every route is served under each version it supports.
+/// A route only differs between versions once it's registered again with `since(V2)` and the old
+/// one capped with `until(V1)`, until then v2 serves the v1 handlers
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ApiVersion {
+    V1,
+    V2,
+}
+
+impl ApiVersion {
+    pub const ALL: [ApiVersion; 2] = [ApiVersion::V1, ApiVersion::V2];
+    pub const LATEST: ApiVersion = ApiVersion::V2;
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "v1",
+            ApiVersion::V2 => "v2",
+        }
+    }
+
+    fn from_path(path: &str) -> Option<Self> {
+        let segment = path.strip_prefix("/api/")?.split('/').next()?;
+        Self::ALL.into_iter().find(|v| v.as_str() == segment)
+    }
+}
+
+impl<S> FromRequestParts<S> for ApiVersion
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        ApiVersion::from_path(parts.uri.path())
+            .ok_or_else(|| AppError::NotFoundError("Unknown API version".into()))
+    }
+}
+
+impl OperationInput for ApiVersion {}
+
+/// Versions a route is registered under, from `since` up to the latest unless capped
+#[derive(Clone, Copy, Debug)]
+pub struct Versions {
+    min: ApiVersion,
+    max: ApiVersion,
+    /// Sunset date announced on every response of the route
+    sunset: Option<NaiveDate>,
+}
+
+impl Versions {
+    pub fn since(min: ApiVersion) -> Self {
+        Self {
+            min,
+            max: ApiVersion::LATEST,
+            sunset: None,
+        }
+    }
+
+    pub fn until(mut self, max: ApiVersion) -> Self {
+        self.max = max;
+        self
+    }
+
+    /// Responses carry `Deprecation: version="v1", sunset="2025-12-31"` for the version served
+    /// and `Sunset: Wed, 31 Dec 2025 00:00:00 GMT`
+    pub fn deprecated(mut self, sunset: NaiveDate) -> Self {
+        self.sunset = Some(sunset);
+        self
+    }
+
+    fn iter(self) -> impl Iterator<Item = ApiVersion> {
+        ApiVersion::ALL
+            .into_iter()
+            .filter(move |v| (self.min..=self.max).contains(v))
+    }
+}
+
+/// Registers routes once, without the `/api/{version}` prefix, and expands them into an
+/// [`ApiRouter`] with a copy per supported version
+pub struct VersionedRouter<S> {
+    router: ApiRouter<S>,
+}
+
+impl<S> VersionedRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    pub fn new() -> Self {
+        Self {
+            router: ApiRouter::new(),
+        }
+    }
+
+    pub fn api_route(mut self, path: &str, versions: Versions, route: ApiMethodRouter<S>) -> Self {
+        for version in versions.iter() {
+            let route = match deprecation(version, versions.sunset) {
+                Some(headers) => route
+                    .clone()
+                    .route_layer(map_response_with_state(headers, insert_deprecation)),
+                None => route.clone(),
+            };
+            self.router = self.router.api_route(&versioned_path(version, path), route);
+        }
+        self
+    }
+
+    /// Like [`Self::api_route`] but left out of the OpenAPI document, for SSE and WebSockets
+    pub fn route(mut self, path: &str, versions: Versions, route: MethodRouter<S>) -> Self {
+        for version in versions.iter() {
+            let route = match deprecation(version, versions.sunset) {
+                Some(headers) => route
+                    .clone()
+                    .route_layer(map_response_with_state(headers, insert_deprecation)),
+                None => route.clone(),
+            };
+            self.router = self.router.route(&versioned_path(version, path), route);
+        }
+        self
+    }
+
+    pub fn into_router(self) -> ApiRouter<S> {
+        self.router
+    }
+}
+
+impl<S> Default for VersionedRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn versioned_path(version: ApiVersion, path: &str) -> String {
+    format!("/api/{}{}", version.as_str(), path)
+}
+
+/// `Deprecation` and `Sunset` values of a deprecated route, `None` while it isn't
+fn deprecation(
+    version: ApiVersion,
+    sunset: Option<NaiveDate>,
+) -> Option<(HeaderValue, HeaderValue)> {
+    let sunset = sunset?;
+    let deprecation = format!(
+        r#"version="{}", sunset="{}""#,
+        version.as_str(),
+        sunset.format("%Y-%m-%d")
+    );
+    let http_date = sunset.format("%a, %d %b %Y 00:00:00 GMT").to_string();
+
+    Some((
+        HeaderValue::from_str(&deprecation).ok()?,
+        HeaderValue::from_str(&http_date).ok()?,
+    ))
+}
+
+async fn insert_deprecation(
+    State((deprecation, sunset)): State<(HeaderValue, HeaderValue)>,
+    mut response: Response,
+) -> Response {
+    let headers = response.headers_mut();
+    headers.insert(HeaderName::from_static(DEPRECATION_HEADER), deprecation);
+    headers.insert(HeaderName::from_static(SUNSET_HEADER), sunset);
+    response
+}
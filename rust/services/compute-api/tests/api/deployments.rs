@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use axum::http::{Method, StatusCode};
+use compute_core::schemas::{CreateDeploymentMessage, DeploymentSourceMessage};
+use lapin::{
+    options::{BasicGetOptions, QueueBindOptions, QueueDeclareOptions},
+    types::FieldTable,
+};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::common::{ESTIMATED_MONTHLY_COST, TestApp, body_json};
+
+#[sqlx::test(migrations = "../../migrations")]
+#[ignore = "needs Redis and RabbitMQ"]
+async fn create_deployment_publishes_message(pool: PgPool) {
+    let app = TestApp::spawn(pool).await;
+    let project_id = app.create_project("shop").await;
+    let (preset_id, preset_cpu_millicores): (Uuid, i32) =
+        sqlx::query_as("SELECT id, cpu_millicores FROM presets WHERE name = 'Starter'")
+            .fetch_one(&app.state.database.pool)
+            .await
+            .unwrap();
+
+    // A private queue next to the provisioner's, so the test sees its own copy of the message
+    let channel = app.state.amqp.channel().await.unwrap();
+    let queue = channel
+        .queue_declare(
+            "",
+            QueueDeclareOptions {
+                exclusive: true,
+                auto_delete: true,
+                ..QueueDeclareOptions::default()
+            },
+            FieldTable::default(),
+        )
+        .await
+        .unwrap();
+    channel
+        .queue_bind(
+            queue.name().as_str(),
+            "compute",
+            "compute.create",
+            QueueBindOptions::default(),
+            FieldTable::default(),
+        )
+        .await
+        .unwrap();
+
+    let response = app
+        .request(
+            Method::POST,
+            &format!("/api/v1/compute/projects/{}/deployments", project_id),
+            Some(json!({
+                "name": "web",
+                "source": { "type": "image", "url": "nginx:1.27" },
+                "port": 80,
+                "desiredReplicas": 2,
+                "presetId": preset_id,
+                "environmentVariables": { "RUST_LOG": "info" }
+            })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let deployment = body_json(response).await;
+    assert_eq!(
+        deployment["estimatedCost"]["monthly"],
+        ESTIMATED_MONTHLY_COST
+    );
+
+    let mut message = None;
+    for _ in 0..20 {
+        message = channel
+            .basic_get(queue.name().as_str(), BasicGetOptions { no_ack: true })
+            .await
+            .unwrap();
+        if message.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let message = message.expect("No compute.create message was published");
+    let message: CreateDeploymentMessage = serde_json::from_slice(&message.delivery.data).unwrap();
+
+    assert_eq!(message.deployment_id.to_string(), deployment["id"]);
+    assert_eq!(message.user_id, app.user_id);
+    assert_eq!(message.project_id, project_id);
+    assert_eq!(message.preset_id, preset_id);
+    assert_eq!(message.name, "web");
+    assert_eq!(message.port, 80);
+    assert_eq!(message.desired_replicas, 2);
+    assert_eq!(
+        message.resource_spec.cpu_limit_millicores,
+        preset_cpu_millicores
+    );
+    assert_eq!(message.region.as_deref(), Some("default"));
+    assert!(matches!(
+        message.source,
+        DeploymentSourceMessage::Image { ref url, .. } if url == "nginx:1.27"
+    ));
+    assert_eq!(
+        message
+            .environment_variables
+            .as_ref()
+            .and_then(|vars| vars.get("RUST_LOG"))
+            .map(String::as_str),
+        Some("info")
+    );
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+#[ignore = "needs Redis and RabbitMQ"]
+async fn create_deployment_rejects_unknown_region(pool: PgPool) {
+    let app = TestApp::spawn(pool).await;
+    let project_id = app.create_project("shop").await;
+    let preset_id: Uuid = sqlx::query_scalar("SELECT id FROM presets WHERE name = 'Starter'")
+        .fetch_one(&app.state.database.pool)
+        .await
+        .unwrap();
+
+    let response = app
+        .request(
+            Method::POST,
+            &format!("/api/v1/compute/projects/{}/deployments", project_id),
+            Some(json!({
+                "name": "web",
+                "source": { "type": "image", "url": "nginx:1.27" },
+                "port": 80,
+                "desiredReplicas": 1,
+                "presetId": preset_id,
+                "region": "mars-1"
+            })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
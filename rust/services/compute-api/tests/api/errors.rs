@@ -0,0 +1,41 @@
+use axum::{http::StatusCode, response::IntoResponse};
+use compute_api::error::AppError;
+use kube::core::Status;
+use serde_json::{Value, json};
+
+fn api_error(code: u16, message: &str) -> AppError {
+    let status: Status =
+        serde_json::from_value(json!({ "code": code, "message": message })).unwrap();
+    AppError::KubeError(kube::Error::Api(Box::new(status)))
+}
+
+async fn respond(error: AppError) -> (StatusCode, Value) {
+    let res = error.into_response();
+    let status = res.status();
+    let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn quota_rejection_is_payment_required() {
+    let (status, body) = respond(api_error(
+        403,
+        "deployments.apps \"web\" is forbidden: exceeded quota: compute-resources, requested: requests.cpu=500m, used: requests.cpu=1500m, limited: requests.cpu=2",
+    ))
+    .await;
+
+    assert_eq!(status, StatusCode::PAYMENT_REQUIRED);
+    assert_eq!(body["error_code"], "QUOTA_EXCEEDED");
+    assert_eq!(body["upgrade_url"], "/billing/presets");
+}
+
+#[tokio::test]
+async fn other_forbidden_answers_stay_internal() {
+    // The service's own RBAC isn't the caller's business
+    let (status, body) = respond(api_error(403, "pods is forbidden: User cannot list")).await;
+
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(body.get("error_code").is_none());
+}
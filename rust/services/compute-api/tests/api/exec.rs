@@ -0,0 +1,50 @@
+use bytes::Bytes;
+use compute_api::features::schemas::{ExecFrame, ExecResize};
+
+#[test]
+fn stdin_frame_carries_the_bytes_after_the_type() {
+    let frame = ExecFrame::parse(Bytes::from_static(b"\x01ls -la\r"));
+
+    assert_eq!(
+        frame,
+        Some(ExecFrame::Stdin(Bytes::from_static(b"ls -la\r")))
+    );
+}
+
+#[test]
+fn stdin_frame_keeps_control_characters() {
+    // Ctrl-D and Ctrl-B are plain input once the type byte is stripped
+    let frame = ExecFrame::parse(Bytes::from_static(b"\x01\x04\x02"));
+
+    assert_eq!(
+        frame,
+        Some(ExecFrame::Stdin(Bytes::from_static(b"\x04\x02")))
+    );
+}
+
+#[test]
+fn resize_frame_is_parsed() {
+    let frame = ExecFrame::parse(Bytes::from_static(b"\x02{\"cols\":120,\"rows\":40}"));
+
+    assert_eq!(
+        frame,
+        Some(ExecFrame::Resize(ExecResize {
+            cols: 120,
+            rows: 40
+        }))
+    );
+}
+
+#[test]
+fn malformed_frames_are_rejected() {
+    for data in [
+        &b""[..],
+        b"\x03hello",
+        b"\x02",
+        b"\x02{\"cols\":120}",
+        b"\x02{\"cols\":-1,\"rows\":40}",
+        b"\x02{\"cols\":70000,\"rows\":40}",
+    ] {
+        assert_eq!(ExecFrame::parse(Bytes::from_static(data)), None, "{data:?}");
+    }
+}
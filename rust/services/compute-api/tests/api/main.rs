@@ -0,0 +1,22 @@
+//! HTTP level tests of compute-api, run them with `cargo test -p compute-api -- --ignored`.
+//! CI runs them against service containers, see `.github/workflows/compute-api.yml`
+//!
+//! Every test gets a fresh Postgres database from `#[sqlx::test]` (`DATABASE_URL`), Redis and
+//! RabbitMQ have to be running at `REDIS_URL` and `AMQP_URL`. Kubernetes, Vault and billing-api
+//! are mocked, see `common`
+//!
+//! `exec` only covers the exec WebSocket's frame parsing and runs without any of them, `errors`
+//! only the status codes errors answer with, `versioning` only the per-version routing and its
+//! deprecation headers
+//!
+//! `openapi` compares the generated document with the committed `openapi.json` and needs none of
+//! them either, rerun it with `UPDATE_OPENAPI=1` after changing routes or schemas
+
+mod common;
+mod deployments;
+mod errors;
+mod exec;
+mod metrics;
+mod openapi;
+mod projects;
+mod versioning;
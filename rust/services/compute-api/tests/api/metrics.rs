@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    http::{Method, Response, StatusCode, header},
+};
+use compute_core::{
+    channel_names::ChannelNames,
+    event::{ComputeEvent, VersionedEvent},
+    models::DeploymentEventLevel,
+};
+use futures::StreamExt;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::common::TestApp;
+
+fn system_message<'a>(deployment_id: &'a str, message: &str) -> VersionedEvent<'a> {
+    VersionedEvent::new(ComputeEvent::DeploymentSystemMessage {
+        deployment_id,
+        level: DeploymentEventLevel::Info,
+        message: message.to_string(),
+    })
+}
+
+/// The frame `compute-api` relays for an event published under `id`
+fn expected_frame(id: u64, event: &VersionedEvent) -> String {
+    let payload = serde_json::to_string(event).unwrap();
+    format!(
+        "event: compute\nid: {}\ndata: {{\"id\":{},{}\n\n",
+        id,
+        id,
+        &payload[1..]
+    )
+}
+
+async fn first_frame(response: Response<Body>) -> String {
+    let mut body = response.into_body().into_data_stream();
+    let frame = tokio::time::timeout(Duration::from_secs(5), body.next())
+        .await
+        .expect("No event within 5 seconds")
+        .expect("Stream ended before the first event")
+        .unwrap();
+    String::from_utf8(frame.to_vec()).unwrap()
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+#[ignore = "needs Redis and RabbitMQ"]
+async fn deployment_metrics_sse_emits_first_event(pool: PgPool) {
+    let app = TestApp::spawn(pool).await;
+    let project_id = app.create_project("shop").await;
+    let deployment_id = Uuid::new_v4().to_string();
+
+    let response = app
+        .request(
+            Method::GET,
+            &format!(
+                "/api/v1/compute/projects/{}/deployments/{}/metrics/sse",
+                project_id, deployment_id
+            ),
+            None,
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "text/event-stream"
+    );
+
+    // The handler subscribed before answering, so nothing published from here on is missed
+    let event = system_message(&deployment_id, "Scaled to 2 replicas");
+    let mut con = app.state.redis.con.clone();
+    let id = event
+        .publish(&ChannelNames::deployment_metrics(&deployment_id), &mut con)
+        .await
+        .unwrap();
+
+    assert_eq!(first_frame(response).await, expected_frame(id, &event));
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+#[ignore = "needs Redis and RabbitMQ"]
+async fn deployment_metrics_sse_replays_after_last_event_id(pool: PgPool) {
+    let app = TestApp::spawn(pool).await;
+    let project_id = app.create_project("shop").await;
+    let deployment_id = Uuid::new_v4().to_string();
+
+    let mut con = app.state.redis.con.clone();
+    let seen = system_message(&deployment_id, "Pulling image");
+    let seen_id = seen
+        .publish(&ChannelNames::deployment_metrics(&deployment_id), &mut con)
+        .await
+        .unwrap();
+    let missed = system_message(&deployment_id, "Started container");
+    let missed_id = missed
+        .publish(&ChannelNames::deployment_events(&deployment_id), &mut con)
+        .await
+        .unwrap();
+
+    let request = app
+        .authorized(
+            Method::GET,
+            &format!(
+                "/api/v1/compute/projects/{}/deployments/{}/metrics/sse",
+                project_id, deployment_id
+            ),
+        )
+        .header("last-event-id", seen_id.to_string());
+    let response = app.send(request, None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    assert_eq!(
+        first_frame(response).await,
+        expected_frame(missed_id, &missed)
+    );
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+#[ignore = "needs Redis and RabbitMQ"]
+async fn metrics_history_is_scoped_to_the_project(pool: PgPool) {
+    let app = TestApp::spawn(pool).await;
+    let project_id = app.create_project("shop").await;
+    let other_project_id = app.create_project("blog").await;
+    let deployment_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO deployments (user_id, project_id, name, source, port, preset_id, status, service)
+        SELECT $1, $2, 'web', '{"type": "image", "url": "nginx:1.27"}', 80, id, 'running', 'web'
+        FROM presets WHERE name = 'Starter'
+        RETURNING id
+        "#,
+    )
+    .bind(app.user_id)
+    .bind(project_id)
+    .fetch_one(&app.state.database.pool)
+    .await
+    .unwrap();
+
+    let history = |project_id: Uuid| {
+        format!(
+            "/api/v1/compute/projects/{}/deployments/{}/metrics/history",
+            project_id, deployment_id
+        )
+    };
+
+    let response = app.request(Method::GET, &history(project_id), None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // The user owns both projects, the deployment still only answers under its own
+    let response = app
+        .request(Method::GET, &history(other_project_id), None)
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,28 @@
+use std::{env, fs, path::Path};
+
+use compute_api::app::openapi;
+use serde_json::Value;
+
+/// The generated document is committed as `openapi.json` next to `Cargo.toml`, so a change to
+/// the routes or schemas shows up in review. `UPDATE_OPENAPI=1` rewrites it
+#[test]
+fn openapi_matches_committed_baseline() {
+    let spec = serde_json::to_value(openapi("compute-api", "test")).unwrap();
+    assert!(spec["components"]["securitySchemes"]["bearerAuth"].is_object());
+    assert!(spec["components"]["securitySchemes"]["cookieAuth"].is_object());
+
+    let baseline_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("openapi.json");
+    if env::var_os("UPDATE_OPENAPI").is_some() {
+        let pretty = serde_json::to_string_pretty(&spec).unwrap() + "\n";
+        fs::write(&baseline_path, pretty).expect("Failed to write openapi.json");
+        return;
+    }
+
+    let baseline = fs::read_to_string(&baseline_path)
+        .expect("No openapi.json baseline, run with UPDATE_OPENAPI=1 and commit it");
+    let baseline: Value = serde_json::from_str(&baseline).expect("openapi.json is not JSON");
+    assert!(
+        spec == baseline,
+        "The OpenAPI document changed, rerun with UPDATE_OPENAPI=1 and commit openapi.json"
+    );
+}
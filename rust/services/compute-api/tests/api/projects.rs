@@ -0,0 +1,230 @@
+use std::time::Duration;
+
+use axum::http::{Method, Request, StatusCode, header};
+use compute_api::features::schemas::{DeploymentCounts, ProjectHealth};
+use compute_core::{models::DeploymentStatus, schemas::UpdateDeploymentMessage};
+use lapin::{
+    options::{BasicGetOptions, QueueBindOptions, QueueDeclareOptions},
+    types::FieldTable,
+};
+use serde_json::json;
+use sqlx::PgPool;
+use users_core::jwt::create_impersonation_token;
+use uuid::Uuid;
+
+use crate::common::{TestApp, body_json};
+
+#[sqlx::test(migrations = "../../migrations")]
+#[ignore = "needs Redis and RabbitMQ"]
+async fn project_crud(pool: PgPool) {
+    let app = TestApp::spawn(pool).await;
+
+    let response = app
+        .request(
+            Method::POST,
+            "/api/v1/compute/projects",
+            Some(json!({ "name": "shop", "description": "Storefront" })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let project = body_json(response).await;
+    assert_eq!(project["name"], "shop");
+    assert_eq!(project["ownerId"], app.user_id.to_string());
+    let uri = format!(
+        "/api/v1/compute/projects/{}",
+        project["id"].as_str().unwrap()
+    );
+
+    let response = app.request(Method::GET, &uri, None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["description"], "Storefront");
+
+    let response = app
+        .request(Method::PATCH, &uri, Some(json!({ "name": "store" })))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["name"], "store");
+
+    let response = app.request(Method::DELETE, &uri, None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app.request(Method::GET, &uri, None).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+#[ignore = "needs Redis and RabbitMQ"]
+async fn create_project_rejects_invalid_name(pool: PgPool) {
+    let app = TestApp::spawn(pool).await;
+
+    let response = app
+        .request(
+            Method::POST,
+            "/api/v1/compute/projects",
+            Some(json!({ "name": "" })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+#[ignore = "needs Redis and RabbitMQ"]
+async fn projects_require_authentication(pool: PgPool) {
+    let app = TestApp::spawn(pool).await;
+
+    let response = app
+        .anonymous_request(Method::GET, "/api/v1/compute/projects")
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+#[ignore = "needs Redis and RabbitMQ"]
+async fn impersonated_writes_are_audited(pool: PgPool) {
+    let app = TestApp::spawn(pool).await;
+    let admin_id: Uuid =
+        sqlx::query_scalar("INSERT INTO users (username, email) VALUES ($1, $2) RETURNING id")
+            .bind("support")
+            .bind("support@poddle.test")
+            .fetch_one(&app.state.database.pool)
+            .await
+            .unwrap();
+    let token = create_impersonation_token(&app.state, app.user_id, admin_id).unwrap();
+
+    let builder = Request::builder()
+        .method(Method::POST)
+        .uri("/api/v1/compute/projects")
+        .header(header::AUTHORIZATION, format!("Bearer {}", token));
+    let response = app.send(builder, Some(json!({ "name": "shop" }))).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let (user_id, impersonated_by, action): (Uuid, Option<Uuid>, String) =
+        sqlx::query_as("SELECT user_id, impersonated_by, action FROM audit_log")
+            .fetch_one(&app.state.database.pool)
+            .await
+            .unwrap();
+    assert_eq!(user_id, app.user_id);
+    assert_eq!(impersonated_by, Some(admin_id));
+    assert_eq!(action, "POST /api/v1/compute/projects");
+}
+
+async fn insert_deployment(app: &TestApp, project_id: Uuid, name: &str, status: &str) -> Uuid {
+    sqlx::query_scalar(
+        r#"
+        INSERT INTO deployments (user_id, project_id, name, source, port, preset_id, status, service)
+        SELECT $1, $2, $3, '{"type": "image", "url": "nginx:1.27"}', 80, id, $4::deployment_status, $3
+        FROM presets WHERE name = 'Starter'
+        RETURNING id
+        "#,
+    )
+    .bind(app.user_id)
+    .bind(project_id)
+    .bind(name)
+    .bind(status)
+    .fetch_one(&app.state.database.pool)
+    .await
+    .expect("Failed to create deployment")
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+#[ignore = "needs Redis and RabbitMQ"]
+async fn project_environment_change_rolls_out_running_deployments(pool: PgPool) {
+    let app = TestApp::spawn(pool).await;
+    let project_id = app.create_project("shop").await;
+    let running = insert_deployment(&app, project_id, "web", "running").await;
+    // Picks the variables up when it resumes
+    insert_deployment(&app, project_id, "worker", "suspended").await;
+
+    let channel = app.state.amqp.channel().await.unwrap();
+    let queue = channel
+        .queue_declare(
+            "",
+            QueueDeclareOptions {
+                exclusive: true,
+                auto_delete: true,
+                ..QueueDeclareOptions::default()
+            },
+            FieldTable::default(),
+        )
+        .await
+        .unwrap();
+    channel
+        .queue_bind(
+            queue.name().as_str(),
+            "compute",
+            "compute.update",
+            QueueBindOptions::default(),
+            FieldTable::default(),
+        )
+        .await
+        .unwrap();
+
+    let response = app
+        .request(
+            Method::PATCH,
+            &format!(
+                "/api/v1/compute/projects/{}/environment-variables",
+                project_id
+            ),
+            Some(json!({ "set": { "API_KEY": "secret" } })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        body_json(response).await["environmentVariables"]["API_KEY"],
+        "secret"
+    );
+
+    let mut messages = Vec::new();
+    for _ in 0..10 {
+        while let Some(message) = channel
+            .basic_get(queue.name().as_str(), BasicGetOptions { no_ack: true })
+            .await
+            .unwrap()
+        {
+            messages.push(
+                serde_json::from_slice::<UpdateDeploymentMessage>(&message.delivery.data).unwrap(),
+            );
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].deployment_id, running);
+    assert_eq!(messages[0].project_id, project_id);
+    // Left out so the provisioner merges the project's variables into the stored ones
+    assert!(messages[0].environment_variables.is_none());
+}
+
+#[test]
+fn project_health_follows_unhealthy_share() {
+    let mut counts = DeploymentCounts::default();
+    assert_eq!(counts.health(), ProjectHealth::Healthy);
+
+    counts.add(DeploymentStatus::Running, 3);
+    counts.add(DeploymentStatus::Queued, 1);
+    counts.add(DeploymentStatus::Deleted, 5);
+    assert_eq!(counts.health(), ProjectHealth::Healthy);
+    assert_eq!(
+        counts,
+        DeploymentCounts {
+            running: 3,
+            building: 1,
+            total: 4,
+            ..Default::default()
+        }
+    );
+
+    counts.add(DeploymentStatus::ImagePullError, 1);
+    assert_eq!(counts.health(), ProjectHealth::Degraded);
+
+    counts.add(DeploymentStatus::Degraded, 2);
+    counts.add(DeploymentStatus::Provisioning, 1);
+    assert_eq!(counts.unhealthy, 3);
+    assert_eq!(counts.total, 8);
+    assert_eq!(counts.health(), ProjectHealth::Degraded);
+
+    // Half of them
+    counts.add(DeploymentStatus::Failed, 2);
+    assert_eq!(counts.health(), ProjectHealth::Critical);
+}
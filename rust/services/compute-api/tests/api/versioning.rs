@@ -0,0 +1,78 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{HeaderMap, Request, StatusCode},
+    routing::get as axum_get,
+};
+use chrono::NaiveDate;
+use compute_api::utilities::versioning::{
+    ApiVersion, DEPRECATION_HEADER, SUNSET_HEADER, VersionedRouter, Versions,
+};
+use tower::ServiceExt;
+
+fn app() -> Router {
+    let sunset = NaiveDate::from_ymd_opt(2025, 12, 31).unwrap();
+
+    VersionedRouter::new()
+        .route(
+            "/current",
+            Versions::since(ApiVersion::V1),
+            axum_get(|| async { "current" }),
+        )
+        .route(
+            "/legacy",
+            Versions::since(ApiVersion::V1)
+                .until(ApiVersion::V1)
+                .deprecated(sunset),
+            axum_get(|| async { "legacy" }),
+        )
+        .route(
+            "/new",
+            Versions::since(ApiVersion::V2),
+            axum_get(|| async { "new" }),
+        )
+        .into_router()
+        .into()
+}
+
+async fn send(uri: &str) -> (StatusCode, HeaderMap) {
+    let res = app()
+        .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    (res.status(), res.headers().clone())
+}
+
+#[tokio::test]
+async fn routes_are_served_between_their_versions() {
+    for (uri, status) in [
+        ("/api/v1/current", StatusCode::OK),
+        ("/api/v2/current", StatusCode::OK),
+        ("/api/v1/legacy", StatusCode::OK),
+        ("/api/v2/legacy", StatusCode::NOT_FOUND),
+        ("/api/v1/new", StatusCode::NOT_FOUND),
+        ("/api/v2/new", StatusCode::OK),
+    ] {
+        assert_eq!(send(uri).await.0, status, "{}", uri);
+    }
+}
+
+#[tokio::test]
+async fn deprecated_routes_announce_their_sunset() {
+    let (_, headers) = send("/api/v1/legacy").await;
+
+    assert_eq!(
+        headers[DEPRECATION_HEADER],
+        r#"version="v1", sunset="2025-12-31""#
+    );
+    assert_eq!(headers[SUNSET_HEADER], "Wed, 31 Dec 2025 00:00:00 GMT");
+}
+
+#[tokio::test]
+async fn other_routes_carry_no_deprecation() {
+    for uri in ["/api/v1/current", "/api/v2/current", "/api/v2/new"] {
+        let (_, headers) = send(uri).await;
+        assert!(!headers.contains_key(DEPRECATION_HEADER), "{}", uri);
+        assert!(!headers.contains_key(SUNSET_HEADER), "{}", uri);
+    }
+}
@@ -1,14 +1,17 @@
 use std::{net::SocketAddr, path::PathBuf};
 
+use compute_core::configs::PrometheusConfig;
 use config::{ConfigBuilder, ConfigError, Environment, File, builder::AsyncState};
-use factory::factories::{observability::ObservabilityConfig, redis::RedisConfig};
+use factory::factories::{
+    database::DatabaseConfig, observability::ObservabilityConfig, redis::RedisConfig,
+};
 use serde::Deserialize;
-use compute_core::configs::PrometheusConfig;
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct Config {
     pub server_address: SocketAddr,
     pub observability: ObservabilityConfig,
+    pub database: DatabaseConfig,
     pub redis: RedisConfig,
     pub prometheus: PrometheusConfig,
 }
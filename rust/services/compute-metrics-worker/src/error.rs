@@ -24,7 +24,7 @@ pub enum AppError {
     #[error("Encountered an error trying to convert an infallible value: {0}")]
     FromRequestPartsError(#[from] std::convert::Infallible),
 
-    #[error("Serde json error")]
+    #[error("Serde json error: {0}")]
     SerdejsonError(#[from] serde_json::Error),
 
     #[error("External service error")]
@@ -35,7 +35,7 @@ pub enum AppError {
     },
     #[error("Service unavailable error")]
     ServiceUnavailable(String),
-    #[error("Internal server error")]
+    #[error("Internal server error: {0}")]
     InternalServerError(String),
 
     #[error("IO error, {0}")]
@@ -44,6 +44,9 @@ pub enum AppError {
     #[error("Redis error: {0}")]
     RedisError(#[from] redis::RedisError),
 
+    #[error("Database error: {0}")]
+    SqlxError(#[from] sqlx::Error),
+
     #[error("Token creation error")]
     TokenCreationError,
     #[error("Invalid token error")]
@@ -10,15 +10,17 @@ use std::result::Result::Ok;
 use std::{env, net::SocketAddr};
 
 use config::Config;
-use factory::factories::{observability::Observability, redis::Redis};
+use factory::factories::{database::Database, observability::Observability, redis::Redis};
 
 use tokio::task::JoinSet;
 use tracing::{error, info};
 use utility::shutdown_signal::shutdown_signal;
 
 use crate::error::AppError;
+use crate::services::metrics_history::start_metrics_persister;
 use crate::services::prometheus::Prometheus;
 use crate::services::prometheus::implementations::start_metrics_scraper;
+use crate::services::scaling_recommender::start_scaling_recommender;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -51,13 +53,28 @@ async fn main() -> anyhow::Result<()> {
     .await;
 
     // Initialize services
-    let redis = Redis::new(&cfg.redis).await;
+    let database = Database::new(&cfg.database).await;
+    let pool_metrics = database.clone().report_metrics();
+    let redis = Redis::new(&cfg.redis).await?;
 
     let mut set = JoinSet::new();
     let prometheus = Prometheus::new(&cfg.prometheus).await?;
 
     // Spawn background tasks
-    set.spawn(start_metrics_scraper(redis, prometheus));
+    set.spawn(start_metrics_scraper(
+        database.pool.clone(),
+        redis.clone(),
+        prometheus,
+    ));
+    set.spawn(start_metrics_persister(
+        database.pool.clone(),
+        redis.clone(),
+    ));
+    set.spawn(start_scaling_recommender(database.pool, redis));
+    set.spawn(async move {
+        pool_metrics.await;
+        Ok(())
+    });
     set.spawn(start_health_server(
         cargo_pkg_name,
         cargo_pkg_version,
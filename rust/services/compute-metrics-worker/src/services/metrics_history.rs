@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use compute_core::{cache_keys::CacheKeys, schemas::MetricSnapshot};
+use factory::factories::redis::Redis;
+use redis::AsyncCommands;
+use sqlx::PgPool;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{error::AppError, services::repository::DeploymentRepository};
+
+/// Snapshots are copied at least this often, Redis has to keep more than this much of them
+const PERSIST_INTERVAL_SECS: u64 = 300;
+
+pub async fn start_metrics_persister(pool: PgPool, redis: Redis) -> Result<(), AppError> {
+    info!(
+        "🗄️ Starting metrics persister, interval: {}s",
+        PERSIST_INTERVAL_SECS
+    );
+
+    let mut interval = tokio::time::interval(Duration::from_secs(PERSIST_INTERVAL_SECS));
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = persist(&pool, &redis).await {
+            error!("❌ Failed to persist metrics: {}", e);
+        }
+    }
+}
+
+/// Copies every deployment's Redis list to `deployment_metrics`, the list itself is left alone
+/// since live charts and alerts read from it
+#[tracing::instrument("persist_metrics", skip_all, err)]
+async fn persist(pool: &PgPool, redis: &Redis) -> Result<(), AppError> {
+    let mut con = redis.get_connection().await?;
+    let pattern = CacheKeys::deployment_metrics("*");
+    let mut keys: Vec<String> = Vec::new();
+    {
+        let mut iter = con.scan_match::<_, String>(&pattern).await?;
+        while let Some(key) = iter.next_item().await {
+            keys.push(key);
+        }
+    }
+
+    let mut persisted = 0;
+    for key in keys {
+        // Pod keys match the pattern too, `deployment:{id}:pod:{uid}:metrics`
+        let Some(deployment_id) = key
+            .strip_prefix("deployment:")
+            .and_then(|k| k.strip_suffix(":metrics"))
+            .and_then(|id| Uuid::parse_str(id).ok())
+        else {
+            continue;
+        };
+
+        let snapshots: Vec<MetricSnapshot> = con.lrange(&key, 0, -1).await?;
+        if snapshots.is_empty() {
+            continue;
+        }
+
+        // A deployment deleted in the meantime must not stop the others
+        match DeploymentRepository::insert_metric_snapshots(&deployment_id, &snapshots, pool).await
+        {
+            Ok(inserted) => persisted += inserted,
+            Err(e) => {
+                error!(deployment_id = %deployment_id, "❌ Failed to persist snapshots: {}", e)
+            }
+        }
+    }
+
+    info!("🗄️ Persisted {} new metric snapshots", persisted);
+
+    Ok(())
+}
@@ -1 +1,4 @@
+pub mod metrics_history;
 pub mod prometheus;
+pub mod repository;
+pub mod scaling_recommender;
@@ -1,20 +1,31 @@
-use crate::{error::AppError, services::prometheus::Prometheus};
+use crate::{
+    error::AppError,
+    services::{prometheus::Prometheus, repository::DeploymentRepository},
+};
 use compute_core::{
     cache_keys::CacheKeys,
     channel_names::ChannelNames,
     configs::PrometheusConfig,
-    event::ComputeEvent,
+    event::{ComputeEvent, VersionedEvent},
+    models::DeploymentEventLevel,
     schemas::{DeploymentMetricUpdate, MetricSnapshot, PodMeta, PodMetricUpdate, PodPhase},
 };
 use factory::factories::redis::Redis;
 use prometheus_http_query::{Client, response::Data};
-use redis::AsyncTypedCommands;
+use redis::{AsyncTypedCommands, aio::MultiplexedConnection};
+use sqlx::PgPool;
 use std::collections::{HashMap, HashSet};
 use std::time::Duration;
+use tokio::time::MissedTickBehavior;
 use tracing::{debug, error, info};
+use utility::retry::{RetryConfig, retry_with_backoff};
 use uuid::Uuid;
 
-pub async fn start_metrics_scraper(redis: Redis, prometheus: Prometheus) -> Result<(), AppError> {
+pub async fn start_metrics_scraper(
+    pool: PgPool,
+    redis: Redis,
+    prometheus: Prometheus,
+) -> Result<(), AppError> {
     let client = prometheus.client;
     let cfg = prometheus.cfg;
 
@@ -25,11 +36,14 @@ pub async fn start_metrics_scraper(redis: Redis, prometheus: Prometheus) -> Resu
     );
 
     let mut interval = tokio::time::interval(Duration::from_secs(cfg.scrape_interval_secs as u64));
+    // Retries can hold a scrape past the next tick, the missed ticks are dropped instead of
+    // firing back to back
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
 
     loop {
         interval.tick().await;
 
-        if let Err(e) = scrape(&cfg, &client, redis.clone()).await {
+        if let Err(e) = scrape(&cfg, &client, &pool, &redis).await {
             error!("❌ Failed to scrape metrics: {}", e);
         }
     }
@@ -54,7 +68,12 @@ struct PodBuffer {
 /// `container_cpu_usage_seconds_total` comes from `cAdvisor` (embedded in `Kubelet`).
 /// It knows about low-level details like `pod`, `namespace`, and `image`, but it is unaware of your high-level Kubernetes `labels`
 #[tracing::instrument("scrape", skip_all, fields(scrape_id  = tracing::field::Empty), err)]
-async fn scrape(cfg: &PrometheusConfig, client: &Client, mut redis: Redis) -> Result<(), AppError> {
+async fn scrape(
+    cfg: &PrometheusConfig,
+    client: &Client,
+    pool: &PgPool,
+    redis: &Redis,
+) -> Result<(), AppError> {
     let scrape_id = Uuid::new_v4();
     tracing::Span::current().record("scrape_id", &scrape_id.to_string());
 
@@ -118,15 +137,17 @@ async fn scrape(cfg: &PrometheusConfig, client: &Client, mut redis: Redis) -> Re
     // Execute queries
     let start = std::time::Instant::now();
 
+    let retry = RetryConfig::default();
     let (cpu_res, mem_res, restart_res) = tokio::try_join!(
-        client.query(cpu_query).get(),
-        client.query(memory_query).get(),
-        client.query(restarts_query).get()
+        retry_with_backoff("CPU query", &retry, || client
+            .query(cpu_query.as_str())
+            .get()),
+        retry_with_backoff("Memory query", &retry, || client.query(memory_query).get()),
+        retry_with_backoff("Restarts query", &retry, || client
+            .query(restarts_query)
+            .get())
     )
-    .map_err(|e| {
-        error!(error = %e, "❌ Prometheus query failed");
-        AppError::InternalServerError(format!("Prometheus query failed: {}", e))
-    })?;
+    .inspect_err(|e| error!(error = %e, "❌ Prometheus query failed"))?;
 
     debug!(
         elapsed = start.elapsed().as_millis(),
@@ -264,8 +285,13 @@ async fn scrape(cfg: &PrometheusConfig, client: &Client, mut redis: Redis) -> Re
     let mut projects_count = 0;
     let mut deployments_count = 0;
     let mut pods_count = 0;
+    // Running pods per deployment, alert thresholds are relative to their combined limits
+    let mut deployment_pods: HashMap<Uuid, usize> = HashMap::new();
 
+    let mut con = redis.get_connection().await?;
     let mut p = redis::pipe();
+    // Published once the snapshots are written, each one takes a round trip for its SSE id
+    let mut messages = Vec::new();
 
     for (id, deployment_map) in project_map {
         projects_count += 1;
@@ -280,11 +306,7 @@ async fn scrape(cfg: &PrometheusConfig, client: &Client, mut redis: Redis) -> Re
             let index_key = CacheKeys::deployment_pods(&id);
 
             // If this fails, we default to an empty list (skipping all updates is safer than corrupting state)
-            let valid_uids: Vec<String> = redis
-                .con
-                .zrange(&index_key, 0, -1)
-                .await
-                .unwrap_or_default();
+            let valid_uids: Vec<String> = con.zrange(&index_key, 0, -1).await.unwrap_or_default();
 
             // Create a HashSet for fast O(1) lookups
             let valid_uid_set: HashSet<String> = valid_uids.into_iter().collect();
@@ -295,7 +317,8 @@ async fn scrape(cfg: &PrometheusConfig, client: &Client, mut redis: Redis) -> Re
 
             // Append Snapshot
             p.lpush_exists(&key, &snapshot).ignore();
-            p.ltrim(&key, -cfg.snapshots_to_keep as isize, -1).ignore();
+            p.ltrim(&key, 0, cfg.snapshots_to_keep as isize - 1)
+                .ignore();
             // p.expire(&key, ttl).ignore();
 
             // We send pod messages after pod_map loop
@@ -333,22 +356,24 @@ async fn scrape(cfg: &PrometheusConfig, client: &Client, mut redis: Redis) -> Re
 
                 // Append snapshots
                 p.lpush_exists(&metrics_key, &snapshot).ignore();
-                p.ltrim(&metrics_key, -cfg.snapshots_to_keep as isize, -1)
+                p.ltrim(&metrics_key, 0, cfg.snapshots_to_keep as isize - 1)
                     .ignore();
                 // p.expire(&metrics_key, ttl).ignore();
 
                 pod_messages.push(PodMetricUpdate { meta, snapshot });
             }
 
+            if let Ok(deployment_id) = Uuid::parse_str(&id) {
+                deployment_pods.insert(deployment_id, pod_messages.len());
+            }
+
             // Publish pod metrics update message to deployment page
             if !pod_messages.is_empty() {
                 let channel = ChannelNames::deployment_metrics(&id);
-                let message = ComputeEvent::PodMetricsUpdate {
+                let message = VersionedEvent::new(ComputeEvent::PodMetricsUpdate {
                     updates: pod_messages,
-                };
-                if let Ok(message) = serde_json::to_string(&message) {
-                    p.publish(channel, message).ignore();
-                }
+                });
+                messages.push((channel, message));
             }
 
             // We can use id cleanly after all referances
@@ -358,12 +383,10 @@ async fn scrape(cfg: &PrometheusConfig, client: &Client, mut redis: Redis) -> Re
         // Publish deployment metrics update message to project page
         if !deployment_messages.is_empty() {
             let channel = ChannelNames::project_metrics(&id);
-            let message = ComputeEvent::DeploymentMetricsUpdate {
+            let message = VersionedEvent::new(ComputeEvent::DeploymentMetricsUpdate {
                 updates: deployment_messages,
-            };
-            if let Ok(message) = serde_json::to_string(&message) {
-                p.publish(channel, message).ignore();
-            }
+            });
+            messages.push((channel, message));
         }
     }
 
@@ -371,9 +394,10 @@ async fn scrape(cfg: &PrometheusConfig, client: &Client, mut redis: Redis) -> Re
     if deployments_count > 0 {
         let start = std::time::Instant::now();
         // We use `turbofish` syntax instead `let _: ()`
-        p.query_async::<()>(&mut redis.con).await.map_err(|e| {
-            AppError::InternalServerError(format!("❌ Redis pipeline failed: {}", e))
-        })?;
+        p.query_async::<()>(&mut con).await?;
+        for (channel, message) in &messages {
+            message.publish(channel, &mut con).await?;
+        }
 
         debug!(
             projects_count = projects_count,
@@ -382,9 +406,103 @@ async fn scrape(cfg: &PrometheusConfig, client: &Client, mut redis: Redis) -> Re
             elapsed = start.elapsed().as_millis(),
             "✅ Deployments scraped"
         );
+
+        // Snapshots are written at this point, so the trailing window includes this scrape
+        if let Err(e) = check_alerts(cfg, pool, &mut con, &deployment_pods).await {
+            error!(error = %e, "❌ Failed to check deployment alerts");
+        }
     } else {
         debug!("⏸️ No deployment to scrape");
     }
 
     Ok(())
 }
+
+/// Compares the trailing average usage of every deployment with its alert thresholds.
+/// Repeated alerts are suppressed for one window using a `SETNX` + `EXPIRE` key.
+#[tracing::instrument("check_alerts", skip_all, err)]
+async fn check_alerts(
+    cfg: &PrometheusConfig,
+    pool: &PgPool,
+    con: &mut MultiplexedConnection,
+    deployment_pods: &HashMap<Uuid, usize>,
+) -> Result<(), AppError> {
+    let ids: Vec<Uuid> = deployment_pods.keys().copied().collect();
+    let rows = DeploymentRepository::get_alert_thresholds(&ids, pool).await?;
+
+    let now = chrono::Utc::now().timestamp();
+
+    for row in rows {
+        let pods = deployment_pods.get(&row.id).copied().unwrap_or_default();
+        if pods == 0 {
+            continue;
+        }
+
+        let threshold = row.alert_thresholds.0;
+        let window_secs = threshold.window_minutes * 60;
+        let count = (window_secs / cfg.scrape_interval_secs.max(1)).max(1);
+
+        let id = row.id.to_string();
+        let snapshots: Vec<MetricSnapshot> = redis::cmd("LRANGE")
+            .arg(CacheKeys::deployment_metrics(&id))
+            .arg(0)
+            .arg(count - 1)
+            .query_async(con)
+            .await?;
+
+        let window: Vec<&MetricSnapshot> = snapshots
+            .iter()
+            .filter(|s| s.ts >= now - window_secs)
+            .collect();
+        if window.is_empty() {
+            continue;
+        }
+
+        let n = window.len() as f64;
+        let cpu_percent = window.iter().map(|s| s.cpu).sum::<f64>()
+            / n
+            / (row.cpu_limit_millicores as f64 * pods as f64)
+            * 100.0;
+        let memory_percent = window.iter().map(|s| s.memory).sum::<f64>()
+            / n
+            / (row.memory_limit_mb as f64 * pods as f64)
+            * 100.0;
+
+        let checks = [
+            ("cpu", "CPU", cpu_percent, threshold.cpu_percent),
+            ("memory", "Memory", memory_percent, threshold.memory_percent),
+        ];
+
+        for (metric, label, usage, limit) in checks {
+            let Some(limit) = limit else {
+                continue;
+            };
+            if usage < limit {
+                continue;
+            }
+
+            let notified_key = CacheKeys::deployment_alert_notified(&id, metric);
+            let first_time: bool = con.set_nx(&notified_key, 1).await?;
+            if !first_time {
+                continue;
+            }
+            con.expire(&notified_key, window_secs).await?;
+
+            let message = ComputeEvent::DeploymentSystemMessage {
+                deployment_id: &id,
+                level: DeploymentEventLevel::Warning,
+                message: format!(
+                    "{} usage averaged {:.1}% of the limit over the last {} minutes (threshold {}%)",
+                    label, usage, threshold.window_minutes, limit
+                ),
+            };
+            VersionedEvent::new(message)
+                .publish(&ChannelNames::deployment_metrics(&id), con)
+                .await?;
+
+            info!(deployment_id = %id, metric = %metric, usage = %usage, "🚨 Deployment usage alert published");
+        }
+    }
+
+    Ok(())
+}
@@ -1,6 +1,7 @@
 use compute_core::configs::PrometheusConfig;
 use prometheus_http_query::Client;
 use tracing::info;
+use utility::retry::{RetryConfig, retry_with_backoff};
 pub mod implementations;
 use crate::error::AppError;
 
@@ -20,7 +21,11 @@ impl Prometheus {
 
         let client = Client::from(client, &cfg.url)?;
 
-        client.query("up").get().await?;
+        // Prometheus may still be starting up next to the worker
+        retry_with_backoff("Prometheus connection", &RetryConfig::default(), || {
+            client.query("up").get()
+        })
+        .await?;
         info!("✅ Successfully connected to Prometheus!");
 
         Ok(Self { client, cfg })
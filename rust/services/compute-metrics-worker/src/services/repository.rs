@@ -0,0 +1,144 @@
+use compute_core::{
+    models::{AlertThreshold, ScalingAction},
+    schemas::MetricSnapshot,
+};
+use sqlx::{FromRow, PgPool, types::Json};
+use tracing::instrument;
+use uuid::Uuid;
+
+/// Alert thresholds of a deployment together with the per-pod limits they are relative to
+#[derive(FromRow, Debug)]
+pub struct DeploymentAlertRow {
+    pub id: Uuid,
+    pub alert_thresholds: Json<AlertThreshold>,
+    pub cpu_limit_millicores: i32,
+    pub memory_limit_mb: i32,
+}
+
+/// 95th percentile CPU of one window, relative to the limits of all desired replicas
+#[derive(FromRow, Debug)]
+pub struct CpuWindowRow {
+    pub deployment_id: Uuid,
+    /// `0` is the most recent window
+    pub window: i32,
+    pub desired_replicas: i32,
+    pub cpu_percent: f64,
+}
+
+pub struct DeploymentRepository;
+
+impl DeploymentRepository {
+    #[instrument("deployment_repository.get_alert_thresholds", skip_all, fields(count = ids.len()), err)]
+    pub async fn get_alert_thresholds(
+        ids: &[Uuid],
+        pool: &PgPool,
+    ) -> Result<Vec<DeploymentAlertRow>, sqlx::Error> {
+        sqlx::query_as!(
+            DeploymentAlertRow,
+            r#"
+            SELECT
+                d.id,
+                d.alert_thresholds AS "alert_thresholds!: Json<AlertThreshold>",
+                p.cpu_millicores + COALESCE(d.addon_cpu_millicores, 0) AS "cpu_limit_millicores!",
+                p.memory_mb + COALESCE(d.addon_memory_mb, 0) AS "memory_limit_mb!"
+            FROM deployments d
+            JOIN presets p ON d.preset_id = p.id
+            WHERE d.id = ANY($1)
+            AND d.alert_thresholds IS NOT NULL
+            "#,
+            ids
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Snapshots already in the table are skipped, returns how many were new
+    #[instrument("deployment_repository.insert_metric_snapshots", skip_all, fields(deployment_id = %deployment_id, count = snapshots.len()), err)]
+    pub async fn insert_metric_snapshots(
+        deployment_id: &Uuid,
+        snapshots: &[MetricSnapshot],
+        pool: &PgPool,
+    ) -> Result<u64, sqlx::Error> {
+        let ts: Vec<i64> = snapshots.iter().map(|s| s.ts).collect();
+        let cpu: Vec<f64> = snapshots.iter().map(|s| s.cpu).collect();
+        let memory: Vec<f64> = snapshots.iter().map(|s| s.memory).collect();
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO deployment_metrics (deployment_id, ts, cpu, memory)
+            SELECT $1, to_timestamp(t.ts), t.cpu, t.memory
+            FROM UNNEST($2::BIGINT[], $3::FLOAT8[], $4::FLOAT8[]) AS t(ts, cpu, memory)
+            ON CONFLICT (deployment_id, ts) DO NOTHING
+            "#,
+            deployment_id,
+            &ts,
+            &cpu,
+            &memory
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Running deployments with at least an hour of persisted history, split into
+    /// `window_count` windows over the last hour
+    #[instrument("deployment_repository.get_cpu_windows", skip_all, err)]
+    pub async fn get_cpu_windows(
+        window_count: i32,
+        pool: &PgPool,
+    ) -> Result<Vec<CpuWindowRow>, sqlx::Error> {
+        sqlx::query_as!(
+            CpuWindowRow,
+            r#"
+            SELECT
+                m.deployment_id,
+                LEAST(FLOOR(EXTRACT(EPOCH FROM NOW() - m.ts) / (3600 / $1)), $1 - 1)::INT AS "window!",
+                d.desired_replicas,
+                PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY m.cpu)
+                    / ((p.cpu_millicores + COALESCE(d.addon_cpu_millicores, 0)) * GREATEST(d.desired_replicas, 1))
+                    * 100 AS "cpu_percent!"
+            FROM deployment_metrics m
+            JOIN deployments d ON m.deployment_id = d.id
+            JOIN presets p ON d.preset_id = p.id
+            WHERE m.ts > NOW() - INTERVAL '1 hour'
+            AND d.status = 'running'
+            AND EXISTS (
+                SELECT 1 FROM deployment_metrics o
+                WHERE o.deployment_id = m.deployment_id AND o.ts <= NOW() - INTERVAL '1 hour'
+            )
+            GROUP BY m.deployment_id, 2, d.desired_replicas, p.cpu_millicores, d.addon_cpu_millicores
+            "#,
+            window_count
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// `false` when the same action was already recommended within the last hour
+    #[instrument("deployment_repository.create_scaling_recommendation", skip_all, fields(deployment_id = %deployment_id), err)]
+    pub async fn create_scaling_recommendation(
+        deployment_id: &Uuid,
+        action: ScalingAction,
+        reason: &str,
+        pool: &PgPool,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO scaling_recommendations (deployment_id, action, reason)
+            SELECT $1, $2, $3
+            WHERE NOT EXISTS (
+                SELECT 1 FROM scaling_recommendations
+                WHERE deployment_id = $1 AND action = $2 AND created_at > NOW() - INTERVAL '1 hour'
+            )
+            "#,
+            deployment_id,
+            action as ScalingAction,
+            reason
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+}
@@ -0,0 +1,118 @@
+use std::{collections::HashMap, time::Duration};
+
+use compute_core::{
+    channel_names::ChannelNames,
+    event::{ComputeEvent, VersionedEvent},
+    models::ScalingAction,
+};
+use factory::factories::redis::Redis;
+use sqlx::PgPool;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{error::AppError, services::repository::DeploymentRepository};
+
+/// Runs as often as snapshots are persisted, more often would see the same history
+const RECOMMEND_INTERVAL_SECS: u64 = 300;
+/// The last hour is split into this many windows
+const WINDOW_COUNT: i32 = 5;
+/// Windows whose p95 CPU crosses this percent of the limit count as busy
+const SCALE_UP_CPU_PERCENT: f64 = 80.0;
+const SCALE_UP_MIN_BUSY_WINDOWS: usize = 3;
+/// Every window has to stay under this percent to recommend scaling down
+const SCALE_DOWN_CPU_PERCENT: f64 = 10.0;
+
+pub async fn start_scaling_recommender(pool: PgPool, redis: Redis) -> Result<(), AppError> {
+    info!(
+        "📐 Starting scaling recommender, interval: {}s",
+        RECOMMEND_INTERVAL_SECS
+    );
+
+    let mut interval = tokio::time::interval(Duration::from_secs(RECOMMEND_INTERVAL_SECS));
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = recommend(&pool, &redis).await {
+            error!("❌ Failed to recommend scaling: {}", e);
+        }
+    }
+}
+
+/// Reads the history persisted by the metrics persister, the Redis lists are too short for an hour
+#[tracing::instrument("recommend_scaling", skip_all, err)]
+async fn recommend(pool: &PgPool, redis: &Redis) -> Result<(), AppError> {
+    let rows = DeploymentRepository::get_cpu_windows(WINDOW_COUNT, pool).await?;
+
+    let mut deployments: HashMap<Uuid, (i32, Vec<f64>)> = HashMap::new();
+    for row in rows {
+        deployments
+            .entry(row.deployment_id)
+            .or_insert_with(|| (row.desired_replicas, Vec::new()))
+            .1
+            .push(row.cpu_percent);
+    }
+
+    let mut con = redis.get_connection().await?;
+    let mut recommended = 0;
+    for (deployment_id, (desired_replicas, windows)) in deployments {
+        // A gap in the history would let a single window decide
+        if windows.len() < WINDOW_COUNT as usize {
+            continue;
+        }
+
+        let busy = windows
+            .iter()
+            .filter(|cpu| **cpu > SCALE_UP_CPU_PERCENT)
+            .count();
+        let peak = windows.iter().copied().fold(0.0, f64::max);
+
+        let (action, reason) = if busy >= SCALE_UP_MIN_BUSY_WINDOWS {
+            (
+                ScalingAction::ScaleUp,
+                format!(
+                    "p95 CPU was above {}% of the limit in {} of the last {} windows",
+                    SCALE_UP_CPU_PERCENT, busy, WINDOW_COUNT
+                ),
+            )
+        } else if desired_replicas > 1 && peak < SCALE_DOWN_CPU_PERCENT {
+            (
+                ScalingAction::ScaleDown,
+                format!(
+                    "p95 CPU stayed below {}% of the limit for the last hour, peaking at {:.1}%",
+                    SCALE_DOWN_CPU_PERCENT, peak
+                ),
+            )
+        } else {
+            continue;
+        };
+
+        if !DeploymentRepository::create_scaling_recommendation(
+            &deployment_id,
+            action,
+            &reason,
+            pool,
+        )
+        .await?
+        {
+            continue;
+        }
+
+        let id = deployment_id.to_string();
+        let message = ComputeEvent::ScalingRecommendation {
+            deployment_id: &id,
+            action,
+            reason,
+        };
+        VersionedEvent::new(message)
+            .publish(&ChannelNames::deployment_events(&id), &mut con)
+            .await?;
+        recommended += 1;
+    }
+
+    if recommended > 0 {
+        info!("📐 Published {} scaling recommendations", recommended);
+    }
+
+    Ok(())
+}
@@ -5,6 +5,7 @@ use axum::{
     http::{HeaderName, HeaderValue, Method, header},
 };
 use http_common::{
+    body_limit::StreamingBodyLimitLayer,
     router::base_routes,
     trace_layer::{custom_make_span::CustomMakeSpan, custom_on_response::CustomOnResponse},
 };
@@ -44,7 +45,8 @@ pub async fn app(
 
     let app = axum::Router::new()
         .merge(base_routes(cargo_pkg_name, cargo_pkg_version))
-        .layer(DefaultBodyLimit::max(50 * 1024 * 1024))
+        .layer(DefaultBodyLimit::disable())
+        .layer(StreamingBodyLimitLayer::new(50 * 1024 * 1024))
         .layer(tracer_layer)
         .layer(cors);
 
@@ -2,8 +2,8 @@ use std::{net::SocketAddr, path::PathBuf};
 
 use config::{ConfigBuilder, ConfigError, Environment, File, builder::AsyncState};
 use factory::factories::{
-    amqp::AmqpConfig, database::DatabaseConfig, observability::ObservabilityConfig,
-    redis::RedisConfig,
+    amqp::AmqpConfig, database::DatabaseConfig, kafka::KafkaConfig, kubernetes::KubernetesConfig,
+    observability::ObservabilityConfig, redis::RedisConfig,
 };
 use serde::Deserialize;
 
@@ -18,8 +18,17 @@ pub struct Config {
     pub database: DatabaseConfig,
     pub redis: RedisConfig,
     pub amqp: AmqpConfig,
+    /// `compute.deployment_deleted` is published here once a deployment is torn down
+    pub kafka: KafkaConfig,
     pub kubernetes: KubernetesServiceConfig,
+    /// The inferred cluster alone when no regions are configured
+    #[serde(default)]
+    pub clusters: KubernetesConfig,
     pub vault: VaultServiceConfig,
+    /// How long in-flight deliveries get to finish on shutdown, 30 seconds when unset
+    pub shutdown_drain_timeout_secs: Option<u64>,
+    /// How often `job_queue` is polled for due jobs, 5 seconds when unset
+    pub job_poll_interval_secs: Option<u64>,
 }
 
 impl Config {
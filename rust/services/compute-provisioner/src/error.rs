@@ -1,3 +1,8 @@
+use axum::Json;
+use axum::http::StatusCode;
+use axum_core::response::{IntoResponse, Response};
+use compute_core::schemas::QuotaExceeded;
+use serde_json::json;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -23,7 +28,7 @@ pub enum AppError {
     #[error("Encountered an error trying to convert an infallible value: {0}")]
     FromRequestPartsError(#[from] std::convert::Infallible),
 
-    #[error("Serde json error")]
+    #[error("Serde json error: {0}")]
     SerdejsonError(#[from] serde_json::Error),
 
     #[error("External service error")]
@@ -34,7 +39,7 @@ pub enum AppError {
     },
     #[error("Service unavailable error")]
     ServiceUnavailable(String),
-    #[error("Internal server error")]
+    #[error("Internal server error: {0}")]
     InternalServerError(String),
 
     #[error("IO error, {0}")]
@@ -51,9 +56,23 @@ pub enum AppError {
 
     #[error("Lapin error, {0}")]
     LapinError(#[from] lapin::Error),
+    #[error("Amqp error: {0}")]
+    AmqpError(#[from] factory::factories::amqp::error::AmqpError),
 
-    #[error("Kube error")]
+    #[error("Kafka error: {0}")]
+    KafkaError(#[from] factory::factories::kafka::error::KafkaError),
+    #[error("Kafka client error: {0}")]
+    KafkaClientError(#[from] rdkafka::error::KafkaError),
+
+    #[error("Kubernetes API error: {0}")]
     KubeError(#[from] kube::Error),
+    #[error("Kubernetes error: {0}")]
+    KubernetesError(#[from] factory::factories::kubernetes::error::KubernetesError),
+    #[error(
+        "Kubernetes quota '{}' exceeded for {}, requested: {}, limit: {}",
+        .0.quota_name, .0.resource, .0.requested, .0.limit
+    )]
+    KubernetesQuotaExceeded(QuotaExceeded),
 
     #[error("Token creation error")]
     TokenCreationError,
@@ -106,3 +125,27 @@ pub enum AppError {
     #[error("HTTP request error: {0}")]
     Request(#[from] reqwest::Error),
 }
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let (status, error_message) = match self {
+            Self::KubernetesQuotaExceeded(quota) => {
+                return (StatusCode::PAYMENT_REQUIRED, Json(quota.response_body())).into_response();
+            }
+            Self::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            Self::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
+            Self::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
+            Self::ValidationError(msg) => (StatusCode::UNPROCESSABLE_ENTITY, msg),
+            Self::NotFoundError(msg) => (StatusCode::NOT_FOUND, msg),
+            Self::InternalServerError(msg) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Internal server error: {}", msg),
+            ),
+            e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        };
+
+        let body = Json(json!({"error": error_message}));
+
+        (status, body).into_response()
+    }
+}
@@ -1,3 +1,4 @@
+use compute_core::schemas::QuotaExceeded;
 use compute_core::services::event_emission_service::error::EventEmissionServiceError;
 
 use crate::error::AppError;
@@ -10,3 +11,9 @@ impl From<EventEmissionServiceError> for AppError {
         }
     }
 }
+
+impl AppError {
+    pub fn kubernetes_quota_exceeded(e: &kube::Error) -> Option<Self> {
+        QuotaExceeded::from_kube_error(e).map(AppError::KubernetesQuotaExceeded)
+    }
+}
@@ -7,15 +7,17 @@ pub mod services;
 use core::panic;
 use std::path::PathBuf;
 use std::result::Result::Ok;
+use std::time::Duration;
 use std::{env, net::SocketAddr};
 
 use config::Config;
 use factory::factories::{
-    amqp::Amqp, database::Database, kubernetes::Kubernetes, observability::Observability,
-    redis::Redis,
+    amqp::Amqp, database::Database, kafka::Kafka, kubernetes::Kubernetes,
+    observability::Observability, redis::Redis,
 };
 
 use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info};
 use utility::shutdown_signal::shutdown_signal;
 
@@ -23,11 +25,15 @@ use crate::{
     error::AppError,
     services::{
         consumer::{ConsumerContext, start_consumer},
+        job_runner::{JobRunnerContext, start_job_runner},
         kubernetes_service::KubernetesService,
         vault_service::VaultService,
     },
 };
 
+const DEFAULT_SHUTDOWN_DRAIN_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_JOB_POLL_INTERVAL_SECS: u64 = 5;
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     rustls::crypto::ring::default_provider()
@@ -61,34 +67,62 @@ async fn main() -> anyhow::Result<()> {
     // Initialize services
     // let rustls_config = build_rustls_config(&cfg)?;
     let database = Database::new(&cfg.database).await;
-    let redis = Redis::new(&cfg.redis).await;
-    let kubernetes = Kubernetes::new().await?;
+    let pool_metrics = database.clone().report_metrics();
+    let redis = Redis::new(&cfg.redis).await?;
+    let kubernetes = Kubernetes::new(&cfg.clusters).await?;
     let amqp = Amqp::new(&cfg.amqp).await;
-    // let kafka = Kafka::new(&cfg, "compute-service-group")?;
+    let producer = Kafka::producer(&cfg.kafka)?;
     // let http_client = reqwest::ClientBuilder::new()
     //     .redirect(reqwest::redirect::Policy::none())
     //     .build()?;
     let vault_service = VaultService::init(&cfg.vault).await?;
 
     let k8s = KubernetesService {
-        client: kubernetes.client,
+        client: kubernetes.client.clone(),
+        clusters: kubernetes,
         cfg: cfg.kubernetes,
         vault_service,
+        dry_run: false,
     };
 
-    k8s.preflight().await?;
+    for region in k8s.clusters.regions.keys() {
+        k8s.in_region(Some(region))?.preflight().await?;
+    }
+
+    let job_runner_ctx = JobRunnerContext {
+        database: database.clone(),
+        redis: redis.clone(),
+        k8s: k8s.clone(),
+        poll_interval: Duration::from_secs(
+            cfg.job_poll_interval_secs
+                .unwrap_or(DEFAULT_JOB_POLL_INTERVAL_SECS),
+        ),
+    };
 
     let ctx = ConsumerContext {
         database,
         redis,
         amqp,
+        producer,
         k8s,
+        drain_timeout: Duration::from_secs(
+            cfg.shutdown_drain_timeout_secs
+                .unwrap_or(DEFAULT_SHUTDOWN_DRAIN_TIMEOUT_SECS),
+        ),
     };
 
     let mut set = JoinSet::new();
 
+    // The consumer is drained rather than aborted, see the shutdown branch below
+    let shutdown = CancellationToken::new();
+    let mut consumer = tokio::spawn(start_consumer(ctx, shutdown.clone()));
+
     // Spawn background tasks
-    set.spawn(start_consumer(ctx));
+    set.spawn(async move {
+        pool_metrics.await;
+        Ok(())
+    });
+    set.spawn(start_job_runner(job_runner_ctx, shutdown.clone()));
     set.spawn(start_health_server(
         cargo_pkg_name,
         cargo_pkg_version,
@@ -101,6 +135,18 @@ async fn main() -> anyhow::Result<()> {
     tokio::select! {
         _ = shutdown_signal() => {
             info!("🛑 Shutdown signal received");
+            shutdown.cancel();
+            if let Err(e) = consumer.await {
+                error!("Consumer panic while draining: {}", e);
+            }
+            set.shutdown().await;
+        }
+        result = &mut consumer => {
+            match result {
+                Ok(Ok(())) => error!("The consumer exited unexpectedly!"),
+                Ok(Err(e)) => error!("Consumer failed: {}", e),
+                Err(e) => error!("Consumer panic: {}", e),
+            }
             set.shutdown().await;
         }
         Some(result) = set.join_next() => {
@@ -110,6 +156,8 @@ async fn main() -> anyhow::Result<()> {
                 Err(e) => error!("Task panic: {}", e),
             }
             // Optional: trigger shutdown if a critical task dies
+            shutdown.cancel();
+            let _ = consumer.await;
             set.shutdown().await;
         }
     }
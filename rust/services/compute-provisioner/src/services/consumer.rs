@@ -1,36 +1,81 @@
+use chrono::Utc;
+use compute_core::cache_keys::CacheKeys;
+use compute_core::channel_names::ChannelNames;
+use compute_core::models::{DeploymentEventLevel, DeploymentEventType, DeploymentStatus};
 use compute_core::schemas::{
-    CreateDeploymentMessage, DeleteDeploymentMessage, UpdateDeploymentMessage,
+    CreateDeploymentMessage, DeleteDeploymentMessage, DeleteUserMessage, DeploymentDeletedEvent,
+    DeploymentDiffMessage, ResumeDeploymentMessage, RotateRegistryCredentialsMessage,
+    RotateSecretsMessage, SuspendDeploymentMessage, UpdateDeploymentMessage,
+};
+use compute_core::services::event_emission_service::{
+    DeploymentEventEmitter, DeploymentEventEmitterInput,
 };
 use factory::factories::{
-    amqp::{Amqp, AmqpPropagator},
+    amqp::{Amqp, AmqpPropagator, DEAD_LETTER_QUEUE},
     database::Database,
     redis::Redis,
 };
 use futures::StreamExt;
 use lapin::{
     Consumer,
-    options::{BasicAckOptions, BasicConsumeOptions, BasicNackOptions, BasicRejectOptions},
-    types::{AMQPValue, FieldTable},
+    message::Delivery,
+    options::{
+        BasicAckOptions, BasicCancelOptions, BasicConsumeOptions, BasicNackOptions,
+        BasicPublishOptions, BasicRejectOptions,
+    },
+    types::{AMQPValue, FieldTable, ShortString},
 };
 
-use redis::aio::MultiplexedConnection;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use redis::{AsyncTypedCommands, aio::MultiplexedConnection};
+use serde::Deserialize;
 use sqlx::PgPool;
+use std::time::Duration;
 use tokio::task::JoinSet;
-use tracing::{Instrument, debug, error, info, info_span};
+use tokio::time::Instant;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+use tracing::{Instrument, Span, debug, error, field::Empty, info, info_span, warn};
 use tracing_opentelemetry::OpenTelemetrySpanExt;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    services::kubernetes_service::{KubernetesService, implementations::api_server_error},
+};
+
+/// billing-api finalizes the deployment's billing records when it sees this
+const DEPLOYMENT_DELETED_TOPIC: &str = "compute.deployment_deleted";
 
-use crate::{error::AppError, services::kubernetes_service::KubernetesService};
+/// Failed deliveries are republished with this header rather than requeued, a plain requeue
+/// can't carry a count
+const RETRY_COUNT_HEADER: &str = "x-retry-count";
+const MAX_RETRIES: i64 = 3;
+
+/// Just enough of any `compute.*` message to tell which deployment it was about
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeadLetteredMessage {
+    project_id: Uuid,
+    deployment_id: Uuid,
+}
 
 #[derive(Clone)]
 pub struct ConsumerContext {
     pub database: Database,
     pub redis: Redis,
     pub amqp: Amqp,
+    pub producer: FutureProducer,
     pub k8s: KubernetesService,
+    pub drain_timeout: Duration,
 }
 
-pub async fn start_consumer(ctx: ConsumerContext) -> Result<(), AppError> {
-    let channel = ctx.amqp.channel().await;
+/// Runs until a consumer task dies or `shutdown` is cancelled, in which case the consumers are
+/// cancelled and in-flight deliveries get up to `ctx.drain_timeout` to finish
+pub async fn start_consumer(
+    ctx: ConsumerContext,
+    shutdown: CancellationToken,
+) -> Result<(), AppError> {
+    let channel = ctx.amqp.channel().await?;
 
     // Start consumers
     let create_consumer = channel
@@ -60,6 +105,86 @@ pub async fn start_consumer(ctx: ConsumerContext) -> Result<(), AppError> {
         )
         .await?;
 
+    let delete_user_consumer = channel
+        .basic_consume(
+            "compute.delete_user",
+            "user_deleter",
+            BasicConsumeOptions::default(),
+            FieldTable::default(),
+        )
+        .await?;
+
+    let suspend_consumer = channel
+        .basic_consume(
+            "compute.suspend",
+            "suspender",
+            BasicConsumeOptions::default(),
+            FieldTable::default(),
+        )
+        .await?;
+
+    let resume_consumer = channel
+        .basic_consume(
+            "compute.resume",
+            "resumer",
+            BasicConsumeOptions::default(),
+            FieldTable::default(),
+        )
+        .await?;
+
+    let registry_credentials_consumer = channel
+        .basic_consume(
+            "compute.registry_credentials",
+            "registry_credentials_rotator",
+            BasicConsumeOptions::default(),
+            FieldTable::default(),
+        )
+        .await?;
+
+    let secrets_consumer = channel
+        .basic_consume(
+            "compute.secrets",
+            "secrets_rotator",
+            BasicConsumeOptions::default(),
+            FieldTable::default(),
+        )
+        .await?;
+
+    let diff_consumer = channel
+        .basic_consume(
+            "compute.diff",
+            "differ",
+            BasicConsumeOptions::default(),
+            FieldTable::default(),
+        )
+        .await?;
+
+    let dead_letter_consumer = channel
+        .basic_consume(
+            DEAD_LETTER_QUEUE,
+            "dead_letter_reporter",
+            BasicConsumeOptions::default(),
+            FieldTable::default(),
+        )
+        .await?;
+
+    let consumer_tags = [
+        &create_consumer,
+        &update_consumer,
+        &delete_consumer,
+        &delete_user_consumer,
+        &suspend_consumer,
+        &resume_consumer,
+        &registry_credentials_consumer,
+        &secrets_consumer,
+        &diff_consumer,
+        &dead_letter_consumer,
+    ]
+    .map(|consumer| consumer.tag());
+
+    // Every delivery is handled in its own task, tracked so shutdown can wait for them
+    let tracker = TaskTracker::new();
+
     // Create a JoinSet to hold our tasks
     let mut set = JoinSet::new();
 
@@ -67,28 +192,109 @@ pub async fn start_consumer(ctx: ConsumerContext) -> Result<(), AppError> {
         ctx.database.pool.clone(),
         ctx.redis.con.clone(),
         ctx.k8s.clone(),
+        ctx.amqp.clone(),
+        tracker.clone(),
         create_consumer,
     ));
     set.spawn(handle_update_messages(
         ctx.database.pool.clone(),
         ctx.redis.con.clone(),
         ctx.k8s.clone(),
+        tracker.clone(),
         update_consumer,
     ));
-    set.spawn(handle_delete_messages(ctx.k8s.clone(), delete_consumer));
+    set.spawn(handle_delete_messages(
+        ctx.redis.con.clone(),
+        ctx.producer.clone(),
+        ctx.k8s.clone(),
+        tracker.clone(),
+        delete_consumer,
+    ));
+    set.spawn(handle_delete_user_messages(
+        ctx.redis.con.clone(),
+        ctx.k8s.clone(),
+        tracker.clone(),
+        delete_user_consumer,
+    ));
+    set.spawn(handle_suspend_messages(
+        ctx.database.pool.clone(),
+        ctx.redis.con.clone(),
+        ctx.k8s.clone(),
+        tracker.clone(),
+        suspend_consumer,
+    ));
+    set.spawn(handle_resume_messages(
+        ctx.database.pool.clone(),
+        ctx.redis.con.clone(),
+        ctx.k8s.clone(),
+        tracker.clone(),
+        resume_consumer,
+    ));
+    set.spawn(handle_registry_credentials_messages(
+        ctx.database.pool.clone(),
+        ctx.redis.con.clone(),
+        ctx.k8s.clone(),
+        tracker.clone(),
+        registry_credentials_consumer,
+    ));
+    set.spawn(handle_secrets_messages(
+        ctx.database.pool.clone(),
+        ctx.redis.con.clone(),
+        ctx.k8s.clone(),
+        tracker.clone(),
+        secrets_consumer,
+    ));
+    set.spawn(handle_diff_messages(
+        ctx.database.pool.clone(),
+        ctx.redis.con.clone(),
+        ctx.k8s.clone(),
+        tracker.clone(),
+        diff_consumer,
+    ));
+    set.spawn(handle_dead_letter_messages(
+        ctx.database.pool.clone(),
+        ctx.redis.con.clone(),
+        tracker.clone(),
+        dead_letter_consumer,
+    ));
 
     info!("✅ RabbitMQ consumers started");
 
-    // Wait for ANY task in the set to exit
-    // If one of them crashes or finishes, this loop will catch it.
-    while let Some(res) = set.join_next().await {
-        match res {
-            Ok(_) => error!("A consumer task finished unexpectedly!"),
-            Err(e) => error!("A consumer task panicked: {}", e),
+    tokio::select! {
+        _ = shutdown.cancelled() => {
+            info!("🛑 Cancelling consumers, draining in-flight messages");
+            // Consumer streams end once RabbitMQ confirms the cancel, nothing new is delivered
+            for tag in &consumer_tags {
+                if let Err(e) = channel
+                    .basic_cancel(tag.as_str(), BasicCancelOptions::default())
+                    .await
+                {
+                    error!(consumer_tag = %tag, "❌ Failed to cancel consumer: {}", e);
+                }
+            }
+
+            // Handlers ack or nack their delivery themselves before their task finishes
+            tracker.close();
+            if tokio::time::timeout(ctx.drain_timeout, tracker.wait())
+                .await
+                .is_err()
+            {
+                warn!(
+                    "⚠️ {} messages still in flight after {}s, RabbitMQ redelivers them once the channel closes",
+                    tracker.len(),
+                    ctx.drain_timeout.as_secs()
+                );
+            } else {
+                info!("✅ In-flight messages drained");
+            }
+        }
+        // If one of them crashes or finishes, this catches it
+        Some(res) = set.join_next() => {
+            match res {
+                Ok(_) => error!("A consumer task finished unexpectedly!"),
+                Err(e) => error!("A consumer task panicked: {}", e),
+            }
         }
-        // Optional: We can break here to crash the whole service
-        // or we could add logic to restart just the specific task.
-        break;
     }
 
     // Clean up the rest
@@ -98,16 +304,109 @@ pub async fn start_consumer(ctx: ConsumerContext) -> Result<(), AppError> {
 }
 
 pub fn get_retry_count(headers: &FieldTable) -> i64 {
-    // x-death is an array of tables
-    if let Some(AMQPValue::FieldArray(x_death_array)) = headers.inner().get("x-death") {
-        // We look at the first entry (most recent event)
-        if let Some(AMQPValue::FieldTable(table)) = x_death_array.as_slice().first() {
-            if let Some(AMQPValue::LongLongInt(count)) = table.inner().get("count") {
-                return *count;
-            }
+    match headers.inner().get(RETRY_COUNT_HEADER) {
+        Some(AMQPValue::LongLongInt(count)) => *count,
+        Some(AMQPValue::LongInt(count)) => *count as i64,
+        _ => 0,
+    }
+}
+
+/// Publishes a copy carrying the next retry count and acks the original. Once the retries are
+/// used up the delivery is nacked without requeue and RabbitMQ moves it to the dead letter exchange.
+async fn retry_or_dead_letter(
+    amqp: &Amqp,
+    delivery: &Delivery,
+    mut headers: FieldTable,
+    retry_count: i64,
+) -> Result<(), AppError> {
+    if retry_count >= MAX_RETRIES {
+        delivery
+            .nack(BasicNackOptions {
+                requeue: false,
+                multiple: false,
+            })
+            .await?;
+        return Ok(());
+    }
+
+    // 1s, 2s, 4s, a cluster that is briefly unreachable gets a chance to come back
+    tokio::time::sleep(Duration::from_secs(1 << retry_count)).await;
+
+    headers.insert(
+        RETRY_COUNT_HEADER.into(),
+        AMQPValue::LongLongInt(retry_count + 1),
+    );
+    amqp.channel()
+        .await?
+        .basic_publish(
+            delivery.exchange.as_str(),
+            delivery.routing_key.as_str(),
+            BasicPublishOptions::default(),
+            &delivery.data,
+            delivery.properties.clone().with_headers(headers),
+        )
+        .await?
+        .await?;
+
+    delivery.ack(BasicAckOptions::default()).await?;
+    Ok(())
+}
+
+/// `queue` and `reason` of the most recent `x-death` entry RabbitMQ added while dead-lettering
+fn dead_letter_origin(headers: &FieldTable) -> (Option<String>, Option<String>) {
+    let Some(AMQPValue::FieldArray(x_death)) = headers.inner().get("x-death") else {
+        return (None, None);
+    };
+    let Some(AMQPValue::FieldTable(entry)) = x_death.as_slice().first() else {
+        return (None, None);
+    };
+
+    let field = |name: &str| match entry.inner().get(name) {
+        Some(AMQPValue::LongString(value)) => Some(value.to_string()),
+        _ => None,
+    };
+    (field("queue"), field("reason"))
+}
+
+/// Span a delivery is processed in, continuing the trace of whoever published it
+fn consume_span(
+    delivery: &Delivery,
+    headers: &FieldTable,
+    queue: &ShortString,
+    consumer_tag: &ShortString,
+) -> Span {
+    let span = info_span!(
+        "amqp.consume",
+        routing_key = %delivery.routing_key,
+        queue = %queue,
+        consumer_tag = %consumer_tag,
+        delivery_tag = delivery.delivery_tag,
+        retry_count = get_retry_count(headers),
+        deployment_id = Empty,
+        duration_ms = Empty,
+        outcome = Empty,
+        otel.status_code = Empty,
+        otel.status_description = Empty,
+    );
+    let _ = span.set_parent(AmqpPropagator::extract_context(headers));
+    span
+}
+
+/// Must be called from within the consume span
+fn record_outcome(started: Instant, result: Result<(), String>) {
+    let span = Span::current();
+    span.record("duration_ms", started.elapsed().as_millis() as u64);
+    match result {
+        Ok(()) => {
+            span.record("outcome", "ok");
+            span.record("otel.status_code", "OK");
+        }
+        Err(e) => {
+            span.record("outcome", "error");
+            span.record("otel.status_code", "ERROR");
+            span.record("otel.status_description", e.as_str());
         }
     }
-    0
 }
 
 #[tracing::instrument(name = "consumer.handle_create_messages", skip_all)]
@@ -115,10 +414,15 @@ async fn handle_create_messages(
     pool: PgPool,
     con: MultiplexedConnection,
     k8s: KubernetesService,
+    amqp: Amqp,
+    tracker: TaskTracker,
     mut consumer: Consumer,
 ) {
     info!("🎯 Create consumer started");
 
+    let queue = consumer.queue();
+    let consumer_tag = consumer.tag();
+
     while let Some(delivery) = consumer.next().await {
         let delivery = match delivery {
             Ok(d) => d,
@@ -128,14 +432,12 @@ async fn handle_create_messages(
             }
         };
 
-        // Extract Tracing Context
         let headers = delivery
             .properties
             .headers()
             .as_ref()
             .cloned()
             .unwrap_or_default();
-        let parent_cx = AmqpPropagator::extract_context(&headers);
 
         // Extract Retry Count
         let retry_count = get_retry_count(&headers);
@@ -144,42 +446,72 @@ async fn handle_create_messages(
         let pool = pool.clone();
         let con = con.clone();
         let k8s = k8s.clone();
-        let span = info_span!("consumer.handle_create_messages", retry_count = retry_count);
-        let _ = span.set_parent(parent_cx);
+        let amqp = amqp.clone();
+        let span = consume_span(&delivery, &headers, &queue, &consumer_tag);
 
-        tokio::spawn(
+        tracker.spawn(
             async move {
+                let started = Instant::now();
+
                 if retry_count > 3 {
                     error!("❌ Max retries reached for create deployment. Dropping message."); 
                     if let Err(e) = delivery.ack(BasicAckOptions::default()).await {
                         error!("❌ Failed to ack for create deployment for max retries: {}", e);
                     }
+                    record_outcome(started, Err("max retries reached".into()));
                     return;
                 }
 
                 match serde_json::from_slice::<CreateDeploymentMessage>(&delivery.data) {
                     Ok(msg) => {
+                        Span::current().record("deployment_id", tracing::field::display(&msg.deployment_id));
                         debug!(deployment_id = %msg.deployment_id, "🎯 Create deployment request received");
 
-                        match k8s.create(pool, con, msg.clone() ).await {
+                        // Never retried, the API stops waiting for the result long before a retry would land
+                        if msg.dry_run {
+                            let result = k8s.dry_run(msg.clone()).await;
+                            let channel = ChannelNames::dry_run(&msg.deployment_id.to_string());
+                            let mut con = con;
+                            let published = match serde_json::to_string(&result) {
+                                Ok(payload) => con.publish(channel, payload).await.map(|_| ()).map_err(|e| e.to_string()),
+                                Err(e) => Err(e.to_string()),
+                            };
+                            if let Err(e) = &published {
+                                error!(request_id = %msg.deployment_id, "❌ Failed to publish dry run result: {}", e);
+                            }
+                            record_outcome(started, published);
+                            if let Err(e) = delivery.ack(BasicAckOptions::default()).await {
+                                error!(request_id = %msg.deployment_id, "❌ Failed to ack for dry run message: {}", e);
+                            }
+                            return;
+                        }
+
+                        let result = match k8s.in_region(msg.region.as_deref()) {
+                            Ok(k8s) => k8s.create(pool, con, msg.clone()).await,
+                            Err(e) => Err(e),
+                        };
+                        match result {
                             Ok(_) => {
+                                record_outcome(started, Ok(()));
                                 info!(deployment_id = %msg.deployment_id, "✅ Deployment created");
                                 if let Err(e) = delivery.ack(BasicAckOptions::default()).await {
                                     error!(deployment_id = %msg.deployment_id, "❌ Failed to ack for create message: {}", e);
                                 }
                             }
                             Err(e) => {
+                                record_outcome(started, Err(e.to_string()));
                                 error!(deployment_id = %msg.deployment_id,
                                     "❌ Failed to create deployment: {}", e
                                 );
 
-                                if let Err(e) = delivery.nack(BasicNackOptions {requeue: false, multiple: false}).await {
-                                    error!(deployment_id = %msg.deployment_id, "❌ Failed to nack for create deployment: {}", e);
+                                if let Err(e) = retry_or_dead_letter(&amqp, &delivery, headers, retry_count).await {
+                                    error!(deployment_id = %msg.deployment_id, "❌ Failed to retry create deployment: {}", e);
                                 }
                             }
                         }
                     }
                     Err(e) => {
+                        record_outcome(started, Err(e.to_string()));
                         error!("❌ Failed to parse CreateDeploymentMessage: {}", e);
                         if let Err(e) = delivery.reject(BasicRejectOptions { requeue: false }).await {
                             error!("❌ Failed to reject for create deployment: {}", e);
@@ -197,10 +529,14 @@ async fn handle_update_messages(
     pool: PgPool,
     con: MultiplexedConnection,
     k8s: KubernetesService,
+    tracker: TaskTracker,
     mut consumer: Consumer,
 ) {
     info!("📏 update consumer started");
 
+    let queue = consumer.queue();
+    let consumer_tag = consumer.tag();
+
     while let Some(delivery) = consumer.next().await {
         let delivery = match delivery {
             Ok(d) => d,
@@ -210,14 +546,12 @@ async fn handle_update_messages(
             }
         };
 
-        // Extract Tracing Context
         let headers = delivery
             .properties
             .headers()
             .as_ref()
             .cloned()
             .unwrap_or_default();
-        let parent_cx = AmqpPropagator::extract_context(&headers);
 
         // Extract Retry Count
         let retry_count = get_retry_count(&headers);
@@ -226,31 +560,40 @@ async fn handle_update_messages(
         let pool = pool.clone();
         let con = con.clone();
         let k8s = k8s.clone();
-        let span = info_span!("consumer.handle_update_messages", retry_count = retry_count);
-        let _ = span.set_parent(parent_cx);
+        let span = consume_span(&delivery, &headers, &queue, &consumer_tag);
 
-        tokio::spawn(
+        tracker.spawn(
             async move {
+                let started = Instant::now();
+
                 if retry_count > 3 {
                     error!("❌ Max retries reached for update deployment. Dropping message."); 
                     if let Err(e) = delivery.ack(BasicAckOptions::default()).await {
                         error!("❌ Failed to ack for update deployment for max retries: {}", e);
                     }
+                    record_outcome(started, Err("max retries reached".into()));
                     return;
                 }
 
                 match serde_json::from_slice::<UpdateDeploymentMessage>(&delivery.data) {
                     Ok(msg) => {
+                        Span::current().record("deployment_id", tracing::field::display(&msg.deployment_id));
                         debug!(deployment_id = %msg.deployment_id, "📏 Update deployment request received");
 
-                        match k8s.update(pool, con, msg.clone()).await {
+                        let result = match k8s.for_deployment(&msg.deployment_id, &pool).await {
+                            Ok(k8s) => k8s.update(pool, con, msg.clone()).await,
+                            Err(e) => Err(e),
+                        };
+                        match result {
                             Ok(_) => {
+                                record_outcome(started, Ok(()));
                                 info!(deployment_id = %msg.deployment_id, "📏 Deployment updated");
                                 if let Err(e) = delivery.ack(BasicAckOptions::default()).await {
                                     error!(deployment_id = %msg.deployment_id, "❌ Failed to ack for update deployment: {}", e);
                                 }
                             }
                             Err(e) => {
+                                record_outcome(started, Err(e.to_string()));
                                 error!(deployment_id = %msg.deployment_id, "❌ Failed to update deployment: {}", e);
 
                                 if let Err(e) = delivery.nack(BasicNackOptions {requeue: false, multiple: false}).await {
@@ -260,6 +603,7 @@ async fn handle_update_messages(
                         }
                     }
                     Err(e) => {
+                        record_outcome(started, Err(e.to_string()));
                         error!("❌ Failed to parse updateDeploymentMessage: {}", e);
                         if let Err(e) = delivery.reject(BasicRejectOptions { requeue: false }).await {
                             error!( "❌ Failed to reject for update deployment: {}", e);
@@ -273,9 +617,18 @@ async fn handle_update_messages(
 }
 
 #[tracing::instrument(name = "consumer.handle_delete_messages", skip_all)]
-async fn handle_delete_messages(k8s: KubernetesService, mut consumer: Consumer) {
+async fn handle_delete_messages(
+    con: MultiplexedConnection,
+    producer: FutureProducer,
+    k8s: KubernetesService,
+    tracker: TaskTracker,
+    mut consumer: Consumer,
+) {
     info!("🗑️ Delete consumer started");
 
+    let queue = consumer.queue();
+    let consumer_tag = consumer.tag();
+
     while let Some(delivery) = consumer.next().await {
         let delivery = match delivery {
             Ok(d) => d,
@@ -285,45 +638,56 @@ async fn handle_delete_messages(k8s: KubernetesService, mut consumer: Consumer)
             }
         };
 
-        // Extract Tracing Context
         let headers = delivery
             .properties
             .headers()
             .as_ref()
             .cloned()
             .unwrap_or_default();
-        let parent_cx = AmqpPropagator::extract_context(&headers);
 
         // Extract Retry Count
         let retry_count = get_retry_count(&headers);
 
         // Clone Service for the async block
+        let mut con = con.clone();
+        let producer = producer.clone();
         let k8s = k8s.clone();
-        let span = info_span!("consumer.handle_delete_messages", retry_count = retry_count);
-        let _ = span.set_parent(parent_cx);
+        let span = consume_span(&delivery, &headers, &queue, &consumer_tag);
 
-        tokio::spawn(
+        tracker.spawn(
             async move {
+                let started = Instant::now();
+
                 if retry_count > 3 {
                     error!("❌ Max retries reached for delete deployment. Dropping message.");  
                     if let Err(e) = delivery.ack(BasicAckOptions::default()).await {
                         error!("❌ Failed to ack for delete deployment for max retries: {}", e);
                     }
+                    record_outcome(started, Err("max retries reached".into()));
                     return;
                 }
 
                 match serde_json::from_slice::<DeleteDeploymentMessage>(&delivery.data) {
                     Ok(msg) => {
+                        Span::current().record("deployment_id", tracing::field::display(&msg.deployment_id));
                         debug!(deployment_id = %msg.deployment_id, "🗑️ Delete deployment request received");
 
-                        match k8s.delete(  msg.clone()).await {
+                        let result = match k8s.in_region(msg.region.as_deref()) {
+                            Ok(k8s) => k8s.delete(msg.clone()).await,
+                            Err(e) => Err(e),
+                        };
+                        match result {
                             Ok(_) => {
+                                record_outcome(started, Ok(()));
                                 info!(deployment_id = %msg.deployment_id, "🗑️ Deployment created");
+                                count_down_user_deletion(&mut con, &msg.user_id).await;
+                                publish_deployment_deleted(&producer, &msg).await;
                                 if let Err(e) = delivery.ack(BasicAckOptions::default()).await {
                                     error!(deployment_id = %msg.deployment_id, "❌ Failed to ack for delete deployment: {}", e);
                                 }
                             }
                             Err(e) => {
+                                record_outcome(started, Err(e.to_string()));
                                 error!(deployment_id = %msg.deployment_id, "❌ Failed to delete deployment: {}", e);
 
                                 if let Err(e) = delivery.nack(BasicNackOptions {requeue: false, multiple: false}).await {
@@ -333,6 +697,7 @@ async fn handle_delete_messages(k8s: KubernetesService, mut consumer: Consumer)
                         }
                     }
                     Err(e) => {
+                        record_outcome(started, Err(e.to_string()));
                         error!("❌ Failed to parse DeleteDeploymentMessage: {}", e);
                         if let Err(e) = delivery.reject(BasicRejectOptions { requeue: false }).await {
                             error!("❌ Failed to reject for delete deployment: {}", e);
@@ -344,3 +709,688 @@ async fn handle_delete_messages(k8s: KubernetesService, mut consumer: Consumer)
         );
     }
 }
+
+#[tracing::instrument(name = "consumer.handle_delete_user_messages", skip_all)]
+async fn handle_delete_user_messages(
+    con: MultiplexedConnection,
+    k8s: KubernetesService,
+    tracker: TaskTracker,
+    mut consumer: Consumer,
+) {
+    info!("🗑️ Delete user consumer started");
+
+    let queue = consumer.queue();
+    let consumer_tag = consumer.tag();
+
+    while let Some(delivery) = consumer.next().await {
+        let delivery = match delivery {
+            Ok(d) => d,
+            Err(e) => {
+                error!("❌ Consumer connection error: {}", e);
+                continue;
+            }
+        };
+
+        let headers = delivery
+            .properties
+            .headers()
+            .as_ref()
+            .cloned()
+            .unwrap_or_default();
+
+        // Extract Retry Count
+        let retry_count = get_retry_count(&headers);
+
+        // Clone Service for the async block
+        let mut con = con.clone();
+        let k8s = k8s.clone();
+        let span = consume_span(&delivery, &headers, &queue, &consumer_tag);
+
+        tracker.spawn(
+            async move {
+                let started = Instant::now();
+
+                if retry_count > 3 {
+                    error!("❌ Max retries reached for delete user. Dropping message.");
+                    if let Err(e) = delivery.ack(BasicAckOptions::default()).await {
+                        error!("❌ Failed to ack for delete user for max retries: {}", e);
+                    }
+                    record_outcome(started, Err("max retries reached".into()));
+                    return;
+                }
+
+                match serde_json::from_slice::<DeleteUserMessage>(&delivery.data) {
+                    Ok(msg) => {
+                        debug!(user_id = %msg.user_id, "🗑️ Delete user request received");
+
+                        match k8s.delete_user(msg.clone()).await {
+                            Ok(_) => {
+                                record_outcome(started, Ok(()));
+                                info!(user_id = %msg.user_id, "🗑️ User resources deleted");
+                                count_down_user_deletion(&mut con, &msg.user_id).await;
+                                if let Err(e) = delivery.ack(BasicAckOptions::default()).await {
+                                    error!(user_id = %msg.user_id, "❌ Failed to ack for delete user: {}", e);
+                                }
+                            }
+                            Err(e) => {
+                                record_outcome(started, Err(e.to_string()));
+                                error!(user_id = %msg.user_id, "❌ Failed to delete user resources: {}", e);
+
+                                if let Err(e) = delivery.nack(BasicNackOptions {requeue: false, multiple: false}).await {
+                                    error!(user_id = %msg.user_id, "❌ Failed to nack for delete user: {}", e);
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        record_outcome(started, Err(e.to_string()));
+                        error!("❌ Failed to parse DeleteUserMessage: {}", e);
+                        if let Err(e) = delivery.reject(BasicRejectOptions { requeue: false }).await {
+                            error!("❌ Failed to reject for delete user: {}", e);
+                        }
+                    }
+                }
+            }
+            .instrument(span),
+        );
+    }
+}
+
+/// An account deletion in users-api waits for this counter to reach zero, deliveries of
+/// deletions nobody waits on find no key and leave it alone
+async fn count_down_user_deletion(con: &mut MultiplexedConnection, user_id: &Uuid) {
+    let key = CacheKeys::user_deletion_pending(&user_id.to_string());
+    let result = redis::Script::new(
+        "if redis.call('EXISTS', KEYS[1]) == 1 then return redis.call('DECR', KEYS[1]) end return nil",
+    )
+    .key(&key)
+    .invoke_async::<Option<i64>>(con)
+    .await;
+
+    if let Err(e) = result {
+        warn!(user_id = %user_id, "⚠️ Failed to count down account deletion: {}", e);
+    }
+}
+
+/// Keyed by user like `billing.charged`, so billing-api sees a user's events in order. A lost
+/// event only leaves the deployment's billing records open, the deletion itself went through
+async fn publish_deployment_deleted(producer: &FutureProducer, msg: &DeleteDeploymentMessage) {
+    let event = DeploymentDeletedEvent {
+        user_id: msg.user_id,
+        project_id: msg.project_id,
+        deployment_id: msg.deployment_id,
+        deleted_at: Utc::now(),
+    };
+    let key = msg.user_id.to_string();
+
+    let result = match serde_json::to_vec(&event) {
+        Ok(payload) => producer
+            .send(
+                FutureRecord::to(DEPLOYMENT_DELETED_TOPIC)
+                    .key(&key)
+                    .payload(&payload),
+                Duration::from_secs(5),
+            )
+            .await
+            .map(|_| ())
+            .map_err(|(e, _)| AppError::from(e)),
+        Err(e) => Err(e.into()),
+    };
+
+    if let Err(e) = result {
+        warn!(deployment_id = %msg.deployment_id, "⚠️ Failed to publish {}: {}", DEPLOYMENT_DELETED_TOPIC, e);
+    }
+}
+
+#[tracing::instrument(name = "consumer.handle_suspend_messages", skip_all)]
+async fn handle_suspend_messages(
+    pool: PgPool,
+    con: MultiplexedConnection,
+    k8s: KubernetesService,
+    tracker: TaskTracker,
+    mut consumer: Consumer,
+) {
+    info!("⏸️ suspend consumer started");
+
+    let queue = consumer.queue();
+    let consumer_tag = consumer.tag();
+
+    while let Some(delivery) = consumer.next().await {
+        let delivery = match delivery {
+            Ok(d) => d,
+            Err(e) => {
+                error!("❌ Consumer connection error: {}", e);
+                continue;
+            }
+        };
+
+        let headers = delivery
+            .properties
+            .headers()
+            .as_ref()
+            .cloned()
+            .unwrap_or_default();
+
+        // Extract Retry Count
+        let retry_count = get_retry_count(&headers);
+
+        // Clone Service for the async block
+        let pool = pool.clone();
+        let con = con.clone();
+        let k8s = k8s.clone();
+        let span = consume_span(&delivery, &headers, &queue, &consumer_tag);
+
+        tracker.spawn(
+            async move {
+                let started = Instant::now();
+
+                if retry_count > 3 {
+                    error!("❌ Max retries reached for suspend deployment. Dropping message.");
+                    if let Err(e) = delivery.ack(BasicAckOptions::default()).await {
+                        error!("❌ Failed to ack for suspend deployment for max retries: {}", e);
+                    }
+                    record_outcome(started, Err("max retries reached".into()));
+                    return;
+                }
+
+                match serde_json::from_slice::<SuspendDeploymentMessage>(&delivery.data) {
+                    Ok(msg) => {
+                        Span::current().record("deployment_id", tracing::field::display(&msg.deployment_id));
+                        debug!(deployment_id = %msg.deployment_id, "⏸️ Suspend deployment request received");
+
+                        let result = match k8s.for_deployment(&msg.deployment_id, &pool).await {
+                            Ok(k8s) => k8s.suspend(pool, con, msg.clone()).await,
+                            Err(e) => Err(e),
+                        };
+                        match result {
+                            Ok(_) => {
+                                record_outcome(started, Ok(()));
+                                info!(deployment_id = %msg.deployment_id, "⏸️ Deployment suspended");
+                                if let Err(e) = delivery.ack(BasicAckOptions::default()).await {
+                                    error!(deployment_id = %msg.deployment_id, "❌ Failed to ack for suspend deployment: {}", e);
+                                }
+                            }
+                            Err(e) => {
+                                record_outcome(started, Err(e.to_string()));
+                                error!(deployment_id = %msg.deployment_id, "❌ Failed to suspend deployment: {}", e);
+
+                                if let Err(e) = delivery.nack(BasicNackOptions {requeue: false, multiple: false}).await {
+                                    error!(deployment_id = %msg.deployment_id, "❌ Failed to nack for suspend deployment: {}", e);
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        record_outcome(started, Err(e.to_string()));
+                        error!("❌ Failed to parse SuspendDeploymentMessage: {}", e);
+                        if let Err(e) = delivery.reject(BasicRejectOptions { requeue: false }).await {
+                            error!("❌ Failed to reject for suspend deployment: {}", e);
+                        }
+                    }
+                }
+            }
+            .instrument(span),
+        );
+    }
+}
+
+#[tracing::instrument(name = "consumer.handle_resume_messages", skip_all)]
+async fn handle_resume_messages(
+    pool: PgPool,
+    con: MultiplexedConnection,
+    k8s: KubernetesService,
+    tracker: TaskTracker,
+    mut consumer: Consumer,
+) {
+    info!("▶️ resume consumer started");
+
+    let queue = consumer.queue();
+    let consumer_tag = consumer.tag();
+
+    while let Some(delivery) = consumer.next().await {
+        let delivery = match delivery {
+            Ok(d) => d,
+            Err(e) => {
+                error!("❌ Consumer connection error: {}", e);
+                continue;
+            }
+        };
+
+        let headers = delivery
+            .properties
+            .headers()
+            .as_ref()
+            .cloned()
+            .unwrap_or_default();
+
+        // Extract Retry Count
+        let retry_count = get_retry_count(&headers);
+
+        // Clone Service for the async block
+        let pool = pool.clone();
+        let con = con.clone();
+        let k8s = k8s.clone();
+        let span = consume_span(&delivery, &headers, &queue, &consumer_tag);
+
+        tracker.spawn(
+            async move {
+                let started = Instant::now();
+
+                if retry_count > 3 {
+                    error!("❌ Max retries reached for resume deployment. Dropping message.");
+                    if let Err(e) = delivery.ack(BasicAckOptions::default()).await {
+                        error!("❌ Failed to ack for resume deployment for max retries: {}", e);
+                    }
+                    record_outcome(started, Err("max retries reached".into()));
+                    return;
+                }
+
+                match serde_json::from_slice::<ResumeDeploymentMessage>(&delivery.data) {
+                    Ok(msg) => {
+                        Span::current().record("deployment_id", tracing::field::display(&msg.deployment_id));
+                        debug!(deployment_id = %msg.deployment_id, "▶️ Resume deployment request received");
+
+                        let result = match k8s.for_deployment(&msg.deployment_id, &pool).await {
+                            Ok(k8s) => k8s.resume(pool, con, msg.clone()).await,
+                            Err(e) => Err(e),
+                        };
+                        match result {
+                            Ok(_) => {
+                                record_outcome(started, Ok(()));
+                                info!(deployment_id = %msg.deployment_id, "▶️ Deployment resumed");
+                                if let Err(e) = delivery.ack(BasicAckOptions::default()).await {
+                                    error!(deployment_id = %msg.deployment_id, "❌ Failed to ack for resume deployment: {}", e);
+                                }
+                            }
+                            Err(e) => {
+                                record_outcome(started, Err(e.to_string()));
+                                error!(deployment_id = %msg.deployment_id, "❌ Failed to resume deployment: {}", e);
+
+                                if let Err(e) = delivery.nack(BasicNackOptions {requeue: false, multiple: false}).await {
+                                    error!(deployment_id = %msg.deployment_id, "❌ Failed to nack for resume deployment: {}", e);
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        record_outcome(started, Err(e.to_string()));
+                        error!("❌ Failed to parse ResumeDeploymentMessage: {}", e);
+                        if let Err(e) = delivery.reject(BasicRejectOptions { requeue: false }).await {
+                            error!("❌ Failed to reject for resume deployment: {}", e);
+                        }
+                    }
+                }
+            }
+            .instrument(span),
+        );
+    }
+}
+
+#[tracing::instrument(name = "consumer.handle_registry_credentials_messages", skip_all)]
+async fn handle_registry_credentials_messages(
+    pool: PgPool,
+    con: MultiplexedConnection,
+    k8s: KubernetesService,
+    tracker: TaskTracker,
+    mut consumer: Consumer,
+) {
+    info!("🔑 registry credentials consumer started");
+
+    let queue = consumer.queue();
+    let consumer_tag = consumer.tag();
+
+    while let Some(delivery) = consumer.next().await {
+        let delivery = match delivery {
+            Ok(d) => d,
+            Err(e) => {
+                error!("❌ Consumer connection error: {}", e);
+                continue;
+            }
+        };
+
+        let headers = delivery
+            .properties
+            .headers()
+            .as_ref()
+            .cloned()
+            .unwrap_or_default();
+
+        // Extract Retry Count
+        let retry_count = get_retry_count(&headers);
+
+        // Clone Service for the async block
+        let pool = pool.clone();
+        let con = con.clone();
+        let k8s = k8s.clone();
+        let span = consume_span(&delivery, &headers, &queue, &consumer_tag);
+
+        tracker.spawn(
+            async move {
+                let started = Instant::now();
+
+                if retry_count > 3 {
+                    error!("❌ Max retries reached for registry credentials rotation. Dropping message.");
+                    if let Err(e) = delivery.ack(BasicAckOptions::default()).await {
+                        error!("❌ Failed to ack for registry credentials rotation for max retries: {}", e);
+                    }
+                    record_outcome(started, Err("max retries reached".into()));
+                    return;
+                }
+
+                match serde_json::from_slice::<RotateRegistryCredentialsMessage>(&delivery.data) {
+                    Ok(msg) => {
+                        Span::current().record("deployment_id", tracing::field::display(&msg.deployment_id));
+                        debug!(deployment_id = %msg.deployment_id, "🔑 Registry credentials rotation request received");
+
+                        let result = match k8s.for_deployment(&msg.deployment_id, &pool).await {
+                            Ok(k8s) => k8s.rotate_registry_credentials(pool, con, msg.clone()).await,
+                            Err(e) => Err(e),
+                        };
+                        match result {
+                            Ok(_) => {
+                                record_outcome(started, Ok(()));
+                                info!(deployment_id = %msg.deployment_id, "🔑 Registry credentials rotated");
+                                if let Err(e) = delivery.ack(BasicAckOptions::default()).await {
+                                    error!(deployment_id = %msg.deployment_id, "❌ Failed to ack for registry credentials rotation: {}", e);
+                                }
+                            }
+                            Err(e) => {
+                                record_outcome(started, Err(e.to_string()));
+                                error!(deployment_id = %msg.deployment_id, "❌ Failed to rotate registry credentials: {}", e);
+
+                                if let Err(e) = delivery.nack(BasicNackOptions {requeue: false, multiple: false}).await {
+                                    error!(deployment_id = %msg.deployment_id, "❌ Failed to nack for registry credentials rotation: {}", e);
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        record_outcome(started, Err(e.to_string()));
+                        error!("❌ Failed to parse RotateRegistryCredentialsMessage: {}", e);
+                        if let Err(e) = delivery.reject(BasicRejectOptions { requeue: false }).await {
+                            error!("❌ Failed to reject for registry credentials rotation: {}", e);
+                        }
+                    }
+                }
+            }
+            .instrument(span),
+        );
+    }
+}
+
+#[tracing::instrument(name = "consumer.handle_secrets_messages", skip_all)]
+async fn handle_secrets_messages(
+    pool: PgPool,
+    con: MultiplexedConnection,
+    k8s: KubernetesService,
+    tracker: TaskTracker,
+    mut consumer: Consumer,
+) {
+    info!("🔐 secrets consumer started");
+
+    let queue = consumer.queue();
+    let consumer_tag = consumer.tag();
+
+    while let Some(delivery) = consumer.next().await {
+        let delivery = match delivery {
+            Ok(d) => d,
+            Err(e) => {
+                error!("❌ Consumer connection error: {}", e);
+                continue;
+            }
+        };
+
+        let headers = delivery
+            .properties
+            .headers()
+            .as_ref()
+            .cloned()
+            .unwrap_or_default();
+
+        // Extract Retry Count
+        let retry_count = get_retry_count(&headers);
+
+        // Clone Service for the async block
+        let pool = pool.clone();
+        let con = con.clone();
+        let k8s = k8s.clone();
+        let span = consume_span(&delivery, &headers, &queue, &consumer_tag);
+
+        tracker.spawn(
+            async move {
+                let started = Instant::now();
+
+                if retry_count > 3 {
+                    error!("❌ Max retries reached for secrets rotation. Dropping message.");
+                    if let Err(e) = delivery.ack(BasicAckOptions::default()).await {
+                        error!("❌ Failed to ack for secrets rotation for max retries: {}", e);
+                    }
+                    record_outcome(started, Err("max retries reached".into()));
+                    return;
+                }
+
+                match serde_json::from_slice::<RotateSecretsMessage>(&delivery.data) {
+                    Ok(msg) => {
+                        Span::current().record("deployment_id", tracing::field::display(&msg.deployment_id));
+                        debug!(deployment_id = %msg.deployment_id, "🔐 Secrets rotation request received");
+
+                        let result = match k8s.for_deployment(&msg.deployment_id, &pool).await {
+                            Ok(k8s) => k8s.rotate_secrets(pool, con, msg.clone()).await,
+                            Err(e) => Err(e),
+                        };
+                        match result {
+                            Ok(_) => {
+                                record_outcome(started, Ok(()));
+                                info!(deployment_id = %msg.deployment_id, "🔐 Secrets rotated");
+                                if let Err(e) = delivery.ack(BasicAckOptions::default()).await {
+                                    error!(deployment_id = %msg.deployment_id, "❌ Failed to ack for secrets rotation: {}", e);
+                                }
+                            }
+                            Err(e) => {
+                                record_outcome(started, Err(e.to_string()));
+                                error!(deployment_id = %msg.deployment_id, "❌ Failed to rotate secrets: {}", e);
+
+                                if let Err(e) = delivery.nack(BasicNackOptions {requeue: false, multiple: false}).await {
+                                    error!(deployment_id = %msg.deployment_id, "❌ Failed to nack for secrets rotation: {}", e);
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        record_outcome(started, Err(e.to_string()));
+                        error!("❌ Failed to parse RotateSecretsMessage: {}", e);
+                        if let Err(e) = delivery.reject(BasicRejectOptions { requeue: false }).await {
+                            error!("❌ Failed to reject for secrets rotation: {}", e);
+                        }
+                    }
+                }
+            }
+            .instrument(span),
+        );
+    }
+}
+
+#[tracing::instrument(name = "consumer.handle_diff_messages", skip_all)]
+async fn handle_diff_messages(
+    pool: PgPool,
+    con: MultiplexedConnection,
+    k8s: KubernetesService,
+    tracker: TaskTracker,
+    mut consumer: Consumer,
+) {
+    info!("🔍 Diff consumer started");
+
+    let queue = consumer.queue();
+    let consumer_tag = consumer.tag();
+
+    while let Some(delivery) = consumer.next().await {
+        let delivery = match delivery {
+            Ok(d) => d,
+            Err(e) => {
+                error!("❌ Consumer connection error: {}", e);
+                continue;
+            }
+        };
+
+        let headers = delivery
+            .properties
+            .headers()
+            .as_ref()
+            .cloned()
+            .unwrap_or_default();
+
+        // Clone Service for the async block
+        let pool = pool.clone();
+        let mut con = con.clone();
+        let k8s = k8s.clone();
+        let span = consume_span(&delivery, &headers, &queue, &consumer_tag);
+
+        tracker.spawn(
+            async move {
+                let started = Instant::now();
+
+                match serde_json::from_slice::<DeploymentDiffMessage>(&delivery.data) {
+                    Ok(msg) => {
+                        Span::current().record("deployment_id", tracing::field::display(&msg.update.deployment_id));
+                        debug!(deployment_id = %msg.update.deployment_id, "🔍 Diff request received");
+
+                        // Never retried, like a dry run the API stops waiting long before a retry would land
+                        let result = k8s
+                            .compute_proposed_k8s_patch(&pool, &msg.update)
+                            .await
+                            .map_err(api_server_error);
+                        let channel = ChannelNames::deployment_diff(&msg.request_id.to_string());
+                        let published = match serde_json::to_string(&result) {
+                            Ok(payload) => con.publish(channel, payload).await.map(|_| ()).map_err(|e| e.to_string()),
+                            Err(e) => Err(e.to_string()),
+                        };
+                        if let Err(e) = &published {
+                            error!(request_id = %msg.request_id, "❌ Failed to publish diff result: {}", e);
+                        }
+                        record_outcome(started, published);
+                        if let Err(e) = delivery.ack(BasicAckOptions::default()).await {
+                            error!(request_id = %msg.request_id, "❌ Failed to ack for diff message: {}", e);
+                        }
+                    }
+                    Err(e) => {
+                        record_outcome(started, Err(e.to_string()));
+                        error!("❌ Failed to parse DeploymentDiffMessage: {}", e);
+                        if let Err(e) = delivery.reject(BasicRejectOptions { requeue: false }).await {
+                            error!("❌ Failed to reject for diff message: {}", e);
+                        }
+                    }
+                }
+            }
+            .instrument(span),
+        );
+    }
+}
+
+#[tracing::instrument(name = "consumer.handle_dead_letter_messages", skip_all)]
+async fn handle_dead_letter_messages(
+    pool: PgPool,
+    con: MultiplexedConnection,
+    tracker: TaskTracker,
+    mut consumer: Consumer,
+) {
+    info!("🪦 dead letter consumer started");
+
+    let queue = consumer.queue();
+    let consumer_tag = consumer.tag();
+
+    while let Some(delivery) = consumer.next().await {
+        let delivery = match delivery {
+            Ok(d) => d,
+            Err(e) => {
+                error!("❌ Consumer connection error: {}", e);
+                continue;
+            }
+        };
+
+        let headers = delivery
+            .properties
+            .headers()
+            .as_ref()
+            .cloned()
+            .unwrap_or_default();
+
+        let retry_count = get_retry_count(&headers);
+        let (origin_queue, reason) = dead_letter_origin(&headers);
+
+        let pool = pool.clone();
+        let mut con = con.clone();
+        let span = consume_span(&delivery, &headers, &queue, &consumer_tag);
+
+        tracker.spawn(
+            async move {
+                let started = Instant::now();
+                let payload = String::from_utf8_lossy(&delivery.data);
+
+                let result = match serde_json::from_slice::<DeadLetteredMessage>(&delivery.data) {
+                    Ok(msg) => {
+                        Span::current()
+                            .record("deployment_id", tracing::field::display(&msg.deployment_id));
+                        error!(
+                            deployment_id = %msg.deployment_id,
+                            project_id = %msg.project_id,
+                            routing_key = %delivery.routing_key,
+                            origin_queue = ?origin_queue,
+                            reason = ?reason,
+                            retry_count = retry_count,
+                            payload = %payload,
+                            "🪦 Message dead-lettered"
+                        );
+
+                        // A failed create leaves nothing running that could recover on its own
+                        if delivery.routing_key.as_str() == "compute.create" {
+                            DeploymentEventEmitter::emit(
+                                DeploymentEventEmitterInput {
+                                    project_id: &msg.project_id,
+                                    deployment_id: &msg.deployment_id,
+                                    status: Some(DeploymentStatus::Failed),
+                                    event_type: Some(DeploymentEventType::StatusChanged),
+                                    level: Some(DeploymentEventLevel::Error),
+                                    message: Some("Provisioning failed, giving up after retries"),
+                                    persist_event: true,
+                                    publish_project: true,
+                                    publish_deployment: true,
+                                },
+                                &pool,
+                                &mut con,
+                            )
+                            .await
+                            .map(|_| ())
+                            .map_err(|e| e.to_string())
+                        } else {
+                            Ok(())
+                        }
+                    }
+                    Err(e) => {
+                        error!(
+                            routing_key = %delivery.routing_key,
+                            origin_queue = ?origin_queue,
+                            reason = ?reason,
+                            payload = %payload,
+                            "🪦 Unparsable message dead-lettered: {}", e
+                        );
+                        Ok(())
+                    }
+                };
+
+                if let Err(e) = &result {
+                    error!(
+                        "❌ Failed to mark dead-lettered deployment as failed: {}",
+                        e
+                    );
+                }
+                record_outcome(started, result);
+
+                // Dead letters are reported once, redelivering them would only repeat the report
+                if let Err(e) = delivery.ack(BasicAckOptions::default()).await {
+                    error!("❌ Failed to ack dead letter: {}", e);
+                }
+            }
+            .instrument(span),
+        );
+    }
+}
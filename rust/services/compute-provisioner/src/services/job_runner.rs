@@ -0,0 +1,201 @@
+use compute_core::models::{
+    DeploymentEventLevel, DeploymentEventType, DeploymentStatus, JobRow, JobType,
+};
+use compute_core::schemas::{CreateDeploymentMessage, UpdateDeploymentMessage};
+use compute_core::services::event_emission_service::{
+    DeploymentEventEmitter, DeploymentEventEmitterInput,
+};
+use factory::factories::{database::Database, redis::Redis};
+use redis::aio::MultiplexedConnection;
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio::time::{MissedTickBehavior, interval};
+use tokio_util::sync::CancellationToken;
+use tracing::{Instrument, error, info, info_span, warn};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    services::{kubernetes_service::KubernetesService, repository::JobQueueRepository},
+};
+
+/// Jobs claimed per poll, each runs in its own task
+const CLAIM_BATCH_SIZE: i64 = 10;
+/// A job still running this long after it was claimed is claimed again, starting a build only
+/// takes a few API calls
+const LEASE_SECS: f64 = 600.0;
+/// Retries wait 10s, 20s, 40s... capped at `MAX_BACKOFF_SECS`
+const BASE_BACKOFF_SECS: f64 = 10.0;
+const MAX_BACKOFF_SECS: f64 = 600.0;
+
+#[derive(Clone)]
+pub struct JobRunnerContext {
+    pub database: Database,
+    pub redis: Redis,
+    pub k8s: KubernetesService,
+    pub poll_interval: Duration,
+}
+
+/// Polls `job_queue` until `shutdown` is cancelled, a job interrupted by shutdown is picked up
+/// again once its lease runs out
+pub async fn start_job_runner(
+    ctx: JobRunnerContext,
+    shutdown: CancellationToken,
+) -> Result<(), AppError> {
+    let mut ticker = interval(ctx.poll_interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    info!(
+        "🧰 Job runner started, polling every {}s",
+        ctx.poll_interval.as_secs()
+    );
+
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = ticker.tick() => {}
+        }
+
+        let jobs = match JobQueueRepository::claim(CLAIM_BATCH_SIZE, LEASE_SECS, &ctx.database.pool)
+            .await
+        {
+            Ok(jobs) => jobs,
+            Err(e) => {
+                error!("❌ Failed to claim jobs: {}", e);
+                continue;
+            }
+        };
+
+        let mut set = JoinSet::new();
+        for job in jobs {
+            let span = info_span!(
+                "job_runner.run",
+                job_id = %job.id,
+                job_type = ?job.job_type,
+                attempt = job.attempts,
+            );
+            set.spawn(
+                run_job(
+                    job,
+                    ctx.database.pool.clone(),
+                    ctx.redis.con.clone(),
+                    ctx.k8s.clone(),
+                )
+                .instrument(span),
+            );
+        }
+        while let Some(result) = set.join_next().await {
+            if let Err(e) = result {
+                error!("❌ Job task panicked: {}", e);
+            }
+        }
+    }
+
+    info!("🛑 Job runner stopped");
+    Ok(())
+}
+
+async fn run_job(job: JobRow, pool: PgPool, con: MultiplexedConnection, k8s: KubernetesService) {
+    // The lease of a job whose runner died ran out after its last attempt
+    if job.attempts > job.max_attempts {
+        if let Err(e) = JobQueueRepository::fail(&job.id, "lease expired", &pool).await {
+            error!("❌ Failed to mark job as failed: {}", e);
+        }
+        return;
+    }
+
+    let (ids, result) = match execute(&job, &pool, con.clone(), &k8s).await {
+        Ok(outcome) => outcome,
+        // A payload that doesn't parse never will
+        Err(e) => {
+            error!("❌ Dropping job with an unparsable payload: {}", e);
+            if let Err(e) = JobQueueRepository::fail(&job.id, &e.to_string(), &pool).await {
+                error!("❌ Failed to mark job as failed: {}", e);
+            }
+            return;
+        }
+    };
+    let (project_id, deployment_id) = ids;
+
+    let marked = match result {
+        Ok(()) => {
+            info!(deployment_id = %deployment_id, "✅ Job done");
+            JobQueueRepository::complete(&job.id, &pool).await
+        }
+        Err(e) if job.attempts < job.max_attempts => {
+            let delay = backoff_secs(job.attempts);
+            warn!(
+                deployment_id = %deployment_id,
+                "⚠️ Job attempt {}/{} failed, retrying in {}s: {}",
+                job.attempts, job.max_attempts, delay, e
+            );
+            JobQueueRepository::retry(&job.id, delay, &e.to_string(), &pool).await
+        }
+        Err(e) => {
+            error!(
+                deployment_id = %deployment_id,
+                "❌ Job failed after {} attempts: {}", job.attempts, e
+            );
+            // A build that never started leaves nothing running that could recover on its own
+            if job.job_type == JobType::Build
+                && let Err(e) = DeploymentEventEmitter::emit(
+                    DeploymentEventEmitterInput {
+                        project_id: &project_id,
+                        deployment_id: &deployment_id,
+                        status: Some(DeploymentStatus::Failed),
+                        event_type: Some(DeploymentEventType::StatusChanged),
+                        level: Some(DeploymentEventLevel::Error),
+                        message: Some("Build could not be started, giving up after retries"),
+                        persist_event: true,
+                        publish_project: true,
+                        publish_deployment: true,
+                    },
+                    &pool,
+                    &mut con.clone(),
+                )
+                .await
+            {
+                error!(deployment_id = %deployment_id, "❌ Failed to mark deployment as failed: {}", e);
+            }
+            JobQueueRepository::fail(&job.id, &e.to_string(), &pool).await
+        }
+    };
+
+    if let Err(e) = marked {
+        error!(deployment_id = %deployment_id, "❌ Failed to record job outcome: {}", e);
+    }
+}
+
+/// Outer error only for a payload that doesn't parse, the inner result is the attempt's
+async fn execute(
+    job: &JobRow,
+    pool: &PgPool,
+    con: MultiplexedConnection,
+    k8s: &KubernetesService,
+) -> Result<((Uuid, Uuid), Result<(), AppError>), serde_json::Error> {
+    match job.job_type {
+        JobType::Build => {
+            let msg: CreateDeploymentMessage = serde_json::from_value(job.payload.clone())?;
+            let ids = (msg.project_id, msg.deployment_id);
+            let result = match k8s.in_region(msg.region.as_deref()) {
+                Ok(k8s) => k8s.create(pool.clone(), con, msg).await,
+                Err(e) => Err(e),
+            };
+            Ok((ids, result))
+        }
+        JobType::Rebuild => {
+            let msg: UpdateDeploymentMessage = serde_json::from_value(job.payload.clone())?;
+            let ids = (msg.project_id, msg.deployment_id);
+            let result = match k8s.for_deployment(&msg.deployment_id, pool).await {
+                Ok(k8s) => k8s.update(pool.clone(), con, msg).await,
+                Err(e) => Err(e),
+            };
+            Ok((ids, result))
+        }
+    }
+}
+
+fn backoff_secs(attempts: i32) -> f64 {
+    (BASE_BACKOFF_SECS * 2f64.powi(attempts.saturating_sub(1))).min(MAX_BACKOFF_SECS)
+}
@@ -2,26 +2,52 @@ use std::collections::{BTreeMap, HashMap};
 
 use base64::Engine;
 use compute_core::formatters::{format_namespace, format_resource_name};
-use compute_core::models::{DeploymentEventType, DeploymentStatus, ResourceSpec};
+use compute_core::models::{
+    AlertThreshold, DeploymentEventLevel, DeploymentEventType, DeploymentStatus, DeploymentType,
+    ResourceSpec,
+};
 use compute_core::schemas::{
-    CreateDeploymentMessage, DeleteDeploymentMessage, DeploymentSourceMessage, ImagePullSecret,
-    UpdateDeploymentMessage,
+    AutoscalingSpec, CanaryConfig, ConfigMapSpec, ContainerSpec, CreateDeploymentMessage,
+    DeleteDeploymentMessage, DeleteUserMessage, DeploymentSourceMessage, DryRunResult,
+    ImagePullSecret, ProbeSpec, ResumeDeploymentMessage, RollingUpdateSpec,
+    RotateRegistryCredentialsMessage, RotateSecretsMessage, SuspendDeploymentMessage,
+    UpdateDeploymentMessage, VolumeAccessMode, VolumeSpec,
 };
 use compute_core::services::event_emission_service::{
     DeploymentEventEmitter, DeploymentEventEmitterInput,
 };
 use k8s_openapi::ByteString;
-use k8s_openapi::api::batch::v1::{Job, JobSpec};
+use k8s_openapi::api::authorization::v1::{
+    ResourceAttributes, SelfSubjectAccessReview, SelfSubjectAccessReviewSpec,
+};
+use k8s_openapi::api::autoscaling::v2::{
+    CrossVersionObjectReference, HorizontalPodAutoscaler, HorizontalPodAutoscalerSpec, MetricSpec,
+    MetricTarget, ResourceMetricSource,
+};
+use k8s_openapi::api::batch::v1::{CronJob, CronJobSpec, Job, JobSpec, JobTemplateSpec};
 use k8s_openapi::api::core::v1::{
-    EmptyDirVolumeSource, EnvFromSource, KeyToPath, LocalObjectReference, PodSecurityContext,
-    SecretEnvSource, SecretVolumeSource, Volume, VolumeMount,
+    ConfigMap, ConfigMapEnvSource, ConfigMapVolumeSource, EmptyDirVolumeSource, EnvFromSource,
+    HTTPGetAction, KeyToPath, LocalObjectReference, PersistentVolumeClaim,
+    PersistentVolumeClaimSpec, PersistentVolumeClaimVolumeSource, PodSecurityContext, Probe,
+    SecretEnvSource, SecretVolumeSource, ServiceAccount, TCPSocketAction, TopologySpreadConstraint,
+    Volume, VolumeMount, VolumeResourceRequirements,
+};
+use k8s_openapi::api::networking::v1::{
+    NetworkPolicy, NetworkPolicyEgressRule, NetworkPolicyIngressRule, NetworkPolicyPeer,
+    NetworkPolicyPort, NetworkPolicySpec,
 };
+use k8s_openapi::api::policy::v1::{PodDisruptionBudget, PodDisruptionBudgetSpec};
+use k8s_openapi::api::rbac::v1::{PolicyRule, Role, RoleBinding, RoleRef, Subject};
 use k8s_openapi::{
     api::{
-        apps::v1::{Deployment as K8sDeployment, DeploymentSpec},
+        apps::v1::{
+            Deployment as K8sDeployment, DeploymentSpec, DeploymentStrategy,
+            RollingUpdateDeployment,
+        },
         core::v1::{
-            Container, ContainerPort, EnvVar, Namespace, PodSpec, PodTemplateSpec,
-            ResourceRequirements, Secret as K8sSecret, Service, ServicePort, ServiceSpec,
+            Container, ContainerPort, EnvVar, Namespace, PodSpec, PodTemplateSpec, ResourceQuota,
+            ResourceQuotaSpec, ResourceRequirements, Secret as K8sSecret, Service, ServicePort,
+            ServiceSpec,
         },
     },
     apimachinery::pkg::{
@@ -37,32 +63,175 @@ use kcr_secrets_hashicorp_com::v1beta1::{
         VaultStaticSecretRolloutRestartTargetsKind, VaultStaticSecretSpec, VaultStaticSecretType,
     },
 };
-use kcr_traefik_io::v1alpha1::ingressroutes::{
-    IngressRoute, IngressRouteRoutes, IngressRouteRoutesServices, IngressRouteSpec,
-    IngressRouteTls, IngressRouteTlsDomains,
+use kcr_traefik_io::v1alpha1::{
+    ingressroutes::{
+        IngressRoute, IngressRouteRoutes, IngressRouteRoutesMiddlewares,
+        IngressRouteRoutesServices, IngressRouteRoutesServicesKind, IngressRouteSpec,
+        IngressRouteTls, IngressRouteTlsDomains,
+    },
+    middlewares::{
+        Middleware, MiddlewareErrors, MiddlewareErrorsService, MiddlewareIpAllowList,
+        MiddlewareSpec,
+    },
+    traefikservices::{
+        TraefikService, TraefikServiceSpec, TraefikServiceWeighted, TraefikServiceWeightedServices,
+    },
 };
 
 use kube::{
     Api,
-    api::{DeleteParams, ObjectMeta, Patch, PatchParams, PostParams},
+    api::{DeleteParams, ListParams, ObjectMeta, Patch, PatchParams, PostParams},
 };
 
 use redis::aio::MultiplexedConnection;
 use sqlx::PgPool;
-use tracing::{Instrument, error, info, info_span};
+use tracing::{Instrument, error, info, info_span, warn};
 use uuid::Uuid;
 
 use crate::error::AppError;
-use crate::services::kubernetes_service::KubernetesService;
+use crate::services::kubernetes_service::{
+    ConfigMapSources, GitCheckout, KubernetesService, MainContainerSettings, NamespaceQuotaConfig,
+    RbacCheckResult,
+};
 use crate::services::repository::DeploymentRepository;
+use crate::services::vault_service::implementations::changed_secret_keys;
 use compute_core::crds::{
-    BuildCacheConfig, GitSource, Image, ImageBuilderRef, ImageSpec, RegistryCache, SourceConfig,
+    BuildCacheConfig, GitSource, Image, ImageBuilderRef, ImageSpec, PrometheusRule,
+    PrometheusRuleGroup, PrometheusRuleRule, PrometheusRuleSpec, RegistryCache, SourceConfig,
 };
 
+/// Name of the main container's port
+const MAIN_PORT_NAME: &str = "http";
+/// ServiceAccount, Role and RoleBinding of the user's pods share the name
+const USER_APP_SERVICE_ACCOUNT: &str = "poddle-user-app";
+/// Finished Jobs a CronJob keeps around, so their pods' logs stay readable
+const CRON_JOB_HISTORY_LIMIT: i32 = 3;
+/// `(api group, resource, verbs)` the provisioner calls, granted by `compute-provisioner-role`.
+/// Keep both in step when a new kind of object is managed. Server-side apply of an object that
+/// doesn't exist yet is authorized as `create`, so every applied kind needs it next to `patch`
+const REQUIRED_PERMISSIONS: &[(&str, &str, &[&str])] = &[
+    ("", "namespaces", &["get", "create", "delete"]),
+    ("", "services", &["create", "patch", "delete"]),
+    ("", "secrets", &["create", "patch", "delete"]),
+    ("", "serviceaccounts", &["create", "patch"]),
+    (
+        "",
+        "configmaps",
+        &["list", "create", "patch", "deletecollection"],
+    ),
+    (
+        "",
+        "persistentvolumeclaims",
+        &["list", "create", "patch", "deletecollection"],
+    ),
+    ("", "resourcequotas", &["create", "patch"]),
+    ("apps", "deployments", &["get", "create", "patch", "delete"]),
+    ("batch", "jobs", &["create"]),
+    (
+        "batch",
+        "cronjobs",
+        &["get", "create", "patch", "update", "delete"],
+    ),
+    (
+        "autoscaling",
+        "horizontalpodautoscalers",
+        &["get", "create", "patch", "delete"],
+    ),
+    (
+        "policy",
+        "poddisruptionbudgets",
+        &["create", "patch", "delete"],
+    ),
+    ("networking.k8s.io", "networkpolicies", &["create", "patch"]),
+    ("rbac.authorization.k8s.io", "roles", &["create", "patch"]),
+    (
+        "rbac.authorization.k8s.io",
+        "rolebindings",
+        &["create", "patch"],
+    ),
+    ("cert-manager.io", "clusterissuers", &["get"]),
+    ("cert-manager.io", "certificates", &["get"]),
+    (
+        "traefik.io",
+        "ingressroutes",
+        &["create", "patch", "delete"],
+    ),
+    (
+        "traefik.io",
+        "middlewares",
+        &["get", "create", "patch", "delete"],
+    ),
+    (
+        "traefik.io",
+        "traefikservices",
+        &["get", "create", "patch", "delete"],
+    ),
+    (
+        "monitoring.coreos.com",
+        "prometheusrules",
+        &["create", "patch", "delete"],
+    ),
+    ("kpack.io", "images", &["create", "patch"]),
+    (
+        "secrets.hashicorp.com",
+        "vaultconnections",
+        &["create", "patch"],
+    ),
+    ("secrets.hashicorp.com", "vaultauths", &["create", "patch"]),
+    (
+        "secrets.hashicorp.com",
+        "vaultstaticsecrets",
+        &["create", "patch", "delete"],
+    ),
+];
+
 impl KubernetesService {
+    /// The same service acting on `region`'s cluster, the default region's when none is given
+    pub fn in_region(&self, region: Option<&str>) -> Result<Self, AppError> {
+        Ok(Self {
+            client: self.clusters.client_for(region)?.clone(),
+            ..self.clone()
+        })
+    }
+
+    /// The service acting on the cluster a stored deployment was created in
+    pub async fn for_deployment(
+        &self,
+        deployment_id: &Uuid,
+        pool: &PgPool,
+    ) -> Result<Self, AppError> {
+        let region = DeploymentRepository::get_region(deployment_id, pool).await?;
+        self.in_region(region.as_deref())
+    }
+
     pub async fn preflight(&self) -> Result<(), AppError> {
         info!("🏁 Performing pre-flight infrastructure checks...");
 
+        let missing = self.preflight_rbac_check().await?;
+        if !missing.is_empty() {
+            for permission in &missing {
+                error!(
+                    group = %permission.group,
+                    resource = %permission.resource,
+                    verb = %permission.verb,
+                    reason = ?permission.reason,
+                    "❌ Service account is missing a permission"
+                );
+            }
+            let names: Vec<String> = missing
+                .iter()
+                .map(|p| match p.group {
+                    "" => format!("{}.{}", p.resource, p.verb),
+                    group => format!("{}.{}.{}", p.resource, group, p.verb),
+                })
+                .collect();
+            return Err(AppError::InternalServerError(format!(
+                "Service account is missing permissions: {}. Please apply the compute-provisioner ClusterRole.",
+                names.join(", ")
+            )));
+        }
+        info!("✅ Service account holds every required permission.");
+
         // Check for ClusterIssuer
         let cluster_issuer_api: Api<ClusterIssuer> = Api::all(self.client.clone());
         match cluster_issuer_api
@@ -114,6 +283,55 @@ impl KubernetesService {
         Ok(())
     }
 
+    /// Asks the API server, through `SelfSubjectAccessReview`s, whether the service account may
+    /// do everything in `REQUIRED_PERMISSIONS` in every namespace. Returns the denied ones
+    pub async fn preflight_rbac_check(&self) -> Result<Vec<RbacCheckResult>, AppError> {
+        let api: Api<SelfSubjectAccessReview> = Api::all(self.client.clone());
+
+        let reviews = REQUIRED_PERMISSIONS
+            .iter()
+            .flat_map(|(group, resource, verbs)| {
+                verbs.iter().map(move |verb| (*group, *resource, *verb))
+            })
+            .map(|(group, resource, verb)| {
+                let api = api.clone();
+                async move {
+                    let review = SelfSubjectAccessReview {
+                        spec: SelfSubjectAccessReviewSpec {
+                            resource_attributes: Some(ResourceAttributes {
+                                group: Some(group.to_string()),
+                                resource: Some(resource.to_string()),
+                                verb: Some(verb.to_string()),
+                                ..Default::default()
+                            }),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    };
+                    let status = api
+                        .create(&PostParams::default(), &review)
+                        .await?
+                        .status
+                        .unwrap_or_default();
+
+                    Ok::<_, AppError>((!status.allowed).then_some(RbacCheckResult {
+                        group,
+                        resource,
+                        verb,
+                        reason: status.reason.or(status.evaluation_error),
+                    }))
+                }
+            });
+
+        let missing = futures::future::try_join_all(reviews)
+            .await?
+            .into_iter()
+            .flatten()
+            .collect();
+
+        Ok(missing)
+    }
+
     // ============================================================================================
     // PUBLIC HANDLERS
     // ============================================================================================
@@ -146,16 +364,61 @@ impl KubernetesService {
 
         let ns = self.ensure_namespace(&msg.user_id).await?;
         let name = format_resource_name(&msg.deployment_id);
+        let main_container = Self::main_container_settings(&msg);
 
         self.apply_vso_resources(&ns).await?;
 
         // This creates the VSO Resource AND writes the initial data to Vault
         let secret_ref = self
-            .apply_vault_static_secret(&msg.deployment_id, &ns, &name, msg.secrets, &pool)
+            .apply_vault_static_secret(
+                &msg.user_id,
+                &msg.deployment_id,
+                &ns,
+                &name,
+                msg.secrets.clone(),
+                &pool,
+            )
+            .await?;
+
+        let is_service = msg.deployment_type == DeploymentType::Service;
+
+        // HPA can be attached before the Deployment exists, it picks up the target once builds finish
+        if let Some(autoscaling) = msg.autoscaling.as_ref().filter(|_| is_service) {
+            self.sync_hpa(
+                &ns,
+                Some(autoscaling),
+                &project_id,
+                &deployment_id,
+                &pool,
+                &mut con,
+            )
             .await?;
+        }
+
+        if msg.create_pdb && is_service && Self::min_replicas(&msg) > 1 {
+            self.apply_pdb(&ns, &name, &deployment_id).await?;
+        }
+
+        if let Some(thresholds) = msg.alert_thresholds.as_ref() {
+            self.sync_prometheus_rule(&ns, &name, &deployment_id, thresholds)
+                .await?;
+        }
+
+        // Claims exist before any Deployment, apply_deployment mounts whatever is claimed
+        for volume in msg.volumes.iter().flatten() {
+            self.apply_pvc(&ns, &name, &project_id, &deployment_id, volume)
+                .await?;
+        }
+        for config_map in msg.config_maps.iter().flatten() {
+            self.apply_config_map(&ns, &name, &project_id, &deployment_id, config_map)
+                .await?;
+        }
 
         match msg.source.clone() {
-            DeploymentSourceMessage::InternalBuildComplete { .. } => Ok(()),
+            // These only ever arrive as updates of an existing deployment
+            DeploymentSourceMessage::InternalBuildComplete { .. }
+            | DeploymentSourceMessage::GitPush { .. }
+            | DeploymentSourceMessage::GitlabPush { .. } => Ok(()),
             DeploymentSourceMessage::Image {
                 url,
                 image_pull_secret,
@@ -202,6 +465,57 @@ impl KubernetesService {
                     msg.project_id, msg.deployment_id
                 );
 
+                // Scheduled tasks get neither a Service nor a route, nothing ever calls into them
+                if msg.deployment_type == DeploymentType::CronJob {
+                    let mut container = self.create_container(
+                        Some(&msg.name),
+                        Some(&otel_resource_attributes),
+                        &name,
+                        Some(&url),
+                        None,
+                        Some(&msg.resource_spec),
+                        secret_ref,
+                        Self::inherit_project_env_vars(
+                            &project_id,
+                            msg.environment_variables.clone(),
+                            &pool,
+                        )
+                        .await?,
+                    );
+                    container.image_pull_policy = main_container.image_pull_policy;
+
+                    self.apply_cronjob(
+                        &ns,
+                        &name,
+                        &msg,
+                        container,
+                        image_pull_secret_data,
+                        &labels,
+                    )
+                    .await?;
+
+                    DeploymentEventEmitter::emit(
+                        DeploymentEventEmitterInput {
+                            project_id: &project_id,
+                            deployment_id: &deployment_id,
+                            status: Some(DeploymentStatus::Running),
+                            event_type: Some(DeploymentEventType::StatusChanged),
+                            level: None,
+                            message: Some("CronJob is scheduled"),
+                            persist_event: true,
+                            publish_project: true,
+                            publish_deployment: true,
+                        },
+                        &pool,
+                        &mut con,
+                    )
+                    .await?;
+
+                    info!("✅ Created cron job {}", msg.deployment_id);
+                    return Ok(());
+                }
+
+                let strategy = Self::deployment_strategy(&msg);
                 self.apply_deployment(
                     Some(&msg.name),
                     Some(&otel_resource_attributes),
@@ -210,11 +524,23 @@ impl KubernetesService {
                     Some(&url),
                     image_pull_secret_data,
                     Some(msg.port),
-                    Some(msg.desired_replicas),
+                    // Replicas are owned by the HPA when autoscaling is enabled
+                    msg.autoscaling.is_none().then_some(msg.desired_replicas),
                     Some(&msg.resource_spec),
                     secret_ref,
-                    msg.environment_variables,
+                    Self::inherit_project_env_vars(&project_id, msg.environment_variables, &pool)
+                        .await?,
+                    msg.init_containers
+                        .map(|containers| containers.iter().map(Self::extra_container).collect()),
+                    msg.sidecar_containers
+                        .iter()
+                        .flatten()
+                        .map(Self::extra_container)
+                        .collect(),
+                    strategy,
+                    main_container,
                     Some(&labels),
+                    msg.tags.as_ref(),
                     &selector,
                 )
                 .await?;
@@ -223,8 +549,20 @@ impl KubernetesService {
                 self.apply_service(&ns, &name, msg.port, Some(&labels), &selector)
                     .await?;
 
-                self.apply_ingressroute(&ns, &name, msg.domain, msg.subdomain, msg.port)
+                // Only deployments that can idle get woken up, other apps keep their own 503s
+                let wake_deployment_id = msg.suspend_after_idle_minutes.map(|_| &deployment_id);
+                let middlewares = self
+                    .route_middlewares(&ns, &name, wake_deployment_id, msg.ip_allowlist.as_ref())
                     .await?;
+                self.apply_ingressroute(
+                    &ns,
+                    &msg.deployment_id,
+                    msg.domain,
+                    msg.subdomain,
+                    Self::route_backend(&name, msg.port, false),
+                    middlewares,
+                )
+                .await?;
 
                 DeploymentEventEmitter::emit(
                     DeploymentEventEmitterInput {
@@ -275,7 +613,10 @@ impl KubernetesService {
                     &deployment_id.to_string(),
                     &preset_id.to_string(),
                     &build_id,
-                    &clone_url,
+                    GitCheckout {
+                        clone_url: &clone_url,
+                        commit_sha: None,
+                    },
                     context_path.as_deref(),
                     dockerfile_path.as_deref(),
                 )
@@ -310,7 +651,10 @@ impl KubernetesService {
                     &deployment_id.to_string(),
                     &preset_id.to_string(),
                     &build_id,
-                    &clone_url,
+                    GitCheckout {
+                        clone_url: &clone_url,
+                        commit_sha: None,
+                    },
                     context_path.as_deref(),
                 )
                 .await?;
@@ -319,6 +663,306 @@ impl KubernetesService {
         }
     }
 
+    /// Runs the Kubernetes side of `create` as a server-side dry run, so admission and schema
+    /// errors surface without persisting anything. Vault, the database and build jobs are left
+    /// out, a source that still has to be built only validates what exists before its build
+    #[tracing::instrument(
+        name = "kubernetes_service.dry_run",
+        skip_all,
+        fields(user_id = %msg.user_id, project_id = %msg.project_id, request_id = %msg.deployment_id)
+    )]
+    pub async fn dry_run(&self, msg: CreateDeploymentMessage) -> DryRunResult {
+        let k8s = match self.in_region(msg.region.as_deref()) {
+            Ok(k8s) => Self {
+                dry_run: true,
+                ..k8s
+            },
+            Err(e) => {
+                return DryRunResult {
+                    valid: false,
+                    errors: vec![api_server_error(e)],
+                };
+            }
+        };
+        let mut errors = Vec::new();
+
+        // Namespaced objects can only be validated inside a namespace that exists, a user's first
+        // deployment is checked against `default`, their quota doesn't exist before it either
+        let ns = format_namespace(&msg.user_id);
+        let namespaces: Api<Namespace> = Api::all(k8s.client.clone());
+        let ns = match namespaces.get_opt(&ns).await {
+            Ok(Some(_)) => ns,
+            Ok(None) => "default".to_string(),
+            Err(e) => {
+                return DryRunResult {
+                    valid: false,
+                    errors: vec![format!("Failed to check namespace: {}", e)],
+                };
+            }
+        };
+        let name = format_resource_name(&msg.deployment_id);
+
+        let mut labels = BTreeMap::new();
+        labels.insert("poddle.io/managed-by".into(), "poddle".into());
+        labels.insert("poddle.io/project-id".into(), msg.project_id.into());
+        labels.insert("poddle.io/deployment-id".into(), msg.deployment_id.into());
+        labels.insert("poddle.io/preset-id".into(), msg.preset_id.into());
+
+        let mut selector = BTreeMap::new();
+        selector.insert(
+            "poddle.io/deployment-id".to_string(),
+            msg.deployment_id.to_string(),
+        );
+
+        if let Some(autoscaling) = msg.autoscaling.as_ref()
+            && let Err(e) = k8s.apply_hpa(&ns, &name, autoscaling, &labels).await
+        {
+            errors.push(api_server_error(e));
+        }
+
+        if msg.create_pdb
+            && Self::min_replicas(&msg) > 1
+            && let Err(e) = k8s.apply_pdb(&ns, &name, &msg.deployment_id).await
+        {
+            errors.push(api_server_error(e));
+        }
+
+        for volume in msg.volumes.iter().flatten() {
+            if let Err(e) = k8s
+                .apply_pvc(&ns, &name, &msg.project_id, &msg.deployment_id, volume)
+                .await
+            {
+                errors.push(api_server_error(e));
+            }
+        }
+
+        for config_map in msg.config_maps.iter().flatten() {
+            if let Err(e) = k8s
+                .apply_config_map(&ns, &name, &msg.project_id, &msg.deployment_id, config_map)
+                .await
+            {
+                errors.push(api_server_error(e));
+            }
+        }
+
+        if let DeploymentSourceMessage::Image {
+            url,
+            image_pull_secret,
+        } = &msg.source
+        {
+            let image_pull_secret_data = match image_pull_secret.as_ref() {
+                Some(secret) => match k8s.apply_image_pull_secret(&ns, &name, secret).await {
+                    Ok(data) => Some(data),
+                    Err(e) => {
+                        errors.push(api_server_error(e));
+                        None
+                    }
+                },
+                None => None,
+            };
+
+            // Vault isn't written to, the reference alone is enough to validate the Deployment
+            let secret_ref = msg.secrets.as_ref().map(|_| format!("{}-secrets", name));
+
+            let strategy = Self::deployment_strategy(&msg);
+            if let Err(e) = k8s
+                .apply_deployment(
+                    Some(&msg.name),
+                    None,
+                    &ns,
+                    &name,
+                    Some(url),
+                    image_pull_secret_data,
+                    Some(msg.port),
+                    msg.autoscaling.is_none().then_some(msg.desired_replicas),
+                    Some(&msg.resource_spec),
+                    secret_ref,
+                    msg.environment_variables.clone(),
+                    msg.init_containers
+                        .as_ref()
+                        .map(|containers| containers.iter().map(Self::extra_container).collect()),
+                    msg.sidecar_containers
+                        .iter()
+                        .flatten()
+                        .map(Self::extra_container)
+                        .collect(),
+                    strategy,
+                    Self::main_container_settings(&msg),
+                    Some(&labels),
+                    msg.tags.as_ref(),
+                    &selector,
+                )
+                .await
+            {
+                errors.push(api_server_error(e));
+            }
+
+            if let Err(e) = k8s
+                .apply_service(&ns, &name, msg.port, Some(&labels), &selector)
+                .await
+            {
+                errors.push(api_server_error(e));
+            }
+
+            let wake_deployment_id = msg.suspend_after_idle_minutes.map(|_| &msg.deployment_id);
+            let ingressroute = match k8s
+                .route_middlewares(&ns, &name, wake_deployment_id, msg.ip_allowlist.as_ref())
+                .await
+            {
+                Ok(middlewares) => {
+                    k8s.apply_ingressroute(
+                        &ns,
+                        &msg.deployment_id,
+                        msg.domain.clone(),
+                        msg.subdomain.clone(),
+                        Self::route_backend(&name, msg.port, false),
+                        middlewares,
+                    )
+                    .await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = ingressroute {
+                errors.push(api_server_error(e));
+            }
+        }
+
+        DryRunResult {
+            valid: errors.is_empty(),
+            errors,
+        }
+    }
+
+    /// JSON Patch from the live Deployment spec to the one `update()` would apply for `msg`,
+    /// both as the API server returns them so defaulted fields don't show up as changes.
+    /// Sources that still have to be built keep the live image, nothing is built to preview them
+    #[tracing::instrument(name = "kubernetes_service.compute_proposed_k8s_patch", skip_all, fields(id = %msg.deployment_id), err)]
+    pub async fn compute_proposed_k8s_patch(
+        &self,
+        pool: &PgPool,
+        msg: &UpdateDeploymentMessage,
+    ) -> Result<json_patch::Patch, AppError> {
+        let k8s = Self {
+            dry_run: true,
+            ..self.for_deployment(&msg.deployment_id, pool).await?
+        };
+        let deployment_id = msg.deployment_id;
+        let ns = format_namespace(&msg.user_id);
+        let name = format_resource_name(&deployment_id);
+
+        let live_spec = k8s
+            .get_live_deployment_spec(&ns, &name)
+            .await?
+            .ok_or_else(|| {
+                AppError::NotFoundError("Deployment isn't running in the cluster yet".into())
+            })?;
+
+        let deployment = DeploymentRepository::get_by_id(&deployment_id, pool)
+            .await
+            .inspect_err(|e| {
+                error!(ns=%ns, name=%name, error = %e, "🚨 Failed to get deployment from database");
+            })?;
+
+        let strategy = live_spec.strategy.clone();
+        let (init_containers, sidecar_containers, main_container) = live_spec
+            .template
+            .spec
+            .clone()
+            .map(Self::declared_on_create)
+            .unwrap_or_default();
+
+        let mut labels = BTreeMap::new();
+        labels.insert("poddle.io/managed-by".into(), "poddle".into());
+        labels.insert("poddle.io/project-id".into(), msg.project_id.into());
+        labels.insert("poddle.io/deployment-id".into(), deployment_id.into());
+        let preset_id = msg.preset_id.unwrap_or(deployment.preset_id);
+        labels.insert("poddle.io/preset-id".into(), preset_id.to_string());
+        let tags = msg
+            .tags
+            .clone()
+            .or_else(|| deployment.tags.clone().map(|j| j.0));
+
+        let mut selector = BTreeMap::new();
+        selector.insert(
+            "poddle.io/deployment-id".to_string(),
+            deployment_id.to_string(),
+        );
+
+        let autoscaled = match msg.autoscaling.as_ref() {
+            Some(autoscaling) => autoscaling.is_some(),
+            None => k8s.hpa_exists(&ns, &name).await?,
+        };
+        let desired_replicas = match (autoscaled, matches!(msg.autoscaling, Some(None))) {
+            (true, _) => None,
+            (false, true) => msg.desired_replicas.or(Some(deployment.desired_replicas)),
+            (false, false) => msg.desired_replicas,
+        };
+
+        let has_secrets = msg.secrets.as_ref().is_some_and(|s| !s.is_empty());
+        let secret_ref = (deployment.vault_secret_path.is_some() || has_secrets)
+            .then(|| format!("{}-secrets", name));
+
+        let environment_variables = Self::inherit_project_env_vars(
+            &msg.project_id,
+            msg.environment_variables
+                .clone()
+                .or_else(|| deployment.environment_variables.and_then(|j| j.0)),
+            pool,
+        )
+        .await?;
+
+        let (image, image_pull_secret_data) = match msg.source.as_ref() {
+            Some(DeploymentSourceMessage::Image {
+                url,
+                image_pull_secret,
+            }) => {
+                let image_pull_secret_data = match image_pull_secret.as_ref() {
+                    Some(secret) => Some(k8s.apply_image_pull_secret(&ns, &name, secret).await?),
+                    None => None,
+                };
+                (Some(url.clone()), image_pull_secret_data)
+            }
+            Some(DeploymentSourceMessage::InternalBuildComplete { url }) => {
+                (Some(url.clone()), None)
+            }
+            _ => (
+                msg.canary
+                    .as_ref()
+                    .filter(|canary| canary.promote)
+                    .map(|canary| canary.canary_image.clone()),
+                None,
+            ),
+        };
+
+        let proposed = k8s
+            .apply_deployment(
+                msg.name.as_deref(),
+                None,
+                &ns,
+                &name,
+                image.as_deref(),
+                image_pull_secret_data,
+                msg.port,
+                desired_replicas,
+                msg.resource_spec.as_ref(),
+                secret_ref,
+                environment_variables,
+                init_containers,
+                sidecar_containers,
+                strategy,
+                main_container,
+                Some(&labels),
+                tags.as_ref(),
+                &selector,
+            )
+            .await?;
+
+        Ok(json_patch::diff(
+            &serde_json::to_value(&live_spec)?,
+            &serde_json::to_value(&proposed.spec)?,
+        ))
+    }
+
     /// Handles "Update" messages.
     /// We pass the `Option` fields directly. `None` means "Don't change".
     /// ---
@@ -368,21 +1012,85 @@ impl KubernetesService {
         let ns = self.ensure_namespace(&user_id).await?;
         let name = format_resource_name(&deployment_id);
 
+        // A plan change re-applies the quota, namespaces created before a limit was raised pick it up
+        if msg.preset_id.is_some() {
+            self.apply_namespace_quota(&ns, &self.cfg.namespace_quota)
+                .await?;
+        }
+
         // Internally handle secrets empty or not and refresh the DB, we need to get deployment after this
         // so deployment will be latest vault secret path
-        self.apply_vault_static_secret(&msg.deployment_id, &ns, &name, msg.secrets, &pool)
-            .await?;
+        self.apply_vault_static_secret(
+            &msg.user_id,
+            &msg.deployment_id,
+            &ns,
+            &name,
+            msg.secrets.clone(),
+            &pool,
+        )
+        .await?;
 
         let deployment = DeploymentRepository::get_by_id(&deployment_id, &pool)
             .await
-            .map_err(|e| {
+            .inspect_err(|e| {
                 error!(ns=%ns, name=%name, error = %e, "🚨 Failed to get deployment from database");
-                AppError::InternalServerError(format!(
-                    "🚨 Failed to get deployment from database: {}",
-                    e
-                ))
             })?;
 
+        let (deployment_type, _) =
+            DeploymentRepository::get_deployment_type(&deployment_id, &pool).await?;
+        if deployment_type == DeploymentType::CronJob {
+            let secret_ref = deployment
+                .vault_secret_path
+                .as_ref()
+                .map(|_| format!("{}-secrets", name));
+            let environment_variables = Self::inherit_project_env_vars(
+                &project_id,
+                msg.environment_variables
+                    .clone()
+                    .or_else(|| deployment.environment_variables.clone().and_then(|j| j.0)),
+                &pool,
+            )
+            .await?;
+            self.update_cronjob(
+                &ns,
+                &name,
+                &msg,
+                &deployment.name,
+                secret_ref,
+                environment_variables,
+            )
+            .await?;
+
+            DeploymentEventEmitter::emit(
+                DeploymentEventEmitterInput {
+                    project_id: &project_id,
+                    deployment_id: &deployment_id,
+                    status: Some(DeploymentStatus::Running),
+                    event_type: Some(DeploymentEventType::StatusChanged),
+                    level: None,
+                    message: Some("CronJob updated successfully"),
+                    persist_event: true,
+                    publish_project: true,
+                    publish_deployment: true,
+                },
+                &pool,
+                &mut con,
+            )
+            .await?;
+
+            info!("✅ Updated cron job {}", msg.deployment_id);
+            return Ok(());
+        }
+
+        // Strategy, init and sidecar containers and the main container's settings are only
+        // declared on create, carry them over from the live Deployment so SSA doesn't prune them
+        let live_spec = self.get_live_deployment_spec(&ns, &name).await?;
+        let strategy = live_spec.as_ref().and_then(|spec| spec.strategy.clone());
+        let (init_containers, sidecar_containers, main_container) = live_spec
+            .and_then(|spec| spec.template.spec)
+            .map(Self::declared_on_create)
+            .unwrap_or_default();
+
         // Define Labels & Selector
         let mut labels = BTreeMap::new();
         labels.insert("poddle.io/managed-by".into(), "poddle".into());
@@ -391,6 +1099,10 @@ impl KubernetesService {
 
         let preset_id = msg.preset_id.unwrap_or(deployment.preset_id);
         labels.insert("poddle.io/preset-id".into(), preset_id.to_string());
+        let tags = msg
+            .tags
+            .clone()
+            .or_else(|| deployment.tags.clone().map(|j| j.0));
 
         let mut selector = BTreeMap::new();
         selector.insert(
@@ -398,25 +1110,102 @@ impl KubernetesService {
             deployment_id.to_string(),
         );
 
+        // `Some(None)` detaches the HPA, `None` keeps whatever is currently attached
+        let autoscaled = match msg.autoscaling.as_ref() {
+            Some(autoscaling) => {
+                self.sync_hpa(
+                    &ns,
+                    autoscaling.as_ref(),
+                    &project_id,
+                    &deployment_id,
+                    &pool,
+                    &mut con,
+                )
+                .await?;
+                autoscaling.is_some()
+            }
+            None => self.hpa_exists(&ns, &name).await?,
+        };
+        let autoscaling_removed = matches!(msg.autoscaling, Some(None));
+
+        if let Some(desired_replicas) = msg.desired_replicas
+            && DeploymentRepository::get_create_pdb(&deployment_id, &pool).await?
+        {
+            self.update_pdb(&ns, &name, &deployment_id, desired_replicas)
+                .await?;
+        }
+
+        if let Some(thresholds) = msg.alert_thresholds.as_ref() {
+            self.sync_prometheus_rule(&ns, &name, &deployment_id, thresholds)
+                .await?;
+        }
+
+        // Synced before the Deployment is applied, it mounts whatever config maps exist then
+        if let Some(config_maps) = msg.config_maps.as_ref() {
+            self.sync_config_maps(&ns, &name, &project_id, &deployment_id, config_maps)
+                .await?;
+        }
+
         // Trust source is db, not apply_vault_static_secret
         let secret_ref = deployment
             .vault_secret_path
             .map(|_| format!("{}-secrets", name));
 
+        // Applied before the routes so the traffic split exists once they point at it
+        if let Some(canary) = msg.canary.as_ref().filter(|canary| !canary.promote) {
+            let port = msg.port.unwrap_or(deployment.port);
+            self.apply_canary(&ns, &name, &deployment_id, canary, port)
+                .await?;
+        }
+
         let materialize = matches!(
             msg.source,
             Some(DeploymentSourceMessage::InternalBuildComplete { .. })
         );
-        if msg.port.is_some() || msg.domain.is_some() || msg.subdomain.is_some() || materialize {
+        if msg.port.is_some()
+            || msg.domain.is_some()
+            || msg.subdomain.is_some()
+            || materialize
+            || msg.canary.is_some()
+            || msg.ip_allowlist.is_some()
+        {
             let port = msg.port.unwrap_or(deployment.port);
 
-            self.apply_service(&ns, &name, port, None, &selector)
+            self.apply_service(&ns, &name, port, Some(&labels), &selector)
                 .await?;
 
+            // A promoted canary hands all traffic back to the deployment's own Service
+            let split = match msg.canary.as_ref() {
+                Some(canary) => !canary.promote,
+                None => self.canary_exists(&ns, &name).await?,
+            };
+
             let domain = msg.domain.clone().or(deployment.domain.clone());
             let subdomain = msg.subdomain.clone().or(deployment.subdomain.clone());
-            self.apply_ingressroute(&ns, &name, domain, subdomain, port)
+            let wake_deployment_id =
+                DeploymentRepository::get_suspend_after_idle_minutes(&msg.deployment_id, &pool)
+                    .await?
+                    .map(|_| &deployment_id);
+            let middlewares = self
+                .route_middlewares(&ns, &name, wake_deployment_id, msg.ip_allowlist.as_ref())
                 .await?;
+            self.apply_ingressroute(
+                &ns,
+                &deployment_id,
+                domain,
+                subdomain,
+                Self::route_backend(&name, port, split),
+                middlewares,
+            )
+            .await?;
+        }
+
+        let promoted_image = msg
+            .canary
+            .filter(|canary| canary.promote)
+            .map(|canary| canary.canary_image);
+        if promoted_image.is_some() {
+            self.delete_canary(&ns, &name).await;
         }
 
         match msg.source {
@@ -436,7 +1225,12 @@ impl KubernetesService {
                     memory_limit_mb: preset.memory_mb
                         + deployment.addon_memory_mb.unwrap_or_default(),
                 };
-                let environment_variables = deployment.environment_variables.map(|j| j.0).flatten();
+                let environment_variables = Self::inherit_project_env_vars(
+                    &project_id,
+                    deployment.environment_variables.and_then(|j| j.0),
+                    &pool,
+                )
+                .await?;
 
                 let otel_service_name = deployment.name;
                 let otel_resource_attributes = format!(
@@ -460,11 +1254,16 @@ impl KubernetesService {
                     Some(&url),
                     image_pull_secret_data,
                     Some(deployment.port),
-                    Some(deployment.desired_replicas),
+                    (!autoscaled).then_some(deployment.desired_replicas),
                     Some(&resource_spec),
                     secret_ref,
                     environment_variables,
+                    init_containers,
+                    sidecar_containers,
+                    strategy,
+                    main_container,
                     Some(&labels),
+                    tags.as_ref(),
                     &selector,
                 )
                 .await?;
@@ -500,9 +1299,13 @@ impl KubernetesService {
                     None
                 };
 
-                let environment_variables = msg
-                    .environment_variables
-                    .or_else(|| deployment.environment_variables.map(|j| j.0).flatten());
+                let environment_variables = Self::inherit_project_env_vars(
+                    &project_id,
+                    msg.environment_variables
+                        .or_else(|| deployment.environment_variables.and_then(|j| j.0)),
+                    &pool,
+                )
+                .await?;
 
                 self.apply_deployment(
                     msg.name.as_deref(),
@@ -512,11 +1315,17 @@ impl KubernetesService {
                     Some(&url),
                     image_pull_secret_data,
                     msg.port.or(Some(deployment.port)),
-                    msg.desired_replicas.or(Some(deployment.desired_replicas)),
+                    (!autoscaled)
+                        .then(|| msg.desired_replicas.unwrap_or(deployment.desired_replicas)),
                     msg.resource_spec.as_ref(),
                     secret_ref,
                     environment_variables,
+                    init_containers,
+                    sidecar_containers,
+                    strategy,
+                    main_container,
                     Some(&labels),
+                    tags.as_ref(),
                     &selector,
                 )
                 .await?;
@@ -552,7 +1361,10 @@ impl KubernetesService {
                     &deployment_id.to_string(),
                     &preset_id.to_string(),
                     &build_id,
-                    &clone_url,
+                    GitCheckout {
+                        clone_url: &clone_url,
+                        commit_sha: None,
+                    },
                     context_path.as_deref(),
                     dockerfile_path.as_deref(),
                 )
@@ -588,7 +1400,10 @@ impl KubernetesService {
                     &deployment_id.to_string(),
                     &preset_id.to_string(),
                     &build_id,
-                    &clone_url,
+                    GitCheckout {
+                        clone_url: &clone_url,
+                        commit_sha: None,
+                    },
                     context_path.as_deref(),
                 )
                 .await?;
@@ -610,30 +1425,59 @@ impl KubernetesService {
                 )
                 .await?;
             }
-            None => {
-                // "Dumb" update: apply changes to K8s using Server-Side Apply (SSA)
-                // image is None here, so SSA will not overwrite the existing image tag in K8s
+            Some(
+                DeploymentSourceMessage::GitPush {
+                    clone_url,
+                    branch,
+                    commit_sha,
+                    context_path,
+                    dockerfile_path,
+                    use_dockerfile,
+                }
+                | DeploymentSourceMessage::GitlabPush {
+                    clone_url,
+                    branch,
+                    commit_sha,
+                    context_path,
+                    dockerfile_path,
+                    use_dockerfile,
+                },
+            ) => {
+                let message = format!(
+                    "🏗️ Pushed {} to {}. Building...",
+                    &commit_sha[..commit_sha.len().min(7)],
+                    branch
+                );
+                info!("{}", message);
 
-                let environment_variables = msg
-                    .environment_variables
-                    .or_else(|| deployment.environment_variables.map(|j| j.0).flatten());
+                let build_id = Uuid::new_v4().to_string();
+                let checkout = GitCheckout {
+                    clone_url: &clone_url,
+                    commit_sha: Some(&commit_sha),
+                };
 
-                self.apply_deployment(
-                    msg.name.as_deref(),
-                    None,
-                    &ns,
-                    &name,
-                    None,
-                    None,
-                    msg.port,
-                    msg.desired_replicas,
-                    msg.resource_spec.as_ref(),
-                    secret_ref,
-                    environment_variables,
-                    Some(&labels),
-                    &selector,
-                )
-                .await?;
+                if use_dockerfile {
+                    self.spawn_buildctl_job(
+                        &project_id.to_string(),
+                        &deployment_id.to_string(),
+                        &preset_id.to_string(),
+                        &build_id,
+                        checkout,
+                        context_path.as_deref(),
+                        dockerfile_path.as_deref(),
+                    )
+                    .await?;
+                } else {
+                    self.spawn_railpack_job(
+                        &project_id.to_string(),
+                        &deployment_id.to_string(),
+                        &preset_id.to_string(),
+                        &build_id,
+                        checkout,
+                        context_path.as_deref(),
+                    )
+                    .await?;
+                }
 
                 DeploymentEventEmitter::emit(
                     DeploymentEventEmitterInput {
@@ -642,7 +1486,7 @@ impl KubernetesService {
                         status: Some(DeploymentStatus::Building),
                         event_type: Some(DeploymentEventType::BuildStarted),
                         level: None,
-                        message: Some("Deployment updated successfully"),
+                        message: Some(&message),
                         persist_event: true,
                         publish_project: true,
                         publish_deployment: true,
@@ -652,9 +1496,73 @@ impl KubernetesService {
                 )
                 .await?;
             }
-        }
+            None => {
+                // "Dumb" update: apply changes to K8s using Server-Side Apply (SSA)
+                // image is None here, so SSA will not overwrite the existing image tag in K8s
 
-        info!("✅ Updated deployment {}", msg.deployment_id);
+                let environment_variables = Self::inherit_project_env_vars(
+                    &project_id,
+                    msg.environment_variables
+                        .or_else(|| deployment.environment_variables.and_then(|j| j.0)),
+                    &pool,
+                )
+                .await?;
+
+                self.apply_deployment(
+                    msg.name.as_deref(),
+                    None,
+                    &ns,
+                    &name,
+                    promoted_image.as_deref(),
+                    None,
+                    msg.port,
+                    match (autoscaled, autoscaling_removed) {
+                        (true, _) => None,
+                        // Replicas were owned by the HPA, hand them back explicitly
+                        (false, true) => msg.desired_replicas.or(Some(deployment.desired_replicas)),
+                        (false, false) => msg.desired_replicas,
+                    },
+                    msg.resource_spec.as_ref(),
+                    secret_ref,
+                    environment_variables,
+                    init_containers,
+                    sidecar_containers,
+                    strategy,
+                    main_container,
+                    Some(&labels),
+                    tags.as_ref(),
+                    &selector,
+                )
+                .await?;
+
+                if let Some(image) = promoted_image.as_deref() {
+                    DeploymentRepository::set_image_url(&deployment_id, image, &pool).await?;
+                }
+
+                DeploymentEventEmitter::emit(
+                    DeploymentEventEmitterInput {
+                        project_id: &project_id,
+                        deployment_id: &deployment_id,
+                        status: Some(DeploymentStatus::Building),
+                        event_type: Some(DeploymentEventType::BuildStarted),
+                        level: None,
+                        message: Some("Deployment updated successfully"),
+                        persist_event: true,
+                        publish_project: true,
+                        publish_deployment: true,
+                    },
+                    &pool,
+                    &mut con,
+                )
+                .await?;
+            }
+        }
+
+        if let Some(restart_at) = msg.restart_at {
+            self.restart_deployment(&ns, &name, restart_at).await?;
+        }
+
+        info!("✅ Updated deployment {}", msg.deployment_id);
         Ok(())
     }
 
@@ -668,15 +1576,50 @@ impl KubernetesService {
 
         let dp = DeleteParams::default();
 
+        let hpa_api: Api<HorizontalPodAutoscaler> = Api::namespaced(self.client.clone(), &ns);
+        let _ = hpa_api.delete(&name, &dp).await;
+
+        let pdb_api: Api<PodDisruptionBudget> = Api::namespaced(self.client.clone(), &ns);
+        let _ = pdb_api.delete(&format!("{}-pdb", name), &dp).await;
+
+        let prometheus_rule_api: Api<PrometheusRule> = Api::namespaced(self.client.clone(), &ns);
+        let _ = prometheus_rule_api
+            .delete(&format!("{}-alerts", name), &dp)
+            .await;
+
+        let pvc_api: Api<PersistentVolumeClaim> = Api::namespaced(self.client.clone(), &ns);
+        let lp = ListParams::default().labels(&format!(
+            "poddle.io/deployment-id={},poddle.io/retain!=true",
+            msg.deployment_id
+        ));
+        let _ = pvc_api.delete_collection(&dp, &lp).await;
+
+        let config_map_api: Api<ConfigMap> = Api::namespaced(self.client.clone(), &ns);
+        let lp =
+            ListParams::default().labels(&format!("poddle.io/deployment-id={}", deployment_id));
+        let _ = config_map_api.delete_collection(&dp, &lp).await;
+
         let ingressroute_api: Api<IngressRoute> = Api::namespaced(self.client.clone(), &ns);
         let _ = ingressroute_api.delete(&name, &dp).await;
 
+        let middleware_api: Api<Middleware> = Api::namespaced(self.client.clone(), &ns);
+        let _ = middleware_api.delete(&format!("{}-wake", name), &dp).await;
+        let _ = middleware_api
+            .delete(&format!("{}-ipallowlist", name), &dp)
+            .await;
+
+        self.delete_canary(&ns, &name).await;
+
         let service_api: Api<Service> = Api::namespaced(self.client.clone(), &ns);
         let _ = service_api.delete(&name, &dp).await;
 
         let deployment_api: Api<K8sDeployment> = Api::namespaced(self.client.clone(), &ns);
         let _ = deployment_api.delete(&name, &dp).await;
 
+        // Background propagation takes the CronJob's Jobs and their pods along
+        let cronjob_api: Api<CronJob> = Api::namespaced(self.client.clone(), &ns);
+        let _ = cronjob_api.delete(&name, &DeleteParams::background()).await;
+
         let vault_static_secret_api: Api<VaultStaticSecret> =
             Api::namespaced(self.client.clone(), &ns);
         let _ = vault_static_secret_api.delete(&name, &dp).await;
@@ -694,6 +1637,277 @@ impl KubernetesService {
         Ok(())
     }
 
+    /// Last step of an account deletion, the deployments are already gone. Deleting the
+    /// namespace cascades to everything left inside, retained volumes included
+    #[tracing::instrument(name = "kubernetes_service.delete_user", skip_all, fields(user_id = %msg.user_id), err)]
+    pub async fn delete_user(&self, msg: DeleteUserMessage) -> Result<(), AppError> {
+        let ns = format_namespace(&msg.user_id);
+
+        self.vault_service.purge_namespace(&ns).await?;
+
+        // The user may have deployed to any region, each cluster has its own namespace
+        for (region, client) in &self.clusters.regions {
+            let namespace_api: Api<Namespace> = Api::all(client.clone());
+            match namespace_api.delete(&ns, &DeleteParams::default()).await {
+                Ok(_) => {}
+                Err(kube::Error::Api(ae)) if ae.code == 404 => {}
+                Err(e) => {
+                    error!(ns=%ns, region=%region, error=%e, "🚨 Namespace deletion failed");
+                    return Err(e.into());
+                }
+            }
+        }
+
+        info!("✅ Deleted namespace of user {}", msg.user_id);
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "kubernetes_service.suspend", skip_all, fields(project_id = %msg.project_id, deployment_id = %msg.deployment_id), err)]
+    pub async fn suspend(
+        &self,
+        pool: PgPool,
+        mut con: MultiplexedConnection,
+        msg: SuspendDeploymentMessage,
+    ) -> Result<(), AppError> {
+        let ns = format_namespace(&msg.user_id);
+        let name = format_resource_name(&msg.deployment_id);
+
+        let previous_status = DeploymentRepository::get_by_id(&msg.deployment_id, &pool)
+            .await?
+            .status;
+
+        // Mark as suspended first, the reconciler skips suspended deployments
+        // and would otherwise copy the zero replicas back into the database
+        DeploymentEventEmitter::emit(
+            DeploymentEventEmitterInput {
+                project_id: &msg.project_id,
+                deployment_id: &msg.deployment_id,
+                status: Some(DeploymentStatus::Suspended),
+                event_type: Some(DeploymentEventType::StatusChanged),
+                level: None,
+                message: Some("Deployment suspended"),
+                persist_event: true,
+                publish_project: true,
+                publish_deployment: true,
+            },
+            &pool,
+            &mut con,
+        )
+        .await?;
+
+        let scaled_down = async {
+            let (deployment_type, _) =
+                DeploymentRepository::get_deployment_type(&msg.deployment_id, &pool).await?;
+            if deployment_type == DeploymentType::CronJob {
+                self.suspend_cronjob(&ns, &name, true).await?;
+            } else {
+                // An HPA stops scaling a Deployment once it has zero replicas, so it can stay in place
+                self.scale_deployment(&ns, &name, 0).await?;
+                self.scale_canary(&ns, &name, 0).await?;
+            }
+            Ok::<_, AppError>(())
+        }
+        .await;
+
+        // The pods keep running, billing and the user have to see the status they run under
+        if let Err(e) = scaled_down {
+            let _ = DeploymentEventEmitter::emit(
+                DeploymentEventEmitterInput {
+                    project_id: &msg.project_id,
+                    deployment_id: &msg.deployment_id,
+                    status: Some(previous_status),
+                    event_type: Some(DeploymentEventType::StatusChanged),
+                    level: Some(DeploymentEventLevel::Error),
+                    message: Some("Failed to suspend deployment"),
+                    persist_event: true,
+                    publish_project: true,
+                    publish_deployment: true,
+                },
+                &pool,
+                &mut con,
+            )
+            .await
+            .inspect_err(|e| {
+                error!(deployment_id = %msg.deployment_id, error = %e, "🚨 Failed to restore the status of a deployment that wasn't suspended");
+            });
+            return Err(e);
+        }
+
+        info!("✅ Suspended deployment {}", msg.deployment_id);
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "kubernetes_service.resume", skip_all, fields(project_id = %msg.project_id, deployment_id = %msg.deployment_id), err)]
+    pub async fn resume(
+        &self,
+        pool: PgPool,
+        mut con: MultiplexedConnection,
+        msg: ResumeDeploymentMessage,
+    ) -> Result<(), AppError> {
+        let ns = format_namespace(&msg.user_id);
+        let name = format_resource_name(&msg.deployment_id);
+
+        let deployment = DeploymentRepository::get_by_id(&msg.deployment_id, &pool)
+            .await
+            .inspect_err(|e| {
+                error!(ns=%ns, name=%name, error = %e, "🚨 Failed to get deployment from database");
+            })?;
+
+        let (deployment_type, _) =
+            DeploymentRepository::get_deployment_type(&msg.deployment_id, &pool).await?;
+        if deployment_type == DeploymentType::CronJob {
+            self.suspend_cronjob(&ns, &name, false).await?;
+        } else {
+            self.scale_deployment(&ns, &name, deployment.desired_replicas)
+                .await?;
+            self.scale_canary(&ns, &name, deployment.desired_replicas)
+                .await?;
+        }
+
+        DeploymentEventEmitter::emit(
+            DeploymentEventEmitterInput {
+                project_id: &msg.project_id,
+                deployment_id: &msg.deployment_id,
+                status: Some(DeploymentStatus::Starting),
+                event_type: Some(DeploymentEventType::StatusChanged),
+                level: None,
+                message: Some("Deployment resumed"),
+                persist_event: true,
+                publish_project: true,
+                publish_deployment: true,
+            },
+            &pool,
+            &mut con,
+        )
+        .await?;
+
+        info!(
+            "✅ Resumed deployment {} with {} replicas",
+            msg.deployment_id, deployment.desired_replicas
+        );
+        Ok(())
+    }
+
+    /// Swaps the pull secret under a running deployment. The new checksum annotation rolls the
+    /// pods over one by one, so replicas never drop to zero
+    #[tracing::instrument(name = "kubernetes_service.rotate_registry_credentials", skip_all, fields(project_id = %msg.project_id, deployment_id = %msg.deployment_id), err)]
+    pub async fn rotate_registry_credentials(
+        &self,
+        pool: PgPool,
+        mut con: MultiplexedConnection,
+        msg: RotateRegistryCredentialsMessage,
+    ) -> Result<(), AppError> {
+        let ns = format_namespace(&msg.user_id);
+        let name = format_resource_name(&msg.deployment_id);
+
+        let secret_api: Api<K8sSecret> = Api::namespaced(self.client.clone(), &ns);
+        match secret_api
+            .delete(&format!("{}-registry", name), &DeleteParams::default())
+            .await
+        {
+            Ok(_) => {}
+            Err(kube::Error::Api(ae)) if ae.code == 404 => {}
+            Err(e) => {
+                error!(ns=%ns, name=%name, error=%e, "🚨 Failed to delete image pull secret");
+                return Err(e.into());
+            }
+        }
+
+        let (secret_name, checksum) = self
+            .apply_image_pull_secret(&ns, &name, &msg.image_pull_secret)
+            .await?;
+
+        let deployment_api: Api<K8sDeployment> = Api::namespaced(self.client.clone(), &ns);
+        let patch = serde_json::json!({
+            "spec": {
+                "template": {
+                    "metadata": {
+                        "annotations": { "poddle.io/registry-checksum": checksum }
+                    },
+                    "spec": {
+                        "imagePullSecrets": [{ "name": secret_name }]
+                    }
+                }
+            }
+        });
+        deployment_api
+            .patch(&name, &PatchParams::default(), &Patch::Merge(&patch))
+            .await
+            .inspect_err(|e| {
+                error!(ns=%ns, name=%name, error=%e, "🚨 Deployment imagePullSecrets patch failed");
+            })?;
+
+        DeploymentEventEmitter::emit(
+            DeploymentEventEmitterInput {
+                project_id: &msg.project_id,
+                deployment_id: &msg.deployment_id,
+                status: None,
+                event_type: Some(DeploymentEventType::DeploymentUpdated),
+                level: None,
+                message: Some("Registry credentials rotated"),
+                persist_event: true,
+                publish_project: false,
+                publish_deployment: true,
+            },
+            &pool,
+            &mut con,
+        )
+        .await?;
+
+        info!(
+            "✅ Rotated registry credentials of deployment {}",
+            msg.deployment_id
+        );
+        Ok(())
+    }
+
+    /// Merges the new values into the deployment's Vault secret. The pods are rolled over by VSO's
+    /// rollout restart target once it has synced the new version into the Kubernetes Secret, a
+    /// restart of our own could run before that and start the pods with the old values
+    #[tracing::instrument(name = "kubernetes_service.rotate_secrets", skip_all, fields(project_id = %msg.project_id, deployment_id = %msg.deployment_id), err)]
+    pub async fn rotate_secrets(
+        &self,
+        pool: PgPool,
+        mut con: MultiplexedConnection,
+        msg: RotateSecretsMessage,
+    ) -> Result<(), AppError> {
+        let ns = format_namespace(&msg.user_id);
+        let deployment_id = msg.deployment_id.to_string();
+
+        let previous = self.vault_service.read_secrets(&ns, &deployment_id).await?;
+        let mut secrets = previous.clone();
+        secrets.extend(msg.secrets);
+        let keys = secrets.keys().cloned().collect();
+        let fields_changed = changed_secret_keys(&previous, &secrets);
+
+        self.vault_service
+            .store_secrets(&ns, &deployment_id, secrets)
+            .await?;
+        self.audit_secret_change(&ns, &msg.deployment_id, &msg.user_id, fields_changed)
+            .await;
+        DeploymentRepository::set_secret_keys(&msg.deployment_id, keys, &pool).await?;
+
+        DeploymentEventEmitter::emit(
+            DeploymentEventEmitterInput {
+                project_id: &msg.project_id,
+                deployment_id: &msg.deployment_id,
+                status: None,
+                event_type: Some(DeploymentEventType::DeploymentUpdated),
+                level: None,
+                message: Some("Secrets rotated, pods restart once the new values are synced"),
+                persist_event: true,
+                publish_project: false,
+                publish_deployment: true,
+            },
+            &pool,
+            &mut con,
+        )
+        .await?;
+
+        info!("✅ Rotated secrets of deployment {}", msg.deployment_id);
+        Ok(())
+    }
+
     // ============================================================================================
     // PRIVATE APPLY FUNCTIONS
     // ============================================================================================
@@ -712,16 +1926,21 @@ impl KubernetesService {
         resource_spec: Option<&ResourceSpec>,
         secret_ref: Option<String>,
         environment_variables: Option<HashMap<String, String>>,
+        init_containers: Option<Vec<Container>>,
+        sidecar_containers: Vec<Container>,
+        strategy: Option<DeploymentStrategy>,
+        main_container: MainContainerSettings,
         labels: Option<&BTreeMap<String, String>>,
+        tags: Option<&HashMap<String, String>>,
         selector: &BTreeMap<String, String>,
-    ) -> Result<(), AppError> {
+    ) -> Result<K8sDeployment, AppError> {
         let api: Api<K8sDeployment> = Api::namespaced(self.client.clone(), ns);
 
         // We use default() to initialize, then only set fields that are Some.
         // k8s-openapi structs are #[serde(skip_serializing_if = "Option::is_none")]
         // so this creates the perfect "Partial JSON" automatically.
 
-        let container = self.create_container(
+        let mut container = self.create_container(
             otel_service_name,
             otel_resource_attributes,
             name,
@@ -731,6 +1950,22 @@ impl KubernetesService {
             secret_ref,
             environment_variables,
         );
+        if let Some(image_pull_policy) = main_container.image_pull_policy {
+            container.image_pull_policy = Some(image_pull_policy);
+        }
+        container.liveness_probe = main_container.liveness_probe;
+        container.readiness_probe = main_container.readiness_probe;
+        container.startup_probe = main_container.startup_probe;
+
+        // Always listed, SSA would otherwise prune the volumes of an earlier apply
+        let (mut volumes, mut volume_mounts) = self.claimed_volumes(ns, selector).await?;
+        let config_maps = self.config_map_sources(ns, selector).await?;
+        volumes.extend(config_maps.volumes);
+        volume_mounts.extend(config_maps.volume_mounts);
+        if !volumes.is_empty() {
+            container.volume_mounts = Some(volume_mounts);
+        }
+        Self::prepend_env_from(&mut container, config_maps.env_from);
 
         let (pull_secret, pull_secret_checksum) = match image_pull_secret_data {
             Some((n, c)) => (Some(n), Some(c)),
@@ -742,9 +1977,17 @@ impl KubernetesService {
         // PodSpec:
         //      image_pull_secrets: Option<Vec<LocalObjectReference>>
         //      containers: Vec<Container>
+        // The user's main image always stays first, sidecars follow it
+        let mut containers = vec![container];
+        containers.extend(sidecar_containers);
+
         let pod_spec = PodSpec {
+            service_account_name: Some(USER_APP_SERVICE_ACCOUNT.to_string()),
             image_pull_secrets,
-            containers: vec![container],
+            init_containers,
+            containers,
+            volumes: (!volumes.is_empty()).then_some(volumes),
+            topology_spread_constraints: main_container.topology_spread_constraints,
             ..Default::default()
         };
 
@@ -754,6 +1997,10 @@ impl KubernetesService {
             let a = annotations.get_or_insert_with(BTreeMap::new);
             a.insert("poddle.io/registry-checksum".to_string(), sum);
         }
+        if let Some(sum) = config_maps.checksum {
+            let a = annotations.get_or_insert_with(BTreeMap::new);
+            a.insert("poddle.io/config-checksum".to_string(), sum);
+        }
 
         // PodTemplateSpec:
         //      metadata: Option<ObjectMeta>
@@ -773,6 +2020,7 @@ impl KubernetesService {
         //      template: PodTemplateSpec
         let deployment_spec = DeploymentSpec {
             replicas: desired_replicas,
+            strategy,
             selector: LabelSelector {
                 match_labels: Some(selector.clone()),
                 ..Default::default()
@@ -784,43 +2032,271 @@ impl KubernetesService {
         // K8sDeployment:
         //      metadata: ObjectMeta
         //      spec: Option<DeploymentSpec>
+        // Tags only label the Deployment itself, pods would otherwise roll on every tag change.
+        // They can't shadow the platform's labels
+        let mut deployment_labels = labels.cloned();
+        for (key, value) in tags.into_iter().flatten() {
+            deployment_labels
+                .get_or_insert_with(BTreeMap::new)
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
+
         let deployment = K8sDeployment {
             metadata: ObjectMeta {
                 name: Some(name.to_string()),
                 namespace: Some(ns.to_string()),
-                labels: labels.cloned(),
+                labels: deployment_labels,
                 ..Default::default()
             },
             spec: Some(deployment_spec),
             ..Default::default()
         };
 
-        api.patch(
-            name,
-            &PatchParams::apply("poddle-provisioner").force(),
-            &Patch::Apply(&deployment),
-        )
-        .await
-        .map_err(|e| {
-            error!(ns=%ns, name=%name, error = %e, "🚨 Deployment SSA failed");
-            AppError::InternalServerError(format!("🚨 Deployment SSA failed: {}", e))
+        api.patch(name, &self.apply_params(), &Patch::Apply(&deployment))
+            .await
+            .map_err(|e| {
+                error!(ns=%ns, name=%name, error = %e, "🚨 Deployment SSA failed");
+                AppError::kubernetes_quota_exceeded(&e).unwrap_or_else(|| e.into())
+            })
+    }
+
+    /// A CronJob running the main container built for a Deployment, minus the port and probes.
+    /// `desired_replicas` pods run per scheduled Job and a run is skipped while the previous one
+    /// is still going
+    #[tracing::instrument(name = "kubernetes_service.apply_cronjob", skip_all, err)]
+    async fn apply_cronjob(
+        &self,
+        ns: &str,
+        name: &str,
+        msg: &CreateDeploymentMessage,
+        mut container: Container,
+        image_pull_secret_data: Option<(String, String)>,
+        labels: &BTreeMap<String, String>,
+    ) -> Result<CronJob, AppError> {
+        let api: Api<CronJob> = Api::namespaced(self.client.clone(), ns);
+
+        let schedule = msg.schedule.as_deref().ok_or_else(|| {
+            AppError::ValidationError("CronJob deployment without a schedule".into())
         })?;
 
-        Ok(())
+        let selector = BTreeMap::from([(
+            "poddle.io/deployment-id".to_string(),
+            msg.deployment_id.to_string(),
+        )]);
+        let (mut volumes, mut volume_mounts) = self.claimed_volumes(ns, &selector).await?;
+        let config_maps = self.config_map_sources(ns, &selector).await?;
+        volumes.extend(config_maps.volumes);
+        volume_mounts.extend(config_maps.volume_mounts);
+        if !volumes.is_empty() {
+            container.volume_mounts = Some(volume_mounts);
+        }
+        Self::prepend_env_from(&mut container, config_maps.env_from);
+
+        let (pull_secret, pull_secret_checksum) = match image_pull_secret_data {
+            Some((n, c)) => (Some(n), Some(c)),
+            None => (None, None),
+        };
+
+        let pod_spec = PodSpec {
+            service_account_name: Some(USER_APP_SERVICE_ACCOUNT.to_string()),
+            image_pull_secrets: pull_secret.map(|name| vec![LocalObjectReference { name }]),
+            init_containers: msg
+                .init_containers
+                .as_ref()
+                .map(|containers| containers.iter().map(Self::extra_container).collect()),
+            containers: vec![container],
+            volumes: (!volumes.is_empty()).then_some(volumes),
+            restart_policy: Some("OnFailure".to_string()),
+            ..Default::default()
+        };
+
+        let mut annotations = pull_secret_checksum
+            .map(|sum| BTreeMap::from([("poddle.io/registry-checksum".to_string(), sum)]));
+        if let Some(sum) = config_maps.checksum {
+            annotations
+                .get_or_insert_with(BTreeMap::new)
+                .insert("poddle.io/config-checksum".to_string(), sum);
+        }
+
+        let job_spec = JobSpec {
+            parallelism: Some(msg.desired_replicas),
+            template: PodTemplateSpec {
+                metadata: Some(ObjectMeta {
+                    labels: Some(labels.clone()),
+                    annotations,
+                    ..Default::default()
+                }),
+                spec: Some(pod_spec),
+            },
+            ..Default::default()
+        };
+
+        // Same as Deployments, tags label the CronJob only and never shadow the platform's labels
+        let mut cronjob_labels = labels.clone();
+        for (key, value) in msg.tags.iter().flatten() {
+            cronjob_labels
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
+
+        let cronjob = CronJob {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                namespace: Some(ns.to_string()),
+                labels: Some(cronjob_labels),
+                ..Default::default()
+            },
+            spec: Some(CronJobSpec {
+                schedule: schedule.to_string(),
+                time_zone: Some("Etc/UTC".to_string()),
+                concurrency_policy: Some("Forbid".to_string()),
+                successful_jobs_history_limit: Some(CRON_JOB_HISTORY_LIMIT),
+                failed_jobs_history_limit: Some(CRON_JOB_HISTORY_LIMIT),
+                job_template: JobTemplateSpec {
+                    metadata: Some(ObjectMeta {
+                        labels: Some(labels.clone()),
+                        ..Default::default()
+                    }),
+                    spec: Some(job_spec),
+                },
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        api.patch(name, &self.apply_params(), &Patch::Apply(&cronjob))
+            .await
+            .map_err(|e| {
+                error!(ns=%ns, name=%name, error = %e, "🚨 CronJob SSA failed");
+                AppError::kubernetes_quota_exceeded(&e).unwrap_or_else(|| e.into())
+            })
     }
 
-    fn create_container(
+    /// Rebuilds the main container of a live CronJob from the update, whatever the update leaves
+    /// out stays as it is. Runs only ever take a prebuilt image, so build sources are refused
+    #[tracing::instrument(name = "kubernetes_service.update_cronjob", skip_all, err)]
+    async fn update_cronjob(
         &self,
-        otel_service_name: Option<&str>,
-        otel_resource_attributes: Option<&str>,
+        ns: &str,
         name: &str,
-        image: Option<&str>,
-        port: Option<i32>,
-        resource_spec: Option<&ResourceSpec>,
+        msg: &UpdateDeploymentMessage,
+        deployment_name: &str,
         secret_ref: Option<String>,
         environment_variables: Option<HashMap<String, String>>,
-    ) -> Container {
-        // Container:
+    ) -> Result<CronJob, AppError> {
+        let api: Api<CronJob> = Api::namespaced(self.client.clone(), ns);
+
+        let (image, image_pull_secret) = match msg.source.as_ref() {
+            None => (None, None),
+            Some(DeploymentSourceMessage::Image {
+                url,
+                image_pull_secret,
+            }) => (Some(url.clone()), image_pull_secret.as_ref()),
+            Some(_) => {
+                return Err(AppError::ValidationError(
+                    "A CronJob deployment runs a prebuilt image, its source must be an Image"
+                        .into(),
+                ));
+            }
+        };
+        let image_pull_secret_data = match image_pull_secret {
+            Some(secret) => Some(self.apply_image_pull_secret(ns, name, secret).await?),
+            None => None,
+        };
+
+        let mut cronjob = api.get(name).await.inspect_err(|e| {
+            error!(ns=%ns, name=%name, error=%e, "🚨 Failed to get cron job");
+        })?;
+        let job_spec = cronjob
+            .spec
+            .as_mut()
+            .and_then(|spec| spec.job_template.spec.as_mut())
+            .ok_or_else(|| AppError::InternalServerError("CronJob without a job spec".into()))?;
+
+        if let Some(desired_replicas) = msg.desired_replicas {
+            job_spec.parallelism = Some(desired_replicas);
+        }
+
+        let template = &mut job_spec.template;
+        if let Some((_, checksum)) = image_pull_secret_data.as_ref() {
+            template
+                .metadata
+                .get_or_insert_default()
+                .annotations
+                .get_or_insert_default()
+                .insert("poddle.io/registry-checksum".to_string(), checksum.clone());
+        }
+        let pod_spec = template
+            .spec
+            .as_mut()
+            .ok_or_else(|| AppError::InternalServerError("CronJob without a pod spec".into()))?;
+        if let Some((pull_secret, _)) = image_pull_secret_data {
+            pod_spec.image_pull_secrets = Some(vec![LocalObjectReference { name: pull_secret }]);
+        }
+
+        let live = pod_spec
+            .containers
+            .iter_mut()
+            .find(|c| c.name == name)
+            .ok_or_else(|| AppError::InternalServerError("CronJob without its container".into()))?;
+        let otel_resource_attributes = format!(
+            "project_id={},deployment_id={},managed_by=poddle",
+            msg.project_id, msg.deployment_id
+        );
+        let mut container = self.create_container(
+            Some(msg.name.as_deref().unwrap_or(deployment_name)),
+            Some(&otel_resource_attributes),
+            name,
+            image.as_deref().or(live.image.as_deref()),
+            None,
+            msg.resource_spec.as_ref(),
+            secret_ref,
+            environment_variables,
+        );
+        container.image_pull_policy = live.image_pull_policy.take();
+        container.volume_mounts = live.volume_mounts.take();
+        if msg.resource_spec.is_none() {
+            container.resources = live.resources.take();
+        }
+        *live = container;
+
+        api.replace(name, &PostParams::default(), &cronjob)
+            .await
+            .map_err(|e| {
+                error!(ns=%ns, name=%name, error = %e, "🚨 CronJob update failed");
+                AppError::kubernetes_quota_exceeded(&e).unwrap_or_else(|| e.into())
+            })
+    }
+
+    /// Merge patch of `spec.suspend` only, a suspended CronJob starts no new Jobs and lets
+    /// running ones finish
+    #[tracing::instrument(name = "kubernetes_service.suspend_cronjob", skip_all, fields(suspend = suspend), err)]
+    async fn suspend_cronjob(&self, ns: &str, name: &str, suspend: bool) -> Result<(), AppError> {
+        let api: Api<CronJob> = Api::namespaced(self.client.clone(), ns);
+        let patch = serde_json::json!({ "spec": { "suspend": suspend } });
+
+        api.patch(name, &PatchParams::default(), &Patch::Merge(&patch))
+            .await
+            .inspect_err(|e| {
+                error!(ns=%ns, name=%name, error=%e, "🚨 CronJob suspend patch failed");
+            })?;
+
+        Ok(())
+    }
+
+    fn create_container(
+        &self,
+        otel_service_name: Option<&str>,
+        otel_resource_attributes: Option<&str>,
+        name: &str,
+        image: Option<&str>,
+        port: Option<i32>,
+        resource_spec: Option<&ResourceSpec>,
+        secret_ref: Option<String>,
+        environment_variables: Option<HashMap<String, String>>,
+    ) -> Container {
+        // Container:
         //      name: String
         //      image: Option<String>
         //      image_pull_policy: Option<String>
@@ -836,6 +2312,8 @@ impl KubernetesService {
         let ports = port.map(|container_port| {
             vec![ContainerPort {
                 container_port,
+                // Probes without an explicit port target it by name, so they follow port changes
+                name: Some(MAIN_PORT_NAME.into()),
                 protocol: Some("TCP".into()),
                 ..Default::default()
             }]
@@ -914,331 +2392,1529 @@ impl KubernetesService {
         container
     }
 
-    #[tracing::instrument(name = "kubernetes_service.apply_service", skip_all, err)]
-    async fn apply_service(
+    #[tracing::instrument(name = "kubernetes_service.apply_hpa", skip_all, err)]
+    async fn apply_hpa(
         &self,
         ns: &str,
         name: &str,
-        port: i32,
-        labels: Option<&BTreeMap<String, String>>,
-        selector: &BTreeMap<String, String>,
+        autoscaling: &AutoscalingSpec,
+        labels: &BTreeMap<String, String>,
     ) -> Result<(), AppError> {
-        let api: Api<Service> = Api::namespaced(self.client.clone(), ns);
+        let api: Api<HorizontalPodAutoscaler> = Api::namespaced(self.client.clone(), ns);
 
-        let service = Service {
+        let hpa = HorizontalPodAutoscaler {
             metadata: ObjectMeta {
                 name: Some(name.to_string()),
                 namespace: Some(ns.to_string()),
-                labels: labels.cloned(),
+                labels: Some(labels.clone()),
                 ..Default::default()
             },
-            spec: Some(ServiceSpec {
-                selector: Some(selector.clone()),
-                ports: Some(vec![ServicePort {
-                    name: Some("http".to_string()),
-                    port,
-                    target_port: Some(IntOrString::Int(port)),
-                    protocol: Some("TCP".to_string()),
+            spec: Some(HorizontalPodAutoscalerSpec {
+                scale_target_ref: CrossVersionObjectReference {
+                    api_version: Some("apps/v1".to_string()),
+                    kind: "Deployment".to_string(),
+                    name: name.to_string(),
+                },
+                min_replicas: Some(autoscaling.min_replicas),
+                max_replicas: autoscaling.max_replicas,
+                metrics: Some(vec![MetricSpec {
+                    type_: "Resource".to_string(),
+                    resource: Some(ResourceMetricSource {
+                        name: "cpu".to_string(),
+                        target: MetricTarget {
+                            type_: "Utilization".to_string(),
+                            average_utilization: Some(
+                                autoscaling.target_cpu_utilization_percentage,
+                            ),
+                            ..Default::default()
+                        },
+                    }),
                     ..Default::default()
                 }]),
-                // Valid options are ExternalName, ClusterIP, NodePort, and LoadBalancer
-                type_: Some("ClusterIP".to_string()),
                 ..Default::default()
             }),
             ..Default::default()
         };
 
-        // let patch = json!({
-        //     "apiVersion": "v1",
-        //     "kind": "Service",
-        //     "metadata": {
-        //         "name": name,
-        //         "namespace": ns,
-        //         "labels": { "poddle.io/deployment": name }
-        //     },
-        //     "spec": {
-        //         "type": "ClusterIP",
-        //         "selector": { "poddle.io/deployment": name },
-        //         "ports": [{
-        //             "name": "http",
-        //             "port": port,
-        //             "targetPort": port,
-        //             "protocol": "TCP"
-        //         }],
-        //     }
-        // });
-
-        api.patch(
-            name,
-            &PatchParams::apply("poddle-provisioner").force(),
-            &Patch::Apply(&service),
-        )
-        .await
-        .map_err(|e| {
-            error!(ns=%ns, name=%name, error=%e, "🚨 Service SSA failed");
-            AppError::InternalServerError(format!("🚨 Service SSA failed: {}", e))
-        })?;
+        api.patch(name, &self.apply_params(), &Patch::Apply(&hpa))
+            .await
+            .map_err(|e| {
+                error!(ns=%ns, name=%name, error=%e, "🚨 HorizontalPodAutoscaler SSA failed");
+                AppError::kubernetes_quota_exceeded(&e).unwrap_or_else(|| e.into())
+            })?;
 
         Ok(())
     }
 
-    #[tracing::instrument(name = "kubernetes_service.apply_ingressroute", skip_all, err)]
-    async fn apply_ingressroute(
-        &self,
-        ns: &str,
-        name: &str,
-        domain: Option<String>,
-        subdomain: Option<String>,
-        port: i32,
-    ) -> Result<(), AppError> {
-        let api: Api<IngressRoute> = Api::namespaced(self.client.clone(), ns);
+    /// Returns `false` when there was no HPA to delete
+    #[tracing::instrument(name = "kubernetes_service.delete_hpa", skip_all, err)]
+    async fn delete_hpa(&self, ns: &str, name: &str) -> Result<bool, AppError> {
+        let api: Api<HorizontalPodAutoscaler> = Api::namespaced(self.client.clone(), ns);
 
-        let mut routes = vec![];
-        let mut domains = vec![];
+        match api.delete(name, &DeleteParams::default()).await {
+            Ok(_) => Ok(true),
+            Err(kube::Error::Api(ae)) if ae.code == 404 => Ok(false),
+            Err(e) => {
+                error!(ns=%ns, name=%name, error=%e, "🚨 HorizontalPodAutoscaler delete failed");
+                Err(e.into())
+            }
+        }
+    }
 
-        // Helper to add route
-        // let mut add_route = |host: String| {
-        //     routes.push(json!({
-        //         "match": format!("Host(`{}`)", host),
-        //         "services": [{ "name": name, "port": port }]
-        //     }));
-        //     domains.push(json!({ "main": host }));
-        // };
+    async fn hpa_exists(&self, ns: &str, name: &str) -> Result<bool, AppError> {
+        let api: Api<HorizontalPodAutoscaler> = Api::namespaced(self.client.clone(), ns);
 
-        // if let Some(sub) = subdomain {
-        //     add_route(format!("{}.{}", sub, self.cfg.traefik.base_domain));
-        // }
-        // if let Some(dom) = domain {
-        //     add_route(dom);
-        // }
+        let hpa = api.get_opt(name).await.inspect_err(|e| {
+            error!(ns=%ns, name=%name, error=%e, "🚨 Failed to get HorizontalPodAutoscaler");
+        })?;
 
-        // if routes.is_empty() {
-        //     return Ok(());
-        // }
+        Ok(hpa.is_some())
+    }
 
-        // let patch = json!({
-        //     "apiVersion": "traefik.io/v1alpha1",
-        //     "kind": "IngressRoute",
-        //     "metadata": {
-        //         "name": name,
-        //         "namespace": ns
-        //     },
-        //     "spec": {
-        //         "entryPoints": self.cfg.traefik.entry_points,
-        //         "routes": routes,
-        //         "tls": {
-        //             "domains": domains,
-        //             "certResolver": self.cfg.cert_manager.cluster_issuer
-        //         }
-        //     }
-        // });
+    /// Applies `{name}-alerts` with a rule per threshold, the usage is taken relative to the pods'
+    /// limits as reported by kube-state-metrics. Without any threshold the rule is deleted
+    #[tracing::instrument(name = "kubernetes_service.sync_prometheus_rule", skip_all, err)]
+    async fn sync_prometheus_rule(
+        &self,
+        ns: &str,
+        name: &str,
+        deployment_id: &Uuid,
+        thresholds: &AlertThreshold,
+    ) -> Result<(), AppError> {
+        let api: Api<PrometheusRule> = Api::namespaced(self.client.clone(), ns);
+        let rule_name = format!("{}-alerts", name);
 
-        // Uses Default TLSStore (Wildcard)
-        // We create wildcard secret from using cert-manager
-        // In Local we create wildcard secret using Vault PKI or self signed, in Prod created by Let's Encrypt
-        if let Some(sub) = subdomain {
-            let full_subdomain = format!("{}.{}", sub, self.cfg.traefik.base_domain);
-            routes.push(IngressRouteRoutes {
-                r#match: format!("Host(`{}`)", full_subdomain),
-                services: Some(vec![IngressRouteRoutesServices {
-                    name: name.to_string(),
-                    port: Some(IntOrString::Int(port)),
-                    ..Default::default()
-                }]),
-                ..Default::default()
-            });
+        let selector = format!(r#"namespace="{}", pod=~"{}-.*", container!="""#, ns, name);
+        let for_ = Some(format!("{}m", thresholds.window_minutes));
+        let labels = Some(BTreeMap::from([
+            (
+                "poddle_io_deployment_id".to_string(),
+                deployment_id.to_string(),
+            ),
+            ("severity".to_string(), "warning".to_string()),
+        ]));
 
-            domains.push(IngressRouteTlsDomains {
-                main: Some(full_subdomain),
-                sans: None,
+        let mut rules = Vec::new();
+        if let Some(cpu_percent) = thresholds.cpu_percent {
+            rules.push(PrometheusRuleRule {
+                alert: "HighCPU".to_string(),
+                expr: format!(
+                    r#"sum by (pod) (rate(container_cpu_usage_seconds_total{{{selector}}}[5m])) / sum by (pod) (kube_pod_container_resource_limits{{{selector}, resource="cpu"}}) * 100 > {cpu_percent}"#,
+                ),
+                for_: for_.clone(),
+                labels: labels.clone(),
+                annotations: Some(BTreeMap::from([(
+                    "summary".to_string(),
+                    format!("CPU usage of {{{{ $labels.pod }}}} is above {}% of the limit", cpu_percent),
+                )])),
             });
         }
-
-        // Uses CertResolver (Traefik native, Let's Encrypt)
-        if let Some(user_domain) = domain {
-            routes.push(IngressRouteRoutes {
-                r#match: format!("Host(`{}`)", user_domain),
-                services: Some(vec![IngressRouteRoutesServices {
-                    name: name.to_string(),
-                    port: Some(IntOrString::Int(port)),
-                    ..Default::default()
-                }]),
-                ..Default::default()
-            });
-            domains.push(IngressRouteTlsDomains {
-                main: Some(user_domain),
-                sans: None,
+        if let Some(memory_percent) = thresholds.memory_percent {
+            rules.push(PrometheusRuleRule {
+                alert: "HighMemory".to_string(),
+                expr: format!(
+                    r#"sum by (pod) (container_memory_working_set_bytes{{{selector}}}) / sum by (pod) (kube_pod_container_resource_limits{{{selector}, resource="memory"}}) * 100 > {memory_percent}"#,
+                ),
+                for_: for_.clone(),
+                labels: labels.clone(),
+                annotations: Some(BTreeMap::from([(
+                    "summary".to_string(),
+                    format!("Memory usage of {{{{ $labels.pod }}}} is above {}% of the limit", memory_percent),
+                )])),
             });
         }
 
-        if routes.is_empty() {
-            return Ok(());
+        if rules.is_empty() {
+            return match api.delete(&rule_name, &DeleteParams::default()).await {
+                Ok(_) => Ok(()),
+                Err(kube::Error::Api(ae)) if ae.code == 404 => Ok(()),
+                Err(e) => {
+                    error!(ns=%ns, name=%rule_name, error=%e, "🚨 PrometheusRule delete failed");
+                    Err(e.into())
+                }
+            };
         }
 
-        let ingress_route = IngressRoute {
+        let prometheus_rule = PrometheusRule {
             metadata: ObjectMeta {
-                name: Some(name.to_string()),
+                name: Some(rule_name.clone()),
                 namespace: Some(ns.to_string()),
+                labels: Some(BTreeMap::from([(
+                    "poddle.io/deployment-id".to_string(),
+                    deployment_id.to_string(),
+                )])),
                 ..Default::default()
             },
-            spec: IngressRouteSpec {
-                entry_points: self.cfg.traefik.entry_points.clone(),
-                routes,
-                tls: Some(IngressRouteTls {
-                    // This uses "letsencrypt"
-                    // Traefik will use this resolver for domains that don't match the TLSStore.
-                    cert_resolver: Some(self.cfg.cert_manager.cluster_issuer.clone()),
-                    domains: Some(domains),
-                    // We set secret_name to NONE.
-                    // - Subdomains will match the Wildcard in the Default TLSStore automatically.
-                    // - Custom Domains will trigger the cert_resolver.
-                    ..Default::default()
-                }),
-                ..Default::default()
+            spec: PrometheusRuleSpec {
+                groups: vec![PrometheusRuleGroup {
+                    name: rule_name.clone(),
+                    rules,
+                }],
             },
         };
 
         api.patch(
-            name,
-            &PatchParams::apply("poddle-provisioner").force(),
-            &Patch::Apply(&ingress_route),
+            &rule_name,
+            &self.apply_params(),
+            &Patch::Apply(&prometheus_rule),
         )
         .await
-        .map_err(|e| {
-            error!(ns=%ns, name=%name, error=%e, "🚨 IngressRoute SSA failed");
-            AppError::InternalServerError(format!("🚨 IngressRoute SSA failed: {}", e))
+        .inspect_err(|e| {
+            error!(ns=%ns, name=%rule_name, error=%e, "🚨 PrometheusRule SSA failed");
         })?;
 
         Ok(())
     }
 
-    /// Create image pull secret
-    #[tracing::instrument(name = "kubernetes_service.apply_image_pull_secret", skip_all, err)]
-    async fn apply_image_pull_secret(
+    /// Attaches or detaches the HPA and notifies subscribers about it
+    async fn sync_hpa(
         &self,
         ns: &str,
-        name: &str,
-        secret: &ImagePullSecret,
-    ) -> Result<(String, String), AppError> {
-        let secret_name = format!("{}-registry", name);
-
-        let auth = base64::engine::general_purpose::STANDARD
-            .encode(format!("{}:{}", secret.username, secret.secret));
+        autoscaling: Option<&AutoscalingSpec>,
+        project_id: &Uuid,
+        deployment_id: &Uuid,
+        pool: &PgPool,
+        con: &mut MultiplexedConnection,
+    ) -> Result<(), AppError> {
+        let name = format_resource_name(deployment_id);
 
-        let dockerconfig = serde_json::json!({
-            "auths": {
-                secret.server.clone(): {
-                    "username": secret.username,
-                    "password": secret.secret,
-                    "auth": auth
+        let message = match autoscaling {
+            Some(autoscaling) => {
+                let mut labels = BTreeMap::new();
+                labels.insert("poddle.io/managed-by".into(), "poddle".into());
+                labels.insert("poddle.io/project-id".into(), project_id.to_string());
+                labels.insert("poddle.io/deployment-id".into(), deployment_id.to_string());
+
+                self.apply_hpa(ns, &name, autoscaling, &labels).await?;
+                format!(
+                    "Autoscaling enabled ({}-{} replicas, target CPU {}%)",
+                    autoscaling.min_replicas,
+                    autoscaling.max_replicas,
+                    autoscaling.target_cpu_utilization_percentage
+                )
+            }
+            None => {
+                if !self.delete_hpa(ns, &name).await? {
+                    return Ok(());
                 }
+                "Autoscaling disabled".to_string()
             }
-        })
-        .to_string();
+        };
 
-        // Calculate SHA256 Checksum of the config
-        // When secrets change pods doesn't restart until timeout beacuse name and image not changed
-        // if we set checksum as label to pod they does restart
-        let checksum = sha256::digest(&dockerconfig);
+        DeploymentEventEmitter::emit(
+            DeploymentEventEmitterInput {
+                project_id,
+                deployment_id,
+                status: None,
+                event_type: Some(DeploymentEventType::SystemMessage),
+                level: None,
+                message: Some(&message),
+                persist_event: true,
+                publish_project: true,
+                publish_deployment: true,
+            },
+            pool,
+            con,
+        )
+        .await?;
 
-        // ByteString type from k8s-openapi automatically Base64 encodes its contents
-        let data = Some(BTreeMap::from([(
-            ".dockerconfigjson".to_string(),
-            ByteString(dockerconfig.into_bytes()),
-        )]));
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "kubernetes_service.apply_pdb", skip_all, err)]
+    async fn apply_pdb(&self, ns: &str, name: &str, deployment_id: &Uuid) -> Result<(), AppError> {
+        let api: Api<PodDisruptionBudget> = Api::namespaced(self.client.clone(), ns);
+        let pdb_name = format!("{}-pdb", name);
+
+        let pdb = PodDisruptionBudget {
+            metadata: ObjectMeta {
+                name: Some(pdb_name.clone()),
+                namespace: Some(ns.to_string()),
+                ..Default::default()
+            },
+            spec: Some(PodDisruptionBudgetSpec {
+                min_available: Some(IntOrString::Int(1)),
+                selector: Some(LabelSelector {
+                    match_labels: Some(BTreeMap::from([(
+                        "poddle.io/deployment-id".to_string(),
+                        deployment_id.to_string(),
+                    )])),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        api.patch(&pdb_name, &self.apply_params(), &Patch::Apply(&pdb))
+            .await
+            .inspect_err(|e| {
+                error!(ns=%ns, name=%pdb_name, error=%e, "🚨 PodDisruptionBudget SSA failed");
+            })?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "kubernetes_service.delete_pdb", skip_all, err)]
+    async fn delete_pdb(&self, ns: &str, name: &str) -> Result<(), AppError> {
+        let api: Api<PodDisruptionBudget> = Api::namespaced(self.client.clone(), ns);
+        let pdb_name = format!("{}-pdb", name);
+
+        match api.delete(&pdb_name, &DeleteParams::default()).await {
+            Ok(_) => Ok(()),
+            Err(kube::Error::Api(ae)) if ae.code == 404 => Ok(()),
+            Err(e) => {
+                error!(ns=%ns, name=%pdb_name, error=%e, "🚨 PodDisruptionBudget delete failed");
+                Err(e.into())
+            }
+        }
+    }
+
+    /// The fewest replicas the deployment runs with, the HPA's floor when it owns them
+    fn min_replicas(msg: &CreateDeploymentMessage) -> i32 {
+        msg.autoscaling
+            .as_ref()
+            .map_or(msg.desired_replicas, |autoscaling| autoscaling.min_replicas)
+    }
+
+    /// With a single replica `minAvailable: 1` would block node drains for good,
+    /// so the budget is only enforced while there are more
+    async fn update_pdb(
+        &self,
+        ns: &str,
+        name: &str,
+        deployment_id: &Uuid,
+        desired_replicas: i32,
+    ) -> Result<(), AppError> {
+        if desired_replicas > 1 {
+            self.apply_pdb(ns, name, deployment_id).await
+        } else {
+            self.delete_pdb(ns, name).await
+        }
+    }
+
+    #[tracing::instrument(name = "kubernetes_service.apply_pvc", skip_all, fields(volume = %volume.name), err)]
+    async fn apply_pvc(
+        &self,
+        ns: &str,
+        name: &str,
+        project_id: &Uuid,
+        deployment_id: &Uuid,
+        volume: &VolumeSpec,
+    ) -> Result<(), AppError> {
+        let api: Api<PersistentVolumeClaim> = Api::namespaced(self.client.clone(), ns);
+        let pvc_name = format!("{}-{}", name, volume.name);
+
+        let mut labels = BTreeMap::new();
+        labels.insert("poddle.io/managed-by".into(), "poddle".into());
+        labels.insert("poddle.io/project-id".into(), project_id.to_string());
+        labels.insert("poddle.io/deployment-id".into(), deployment_id.to_string());
+        labels.insert("poddle.io/volume-name".into(), volume.name.clone());
+        labels.insert(
+            "poddle.io/retain".into(),
+            volume.retain_on_delete.to_string(),
+        );
+
+        let pvc = PersistentVolumeClaim {
+            metadata: ObjectMeta {
+                name: Some(pvc_name.clone()),
+                namespace: Some(ns.to_string()),
+                labels: Some(labels),
+                annotations: Some(BTreeMap::from([(
+                    "poddle.io/mount-path".to_string(),
+                    volume.mount_path.clone(),
+                )])),
+                ..Default::default()
+            },
+            spec: Some(PersistentVolumeClaimSpec {
+                access_modes: Some(vec![volume.access_mode.to_string()]),
+                storage_class_name: volume.storage_class.clone(),
+                resources: Some(VolumeResourceRequirements {
+                    requests: Some(BTreeMap::from([(
+                        "storage".to_string(),
+                        Quantity(format!("{}Gi", volume.size_gi)),
+                    )])),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        api.patch(&pvc_name, &self.apply_params(), &Patch::Apply(&pvc))
+            .await
+            .map_err(|e| {
+                error!(ns=%ns, name=%pvc_name, error=%e, "🚨 PersistentVolumeClaim SSA failed");
+                AppError::kubernetes_quota_exceeded(&e).unwrap_or_else(|| e.into())
+            })?;
+
+        Ok(())
+    }
+
+    /// Pod volumes and main container mounts for every claim of the deployment,
+    /// the mount path travels on the claim itself
+    async fn claimed_volumes(
+        &self,
+        ns: &str,
+        selector: &BTreeMap<String, String>,
+    ) -> Result<(Vec<Volume>, Vec<VolumeMount>), AppError> {
+        let Some(deployment_id) = selector.get("poddle.io/deployment-id") else {
+            return Ok((Vec::new(), Vec::new()));
+        };
+
+        let api: Api<PersistentVolumeClaim> = Api::namespaced(self.client.clone(), ns);
+        let lp =
+            ListParams::default().labels(&format!("poddle.io/deployment-id={}", deployment_id));
+        let claims = api.list(&lp).await.inspect_err(|e| {
+            error!(ns=%ns, error=%e, "🚨 Failed to list PersistentVolumeClaims");
+        })?;
+
+        let mut volumes = Vec::new();
+        let mut volume_mounts = Vec::new();
+        for claim in claims {
+            let (Some(claim_name), Some(volume_name), Some(mount_path)) = (
+                claim.metadata.name.as_ref(),
+                claim
+                    .metadata
+                    .labels
+                    .as_ref()
+                    .and_then(|l| l.get("poddle.io/volume-name")),
+                claim
+                    .metadata
+                    .annotations
+                    .as_ref()
+                    .and_then(|a| a.get("poddle.io/mount-path")),
+            ) else {
+                continue;
+            };
+
+            volumes.push(Volume {
+                name: volume_name.clone(),
+                persistent_volume_claim: Some(PersistentVolumeClaimVolumeSource {
+                    claim_name: claim_name.clone(),
+                    read_only: None,
+                }),
+                ..Default::default()
+            });
+            volume_mounts.push(VolumeMount {
+                name: volume_name.clone(),
+                mount_path: mount_path.clone(),
+                ..Default::default()
+            });
+        }
+
+        Ok((volumes, volume_mounts))
+    }
+
+    #[tracing::instrument(name = "kubernetes_service.apply_config_map", skip_all, fields(config_map = %config_map.name), err)]
+    async fn apply_config_map(
+        &self,
+        ns: &str,
+        name: &str,
+        project_id: &Uuid,
+        deployment_id: &Uuid,
+        config_map: &ConfigMapSpec,
+    ) -> Result<(), AppError> {
+        let api: Api<ConfigMap> = Api::namespaced(self.client.clone(), ns);
+        let config_map_name = format!("{}-{}", name, config_map.name);
+
+        let mut labels = BTreeMap::new();
+        labels.insert("poddle.io/managed-by".into(), "poddle".into());
+        labels.insert("poddle.io/project-id".into(), project_id.to_string());
+        labels.insert("poddle.io/deployment-id".into(), deployment_id.to_string());
+        labels.insert("poddle.io/config-map-name".into(), config_map.name.clone());
+
+        let object = ConfigMap {
+            metadata: ObjectMeta {
+                name: Some(config_map_name.clone()),
+                namespace: Some(ns.to_string()),
+                labels: Some(labels),
+                annotations: config_map.mount_path.as_ref().map(|mount_path| {
+                    BTreeMap::from([("poddle.io/mount-path".to_string(), mount_path.clone())])
+                }),
+                ..Default::default()
+            },
+            data: Some(config_map.data.clone().into_iter().collect()),
+            ..Default::default()
+        };
+
+        api.patch(
+            &config_map_name,
+            &self.apply_params(),
+            &Patch::Apply(&object),
+        )
+        .await
+        .map_err(|e| {
+            error!(ns=%ns, name=%config_map_name, error=%e, "🚨 ConfigMap SSA failed");
+            AppError::kubernetes_quota_exceeded(&e).unwrap_or_else(|| e.into())
+        })?;
+
+        Ok(())
+    }
+
+    /// Applies `config_maps` and deletes the deployment's config maps left out of them
+    async fn sync_config_maps(
+        &self,
+        ns: &str,
+        name: &str,
+        project_id: &Uuid,
+        deployment_id: &Uuid,
+        config_maps: &[ConfigMapSpec],
+    ) -> Result<(), AppError> {
+        for config_map in config_maps {
+            self.apply_config_map(ns, name, project_id, deployment_id, config_map)
+                .await?;
+        }
+
+        let mut selector = format!("poddle.io/deployment-id={}", deployment_id);
+        if !config_maps.is_empty() {
+            let kept: Vec<&str> = config_maps.iter().map(|c| c.name.as_str()).collect();
+            selector.push_str(&format!(
+                ",poddle.io/config-map-name notin ({})",
+                kept.join(",")
+            ));
+        }
+
+        let api: Api<ConfigMap> = Api::namespaced(self.client.clone(), ns);
+        api.delete_collection(
+            &DeleteParams::default(),
+            &ListParams::default().labels(&selector),
+        )
+        .await
+        .inspect_err(|e| {
+            error!(ns=%ns, name=%name, error=%e, "🚨 Failed to delete stale ConfigMaps");
+        })?;
+
+        Ok(())
+    }
+
+    /// Volumes, mounts and env sources for every config map of the deployment,
+    /// the mount path travels on the config map itself
+    async fn config_map_sources(
+        &self,
+        ns: &str,
+        selector: &BTreeMap<String, String>,
+    ) -> Result<ConfigMapSources, AppError> {
+        let Some(deployment_id) = selector.get("poddle.io/deployment-id") else {
+            return Ok(ConfigMapSources::default());
+        };
+
+        let api: Api<ConfigMap> = Api::namespaced(self.client.clone(), ns);
+        let lp =
+            ListParams::default().labels(&format!("poddle.io/deployment-id={}", deployment_id));
+        let mut config_maps = api.list(&lp).await.inspect_err(|e| {
+            error!(ns=%ns, error=%e, "🚨 Failed to list ConfigMaps");
+        })?;
+        config_maps
+            .items
+            .sort_by(|a, b| a.metadata.name.cmp(&b.metadata.name));
+
+        let mut sources = ConfigMapSources::default();
+        let mut digest = String::new();
+        for config_map in config_maps {
+            let (Some(config_map_name), Some(short_name)) = (
+                config_map.metadata.name.as_ref(),
+                config_map
+                    .metadata
+                    .labels
+                    .as_ref()
+                    .and_then(|l| l.get("poddle.io/config-map-name")),
+            ) else {
+                continue;
+            };
+
+            for (key, value) in config_map.data.iter().flatten() {
+                digest.push_str(&format!("{}/{}={}\n", short_name, key, value));
+            }
+
+            match config_map
+                .metadata
+                .annotations
+                .as_ref()
+                .and_then(|a| a.get("poddle.io/mount-path"))
+            {
+                Some(mount_path) => {
+                    // Prefixed so it can't collide with a claimed volume of the same name
+                    let volume_name = format!("config-{}", short_name);
+                    sources.volumes.push(Volume {
+                        name: volume_name.clone(),
+                        config_map: Some(ConfigMapVolumeSource {
+                            name: config_map_name.clone(),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    });
+                    sources.volume_mounts.push(VolumeMount {
+                        name: volume_name,
+                        mount_path: mount_path.clone(),
+                        read_only: Some(true),
+                        ..Default::default()
+                    });
+                }
+                None => sources.env_from.push(EnvFromSource {
+                    config_map_ref: Some(ConfigMapEnvSource {
+                        name: config_map_name.clone(),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+            }
+        }
+        sources.checksum = (!digest.is_empty()).then(|| sha256::digest(&digest));
+
+        Ok(sources)
+    }
+
+    /// Later sources win on a shared key, so the secrets keep precedence over config maps
+    fn prepend_env_from(container: &mut Container, env_from: Vec<EnvFromSource>) {
+        if env_from.is_empty() {
+            return;
+        }
+        let secrets = container.env_from.take().unwrap_or_default();
+        container.env_from = Some(env_from.into_iter().chain(secrets).collect());
+    }
+
+    #[tracing::instrument(name = "kubernetes_service.apply_service", skip_all, err)]
+    async fn apply_service(
+        &self,
+        ns: &str,
+        name: &str,
+        port: i32,
+        labels: Option<&BTreeMap<String, String>>,
+        selector: &BTreeMap<String, String>,
+    ) -> Result<(), AppError> {
+        let api: Api<Service> = Api::namespaced(self.client.clone(), ns);
+
+        let service = Service {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                namespace: Some(ns.to_string()),
+                labels: labels.cloned(),
+                ..Default::default()
+            },
+            spec: Some(ServiceSpec {
+                selector: Some(selector.clone()),
+                ports: Some(vec![ServicePort {
+                    name: Some("http".to_string()),
+                    port,
+                    target_port: Some(IntOrString::Int(port)),
+                    protocol: Some("TCP".to_string()),
+                    ..Default::default()
+                }]),
+                // Valid options are ExternalName, ClusterIP, NodePort, and LoadBalancer
+                type_: Some("ClusterIP".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        // let patch = json!({
+        //     "apiVersion": "v1",
+        //     "kind": "Service",
+        //     "metadata": {
+        //         "name": name,
+        //         "namespace": ns,
+        //         "labels": { "poddle.io/deployment": name }
+        //     },
+        //     "spec": {
+        //         "type": "ClusterIP",
+        //         "selector": { "poddle.io/deployment": name },
+        //         "ports": [{
+        //             "name": "http",
+        //             "port": port,
+        //             "targetPort": port,
+        //             "protocol": "TCP"
+        //         }],
+        //     }
+        // });
+
+        api.patch(name, &self.apply_params(), &Patch::Apply(&service))
+            .await
+            .map_err(|e| {
+                error!(ns=%ns, name=%name, error=%e, "🚨 Service SSA failed");
+                AppError::kubernetes_quota_exceeded(&e).unwrap_or_else(|| e.into())
+            })?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "kubernetes_service.apply_ingressroute", skip_all, err)]
+    async fn apply_ingressroute(
+        &self,
+        ns: &str,
+        deployment_id: &Uuid,
+        domain: Option<String>,
+        subdomain: Option<String>,
+        backend: IngressRouteRoutesServices,
+        middlewares: Option<Vec<IngressRouteRoutesMiddlewares>>,
+    ) -> Result<(), AppError> {
+        let api: Api<IngressRoute> = Api::namespaced(self.client.clone(), ns);
+        let name = &format_resource_name(deployment_id);
+
+        let mut routes = vec![];
+        let mut domains = vec![];
+
+        // Helper to add route
+        // let mut add_route = |host: String| {
+        //     routes.push(json!({
+        //         "match": format!("Host(`{}`)", host),
+        //         "services": [{ "name": name, "port": port }]
+        //     }));
+        //     domains.push(json!({ "main": host }));
+        // };
+
+        // if let Some(sub) = subdomain {
+        //     add_route(format!("{}.{}", sub, self.cfg.traefik.base_domain));
+        // }
+        // if let Some(dom) = domain {
+        //     add_route(dom);
+        // }
+
+        // if routes.is_empty() {
+        //     return Ok(());
+        // }
+
+        // let patch = json!({
+        //     "apiVersion": "traefik.io/v1alpha1",
+        //     "kind": "IngressRoute",
+        //     "metadata": {
+        //         "name": name,
+        //         "namespace": ns
+        //     },
+        //     "spec": {
+        //         "entryPoints": self.cfg.traefik.entry_points,
+        //         "routes": routes,
+        //         "tls": {
+        //             "domains": domains,
+        //             "certResolver": self.cfg.cert_manager.cluster_issuer
+        //         }
+        //     }
+        // });
+
+        // Uses Default TLSStore (Wildcard)
+        // We create wildcard secret from using cert-manager
+        // In Local we create wildcard secret using Vault PKI or self signed, in Prod created by Let's Encrypt
+        if let Some(sub) = subdomain {
+            let full_subdomain = format!("{}.{}", sub, self.cfg.traefik.base_domain);
+            routes.push(IngressRouteRoutes {
+                r#match: format!("Host(`{}`)", full_subdomain),
+                services: Some(vec![backend.clone()]),
+                middlewares: middlewares.clone(),
+                ..Default::default()
+            });
+
+            domains.push(IngressRouteTlsDomains {
+                main: Some(full_subdomain),
+                sans: None,
+            });
+        }
+
+        // Uses CertResolver (Traefik native, Let's Encrypt)
+        if let Some(user_domain) = domain {
+            self.apply_acme_egress_policy(ns).await?;
+
+            routes.push(IngressRouteRoutes {
+                r#match: format!("Host(`{}`)", user_domain),
+                services: Some(vec![backend.clone()]),
+                middlewares: middlewares.clone(),
+                ..Default::default()
+            });
+            domains.push(IngressRouteTlsDomains {
+                main: Some(user_domain),
+                sans: None,
+            });
+        }
+
+        if routes.is_empty() {
+            return Ok(());
+        }
+
+        let ingress_route = IngressRoute {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                namespace: Some(ns.to_string()),
+                labels: Some(BTreeMap::from([(
+                    "poddle.io/deployment-id".to_string(),
+                    deployment_id.to_string(),
+                )])),
+                ..Default::default()
+            },
+            spec: IngressRouteSpec {
+                entry_points: self.cfg.traefik.entry_points.clone(),
+                routes,
+                tls: Some(IngressRouteTls {
+                    // This uses "letsencrypt"
+                    // Traefik will use this resolver for domains that don't match the TLSStore.
+                    cert_resolver: Some(self.cfg.cert_manager.cluster_issuer.clone()),
+                    domains: Some(domains),
+                    // We set secret_name to NONE.
+                    // - Subdomains will match the Wildcard in the Default TLSStore automatically.
+                    // - Custom Domains will trigger the cert_resolver.
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        };
+
+        api.patch(name, &self.apply_params(), &Patch::Apply(&ingress_route))
+            .await
+            .inspect_err(|e| {
+                error!(ns=%ns, name=%name, error=%e, "🚨 IngressRoute SSA failed");
+            })?;
+
+        Ok(())
+    }
+
+    /// Middlewares every route of the deployment goes through. The allowlist comes first,
+    /// rejected clients shouldn't wake a suspended deployment
+    async fn route_middlewares(
+        &self,
+        ns: &str,
+        name: &str,
+        wake_deployment_id: Option<&Uuid>,
+        ip_allowlist: Option<&Vec<String>>,
+    ) -> Result<Option<Vec<IngressRouteRoutesMiddlewares>>, AppError> {
+        let mut middlewares = vec![];
+        if let Some(allowlist) = self
+            .sync_ip_allowlist_middleware(ns, name, ip_allowlist)
+            .await?
+        {
+            middlewares.push(allowlist);
+        }
+        if let Some(deployment_id) = wake_deployment_id
+            && let Some(wake) = self.apply_wake_middleware(ns, name, deployment_id).await?
+        {
+            middlewares.extend(wake);
+        }
+
+        Ok((!middlewares.is_empty()).then_some(middlewares))
+    }
+
+    /// Routes go to the deployment's Service, or to its weighted split while a canary is live
+    fn route_backend(name: &str, port: i32, split: bool) -> IngressRouteRoutesServices {
+        if split {
+            IngressRouteRoutesServices {
+                name: format!("{}-split", name),
+                kind: Some(IngressRouteRoutesServicesKind::TraefikService),
+                ..Default::default()
+            }
+        } else {
+            IngressRouteRoutesServices {
+                name: name.to_string(),
+                port: Some(IntOrString::Int(port)),
+                ..Default::default()
+            }
+        }
+    }
+
+    async fn canary_exists(&self, ns: &str, name: &str) -> Result<bool, AppError> {
+        let api: Api<TraefikService> = Api::namespaced(self.client.clone(), ns);
+        let split_name = format!("{}-split", name);
+
+        let split = api.get_opt(&split_name).await.inspect_err(|e| {
+            error!(ns=%ns, name=%split_name, error=%e, "🚨 Failed to get TraefikService");
+        })?;
+
+        Ok(split.is_some())
+    }
+
+    /// Runs `canary_image` as `{name}-canary` next to the deployment and splits the traffic
+    /// between both Services by `canary_weight`. The canary copies the live pod spec so only the
+    /// image differs
+    #[tracing::instrument(name = "kubernetes_service.apply_canary", skip_all, err)]
+    async fn apply_canary(
+        &self,
+        ns: &str,
+        name: &str,
+        deployment_id: &Uuid,
+        canary: &CanaryConfig,
+        port: i32,
+    ) -> Result<(), AppError> {
+        let Some(primary) = self.get_live_deployment_spec(ns, name).await? else {
+            return Err(AppError::BadRequest(format!(
+                "Deployment {} isn't running, there is nothing to canary against",
+                deployment_id
+            )));
+        };
+
+        let canary_name = format!("{}-canary", name);
+        let replicas = canary_replicas(primary.replicas.unwrap_or(1), canary.canary_weight);
+
+        // Without the deployment id label the primary's selector, Service and PDB never match
+        // canary pods
+        let mut labels = primary
+            .template
+            .metadata
+            .and_then(|metadata| metadata.labels)
+            .unwrap_or_default();
+        labels.remove("poddle.io/deployment-id");
+        labels.insert("poddle.io/canary-of".into(), deployment_id.to_string());
+
+        let mut selector = BTreeMap::new();
+        selector.insert("poddle.io/canary-of".to_string(), deployment_id.to_string());
+
+        // Volume claims are ReadWriteOnce and already mounted by the primary pods
+        let mut pod_spec = primary.template.spec.unwrap_or_default();
+        let claimed: Vec<String> = pod_spec
+            .volumes
+            .iter()
+            .flatten()
+            .filter(|volume| volume.persistent_volume_claim.is_some())
+            .map(|volume| volume.name.clone())
+            .collect();
+        if let Some(volumes) = pod_spec.volumes.as_mut() {
+            volumes.retain(|volume| !claimed.contains(&volume.name));
+        }
+        for container in pod_spec.containers.iter_mut() {
+            if let Some(mounts) = container.volume_mounts.as_mut() {
+                mounts.retain(|mount| !claimed.contains(&mount.name));
+            }
+        }
+        if let Some(container) = pod_spec.containers.first_mut() {
+            container.image = Some(canary.canary_image.clone());
+        }
+
+        let mut annotations = BTreeMap::new();
+        annotations.insert(
+            "poddle.io/canary-weight".to_string(),
+            canary.canary_weight.to_string(),
+        );
+
+        let deployment = K8sDeployment {
+            metadata: ObjectMeta {
+                name: Some(canary_name.clone()),
+                namespace: Some(ns.to_string()),
+                labels: Some(labels.clone()),
+                annotations: Some(annotations),
+                ..Default::default()
+            },
+            spec: Some(DeploymentSpec {
+                replicas: Some(replicas),
+                selector: LabelSelector {
+                    match_labels: Some(selector.clone()),
+                    ..Default::default()
+                },
+                template: PodTemplateSpec {
+                    metadata: Some(ObjectMeta {
+                        labels: Some(labels.clone()),
+                        ..Default::default()
+                    }),
+                    spec: Some(pod_spec),
+                },
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let deployment_api: Api<K8sDeployment> = Api::namespaced(self.client.clone(), ns);
+        deployment_api
+            .patch(
+                &canary_name,
+                &self.apply_params(),
+                &Patch::Apply(&deployment),
+            )
+            .await
+            .map_err(|e| {
+                error!(ns=%ns, name=%canary_name, error = %e, "🚨 Canary Deployment SSA failed");
+                AppError::kubernetes_quota_exceeded(&e).unwrap_or_else(|| e.into())
+            })?;
+
+        self.apply_service(ns, &canary_name, port, Some(&labels), &selector)
+            .await?;
+
+        let weight = i64::from(canary.canary_weight);
+        let split_name = format!("{}-split", name);
+        let split = TraefikService {
+            metadata: ObjectMeta {
+                name: Some(split_name.clone()),
+                namespace: Some(ns.to_string()),
+                ..Default::default()
+            },
+            spec: TraefikServiceSpec {
+                weighted: Some(TraefikServiceWeighted {
+                    services: Some(vec![
+                        TraefikServiceWeightedServices {
+                            name: name.to_string(),
+                            port: Some(IntOrString::Int(port)),
+                            weight: Some(100 - weight),
+                            ..Default::default()
+                        },
+                        TraefikServiceWeightedServices {
+                            name: canary_name,
+                            port: Some(IntOrString::Int(port)),
+                            weight: Some(weight),
+                            ..Default::default()
+                        },
+                    ]),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        };
+
+        let split_api: Api<TraefikService> = Api::namespaced(self.client.clone(), ns);
+        split_api
+            .patch(&split_name, &self.apply_params(), &Patch::Apply(&split))
+            .await
+            .inspect_err(|e| {
+                error!(ns=%ns, name=%split_name, error=%e, "🚨 TraefikService SSA failed");
+            })?;
+
+        info!(
+            "🐤 Canary {} takes {}% of traffic with {} replicas",
+            canary.canary_image, canary.canary_weight, replicas
+        );
+
+        Ok(())
+    }
+
+    /// Keeps the canary at its share of the deployment's replicas
+    async fn scale_canary(
+        &self,
+        ns: &str,
+        name: &str,
+        primary_replicas: i32,
+    ) -> Result<(), AppError> {
+        let api: Api<K8sDeployment> = Api::namespaced(self.client.clone(), ns);
+        let canary_name = format!("{}-canary", name);
+
+        let canary = api.get_opt(&canary_name).await.inspect_err(|e| {
+            error!(ns=%ns, name=%canary_name, error=%e, "🚨 Failed to get canary deployment");
+        })?;
+        let Some(canary) = canary else {
+            return Ok(());
+        };
+
+        let weight = canary
+            .metadata
+            .annotations
+            .as_ref()
+            .and_then(|annotations| annotations.get("poddle.io/canary-weight"))
+            .and_then(|weight| weight.parse().ok())
+            .unwrap_or_default();
+
+        self.scale_deployment(ns, &canary_name, canary_replicas(primary_replicas, weight))
+            .await
+    }
+
+    /// Routes have to stop pointing at the split before this runs
+    async fn delete_canary(&self, ns: &str, name: &str) {
+        let dp = DeleteParams::default();
+        let canary_name = format!("{}-canary", name);
+
+        let split_api: Api<TraefikService> = Api::namespaced(self.client.clone(), ns);
+        let _ = split_api.delete(&format!("{}-split", name), &dp).await;
+
+        let service_api: Api<Service> = Api::namespaced(self.client.clone(), ns);
+        let _ = service_api.delete(&canary_name, &dp).await;
+
+        let deployment_api: Api<K8sDeployment> = Api::namespaced(self.client.clone(), ns);
+        let _ = deployment_api.delete(&canary_name, &dp).await;
+    }
+
+    /// A suspended deployment has no endpoints, so Traefik answers 503 and this middleware
+    /// fetches the response from compute-api's wake endpoint instead, which resumes it
+    #[tracing::instrument(name = "kubernetes_service.apply_wake_middleware", skip_all, err)]
+    async fn apply_wake_middleware(
+        &self,
+        ns: &str,
+        name: &str,
+        deployment_id: &Uuid,
+    ) -> Result<Option<Vec<IngressRouteRoutesMiddlewares>>, AppError> {
+        let Some(wake_service) = &self.cfg.traefik.wake_service else {
+            warn!(ns=%ns, name=%name, "⚠️ No wake service configured, idle deployment won't wake up on requests");
+            return Ok(None);
+        };
+
+        let middleware_name = format!("{}-wake", name);
+        let middleware = Middleware {
+            metadata: ObjectMeta {
+                name: Some(middleware_name.clone()),
+                namespace: Some(ns.to_string()),
+                ..Default::default()
+            },
+            spec: MiddlewareSpec {
+                errors: Some(MiddlewareErrors {
+                    status: Some(vec!["503".into()]),
+                    query: Some(format!(
+                        "/api/v1/compute/deployments/{}/wake",
+                        deployment_id
+                    )),
+                    service: Some(MiddlewareErrorsService {
+                        name: wake_service.name.clone(),
+                        namespace: Some(wake_service.namespace.clone()),
+                        port: Some(IntOrString::Int(wake_service.port)),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        };
+
+        let api: Api<Middleware> = Api::namespaced(self.client.clone(), ns);
+        api.patch(
+            &middleware_name,
+            &self.apply_params(),
+            &Patch::Apply(&middleware),
+        )
+        .await
+        .inspect_err(|e| {
+            error!(ns=%ns, name=%middleware_name, error=%e, "🚨 Middleware SSA failed");
+        })?;
+
+        Ok(Some(vec![IngressRouteRoutesMiddlewares {
+            name: middleware_name,
+            namespace: Some(ns.to_string()),
+        }]))
+    }
+
+    /// Applies `{name}-ipallowlist` with the given source ranges, an empty list deletes it.
+    /// Without a list the middleware already in place, if any, stays referenced
+    #[tracing::instrument(
+        name = "kubernetes_service.sync_ip_allowlist_middleware",
+        skip_all,
+        err
+    )]
+    async fn sync_ip_allowlist_middleware(
+        &self,
+        ns: &str,
+        name: &str,
+        ip_allowlist: Option<&Vec<String>>,
+    ) -> Result<Option<IngressRouteRoutesMiddlewares>, AppError> {
+        let api: Api<Middleware> = Api::namespaced(self.client.clone(), ns);
+        let middleware_name = format!("{}-ipallowlist", name);
+        let reference = IngressRouteRoutesMiddlewares {
+            name: middleware_name.clone(),
+            namespace: Some(ns.to_string()),
+        };
+
+        match ip_allowlist {
+            None => {
+                let existing = api.get_opt(&middleware_name).await.inspect_err(|e| {
+                    error!(ns=%ns, name=%middleware_name, error=%e, "🚨 Failed to get Middleware");
+                })?;
+                Ok(existing.map(|_| reference))
+            }
+            Some(ranges) if ranges.is_empty() => {
+                match api.delete(&middleware_name, &DeleteParams::default()).await {
+                    Ok(_) => {}
+                    Err(kube::Error::Api(ae)) if ae.code == 404 => {}
+                    Err(e) => {
+                        error!(ns=%ns, name=%middleware_name, error=%e, "🚨 Middleware delete failed");
+                        return Err(e.into());
+                    }
+                }
+                Ok(None)
+            }
+            Some(ranges) => {
+                let middleware = Middleware {
+                    metadata: ObjectMeta {
+                        name: Some(middleware_name.clone()),
+                        namespace: Some(ns.to_string()),
+                        ..Default::default()
+                    },
+                    spec: MiddlewareSpec {
+                        ip_allow_list: Some(MiddlewareIpAllowList {
+                            source_range: Some(ranges.clone()),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    },
+                };
+
+                api.patch(
+                    &middleware_name,
+                    &self.apply_params(),
+                    &Patch::Apply(&middleware),
+                )
+                .await
+                .inspect_err(|e| {
+                    error!(ns=%ns, name=%middleware_name, error=%e, "🚨 Middleware SSA failed");
+                })?;
+
+                Ok(Some(reference))
+            }
+        }
+    }
+
+    /// Create image pull secret
+    #[tracing::instrument(name = "kubernetes_service.apply_image_pull_secret", skip_all, err)]
+    async fn apply_image_pull_secret(
+        &self,
+        ns: &str,
+        name: &str,
+        secret: &ImagePullSecret,
+    ) -> Result<(String, String), AppError> {
+        let secret_name = format!("{}-registry", name);
+
+        let auth = base64::engine::general_purpose::STANDARD
+            .encode(format!("{}:{}", secret.username, secret.secret));
+
+        let dockerconfig = serde_json::json!({
+            "auths": {
+                secret.server.clone(): {
+                    "username": secret.username,
+                    "password": secret.secret,
+                    "auth": auth
+                }
+            }
+        })
+        .to_string();
+
+        // Calculate SHA256 Checksum of the config
+        // When secrets change pods doesn't restart until timeout beacuse name and image not changed
+        // if we set checksum as label to pod they does restart
+        let checksum = sha256::digest(&dockerconfig);
+
+        // ByteString type from k8s-openapi automatically Base64 encodes its contents
+        let data = Some(BTreeMap::from([(
+            ".dockerconfigjson".to_string(),
+            ByteString(dockerconfig.into_bytes()),
+        )]));
+
+        let secret = K8sSecret {
+            metadata: ObjectMeta {
+                name: Some(secret_name.clone()),
+                namespace: Some(ns.to_string()),
+                ..Default::default()
+            },
+            type_: Some("kubernetes.io/dockerconfigjson".to_string()),
+            data,
+            ..Default::default()
+        };
+
+        // let patch = json!({
+        //     "apiVersion": "v1",
+        //     "kind": "Secret",
+        //     "metadata": { "name": secret_name, "namespace": ns },
+        //     "type": "kubernetes.io/dockerconfigjson",
+        //     "data": {
+        //         ".dockerconfigjson": base64::engine::general_purpose::STANDARD.encode(
+        //             json!({ "auths": { creds.server.clone(): { "username": creds.username, "password": creds.secret, "auth": auth } } }).to_string()
+        //         )
+        //     }
+        // });
+
+        let api: Api<K8sSecret> = Api::namespaced(self.client.clone(), ns);
+
+        api.patch(&secret_name, &self.apply_params(), &Patch::Apply(&secret))
+            .await
+            .map_err(|e| {
+                error!(ns = %ns, error = %e, "🚨 Image Pull Secret SSA failed");
+                AppError::kubernetes_quota_exceeded(&e).unwrap_or_else(|| e.into())
+            })?;
+
+        Ok((secret_name, checksum))
+    }
+
+    #[tracing::instrument(name = "kubernetes_service.ensure_namespace", skip_all, fields(user_id = %user_id), err)]
+    async fn ensure_namespace(&self, user_id: &Uuid) -> Result<String, AppError> {
+        let name = format_namespace(&user_id);
+
+        let api: Api<Namespace> = Api::all(self.client.clone());
+
+        match api.get(&name).await {
+            // Older namespaces predate the ServiceAccount, SSA makes this a no-op afterwards
+            Ok(_) => {
+                self.create_namespace_rbac(&name).await?;
+                return Ok(name);
+            }
+
+            Err(kube::Error::Api(ae)) if ae.code == 404 => {
+                info!(user_id = %user_id, "🏗️ Creating namespace {}", name);
+            }
+
+            Err(e) => {
+                error!(
+                    user_id = %user_id,
+                    error = %e,
+                    "⚠️ Failed to check namespace existence"
+                );
+                return Err(e.into());
+            }
+        }
+
+        let mut labels = BTreeMap::new();
+        labels.insert("user-id".to_string(), user_id.to_string());
+
+        let new_ns = Namespace {
+            metadata: ObjectMeta {
+                name: Some(name.clone()),
+                labels: Some(labels),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        api.create(&PostParams::default(), &new_ns)
+            .await
+            .inspect_err(|e| {
+                error!(
+                    user_id = %user_id,
+                    error = %e,
+                    "🚨 Failed to create namespace"
+                );
+            })?;
+
+        self.apply_namespace_quota(&name, &self.cfg.namespace_quota)
+            .await?;
+        self.create_network_policy(&name).await?;
+        self.create_namespace_rbac(&name).await?;
+
+        Ok(name)
+    }
+
+    /// ServiceAccount the user's pods run as instead of `default`, bound to a Role that only
+    /// reaches into its own namespace
+    #[tracing::instrument(name = "kubernetes_service.create_namespace_rbac", skip_all, fields(ns = %ns), err)]
+    async fn create_namespace_rbac(&self, ns: &str) -> Result<(), AppError> {
+        let (resources, verbs) = match self.cfg.user_app_role.as_ref() {
+            Some(role) => (role.resources.clone(), role.verbs.clone()),
+            None => (
+                vec!["secrets".to_string(), "configmaps".to_string()],
+                vec!["get".to_string(), "list".to_string(), "watch".to_string()],
+            ),
+        };
+
+        let mut labels = BTreeMap::new();
+        labels.insert("poddle.io/managed-by".into(), "poddle".into());
+
+        let metadata = ObjectMeta {
+            name: Some(USER_APP_SERVICE_ACCOUNT.to_string()),
+            namespace: Some(ns.to_string()),
+            labels: Some(labels),
+            ..Default::default()
+        };
+
+        let service_account = ServiceAccount {
+            metadata: metadata.clone(),
+            ..Default::default()
+        };
+        let role = Role {
+            metadata: metadata.clone(),
+            rules: Some(vec![PolicyRule {
+                api_groups: Some(vec!["".to_string()]),
+                resources: Some(resources),
+                verbs,
+                ..Default::default()
+            }]),
+        };
+        let role_binding = RoleBinding {
+            metadata,
+            role_ref: RoleRef {
+                api_group: "rbac.authorization.k8s.io".to_string(),
+                kind: "Role".to_string(),
+                name: USER_APP_SERVICE_ACCOUNT.to_string(),
+            },
+            subjects: Some(vec![Subject {
+                kind: "ServiceAccount".to_string(),
+                name: USER_APP_SERVICE_ACCOUNT.to_string(),
+                namespace: Some(ns.to_string()),
+                ..Default::default()
+            }]),
+        };
+
+        let api: Api<ServiceAccount> = Api::namespaced(self.client.clone(), ns);
+        api.patch(
+            USER_APP_SERVICE_ACCOUNT,
+            &self.apply_params(),
+            &Patch::Apply(&service_account),
+        )
+        .await
+        .inspect_err(|e| {
+            error!(ns=%ns, error=%e, "🚨 ServiceAccount SSA failed");
+        })?;
+
+        let api: Api<Role> = Api::namespaced(self.client.clone(), ns);
+        api.patch(
+            USER_APP_SERVICE_ACCOUNT,
+            &self.apply_params(),
+            &Patch::Apply(&role),
+        )
+        .await
+        .inspect_err(|e| {
+            error!(ns=%ns, error=%e, "🚨 Role SSA failed");
+        })?;
+
+        let api: Api<RoleBinding> = Api::namespaced(self.client.clone(), ns);
+        api.patch(
+            USER_APP_SERVICE_ACCOUNT,
+            &self.apply_params(),
+            &Patch::Apply(&role_binding),
+        )
+        .await
+        .inspect_err(|e| {
+            error!(ns=%ns, error=%e, "🚨 RoleBinding SSA failed");
+        })?;
+
+        Ok(())
+    }
+
+    /// Caps what a single user can consume in total, SSA so a plan change can re-apply it with new limits
+    #[tracing::instrument(name = "kubernetes_service.apply_namespace_quota", skip_all, fields(ns = %ns), err)]
+    pub async fn apply_namespace_quota(
+        &self,
+        ns: &str,
+        quota: &NamespaceQuotaConfig,
+    ) -> Result<(), AppError> {
+        let cpu = Quantity(quota.max_cpu_cores.to_string());
+        let memory = Quantity(format!("{}Gi", quota.max_memory_gb));
+
+        let mut hard = BTreeMap::new();
+        hard.insert("requests.cpu".to_string(), cpu.clone());
+        hard.insert("limits.cpu".to_string(), cpu);
+        hard.insert("requests.memory".to_string(), memory.clone());
+        hard.insert("limits.memory".to_string(), memory);
+        hard.insert("pods".to_string(), Quantity(quota.max_pods.to_string()));
+
+        let mut labels = BTreeMap::new();
+        labels.insert("poddle.io/managed-by".into(), "poddle".into());
 
-        let secret = K8sSecret {
+        let resource_quota = ResourceQuota {
             metadata: ObjectMeta {
-                name: Some(secret_name.clone()),
+                name: Some("user-quota".to_string()),
                 namespace: Some(ns.to_string()),
+                labels: Some(labels),
                 ..Default::default()
             },
-            type_: Some("kubernetes.io/dockerconfigjson".to_string()),
-            data,
+            spec: Some(ResourceQuotaSpec {
+                hard: Some(hard),
+                ..Default::default()
+            }),
             ..Default::default()
         };
 
-        // let patch = json!({
-        //     "apiVersion": "v1",
-        //     "kind": "Secret",
-        //     "metadata": { "name": secret_name, "namespace": ns },
-        //     "type": "kubernetes.io/dockerconfigjson",
-        //     "data": {
-        //         ".dockerconfigjson": base64::engine::general_purpose::STANDARD.encode(
-        //             json!({ "auths": { creds.server.clone(): { "username": creds.username, "password": creds.secret, "auth": auth } } }).to_string()
-        //         )
-        //     }
-        // });
-
-        let api: Api<K8sSecret> = Api::namespaced(self.client.clone(), ns);
-
+        let api: Api<ResourceQuota> = Api::namespaced(self.client.clone(), ns);
         api.patch(
-            &secret_name,
-            &PatchParams::apply("poddle-provisioner").force(),
-            &Patch::Apply(&secret),
+            "user-quota",
+            &self.apply_params(),
+            &Patch::Apply(&resource_quota),
         )
         .await
-        .map_err(|e| {
-            error!(ns = %ns, error = %e, "🚨 Image Pull Secret SSA failed");
-            AppError::InternalServerError(format!("🚨 Image Pull Secret SSA failed: {}", e))
+        .inspect_err(|e| {
+            error!(ns=%ns, error=%e, "🚨 ResourceQuota SSA failed");
         })?;
 
-        Ok((secret_name, checksum))
+        Ok(())
     }
 
-    #[tracing::instrument(name = "kubernetes_service.ensure_namespace", skip_all, fields(user_id = %user_id), err)]
-    async fn ensure_namespace(&self, user_id: &Uuid) -> Result<String, AppError> {
-        let name = format_namespace(&user_id);
+    /// Default deny for a user's namespace. Pods only receive traffic from Traefik and each
+    /// other, and only reach each other and DNS
+    #[tracing::instrument(name = "kubernetes_service.create_network_policy", skip_all, fields(ns = %ns), err)]
+    async fn create_network_policy(&self, ns: &str) -> Result<(), AppError> {
+        let namespace_peer = |namespace: &str| NetworkPolicyPeer {
+            namespace_selector: Some(LabelSelector {
+                match_labels: Some(BTreeMap::from([(
+                    "kubernetes.io/metadata.name".to_string(),
+                    namespace.to_string(),
+                )])),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        // An empty pod selector without a namespace selector means every pod of this namespace
+        let same_namespace = NetworkPolicyPeer {
+            pod_selector: Some(LabelSelector::default()),
+            ..Default::default()
+        };
+        let dns_ports = ["UDP", "TCP"]
+            .map(|protocol| NetworkPolicyPort {
+                port: Some(IntOrString::Int(53)),
+                protocol: Some(protocol.to_string()),
+                ..Default::default()
+            })
+            .to_vec();
 
-        let api: Api<Namespace> = Api::all(self.client.clone());
+        let mut labels = BTreeMap::new();
+        labels.insert("poddle.io/managed-by".into(), "poddle".into());
 
-        match api.get(&name).await {
-            Ok(_) => return Ok(name),
+        let network_policy = NetworkPolicy {
+            metadata: ObjectMeta {
+                name: Some("default-deny".to_string()),
+                namespace: Some(ns.to_string()),
+                labels: Some(labels),
+                ..Default::default()
+            },
+            spec: Some(NetworkPolicySpec {
+                pod_selector: Some(LabelSelector::default()),
+                policy_types: Some(vec!["Ingress".to_string(), "Egress".to_string()]),
+                ingress: Some(vec![NetworkPolicyIngressRule {
+                    from: Some(vec![
+                        namespace_peer(&self.cfg.traefik.namespace),
+                        same_namespace.clone(),
+                    ]),
+                    ..Default::default()
+                }]),
+                egress: Some(vec![
+                    NetworkPolicyEgressRule {
+                        to: Some(vec![same_namespace]),
+                        ..Default::default()
+                    },
+                    NetworkPolicyEgressRule {
+                        ports: Some(dns_ports),
+                        ..Default::default()
+                    },
+                ]),
+            }),
+        };
 
-            Err(kube::Error::Api(ae)) if ae.code == 404 => {
-                info!(user_id = %user_id, "🏗️ Creating namespace {}", name);
-            }
+        let api: Api<NetworkPolicy> = Api::namespaced(self.client.clone(), ns);
+        api.patch(
+            "default-deny",
+            &self.apply_params(),
+            &Patch::Apply(&network_policy),
+        )
+        .await
+        .inspect_err(|e| {
+            error!(ns=%ns, error=%e, "🚨 NetworkPolicy SSA failed");
+        })?;
 
-            Err(e) => {
-                error!(
-                    user_id = %user_id,
-                    error = %e,
-                    "⚠️ Failed to check namespace existence"
-                );
-                return Err(AppError::InternalServerError(format!(
-                    "🚨 Kubernetes API unavailable while checking namespace: {}",
-                    e
-                )));
-            }
-        }
+        Ok(())
+    }
 
+    /// Opens outbound HTTPS under the default deny once a custom domain is routed, ACME needs it.
+    /// Shared by the whole namespace and kept after the domain is gone, the namespace is a
+    /// single user's and another of their deployments may still rely on it
+    #[tracing::instrument(name = "kubernetes_service.apply_acme_egress_policy", skip_all, fields(ns = %ns), err)]
+    async fn apply_acme_egress_policy(&self, ns: &str) -> Result<(), AppError> {
         let mut labels = BTreeMap::new();
-        labels.insert("user-id".to_string(), user_id.to_string());
+        labels.insert("poddle.io/managed-by".into(), "poddle".into());
 
-        let new_ns = Namespace {
+        let network_policy = NetworkPolicy {
             metadata: ObjectMeta {
-                name: Some(name.clone()),
+                name: Some("allow-acme-egress".to_string()),
+                namespace: Some(ns.to_string()),
                 labels: Some(labels),
                 ..Default::default()
             },
-            ..Default::default()
+            spec: Some(NetworkPolicySpec {
+                pod_selector: Some(LabelSelector::default()),
+                policy_types: Some(vec!["Egress".to_string()]),
+                egress: Some(vec![NetworkPolicyEgressRule {
+                    ports: Some(vec![NetworkPolicyPort {
+                        port: Some(IntOrString::Int(443)),
+                        protocol: Some("TCP".to_string()),
+                        ..Default::default()
+                    }]),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }),
         };
 
-        api.create(&PostParams::default(), &new_ns)
-            .await
-            .map_err(|e| {
-                error!(
-                    user_id = %user_id,
-                    error = %e,
-                    "🚨 Failed to create namespace"
-                );
-                AppError::InternalServerError(format!(
-                    "🚨 Failed to create namespace '{}': {}",
-                    name, e
-                ))
-            })?;
+        let api: Api<NetworkPolicy> = Api::namespaced(self.client.clone(), ns);
+        api.patch(
+            "allow-acme-egress",
+            &self.apply_params(),
+            &Patch::Apply(&network_policy),
+        )
+        .await
+        .inspect_err(|e| {
+            error!(ns=%ns, error=%e, "🚨 ACME egress NetworkPolicy SSA failed");
+        })?;
 
-        Ok(name)
+        Ok(())
     }
 
     /// Creates VaultConnection & VaultAuth
@@ -1278,28 +3954,26 @@ impl KubernetesService {
         vault_connection_api
             .patch(
                 vc_api_patch_name,
-                &PatchParams::apply("poddle-provisioner").force(),
+                &self.apply_params(),
                 &Patch::Apply(vault_connection),
             )
             .instrument(info_span!("apply_vault_connection"))
             .await
-            .map_err(|e| {
+            .inspect_err(|e| {
                 error!(ns=%ns, error = %e, "🚨 VaultConnection SSA failed");
-                AppError::InternalServerError(format!("🚨 VaultConnection SSA failed: {}", e))
             })?;
 
         let va_api_patch_name = &vault_auth.metadata.name.clone().unwrap();
         vault_auth_api
             .patch(
                 va_api_patch_name,
-                &PatchParams::apply("poddle-provisioner").force(),
+                &self.apply_params(),
                 &Patch::Apply(vault_auth),
             )
             .instrument(info_span!("apply_vault_api"))
             .await
-            .map_err(|e| {
+            .inspect_err(|e| {
                 error!(ns=%ns, error = %e, "🚨 VaultAuth SSA failed");
-                AppError::InternalServerError(format!("🚨 VaultAuth SSA failed: {}", e))
             })?;
 
         Ok(())
@@ -1309,6 +3983,7 @@ impl KubernetesService {
     #[tracing::instrument(name = "kubernetes_service.create_vault_static_secret", skip_all, fields(deployment_id = %deployment_id), err)]
     async fn apply_vault_static_secret(
         &self,
+        user_id: &Uuid,
         deployment_id: &Uuid,
         ns: &str,
         name: &str,
@@ -1323,6 +3998,12 @@ impl KubernetesService {
         let secret_name = format!("{}-secrets", name);
 
         let keys = secrets.keys().cloned().collect();
+        let previous = self
+            .vault_service
+            .try_read_secrets(ns, &deployment_id.to_string())
+            .await?
+            .unwrap_or_default();
+        let fields_changed = changed_secret_keys(&previous, &secrets);
 
         // Write to Vault
         let path = self
@@ -1330,6 +4011,12 @@ impl KubernetesService {
             .store_secrets(ns, &deployment_id.to_string(), secrets)
             .await?;
 
+        // Updates resend the whole secret, only record the ones that actually touched it
+        if !fields_changed.is_empty() {
+            self.audit_secret_change(ns, deployment_id, user_id, fields_changed)
+                .await;
+        }
+
         DeploymentRepository::set_vault_secret_path(deployment_id, &path, pool).await?;
         DeploymentRepository::set_secret_keys(deployment_id, keys, pool).await?;
 
@@ -1337,6 +4024,10 @@ impl KubernetesService {
             metadata: ObjectMeta {
                 name: Some(name.to_owned()),
                 namespace: Some(ns.to_owned()),
+                labels: Some(BTreeMap::from([(
+                    "poddle.io/deployment-id".to_string(),
+                    deployment_id.to_string(),
+                )])),
                 ..Default::default()
             },
             spec: VaultStaticSecretSpec {
@@ -1371,19 +4062,35 @@ impl KubernetesService {
 
         api.patch(
             name,
-            &PatchParams::apply("poddle-provisioner").force(),
+            &self.apply_params(),
             &Patch::Apply(vault_static_secret),
         )
         .instrument(info_span!("apply_vault_static_secret"))
         .await
-        .map_err(|e| {
+        .inspect_err(|e| {
             error!(deployment_id=%deployment_id, error = %e, "🚨 VaultStaticSecret SSA failed");
-            AppError::InternalServerError(format!("🚨 VaultStaticSecret SSA failed: {}", e))
         })?;
 
         Ok(Some(secret_name))
     }
 
+    /// The secrets are already written by now, so a failed audit is logged instead of failing the deployment
+    async fn audit_secret_change(
+        &self,
+        ns: &str,
+        deployment_id: &Uuid,
+        user_id: &Uuid,
+        fields_changed: Vec<String>,
+    ) {
+        if let Err(e) = self
+            .vault_service
+            .audit_secret_change(ns, &deployment_id.to_string(), user_id, fields_changed)
+            .await
+        {
+            warn!(deployment_id=%deployment_id, error = %e, "⚠️ Secret change left out of the audit trail");
+        }
+    }
+
     // ============================================================================================
     // BUILD
     // ============================================================================================
@@ -1395,7 +4102,7 @@ impl KubernetesService {
         deployment_id: &str,
         preset_id: &str,
         build_id: &str,
-        clone_url: &str,
+        checkout: GitCheckout<'_>,
         context_path: Option<&str>,
         dockerfile_path: Option<&str>,
     ) -> Result<(), AppError> {
@@ -1412,22 +4119,7 @@ impl KubernetesService {
         let dockerfile_path = dockerfile_path.unwrap_or("Dockerfile");
 
         // --- Init container: git clone ---
-        let init_containers = Some(vec![Container {
-            name: "git-clone".into(),
-            image: Some("alpine/git:latest".into()),
-            image_pull_policy: Some("IfNotPresent".into()),
-            args: Some(vec![
-                "clone".into(),
-                clone_url.to_string(),
-                "/workspace".into(),
-            ]),
-            volume_mounts: Some(vec![VolumeMount {
-                name: "workspace".into(),
-                mount_path: "/workspace".into(),
-                ..Default::default()
-            }]),
-            ..Default::default()
-        }]);
+        let init_containers = Some(vec![git_clone_container(&checkout)]);
 
         // --- Build container ---
         let (dockerfile, filename) = match dockerfile_path.rsplit_once('/') {
@@ -1549,7 +4241,7 @@ impl KubernetesService {
             .await
             .map_err(|e| {
                 error!(error=%e, "🚨 Failed to create buildctl Job");
-                AppError::InternalServerError(format!("🚨 Failed to create buildctl Job: {}", e))
+                AppError::kubernetes_quota_exceeded(&e).unwrap_or_else(|| e.into())
             })?;
 
         info!("🚀 Spawned buildctl Job: {}", job_name);
@@ -1563,7 +4255,7 @@ impl KubernetesService {
         deployment_id: &str,
         preset_id: &str,
         build_id: &str,
-        clone_url: &str,
+        checkout: GitCheckout<'_>,
         context_path: Option<&str>,
     ) -> Result<(), AppError> {
         let namespace = "buildkit";
@@ -1578,22 +4270,7 @@ impl KubernetesService {
         let context = context_path.unwrap_or(".");
 
         // --- Init container: git clone ---
-        let git_clone = Container {
-            name: "git-clone".into(),
-            image: Some("alpine/git:latest".into()),
-            image_pull_policy: Some("IfNotPresent".into()),
-            args: Some(vec![
-                "clone".into(),
-                clone_url.to_string(),
-                "/workspace".into(),
-            ]),
-            volume_mounts: Some(vec![VolumeMount {
-                name: "workspace".into(),
-                mount_path: "/workspace".into(),
-                ..Default::default()
-            }]),
-            ..Default::default()
-        };
+        let git_clone = git_clone_container(&checkout);
 
         // --- Init container: railpack prepare ---
         // This container analyzes the app, and outputs the build plan
@@ -1769,7 +4446,7 @@ impl KubernetesService {
             .await
             .map_err(|e| {
                 error!(error=%e, "🚨 Failed to create railpack Job");
-                AppError::InternalServerError(format!("🚨 Failed to create railpack Job: {}", e))
+                AppError::kubernetes_quota_exceeded(&e).unwrap_or_else(|| e.into())
             })?;
 
         info!("🚀 Spawned Railpack Job: {}", job_name);
@@ -1828,16 +4505,11 @@ impl KubernetesService {
 
         let api: Api<Image> = Api::namespaced(self.client.clone(), "kpack-build");
 
-        api.patch(
-            &image_name,
-            &PatchParams::apply("poddle-provisioner").force(),
-            &Patch::Apply(&image),
-        )
-        .await
-        .map_err(|e| {
-            error!(deployment_id=%deployment_id, error = %e, "🚨 Image SSA failed");
-            AppError::InternalServerError(format!("🚨 Image SSA failed: {}", e))
-        })?;
+        api.patch(&image_name, &self.apply_params(), &Patch::Apply(&image))
+            .await
+            .inspect_err(|e| {
+                error!(deployment_id=%deployment_id, error = %e, "🚨 Image SSA failed");
+            })?;
 
         Ok(())
     }
@@ -1845,4 +4517,309 @@ impl KubernetesService {
     // ============================================================================================
     // HELPERS
     // ============================================================================================
+
+    /// A ReadWriteOnce claim is mounted by one node at a time. A surged pod on another node
+    /// would wait for it forever while the old pod keeps it, so such deployments are recreated
+    fn deployment_strategy(msg: &CreateDeploymentMessage) -> Option<DeploymentStrategy> {
+        let read_write_once = msg
+            .volumes
+            .iter()
+            .flatten()
+            .any(|volume| matches!(volume.access_mode, VolumeAccessMode::ReadWriteOnce));
+        if read_write_once {
+            return Some(DeploymentStrategy {
+                type_: Some("Recreate".to_string()),
+                rolling_update: None,
+            });
+        }
+
+        msg.rolling_update
+            .as_ref()
+            .map(Self::rolling_update_strategy)
+    }
+
+    fn rolling_update_strategy(spec: &RollingUpdateSpec) -> DeploymentStrategy {
+        DeploymentStrategy {
+            type_: Some("RollingUpdate".to_string()),
+            rolling_update: Some(RollingUpdateDeployment {
+                max_surge: Some(spec.max_surge.clone()),
+                max_unavailable: Some(spec.max_unavailable.clone()),
+            }),
+        }
+    }
+
+    /// Init containers, sidecars and main container settings of a live pod template, the main
+    /// container is first
+    fn declared_on_create(
+        pod_spec: PodSpec,
+    ) -> (
+        Option<Vec<Container>>,
+        Vec<Container>,
+        MainContainerSettings,
+    ) {
+        let mut containers = pod_spec.containers.into_iter();
+        let main_container = MainContainerSettings {
+            topology_spread_constraints: pod_spec.topology_spread_constraints,
+            ..containers
+                .next()
+                .map(MainContainerSettings::from)
+                .unwrap_or_default()
+        };
+        (
+            pod_spec.init_containers,
+            containers.collect(),
+            main_container,
+        )
+    }
+
+    /// Project level variables underneath the deployment's own, which win on conflicting keys
+    async fn inherit_project_env_vars(
+        project_id: &Uuid,
+        environment_variables: Option<HashMap<String, String>>,
+        pool: &PgPool,
+    ) -> Result<Option<HashMap<String, String>>, AppError> {
+        let Some(mut inherited) = DeploymentRepository::get_project_env_vars(project_id, pool)
+            .await
+            .inspect_err(|e| {
+                error!(project_id = %project_id, error = %e, "🚨 Failed to get project environment variables");
+            })?
+        else {
+            return Ok(environment_variables);
+        };
+        inherited.extend(environment_variables.unwrap_or_default());
+
+        Ok(Some(inherited))
+    }
+
+    fn main_container_settings(msg: &CreateDeploymentMessage) -> MainContainerSettings {
+        MainContainerSettings {
+            image_pull_policy: Some(msg.image_pull_policy.unwrap_or_default().to_string()),
+            liveness_probe: msg.liveness_probe.as_ref().map(Self::probe),
+            readiness_probe: msg.readiness_probe.as_ref().map(Self::probe),
+            startup_probe: msg.startup_probe.as_ref().map(Self::probe),
+            // At most one pod more in any zone than in the emptiest one, pods stay pending
+            // rather than pile up in a zone
+            topology_spread_constraints: (msg.spread_across_zones && msg.desired_replicas > 1)
+                .then(|| {
+                    vec![TopologySpreadConstraint {
+                        max_skew: 1,
+                        topology_key: "topology.kubernetes.io/zone".to_string(),
+                        when_unsatisfiable: "DoNotSchedule".to_string(),
+                        label_selector: Some(LabelSelector {
+                            match_labels: Some(BTreeMap::from([(
+                                "poddle.io/deployment-id".to_string(),
+                                msg.deployment_id.to_string(),
+                            )])),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    }]
+                }),
+        }
+    }
+
+    fn probe(spec: &ProbeSpec) -> Probe {
+        let port = spec
+            .port
+            .map(IntOrString::Int)
+            .unwrap_or_else(|| IntOrString::String(MAIN_PORT_NAME.into()));
+        let (http_get, tcp_socket) = match spec.path.as_ref() {
+            Some(path) => (
+                Some(HTTPGetAction {
+                    path: Some(path.clone()),
+                    port,
+                    ..Default::default()
+                }),
+                None,
+            ),
+            None => (
+                None,
+                Some(TCPSocketAction {
+                    port,
+                    ..Default::default()
+                }),
+            ),
+        };
+
+        Probe {
+            http_get,
+            tcp_socket,
+            initial_delay_seconds: spec.initial_delay_seconds,
+            period_seconds: spec.period_seconds,
+            timeout_seconds: spec.timeout_seconds,
+            failure_threshold: spec.failure_threshold,
+            ..Default::default()
+        }
+    }
+
+    fn extra_container(spec: &ContainerSpec) -> Container {
+        let mut resources = BTreeMap::new();
+        resources.insert(
+            "cpu".to_string(),
+            Quantity(format!("{}m", spec.cpu_millicores)),
+        );
+        resources.insert(
+            "memory".to_string(),
+            Quantity(format!("{}Mi", spec.memory_mb)),
+        );
+
+        let env = spec.environment_variables.as_ref().map(|vars| {
+            vars.iter()
+                .map(|(name, value)| EnvVar {
+                    name: name.clone(),
+                    value: Some(value.clone()),
+                    ..Default::default()
+                })
+                .collect()
+        });
+
+        let ports = spec.port.map(|container_port| {
+            vec![ContainerPort {
+                container_port,
+                protocol: Some("TCP".into()),
+                ..Default::default()
+            }]
+        });
+
+        Container {
+            name: spec.name.clone(),
+            image: Some(spec.image.clone()),
+            image_pull_policy: Some("IfNotPresent".to_string()),
+            command: spec.command.clone(),
+            args: spec.args.clone(),
+            env,
+            ports,
+            resources: Some(ResourceRequirements {
+                requests: Some(resources.clone()),
+                limits: Some(resources),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// Server-side apply as the provisioner, validated only while this is a dry-run service
+    fn apply_params(&self) -> PatchParams {
+        let params = PatchParams::apply("poddle-provisioner").force();
+        if self.dry_run {
+            params.dry_run()
+        } else {
+            params
+        }
+    }
+
+    /// Merge patch instead of SSA, only `spec.replicas` changes and nothing else may be pruned
+    #[tracing::instrument(name = "kubernetes_service.scale_deployment", skip_all, fields(replicas = replicas), err)]
+    async fn scale_deployment(&self, ns: &str, name: &str, replicas: i32) -> Result<(), AppError> {
+        let api: Api<K8sDeployment> = Api::namespaced(self.client.clone(), ns);
+        let patch = serde_json::json!({ "spec": { "replicas": replicas } });
+
+        api.patch(name, &PatchParams::default(), &Patch::Merge(&patch))
+            .await
+            .map_err(|e| {
+                error!(ns=%ns, name=%name, error=%e, "🚨 Deployment scale failed");
+                AppError::kubernetes_quota_exceeded(&e).unwrap_or_else(|| e.into())
+            })?;
+
+        Ok(())
+    }
+
+    /// Merge patch like `kubectl rollout restart`, the annotation isn't owned by the SSA field
+    /// manager so the next apply doesn't prune it and roll the pods a second time
+    #[tracing::instrument(name = "kubernetes_service.restart_deployment", skip_all, fields(restart_at = restart_at), err)]
+    async fn restart_deployment(
+        &self,
+        ns: &str,
+        name: &str,
+        restart_at: i64,
+    ) -> Result<(), AppError> {
+        let api: Api<K8sDeployment> = Api::namespaced(self.client.clone(), ns);
+        let patch = serde_json::json!({
+            "spec": {
+                "template": {
+                    "metadata": {
+                        "annotations": {
+                            "poddle.io/restart-at": restart_at.to_string()
+                        }
+                    }
+                }
+            }
+        });
+
+        api.patch(name, &PatchParams::default(), &Patch::Merge(&patch))
+            .await
+            .inspect_err(|e| {
+                error!(ns=%ns, name=%name, error=%e, "🚨 Deployment restart patch failed");
+            })?;
+
+        Ok(())
+    }
+
+    async fn get_live_deployment_spec(
+        &self,
+        ns: &str,
+        name: &str,
+    ) -> Result<Option<DeploymentSpec>, AppError> {
+        let api: Api<K8sDeployment> = Api::namespaced(self.client.clone(), ns);
+
+        let d = api.get_opt(name).await.inspect_err(|e| {
+            error!(ns=%ns, name=%name, error=%e, "🚨 Failed to get deployment");
+        })?;
+
+        Ok(d.and_then(|d| d.spec))
+    }
+}
+
+/// The API server's message, without the generic wrapper `AppError` displays
+pub fn api_server_error(e: AppError) -> String {
+    match e {
+        AppError::InternalServerError(message) | AppError::BadRequest(message) => message,
+        e => e.to_string(),
+    }
+}
+
+/// The canary's share of the deployment's replicas, never rounded down to nothing while it
+/// still gets traffic
+fn canary_replicas(primary_replicas: i32, weight: u8) -> i32 {
+    if primary_replicas == 0 || weight == 0 {
+        return 0;
+    }
+    (primary_replicas * i32::from(weight) + 99) / 100
+}
+
+/// Clones into the shared `/workspace` volume, the URL and commit are passed as positional
+/// shell arguments so they are never interpreted by the shell
+fn git_clone_container(checkout: &GitCheckout) -> Container {
+    let (command, args) = match checkout.commit_sha {
+        Some(commit_sha) => (
+            Some(vec!["sh".into(), "-c".into()]),
+            vec![
+                r#"git clone "$0" /workspace && git -C /workspace checkout "$1""#.into(),
+                checkout.clone_url.to_string(),
+                commit_sha.to_string(),
+            ],
+        ),
+        None => (
+            None,
+            vec![
+                "clone".into(),
+                checkout.clone_url.to_string(),
+                "/workspace".into(),
+            ],
+        ),
+    };
+
+    Container {
+        name: "git-clone".into(),
+        image: Some("alpine/git:latest".into()),
+        image_pull_policy: Some("IfNotPresent".into()),
+        command,
+        args: Some(args),
+        volume_mounts: Some(vec![VolumeMount {
+            name: "workspace".into(),
+            mount_path: "/workspace".into(),
+            ..Default::default()
+        }]),
+        ..Default::default()
+    }
 }
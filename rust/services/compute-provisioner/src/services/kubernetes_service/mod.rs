@@ -1,4 +1,8 @@
 use compute_core::configs::PrometheusConfig;
+use factory::factories::kubernetes::Kubernetes;
+use k8s_openapi::api::core::v1::{
+    Container, EnvFromSource, Probe, TopologySpreadConstraint, Volume, VolumeMount,
+};
 use kube::Client;
 use serde::Deserialize;
 
@@ -19,6 +23,31 @@ pub struct TraefikConfig {
     pub namespace: String,
     // pub cluster_issuer: String,
     pub entry_points: Option<Vec<String>>,
+    /// compute-api's Service, idle suspended deployments are woken up through it. Traefik has to
+    /// allow cross namespace references for the errors middleware to reach it
+    pub wake_service: Option<WakeServiceConfig>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct WakeServiceConfig {
+    pub name: String,
+    pub namespace: String,
+    pub port: i32,
+}
+
+/// Hard limits of the `user-quota` ResourceQuota applied to every user namespace
+#[derive(Deserialize, Clone, Debug)]
+pub struct NamespaceQuotaConfig {
+    pub max_cpu_cores: f64,
+    pub max_memory_gb: u32,
+    pub max_pods: u32,
+}
+
+/// Rules of the Role the pods of every user namespace run under, limited to that namespace
+#[derive(Deserialize, Clone, Debug)]
+pub struct UserAppRoleConfig {
+    pub resources: Vec<String>,
+    pub verbs: Vec<String>,
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -29,11 +58,69 @@ pub struct KubernetesServiceConfig {
     pub prometheus: PrometheusConfig,
     pub cert_manager: CertManagerConfig,
     pub build_image_pull_secret: String,
+    pub namespace_quota: NamespaceQuotaConfig,
+    /// Read access to secrets and configmaps when unset. The provisioner can only grant what
+    /// its own ClusterRole allows
+    pub user_app_role: Option<UserAppRoleConfig>,
 }
 
 #[derive(Clone)]
 pub struct KubernetesService {
+    /// Cluster of the region this service acts on, see [`KubernetesService::in_region`]
     pub client: Client,
+    pub clusters: Kubernetes,
     pub cfg: KubernetesServiceConfig,
     pub vault_service: VaultService,
+    /// Server-side applies are only validated by the API server, never persisted
+    pub dry_run: bool,
+}
+
+/// Repository a build job clones, checked out at `commit_sha` when one is given
+pub struct GitCheckout<'a> {
+    pub clone_url: &'a str,
+    pub commit_sha: Option<&'a str>,
+}
+
+/// Pull policy and health probes of the main container, and how its pods spread over zones.
+/// They are only declared on create, updates carry the live ones over so SSA doesn't prune them
+#[derive(Default)]
+pub struct MainContainerSettings {
+    pub image_pull_policy: Option<String>,
+    pub liveness_probe: Option<Probe>,
+    pub readiness_probe: Option<Probe>,
+    pub startup_probe: Option<Probe>,
+    pub topology_spread_constraints: Option<Vec<TopologySpreadConstraint>>,
+}
+
+/// A permission the provisioner's service account was denied, cluster wide
+#[derive(Debug)]
+pub struct RbacCheckResult {
+    pub group: &'static str,
+    pub resource: &'static str,
+    pub verb: &'static str,
+    /// The API server's explanation, when it gives one
+    pub reason: Option<String>,
+}
+
+/// What the deployment's config maps add to the pod, listed live on every apply like the claims
+#[derive(Default)]
+pub struct ConfigMapSources {
+    pub volumes: Vec<Volume>,
+    pub volume_mounts: Vec<VolumeMount>,
+    /// Config maps without a mount path, every key becomes a variable
+    pub env_from: Vec<EnvFromSource>,
+    /// Digest of every config map's data, stamped on the pod template so a change rolls the pods
+    pub checksum: Option<String>,
+}
+
+impl From<Container> for MainContainerSettings {
+    fn from(container: Container) -> Self {
+        Self {
+            image_pull_policy: container.image_pull_policy,
+            liveness_probe: container.liveness_probe,
+            readiness_probe: container.readiness_probe,
+            startup_probe: container.startup_probe,
+            topology_spread_constraints: None,
+        }
+    }
 }
@@ -1,4 +1,5 @@
 pub mod consumer;
+pub mod job_runner;
 pub mod kubernetes_service;
 pub mod repository;
 pub mod traits;
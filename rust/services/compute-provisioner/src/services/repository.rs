@@ -1,5 +1,5 @@
 use compute_core::{
-    models::{DeploymentRow, DeploymentStatus, PresetRow},
+    models::{DeploymentRow, DeploymentStatus, DeploymentType, JobRow, JobType, PresetRow},
     schemas::DeploymentSource,
 };
 use sqlx::{PgPool, postgres::PgQueryResult, types::Json};
@@ -32,6 +32,7 @@ impl DeploymentRepository {
                 secret_keys,
                 environment_variables AS "environment_variables: Json<Option<HashMap<String, String>>>",
                 labels AS "labels: Json<Option<HashMap<String, String>>>",
+                tags AS "tags: Json<HashMap<String, String>>",
                 status AS "status: DeploymentStatus",
                 domain,
                 subdomain,
@@ -47,6 +48,34 @@ impl DeploymentRepository {
         .await
     }
 
+    /// `None` for deployments created before regions existed, they run in the default region
+    #[instrument("deployment_repository.get_region", skip_all, fields(deployment_id = %id), err)]
+    pub async fn get_region(id: &Uuid, pool: &PgPool) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar!("SELECT region FROM deployments WHERE id = $1", id)
+            .fetch_one(pool)
+            .await
+    }
+
+    /// What the deployment runs as and, for a `CronJob`, its schedule
+    #[instrument("deployment_repository.get_deployment_type", skip_all, fields(deployment_id = %id), err)]
+    pub async fn get_deployment_type(
+        id: &Uuid,
+        pool: &PgPool,
+    ) -> Result<(DeploymentType, Option<String>), sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+            SELECT deployment_type AS "deployment_type: DeploymentType", schedule
+            FROM deployments
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok((row.deployment_type, row.schedule))
+    }
+
     #[instrument("deployment_repository.get_preset_by_id", skip_all, fields(preset_id = %id), err)]
     pub async fn get_preset_by_id(id: &Uuid, pool: &PgPool) -> Result<PresetRow, sqlx::Error> {
         sqlx::query_as!(
@@ -61,6 +90,68 @@ impl DeploymentRepository {
         .await
     }
 
+    /// Keeps the stored image in line with a promoted canary, build sourced deployments are
+    /// left alone since their next build replaces the image anyway
+    #[instrument("deployment_repository.set_image_url", skip_all, fields(deployment_id = %id), err)]
+    pub async fn set_image_url(id: &Uuid, url: &str, pool: &PgPool) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE deployments
+            SET source = jsonb_set(source, '{url}', to_jsonb($2::text))
+            WHERE id = $1
+            AND source->>'type' = 'image'
+            "#,
+            id,
+            url
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument("deployment_repository.get_project_env_vars", skip_all, fields(project_id = %id), err)]
+    pub async fn get_project_env_vars(
+        id: &Uuid,
+        pool: &PgPool,
+    ) -> Result<Option<HashMap<String, String>>, sqlx::Error> {
+        let env_vars = sqlx::query_scalar!(
+            r#"
+            SELECT project_env_vars AS "project_env_vars: Json<HashMap<String, String>>"
+            FROM projects
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(env_vars.map(|j| j.0))
+    }
+
+    #[instrument("deployment_repository.get_create_pdb", skip_all, fields(deployment_id = %id), err)]
+    pub async fn get_create_pdb(id: &Uuid, pool: &PgPool) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar!("SELECT create_pdb FROM deployments WHERE id = $1", id)
+            .fetch_one(pool)
+            .await
+    }
+
+    #[instrument("deployment_repository.get_suspend_after_idle_minutes", skip_all, fields(deployment_id = %id), err)]
+    pub async fn get_suspend_after_idle_minutes(
+        id: &Uuid,
+        pool: &PgPool,
+    ) -> Result<Option<i32>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT suspend_after_idle_minutes FROM deployments
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_one(pool)
+        .await
+    }
+
     #[instrument("deployment_repository.get_vault_secret_path", skip_all, fields(deployment_id = %id), err)]
     pub async fn get_vault_secret_path(
         id: &Uuid,
@@ -115,3 +206,93 @@ impl DeploymentRepository {
         .await
     }
 }
+
+pub struct JobQueueRepository;
+
+impl JobQueueRepository {
+    /// Marks up to `limit` due jobs running for `lease_secs`, runners polling at the same time
+    /// skip each other's rows. A running job whose lease ran out was left by a runner that died
+    #[instrument("job_queue_repository.claim", skip_all, err)]
+    pub async fn claim(
+        limit: i64,
+        lease_secs: f64,
+        pool: &PgPool,
+    ) -> Result<Vec<JobRow>, sqlx::Error> {
+        sqlx::query_as!(
+            JobRow,
+            r#"
+            UPDATE job_queue
+            SET
+                status = 'running',
+                attempts = attempts + 1,
+                next_attempt_at = NOW() + make_interval(secs => $2)
+            WHERE id IN (
+                SELECT id FROM job_queue
+                WHERE status IN ('pending', 'running') AND next_attempt_at <= NOW()
+                ORDER BY next_attempt_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, job_type AS "job_type: JobType", payload, attempts, max_attempts
+            "#,
+            limit,
+            lease_secs
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    #[instrument("job_queue_repository.complete", skip_all, fields(job_id = %id), err)]
+    pub async fn complete(id: &Uuid, pool: &PgPool) -> Result<PgQueryResult, sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE job_queue
+            SET status = 'done', last_error = NULL
+            WHERE id = $1
+            "#,
+            id
+        )
+        .execute(pool)
+        .await
+    }
+
+    /// Back to pending, picked up again once `delay_secs` passed
+    #[instrument("job_queue_repository.retry", skip_all, fields(job_id = %id), err)]
+    pub async fn retry(
+        id: &Uuid,
+        delay_secs: f64,
+        error: &str,
+        pool: &PgPool,
+    ) -> Result<PgQueryResult, sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE job_queue
+            SET
+                status = 'pending',
+                next_attempt_at = NOW() + make_interval(secs => $2),
+                last_error = $3
+            WHERE id = $1
+            "#,
+            id,
+            delay_secs,
+            error
+        )
+        .execute(pool)
+        .await
+    }
+
+    #[instrument("job_queue_repository.fail", skip_all, fields(job_id = %id), err)]
+    pub async fn fail(id: &Uuid, error: &str, pool: &PgPool) -> Result<PgQueryResult, sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE job_queue
+            SET status = 'failed', last_error = $2
+            WHERE id = $1
+            "#,
+            id,
+            error
+        )
+        .execute(pool)
+        .await
+    }
+}
@@ -1,15 +1,20 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use chrono::Utc;
+use compute_core::schemas::{SecretAuditEntry, SecretAuditTrail};
 use tracing::{error, info};
+use uuid::Uuid;
 use vaultrs::client::{Client, VaultClient, VaultClientSettingsBuilder};
 
 use vaultrs::auth::kubernetes;
+use vaultrs::error::ClientError;
 use vaultrs::kv2;
 
 use crate::error::AppError;
 use crate::services::vault_service::{
-    VaultAuthConfig, VaultAuthKubernetesConfig, VaultService, VaultServiceConfig,
+    SECRET_AUDIT_MAX_ENTRIES, VaultAuthConfig, VaultAuthKubernetesConfig, VaultService,
+    VaultServiceConfig,
 };
 
 impl Default for VaultAuthKubernetesConfig {
@@ -65,9 +70,8 @@ impl VaultService {
 
         kv2::set(&*self.client, &self.cfg.kv_mount, &path, &secrets)
             .await
-            .map_err(|e| {
+            .inspect_err(|e| {
                 error!(ns=%ns, deployment_id=%deployment_id, error = %e, "🚨 Failed to store secrets in Vault");
-                AppError::InternalServerError(format!("🚨 Failed to store secrets in Vault: {}", e))
             })?;
 
         info!(ns = %ns, deployment_id = %deployment_id, "🔐 Secrets stored in Vault at {}", path);
@@ -84,17 +88,75 @@ impl VaultService {
 
         let secret = kv2::read(&*self.client, &self.cfg.kv_mount, &path)
             .await
-            .map_err(|e| {
+            .inspect_err(|e| {
                 error!(ns=%ns, deployment_id=%deployment_id, error = %e, "🚨 Failed to read secrets from Vault");
-                AppError::InternalServerError(format!(
-                    "🚨 Failed to read secrets from Vault: {}",
-                    e
-                ))
             })?;
 
         Ok(secret)
     }
 
+    /// Like `read_secrets`, but `None` when nothing was ever stored for the deployment
+    pub async fn try_read_secrets(
+        &self,
+        ns: &str,
+        deployment_id: &str,
+    ) -> Result<Option<HashMap<String, String>>, AppError> {
+        let path = format!("{}/{}", ns, deployment_id);
+
+        match kv2::read(&*self.client, &self.cfg.kv_mount, &path).await {
+            Ok(secret) => Ok(Some(secret)),
+            Err(ClientError::APIError { code: 404, .. }) => Ok(None),
+            Err(e) => {
+                error!(ns=%ns, deployment_id=%deployment_id, error = %e, "🚨 Failed to read secrets from Vault");
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Appends who changed which keys to `audit/{ns}/{deployment_id}`, values never leave the secret.
+    /// Keeps the last `SECRET_AUDIT_MAX_ENTRIES` entries
+    #[tracing::instrument(name = "vault_service.audit_secret_change", skip_all, fields(ns = %ns, deployment_id = %deployment_id), err)]
+    pub async fn audit_secret_change(
+        &self,
+        ns: &str,
+        deployment_id: &str,
+        changed_by: &Uuid,
+        fields_changed: Vec<String>,
+    ) -> Result<(), AppError> {
+        let path = format!("audit/{}/{}", ns, deployment_id);
+
+        let mut trail = match kv2::read::<SecretAuditTrail>(
+            &*self.client,
+            &self.cfg.kv_mount,
+            &path,
+        )
+        .await
+        {
+            Ok(trail) => trail,
+            Err(ClientError::APIError { code: 404, .. }) => SecretAuditTrail::default(),
+            Err(e) => {
+                error!(ns=%ns, deployment_id=%deployment_id, error = %e, "🚨 Failed to read secret audit trail from Vault");
+                return Err(e.into());
+            }
+        };
+
+        trail.entries.push(SecretAuditEntry {
+            changed_by: *changed_by,
+            changed_at: Utc::now(),
+            fields_changed,
+        });
+        let overflow = trail.entries.len().saturating_sub(SECRET_AUDIT_MAX_ENTRIES);
+        trail.entries.drain(..overflow);
+
+        kv2::set(&*self.client, &self.cfg.kv_mount, &path, &trail)
+            .await
+            .inspect_err(|e| {
+                error!(ns=%ns, deployment_id=%deployment_id, error = %e, "🚨 Failed to write secret audit trail to Vault");
+            })?;
+
+        Ok(())
+    }
+
     /// Update deployment secrets
     pub async fn update_secrets(
         &self,
@@ -106,9 +168,8 @@ impl VaultService {
 
         kv2::set(&*self.client, &self.cfg.kv_mount, &path, &secrets)
             .await
-            .map_err(|e| {
+            .inspect_err(|e| {
                 error!(ns=%ns, deployment_id=%deployment_id, error = %e, "🚨 Failed to update secrets in Vault");
-                AppError::InternalServerError(format!("🚨 Failed to update secrets in Vault: {}", e))
             })?;
 
         Ok(())
@@ -120,14 +181,38 @@ impl VaultService {
 
         kv2::delete_latest(&*self.client, &self.cfg.kv_mount, &path)
             .await
-            .map_err(|e| {
+            .inspect_err(|e| {
                 error!(ns=%ns, deployment_id=%deployment_id, error = %e, "🚨 Failed to delete secrets from Vault");
-                AppError::InternalServerError(format!("🚨 Failed to delete secrets from Vault: {}", e))
             })?;
 
         Ok(())
     }
 
+    /// Permanently removes every deployment's secrets under the namespace, versions included
+    pub async fn purge_namespace(&self, ns: &str) -> Result<(), AppError> {
+        let deployment_ids = match kv2::list(&*self.client, &self.cfg.kv_mount, ns).await {
+            Ok(ids) => ids,
+            // Nothing was ever stored for the namespace, or an earlier purge got everything
+            Err(ClientError::APIError { code: 404, .. }) => return Ok(()),
+            Err(e) => {
+                error!(ns=%ns, error = %e, "🚨 Failed to list secrets in Vault");
+                return Err(e.into());
+            }
+        };
+
+        for deployment_id in deployment_ids {
+            let path = format!("{}/{}", ns, deployment_id.trim_end_matches('/'));
+            kv2::delete_metadata(&*self.client, &self.cfg.kv_mount, &path)
+                .await
+                .inspect_err(|e| {
+                    error!(ns=%ns, path=%path, error = %e, "🚨 Failed to purge secrets from Vault");
+                })?;
+        }
+
+        info!(ns = %ns, "🔐 Purged secrets of namespace from Vault");
+        Ok(())
+    }
+
     /// Get secret keys
     pub async fn get_secret_keys(
         &self,
@@ -138,3 +223,23 @@ impl VaultService {
         Ok(secrets.keys().cloned().collect())
     }
 }
+
+/// Keys added, removed or given a new value between two versions of a secret, sorted
+pub fn changed_secret_keys(
+    previous: &HashMap<String, String>,
+    next: &HashMap<String, String>,
+) -> Vec<String> {
+    let mut keys: Vec<String> = next
+        .iter()
+        .filter(|(key, value)| previous.get(*key) != Some(*value))
+        .map(|(key, _)| key.clone())
+        .chain(
+            previous
+                .keys()
+                .filter(|key| !next.contains_key(*key))
+                .cloned(),
+        )
+        .collect();
+    keys.sort();
+    keys
+}
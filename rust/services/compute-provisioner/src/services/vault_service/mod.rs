@@ -4,6 +4,9 @@ use std::sync::Arc;
 use serde::Deserialize;
 use vaultrs::client::VaultClient;
 
+/// Older secret changes fall off the audit trail
+pub const SECRET_AUDIT_MAX_ENTRIES: usize = 100;
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct VaultConnectionConfig {
     pub address: String,
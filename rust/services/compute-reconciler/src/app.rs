@@ -1,10 +1,12 @@
-use crate::error::AppError;
+use crate::{error::AppError, services::reconcilation_loop::ReconcilerHealth};
 use axum::{
     Router,
-    extract::DefaultBodyLimit,
-    http::{HeaderName, HeaderValue, Method, header},
+    extract::{DefaultBodyLimit, State},
+    http::{HeaderName, HeaderValue, Method, StatusCode, header},
+    routing::get,
 };
 use http_common::{
+    body_limit::StreamingBodyLimitLayer,
     router::base_routes,
     trace_layer::{custom_make_span::CustomMakeSpan, custom_on_response::CustomOnResponse},
 };
@@ -13,6 +15,7 @@ use tower_http::{cors::CorsLayer, trace::TraceLayer};
 pub async fn app(
     cargo_pkg_name: &'static str,
     cargo_pkg_version: &'static str,
+    health: ReconcilerHealth,
 ) -> Result<Router, AppError> {
     let cors = CorsLayer::new()
         .allow_origin([
@@ -44,9 +47,20 @@ pub async fn app(
 
     let app = axum::Router::new()
         .merge(base_routes(cargo_pkg_name, cargo_pkg_version))
-        .layer(DefaultBodyLimit::max(50 * 1024 * 1024))
+        .route("/healthz/reconciler", get(reconciler_health_handler))
+        .with_state(health)
+        .layer(DefaultBodyLimit::disable())
+        .layer(StreamingBodyLimitLayer::new(50 * 1024 * 1024))
         .layer(tracer_layer)
         .layer(cors);
 
     Ok(app)
 }
+
+async fn reconciler_health_handler(State(health): State<ReconcilerHealth>) -> StatusCode {
+    if health.is_healthy() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
@@ -3,11 +3,13 @@ use std::{net::SocketAddr, path::PathBuf};
 use compute_core::configs::PrometheusConfig;
 use config::{ConfigBuilder, ConfigError, Environment, File, builder::AsyncState};
 use factory::factories::{
-    amqp::AmqpConfig, database::DatabaseConfig, observability::ObservabilityConfig,
-    redis::RedisConfig,
+    amqp::AmqpConfig, database::DatabaseConfig, kubernetes::KubernetesConfig,
+    observability::ObservabilityConfig, redis::RedisConfig,
 };
 use serde::Deserialize;
 
+use crate::services::s3::S3ServiceConfig;
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct Config {
     pub server_address: SocketAddr,
@@ -17,6 +19,16 @@ pub struct Config {
     pub amqp: AmqpConfig,
     pub prometheus: PrometheusConfig,
     pub reconciliation_interval_secs: u64,
+    pub s3: S3ServiceConfig,
+    /// How many of a terminating pod's last log lines are archived
+    pub log_archive_tail_lines: Option<i64>,
+    /// Empty user namespaces are only logged until this is turned off, defaults to on
+    pub namespace_gc_dry_run: Option<bool>,
+    /// Orphaned deployment resources are only logged until this is turned off, defaults to on
+    pub orphan_cleaner_dry_run: Option<bool>,
+    /// The inferred cluster alone when no regions are configured
+    #[serde(default)]
+    pub clusters: KubernetesConfig,
 }
 
 impl Config {
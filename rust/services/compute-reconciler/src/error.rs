@@ -27,7 +27,7 @@ pub enum AppError {
     #[error("Encountered an error trying to convert an infallible value: {0}")]
     FromRequestPartsError(#[from] std::convert::Infallible),
 
-    #[error("Serde json error")]
+    #[error("Serde json error: {0}")]
     SerdejsonError(#[from] serde_json::Error),
 
     #[error("External service error")]
@@ -38,7 +38,7 @@ pub enum AppError {
     },
     #[error("Service unavailable error")]
     ServiceUnavailable(String),
-    #[error("Internal server error")]
+    #[error("Internal server error: {0}")]
     InternalServerError(String),
 
     #[error("IO error, {0}")]
@@ -47,6 +47,18 @@ pub enum AppError {
     #[error("Redis error: {0}")]
     RedisError(#[from] redis::RedisError),
 
+    #[error("Object store error: {0}")]
+    ObjectStoreError(#[from] object_store::Error),
+
+    #[error("Kubernetes API error: {0}")]
+    KubeError(#[from] kube::Error),
+
+    #[error("Amqp error: {0}")]
+    AmqpError(#[from] factory::factories::amqp::error::AmqpError),
+
+    #[error("PrometheusHttpQueryError, {0}")]
+    PrometheusHttpQueryError(#[from] prometheus_http_query::Error),
+
     #[error("Token creation error")]
     TokenCreationError,
     #[error("Invalid token error")]
@@ -140,6 +152,22 @@ impl IntoResponse for AppError {
                 StatusCode::INTERNAL_SERVER_ERROR,
                 format!("Internal server error: {}", e),
             ),
+            Self::ObjectStoreError(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Internal server error: {}", e),
+            ),
+            Self::KubeError(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Internal server error: {}", e),
+            ),
+            Self::AmqpError(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Internal server error: {}", e),
+            ),
+            Self::PrometheusHttpQueryError(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Internal server error: {}", e),
+            ),
 
             Self::InvalidTokenError => (
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -13,6 +13,7 @@ use factory::factories::{
     database::Database, kubernetes::Kubernetes, observability::Observability, redis::Redis,
 };
 
+use tokio::sync::mpsc;
 use tokio::task::JoinSet;
 use tracing::{error, info};
 use utility::shutdown_signal::shutdown_signal;
@@ -20,9 +21,22 @@ use utility::shutdown_signal::shutdown_signal;
 use crate::{
     config::Config,
     error::AppError,
-    services::{event_watcher::event_watcher, reconcilation_loop::start_reconciliation_loop},
+    services::{
+        event_watcher::event_watcher,
+        log_archiver::log_archiver,
+        namespace_gc::namespace_gc_task,
+        notifier::Notifier,
+        orphan_cleaner::orphan_cleaner_task,
+        reconcilation_loop::{ReconcilerHealth, start_reconciliation_loop},
+        restart_scheduler::restart_scheduler_task,
+        s3::build_s3,
+    },
 };
 
+const DEFAULT_LOG_ARCHIVE_TAIL_LINES: i64 = 5000;
+/// Pods terminating faster than the archiver uploads queue up to this many
+const LOG_ARCHIVE_QUEUE_SIZE: usize = 256;
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     rustls::crypto::ring::default_provider()
@@ -53,30 +67,71 @@ async fn main() -> anyhow::Result<()> {
     )
     .await;
 
-    let kubernetes = Kubernetes::new().await?;
+    let kubernetes = Kubernetes::new(&cfg.clusters).await?;
     let database = Database::new(&cfg.database).await;
-    let redis = Redis::new(&cfg.redis).await;
+    let pool_metrics = database.clone().report_metrics();
+    let redis = Redis::new(&cfg.redis).await?;
     let amqp = Amqp::new(&cfg.amqp).await;
+    let s3 = build_s3(&cfg.s3);
+    let notifier = Notifier::new()?;
+    let health = ReconcilerHealth::new(cfg.reconciliation_interval_secs);
+    let prometheus = prometheus_http_query::Client::try_from(cfg.prometheus.url.as_str())?;
 
     let mut set = JoinSet::new();
 
-    // Spawn tasks into the set
-    set.spawn(event_watcher(
-        cfg.clone(),
+    // Spawn tasks into the set, every region's cluster is watched on its own
+    for client in kubernetes.regions.values() {
+        let (archive_tx, archive_rx) = mpsc::channel(LOG_ARCHIVE_QUEUE_SIZE);
+        set.spawn(event_watcher(
+            cfg.clone(),
+            database.pool.clone(),
+            redis.con.clone(),
+            amqp.clone(),
+            client.clone(),
+            archive_tx,
+            notifier.clone(),
+        ));
+        set.spawn(log_archiver(
+            archive_rx,
+            cfg.log_archive_tail_lines
+                .unwrap_or(DEFAULT_LOG_ARCHIVE_TAIL_LINES),
+            database.pool.clone(),
+            s3.clone(),
+            client.clone(),
+        ));
+        set.spawn(namespace_gc_task(
+            database.pool.clone(),
+            client.clone(),
+            cfg.namespace_gc_dry_run.unwrap_or(true),
+        ));
+        set.spawn(orphan_cleaner_task(
+            database.pool.clone(),
+            client.clone(),
+            cfg.orphan_cleaner_dry_run.unwrap_or(true),
+        ));
+    }
+    set.spawn(start_reconciliation_loop(
+        health.clone(),
         database.pool.clone(),
         redis.con.clone(),
         amqp.clone(),
-        kubernetes.client.clone(),
+        prometheus,
+        kubernetes.clone(),
     ));
-    set.spawn(start_reconciliation_loop(
-        cfg.reconciliation_interval_secs,
+    set.spawn(restart_scheduler_task(
         database.pool.clone(),
-        kubernetes.client.clone(),
+        redis.con.clone(),
+        amqp.clone(),
     ));
+    set.spawn(async move {
+        pool_metrics.await;
+        Ok(())
+    });
     set.spawn(start_health_server(
         cargo_pkg_name,
         cargo_pkg_version,
         cfg.server_address,
+        health,
     ));
 
     info!("✅ All background tasks started");
@@ -108,8 +163,9 @@ async fn start_health_server(
     cargo_pkg_name: &'static str,
     cargo_pkg_version: &'static str,
     addr: SocketAddr,
+    health: ReconcilerHealth,
 ) -> Result<(), AppError> {
-    let app = app::app(cargo_pkg_name, cargo_pkg_version).await?;
+    let app = app::app(cargo_pkg_name, cargo_pkg_version, health).await?;
     let listener = tokio::net::TcpListener::bind(addr).await?;
 
     info!("🚀 {} service running at {:#?}", cargo_pkg_name, addr);
@@ -0,0 +1,209 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use compute_core::cache_keys::CacheKeys;
+use compute_core::channel_names::ChannelNames;
+use compute_core::event::{ComputeEvent, VersionedEvent};
+use compute_core::models::{
+    DeploymentEventLevel, DeploymentEventType, DeploymentStatus, NotificationEvent,
+};
+use compute_core::services::event_emission_service::{
+    DeploymentEventEmitter, DeploymentEventEmitterInput,
+};
+use k8s_openapi::api::batch::v1::Job;
+use kube::{Api, Client, api::DeleteParams};
+use redis::AsyncTypedCommands;
+use redis::aio::MultiplexedConnection;
+use sqlx::PgPool;
+use tracing::{Instrument, error, info_span, warn};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::services::notifier::Notifier;
+
+/// Used when the deployment doesn't set `build_timeout_seconds`
+const DEFAULT_BUILD_TIMEOUT_SECS: i64 = 900;
+/// The start time outlives the timeout a little, a reconciler restarting right at the deadline
+/// still finds it
+const STARTED_AT_GRACE_SECS: i64 = 3600;
+
+/// A build Job that hasn't finished yet
+pub struct RunningBuild {
+    pub project_id: Uuid,
+    pub deployment_id: Uuid,
+    pub build_id: Uuid,
+    pub namespace: String,
+    pub job_name: String,
+    /// Unix seconds
+    pub started_at: i64,
+}
+
+/// Starts the timeout of a running build. The start time is kept in Redis, so a reconciler that
+/// restarts arms it again from the replayed Job with the time that is left. `replayed` Jobs
+/// are armed even when the start time was recorded before
+pub async fn arm_build_timeout(
+    build: RunningBuild,
+    replayed: bool,
+    client: &Client,
+    pool: &PgPool,
+    con: &mut MultiplexedConnection,
+    notifier: &Notifier,
+) -> Result<(), AppError> {
+    let key = CacheKeys::build_started_at(&build.build_id.to_string());
+
+    let timeout_secs = sqlx::query_scalar!(
+        r#"
+        SELECT COALESCE(build_timeout_seconds, $2) AS "timeout!"
+        FROM deployments
+        WHERE id = $1
+        "#,
+        build.deployment_id,
+        DEFAULT_BUILD_TIMEOUT_SECS as i32
+    )
+    .fetch_optional(pool)
+    .await?;
+    let Some(timeout_secs) = timeout_secs.map(i64::from) else {
+        return Ok(());
+    };
+
+    // Every status change of the Job arrives here, only the first one arms the timer
+    let first_time = con.set_nx(&key, build.started_at).await?;
+    if first_time {
+        con.expire(&key, timeout_secs + STARTED_AT_GRACE_SECS)
+            .await?;
+    } else if !replayed {
+        return Ok(());
+    }
+    let started_at = con
+        .get_int(&key)
+        .await?
+        .map(|started_at| started_at as i64)
+        .unwrap_or(build.started_at);
+
+    let remaining = (started_at + timeout_secs - Utc::now().timestamp()).max(0) as u64;
+    let span = info_span!(
+        "build_timeout",
+        deployment_id = %build.deployment_id,
+        build_id = %build.build_id,
+    );
+    let (client, pool, mut con, notifier) =
+        (client.clone(), pool.clone(), con.clone(), notifier.clone());
+
+    tokio::spawn(
+        async move {
+            tokio::time::sleep(Duration::from_secs(remaining)).await;
+            if let Err(e) =
+                cancel_build(&build, timeout_secs, &client, &pool, &mut con, &notifier).await
+            {
+                error!(error = %e, "❌ Failed to cancel timed out build");
+            }
+        }
+        .instrument(span),
+    );
+
+    Ok(())
+}
+
+/// A finished build drops its start time, the timer armed for it then does nothing
+pub async fn clear_build_timeout(
+    build_id: &Uuid,
+    con: &mut MultiplexedConnection,
+) -> Result<(), AppError> {
+    con.del(CacheKeys::build_started_at(&build_id.to_string()))
+        .await?;
+    Ok(())
+}
+
+async fn cancel_build(
+    build: &RunningBuild,
+    timeout_secs: i64,
+    client: &Client,
+    pool: &PgPool,
+    con: &mut MultiplexedConnection,
+    notifier: &Notifier,
+) -> Result<(), AppError> {
+    let key = CacheKeys::build_started_at(&build.build_id.to_string());
+    if !con.exists(&key).await? {
+        return Ok(());
+    }
+
+    warn!(
+        "⏱️ Build {} ran past {}s, cancelling it",
+        build.build_id, timeout_secs
+    );
+
+    // Background propagation takes the build pod along
+    let api: Api<Job> = Api::namespaced(client.clone(), &build.namespace);
+    match api
+        .delete(&build.job_name, &DeleteParams::background())
+        .await
+    {
+        Ok(_) => {}
+        Err(kube::Error::Api(ae)) if ae.code == 404 => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    // A timer armed twice, after a watcher restart, fails the build once
+    let cancelled = sqlx::query!(
+        r#"
+        UPDATE builds
+        SET status = 'failed', finished_at = NOW()
+        WHERE id = $1 AND status = 'running'
+        "#,
+        build.build_id
+    )
+    .execute(pool)
+    .await?;
+    con.del(&key).await?;
+    if cancelled.rows_affected() == 0 {
+        return Ok(());
+    }
+
+    let message = format!(
+        "Build {} timed out after {} minutes and was cancelled",
+        build.build_id,
+        timeout_secs / 60
+    );
+
+    DeploymentEventEmitter::emit(
+        DeploymentEventEmitterInput {
+            project_id: &build.project_id,
+            deployment_id: &build.deployment_id,
+            status: Some(DeploymentStatus::BuildFailed),
+            event_type: Some(DeploymentEventType::BuildFailed),
+            level: Some(DeploymentEventLevel::Error),
+            message: Some(&message),
+            persist_event: true,
+            publish_project: true,
+            publish_deployment: true,
+        },
+        pool,
+        con,
+    )
+    .await?;
+
+    let deployment_id = build.deployment_id.to_string();
+    let system_message = ComputeEvent::DeploymentSystemMessage {
+        deployment_id: &deployment_id,
+        level: DeploymentEventLevel::Error,
+        message: message.clone(),
+    };
+    VersionedEvent::new(system_message)
+        .publish(&ChannelNames::deployment_metrics(&deployment_id), con)
+        .await?;
+
+    if let Err(e) = notifier
+        .notify(
+            &build.deployment_id,
+            NotificationEvent::BuildFailed,
+            &message,
+            pool,
+            con,
+        )
+        .await
+    {
+        warn!(error = %e, "⚠️ Failed to send notification");
+    }
+
+    Ok(())
+}
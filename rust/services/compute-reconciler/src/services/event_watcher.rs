@@ -1,33 +1,43 @@
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use compute_core::cache_keys::CacheKeys;
 use compute_core::channel_names::ChannelNames;
 use compute_core::determiners::determine_deployment_status;
-use compute_core::event::ComputeEvent;
-use compute_core::models::{DeploymentEventType, DeploymentStatus};
+use compute_core::event::{ComputeEvent, VersionedEvent};
+use compute_core::models::{
+    BuildStatus, BuildType, DeploymentEventLevel, DeploymentEventType, DeploymentStatus,
+    NotificationEvent,
+};
+use compute_core::repository::DeploymentRepository;
 use compute_core::schemas::{
     DeploymentSourceMessage, MetricSnapshot, Pod, PodMeta, PodPhase, UpdateDeploymentMessage,
 };
 use compute_core::services::event_emission_service::{
     DeploymentEventEmitter, DeploymentEventEmitterInput,
 };
-use factory::factories::amqp::{Amqp, AmqpPropagator};
+use factory::factories::amqp::Amqp;
 use futures::StreamExt;
 use k8s_openapi::api::apps::v1::Deployment as K8sDeployment;
 use k8s_openapi::api::batch::v1::Job;
-use k8s_openapi::api::core::v1::Pod as K8sPod;
+use k8s_openapi::api::core::v1::{
+    Event as K8sEvent, PersistentVolumeClaim, Pod as K8sPod, PodSpec,
+};
 use kube::runtime::watcher::{Config as WatcherConfig, Event};
 use kube::{Api, Client};
-use lapin::BasicProperties;
-use lapin::options::BasicPublishOptions;
-use lapin::types::FieldTable;
 use redis::aio::MultiplexedConnection;
-use redis::{AsyncTypedCommands, pipe};
+use redis::{AsyncTypedCommands, ExistenceCheck, SetExpiry, SetOptions, pipe};
 use sqlx::PgPool;
+use tokio::sync::mpsc::Sender;
 use tracing::{Instrument, error, info, info_span, warn};
 use uuid::Uuid;
 
 use crate::config::Config;
 use crate::error::AppError;
+use crate::services::build_timeout::{RunningBuild, arm_build_timeout, clear_build_timeout};
+use crate::services::log_archiver::PodLogArchiveRequest;
+use crate::services::notifier::{Notifier, status_notification};
+
+/// A terminating pod shows up several times before it's deleted, it's archived once
+const LOG_ARCHIVE_DEDUP_TTL_SECS: u64 = 3600;
 
 pub async fn event_watcher(
     cfg: Config,
@@ -35,38 +45,57 @@ pub async fn event_watcher(
     mut con: MultiplexedConnection,
     amqp: Amqp,
     client: Client,
+    archive_tx: Sender<PodLogArchiveRequest>,
+    notifier: Notifier,
 ) -> Result<(), AppError> {
     let watcher_config = WatcherConfig::default().labels("poddle.io/managed-by=poddle");
 
     let deployment: Api<K8sDeployment> = Api::all(client.clone());
     let pod: Api<K8sPod> = Api::all(client.clone());
     let buildkit_job: Api<Job> = Api::all(client.clone());
+    let pvc: Api<PersistentVolumeClaim> = Api::all(client.clone());
+    let k8s_event: Api<K8sEvent> = Api::all(client.clone());
     // let kpack_build: Api<Build> = Api::all(client.clone());
 
     let mut deployment_stream = kube::runtime::watcher(deployment, watcher_config.clone()).boxed();
     let mut pod_stream = kube::runtime::watcher(pod, watcher_config.clone()).boxed();
     let mut buildkit_job_stream =
         kube::runtime::watcher(buildkit_job, watcher_config.clone()).boxed();
+    let mut pvc_stream = kube::runtime::watcher(pvc, watcher_config.clone()).boxed();
+    // Events are created by the kubelet and controllers without our labels, so they are narrowed
+    // by field instead and matched to a deployment through the pod they are about
+    let k8s_event_config = WatcherConfig::default().fields("type=Warning,involvedObject.kind=Pod");
+    let mut k8s_event_stream = kube::runtime::watcher(k8s_event, k8s_event_config).boxed();
     // let mut kpack_build_stream = kube::runtime::watcher(kpack_build, watcher_config).boxed();
 
     info!("🔍 Starting Kubernetes watchers");
     loop {
         tokio::select! {
             Some(event) = deployment_stream.next() => {
-                if let Err(e) = handle_deployment_event(event, &pool, &mut con).await {
+                if let Err(e) = handle_deployment_event(event, &pool, &mut con, &notifier).await {
                     error!(error = %e, "❌ Failed to handle deployment event: {}", e);
                 }
             }
             Some(event) = pod_stream.next() => {
-                if let Err(e) = handle_pod_event(event, &cfg, &pool, &mut con).await {
+                if let Err(e) = handle_pod_event(event, &cfg, &pool, &mut con, &archive_tx, &notifier).await {
                     error!(error = %e, "❌ Failed to handle pod event");
                 }
             }
             Some(event) = buildkit_job_stream.next() => {
-                if let Err(e) = handle_buildkit_job_event(event, &cfg, &pool, &mut con, &amqp).await {
+                if let Err(e) = handle_buildkit_job_event(event, &cfg, &pool, &mut con, &amqp, &client, &notifier).await {
                     error!(error = %e, "❌ Failed to handle job event");
                 }
             }
+            Some(event) = pvc_stream.next() => {
+                if let Err(e) = handle_pvc_event(event, &mut con).await {
+                    error!(error = %e, "❌ Failed to handle persistent volume claim event");
+                }
+            }
+            Some(event) = k8s_event_stream.next() => {
+                if let Err(e) = handle_k8s_event(event, &mut con).await {
+                    error!(error = %e, "❌ Failed to handle kubernetes event");
+                }
+            }
             // Some(event) = kpack_build_stream.next() => {
             //     if let Err(e) = handle_kpack_build_event(event, &pool, &mut redis, &amqp, &client).await {
             //         error!(error = %e, "❌ Failed to handle kpack build event");
@@ -87,6 +116,7 @@ async fn handle_deployment_event(
     event: Result<Event<K8sDeployment>, kube::runtime::watcher::Error>,
     pool: &PgPool,
     mut con: &mut MultiplexedConnection,
+    notifier: &Notifier,
 ) -> Result<(), AppError> {
     match event {
         Ok(Event::Apply(deployment)) => {
@@ -156,15 +186,18 @@ async fn handle_deployment_event(
                 con.lpush(&metrics_key, idle_snapshot).await?;
             }
 
-            DeploymentEventEmitter::emit(
+            // Every resync re-applies the current status, only a real transition goes to history
+            let previous = DeploymentRepository::get_status(&deployment_id, pool).await?;
+            let message = format!("Deployment status changed to {}", new_status);
+            let changed = DeploymentEventEmitter::emit(
                 DeploymentEventEmitterInput {
                     project_id: &project_id,
                     deployment_id: &deployment_id,
                     status: Some(new_status),
                     event_type: Some(DeploymentEventType::StatusChanged),
                     level: None,
-                    message: Some(&format!("Deployment status changed to {}", new_status)),
-                    persist_event: true,
+                    message: Some(&message),
+                    persist_event: previous != Some(new_status),
                     publish_project: true,
                     publish_deployment: true,
                 },
@@ -172,6 +205,10 @@ async fn handle_deployment_event(
                 &mut con,
             )
             .await?;
+            if changed {
+                let event = status_notification(new_status);
+                notify(notifier, &deployment_id, event, &message, pool, con).await;
+            }
         }
         Ok(Event::Delete(deployment)) => {
             let labels = deployment.metadata.labels.as_ref();
@@ -250,6 +287,8 @@ async fn handle_pod_event(
     _cfg: &Config,
     pool: &PgPool,
     mut con: &mut MultiplexedConnection,
+    archive_tx: &Sender<PodLogArchiveRequest>,
+    notifier: &Notifier,
 ) -> Result<(), AppError> {
     match event {
         Ok(Event::Apply(pod)) => {
@@ -293,6 +332,21 @@ async fn handle_pod_event(
 
             let uid = pod.metadata.uid.as_ref().unwrap().to_string();
             let name = pod.metadata.name.as_ref().unwrap().to_string();
+
+            // Terminating pods still have their containers, by the Delete event the logs are gone
+            if pod.metadata.deletion_timestamp.is_some()
+                && let Some(ns) = &ns
+            {
+                let req = PodLogArchiveRequest {
+                    deployment_id,
+                    namespace: ns.clone(),
+                    pod_name: name.clone(),
+                    pod_uid: uid.clone(),
+                    container: main_container(pod.spec.as_ref()),
+                };
+                request_log_archive(req, con, archive_tx).await?;
+            }
+
             let phase = pod
                 .status
                 .as_ref()
@@ -347,16 +401,15 @@ async fn handle_pod_event(
                     .execute(pool)
                     .await?;
 
-                    // rate limit using Redis SETNX + TTL
+                    // rate limit using Redis SET NX EX, 5 minutes TTL (or longer)
                     let notified_key =
                         CacheKeys::deployment_image_error_notified(&deployment_id.to_string());
-
-                    let first_time: bool = con.set_nx(&notified_key, 1).await?;
+                    let options = SetOptions::default()
+                        .conditional_set(ExistenceCheck::NX)
+                        .with_expiration(SetExpiry::EX(300));
+                    let first_time = con.set_options(&notified_key, 1, options).await?.is_some();
 
                     if first_time {
-                        // 5 minutes TTL (or longer)
-                        con.expire(&notified_key, 300).await?;
-
                         let mut msg = "Image pull failed. This image may be private or credentials are missing/invalid.".to_string();
                         if let Some(detail) = &crash_message {
                             msg.push_str(&format!(" Details: {}", detail));
@@ -378,9 +431,14 @@ async fn handle_pod_event(
                             &mut con,
                         )
                         .await?;
+                        // The status was already set above, the emit never sees it change
+                        let event = status_notification(DeploymentStatus::ImagePullError);
+                        notify(notifier, &deployment_id, event, &msg, pool, con).await;
                     }
                 } else {
-                    DeploymentEventEmitter::emit(
+                    // Every pod update while crashing lands here, the restart count below keeps later ones
+                    let previous = DeploymentRepository::get_status(&deployment_id, pool).await?;
+                    let changed = DeploymentEventEmitter::emit(
                         DeploymentEventEmitterInput {
                             project_id: &project_id,
                             deployment_id: &deployment_id,
@@ -388,7 +446,7 @@ async fn handle_pod_event(
                             event_type: Some(DeploymentEventType::UnhealthyDetected),
                             level: None,
                             message: Some("Deployment is crashing unhealthy"),
-                            persist_event: true,
+                            persist_event: previous != Some(DeploymentStatus::Unhealthy),
                             publish_project: true,
                             publish_deployment: true,
                         },
@@ -396,6 +454,11 @@ async fn handle_pod_event(
                         &mut con,
                     )
                     .await?;
+                    if changed {
+                        let event = status_notification(DeploymentStatus::Unhealthy);
+                        let message = format!("Pod {} is crashing: {}", name, reason);
+                        notify(notifier, &deployment_id, event, &message, pool, con).await;
+                    }
 
                     // keep your CrashLoopBackOff restart-based spam control if you want
                     if restart_count > 0 && restart_count % 3 == 0 {
@@ -438,6 +501,9 @@ async fn handle_pod_event(
 
             let score = Utc::now().timestamp();
             p.zadd(&index_key, &uid, score).ignore();
+            if let Some(ns) = &ns {
+                p.set(CacheKeys::pod_deployment(ns, &name), dep_id).ignore();
+            }
 
             let meta = PodMeta {
                 uid,
@@ -465,8 +531,8 @@ async fn handle_pod_event(
                     ..Default::default()
                 },
             };
-            p.publish(channel, message);
             p.query_async::<()>(con).await?;
+            VersionedEvent::new(message).publish(&channel, con).await?;
         }
         Ok(Event::Delete(pod)) => {
             let labels = pod.metadata.labels.as_ref();
@@ -487,6 +553,19 @@ async fn handle_pod_event(
 
             let uid = pod.metadata.uid.as_ref().unwrap().to_string();
             let name = pod.metadata.name.as_ref().unwrap().to_string();
+
+            // Catches pods whose terminating update was missed, e.g. across a watcher restart
+            if let Some(ns) = pod.metadata.namespace.as_deref() {
+                let req = PodLogArchiveRequest {
+                    deployment_id,
+                    namespace: ns.to_string(),
+                    pod_name: name.clone(),
+                    pod_uid: uid.clone(),
+                    container: main_container(pod.spec.as_ref()),
+                };
+                request_log_archive(req, con, archive_tx).await?;
+            }
+
             let phase: PodPhase = pod
                 .status
                 .as_ref()
@@ -524,11 +603,14 @@ async fn handle_pod_event(
             p.zrem(index_key, &uid).ignore();
             p.del(&meta_key).ignore();
             p.del(&metrics_key).ignore();
+            if let Some(ns) = pod.metadata.namespace.as_deref() {
+                p.del(CacheKeys::pod_deployment(ns, &name)).ignore();
+            }
 
             let channel = ChannelNames::deployment_metrics(&deployment_id.to_string());
             let message = ComputeEvent::PodDelete { uid };
-            p.publish(channel, message);
             p.query_async::<()>(con).await?;
+            VersionedEvent::new(message).publish(&channel, con).await?;
         }
         Ok(Event::Init) | Ok(Event::InitApply(_)) | Ok(Event::InitDone) => {}
         Err(e) => error!("❌ Pod watcher error: {}", e),
@@ -537,6 +619,169 @@ async fn handle_pod_event(
     Ok(())
 }
 
+/// Queues the pod's logs for archiving unless that was already done for this pod
+async fn request_log_archive(
+    req: PodLogArchiveRequest,
+    con: &mut MultiplexedConnection,
+    archive_tx: &Sender<PodLogArchiveRequest>,
+) -> Result<(), AppError> {
+    let archived_key = CacheKeys::pod_log_archived(&req.pod_uid);
+    if con.exists(&archived_key).await? {
+        return Ok(());
+    }
+
+    // Never block the watcher on a backed up archiver
+    if let Err(e) = archive_tx.try_send(req) {
+        warn!("⚠️ Dropping pod log archive request: {}", e);
+        return Ok(());
+    }
+
+    // Marked only once queued, a dropped request is tried again on the pod's next event
+    let options = SetOptions::default()
+        .conditional_set(ExistenceCheck::NX)
+        .with_expiration(SetExpiry::EX(LOG_ARCHIVE_DEDUP_TTL_SECS));
+    con.set_options(&archived_key, 1, options).await?;
+
+    Ok(())
+}
+
+/// Our pods run a single container, naming it keeps sidecars injected later out of the archive
+fn main_container(spec: Option<&PodSpec>) -> Option<String> {
+    spec.and_then(|s| s.containers.first())
+        .map(|c| c.name.clone())
+}
+
+/// Warning events about our pods, OOM kills and failing probes only show up here
+#[tracing::instrument("handle_k8s_event", skip_all, err)]
+async fn handle_k8s_event(
+    event: Result<Event<K8sEvent>, kube::runtime::watcher::Error>,
+    con: &mut MultiplexedConnection,
+) -> Result<(), AppError> {
+    // Init events replay what already happened before the watcher (re)started
+    let k8s_event = match event {
+        Ok(Event::Apply(k8s_event)) => k8s_event,
+        Ok(Event::Init) | Ok(Event::InitApply(_)) | Ok(Event::InitDone) | Ok(Event::Delete(_)) => {
+            return Ok(());
+        }
+        Err(e) => {
+            error!("❌ Event watcher error: {}", e);
+            return Ok(());
+        }
+    };
+
+    let involved = &k8s_event.involved_object;
+    let (Some(ns), Some(pod_name)) = (involved.namespace.as_deref(), involved.name.as_deref())
+    else {
+        return Ok(());
+    };
+
+    // Pods of other workloads were never recorded by the pod watcher
+    let Some(deployment_id) = con.get(CacheKeys::pod_deployment(ns, pod_name)).await? else {
+        return Ok(());
+    };
+
+    let reason = k8s_event.reason.as_deref().unwrap_or("Unknown");
+    let message = k8s_event.message.as_deref().unwrap_or_default();
+
+    warn!(
+        deployment_id = %deployment_id,
+        namespace = %ns,
+        pod = %pod_name,
+        kind = ?involved.kind,
+        field_path = ?involved.field_path,
+        reason = %reason,
+        count = ?k8s_event.count,
+        "⚠️ Kubernetes warning event: {}",
+        message
+    );
+
+    let system_message = ComputeEvent::DeploymentSystemMessage {
+        deployment_id: &deployment_id,
+        level: DeploymentEventLevel::Warning,
+        message: format!("Pod {}: {}: {}", pod_name, reason, message),
+    };
+    VersionedEvent::new(system_message)
+        .publish(&ChannelNames::deployment_metrics(&deployment_id), con)
+        .await?;
+
+    Ok(())
+}
+
+/// Claims only move between `Pending`, `Bound` and `Lost`, each change is pushed to the deployment channel
+#[tracing::instrument("handle_pvc_event", skip_all, err)]
+async fn handle_pvc_event(
+    event: Result<Event<PersistentVolumeClaim>, kube::runtime::watcher::Error>,
+    con: &mut MultiplexedConnection,
+) -> Result<(), AppError> {
+    match event {
+        Ok(Event::Apply(pvc)) => {
+            let labels = pvc.metadata.labels.as_ref();
+            let deployment_id = labels
+                .and_then(|l| l.get("poddle.io/deployment-id"))
+                .and_then(|id| Uuid::parse_str(id).ok());
+            let volume = labels.and_then(|l| l.get("poddle.io/volume-name"));
+
+            let (Some(deployment_id), Some(volume)) = (deployment_id, volume) else {
+                // Not our claim, skip
+                return Ok(());
+            };
+
+            let phase = pvc
+                .status
+                .as_ref()
+                .and_then(|s| s.phase.as_deref())
+                .unwrap_or("Pending");
+
+            info!(
+                deployment_id = %deployment_id,
+                volume = %volume,
+                phase = %phase,
+                "📥 PersistentVolumeClaim Event::Apply received",
+            );
+
+            let channel = ChannelNames::deployment_metrics(&deployment_id.to_string());
+            let message = ComputeEvent::VolumeStatus { volume, phase };
+            VersionedEvent::new(message).publish(&channel, con).await?;
+        }
+        Ok(Event::Delete(_)) => {}
+        Ok(Event::Init) | Ok(Event::InitApply(_)) | Ok(Event::InitDone) => {}
+        Err(e) => error!("❌ PersistentVolumeClaim watcher error: {}", e),
+    }
+
+    Ok(())
+}
+
+/// Upserts the build's row, a finished build is never moved back to running by a late event
+#[tracing::instrument("record_build", skip_all, fields(build_id = %build_id, status = ?status), err)]
+async fn record_build(
+    build_id: &Uuid,
+    deployment_id: &Uuid,
+    status: BuildStatus,
+    started_at: DateTime<Utc>,
+    image: &str,
+    pool: &PgPool,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO builds (id, deployment_id, build_type, status, started_at, finished_at, image)
+        VALUES ($1, $2, $3, $4, $5, CASE WHEN $4 = 'running'::build_status THEN NULL ELSE NOW() END, $6)
+        ON CONFLICT (id) DO UPDATE
+        SET status = EXCLUDED.status, finished_at = EXCLUDED.finished_at
+        WHERE builds.status = 'running'
+        "#,
+        build_id,
+        deployment_id,
+        BuildType::Buildkit as BuildType,
+        status as BuildStatus,
+        started_at,
+        image
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
 #[tracing::instrument("handle_buildkit_job_event", skip_all, err)]
 async fn handle_buildkit_job_event(
     event: Result<Event<Job>, kube::runtime::watcher::Error>,
@@ -544,6 +789,8 @@ async fn handle_buildkit_job_event(
     pool: &PgPool,
     mut con: &mut MultiplexedConnection,
     amqp: &Amqp,
+    client: &Client,
+    notifier: &Notifier,
 ) -> Result<(), AppError> {
     match event {
         Ok(Event::Apply(job)) => {
@@ -577,15 +824,38 @@ async fn handle_buildkit_job_event(
             let succeeded = job.status.as_ref().and_then(|s| s.succeeded).unwrap_or(0);
             let failed = job.status.as_ref().and_then(|s| s.failed).unwrap_or(0);
 
+            // Construct the new image string, this depends on build
+            let image = format!(
+                "me-central1-docker.pkg.dev/poddle-mvp/buildkit/{}:{}",
+                deployment_id, build_id
+            );
+
+            let status = match (succeeded > 0, failed > 0) {
+                (true, _) => BuildStatus::Succeeded,
+                (false, true) => BuildStatus::Failed,
+                (false, false) => BuildStatus::Running,
+            };
+            if let Ok(build_id) = Uuid::parse_str(build_id) {
+                let started_at = job
+                    .status
+                    .as_ref()
+                    .and_then(|s| s.start_time.as_ref())
+                    .or(job.metadata.creation_timestamp.as_ref())
+                    .and_then(|t| DateTime::from_timestamp(t.0.as_second(), 0))
+                    .unwrap_or_else(Utc::now);
+                record_build(&build_id, &deployment_id, status, started_at, &image, pool).await?;
+
+                match running_build(&job) {
+                    Some(build) => {
+                        arm_build_timeout(build, false, client, pool, con, notifier).await?
+                    }
+                    None => clear_build_timeout(&build_id, con).await?,
+                }
+            }
+
             if succeeded > 0 {
                 info!("✅ Build Job {} Succeeded", name);
 
-                // Construct the new image string, this depends on build
-                let image = format!(
-                    "me-central1-docker.pkg.dev/poddle-mvp/buildkit/{}:{}",
-                    deployment_id, build_id
-                );
-
                 let user_id = sqlx::query_scalar!(
                     "SELECT user_id FROM deployments WHERE id = $1",
                     deployment_id
@@ -608,38 +878,30 @@ async fn handle_buildkit_job_event(
                     secrets: None,
                     environment_variables: None,
                     labels: None,
+                    tags: None,
                     domain: None,
                     subdomain: None,
+                    autoscaling: None,
+                    canary: None,
+                    restart_at: None,
+                    ip_allowlist: None,
+                    alert_thresholds: None,
+                    config_maps: None,
                     timestamp: Utc::now().timestamp(),
                 };
 
-                let channel = amqp.channel().await;
-
-                let payload = serde_json::to_vec(&message)?;
-
-                let mut headers = FieldTable::default();
-                AmqpPropagator::inject_context(&mut headers);
-
-                // Publish message
-                channel
-                    .basic_publish(
-                        "compute",
-                        "compute.update",
-                        BasicPublishOptions::default(),
-                        &payload,
-                        BasicProperties::default()
-                            .with_delivery_mode(2)
-                            .with_content_type("application/json".into())
-                            .with_headers(headers),
-                    )
+                amqp.basic_publish("compute", "compute.update", &message)
                     .instrument(info_span!("basic_publish.compute.update"))
-                    .await?
                     .await?;
 
                 info!(
                     "📤 Published deployment update message for {}",
                     deployment_id
                 );
+
+                let message = format!("Build {} succeeded, rolling out the new image", build_id);
+                let event = Some(NotificationEvent::BuildSucceeded);
+                notify(notifier, &deployment_id, event, &message, pool, con).await;
             } else if failed > 0 {
                 error!("❌ Build Job {} Failed", name);
 
@@ -659,15 +921,69 @@ async fn handle_buildkit_job_event(
                     &mut con,
                 )
                 .await?;
+
+                let message = format!("Build {} failed", build_id);
+                let event = Some(NotificationEvent::BuildFailed);
+                notify(notifier, &deployment_id, event, &message, pool, con).await;
             }
         }
         Ok(Event::Delete(job)) => {
             info!("✅ buildkit build watcher delete: {:?}", job.metadata.name)
         }
         Ok(Event::Init) => info!("✅ buildkit build watcher init"),
-        Ok(Event::InitApply(_)) => {}
+        // Timers die with the reconciler, builds still running get theirs back
+        Ok(Event::InitApply(job)) => {
+            if let Some(build) = running_build(&job) {
+                arm_build_timeout(build, true, client, pool, con, notifier).await?;
+            }
+        }
         Ok(Event::InitDone) => info!("✅ buildkit build watcher ready"),
         Err(e) => error!("❌ buildkit build watcher error: {}", e),
     }
     Ok(())
 }
+
+/// Ids and start of a build Job that hasn't succeeded or failed yet
+fn running_build(job: &Job) -> Option<RunningBuild> {
+    let status = job.status.as_ref();
+    if status.and_then(|s| s.succeeded).unwrap_or(0) > 0
+        || status.and_then(|s| s.failed).unwrap_or(0) > 0
+    {
+        return None;
+    }
+
+    let labels = job.metadata.labels.as_ref()?;
+    let label = |key: &str| labels.get(key).and_then(|id| Uuid::parse_str(id).ok());
+    let started_at = status
+        .and_then(|s| s.start_time.as_ref())
+        .or(job.metadata.creation_timestamp.as_ref())
+        .map(|t| t.0.as_second())
+        .unwrap_or_else(|| Utc::now().timestamp());
+
+    Some(RunningBuild {
+        project_id: label("poddle.io/project-id")?,
+        deployment_id: label("poddle.io/deployment-id")?,
+        build_id: label("poddle.io/build-id")?,
+        namespace: job.metadata.namespace.clone()?,
+        job_name: job.metadata.name.clone()?,
+        started_at,
+    })
+}
+
+/// A failed notification is only logged, the event itself was handled
+async fn notify(
+    notifier: &Notifier,
+    deployment_id: &Uuid,
+    event: Option<NotificationEvent>,
+    message: &str,
+    pool: &PgPool,
+    con: &mut MultiplexedConnection,
+) {
+    if let Some(event) = event
+        && let Err(e) = notifier
+            .notify(deployment_id, event, message, pool, con)
+            .await
+    {
+        warn!(deployment_id = %deployment_id, error = %e, "⚠️ Failed to send notification");
+    }
+}
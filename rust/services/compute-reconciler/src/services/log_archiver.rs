@@ -0,0 +1,120 @@
+use std::io::Write;
+
+use chrono::Utc;
+use flate2::{Compression, write::GzEncoder};
+use k8s_openapi::api::core::v1::Pod as K8sPod;
+use kube::{Api, Client, api::LogParams};
+use object_store::{ObjectStore, PutPayload, aws::AmazonS3, path::Path};
+use sqlx::PgPool;
+use tokio::sync::mpsc::Receiver;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+/// A pod on its way out whose logs should outlive it
+#[derive(Debug)]
+pub struct PodLogArchiveRequest {
+    pub deployment_id: Uuid,
+    pub namespace: String,
+    pub pod_name: String,
+    pub pod_uid: String,
+    pub container: Option<String>,
+}
+
+/// Archives pods one at a time so a slow upload never holds up the event watcher
+pub async fn log_archiver(
+    mut rx: Receiver<PodLogArchiveRequest>,
+    tail_lines: i64,
+    pool: PgPool,
+    s3: AmazonS3,
+    client: Client,
+) -> Result<(), AppError> {
+    info!("🗄️ Starting pod log archiver");
+
+    while let Some(req) = rx.recv().await {
+        if let Err(e) = archive_pod_logs(&req, tail_lines, &pool, &s3, &client).await {
+            error!(
+                deployment_id = %req.deployment_id,
+                pod_uid = %req.pod_uid,
+                "❌ Failed to archive pod logs: {}",
+                e
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(
+    name = "archive_pod_logs",
+    skip_all,
+    fields(deployment_id = %req.deployment_id, pod_uid = %req.pod_uid),
+    err
+)]
+async fn archive_pod_logs(
+    req: &PodLogArchiveRequest,
+    tail_lines: i64,
+    pool: &PgPool,
+    s3: &AmazonS3,
+    client: &Client,
+) -> Result<(), AppError> {
+    let pods: Api<K8sPod> = Api::namespaced(client.clone(), &req.namespace);
+    let params = LogParams {
+        container: req.container.clone(),
+        tail_lines: Some(tail_lines),
+        timestamps: true,
+        ..Default::default()
+    };
+
+    let logs = match pods.logs(&req.pod_name, &params).await {
+        Ok(logs) => logs,
+        // The kubelet already cleaned the containers up, nothing left to save
+        Err(kube::Error::Api(e)) if e.code == 404 => {
+            warn!(
+                "⚠️ Pod {} is gone, its logs can't be archived",
+                req.pod_name
+            );
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if logs.is_empty() {
+        return Ok(());
+    }
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(logs.as_bytes())?;
+    let compressed = encoder.finish()?;
+
+    let object_path = format!(
+        "logs/{}/{}/{}.log.gz",
+        req.deployment_id,
+        req.pod_uid,
+        Utc::now().timestamp()
+    );
+    s3.put(
+        &Path::from(object_path.as_str()),
+        PutPayload::from(compressed),
+    )
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO pod_log_archives (deployment_id, pod_uid, object_path)
+        VALUES ($1, $2, $3)
+        "#,
+        req.deployment_id,
+        req.pod_uid,
+        object_path
+    )
+    .execute(pool)
+    .await?;
+
+    info!(
+        "🗄️ Archived logs of pod {} to {}",
+        req.pod_name, object_path
+    );
+
+    Ok(())
+}
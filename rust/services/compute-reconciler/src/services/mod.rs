@@ -1,2 +1,9 @@
+pub mod build_timeout;
 pub mod event_watcher;
+pub mod log_archiver;
+pub mod namespace_gc;
+pub mod notifier;
+pub mod orphan_cleaner;
 pub mod reconcilation_loop;
+pub mod restart_scheduler;
+pub mod s3;
@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use compute_core::formatters::format_namespace;
+use k8s_openapi::api::core::v1::{Namespace, PersistentVolumeClaim};
+use kube::{
+    Api, Client,
+    api::{DeleteParams, ListParams},
+};
+use sqlx::PgPool;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+const NAMESPACE_GC_INTERVAL_SECS: u64 = 24 * 60 * 60;
+/// The provisioner creates the namespace before the first deployment is running, younger ones
+/// may still be on their way to getting one
+const NAMESPACE_GC_MIN_AGE_SECS: i64 = 24 * 60 * 60;
+
+/// Deletes the namespaces of users without deployments, Kubernetes cascades the deletion to the
+/// VaultAuth, VaultConnection and secrets left inside. A dry run only logs the candidates
+pub async fn namespace_gc_task(
+    pool: PgPool,
+    client: Client,
+    dry_run: bool,
+) -> Result<(), AppError> {
+    let mut interval = tokio::time::interval(Duration::from_secs(NAMESPACE_GC_INTERVAL_SECS));
+
+    info!(
+        dry_run = dry_run,
+        "🧹 Starting namespace garbage collection"
+    );
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = collect_namespaces(&pool, &client, dry_run).await {
+            error!(error = %e, "❌ Namespace garbage collection failed");
+        }
+    }
+}
+
+#[tracing::instrument("collect_namespaces", skip_all, fields(dry_run = dry_run), err)]
+async fn collect_namespaces(pool: &PgPool, client: &Client, dry_run: bool) -> Result<(), AppError> {
+    let api: Api<Namespace> = Api::all(client.clone());
+    // Only namespaces created by the provisioner carry the label
+    let namespaces = api.list(&ListParams::default().labels("user-id")).await?;
+    let cutoff = Utc::now().timestamp() - NAMESPACE_GC_MIN_AGE_SECS;
+
+    let mut collected = 0;
+    for namespace in namespaces {
+        let metadata = namespace.metadata;
+        let Some(name) = metadata.name.as_deref() else {
+            continue;
+        };
+        if metadata.deletion_timestamp.is_some() {
+            continue;
+        }
+        if metadata
+            .creation_timestamp
+            .as_ref()
+            .is_none_or(|created| created.0.as_second() > cutoff)
+        {
+            continue;
+        }
+
+        let user_id = metadata
+            .labels
+            .as_ref()
+            .and_then(|l| l.get("user-id"))
+            .and_then(|id| Uuid::parse_str(id).ok());
+        let Some(user_id) = user_id.filter(|id| format_namespace(id) == name) else {
+            warn!(namespace = %name, "⚠️ Namespace label doesn't match a user namespace, skipping");
+            continue;
+        };
+
+        let has_deployments = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM deployments WHERE user_id = $1 AND status != 'deleted')",
+            user_id
+        )
+        .fetch_one(pool)
+        .await?
+        .unwrap_or(true);
+        if has_deployments {
+            continue;
+        }
+
+        // Volumes kept on purpose outlive their deployment, so does the namespace holding them
+        let pvc_api: Api<PersistentVolumeClaim> = Api::namespaced(client.clone(), name);
+        let retained = pvc_api
+            .list_metadata(
+                &ListParams::default()
+                    .labels("poddle.io/retain=true")
+                    .limit(1),
+            )
+            .await?;
+        if !retained.items.is_empty() {
+            info!(namespace = %name, user_id = %user_id, "🧹 Keeping namespace with retained volumes");
+            continue;
+        }
+
+        if dry_run {
+            info!(namespace = %name, user_id = %user_id, "🧹 Dry run, would delete namespace without deployments");
+        } else {
+            api.delete(name, &DeleteParams::default()).await?;
+            info!(namespace = %name, user_id = %user_id, "🧹 Deleted namespace without deployments");
+        }
+        collected += 1;
+    }
+
+    info!(
+        collected = collected,
+        dry_run = dry_run,
+        "🧹 Namespace garbage collection finished"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,221 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use compute_core::cache_keys::CacheKeys;
+use compute_core::models::{
+    DeploymentStatus, NotificationChannelRow, NotificationChannelType, NotificationEvent,
+};
+use redis::aio::MultiplexedConnection;
+use redis::{AsyncTypedCommands, ExistenceCheck, SetExpiry, SetOptions};
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+/// A deployment posts the same event at most once per this many seconds
+const NOTIFICATION_RATE_LIMIT_SECS: u64 = 5 * 60;
+/// users-api drops the cached channels whenever they change, this only bounds a missed drop
+const NOTIFICATION_CHANNELS_CACHE_TTL_SECS: u64 = 10 * 60;
+const WEBHOOK_TIMEOUT_SECS: u64 = 10;
+
+/// Posts deployment events to the Slack and Discord webhooks their owners subscribed
+#[derive(Clone)]
+pub struct Notifier {
+    http: reqwest::Client,
+}
+
+impl Notifier {
+    pub fn new() -> Result<Self, AppError> {
+        let http = reqwest::ClientBuilder::new()
+            .timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECS))
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+        Ok(Self { http })
+    }
+
+    /// Deliveries run in the background, a slow webhook never holds up the watchers
+    #[tracing::instrument("notifier.notify", skip_all, fields(deployment_id = %deployment_id, event = %event))]
+    pub async fn notify(
+        &self,
+        deployment_id: &Uuid,
+        event: NotificationEvent,
+        message: &str,
+        pool: &PgPool,
+        con: &mut MultiplexedConnection,
+    ) -> Result<(), AppError> {
+        let Some(deployment) = sqlx::query!(
+            "SELECT user_id, project_id, name FROM deployments WHERE id = $1",
+            deployment_id
+        )
+        .fetch_optional(pool)
+        .await?
+        else {
+            return Ok(());
+        };
+
+        let channels: Vec<NotificationChannelRow> = get_channels(&deployment.user_id, pool, con)
+            .await?
+            .into_iter()
+            .filter(|c| c.events.contains(&event))
+            .collect();
+        if channels.is_empty() {
+            return Ok(());
+        }
+
+        let notified_key =
+            CacheKeys::deployment_notified(&deployment_id.to_string(), &event.to_string());
+        let options = SetOptions::default()
+            .conditional_set(ExistenceCheck::NX)
+            .with_expiration(SetExpiry::EX(NOTIFICATION_RATE_LIMIT_SECS));
+        if con.set_options(&notified_key, 1, options).await?.is_none() {
+            return Ok(());
+        }
+
+        let notification = Notification {
+            event,
+            deployment_id: *deployment_id,
+            project_id: deployment.project_id,
+            deployment_name: deployment.name,
+            message: message.to_string(),
+        };
+
+        for channel in channels {
+            let payload = match channel.channel_type {
+                NotificationChannelType::Slack => notification.slack_payload(),
+                NotificationChannelType::Discord => notification.discord_payload(),
+            };
+            let http = self.http.clone();
+            tokio::spawn(async move {
+                let result = http
+                    .post(&channel.webhook_url)
+                    .json(&payload)
+                    .send()
+                    .await
+                    .and_then(|r| r.error_for_status());
+                match result {
+                    Ok(_) => info!(channel_id = %channel.id, "🔔 Notification delivered"),
+                    Err(e) => {
+                        error!(channel_id = %channel.id, error = %e, "❌ Notification delivery failed")
+                    }
+                }
+            });
+        }
+
+        Ok(())
+    }
+}
+
+async fn get_channels(
+    user_id: &Uuid,
+    pool: &PgPool,
+    con: &mut MultiplexedConnection,
+) -> Result<Vec<NotificationChannelRow>, AppError> {
+    let key = CacheKeys::user_notification_channels(&user_id.to_string());
+    if let Some(json) = con.get(&key).await? {
+        return Ok(serde_json::from_str(&json)?);
+    }
+
+    let channels = sqlx::query_as!(
+        NotificationChannelRow,
+        r#"
+        SELECT
+            id,
+            user_id,
+            type AS "channel_type: NotificationChannelType",
+            webhook_url,
+            events AS "events: Vec<NotificationEvent>",
+            created_at,
+            updated_at
+        FROM notification_channels
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    con.set_ex(
+        &key,
+        serde_json::to_string(&channels)?,
+        NOTIFICATION_CHANNELS_CACHE_TTL_SECS,
+    )
+    .await?;
+
+    Ok(channels)
+}
+
+struct Notification {
+    event: NotificationEvent,
+    deployment_id: Uuid,
+    project_id: Uuid,
+    deployment_name: String,
+    message: String,
+}
+
+impl Notification {
+    fn title(&self) -> String {
+        let name = &self.deployment_name;
+        match self.event {
+            NotificationEvent::DeploymentRunning => format!("✅ {name} is running"),
+            NotificationEvent::DeploymentUnhealthy => format!("⚠️ {name} is unhealthy"),
+            NotificationEvent::DeploymentFailed => format!("❌ {name} failed"),
+            NotificationEvent::BuildSucceeded => format!("✅ {name} was built"),
+            NotificationEvent::BuildFailed => format!("❌ {name} failed to build"),
+        }
+    }
+
+    /// Green, amber or red
+    fn color(&self) -> u32 {
+        match self.event {
+            NotificationEvent::DeploymentRunning | NotificationEvent::BuildSucceeded => 0x2EB67D,
+            NotificationEvent::DeploymentUnhealthy => 0xECB22E,
+            NotificationEvent::DeploymentFailed | NotificationEvent::BuildFailed => 0xE01E5A,
+        }
+    }
+
+    fn slack_payload(&self) -> Value {
+        json!({
+            "text": self.title(),
+            "attachments": [{
+                "color": format!("#{:06X}", self.color()),
+                "text": self.message,
+                "fields": [
+                    { "title": "Event", "value": self.event.to_string(), "short": true },
+                    { "title": "Deployment", "value": self.deployment_id.to_string(), "short": true },
+                    { "title": "Project", "value": self.project_id.to_string(), "short": true },
+                ],
+                "ts": Utc::now().timestamp(),
+            }],
+        })
+    }
+
+    fn discord_payload(&self) -> Value {
+        json!({
+            "embeds": [{
+                "title": self.title(),
+                "description": self.message,
+                "color": self.color(),
+                "fields": [
+                    { "name": "Event", "value": self.event.to_string(), "inline": true },
+                    { "name": "Deployment", "value": self.deployment_id.to_string(), "inline": true },
+                    { "name": "Project", "value": self.project_id.to_string(), "inline": true },
+                ],
+                "timestamp": Utc::now().to_rfc3339(),
+            }],
+        })
+    }
+}
+
+/// The event a deployment moving to `status` is announced as, if any
+pub fn status_notification(status: DeploymentStatus) -> Option<NotificationEvent> {
+    match status {
+        DeploymentStatus::Running => Some(NotificationEvent::DeploymentRunning),
+        DeploymentStatus::Unhealthy
+        | DeploymentStatus::Degraded
+        | DeploymentStatus::ImagePullError => Some(NotificationEvent::DeploymentUnhealthy),
+        DeploymentStatus::Failed => Some(NotificationEvent::DeploymentFailed),
+        _ => None,
+    }
+}
@@ -0,0 +1,208 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use chrono::Utc;
+use k8s_openapi::api::core::v1::Namespace;
+use kube::{
+    Api, Client,
+    api::{ApiResource, DeleteParams, DynamicObject, GroupVersionKind, ListParams},
+};
+use sqlx::PgPool;
+use tracing::{error, info, warn};
+use users_core::audit::AuditEntry;
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+const ORPHAN_CLEANER_INTERVAL_SECS: u64 = 60 * 60;
+/// Leaves objects of a deployment that is still being created or deleted alone
+const ORPHAN_MIN_AGE_SECS: i64 = 60 * 60;
+const DEPLOYMENT_ID_LABEL: &str = "poddle.io/deployment-id";
+
+/// Group, version, kind and plural of every kind the provisioner labels with the deployment id
+const ORPHAN_KINDS: [(&str, &str, &str, &str); 4] = [
+    ("apps", "v1", "Deployment", "deployments"),
+    ("", "v1", "Service", "services"),
+    ("traefik.io", "v1alpha1", "IngressRoute", "ingressroutes"),
+    (
+        "secrets.hashicorp.com",
+        "v1beta1",
+        "VaultStaticSecret",
+        "vaultstaticsecrets",
+    ),
+];
+
+/// Deletes objects whose deployment has no row left or is marked deleted, what a provisioner
+/// crash or a row removed ahead of its resources leaves behind. A dry run only logs them
+pub async fn orphan_cleaner_task(
+    pool: PgPool,
+    client: Client,
+    dry_run: bool,
+) -> Result<(), AppError> {
+    let mut interval = tokio::time::interval(Duration::from_secs(ORPHAN_CLEANER_INTERVAL_SECS));
+
+    info!(dry_run = dry_run, "🧹 Starting orphaned resource cleanup");
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = clean_orphans(&pool, &client, dry_run).await {
+            error!(error = %e, "❌ Orphaned resource cleanup failed");
+        }
+    }
+}
+
+/// A labeled object found in one of the user namespaces
+struct Labeled {
+    resource: ApiResource,
+    namespace: String,
+    name: String,
+    deployment_id: Uuid,
+}
+
+#[tracing::instrument("clean_orphans", skip_all, fields(dry_run = dry_run), err)]
+async fn clean_orphans(pool: &PgPool, client: &Client, dry_run: bool) -> Result<(), AppError> {
+    let cutoff = Utc::now().timestamp() - ORPHAN_MIN_AGE_SECS;
+
+    let mut objects = Vec::new();
+    for (group, version, kind, plural) in ORPHAN_KINDS {
+        let resource =
+            ApiResource::from_gvk_with_plural(&GroupVersionKind::gvk(group, version, kind), plural);
+        let api: Api<DynamicObject> = Api::all_with(client.clone(), &resource);
+
+        let list = match api
+            .list_metadata(&ListParams::default().labels(DEPLOYMENT_ID_LABEL))
+            .await
+        {
+            Ok(list) => list,
+            // The CRD isn't installed in this cluster, nothing of the kind can be orphaned
+            Err(kube::Error::Api(ae)) if ae.code == 404 => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        for object in list {
+            let metadata = object.metadata;
+            if metadata.deletion_timestamp.is_some()
+                || metadata
+                    .creation_timestamp
+                    .as_ref()
+                    .is_none_or(|created| created.0.as_second() > cutoff)
+            {
+                continue;
+            }
+            let (Some(namespace), Some(name)) = (metadata.namespace, metadata.name) else {
+                continue;
+            };
+            // Only namespaces created by the provisioner hold deployments
+            if !namespace.starts_with("user-") {
+                continue;
+            }
+            let deployment_id = metadata
+                .labels
+                .as_ref()
+                .and_then(|l| l.get(DEPLOYMENT_ID_LABEL))
+                .and_then(|id| Uuid::parse_str(id).ok());
+            let Some(deployment_id) = deployment_id else {
+                warn!(namespace = %namespace, name = %name, kind = %kind, "⚠️ Invalid deployment id label, skipping");
+                continue;
+            };
+
+            objects.push(Labeled {
+                resource: resource.clone(),
+                namespace,
+                name,
+                deployment_id,
+            });
+        }
+    }
+
+    let ids: Vec<Uuid> = objects
+        .iter()
+        .map(|o| o.deployment_id)
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let live: HashSet<Uuid> = sqlx::query_scalar!(
+        "SELECT id FROM deployments WHERE id = ANY($1) AND status != 'deleted'",
+        &ids
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .collect();
+
+    let mut owners: HashMap<String, Option<Uuid>> = HashMap::new();
+    let mut cleaned = 0;
+    for object in objects
+        .into_iter()
+        .filter(|o| !live.contains(&o.deployment_id))
+    {
+        let kind = object.resource.kind.as_str();
+
+        if dry_run {
+            info!(namespace = %object.namespace, name = %object.name, kind = %kind, deployment_id = %object.deployment_id, "🧹 Dry run, would delete orphaned resource");
+            cleaned += 1;
+            continue;
+        }
+
+        let api: Api<DynamicObject> =
+            Api::namespaced_with(client.clone(), &object.namespace, &object.resource);
+        match api.delete(&object.name, &DeleteParams::default()).await {
+            Ok(_) => {}
+            Err(kube::Error::Api(ae)) if ae.code == 404 => continue,
+            Err(e) => {
+                error!(namespace = %object.namespace, name = %object.name, kind = %kind, error = %e, "🚨 Orphaned resource delete failed");
+                continue;
+            }
+        }
+        info!(namespace = %object.namespace, name = %object.name, kind = %kind, deployment_id = %object.deployment_id, "🧹 Deleted orphaned resource");
+        cleaned += 1;
+
+        if !owners.contains_key(&object.namespace) {
+            let owner = namespace_owner(client, &object.namespace).await;
+            owners.insert(object.namespace.clone(), owner);
+        }
+        let Some(user_id) = owners.get(&object.namespace).copied().flatten() else {
+            continue;
+        };
+        let entry = AuditEntry {
+            user_id,
+            impersonated_by: None,
+            action: "orphan_cleanup.delete",
+            resource_type: kind,
+            resource_id: Some(object.deployment_id),
+        };
+        match entry.insert(pool).await {
+            Ok(()) => {}
+            // The user is gone too, their audit log went with them
+            Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => {}
+            Err(e) => {
+                warn!(namespace = %object.namespace, name = %object.name, error = %e, "⚠️ Orphaned resource deletion left out of the audit log");
+            }
+        }
+    }
+
+    info!(
+        cleaned = cleaned,
+        dry_run = dry_run,
+        "🧹 Orphaned resource cleanup finished"
+    );
+
+    Ok(())
+}
+
+/// The provisioner labels every namespace it creates with its user
+async fn namespace_owner(client: &Client, namespace: &str) -> Option<Uuid> {
+    let api: Api<Namespace> = Api::all(client.clone());
+    match api.get_metadata_opt(namespace).await {
+        Ok(metadata) => metadata
+            .and_then(|n| n.metadata.labels)
+            .and_then(|l| l.get("user-id").and_then(|id| Uuid::parse_str(id).ok())),
+        Err(e) => {
+            warn!(namespace = %namespace, error = %e, "⚠️ Failed to get namespace owner");
+            None
+        }
+    }
+}
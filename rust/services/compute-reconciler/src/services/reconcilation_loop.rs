@@ -1,24 +1,80 @@
+use chrono::{DateTime, Utc};
 use compute_core::{
+    cache_keys::CacheKeys,
     determiners::determine_deployment_status,
     formatters::{format_namespace, format_resource_name},
-    models::DeploymentStatus,
+    models::{DeploymentStatus, DeploymentType},
+    schemas::SuspendDeploymentMessage,
 };
+use factory::factories::{amqp::Amqp, kubernetes::Kubernetes};
 use k8s_openapi::api::apps::v1::Deployment as K8sDeployment;
-use kube::{Api, Client};
+use k8s_openapi::api::batch::v1::CronJob;
+use kube::Api;
+use opentelemetry::{KeyValue, global};
+use prometheus_http_query::{Client as PrometheusClient, response::Data};
+use redis::{AsyncTypedCommands, aio::MultiplexedConnection};
 use sqlx::PgPool;
-use std::time::Duration;
+use std::sync::{
+    Arc,
+    atomic::{AtomicI64, Ordering},
+};
+use std::time::{Duration, Instant};
 use tracing::{error, info, warn};
+use uuid::Uuid;
 
 use crate::error::AppError;
 
+/// Shared between the loop and `/healthz/reconciler`
+#[derive(Clone, Debug)]
+pub struct ReconcilerHealth {
+    pub interval_secs: u64,
+    /// Unix seconds of the last successful pass, starts at boot so the first pass has time to finish
+    pub last_success: Arc<AtomicI64>,
+}
+
+impl ReconcilerHealth {
+    pub fn new(interval_secs: u64) -> Self {
+        Self {
+            interval_secs,
+            last_success: Arc::new(AtomicI64::new(Utc::now().timestamp())),
+        }
+    }
+
+    /// A single slow pass is tolerated, missing two in a row means the loop is stuck or failing
+    pub fn is_healthy(&self) -> bool {
+        let last_success = self.last_success.load(Ordering::Relaxed);
+        Utc::now().timestamp() - last_success <= 2 * self.interval_secs as i64
+    }
+}
+
 /// Periodic reconciliation to catch missed events and fix drift
 pub async fn start_reconciliation_loop(
-    reconciliation_interval_secs: u64,
+    health: ReconcilerHealth,
     pool: PgPool,
-    client: Client,
+    mut con: MultiplexedConnection,
+    amqp: Amqp,
+    prometheus: PrometheusClient,
+    kubernetes: Kubernetes,
 ) -> Result<(), AppError> {
+    let reconciliation_interval_secs = health.interval_secs;
     let mut interval = tokio::time::interval(Duration::from_secs(reconciliation_interval_secs));
 
+    let meter = global::meter("reconciler");
+    let duration = meter
+        .f64_histogram("reconciler.loop.duration_seconds")
+        .with_description("Time a reconciliation pass took, failed ones included")
+        .with_unit("s")
+        .build();
+    let errors = meter
+        .u64_counter("reconciler.loop.errors_total")
+        .with_description("Reconciliation passes that failed")
+        .build();
+    let last_success = meter
+        .i64_gauge("reconciler.loop.last_success_timestamp")
+        .with_description("Unix time of the last successful pass")
+        .with_unit("s")
+        .build();
+
     info!(
         "🔄 Starting reconciliation loop, interval: {}",
         reconciliation_interval_secs
@@ -27,18 +83,46 @@ pub async fn start_reconciliation_loop(
     loop {
         interval.tick().await;
 
-        if let Err(e) = reconcile_deployments(&pool, &client).await {
-            error!(error = %e, "❌ Reconciliation failed");
+        let started = Instant::now();
+        let result = reconcile_deployments(&pool, &mut con, &amqp, &prometheus, &kubernetes).await;
+        duration.record(started.elapsed().as_secs_f64(), &[]);
+
+        match result {
+            Ok(()) => {
+                let now = Utc::now().timestamp();
+                health.last_success.store(now, Ordering::Relaxed);
+                last_success.record(now, &[]);
+            }
+            Err(e) => {
+                errors.add(1, &[KeyValue::new("reason", error_reason(&e))]);
+                error!(error = %e, "❌ Reconciliation failed");
+            }
         }
     }
 }
 
+fn error_reason(e: &AppError) -> &'static str {
+    match e {
+        AppError::SqlxError(_) => "database",
+        AppError::RedisError(_) => "redis",
+        _ => "other",
+    }
+}
+
 #[tracing::instrument("reconcile_deployments", skip_all, err)]
-async fn reconcile_deployments(pool: &PgPool, client: &Client) -> Result<(), AppError> {
+async fn reconcile_deployments(
+    pool: &PgPool,
+    con: &mut MultiplexedConnection,
+    amqp: &Amqp,
+    prometheus: &PrometheusClient,
+    kubernetes: &Kubernetes,
+) -> Result<(), AppError> {
     // Fetch all active deployments from database
     let db_deployments = sqlx::query!(
         r#"
-        SELECT id, user_id, status as "status: DeploymentStatus", desired_replicas, ready_replicas, available_replicas
+        SELECT id, user_id, project_id, status as "status: DeploymentStatus", desired_replicas, ready_replicas, available_replicas,
+            suspend_after_idle_minutes, region, updated_at,
+            deployment_type AS "deployment_type: DeploymentType"
         FROM deployments
         WHERE status NOT IN ('failed', 'suspended', 'image_pull_error')
         "#
@@ -58,6 +142,36 @@ async fn reconcile_deployments(pool: &PgPool, client: &Client) -> Result<(), App
         let name = &format_resource_name(&db_deployment.id);
         let id = db_deployment.id;
 
+        let Ok(client) = kubernetes.client_for(db_deployment.region.as_deref()) else {
+            warn!(id = %id, region = ?db_deployment.region, "⚠️ Deployment is in an unknown region, skipping");
+            continue;
+        };
+
+        // Scheduled tasks have no Deployment, whether their CronJob still exists is all there is
+        if db_deployment.deployment_type == DeploymentType::CronJob {
+            let cronjob_api: Api<CronJob> = Api::namespaced(client.clone(), namespace);
+            match cronjob_api.get_opt(name).await {
+                Ok(Some(_)) => {}
+                Ok(None) => {
+                    warn!(id = %id, "⚠️ CronJob {} exists in DB but not in K8s - marking as failed", id);
+                    sqlx::query!(
+                        r#"
+                            UPDATE deployments
+                            SET status = 'failed', updated_at = NOW()
+                            WHERE id = $1
+                        "#,
+                        id
+                    )
+                    .execute(pool)
+                    .await?;
+                }
+                Err(e) => {
+                    error!(error = %e, id = %id, "❌ Failed to check K8s cron job");
+                }
+            }
+            continue;
+        }
+
         // Try to fetch from Kubernetes
         let deployment_api: Api<K8sDeployment> = Api::namespaced(client.clone(), namespace);
 
@@ -119,6 +233,22 @@ async fn reconcile_deployments(pool: &PgPool, client: &Client) -> Result<(), App
                     .execute(pool)
                     .await?;
                 }
+
+                if let Some(idle_minutes) = db_deployment.suspend_after_idle_minutes {
+                    let idle = IdleDeployment {
+                        id,
+                        user_id: db_deployment.user_id,
+                        project_id: db_deployment.project_id,
+                        namespace,
+                        name,
+                        idle_minutes,
+                        updated_at: db_deployment.updated_at,
+                    };
+                    // Prometheus being down shouldn't fail the drift checks of everything else
+                    if let Err(e) = suspend_if_idle(&idle, con, amqp, prometheus).await {
+                        error!(error = %e, id = %id, "❌ Failed to check deployment idleness");
+                    }
+                }
             }
             Err(kube::Error::Api(e)) if e.code == 404 => {
                 // Deployment deleted from K8s but still in DB
@@ -152,3 +282,66 @@ async fn reconcile_deployments(pool: &PgPool, client: &Client) -> Result<(), App
 
     Ok(())
 }
+
+struct IdleDeployment<'a> {
+    id: Uuid,
+    user_id: Uuid,
+    project_id: Uuid,
+    namespace: &'a str,
+    name: &'a str,
+    idle_minutes: i32,
+    updated_at: DateTime<Utc>,
+}
+
+/// Idleness is judged by Traefik's request counter alone, an idle deployment keeps its replicas
+/// ready until it's suspended
+#[tracing::instrument("suspend_if_idle", skip_all, fields(deployment_id = %deployment.id), err)]
+async fn suspend_if_idle(
+    deployment: &IdleDeployment<'_>,
+    con: &mut MultiplexedConnection,
+    amqp: &Amqp,
+    prometheus: &PrometheusClient,
+) -> Result<(), AppError> {
+    // A freshly created or updated deployment hasn't had the chance to receive traffic yet
+    let idle_window = chrono::Duration::minutes(deployment.idle_minutes as i64);
+    if Utc::now() - deployment.updated_at < idle_window {
+        return Ok(());
+    }
+
+    // Traefik names IngressRoute services `{namespace}-{service}-{port}@kubernetescrd`
+    let query = format!(
+        r#"sum(increase(traefik_service_requests_total{{service=~"{}-{}-.*"}}[{}m]))"#,
+        deployment.namespace, deployment.name, deployment.idle_minutes
+    );
+    let response = prometheus.query(query).get().await?;
+
+    // No series at all means Traefik hasn't served a single request in the window
+    let requests = match response.data() {
+        Data::Vector(vecs) => vecs.first().map(|v| v.sample().value()).unwrap_or(0.0),
+        _ => return Ok(()),
+    };
+    if requests > 0.0 {
+        return Ok(());
+    }
+
+    let id = deployment.id.to_string();
+    con.set(CacheKeys::deployment_idle_suspended(&id), 1)
+        .await?;
+
+    let message = SuspendDeploymentMessage {
+        user_id: deployment.user_id,
+        project_id: deployment.project_id,
+        deployment_id: deployment.id,
+        timestamp: Utc::now().timestamp(),
+    };
+    amqp.basic_publish("compute", "compute.suspend", &message)
+        .await?;
+
+    info!(
+        id = %deployment.id,
+        "💤 Suspending deployment idle for {} minutes",
+        deployment.idle_minutes
+    );
+
+    Ok(())
+}
@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Timelike, Utc};
+use compute_core::cache_keys::CacheKeys;
+use compute_core::cron::CronSchedule;
+use compute_core::schemas::UpdateDeploymentMessage;
+use factory::factories::amqp::Amqp;
+use redis::{
+    AsyncTypedCommands, ExistenceCheck, SetExpiry, SetOptions, aio::MultiplexedConnection,
+};
+use sqlx::PgPool;
+use tracing::{Instrument, error, info, info_span, warn};
+
+use crate::error::AppError;
+
+/// Outlives the minute it's taken for, a replica whose clock lags a little still finds it
+const RESTART_SCHEDULER_LOCK_TTL_SECS: u64 = 120;
+
+/// Wakes at the start of every minute and asks the provisioner to roll the pods of deployments
+/// whose `restart_schedule` matches it
+pub async fn restart_scheduler_task(
+    pool: PgPool,
+    mut con: MultiplexedConnection,
+    amqp: Amqp,
+) -> Result<(), AppError> {
+    info!("🔁 Starting scheduled restarts");
+
+    loop {
+        let now = Utc::now();
+        let next_minute = 60 - u64::from(now.second());
+        tokio::time::sleep(Duration::from_secs(next_minute)).await;
+
+        let now = Utc::now();
+        match claim_minute(&mut con, &now).await {
+            Ok(true) => {}
+            // Another replica publishes this minute's restarts
+            Ok(false) => continue,
+            Err(e) => {
+                error!(error = %e, "❌ Failed to claim the scheduled restarts");
+                continue;
+            }
+        }
+
+        if let Err(e) = restart_scheduled(&pool, &amqp, now).await {
+            error!(error = %e, "❌ Scheduled restarts failed");
+        }
+    }
+}
+
+/// Every reconciler replica wakes each minute, only the one taking the minute's lock restarts
+async fn claim_minute(
+    con: &mut MultiplexedConnection,
+    now: &DateTime<Utc>,
+) -> Result<bool, AppError> {
+    let minute = now.timestamp() / 60;
+    let options = SetOptions::default()
+        .conditional_set(ExistenceCheck::NX)
+        .with_expiration(SetExpiry::EX(RESTART_SCHEDULER_LOCK_TTL_SECS));
+    let claimed = con
+        .set_options(CacheKeys::restart_scheduler_lock(minute), 1, options)
+        .await?;
+
+    Ok(claimed.is_some())
+}
+
+#[tracing::instrument("restart_scheduled", skip_all, err)]
+async fn restart_scheduled(pool: &PgPool, amqp: &Amqp, now: DateTime<Utc>) -> Result<(), AppError> {
+    // Suspended deployments have no pods to roll, the rest are mid-change already. A CronJob
+    // starts fresh pods every run and has no Deployment to restart
+    let deployments = sqlx::query!(
+        r#"
+        SELECT id, user_id, project_id, restart_schedule AS "restart_schedule!"
+        FROM deployments
+        WHERE restart_schedule IS NOT NULL
+          AND deployment_type = 'service'
+          AND status IN ('running', 'unhealthy', 'degraded')
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut restarted = 0;
+    for deployment in deployments {
+        let Some(schedule) = CronSchedule::parse(&deployment.restart_schedule) else {
+            warn!(deployment_id = %deployment.id, "⚠️ Invalid restart schedule, skipping");
+            continue;
+        };
+        if !schedule.matches(&now) {
+            continue;
+        }
+
+        let message = UpdateDeploymentMessage {
+            user_id: deployment.user_id,
+            project_id: deployment.project_id,
+            deployment_id: deployment.id,
+            name: None,
+            source: None,
+            port: None,
+            desired_replicas: None,
+            preset_id: None,
+            resource_spec: None,
+            secrets: None,
+            environment_variables: None,
+            labels: None,
+            tags: None,
+            domain: None,
+            subdomain: None,
+            autoscaling: None,
+            canary: None,
+            restart_at: Some(now.timestamp()),
+            ip_allowlist: None,
+            alert_thresholds: None,
+            config_maps: None,
+            timestamp: now.timestamp(),
+        };
+
+        amqp.basic_publish("compute", "compute.update", &message)
+            .instrument(info_span!("basic_publish.compute.update"))
+            .await?;
+
+        info!(deployment_id = %deployment.id, "🔁 Published scheduled restart");
+        restarted += 1;
+    }
+
+    if restarted > 0 {
+        info!(restarted = restarted, "🔁 Scheduled restarts published");
+    }
+
+    Ok(())
+}
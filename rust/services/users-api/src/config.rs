@@ -2,7 +2,7 @@ use std::{net::SocketAddr, path::PathBuf};
 
 use config::{ConfigBuilder, ConfigError, Environment, File, builder::AsyncState};
 use factory::factories::{
-    amqp::AmqpConfig, database::DatabaseConfig, mailtrap::MailtrapConfig,
+    amqp::AmqpConfig, database::DatabaseConfig, email::EmailConfig,
     observability::ObservabilityConfig, redis::RedisConfig,
 };
 use serde::Deserialize;
@@ -10,7 +10,7 @@ use users_core::jwt::JwtConfig;
 
 use crate::services::{
     github_oauth::GithubOAuthServiceConfig, google_oauth::GoogleOAuthServiceConfig,
-    s3::S3ServiceConfig,
+    s3::S3ServiceConfig, totp::TotpServiceConfig,
 };
 
 #[derive(Deserialize, Clone, Debug)]
@@ -23,11 +23,15 @@ pub struct Config {
     pub amqp: AmqpConfig,
     pub cookie_key: String,
     pub cookie_secure: bool,
+    /// HMAC key of the CSRF tokens
+    pub csrf_key: String,
     pub jwt: JwtConfig,
     pub google_oauth: GoogleOAuthServiceConfig,
     pub github_oauth: GithubOAuthServiceConfig,
     pub s3: S3ServiceConfig,
-    pub mailtrap: MailtrapConfig,
+    pub totp: TotpServiceConfig,
+    #[serde(flatten)]
+    pub email: EmailConfig,
 }
 
 impl Config {
@@ -1,7 +1,7 @@
 use axum::Json;
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
-use http_contracts::error::schema::ErrorResponse;
+use http_contracts::error::{AppError as HttpError, schema::ErrorResponse};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -27,7 +27,7 @@ pub enum AppError {
     #[error("Encountered an error trying to convert an infallible value: {0}")]
     FromRequestPartsError(#[from] std::convert::Infallible),
 
-    #[error("Serde json error")]
+    #[error("Serde json error: {0}")]
     SerdejsonError(#[from] serde_json::Error),
 
     #[error("External service error")]
@@ -38,10 +38,14 @@ pub enum AppError {
     },
     #[error("Service unavailable error")]
     ServiceUnavailable(String),
-    #[error("Internal server error")]
+    #[error("Internal server error: {0}")]
     InternalServerError(String),
     #[error("Object storage error: {0}")]
     ObjectStorageError(#[from] object_store::Error),
+    #[error("Redis error: {0}")]
+    RedisError(#[from] redis::RedisError),
+    #[error("AMQP error: {0}")]
+    AmqpError(#[from] factory::factories::amqp::error::AmqpError),
 
     #[error("Token creation error")]
     TokenCreationError,
@@ -67,7 +71,7 @@ pub enum AppError {
     MissingGithubOAuthIdError,
     #[error("Invalid authorization token error")]
     InvalidAuthorizationTokenError,
-    #[error("jsonwebtoken error")]
+    #[error("jsonwebtoken error: {0}")]
     JsonWebTokenError(#[from] jsonwebtoken::errors::Error),
     #[error("Missing session token token error")]
     MissingSessionTokenError,
@@ -116,7 +120,7 @@ pub enum AppError {
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let (status, error) = match self {
-            Self::SqlxError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            Self::SqlxError(e) => return HttpError::from(e).into_response(),
             Self::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
             Self::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
             Self::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
@@ -145,6 +149,8 @@ impl IntoResponse for AppError {
                 format!("Internal server error: {}", msg),
             ),
             Self::ObjectStorageError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            Self::RedisError(e) => return HttpError::from(e).into_response(),
+            Self::AmqpError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
 
             Self::InvalidTokenError => (
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -0,0 +1,125 @@
+use aide::axum::{
+    ApiRouter, IntoApiResponse,
+    routing::{get, patch, post},
+};
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use axum_extra::extract::Query;
+use chrono::{Duration, Utc};
+use factory::factories::database::Database;
+use http_contracts::{
+    message::MessageResponse,
+    pagination::schema::{Paginated, Pagination},
+};
+use tracing::{instrument, warn};
+use users_core::{
+    audit::AuditEntry,
+    jwt::{AdminClaims, IMPERSONATION_TOKEN_EXPIRE_IN_MINUTES, create_impersonation_token},
+};
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::AppError,
+    features::{
+        repositories::{feedbacks::FeedbacksRepository, users::UsersRepository},
+        schemas::{ImpersonationResponse, UpdateFeedbackStatusRequest},
+    },
+    utilities::app_state::AppState,
+};
+
+/// Nested under `/api/v1/admin`, every handler takes `AdminClaims`
+pub fn get_routes() -> ApiRouter<AppState> {
+    ApiRouter::new()
+        .api_route("/users/feedback", get(get_feedbacks_handler))
+        .api_route(
+            "/users/feedback/{feedback_id}",
+            patch(update_feedback_status_handler).delete(delete_feedback_handler),
+        )
+        .api_route(
+            "/users/{user_id}/impersonate",
+            post(impersonate_user_handler),
+        )
+}
+
+#[instrument(name = "admin.get_feedbacks_handler", skip_all, fields(admin_id = %claims.sub), err)]
+pub async fn get_feedbacks_handler(
+    AdminClaims(claims): AdminClaims,
+    Query(p): Query<Pagination>,
+    State(db): State<Database>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let (data, total) =
+        FeedbacksRepository::get_many_with_users(p.offset, p.limit, &db.pool).await?;
+
+    Ok(Json(Paginated::new(data, total, &p)))
+}
+
+#[instrument(name = "admin.update_feedback_status_handler", skip_all, fields(admin_id = %claims.sub, feedback_id = %feedback_id), err)]
+pub async fn update_feedback_status_handler(
+    AdminClaims(claims): AdminClaims,
+    Path(feedback_id): Path<Uuid>,
+    State(db): State<Database>,
+    Json(req): Json<UpdateFeedbackStatusRequest>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let feedback = FeedbacksRepository::update_status(&feedback_id, req.status, &db.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFoundError("Feedback not found".into()))?;
+
+    Ok(Json(feedback))
+}
+
+#[instrument(name = "admin.delete_feedback_handler", skip_all, fields(admin_id = %claims.sub, feedback_id = %feedback_id), err)]
+pub async fn delete_feedback_handler(
+    AdminClaims(claims): AdminClaims,
+    Path(feedback_id): Path<Uuid>,
+    State(db): State<Database>,
+) -> Result<impl IntoApiResponse, AppError> {
+    if !FeedbacksRepository::delete(&feedback_id, &db.pool).await? {
+        return Err(AppError::NotFoundError("Feedback not found".into()));
+    }
+
+    Ok(Json(MessageResponse::new("Feedback deleted")))
+}
+
+/// The token is handed back in the body only, setting it as a cookie would replace the
+/// admin's own session in the browser
+#[instrument(name = "admin.impersonate_user_handler", skip_all, fields(admin_id = %claims.sub, user_id = %user_id), err)]
+pub async fn impersonate_user_handler(
+    AdminClaims(claims): AdminClaims,
+    Path(user_id): Path<Uuid>,
+    State(db): State<Database>,
+    State(config): State<Config>,
+) -> Result<impl IntoApiResponse, AppError> {
+    if user_id == claims.sub {
+        return Err(AppError::BadRequest("Can't impersonate yourself".into()));
+    }
+
+    let user = UsersRepository::get(&user_id, &db.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => AppError::NotFoundError("User not found".to_string()),
+            e => e.into(),
+        })?;
+
+    let access_token = create_impersonation_token(&config, user.id, claims.sub)?;
+    let expires_at = Utc::now() + Duration::minutes(IMPERSONATION_TOKEN_EXPIRE_IN_MINUTES);
+
+    AuditEntry {
+        user_id: user.id,
+        impersonated_by: Some(claims.sub),
+        action: "impersonate",
+        resource_type: "users",
+        resource_id: Some(user.id),
+    }
+    .insert(&db.pool)
+    .await?;
+    warn!(admin_id = %claims.sub, user_id = %user.id, "🕵️ Admin started impersonating a user");
+
+    Ok(Json(ImpersonationResponse {
+        user_id: user.id,
+        access_token,
+        expires_at,
+    }))
+}
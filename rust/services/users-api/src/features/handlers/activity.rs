@@ -0,0 +1,82 @@
+use aide::axum::IntoApiResponse;
+use axum::{Json, extract::State};
+use axum_extra::extract::Query;
+use factory::factories::{database::Database, redis::Redis};
+use redis::AsyncCommands;
+use tracing::{instrument, warn};
+use users_core::jwt::Claims;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    features::{
+        repositories::activity::ActivityRepository,
+        schemas::{ActivityItem, ActivityQuery},
+    },
+};
+
+const ACTIVITY_PAGE_SIZE: i64 = 50;
+/// Short enough that new events show up on the next poll or so, long enough to absorb a
+/// frontend polling in a tight loop
+const ACTIVITY_CACHE_TTL_SECS: u64 = 5;
+
+#[instrument(name = "get_activity_handler", skip_all, fields(user_id = %claims.sub), err)]
+pub async fn get_activity_handler(
+    claims: Claims,
+    Query(q): Query<ActivityQuery>,
+    State(database): State<Database>,
+    State(redis): State<Redis>,
+) -> Result<impl IntoApiResponse, AppError> {
+    if q.before.is_some() && q.after.is_some() {
+        return Err(AppError::ValidationError(
+            "Only one of before and after can be given".into(),
+        ));
+    }
+
+    // The cache only absorbs polling, the feed is read from Postgres whenever Redis fails
+    let mut con = redis.con.clone();
+    let cache_key = user_activity_key(&claims.sub, &q);
+    let cached: Option<String> = con
+        .get(&cache_key)
+        .await
+        .inspect_err(|e| warn!(error = %e, "⚠️ Failed to get cached activity"))
+        .unwrap_or_default();
+    if let Some(items) = cached.and_then(|s| serde_json::from_str::<Vec<ActivityItem>>(&s).ok()) {
+        return Ok(Json(items));
+    }
+
+    let mut items = ActivityRepository::get_many(
+        &claims.sub,
+        q.before,
+        q.after,
+        ACTIVITY_PAGE_SIZE,
+        &database.pool,
+    )
+    .await?;
+    // The page after a cursor is read oldest first, the feed is always newest first
+    if q.after.is_some() {
+        items.reverse();
+    }
+
+    if let Err(e) = con
+        .set_ex::<_, _, ()>(
+            &cache_key,
+            serde_json::to_string(&items)?,
+            ACTIVITY_CACHE_TTL_SECS,
+        )
+        .await
+    {
+        warn!(error = %e, "⚠️ Failed to cache activity");
+    }
+
+    Ok(Json(items))
+}
+
+/// `users:{user_id}:activity:{cursor}`
+fn user_activity_key(user_id: &Uuid, q: &ActivityQuery) -> String {
+    match (q.before, q.after) {
+        (Some(before), _) => format!("users:{user_id}:activity:before:{before}"),
+        (_, Some(after)) => format!("users:{user_id}:activity:after:{after}"),
+        _ => format!("users:{user_id}:activity:latest"),
+    }
+}
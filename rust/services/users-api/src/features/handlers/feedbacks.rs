@@ -1,14 +1,17 @@
 use aide::axum::IntoApiResponse;
 use axum::{Json, extract::State};
 use axum_extra::extract::Query;
-use factory::factories::{database::Database, mailtrap::Mailtrap};
+use factory::factories::{
+    database::Database,
+    email::{Email, EmailProvider, EmailTemplate},
+};
 use http_contracts::{
-    list::schema::ListResponse, message::MessageResponse, pagination::schema::Pagination,
+    message::MessageResponse,
+    pagination::schema::{Paginated, Pagination},
 };
 use tracing::{error, instrument};
 
 use crate::{
-    config::Config,
     error::AppError,
     features::{repositories::feedbacks::FeedbacksRepository, schemas::CreateFeedbackRequest},
 };
@@ -20,12 +23,12 @@ pub async fn get_feedbacks_handler(
 ) -> Result<impl IntoApiResponse, AppError> {
     let (data, total) = FeedbacksRepository::get_many(p.offset, p.limit, &db.pool).await?;
 
-    Ok(Json(ListResponse { data, total }))
+    Ok(Json(Paginated::new(data, total, &p)))
 }
 
 #[instrument(name = "create_feedback_handler", skip_all)]
 pub async fn create_feedback_handler(
-    State(cfg): State<Config>,
+    State(email): State<Email>,
     State(db): State<Database>,
     Json(req): Json<CreateFeedbackRequest>,
 ) -> Result<impl IntoApiResponse, AppError> {
@@ -37,12 +40,15 @@ pub async fn create_feedback_handler(
         ));
     }
 
-    let mailtrap = Mailtrap::new();
-    if let Err(err) = mailtrap
-        .send_feedback_confirmation(&req.name, &req.email, &req.message, &cfg.mailtrap)
+    if let Err(err) = email
+        .send_templated_email(
+            &req.email,
+            EmailTemplate::FeedbackConfirmation,
+            serde_json::json!({ "name": req.name, "message": req.message }),
+        )
         .await
     {
-        error!(name: "MailtrapError", "Email failed but DB saved: {}", err);
+        error!(name: "EmailError", "Email failed but DB saved: {}", err);
     }
 
     let message = format!("Thank you for your feedback, {}!", req.name);
@@ -1,5 +1,8 @@
+pub mod activity;
 pub mod feedbacks;
+pub mod notifications;
 pub mod oauth_users;
 pub mod sessions;
 pub mod stats;
+pub mod two_factor;
 pub mod users;
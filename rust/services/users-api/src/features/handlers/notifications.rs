@@ -0,0 +1,115 @@
+use aide::axum::IntoApiResponse;
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use compute_core::cache_keys::CacheKeys;
+use factory::factories::{database::Database, redis::Redis};
+use redis::AsyncCommands;
+use tracing::instrument;
+use users_core::jwt::Claims;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    features::{
+        repositories::notification_channels::NotificationChannelsRepository,
+        schemas::{CreateNotificationChannelRequest, UpdateNotificationChannelRequest},
+    },
+};
+
+/// Channels a user may have, each one is posted to on every subscribed event
+const MAX_NOTIFICATION_CHANNELS: usize = 10;
+
+#[instrument(name = "get_notification_channels_handler", skip_all, fields(user_id = %claims.sub), err)]
+pub async fn get_notification_channels_handler(
+    claims: Claims,
+    State(database): State<Database>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let channels = NotificationChannelsRepository::get_many(&claims.sub, &database.pool).await?;
+
+    Ok(Json(channels))
+}
+
+#[instrument(name = "create_notification_channel_handler", skip_all, fields(user_id = %claims.sub), err)]
+pub async fn create_notification_channel_handler(
+    claims: Claims,
+    State(database): State<Database>,
+    State(redis): State<Redis>,
+    Json(req): Json<CreateNotificationChannelRequest>,
+) -> Result<impl IntoApiResponse, AppError> {
+    req.validate()?;
+
+    let channels = NotificationChannelsRepository::get_many(&claims.sub, &database.pool).await?;
+    if channels.len() >= MAX_NOTIFICATION_CHANNELS {
+        return Err(AppError::BadRequest(format!(
+            "At most {} notification channels are allowed",
+            MAX_NOTIFICATION_CHANNELS
+        )));
+    }
+
+    let channel = NotificationChannelsRepository::create(&claims.sub, &req, &database.pool).await?;
+    invalidate_notification_channels(&claims.sub, &redis).await?;
+
+    Ok((StatusCode::CREATED, Json(channel)))
+}
+
+#[instrument(name = "update_notification_channel_handler", skip_all, fields(user_id = %claims.sub, channel_id = %channel_id), err)]
+pub async fn update_notification_channel_handler(
+    claims: Claims,
+    Path(channel_id): Path<Uuid>,
+    State(database): State<Database>,
+    State(redis): State<Redis>,
+    Json(req): Json<UpdateNotificationChannelRequest>,
+) -> Result<impl IntoApiResponse, AppError> {
+    req.validate()?;
+
+    let channel = NotificationChannelsRepository::get_one(&claims.sub, &channel_id, &database.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFoundError("Notification channel not found".to_string()))?;
+    if let Some(webhook_url) = &req.webhook_url
+        && !channel.channel_type.accepts_webhook_url(webhook_url)
+    {
+        return Err(AppError::ValidationError(format!(
+            "Not an incoming webhook URL of {:?}",
+            channel.channel_type
+        )));
+    }
+
+    let channel =
+        NotificationChannelsRepository::update(&claims.sub, &channel_id, &req, &database.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFoundError("Notification channel not found".to_string()))?;
+    invalidate_notification_channels(&claims.sub, &redis).await?;
+
+    Ok(Json(channel))
+}
+
+#[instrument(name = "delete_notification_channel_handler", skip_all, fields(user_id = %claims.sub, channel_id = %channel_id), err)]
+pub async fn delete_notification_channel_handler(
+    claims: Claims,
+    Path(channel_id): Path<Uuid>,
+    State(database): State<Database>,
+    State(redis): State<Redis>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let deleted =
+        NotificationChannelsRepository::delete(&claims.sub, &channel_id, &database.pool).await?;
+    if deleted == 0 {
+        return Err(AppError::NotFoundError(
+            "Notification channel not found".to_string(),
+        ));
+    }
+    invalidate_notification_channels(&claims.sub, &redis).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// compute-reconciler caches the channels it delivers to, changes have to reach it right away
+async fn invalidate_notification_channels(user_id: &Uuid, redis: &Redis) -> Result<(), AppError> {
+    let mut con = redis.con.clone();
+    con.del::<_, ()>(CacheKeys::user_notification_channels(&user_id.to_string()))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use aide::axum::IntoApiResponse;
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use factory::factories::database::Database;
+use http_contracts::message::MessageResponse;
+use tracing::instrument;
+use users_core::jwt::Claims;
+use uuid::Uuid;
+
+use crate::{error::AppError, features::repositories::sessions::SessionsRepository};
+
+#[instrument(name = "get_sessions_handler", skip_all, fields(user_id = %claims.sub), err)]
+pub async fn get_sessions_handler(
+    claims: Claims,
+    State(database): State<Database>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let sessions = SessionsRepository::get_active(&claims.sub, claims.sid, &database.pool).await?;
+
+    Ok(Json(sessions))
+}
+
+#[instrument(name = "delete_session_handler", skip_all, fields(user_id = %claims.sub, session_id = %session_id), err)]
+pub async fn delete_session_handler(
+    claims: Claims,
+    Path(session_id): Path<Uuid>,
+    State(database): State<Database>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let revoked =
+        SessionsRepository::revoke_user_family(&claims.sub, &session_id, &database.pool).await?;
+
+    match revoked {
+        0 => Err(AppError::NotFoundError("Session not found".to_string())),
+        _ => Ok(StatusCode::NO_CONTENT),
+    }
+}
+
+/// Tokens issued before sessions were tracked carry no session, such a caller is logged out too
+#[instrument(name = "delete_other_sessions_handler", skip_all, fields(user_id = %claims.sub), err)]
+pub async fn delete_other_sessions_handler(
+    claims: Claims,
+    State(database): State<Database>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let revoked =
+        SessionsRepository::revoke_all_except(&claims.sub, claims.sid, &database.pool).await?;
+
+    Ok(Json(MessageResponse::new(format!(
+        "Revoked {} sessions",
+        revoked
+    ))))
+}
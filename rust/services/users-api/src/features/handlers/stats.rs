@@ -1,23 +1,57 @@
 use aide::axum::IntoApiResponse;
 use axum::{Json, extract::State};
-use factory::factories::database::Database;
+use factory::factories::{database::Database, redis::Redis};
+use futures::TryStreamExt;
+use object_store::{ObjectStore, aws::AmazonS3, path::Path as ObjectStorePath};
+use redis::AsyncCommands;
 use tracing::instrument;
+use users_core::jwt::Claims;
+use uuid::Uuid;
 
-use crate::{error::AppError, features::schemas::StatsResponse};
+use crate::{
+    error::AppError,
+    features::{repositories::users::UsersRepository, schemas::UserStats},
+};
 
-#[instrument(name = "get_stats_handler", skip_all)]
+const STATS_CACHE_TTL_SECS: u64 = 60;
+
+#[instrument(name = "get_stats_handler", skip_all, fields(user_id = %claims.sub), err)]
 pub async fn get_stats_handler(
+    claims: Claims,
     State(database): State<Database>,
+    State(redis): State<Redis>,
+    State(s3): State<AmazonS3>,
 ) -> Result<impl IntoApiResponse, AppError> {
-    let users_total = sqlx::query_scalar!("SELECT COUNT(*) from users")
-        .fetch_one(&database.pool)
-        .await?;
-    let deployments_total = sqlx::query!("SELECT COUNT(*) as count from deployments")
-        .fetch_one(&database.pool)
+    let mut con = redis.con.clone();
+    let cache_key = user_stats_key(&claims.sub);
+    let cached: Option<String> = con.get(&cache_key).await?;
+    if let Some(stats) = cached.and_then(|s| serde_json::from_str::<UserStats>(&s).ok()) {
+        return Ok(Json(stats));
+    }
+
+    let mut stats = UsersRepository::get_stats(&claims.sub, &database.pool).await?;
+
+    // Uploads are stored under `{user_id}/`, see update_user_handler
+    let prefix = ObjectStorePath::from(claims.sub.to_string());
+    stats.storage_used_bytes = s3
+        .list(Some(&prefix))
+        .try_fold(
+            0i64,
+            |total, meta| async move { Ok(total + meta.size as i64) },
+        )
         .await?;
 
-    Ok(Json(StatsResponse {
-        users_total: users_total.unwrap_or(0),
-        deployments_total: deployments_total.count.unwrap_or(0),
-    }))
+    con.set_ex::<_, _, ()>(
+        &cache_key,
+        serde_json::to_string(&stats)?,
+        STATS_CACHE_TTL_SECS,
+    )
+    .await?;
+
+    Ok(Json(stats))
+}
+
+/// `users:{user_id}:stats`
+fn user_stats_key(user_id: &Uuid) -> String {
+    format!("users:{user_id}:stats")
 }
@@ -0,0 +1,170 @@
+use std::net::SocketAddr;
+
+use aide::axum::IntoApiResponse;
+use axum::{
+    Json,
+    extract::{ConnectInfo, State},
+};
+use axum_extra::{TypedHeader, extract::PrivateCookieJar, headers::UserAgent};
+use factory::factories::{database::Database, redis::Redis};
+use http_contracts::message::MessageResponse;
+use redis::AsyncCommands;
+use tracing::instrument;
+use users_core::jwt::{Claims, JwtCapability, TokenType, verify_token};
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::AppError,
+    features::{
+        helpers::finalize_session,
+        repositories::{totp::TotpRepository, users::UsersRepository},
+        schemas::{TotpCodeRequest, TotpSetupResponse, TwoFactorLoginRequest},
+    },
+    services::totp,
+};
+
+/// Wrong codes tolerated per temp token before it has to be requested again
+const MAX_LOGIN_ATTEMPTS: i64 = 5;
+
+// -- =====================
+// -- SETUP
+// -- =====================
+#[instrument(name = "two_factor_setup_handler", skip_all, fields(user_id = %claims.sub), err)]
+pub async fn two_factor_setup_handler(
+    claims: Claims,
+    State(database): State<Database>,
+    State(config): State<Config>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let user = UsersRepository::get(&claims.sub, &database.pool).await?;
+
+    let secret = totp::generate_secret();
+    let sealed = totp::encrypt_secret(&config.totp, &secret)?;
+    let backup_codes = totp::generate_backup_codes();
+    let code_hashes: Vec<String> = backup_codes
+        .iter()
+        .map(|c| totp::hash_backup_code(c))
+        .collect();
+
+    let mut tx = database.pool.begin().await?;
+    if !TotpRepository::upsert_pending(&user.id, &sealed, &mut tx).await? {
+        return Err(AppError::BadRequest(
+            "Two-factor authentication is already enabled".to_string(),
+        ));
+    }
+    TotpRepository::replace_backup_codes(&user.id, &code_hashes, &mut tx).await?;
+    tx.commit().await?;
+
+    Ok(Json(TotpSetupResponse {
+        provisioning_uri: totp::provisioning_uri(&config.totp, &user.email, &secret),
+        secret: totp::encode_secret(&secret),
+        backup_codes,
+    }))
+}
+
+// -- =====================
+// -- VERIFY
+// -- =====================
+#[instrument(name = "two_factor_verify_handler", skip_all, fields(user_id = %claims.sub), err)]
+pub async fn two_factor_verify_handler(
+    claims: Claims,
+    State(database): State<Database>,
+    State(config): State<Config>,
+    Json(req): Json<TotpCodeRequest>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let stored = TotpRepository::get(&claims.sub, &database.pool)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Set up two-factor authentication first".into()))?;
+    if stored.is_active {
+        return Err(AppError::BadRequest(
+            "Two-factor authentication is already enabled".to_string(),
+        ));
+    }
+
+    let secret = totp::decrypt_secret(&config.totp, &stored.secret_ciphertext)?;
+    let step = totp::verify_code(&secret, req.code.trim(), chrono::Utc::now().timestamp())
+        .ok_or_else(|| AppError::ValidationError("Invalid code".to_string()))?;
+
+    if !TotpRepository::use_step(&claims.sub, step, true, &database.pool).await? {
+        return Err(AppError::ValidationError("Code already used".to_string()));
+    }
+
+    Ok(Json(MessageResponse::new(
+        "Two-factor authentication enabled",
+    )))
+}
+
+// -- =====================
+// -- LOGIN
+// -- =====================
+#[instrument(name = "two_factor_login_handler", skip_all, fields(user_id), err)]
+pub async fn two_factor_login_handler(
+    jar: PrivateCookieJar,
+    State(database): State<Database>,
+    State(redis): State<Redis>,
+    State(config): State<Config>,
+    TypedHeader(user_agent): TypedHeader<UserAgent>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Json(req): Json<TwoFactorLoginRequest>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let claims = verify_token(&config, &req.temp_token)?;
+    if claims.typ != TokenType::TwoFactor {
+        return Err(AppError::InvalidTokenError);
+    }
+    tracing::Span::current().record("user_id", claims.sub.to_string());
+
+    let mut con = redis.con.clone();
+    let ttl = config.two_factor_token_expire_in_minutes() * 60;
+    let attempts_key = two_factor_attempts_key(&claims.jti);
+    let attempts: i64 = con.incr(&attempts_key, 1).await?;
+    if attempts == 1 {
+        con.expire::<_, ()>(&attempts_key, ttl).await?;
+    }
+    if attempts > MAX_LOGIN_ATTEMPTS {
+        return Err(AppError::Unauthorized(
+            "Too many attempts, log in again".into(),
+        ));
+    }
+
+    let stored = TotpRepository::get(&claims.sub, &database.pool)
+        .await?
+        .filter(|s| s.is_active)
+        .ok_or(AppError::InvalidTokenError)?;
+
+    let code = req.code.trim();
+    let accepted = if code.bytes().all(|b| b.is_ascii_digit()) {
+        let secret = totp::decrypt_secret(&config.totp, &stored.secret_ciphertext)?;
+        match totp::verify_code(&secret, code, chrono::Utc::now().timestamp()) {
+            Some(step) => {
+                TotpRepository::use_step(&claims.sub, step, false, &database.pool).await?
+            }
+            None => false,
+        }
+    } else {
+        let code_hash = totp::hash_backup_code(code);
+        TotpRepository::use_backup_code(&claims.sub, &code_hash, &database.pool).await?
+    };
+    if !accepted {
+        return Err(AppError::ValidationError("Invalid code".to_string()));
+    }
+
+    // The temp token is single use once it produced a session
+    con.set_ex::<_, _, ()>(&attempts_key, MAX_LOGIN_ATTEMPTS + 1, ttl as u64)
+        .await?;
+
+    let user = UsersRepository::get(&claims.sub, &database.pool).await?;
+    finalize_session(
+        user,
+        user_agent.as_str(),
+        &addr.ip().to_string(),
+        &config,
+        jar,
+        &database.pool,
+    )
+    .await
+}
+
+/// `two_factor:{jti}:attempts`
+fn two_factor_attempts_key(jti: &Uuid) -> String {
+    format!("two_factor:{jti}:attempts")
+}
@@ -3,19 +3,38 @@ use crate::{
     error::AppError,
     features::{
         helpers::finalize_session,
-        repositories::{oauth_users::OAuthUsersRepository, users::UsersRepository},
+        repositories::{
+            oauth_users::OAuthUsersRepository, sessions::SessionsRepository, totp::TotpRepository,
+            users::UsersRepository,
+        },
         schemas::{
-            EmailAuthRequest, RedirectResponse, TokenQuery, Tokens, UserIn, UserMutationPayload,
+            ChangeEmailRequest, DeleteAccountRequest, EmailAuthRequest,
+            PasswordResetConfirmRequest, PasswordResetRequest, RedirectResponse, TokenQuery,
+            Tokens, TwoFactorChallenge, UserIn, UserMutationPayload,
         },
     },
 };
 use aide::axum::IntoApiResponse;
-use bcrypt::{hash, verify};
-use factory::factories::{database::Database, mailtrap::Mailtrap};
+use bcrypt::{DEFAULT_COST, hash, verify};
+use compute_core::{
+    cache_keys::CacheKeys,
+    schemas::{DeleteDeploymentMessage, DeleteUserMessage},
+};
+use factory::factories::{
+    amqp::Amqp,
+    database::Database,
+    email::{Email, EmailProvider, EmailTemplate},
+    redis::Redis,
+};
 use http_contracts::message::MessageResponse;
+use redis::{AsyncCommands, aio::MultiplexedConnection};
 use serde_json::json;
-use std::net::SocketAddr;
-use users_core::jwt::{Claims, TokenType, create_token, verify_token};
+use std::{net::SocketAddr, time::Duration};
+use users_core::jwt::{
+    Claims, JwtCapability, TokenType, create_email_change_token, create_session_token,
+    create_token, verify_email_change_token, verify_token,
+};
+use validator::Validate;
 
 use axum::{
     Json,
@@ -28,12 +47,16 @@ use axum_extra::{
     extract::{PrivateCookieJar, cookie::Cookie},
     headers::{Authorization, UserAgent, authorization::Bearer},
 };
-use chrono::Utc;
 use cookie::{SameSite, time::Duration as CookieDuration};
 use object_store::{ObjectStore, aws::AmazonS3, path::Path as ObjectStorePath};
-use tracing::{debug, error, info_span, instrument, warn};
+use tracing::{Instrument, debug, error, info_span, instrument, warn};
 use uuid::Uuid;
 
+/// Outlives the waits of a few retries, a deletion that never finishes stops being waited on
+const ACCOUNT_DELETION_TTL_SECONDS: i64 = 10 * 60;
+const ACCOUNT_DELETION_WAIT: Duration = Duration::from_secs(30);
+const ACCOUNT_DELETION_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 // -- =====================
 // -- EMAILT AUTH
 // -- =====================
@@ -42,6 +65,7 @@ pub async fn email_auth_handler(
     jar: PrivateCookieJar,
     State(database): State<Database>,
     State(config): State<Config>,
+    State(email): State<Email>,
     TypedHeader(user_agent): TypedHeader<UserAgent>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Json(req): Json<EmailAuthRequest>,
@@ -67,6 +91,16 @@ pub async fn email_auth_handler(
                 return Err(AppError::ValidationError("Incorrect password".to_string()));
             }
 
+            // The password alone is not enough, tokens are only issued by the 2FA step
+            if TotpRepository::is_active(&user.id, &database.pool).await? {
+                let temp_token = create_token(&config, user.id, TokenType::TwoFactor)?;
+                let challenge = Json(TwoFactorChallenge {
+                    requires_2fa: true,
+                    temp_token,
+                });
+                return Ok(challenge.into_response());
+            }
+
             let res = finalize_session(
                 user,
                 user_agent.as_str(),
@@ -96,9 +130,12 @@ pub async fn email_auth_handler(
             config.frontend_endpoint, token
         );
 
-        let mailtrap = Mailtrap::new();
-        if let Err(e) = mailtrap
-            .send_password_setup_link(&user.username, &user.email, &setup_link, &config.mailtrap)
+        if let Err(e) = email
+            .send_templated_email(
+                &user.email,
+                EmailTemplate::PasswordSetup,
+                serde_json::json!({ "name": user.username, "link": setup_link }),
+            )
             .await
         {
             error!("Failed to send password setup email: {}", e);
@@ -142,14 +179,11 @@ pub async fn email_auth_handler(
     let token = create_token(&config, user.id, TokenType::EmailVerification)?;
     let verification_link = format!("{}/auth/verify?token={}", config.frontend_endpoint, token);
 
-    let mailtrap = Mailtrap::new();
-
-    match mailtrap
-        .send_email_verification_link(
-            &user.username,
+    match email
+        .send_templated_email(
             &user.email,
-            &verification_link,
-            &config.mailtrap,
+            EmailTemplate::Verification,
+            serde_json::json!({ "name": user.username, "link": verification_link }),
         )
         .await
     {
@@ -275,42 +309,419 @@ pub async fn update_user_handler(
     Ok(())
 }
 
+// -- =====================
+// -- CHANGE EMAIL
+// -- =====================
+#[instrument(name = "change_email_handler", skip_all, fields(user_id = %claims.sub), err)]
+pub async fn change_email_handler(
+    claims: Claims,
+    State(database): State<Database>,
+    State(redis): State<Redis>,
+    State(config): State<Config>,
+    State(email): State<Email>,
+    Json(req): Json<ChangeEmailRequest>,
+) -> Result<impl IntoApiResponse, AppError> {
+    req.validate()?;
+
+    let user = UsersRepository::get(&claims.sub, &database.pool).await?;
+    let Some(password_hash) = user.password.clone() else {
+        return Err(AppError::BadRequest(
+            "Set up a password before changing the email".to_string(),
+        ));
+    };
+
+    let password_input = req.password.clone();
+    let same = tokio::task::spawn_blocking(move || {
+        let _span = info_span!("password_verifying").entered();
+        verify(&password_input, &password_hash)
+    })
+    .await
+    .map_err(|e| AppError::InternalServerError(e.to_string()))??;
+    if !same {
+        return Err(AppError::ValidationError("Incorrect password".to_string()));
+    }
+
+    let new_email = req.new_email.trim().to_lowercase();
+    if new_email == user.email.to_lowercase() {
+        return Err(AppError::BadRequest(
+            "New email is the same as the current one".to_string(),
+        ));
+    }
+    if UsersRepository::find_by_email(&new_email, &database.pool)
+        .await?
+        .is_some()
+    {
+        return Err(AppError::BadRequest("Email is already in use".to_string()));
+    }
+
+    // Held until the link expires so a request can't be spammed into other inboxes
+    let mut con = redis.con.clone();
+    let pending_key = email_change_pending_key(&user.id);
+    let ttl = (config.email_change_token_expire_in_minutes() * 60) as u64;
+    let locked: bool = redis::cmd("SET")
+        .arg(&pending_key)
+        .arg(&new_email)
+        .arg("NX")
+        .arg("EX")
+        .arg(ttl)
+        .query_async::<Option<String>>(&mut con)
+        .await?
+        .is_some();
+    if !locked {
+        return Err(AppError::BadRequest(
+            "An email change is already pending, check your inbox".to_string(),
+        ));
+    }
+
+    let token = create_email_change_token(&config, user.id, &new_email)?;
+    let link = format!(
+        "{}/auth/email/change/verify?token={}",
+        config.frontend_endpoint, token
+    );
+
+    if let Err(e) = email
+        .send_templated_email(
+            &new_email,
+            EmailTemplate::EmailChange,
+            serde_json::json!({ "name": user.username, "link": link }),
+        )
+        .await
+    {
+        con.del::<_, ()>(&pending_key).await?;
+        return Err(e.into());
+    }
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(MessageResponse::new(
+            "Verification link sent to the new email address",
+        )),
+    ))
+}
+
+#[instrument(name = "verify_email_change_handler", skip_all, fields(user_id), err)]
+pub async fn verify_email_change_handler(
+    jar: PrivateCookieJar,
+    State(database): State<Database>,
+    State(redis): State<Redis>,
+    State(config): State<Config>,
+    Query(token_query): Query<TokenQuery>,
+) -> Result<impl IntoApiResponse, AppError> {
+    let claims = verify_email_change_token(&config, &token_query.token)?;
+    tracing::Span::current().record("user_id", claims.sub.to_string());
+
+    // Someone may have signed up with the address while the link was waiting in the inbox
+    if let Some(owner) = UsersRepository::find_by_email(&claims.new_email, &database.pool).await?
+        && owner.id != claims.sub
+    {
+        return Err(AppError::BadRequest("Email is already in use".to_string()));
+    }
+
+    let query_result =
+        UsersRepository::update_email(&claims.sub, &claims.new_email, &database.pool).await?;
+    if query_result.rows_affected() == 0 {
+        return Err(AppError::NotFoundError("User not found".to_string()));
+    }
+
+    let mut con = redis.con.clone();
+    con.del::<_, ()>(email_change_pending_key(&claims.sub))
+        .await?;
+
+    let to = if jar.get("refresh_token").is_none() {
+        "/auth".to_string()
+    } else {
+        "/console/dashboard".to_string()
+    };
+
+    Ok((jar, Json(RedirectResponse { to })))
+}
+
+/// `user:{id}:email_change_pending`
+fn email_change_pending_key(user_id: &Uuid) -> String {
+    format!("user:{user_id}:email_change_pending")
+}
+
+// -- =====================
+// -- PASSWORD RESET
+// -- =====================
+#[instrument(name = "password_reset_handler", skip_all, fields(user_id), err)]
+pub async fn password_reset_handler(
+    State(database): State<Database>,
+    State(config): State<Config>,
+    State(email): State<Email>,
+    Json(req): Json<PasswordResetRequest>,
+) -> Result<impl IntoApiResponse, AppError> {
+    req.validate()?;
+
+    // Looked up and mailed in the background, so neither the body nor the response time tells
+    // whether the address is registered
+    tokio::spawn(
+        async move {
+            if let Err(e) = send_password_reset(&req.email, &database, &config, &email).await {
+                error!("❌ Failed to send password reset link: {}", e);
+            }
+        }
+        .in_current_span(),
+    );
+
+    Ok(Json(MessageResponse::new(
+        "If an account exists for this email, a reset link has been sent",
+    )))
+}
+
+async fn send_password_reset(
+    address: &str,
+    database: &Database,
+    config: &Config,
+    email: &Email,
+) -> Result<(), AppError> {
+    let Some(user) = UsersRepository::find_by_email(address, &database.pool).await? else {
+        return Ok(());
+    };
+    tracing::Span::current().record("user_id", user.id.to_string());
+
+    let token = create_token(config, user.id, TokenType::PasswordReset)?;
+    let reset_link = format!(
+        "{}/auth/reset-password?token={}",
+        config.frontend_endpoint, token
+    );
+
+    email
+        .send_templated_email(
+            &user.email,
+            EmailTemplate::PasswordReset,
+            serde_json::json!({ "name": user.username, "link": reset_link }),
+        )
+        .await
+        .map_err(|e| AppError::InternalServerError(e.to_string()))
+}
+
+#[instrument(
+    name = "password_reset_confirm_handler",
+    skip_all,
+    fields(user_id),
+    err
+)]
+pub async fn password_reset_confirm_handler(
+    State(database): State<Database>,
+    State(redis): State<Redis>,
+    State(config): State<Config>,
+    Json(req): Json<PasswordResetConfirmRequest>,
+) -> Result<impl IntoApiResponse, AppError> {
+    req.validate()?;
+
+    let claims = verify_token(&config, &req.token)?;
+    if claims.typ != TokenType::PasswordReset {
+        return Err(AppError::InvalidTokenError);
+    }
+    tracing::Span::current().record("user_id", claims.sub.to_string());
+
+    // Kept until the token expires on its own, after that the signature check rejects it
+    let mut con = redis.con.clone();
+    let used_key = format!("password_reset:{}:used", claims.jti);
+    let ttl = (claims.exp - chrono::Utc::now().timestamp()).max(1) as u64;
+    let first_use = redis::cmd("SET")
+        .arg(&used_key)
+        .arg(1)
+        .arg("NX")
+        .arg("EX")
+        .arg(ttl)
+        .query_async::<Option<String>>(&mut con)
+        .await?
+        .is_some();
+    if !first_use {
+        return Err(AppError::InvalidTokenError);
+    }
+
+    let new_password = req.new_password;
+    let updated = async {
+        let hash_password = tokio::task::spawn_blocking(move || {
+            let _span = info_span!("password_hashing").entered();
+            hash(new_password, DEFAULT_COST)
+        })
+        .await
+        .map_err(|e| AppError::InternalServerError(e.to_string()))??;
+
+        let query_result =
+            UsersRepository::update_password(&claims.sub, &hash_password, &database.pool).await?;
+        if query_result.rows_affected() == 0 {
+            return Err(AppError::NotFoundError("User not found".to_string()));
+        }
+        Ok(())
+    }
+    .await;
+
+    // The password didn't change, the emailed link has to keep working for another try
+    if let Err(e) = updated {
+        if let Err(e) = con.del::<_, ()>(&used_key).await {
+            warn!(error = %e, "⚠️ Failed to release the password reset token");
+        }
+        return Err(e);
+    }
+
+    // Whoever knew the old password is logged out everywhere
+    SessionsRepository::revoke_all_except(&claims.sub, None, &database.pool).await?;
+
+    Ok(Json(MessageResponse::new(
+        "Password reset successfully. You can now log in with your new password.",
+    )))
+}
+
 // -- =====================
 // -- DELETE USER
 // -- =====================
-#[instrument(name = "delete_user_handler", skip(claims, database), err)]
+/// Every stage can run again, a deployment's row is deleted in the same transaction its
+/// `compute.delete` is published in, so a retry only publishes for deployments still left
+#[instrument(name = "delete_user_handler", skip_all, fields(user_id = %claims.sub), err)]
 pub async fn delete_user_handler(
     claims: Claims,
+    jar: PrivateCookieJar,
     State(database): State<Database>,
+    State(redis): State<Redis>,
+    State(amqp): State<Amqp>,
+    State(email): State<Email>,
+    Json(req): Json<DeleteAccountRequest>,
 ) -> Result<impl IntoApiResponse, AppError> {
-    debug!("claims: {:#?}", claims);
+    req.validate()?;
+
+    let user = UsersRepository::get(&claims.sub, &database.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => AppError::NotFoundError("User not found".to_string()),
+            e => e.into(),
+        })?;
+    let Some(password_hash) = user.password.clone() else {
+        return Err(AppError::BadRequest(
+            "Set up a password before deleting the account".to_string(),
+        ));
+    };
+
+    let password_input = req.password.clone();
+    let same = tokio::task::spawn_blocking(move || {
+        let _span = info_span!("password_verifying").entered();
+        verify(&password_input, &password_hash)
+    })
+    .await
+    .map_err(|e| AppError::InternalServerError(e.to_string()))??;
+    if !same {
+        return Err(AppError::ValidationError("Incorrect password".to_string()));
+    }
 
-    let query_result = sqlx::query!("DELETE FROM users WHERE id = $1", claims.sub)
-        .execute(&database.pool)
+    let mut con = redis.con.clone();
+    let pending_key = CacheKeys::user_deletion_pending(&user.id.to_string());
+
+    let deployments = UsersRepository::get_deployments(&user.id, &database.pool).await?;
+    for (deployment_id, project_id, region) in deployments {
+        let mut tx = database.pool.begin().await?;
+        UsersRepository::delete_deployment(&user.id, &deployment_id, &mut tx).await?;
+
+        // Counted before publishing, the provisioner may be done before the next one goes out
+        redis::pipe()
+            .incr(&pending_key, 1)
+            .expire(&pending_key, ACCOUNT_DELETION_TTL_SECONDS)
+            .query_async::<()>(&mut con)
+            .await?;
+
+        let message = DeleteDeploymentMessage {
+            user_id: user.id,
+            project_id,
+            deployment_id,
+            region,
+            timestamp: chrono::Utc::now().timestamp(),
+        };
+        amqp.basic_publish("compute", "compute.delete", &message)
+            .await?;
+
+        tx.commit().await?;
+    }
+    if !wait_for_user_deletion(&mut con, &pending_key).await? {
+        return Ok(account_deletion_in_progress());
+    }
+
+    // Deleting the namespace and purging Vault are idempotent, publishing again is harmless
+    redis::pipe()
+        .incr(&pending_key, 1)
+        .expire(&pending_key, ACCOUNT_DELETION_TTL_SECONDS)
+        .query_async::<()>(&mut con)
         .await?;
+    let message = DeleteUserMessage {
+        user_id: user.id,
+        timestamp: chrono::Utc::now().timestamp(),
+    };
+    amqp.basic_publish("compute", "compute.delete_user", &message)
+        .await?;
+    if !wait_for_user_deletion(&mut con, &pending_key).await? {
+        return Ok(account_deletion_in_progress());
+    }
+
+    // Sessions cascade with the user anyway, revoking them first stops a refresh racing the
+    // deletion from minting another access token
+    SessionsRepository::revoke_all_except(&user.id, None, &database.pool).await?;
+    let mut tx = database.pool.begin().await?;
+    if !UsersRepository::delete(&user.id, &mut tx).await? {
+        return Err(AppError::NotFoundError("User not found".to_string()));
+    }
+    tx.commit().await?;
+    con.del::<_, ()>(&pending_key).await?;
+
+    if let Err(e) = email
+        .send_templated_email(
+            &user.email,
+            EmailTemplate::AccountDeleted,
+            serde_json::json!({ "name": user.username }),
+        )
+        .await
+    {
+        // The account is gone either way, a retry would find nothing to delete
+        error!(user_id = %user.id, error = %e, "❌ Failed to send account deletion email");
+    }
+
+    let jar = jar
+        .remove(Cookie::build("access_token").path("/"))
+        .remove(Cookie::build("refresh_token").path("/"));
 
-    match query_result.rows_affected() {
-        0 => Err(AppError::NotFoundError("User not found".to_string())),
-        _ => Ok(StatusCode::NO_CONTENT),
+    Ok((jar, StatusCode::NO_CONTENT).into_response())
+}
+
+/// Polls the counter the provisioner counts down, false once the wait runs out. A missing key
+/// means the deletions were done, or the counter expired waiting for ones that never will be
+async fn wait_for_user_deletion(
+    con: &mut MultiplexedConnection,
+    pending_key: &str,
+) -> Result<bool, AppError> {
+    let deadline = tokio::time::Instant::now() + ACCOUNT_DELETION_WAIT;
+    loop {
+        let pending: Option<i64> = con.get(pending_key).await?;
+        if pending.is_none_or(|p| p <= 0) {
+            return Ok(true);
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Ok(false);
+        }
+        tokio::time::sleep(ACCOUNT_DELETION_POLL_INTERVAL).await;
     }
 }
 
+fn account_deletion_in_progress() -> axum::response::Response {
+    (
+        StatusCode::ACCEPTED,
+        Json(MessageResponse::new(
+            "Account deletion is in progress, send the request again to finish it",
+        )),
+    )
+        .into_response()
+}
+
 // -- =====================
 // -- REFRESH TOKEN
 // -- =====================
-#[instrument(name = "refresh_handler", skip(config, jar, auth_header), err)]
+#[instrument(name = "refresh_handler", skip_all, fields(user_id), err)]
 pub async fn refresh_handler(
+    State(database): State<Database>,
     State(config): State<Config>,
     jar: PrivateCookieJar,
     auth_header: Option<TypedHeader<Authorization<Bearer>>>,
 ) -> Result<impl IntoApiResponse, AppError> {
-    // TODO we need to refine the sesion logic
-    // Currently we are not effectively using sessions. access token is stateless and refresh is statefull
-    // - Look up the refresh token in the sessions table
-    // - Check if `is_active == false` -> If false, someone might be tampering with tokens. You should revoke all sessions for that user to be safe.
-    // - If valid, generate a new Access Token and a new Refresh Token. Update the DB row with the new refresh token, and send the new cookies back.
-    // - When a user logs out `UPDATE sessions SET is_active = FALSE WHERE refresh_token = $1`
-
     let token = if let Some(cookie) = jar.get("refresh_token") {
         cookie.value().to_string()
     } else if let Some(TypedHeader(Authorization(bearer))) = auth_header {
@@ -323,30 +734,60 @@ pub async fn refresh_handler(
     if claims.typ != TokenType::Refresh {
         return Err(AppError::Unauthorized("Refresh token required".into()));
     }
+    tracing::Span::current().record("user_id", claims.sub.to_string());
+
+    let session = SessionsRepository::get_by_refresh_token(&token, &database.pool)
+        .await?
+        .ok_or_else(|| AppError::Unauthorized("Unknown session".into()))?;
+
+    // Refresh tokens are single use, seeing one twice means it was stolen
+    if session.revoked_at.is_some() || !session.is_active {
+        let revoked = SessionsRepository::revoke_family(&session.family_id, &database.pool).await?;
+        warn!(
+            family_id = %session.family_id,
+            "🚨 Refresh token reuse detected, revoked {} sessions",
+            revoked
+        );
+        return Err(AppError::Unauthorized("Session revoked".into()));
+    }
 
-    let now = Utc::now().timestamp();
-    let threshold_secs = config.jwt.refresh_token_renewal_threshold_days * 24 * 60 * 60;
-    let refresh_token = if claims.exp.saturating_sub(now) < threshold_secs {
-        Some(create_token(&config, claims.sub, TokenType::Refresh)?)
-    } else {
-        None
-    };
+    let refresh_token = create_token(&config, claims.sub, TokenType::Refresh)?;
 
-    let jar = if let Some(ref refresh) = refresh_token {
-        let refresh_cookie = Cookie::build(("refresh_token", refresh.clone()))
-            .http_only(true)
-            .path("/")
-            .same_site(SameSite::Lax)
-            .max_age(CookieDuration::days(
-                config.jwt.refresh_token_expire_in_days,
-            ))
-            .secure(config.cookie_secure);
-        jar.add(refresh_cookie)
-    } else {
-        jar
-    };
+    let mut tx = database.pool.begin().await?;
+    if !SessionsRepository::revoke(&session.id, &mut *tx).await? {
+        // Lost the race against another refresh with the same token
+        tx.rollback().await?;
+        SessionsRepository::revoke_family(&session.family_id, &database.pool).await?;
+        return Err(AppError::Unauthorized("Session revoked".into()));
+    }
+    SessionsRepository::create(
+        &session.user_id,
+        session.user_agent.as_deref().unwrap_or_default(),
+        session.ip_address.as_deref().unwrap_or_default(),
+        &refresh_token,
+        &session.family_id,
+        &mut *tx,
+    )
+    .await?;
+    tx.commit().await?;
+
+    let refresh_cookie = Cookie::build(("refresh_token", refresh_token.clone()))
+        .http_only(true)
+        .path("/")
+        .same_site(SameSite::Lax)
+        .max_age(CookieDuration::days(
+            config.jwt.refresh_token_expire_in_days,
+        ))
+        .secure(config.cookie_secure);
 
-    let access_token = create_token(&config, claims.sub, TokenType::Access)?;
+    // Read again so role changes apply from the next refresh on
+    let user = UsersRepository::get(&claims.sub, &database.pool).await?;
+    let access_token = create_session_token(
+        &config,
+        claims.sub,
+        session.family_id,
+        &user.role.to_string(),
+    )?;
     let access_cookie = Cookie::build(("access_token", access_token.clone()))
         .http_only(true)
         .path("/")
@@ -355,11 +796,11 @@ pub async fn refresh_handler(
             config.jwt.access_token_expire_in_minute,
         ))
         .secure(config.cookie_secure);
-    let jar = jar.add(access_cookie);
+    let jar = jar.add(refresh_cookie).add(access_cookie);
 
     let response = Json(Tokens {
         access_token,
-        refresh_token,
+        refresh_token: Some(refresh_token),
     });
 
     Ok((jar, response))
@@ -2,7 +2,8 @@ use axum::Json;
 use cookie::{SameSite, time::Duration};
 use sqlx::PgPool;
 use tracing::instrument;
-use users_core::jwt::{TokenType, create_token};
+use users_core::jwt::{TokenType, create_session_token, create_token};
+use uuid::Uuid;
 
 use axum_extra::extract::{PrivateCookieJar, cookie::Cookie};
 
@@ -25,7 +26,9 @@ pub async fn finalize_session(
     jar: PrivateCookieJar,
     pool: &PgPool,
 ) -> Result<(PrivateCookieJar, Json<AuthResponse>), AppError> {
-    let access_token = create_token(config, user.id, TokenType::Access)?;
+    // A login starts a new family, refreshes rotate within it
+    let family_id = Uuid::new_v4();
+    let access_token = create_session_token(config, user.id, family_id, &user.role.to_string())?;
     let refresh_token = create_token(config, user.id, TokenType::Refresh)?;
 
     let access_cookie = Cookie::build(("access_token", access_token.clone()))
@@ -44,10 +47,11 @@ pub async fn finalize_session(
 
     SessionsRepository::create(
         &user.id,
-        &user_agent.to_string(),
-        &ip_addr.to_string(),
+        user_agent,
+        ip_addr,
         &refresh_token,
-        &pool,
+        &family_id,
+        pool,
     )
     .await?;
 
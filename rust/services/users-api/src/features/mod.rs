@@ -1,3 +1,4 @@
+pub mod admin;
 pub mod handlers;
 pub mod helpers;
 pub mod implementations;
@@ -9,17 +10,47 @@ use crate::utilities::app_state::AppState;
 
 use aide::axum::{
     ApiRouter,
-    routing::{get, post},
+    routing::{delete, get, patch, post},
 };
 
 pub fn get_routes() -> ApiRouter<AppState> {
     ApiRouter::new()
+        .nest("/api/v1/admin", admin::get_routes())
         .api_route(
             "/api/v1/users/profile",
             get(handlers::users::get_user_handler)
                 .patch(handlers::users::update_user_handler)
                 .delete(handlers::users::delete_user_handler),
         )
+        .api_route(
+            "/api/v1/users/email",
+            patch(handlers::users::change_email_handler),
+        )
+        .api_route(
+            "/api/v1/users/auth/email/change/verify",
+            get(handlers::users::verify_email_change_handler),
+        )
+        .api_route(
+            "/api/v1/users/sessions",
+            get(handlers::sessions::get_sessions_handler)
+                .delete(handlers::sessions::delete_other_sessions_handler),
+        )
+        .api_route(
+            "/api/v1/users/sessions/{session_id}",
+            delete(handlers::sessions::delete_session_handler),
+        )
+        .api_route(
+            "/api/v1/users/2fa/setup",
+            post(handlers::two_factor::two_factor_setup_handler),
+        )
+        .api_route(
+            "/api/v1/users/2fa/verify",
+            post(handlers::two_factor::two_factor_verify_handler),
+        )
+        .api_route(
+            "/api/v1/users/auth/2fa",
+            post(handlers::two_factor::two_factor_login_handler),
+        )
         .api_route(
             "/api/v1/users/auth/refresh",
             post(handlers::users::refresh_handler),
@@ -44,6 +75,14 @@ pub fn get_routes() -> ApiRouter<AppState> {
             "/api/v1/users/auth/email",
             post(handlers::users::email_auth_handler),
         )
+        .api_route(
+            "/api/v1/users/auth/password/reset",
+            post(handlers::users::password_reset_handler),
+        )
+        .api_route(
+            "/api/v1/users/auth/password/reset/confirm",
+            post(handlers::users::password_reset_confirm_handler),
+        )
         .api_route(
             "/api/v1/users/auth/password-setup",
             get(handlers::oauth_users::password_setup_handler),
@@ -60,6 +99,20 @@ pub fn get_routes() -> ApiRouter<AppState> {
             "/api/v1/users/stats",
             get(handlers::stats::get_stats_handler),
         )
+        .api_route(
+            "/api/v1/users/activity",
+            get(handlers::activity::get_activity_handler),
+        )
+        .api_route(
+            "/api/v1/users/notifications",
+            get(handlers::notifications::get_notification_channels_handler)
+                .post(handlers::notifications::create_notification_channel_handler),
+        )
+        .api_route(
+            "/api/v1/users/notifications/{channel_id}",
+            patch(handlers::notifications::update_notification_channel_handler)
+                .delete(handlers::notifications::delete_notification_channel_handler),
+        )
         .api_route(
             "/api/v1/users/feedback",
             get(handlers::feedbacks::get_feedbacks_handler)
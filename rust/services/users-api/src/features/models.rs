@@ -27,6 +27,16 @@ pub enum UserStatus {
     PendingVerification,
 }
 
+#[derive(Type, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, JsonSchema, Debug)]
+#[sqlx(type_name = "feedback_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum FeedbackStatus {
+    #[default]
+    Pending,
+    Reviewed,
+    Actioned,
+}
+
 #[derive(Type, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, Debug)]
 #[sqlx(type_name = "provider", rename_all = "snake_case")]
 pub enum Provider {
@@ -34,6 +44,17 @@ pub enum Provider {
     Github,
 }
 
+/// Lowercase like the `user_role` enum, this is what access tokens carry
+impl Display for UserRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            UserRole::Admin => "admin",
+            UserRole::Regular => "regular",
+        };
+        write!(f, "{s}")
+    }
+}
+
 impl Display for Provider {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self {
@@ -56,9 +77,20 @@ pub struct Feedback {
     pub name: String,
     pub email: String,
     pub message: String,
+    pub status: FeedbackStatus,
     pub created_at: DateTime<Utc>,
 }
 
+/// Feedback as moderators see it, the user is matched by email since feedback can be anonymous
+#[derive(Serialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedbackWithUser {
+    #[serde(flatten)]
+    pub feedback: Feedback,
+    pub user_id: Option<Uuid>,
+    pub username: Option<String>,
+}
+
 #[derive(FromRow, Serialize, Deserialize, PartialEq, Eq, JsonSchema, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct OAuthUser {
@@ -97,7 +129,32 @@ pub struct Session {
     pub device_name: Option<String>,
     pub refresh_token: Option<String>,
     pub is_active: bool,
-    pub last_activity_at: DateTime<Utc>,
+    /// Shared by all rotations of one login
+    pub family_id: Uuid,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One logged in device, `id` is the session family so it stays the same across refreshes
+#[derive(FromRow, Serialize, Deserialize, Clone, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveSession {
+    pub id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub device_name: Option<String>,
+    pub last_used_at: DateTime<Utc>,
+    pub current: bool,
+}
+
+#[derive(FromRow, Debug)]
+pub struct UserTotpSecret {
+    pub user_id: Uuid,
+    /// AES-GCM sealed, see `services::totp`
+    pub secret_ciphertext: String,
+    pub is_active: bool,
+    pub last_used_step: Option<i64>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -0,0 +1,86 @@
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::features::schemas::ActivityItem;
+
+pub struct ActivityRepository;
+
+impl ActivityRepository {
+    // ----------------------------------------------------------------------------
+    // get_many
+    // ----------------------------------------------------------------------------
+    /// Events and builds of the user's deployments, ordered by `(created_at, id)` descending. A
+    /// build is placed at its start, so it doesn't move between pages when it finishes.
+    /// `after` walks that order backwards, its page comes back oldest first. A cursor that
+    /// doesn't name an item of the feed yields nothing
+    #[instrument("activity_repository.get_many", skip_all, fields(user_id = %user_id), err)]
+    pub async fn get_many(
+        user_id: &Uuid,
+        before: Option<Uuid>,
+        after: Option<Uuid>,
+        limit: i64,
+        pool: &PgPool,
+    ) -> Result<Vec<ActivityItem>, sqlx::Error> {
+        // The cursor is looked up by primary key so `activity` is only referenced once and
+        // stays inlined, the outer LIMIT then reaches into both branches
+        sqlx::query_as!(
+            ActivityItem,
+            r#"
+            WITH cursor AS (
+                SELECT created_at, id FROM deployment_events WHERE id = COALESCE($2::UUID, $3::UUID)
+                UNION ALL
+                SELECT started_at, id FROM builds WHERE id = COALESCE($2::UUID, $3::UUID)
+            ),
+            activity AS (
+                SELECT
+                    e.id,
+                    e.type::TEXT AS activity_type,
+                    'deployment' AS resource_type,
+                    d.id AS resource_id,
+                    d.name AS resource_name,
+                    COALESCE(e.message, REPLACE(e.type::TEXT, '_', ' ')) AS description,
+                    e.created_at
+                FROM deployment_events e
+                JOIN deployments d ON d.id = e.deployment_id
+                WHERE d.user_id = $1
+                UNION ALL
+                SELECT
+                    b.id,
+                    'build_' || b.status::TEXT,
+                    'deployment',
+                    d.id,
+                    d.name,
+                    'Build ' || b.status::TEXT || ' (' || b.build_type::TEXT || ')',
+                    b.started_at
+                FROM builds b
+                JOIN deployments d ON d.id = b.deployment_id
+                WHERE d.user_id = $1
+            )
+            SELECT
+                id AS "id!",
+                activity_type AS "activity_type!",
+                resource_type AS "resource_type!",
+                resource_id AS "resource_id!",
+                resource_name AS "resource_name!",
+                description AS "description!",
+                created_at AS "created_at!"
+            FROM activity
+            WHERE ($2::UUID IS NULL OR (created_at, id) < (SELECT created_at, id FROM cursor))
+            AND ($3::UUID IS NULL OR (created_at, id) > (SELECT created_at, id FROM cursor))
+            ORDER BY
+                CASE WHEN $3::UUID IS NULL THEN created_at END DESC,
+                CASE WHEN $3::UUID IS NULL THEN id END DESC,
+                created_at ASC,
+                id ASC
+            LIMIT $4
+            "#,
+            user_id,
+            before,
+            after,
+            limit
+        )
+        .fetch_all(pool)
+        .await
+    }
+}
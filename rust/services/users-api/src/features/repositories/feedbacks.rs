@@ -1,7 +1,12 @@
 use sqlx::{Executor, PgPool, Postgres, postgres::PgQueryResult};
 use tracing::instrument;
 
-use crate::features::{models::Feedback, schemas::CreateFeedbackRequest};
+use uuid::Uuid;
+
+use crate::features::{
+    models::{Feedback, FeedbackStatus, FeedbackWithUser},
+    schemas::CreateFeedbackRequest,
+};
 
 pub struct FeedbacksRepository;
 
@@ -42,7 +47,12 @@ impl FeedbacksRepository {
         let rows = sqlx::query!(
             r#"
             SELECT
-                *,
+                id,
+                name,
+                email,
+                message,
+                status AS "status: FeedbackStatus",
+                created_at,
                 COUNT(*) OVER() as "total!"
             FROM feedbacks
             ORDER BY created_at DESC
@@ -64,10 +74,107 @@ impl FeedbacksRepository {
                 name: r.name,
                 email: r.email,
                 message: r.message,
+                status: r.status,
                 created_at: r.created_at,
             })
             .collect();
 
         Ok((feedbacks, total))
     }
+
+    // ----------------------------------------------------------------------------
+    // get_many_with_users
+    // ----------------------------------------------------------------------------
+    #[tracing::instrument("feedbacks_repository.get_many_with_users", skip_all, err)]
+    pub async fn get_many_with_users(
+        offset: i64,
+        limit: i64,
+        pool: &PgPool,
+    ) -> Result<(Vec<FeedbackWithUser>, i64), sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                f.id,
+                f.name,
+                f.email,
+                f.message,
+                f.status AS "status: FeedbackStatus",
+                f.created_at,
+                u.id AS "user_id?",
+                u.username AS "username?",
+                COUNT(*) OVER() as "total!"
+            FROM feedbacks f
+            LEFT JOIN users u ON u.email = f.email
+            ORDER BY f.created_at DESC
+            OFFSET $1
+            LIMIT $2
+            "#,
+            offset,
+            limit
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let total = rows.first().map(|r| r.total).unwrap_or(0);
+
+        let feedbacks = rows
+            .into_iter()
+            .map(|r| FeedbackWithUser {
+                feedback: Feedback {
+                    id: r.id,
+                    name: r.name,
+                    email: r.email,
+                    message: r.message,
+                    status: r.status,
+                    created_at: r.created_at,
+                },
+                user_id: r.user_id,
+                username: r.username,
+            })
+            .collect();
+
+        Ok((feedbacks, total))
+    }
+
+    // ----------------------------------------------------------------------------
+    // update_status
+    // ----------------------------------------------------------------------------
+    #[tracing::instrument("feedbacks_repository.update_status", skip_all, fields(feedback_id = %id), err)]
+    pub async fn update_status(
+        id: &Uuid,
+        status: FeedbackStatus,
+        pool: &PgPool,
+    ) -> Result<Option<Feedback>, sqlx::Error> {
+        sqlx::query_as!(
+            Feedback,
+            r#"
+            UPDATE feedbacks
+            SET status = $2
+            WHERE id = $1
+            RETURNING
+                id,
+                name,
+                email,
+                message,
+                status AS "status: FeedbackStatus",
+                created_at
+            "#,
+            id,
+            status as FeedbackStatus
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
+    // ----------------------------------------------------------------------------
+    // delete
+    // ----------------------------------------------------------------------------
+    #[tracing::instrument("feedbacks_repository.delete", skip_all, fields(feedback_id = %id), err)]
+    pub async fn delete(id: &Uuid, pool: &PgPool) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!("DELETE FROM feedbacks WHERE id = $1", id)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
 }
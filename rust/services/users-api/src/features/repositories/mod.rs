@@ -1,4 +1,7 @@
+pub mod activity;
 pub mod feedbacks;
+pub mod notification_channels;
 pub mod oauth_users;
+pub mod sessions;
+pub mod totp;
 pub mod users;
-pub mod sessions;
\ No newline at end of file
@@ -0,0 +1,155 @@
+use compute_core::models::{NotificationChannelRow, NotificationChannelType, NotificationEvent};
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::features::schemas::{
+    CreateNotificationChannelRequest, UpdateNotificationChannelRequest,
+};
+
+pub struct NotificationChannelsRepository;
+
+impl NotificationChannelsRepository {
+    // ----------------------------------------------------------------------------
+    // get_many
+    // ----------------------------------------------------------------------------
+    #[instrument("notification_channels_repository.get_many", skip_all, fields(user_id = %user_id), err)]
+    pub async fn get_many(
+        user_id: &Uuid,
+        pool: &PgPool,
+    ) -> Result<Vec<NotificationChannelRow>, sqlx::Error> {
+        sqlx::query_as!(
+            NotificationChannelRow,
+            r#"
+            SELECT
+                id,
+                user_id,
+                type AS "channel_type: NotificationChannelType",
+                webhook_url,
+                events AS "events: Vec<NotificationEvent>",
+                created_at,
+                updated_at
+            FROM notification_channels
+            WHERE user_id = $1
+            ORDER BY created_at
+            "#,
+            user_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    // ----------------------------------------------------------------------------
+    // get_one
+    // ----------------------------------------------------------------------------
+    #[instrument("notification_channels_repository.get_one", skip_all, fields(user_id = %user_id, channel_id = %id), err)]
+    pub async fn get_one(
+        user_id: &Uuid,
+        id: &Uuid,
+        pool: &PgPool,
+    ) -> Result<Option<NotificationChannelRow>, sqlx::Error> {
+        sqlx::query_as!(
+            NotificationChannelRow,
+            r#"
+            SELECT
+                id,
+                user_id,
+                type AS "channel_type: NotificationChannelType",
+                webhook_url,
+                events AS "events: Vec<NotificationEvent>",
+                created_at,
+                updated_at
+            FROM notification_channels
+            WHERE id = $1 AND user_id = $2
+            "#,
+            id,
+            user_id
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
+    // ----------------------------------------------------------------------------
+    // create
+    // ----------------------------------------------------------------------------
+    #[instrument("notification_channels_repository.create", skip_all, fields(user_id = %user_id), err)]
+    pub async fn create(
+        user_id: &Uuid,
+        req: &CreateNotificationChannelRequest,
+        pool: &PgPool,
+    ) -> Result<NotificationChannelRow, sqlx::Error> {
+        sqlx::query_as!(
+            NotificationChannelRow,
+            r#"
+            INSERT INTO notification_channels (user_id, type, webhook_url, events)
+            VALUES ($1, $2, $3, $4)
+            RETURNING
+                id,
+                user_id,
+                type AS "channel_type: NotificationChannelType",
+                webhook_url,
+                events AS "events: Vec<NotificationEvent>",
+                created_at,
+                updated_at
+            "#,
+            user_id,
+            req.channel_type as NotificationChannelType,
+            req.webhook_url,
+            &req.events as &[NotificationEvent]
+        )
+        .fetch_one(pool)
+        .await
+    }
+
+    // ----------------------------------------------------------------------------
+    // update
+    // ----------------------------------------------------------------------------
+    #[instrument("notification_channels_repository.update", skip_all, fields(user_id = %user_id, channel_id = %id), err)]
+    pub async fn update(
+        user_id: &Uuid,
+        id: &Uuid,
+        req: &UpdateNotificationChannelRequest,
+        pool: &PgPool,
+    ) -> Result<Option<NotificationChannelRow>, sqlx::Error> {
+        sqlx::query_as!(
+            NotificationChannelRow,
+            r#"
+            UPDATE notification_channels
+            SET
+                webhook_url = COALESCE($3, webhook_url),
+                events = COALESCE($4, events)
+            WHERE id = $1 AND user_id = $2
+            RETURNING
+                id,
+                user_id,
+                type AS "channel_type: NotificationChannelType",
+                webhook_url,
+                events AS "events: Vec<NotificationEvent>",
+                created_at,
+                updated_at
+            "#,
+            id,
+            user_id,
+            req.webhook_url,
+            req.events.as_deref() as Option<&[NotificationEvent]>
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
+    // ----------------------------------------------------------------------------
+    // delete
+    // ----------------------------------------------------------------------------
+    #[instrument("notification_channels_repository.delete", skip_all, fields(user_id = %user_id, channel_id = %id), err)]
+    pub async fn delete(user_id: &Uuid, id: &Uuid, pool: &PgPool) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!(
+            "DELETE FROM notification_channels WHERE id = $1 AND user_id = $2",
+            id,
+            user_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -1,31 +1,200 @@
-use sqlx::{PgPool, postgres::PgQueryResult};
+use sqlx::{Executor, PgPool, Postgres, postgres::PgQueryResult};
 use uuid::Uuid;
 
+use crate::features::models::{ActiveSession, Session};
+
 pub struct SessionsRepository;
 
 impl SessionsRepository {
     // ----------------------------------------------------------------------------
     // create
     // ----------------------------------------------------------------------------
-    #[tracing::instrument("sessions_repository.create", skip_all, err)]
-    pub async fn create(
+    #[tracing::instrument("sessions_repository.create", skip_all, fields(family_id = %family_id), err)]
+    pub async fn create<'e, E>(
         user_id: &Uuid,
         user_agent: &str,
         ip_address: &str,
         refresh_token: &str,
-        pool: &PgPool,
-    ) -> Result<PgQueryResult, sqlx::Error> {
+        family_id: &Uuid,
+        executor: E,
+    ) -> Result<PgQueryResult, sqlx::Error>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
         sqlx::query!(
             r#"
-            INSERT INTO sessions (user_id, user_agent, ip_address, refresh_token)
-            VALUES ($1, $2, $3, $4)
+            INSERT INTO sessions (user_id, user_agent, ip_address, refresh_token, family_id)
+            VALUES ($1, $2, $3, $4, $5)
             "#,
             user_id,
             user_agent,
             ip_address,
+            refresh_token,
+            family_id
+        )
+        .execute(executor)
+        .await
+    }
+
+    // ----------------------------------------------------------------------------
+    // get_by_refresh_token
+    // ----------------------------------------------------------------------------
+    #[tracing::instrument("sessions_repository.get_by_refresh_token", skip_all, err)]
+    pub async fn get_by_refresh_token(
+        refresh_token: &str,
+        pool: &PgPool,
+    ) -> Result<Option<Session>, sqlx::Error> {
+        sqlx::query_as!(
+            Session,
+            r#"
+            SELECT
+                id,
+                user_id,
+                user_agent,
+                ip_address,
+                device_name,
+                refresh_token,
+                is_active,
+                family_id,
+                revoked_at,
+                created_at,
+                updated_at
+            FROM sessions
+            WHERE refresh_token = $1
+            "#,
             refresh_token
         )
+        .fetch_optional(pool)
+        .await
+    }
+
+    // ----------------------------------------------------------------------------
+    // revoke
+    // ----------------------------------------------------------------------------
+    /// `false` when the session was already revoked, e.g. by a concurrent refresh
+    #[tracing::instrument("sessions_repository.revoke", skip_all, fields(session_id = %session_id), err)]
+    pub async fn revoke<'e, E>(session_id: &Uuid, executor: E) -> Result<bool, sqlx::Error>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let result = sqlx::query!(
+            r#"
+            UPDATE sessions
+            SET is_active = FALSE, revoked_at = NOW()
+            WHERE id = $1
+            AND revoked_at IS NULL
+            "#,
+            session_id
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    // ----------------------------------------------------------------------------
+    // revoke_family
+    // ----------------------------------------------------------------------------
+    #[tracing::instrument("sessions_repository.revoke_family", skip_all, fields(family_id = %family_id), err)]
+    pub async fn revoke_family(family_id: &Uuid, pool: &PgPool) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE sessions
+            SET is_active = FALSE, revoked_at = COALESCE(revoked_at, NOW())
+            WHERE family_id = $1
+            AND is_active
+            "#,
+            family_id
+        )
         .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // ----------------------------------------------------------------------------
+    // get_active
+    // ----------------------------------------------------------------------------
+    /// Each family has one live row, created by its latest refresh
+    #[tracing::instrument("sessions_repository.get_active", skip_all, fields(user_id = %user_id), err)]
+    pub async fn get_active(
+        user_id: &Uuid,
+        current_family_id: Option<Uuid>,
+        pool: &PgPool,
+    ) -> Result<Vec<ActiveSession>, sqlx::Error> {
+        sqlx::query_as!(
+            ActiveSession,
+            r#"
+            SELECT
+                family_id AS id,
+                user_agent,
+                ip_address,
+                device_name,
+                created_at AS last_used_at,
+                family_id IS NOT DISTINCT FROM $2 AS "current!"
+            FROM sessions
+            WHERE user_id = $1
+            AND is_active
+            AND revoked_at IS NULL
+            ORDER BY created_at DESC
+            "#,
+            user_id,
+            current_family_id
+        )
+        .fetch_all(pool)
         .await
     }
+
+    // ----------------------------------------------------------------------------
+    // revoke_user_family
+    // ----------------------------------------------------------------------------
+    #[tracing::instrument("sessions_repository.revoke_user_family", skip_all, fields(user_id = %user_id, family_id = %family_id), err)]
+    pub async fn revoke_user_family(
+        user_id: &Uuid,
+        family_id: &Uuid,
+        pool: &PgPool,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE sessions
+            SET is_active = FALSE, revoked_at = NOW()
+            WHERE user_id = $1
+            AND family_id = $2
+            AND is_active
+            "#,
+            user_id,
+            family_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // ----------------------------------------------------------------------------
+    // revoke_all_except
+    // ----------------------------------------------------------------------------
+    /// Without a family to keep every session of the user is revoked
+    #[tracing::instrument("sessions_repository.revoke_all_except", skip_all, fields(user_id = %user_id), err)]
+    pub async fn revoke_all_except(
+        user_id: &Uuid,
+        keep_family_id: Option<Uuid>,
+        pool: &PgPool,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE sessions
+            SET is_active = FALSE, revoked_at = NOW()
+            WHERE user_id = $1
+            AND family_id IS DISTINCT FROM $2
+            AND is_active
+            "#,
+            user_id,
+            keep_family_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
 }
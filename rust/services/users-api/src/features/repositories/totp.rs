@@ -0,0 +1,165 @@
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::features::models::UserTotpSecret;
+
+pub struct TotpRepository;
+
+impl TotpRepository {
+    // ----------------------------------------------------------------------------
+    // get
+    // ----------------------------------------------------------------------------
+    #[tracing::instrument("totp_repository.get", skip_all, fields(user_id = %user_id), err)]
+    pub async fn get<'e, E>(
+        user_id: &Uuid,
+        executor: E,
+    ) -> Result<Option<UserTotpSecret>, sqlx::Error>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            UserTotpSecret,
+            r#"
+            SELECT user_id, secret_ciphertext, is_active, last_used_step, created_at, updated_at
+            FROM user_totp_secrets
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_optional(executor)
+        .await
+    }
+
+    // ----------------------------------------------------------------------------
+    // is_active
+    // ----------------------------------------------------------------------------
+    #[tracing::instrument("totp_repository.is_active", skip_all, fields(user_id = %user_id), err)]
+    pub async fn is_active(user_id: &Uuid, pool: &PgPool) -> Result<bool, sqlx::Error> {
+        let active = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM user_totp_secrets
+                WHERE user_id = $1
+                AND is_active
+            ) AS "exists!"
+            "#,
+            user_id
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(active)
+    }
+
+    // ----------------------------------------------------------------------------
+    // upsert_pending
+    // ----------------------------------------------------------------------------
+    /// Replaces a secret that was never confirmed, an active one is left untouched
+    #[tracing::instrument("totp_repository.upsert_pending", skip_all, fields(user_id = %user_id), err)]
+    pub async fn upsert_pending(
+        user_id: &Uuid,
+        secret_ciphertext: &str,
+        tx: &mut Transaction<'_, Postgres>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO user_totp_secrets (user_id, secret_ciphertext)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE
+            SET secret_ciphertext = EXCLUDED.secret_ciphertext, last_used_step = NULL
+            WHERE NOT user_totp_secrets.is_active
+            "#,
+            user_id,
+            secret_ciphertext
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    // ----------------------------------------------------------------------------
+    // replace_backup_codes
+    // ----------------------------------------------------------------------------
+    #[tracing::instrument("totp_repository.replace_backup_codes", skip_all, fields(user_id = %user_id), err)]
+    pub async fn replace_backup_codes(
+        user_id: &Uuid,
+        code_hashes: &[String],
+        tx: &mut Transaction<'_, Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"DELETE FROM user_totp_backup_codes WHERE user_id = $1"#,
+            user_id
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO user_totp_backup_codes (user_id, code_hash)
+            SELECT $1, UNNEST($2::VARCHAR[])
+            "#,
+            user_id,
+            code_hashes
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    // ----------------------------------------------------------------------------
+    // use_step
+    // ----------------------------------------------------------------------------
+    /// Records the time step of an accepted code, optionally activating the secret.
+    /// `false` when the step (or a later one) was already used.
+    #[tracing::instrument("totp_repository.use_step", skip_all, fields(user_id = %user_id, step = step), err)]
+    pub async fn use_step(
+        user_id: &Uuid,
+        step: i64,
+        activate: bool,
+        pool: &PgPool,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE user_totp_secrets
+            SET last_used_step = $2, is_active = is_active OR $3
+            WHERE user_id = $1
+            AND (last_used_step IS NULL OR last_used_step < $2)
+            "#,
+            user_id,
+            step,
+            activate
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    // ----------------------------------------------------------------------------
+    // use_backup_code
+    // ----------------------------------------------------------------------------
+    #[tracing::instrument("totp_repository.use_backup_code", skip_all, fields(user_id = %user_id), err)]
+    pub async fn use_backup_code(
+        user_id: &Uuid,
+        code_hash: &str,
+        pool: &PgPool,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE user_totp_backup_codes
+            SET used_at = NOW()
+            WHERE user_id = $1
+            AND code_hash = $2
+            AND used_at IS NULL
+            "#,
+            user_id,
+            code_hash
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+}
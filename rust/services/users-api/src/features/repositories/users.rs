@@ -2,7 +2,7 @@ use crate::{
     error::AppError,
     features::{
         models::{User, UserRole, UserStatus},
-        schemas::UserMutationPayload,
+        schemas::{UserMutationPayload, UserStats},
     },
 };
 use sqlx::{Executor, PgPool, Postgres, Transaction, postgres::PgQueryResult};
@@ -126,6 +126,24 @@ impl UsersRepository {
         .await
     }
 
+    /// The new address was proven by the link, so it counts as verified
+    #[tracing::instrument("users_repository.update_email", skip_all, fields(user_id = %id), err)]
+    pub async fn update_email(
+        id: &Uuid,
+        email: &str,
+        pool: &PgPool,
+    ) -> Result<PgQueryResult, sqlx::Error> {
+        sqlx::query!(
+            r#"UPDATE users
+            SET email = $2, email_verified = TRUE WHERE id = $1
+            "#,
+            id,
+            email
+        )
+        .execute(pool)
+        .await
+    }
+
     #[tracing::instrument("users_repository.update_password", skip_all, err)]
     pub async fn update_password(
         user_id: &Uuid,
@@ -140,4 +158,106 @@ impl UsersRepository {
         .execute(pool)
         .await?)
     }
+
+    // ----------------------------------------------------------------------------
+    // get_stats
+    // ----------------------------------------------------------------------------
+    /// `storage_used_bytes` is left at zero, it lives in object storage
+    #[tracing::instrument("users_repository.get_stats", skip_all, err)]
+    pub async fn get_stats(id: &Uuid, pool: &PgPool) -> Result<UserStats, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                COUNT(DISTINCT p.id) AS "total_projects!",
+                COUNT(d.id) AS "total_deployments!",
+                COUNT(d.id) FILTER (WHERE d.status = 'running') AS "total_running_deployments!",
+                u.created_at AS member_since
+            FROM users u
+            LEFT JOIN projects p ON p.owner_id = u.id
+            LEFT JOIN deployments d ON d.project_id = p.id
+            WHERE u.id = $1
+            GROUP BY u.id
+            "#,
+            id
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(UserStats {
+            total_projects: row.total_projects,
+            total_deployments: row.total_deployments,
+            total_running_deployments: row.total_running_deployments,
+            member_since: row.member_since,
+            storage_used_bytes: 0,
+        })
+    }
+
+    // ----------------------------------------------------------------------------
+    // get_deployments
+    // ----------------------------------------------------------------------------
+    /// Returns (deployment_id, project_id) of every deployment still running anything
+    #[tracing::instrument("users_repository.get_deployments", skip_all, err)]
+    pub async fn get_deployments(
+        id: &Uuid,
+        pool: &PgPool,
+    ) -> Result<Vec<(Uuid, Uuid, Option<String>)>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, project_id, region
+            FROM deployments
+            WHERE user_id = $1 AND status != 'deleted'
+            "#,
+            id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| (r.id, r.project_id, r.region))
+            .collect())
+    }
+
+    // ----------------------------------------------------------------------------
+    // delete_deployment
+    // ----------------------------------------------------------------------------
+    #[tracing::instrument("users_repository.delete_deployment", skip_all, err)]
+    pub async fn delete_deployment(
+        user_id: &Uuid,
+        deployment_id: &Uuid,
+        tx: &mut Transaction<'_, Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "DELETE FROM deployments WHERE id = $1 AND user_id = $2",
+            deployment_id,
+            user_id
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    // ----------------------------------------------------------------------------
+    // delete
+    // ----------------------------------------------------------------------------
+    /// Billing rows only null their `user_id` on delete, which the column doesn't allow, so
+    /// they go first. Everything else cascades from the user
+    #[tracing::instrument("users_repository.delete", skip_all, err)]
+    pub async fn delete(
+        id: &Uuid,
+        tx: &mut Transaction<'_, Postgres>,
+    ) -> Result<bool, sqlx::Error> {
+        sqlx::query!("DELETE FROM billings WHERE user_id = $1", id)
+            .execute(&mut **tx)
+            .await?;
+        sqlx::query!("DELETE FROM balances WHERE user_id = $1", id)
+            .execute(&mut **tx)
+            .await?;
+        let result = sqlx::query!("DELETE FROM users WHERE id = $1", id)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
 }
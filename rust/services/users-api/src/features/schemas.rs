@@ -1,9 +1,10 @@
-use crate::features::models::{User, UserRole, UserStatus};
+use crate::features::models::{FeedbackStatus, User, UserRole, UserStatus};
 use chrono::{DateTime, Utc};
+use compute_core::models::{NotificationChannelType, NotificationEvent};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
 #[derive(Deserialize, JsonSchema, Debug)]
 pub struct TokenQuery {
@@ -111,11 +112,45 @@ pub struct UserOut {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Serialize, JsonSchema, Debug)]
+#[derive(Deserialize, Serialize, JsonSchema, Debug)]
 #[serde(rename_all = "camelCase")]
-pub struct StatsResponse {
-    pub users_total: i64,
-    pub deployments_total: i64,
+pub struct UserStats {
+    pub total_projects: i64,
+    pub total_deployments: i64,
+    pub total_running_deployments: i64,
+    pub member_since: DateTime<Utc>,
+    pub storage_used_bytes: i64,
+}
+
+/// Newest items first. `before` pages back from an item, `after` returns the items that came in
+/// since one, they can't be combined
+#[derive(Deserialize, JsonSchema, Debug)]
+pub struct ActivityQuery {
+    pub before: Option<Uuid>,
+    pub after: Option<Uuid>,
+}
+
+/// One entry of the activity timeline, an event or build of one of the user's deployments
+#[derive(Deserialize, Serialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityItem {
+    /// Passed back as `before` or `after` to page from this item
+    pub id: Uuid,
+    /// The deployment event type, or `build_{status}` for a build
+    #[serde(rename = "type")]
+    pub activity_type: String,
+    /// Only `deployment` for now
+    pub resource_type: String,
+    pub resource_id: Uuid,
+    pub resource_name: String,
+    pub description: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateFeedbackStatusRequest {
+    pub status: FeedbackStatus,
 }
 
 #[derive(Deserialize, Default, JsonSchema, Debug)]
@@ -136,3 +171,104 @@ pub struct PasswordSetupRequest {
     ))]
     pub password: String,
 }
+
+#[derive(Deserialize, Validate, JsonSchema, Debug)]
+pub struct ChangeEmailRequest {
+    #[validate(email(message = "Invalid email address"))]
+    pub new_email: String,
+    /// Current password, accounts without one have to set it up first
+    pub password: String,
+}
+
+#[derive(Deserialize, Validate, JsonSchema, Debug)]
+pub struct DeleteAccountRequest {
+    /// Current password, accounts without one have to set it up first
+    #[validate(length(min = 1))]
+    pub password: String,
+}
+
+#[derive(Deserialize, Validate, JsonSchema, Debug)]
+pub struct PasswordResetRequest {
+    #[validate(email(message = "Invalid email address"))]
+    pub email: String,
+}
+
+#[derive(Deserialize, Validate, JsonSchema, Debug)]
+pub struct PasswordResetConfirmRequest {
+    pub token: String,
+    #[validate(length(
+        min = 8,
+        max = 24,
+        message = "Password should be long between 8 and 24"
+    ))]
+    pub new_password: String,
+}
+
+#[derive(Serialize, JsonSchema, Debug)]
+pub struct TotpSetupResponse {
+    /// `otpauth://` URI for the QR code
+    pub provisioning_uri: String,
+    /// Base32 secret for manual entry
+    pub secret: String,
+    /// Shown only once, each can replace a code a single time
+    pub backup_codes: Vec<String>,
+}
+
+#[derive(Deserialize, JsonSchema, Debug)]
+pub struct TotpCodeRequest {
+    pub code: String,
+}
+
+/// Returned by email login instead of tokens when the account has 2FA enabled
+#[derive(Serialize, JsonSchema, Debug)]
+pub struct TwoFactorChallenge {
+    pub requires_2fa: bool,
+    pub temp_token: String,
+}
+
+#[derive(Deserialize, JsonSchema, Debug)]
+pub struct TwoFactorLoginRequest {
+    pub temp_token: String,
+    /// Either a TOTP code or one of the backup codes
+    pub code: String,
+}
+
+#[derive(Deserialize, Validate, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+#[validate(schema(function = "validate_notification_channel"))]
+pub struct CreateNotificationChannelRequest {
+    #[serde(rename = "type")]
+    pub channel_type: NotificationChannelType,
+    /// An incoming webhook URL of the chosen provider
+    #[validate(length(max = 2048))]
+    pub webhook_url: String,
+    #[validate(length(min = 1))]
+    pub events: Vec<NotificationEvent>,
+}
+
+fn validate_notification_channel(
+    req: &CreateNotificationChannelRequest,
+) -> Result<(), ValidationError> {
+    if !req.channel_type.accepts_webhook_url(&req.webhook_url) {
+        return Err(ValidationError::new("invalid_webhook_url"));
+    }
+    Ok(())
+}
+
+#[derive(Deserialize, Validate, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateNotificationChannelRequest {
+    #[validate(length(max = 2048))]
+    pub webhook_url: Option<String>,
+    #[validate(length(min = 1))]
+    pub events: Option<Vec<NotificationEvent>>,
+}
+
+/// Access token an admin uses to act as the user, it can't be refreshed
+#[derive(Serialize, JsonSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ImpersonationResponse {
+    pub user_id: Uuid,
+    pub access_token: String,
+    pub expires_at: DateTime<Utc>,
+}
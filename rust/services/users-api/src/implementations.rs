@@ -4,7 +4,9 @@ use aide::{
     openapi::{Operation, Response, StatusCode},
 };
 use axum::Json;
-use factory::factories::{mailtrap::error::MailtrapError, zepto::error::ZeptoError};
+use factory::factories::{
+    email::error::EmailError, mailtrap::error::MailtrapError, zepto::error::ZeptoError,
+};
 use http_contracts::error::schema::ErrorResponse;
 use users_core::jwt::JwtCapability;
 
@@ -77,6 +79,7 @@ impl From<ClaimsError> for AppError {
             ClaimsError::WrongType => AppError::WrongTokenTypeError,
             ClaimsError::Invalid => AppError::InvalidTokenError,
             ClaimsError::KeyError => AppError::KeyError,
+            ClaimsError::NotAdmin => AppError::Forbidden("Admin role required".into()),
         }
     }
 }
@@ -109,6 +112,15 @@ impl From<MailtrapError> for AppError {
     }
 }
 
+impl From<EmailError> for AppError {
+    fn from(err: EmailError) -> Self {
+        match err {
+            EmailError::Mailtrap(e) => e.into(),
+            EmailError::Zepto(e) => e.into(),
+        }
+    }
+}
+
 // -------------------------------------------------------------------------------
 // --------------------------- Factory implementations ---------------------------
 // -------------------------------------------------------------------------------
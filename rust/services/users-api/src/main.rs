@@ -1,17 +1,9 @@
-pub mod app;
-pub mod config;
-pub mod error;
-pub mod features;
-pub mod implementations;
-pub mod services;
-pub mod utilities;
-
 use std::path::PathBuf;
 use std::result::Result::Ok;
 use std::{env, net::SocketAddr};
 
-use config::Config;
 use factory::factories::observability::Observability;
+use users_api::{app, config::Config};
 
 use tracing::info;
 use utility::shutdown_signal::shutdown_signal;
@@ -1,3 +1,4 @@
 pub mod github_oauth;
 pub mod google_oauth;
 pub mod s3;
+pub mod totp;
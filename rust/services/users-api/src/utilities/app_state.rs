@@ -9,7 +9,10 @@ use crate::{
 };
 use axum::extract::FromRef;
 use axum_extra::extract::cookie::Key;
-use factory::factories::{amqp::Amqp, database::Database, kafka::Kafka, redis::Redis};
+use factory::factories::{
+    amqp::Amqp, database::Database, email::Email, kafka::Kafka, redis::Redis,
+};
+use http_common::metrics::HttpMetrics;
 use object_store::aws::AmazonS3;
 use reqwest::Client;
 use rustls::ClientConfig;
@@ -28,12 +31,17 @@ pub struct AppState {
     pub github_oauth_client: GithubOAuthClient,
     pub http_client: Client,
     pub s3: AmazonS3,
+    pub email: Email,
+    pub metrics: HttpMetrics,
 }
 
 impl AppState {
     pub async fn init(cfg: &Config) -> Result<Self, AppError> {
         let database = Database::new(&cfg.database).await;
-        let redis = Redis::new(&cfg.redis).await;
+        tokio::spawn(database.clone().report_metrics());
+        let redis = Redis::new(&cfg.redis)
+            .await
+            .map_err(|e| AppError::InternalServerError(e.to_string()))?;
         let amqp = Amqp::new(&cfg.amqp).await;
         let key = Key::from(cfg.cookie_key.as_bytes());
         let google_oauth_client = build_google_oauth_client(&cfg.google_oauth);
@@ -43,6 +51,7 @@ impl AppState {
             .build()
             .unwrap_or_else(|e| panic!("Failed to construct http client: {}", e));
         let s3 = build_s3(&cfg.s3);
+        let email = Email::new(&cfg.email);
 
         Ok(Self {
             rustls_config: None,
@@ -56,6 +65,8 @@ impl AppState {
             github_oauth_client,
             http_client,
             s3,
+            email,
+            metrics: HttpMetrics::new(),
         })
     }
 }
@@ -0,0 +1,187 @@
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    extract::FromRef,
+    http::{Request, StatusCode, header},
+    routing::get,
+};
+use axum_extra::extract::cookie::Key;
+use factory::factories::{
+    database::Database,
+    redis::{Redis, RedisConfig},
+};
+use serde_json::Value;
+use sqlx::PgPool;
+use tower::ServiceExt;
+use users_api::features::handlers::activity::get_activity_handler;
+use users_core::jwt::{JwtCapability, TokenType, create_token};
+use uuid::Uuid;
+
+#[derive(Clone, FromRef)]
+struct TestState {
+    database: Database,
+    redis: Redis,
+    key: Key,
+}
+
+impl JwtCapability for TestState {
+    fn jwt_secret(&self) -> &str {
+        "test-secret"
+    }
+
+    fn access_token_expire_in_minute(&self) -> i64 {
+        15
+    }
+
+    fn refresh_token_expire_in_days(&self) -> i64 {
+        7
+    }
+
+    fn email_verification_token_expire_in_hours(&self) -> i64 {
+        24
+    }
+
+    fn password_setup_token_expire_in_minutes(&self) -> i64 {
+        30
+    }
+}
+
+struct Feed {
+    router: Router,
+    token: String,
+    user_id: Uuid,
+}
+
+impl Feed {
+    async fn new(pool: PgPool) -> Self {
+        let url = std::env::var("REDIS_URL").unwrap_or("redis://127.0.0.1:6379".to_string());
+        let redis = Redis::new(&RedisConfig {
+            url: Some(url),
+            params: None,
+            tls_config: None,
+        })
+        .await
+        .unwrap();
+
+        let user_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO users (username, email) VALUES ('reader', 'reader@poddle.test') RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .expect("Failed to create test user");
+
+        let state = TestState {
+            database: Database { pool },
+            redis,
+            key: Key::from(&[7; 64]),
+        };
+        let token = create_token(&state, user_id, TokenType::Access).unwrap();
+
+        Self {
+            router: Router::new()
+                .route("/activity", get(get_activity_handler))
+                .with_state(state),
+            token,
+            user_id,
+        }
+    }
+
+    /// Ids of the page, newest first
+    async fn page(&self, query: &str) -> Vec<String> {
+        let request = Request::builder()
+            .uri(format!("/activity{}", query))
+            .header(header::AUTHORIZATION, format!("Bearer {}", self.token))
+            .body(Body::empty())
+            .unwrap();
+        let response = self.router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let items: Vec<Value> = serde_json::from_slice(&body).unwrap();
+        items
+            .iter()
+            .map(|item| item["id"].as_str().unwrap().to_string())
+            .collect()
+    }
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+#[ignore = "needs Postgres and Redis"]
+async fn builds_are_placed_at_their_start(pool: PgPool) {
+    let feed = Feed::new(pool.clone()).await;
+
+    let (project_id, deployment_id): (Uuid, Uuid) = sqlx::query_as(
+        r#"
+        WITH project AS (
+            INSERT INTO projects (owner_id, name) VALUES ($1, 'feed') RETURNING id
+        ), preset AS (
+            INSERT INTO presets (name, description, cpu_millicores, memory_mb, monthly_price)
+            VALUES ('Feed test', 'Test preset', 500, 512, 5)
+            RETURNING id
+        )
+        INSERT INTO deployments (user_id, project_id, name, source, port, preset_id, status, service)
+        SELECT $1, project.id, 'web', '{"type": "image", "url": "nginx:1.27"}', 80, preset.id, 'running', 'web'
+        FROM project, preset
+        RETURNING project_id, id
+        "#,
+    )
+    .bind(feed.user_id)
+    .fetch_one(&pool)
+    .await
+    .expect("Failed to create deployment");
+
+    let event = |minutes_ago: i32| {
+        let pool = pool.clone();
+        async move {
+            sqlx::query_scalar::<_, Uuid>(
+                r#"
+                INSERT INTO deployment_events (project_id, deployment_id, type, level, message, created_at)
+                VALUES ($1, $2, 'status_changed', 'info', 'changed', NOW() - make_interval(mins => $3))
+                RETURNING id
+                "#,
+            )
+            .bind(project_id)
+            .bind(deployment_id)
+            .bind(minutes_ago)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+            .to_string()
+        }
+    };
+
+    let oldest = event(30).await;
+    // Started before the newest event, finished after it
+    let build = Uuid::new_v4().to_string();
+    sqlx::query(
+        r#"
+        INSERT INTO builds (id, deployment_id, build_type, status, started_at, finished_at, image)
+        VALUES ($1::UUID, $2, 'buildkit', 'succeeded', NOW() - INTERVAL '20 minutes', NOW() - INTERVAL '1 minute', 'web:1')
+        "#,
+    )
+    .bind(&build)
+    .bind(deployment_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    let newest = event(10).await;
+
+    assert_eq!(
+        feed.page("").await,
+        [newest.as_str(), build.as_str(), oldest.as_str()]
+    );
+
+    // A build works as a cursor both ways
+    assert_eq!(
+        feed.page(&format!("?before={}", build)).await,
+        [oldest.as_str()]
+    );
+    assert_eq!(
+        feed.page(&format!("?after={}", build)).await,
+        [newest.as_str()]
+    );
+    assert_eq!(
+        feed.page(&format!("?after={}", oldest)).await,
+        [newest.as_str(), build.as_str()]
+    );
+}
@@ -0,0 +1,7 @@
+//! Tests of users-api's building blocks. `totp` needs no running services, `activity` and
+//! `password_reset` need Postgres (`DATABASE_URL`) and Redis (`REDIS_URL`), run them with
+//! `cargo test -p users-api -- --ignored`
+
+mod activity;
+mod password_reset;
+mod totp;
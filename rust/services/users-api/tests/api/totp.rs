@@ -0,0 +1,85 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use users_api::services::totp::{TotpServiceConfig, decrypt_secret, encrypt_secret, verify_code};
+
+/// The SHA-1 seed of RFC 6238 appendix B
+const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+fn config(encryption_key: &str) -> TotpServiceConfig {
+    TotpServiceConfig {
+        issuer: "Poddle".to_string(),
+        encryption_key: encryption_key.to_string(),
+    }
+}
+
+#[test]
+fn rfc_6238_test_vectors_verify() {
+    // Appendix B lists 8 digit codes, ours are their last 6
+    let vectors = [
+        (59, "287082"),
+        (1111111109, "081804"),
+        (1111111111, "050471"),
+        (1234567890, "005924"),
+        (2000000000, "279037"),
+        (20000000000, "353130"),
+    ];
+
+    for (unix_time, code) in vectors {
+        assert_eq!(
+            verify_code(RFC_SECRET, code, unix_time),
+            Some(unix_time / 30),
+            "code {} at {}",
+            code,
+            unix_time
+        );
+    }
+}
+
+#[test]
+fn codes_of_neighbouring_steps_are_accepted() {
+    // "287082" belongs to step 1, 30 to 59 seconds
+    assert_eq!(verify_code(RFC_SECRET, "287082", 29), Some(1));
+    assert_eq!(verify_code(RFC_SECRET, "287082", 89), Some(1));
+
+    assert_eq!(verify_code(RFC_SECRET, "287082", 90), None);
+    assert_eq!(verify_code(RFC_SECRET, "287082", 119), None);
+}
+
+#[test]
+fn malformed_codes_are_rejected() {
+    assert_eq!(verify_code(RFC_SECRET, "28708", 59), None);
+    assert_eq!(verify_code(RFC_SECRET, "2870822", 59), None);
+    assert_eq!(verify_code(RFC_SECRET, "28708a", 59), None);
+}
+
+#[test]
+fn encrypted_secret_round_trips() {
+    let cfg = config("first key");
+
+    let sealed = encrypt_secret(&cfg, RFC_SECRET).unwrap();
+    assert_ne!(sealed.as_bytes(), RFC_SECRET);
+    assert_eq!(decrypt_secret(&cfg, &sealed).unwrap(), RFC_SECRET);
+
+    // A fresh nonce every time
+    assert_ne!(encrypt_secret(&cfg, RFC_SECRET).unwrap(), sealed);
+}
+
+#[test]
+fn secret_does_not_decrypt_with_another_key() {
+    let sealed = encrypt_secret(&config("first key"), RFC_SECRET).unwrap();
+
+    assert!(decrypt_secret(&config("second key"), &sealed).is_err());
+}
+
+#[test]
+fn tampered_or_truncated_secret_does_not_decrypt() {
+    let cfg = config("first key");
+    let sealed = encrypt_secret(&cfg, RFC_SECRET).unwrap();
+
+    let mut bytes = STANDARD.decode(&sealed).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0x01;
+    assert!(decrypt_secret(&cfg, &STANDARD.encode(&bytes)).is_err());
+
+    assert!(decrypt_secret(&cfg, &STANDARD.encode(&bytes[..12])).is_err());
+    assert!(decrypt_secret(&cfg, "not base64!").is_err());
+}